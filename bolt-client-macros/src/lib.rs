@@ -2,59 +2,365 @@
 
 use proc_macro::TokenStream;
 
-use syn::*;
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    *,
+};
 
 use quote::quote;
 
-fn get_fn_info(
-    func: &ItemFn,
-    args: AttributeArgs,
-) -> (&Vec<Attribute>, &Visibility, &Signature, Vec<u32>, &Block) {
-    let signature = &func.sig;
-    let function_body = &func.block;
-    let attributes = &func.attrs;
-    let visibility = &func.vis;
-    let versions: Vec<u32> = args
-        .into_iter()
-        .map(|item| {
-            if let NestedMeta::Lit(lit) = item {
-                match lit {
-                    Lit::Int(lit_int) => lit_int
-                        .base10_parse::<u32>()
-                        .expect("couldn't parse version"),
-                    Lit::Float(lit_float) => {
-                        let version = lit_float
-                            .base10_parse::<f64>()
-                            .expect("couldn't parse version");
-                        let major = version.trunc() as u32;
-                        let minor = (version.fract() * 10.0).round() as u32;
-                        minor << 8 | major
-                    }
-                    _ => panic!("invalid version token: {:?}", lit),
+/// A Bolt protocol version, as a `(major, minor)` pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Version(u32, u32);
+
+impl Version {
+    fn to_doc(self) -> String {
+        match self {
+            Version(major, 0) => format!("v{}", major),
+            Version(major, minor) => format!("v{}.{}", major, minor),
+        }
+    }
+}
+
+impl Parse for Version {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let lit: Lit = input.parse()?;
+        let digits = match &lit {
+            Lit::Int(lit_int) if lit_int.suffix().is_empty() => lit_int.base10_digits(),
+            Lit::Float(lit_float) if lit_float.suffix().is_empty() => lit_float.base10_digits(),
+            _ => {
+                return Err(Error::new_spanned(
+                    lit,
+                    "expected a Bolt version, like `4` or `4.1`",
+                ))
+            }
+        };
+        let (major, minor) = digits.split_once('.').unwrap_or((digits, "0"));
+        match (major.parse(), minor.parse()) {
+            (Ok(major), Ok(minor)) if major > 0 && major <= 0xff && minor <= 0xff => {
+                Ok(Version(major, minor))
+            }
+            _ => Err(Error::new_spanned(lit, "invalid Bolt version")),
+        }
+    }
+}
+
+/// A set of versions for which a method is supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum VersionSpec {
+    Exact(Version),
+    Range {
+        start: Option<Version>,
+        end: Option<Version>,
+        inclusive: bool,
+    },
+}
+
+impl VersionSpec {
+    fn to_condition(&self) -> proc_macro2::TokenStream {
+        fn tuple(Version(major, minor): Version) -> proc_macro2::TokenStream {
+            quote!((#major, #minor))
+        }
+
+        match self {
+            VersionSpec::Exact(version) => {
+                let version = tuple(*version);
+                quote!(version == #version)
+            }
+            VersionSpec::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let start = start.map(tuple);
+                match end.map(tuple) {
+                    Some(end) if *inclusive => quote!((#start..=#end).contains(&version)),
+                    end => quote!((#start..#end).contains(&version)),
+                }
+            }
+        }
+    }
+
+    fn to_doc(&self) -> String {
+        match self {
+            VersionSpec::Exact(version) => version.to_doc(),
+            VersionSpec::Range {
+                start: Some(start),
+                end: None,
+                ..
+            } => format!("{}+", start.to_doc()),
+            VersionSpec::Range {
+                start,
+                end: Some(end),
+                inclusive,
+            } => {
+                let start = start.unwrap_or(Version(1, 0)).to_doc();
+                match inclusive {
+                    true => format!("{} - {}", start, end.to_doc()),
+                    false => format!("{} up to (excluding) {}", start, end.to_doc()),
                 }
-            } else {
-                panic!("invalid version token: {:?}", item);
             }
+            VersionSpec::Range {
+                start: None,
+                end: None,
+                ..
+            } => unreachable!("ranges have at least one bound"),
+        }
+    }
+}
+
+impl Parse for VersionSpec {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        // `min = <version>` or `max = <version>`
+        if input.peek(Ident) {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            let version = Some(input.parse()?);
+            return match key.to_string().as_str() {
+                "min" => Ok(VersionSpec::Range {
+                    start: version,
+                    end: None,
+                    inclusive: true,
+                }),
+                "max" => Ok(VersionSpec::Range {
+                    start: None,
+                    end: version,
+                    inclusive: true,
+                }),
+                _ => Err(Error::new_spanned(key, "expected `min` or `max`")),
+            };
+        }
+
+        let start = if input.peek(Token![..]) {
+            None
+        } else {
+            Some(input.parse()?)
+        };
+        let inclusive = if input.peek(Token![..=]) {
+            input.parse::<Token![..=]>()?;
+            true
+        } else if input.peek(Token![..]) {
+            input.parse::<Token![..]>()?;
+            false
+        } else {
+            // A single version
+            return Ok(VersionSpec::Exact(start.unwrap()));
+        };
+        let end = if input.is_empty() || input.peek(Token![,]) {
+            if inclusive || start.is_none() {
+                return Err(input.error("expected a Bolt version after `..`"));
+            }
+            None
+        } else {
+            Some(input.parse()?)
+        };
+        Ok(VersionSpec::Range {
+            start,
+            end,
+            inclusive,
         })
-        .collect();
-    (attributes, visibility, signature, versions, function_body)
+    }
+}
+
+/// The arguments of a `#[bolt_version(...)]` attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct VersionSpecs(Vec<VersionSpec>);
+
+impl Parse for VersionSpecs {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        if input.is_empty() {
+            return Err(input.error("expected at least one Bolt version"));
+        }
+        let mut specs = Vec::new();
+        let mut bounds = (None, None);
+        let span = input.span();
+        for spec in Punctuated::<VersionSpec, Token![,]>::parse_terminated(input)? {
+            // Combine `min` and `max` into a single range
+            match spec {
+                VersionSpec::Range {
+                    start: Some(start),
+                    end: None,
+                    inclusive: true,
+                } if bounds.0.is_none() => bounds.0 = Some(start),
+                VersionSpec::Range {
+                    start: None,
+                    end: Some(end),
+                    inclusive: true,
+                } if bounds.1.is_none() => bounds.1 = Some(end),
+                spec => specs.push(spec),
+            }
+        }
+        if bounds != (None, None) {
+            specs.push(VersionSpec::Range {
+                start: bounds.0,
+                end: bounds.1,
+                inclusive: true,
+            });
+        }
+
+        for spec in &specs {
+            if let VersionSpec::Range {
+                start: Some(start),
+                end: Some(end),
+                inclusive,
+            } = spec
+            {
+                if start > end || (start == end && !inclusive) {
+                    return Err(Error::new(
+                        span,
+                        format!(
+                            "empty version range: {} to {}",
+                            start.to_doc(),
+                            end.to_doc()
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(VersionSpecs(specs))
+    }
 }
 
+impl VersionSpecs {
+    fn to_doc(&self) -> String {
+        let versions: Vec<String> = self.0.iter().map(VersionSpec::to_doc).collect();
+        format!(
+            "Supported for Bolt {}. For other versions, \
+             [`UnsupportedOperation`](crate::error::CommunicationError::UnsupportedOperation) is \
+             returned.",
+            versions.join(", ")
+        )
+    }
+}
+
+/// Restrict a `Client` method to the given Bolt versions, returning an `UnsupportedOperation`
+/// error for others. The method's error type must implement `From<CommunicationError>`. A
+/// "Compatibility" section listing the versions is added to the method's docs.
+///
+/// Versions can be listed individually, or as ranges:
+/// - `#[bolt_version(1, 2, 4.1)]`
+/// - `#[bolt_version(min = 3)]`, `#[bolt_version(max = 2)]`, or both
+/// - `#[bolt_version(4.3..=5.4)]`, `#[bolt_version(4.3..5)]`, or `#[bolt_version(4.3..)]`
+///
+/// Unbounded ranges include versions newer than this crate, so they don't need updating when a new
+/// version is supported.
 #[proc_macro_attribute]
 pub fn bolt_version(attr_args: TokenStream, item: TokenStream) -> TokenStream {
-    let func = syn::parse_macro_input!(item as syn::ItemFn);
-    let args = syn::parse_macro_input!(attr_args as syn::AttributeArgs);
-    let (attributes, visibility, signature, versions, function_body) = get_fn_info(&func, args);
+    let func = parse_macro_input!(item as ItemFn);
+    let specs = parse_macro_input!(attr_args as VersionSpecs);
+
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = &func;
+    let conditions = specs.0.iter().map(VersionSpec::to_condition);
+    let doc = specs.to_doc();
+    let operation = sig.ident.to_string();
 
     quote!(
-        #(#attributes)*
-        #visibility #signature {
-            if [#(#versions),*].contains(&self.version) {
-                #function_body
+        #(#attrs)*
+        #[doc = ""]
+        #[doc = "# Compatibility"]
+        #[doc = #doc]
+        #vis #sig {
+            if {
+                let version = self.raw_version();
+                let version = (version & 0xff, version >> 8 & 0xff);
+                false #(|| #conditions)*
+            } {
+                #block
             } else {
-                Err(crate::error::CommunicationError::UnsupportedOperation(self.version))
+                Err(crate::error::CommunicationError::UnsupportedOperation {
+                    operation: #operation,
+                    version: self.raw_version(),
+                }
+                .into())
             }
         }
     )
     .into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn specs(input: &str) -> Result<VersionSpecs> {
+        syn::parse_str(input)
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            specs("1, 4.1").unwrap(),
+            VersionSpecs(vec![
+                VersionSpec::Exact(Version(1, 0)),
+                VersionSpec::Exact(Version(4, 1))
+            ])
+        );
+        assert_eq!(
+            specs("min = 3").unwrap().to_doc(),
+            specs("3..").unwrap().to_doc()
+        );
+        assert_eq!(
+            specs("min = 4.3, max = 5.4").unwrap(),
+            specs("4.3..=5.4").unwrap()
+        );
+        assert_eq!(
+            specs("1..=2").unwrap().0,
+            [VersionSpec::Range {
+                start: Some(Version(1, 0)),
+                end: Some(Version(2, 0)),
+                inclusive: true
+            }]
+        );
+        assert_eq!(
+            specs("..4").unwrap().0,
+            [VersionSpec::Range {
+                start: None,
+                end: Some(Version(4, 0)),
+                inclusive: false
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        for (input, error) in [
+            (
+                "",
+                "unexpected end of input, expected at least one Bolt version",
+            ),
+            ("\"4\"", "expected a Bolt version, like `4` or `4.1`"),
+            ("4u8", "expected a Bolt version, like `4` or `4.1`"),
+            ("0", "invalid Bolt version"),
+            ("since = 3", "expected `min` or `max`"),
+            (
+                "3..=",
+                "unexpected end of input, expected a Bolt version after `..`",
+            ),
+            (
+                "..",
+                "unexpected end of input, expected a Bolt version after `..`",
+            ),
+            ("4.4..4.1", "empty version range: v4.4 to v4.1"),
+        ] {
+            assert_eq!(specs(input).unwrap_err().to_string(), error, "{}", input);
+        }
+    }
+
+    #[test]
+    fn doc() {
+        assert!(specs("min = 3, 1")
+            .unwrap()
+            .to_doc()
+            .starts_with("Supported for Bolt v1, v3+."));
+        assert!(specs("1..=2")
+            .unwrap()
+            .to_doc()
+            .starts_with("Supported for Bolt v1 - v2."));
+    }
+}
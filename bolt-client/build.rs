@@ -0,0 +1,22 @@
+use std::{env, process::Command};
+
+fn main() {
+    // Used to fill in the default bolt_agent sent to servers
+    println!(
+        "cargo:rustc-env=BOLT_CLIENT_TARGET={}",
+        env::var("TARGET").unwrap_or_default()
+    );
+
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| String::from("rustc"));
+    let rustc_version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or_default();
+    println!(
+        "cargo:rustc-env=BOLT_CLIENT_RUSTC_VERSION={}",
+        rustc_version.trim()
+    );
+    println!("cargo:rerun-if-changed=build.rs");
+}
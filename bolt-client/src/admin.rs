@@ -0,0 +1,574 @@
+//! Helpers for administering the databases of a multi-database server, such as Neo4j 4.0+.
+//!
+//! The [`Client::list_databases`], [`Client::create_database`], and [`Client::drop_database`]
+//! methods run the corresponding administration queries against the `system` database, as
+//! auto-commit transactions. A newly created database may take a while to start, so
+//! [`Client::create_database`] returns a [`PendingDatabase`], which can wait for the database to
+//! come online:
+//! ```
+//! # use std::{env, time::Duration};
+//! # use bolt_client::*;
+//! # use bolt_proto::version::*;
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let result = ClientBuilder::new(env::var("BOLT_TEST_ADDR")?)
+//! #     .with_domain(env::var("BOLT_TEST_DOMAIN").ok())
+//! #     .with_versions([V4_4, V4_3, V4_2, V4_1])
+//! #     .with_basic_auth(env::var("BOLT_TEST_USERNAME")?, env::var("BOLT_TEST_PASSWORD")?)
+//! #     .connect()
+//! #     .await;
+//! # skip_if_handshake_failed!(result, Ok(()));
+//! # let mut client = result.unwrap();
+//! let databases = client
+//!     .create_database("reports")
+//!     .await?
+//!     .wait_online(Duration::from_secs(30))
+//!     .await?;
+//! assert!(databases.iter().all(|db| db.is_online()));
+//!
+//! client.drop_database("reports").await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Creating and dropping databases is only supported by the Enterprise Edition of Neo4j.
+//!
+//! Users can be managed the same way, with [`Client::list_users`], [`Client::create_user`],
+//! [`Client::alter_user_password`], and [`Client::drop_user`], and roles can be granted to and
+//! revoked from users with [`Client::grant_role`] and [`Client::revoke_role`]. Passwords are sent
+//! as query parameters, so they are never part of the query text. Like the database helpers, these
+//! queries set the `db` entry to `system` and run as the authenticated user, without an `imp_user`
+//! entry.
+//! ```
+//! # use std::env;
+//! # use bolt_client::*;
+//! # use bolt_proto::version::*;
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let result = ClientBuilder::new(env::var("BOLT_TEST_ADDR")?)
+//! #     .with_domain(env::var("BOLT_TEST_DOMAIN").ok())
+//! #     .with_versions([V4_4, V4_3, V4_2, V4_1])
+//! #     .with_basic_auth(env::var("BOLT_TEST_USERNAME")?, env::var("BOLT_TEST_PASSWORD")?)
+//! #     .connect()
+//! #     .await;
+//! # skip_if_handshake_failed!(result, Ok(()));
+//! # let mut client = result.unwrap();
+//! client.create_user("reporter", "initial password", true).await?;
+//! let users = client.list_users().await?;
+//! let reporter = users.iter().find(|user| user.name() == "reporter").unwrap();
+//! assert!(reporter.password_change_required());
+//!
+//! client.drop_user("reporter").await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Roles, and suspended users, are only supported by the Enterprise Edition of Neo4j.
+
+use std::time::{Duration, Instant};
+
+use bolt_client_macros::bolt_version;
+use bolt_proto::{message::Failure, Message, Value};
+use futures_util::io::{AsyncRead, AsyncWrite};
+
+use crate::{
+    error::{AdminError, CommunicationError},
+    Client, Metadata, NamedRecord, Params,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A database on the server, as listed by `SHOW DATABASES`.
+///
+/// In a cluster, each member hosting the database is listed separately, with its own
+/// [`address`](DatabaseInfo::address), [`role`](DatabaseInfo::role), and
+/// [status](DatabaseInfo::current_status).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseInfo {
+    name: String,
+    address: Option<String>,
+    role: Option<String>,
+    requested_status: String,
+    current_status: String,
+    error: Option<String>,
+    default: bool,
+    home: bool,
+}
+
+impl DatabaseInfo {
+    /// Read a database from a record returned by `SHOW DATABASES` or `SHOW DATABASE`. Missing
+    /// columns are left empty, since the columns returned vary between server versions.
+    pub fn from_record(record: &NamedRecord) -> Self {
+        let string = |key| match record.get(key) {
+            Some(Value::String(value)) if !value.is_empty() => Some(value.clone()),
+            _ => None,
+        };
+        let flag = |key| matches!(record.get(key), Some(Value::Boolean(true)));
+
+        Self {
+            name: string("name").unwrap_or_default(),
+            address: string("address"),
+            role: string("role"),
+            requested_status: string("requestedStatus").unwrap_or_default(),
+            current_status: string("currentStatus").unwrap_or_default(),
+            error: string("error"),
+            default: flag("default"),
+            home: flag("home"),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn address(&self) -> Option<&str> {
+        self.address.as_deref()
+    }
+
+    pub fn role(&self) -> Option<&str> {
+        self.role.as_deref()
+    }
+
+    /// Get the status the database should be in, e.g. `"online"` or `"offline"`.
+    pub fn requested_status(&self) -> &str {
+        &self.requested_status
+    }
+
+    /// Get the status the database is currently in, e.g. `"initial"`, `"online"`, or
+    /// `"offline"`.
+    pub fn current_status(&self) -> &str {
+        &self.current_status
+    }
+
+    /// Get the error that prevented the database from reaching its requested status, if any.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Check whether this is the server's default database.
+    pub fn is_default(&self) -> bool {
+        self.default
+    }
+
+    /// Check whether this is the current user's home database. _(Neo4j 4.3+ only, `false` for
+    /// older versions.)_
+    pub fn is_home(&self) -> bool {
+        self.home
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.current_status == "online"
+    }
+}
+
+/// A user on the server, as listed by `SHOW USERS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserInfo {
+    name: String,
+    roles: Option<Vec<String>>,
+    password_change_required: bool,
+    suspended: Option<bool>,
+    home_database: Option<String>,
+}
+
+impl UserInfo {
+    /// Read a user from a record returned by `SHOW USERS`. Missing columns are left empty, since
+    /// the columns returned vary between server versions and editions.
+    pub fn from_record(record: &NamedRecord) -> Self {
+        let string = |key| match record.get(key) {
+            Some(Value::String(value)) if !value.is_empty() => Some(value.clone()),
+            _ => None,
+        };
+        let flag = |key| match record.get(key) {
+            Some(Value::Boolean(value)) => Some(*value),
+            _ => None,
+        };
+        let roles = match record.get("roles") {
+            Some(Value::List(roles)) => Some(
+                roles
+                    .iter()
+                    .filter_map(|role| role.as_str().map(String::from))
+                    .collect(),
+            ),
+            _ => None,
+        };
+
+        Self {
+            name: string("user").unwrap_or_default(),
+            roles,
+            password_change_required: flag("passwordChangeRequired").unwrap_or_default(),
+            suspended: flag("suspended"),
+            home_database: string("home"),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the roles granted to the user. _(Enterprise Edition only, [`None`] otherwise.)_
+    pub fn roles(&self) -> Option<&[String]> {
+        self.roles.as_deref()
+    }
+
+    /// Check whether the user must change their password the next time they log in.
+    pub fn password_change_required(&self) -> bool {
+        self.password_change_required
+    }
+
+    /// Check whether the user is suspended. _(Enterprise Edition only, [`None`] otherwise.)_
+    pub fn is_suspended(&self) -> Option<bool> {
+        self.suspended
+    }
+
+    /// Get the user's home database, if one is set. _(Neo4j 4.3+ only.)_
+    pub fn home_database(&self) -> Option<&str> {
+        self.home_database.as_deref()
+    }
+}
+
+/// A database that has been created, but may not be online yet. Returned by
+/// [`Client::create_database`].
+#[derive(Debug)]
+pub struct PendingDatabase<'a, S: AsyncRead + AsyncWrite + Unpin> {
+    client: &'a mut Client<S>,
+    name: String,
+    bookmark: Option<String>,
+}
+
+impl<'a, S: AsyncRead + AsyncWrite + Unpin> PendingDatabase<'a, S> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the bookmark of the transaction that created the database, if the server sent one.
+    pub fn bookmark(&self) -> Option<&str> {
+        self.bookmark.as_deref()
+    }
+
+    /// Poll the server until the database is online on every cluster member hosting it,
+    /// returning its [`DatabaseInfo`] from each member.
+    ///
+    /// If the database reports an error, [`AdminError::DatabaseFailed`] is returned. If it is not
+    /// online once `timeout` has elapsed, [`AdminError::Timeout`] is returned with its last
+    /// status; the database is not dropped.
+    pub async fn wait_online(self, timeout: Duration) -> Result<Vec<DatabaseInfo>, AdminError> {
+        let query = format!("SHOW DATABASE {};", escape_name(&self.name));
+        let deadline = Instant::now() + timeout;
+        loop {
+            let databases: Vec<DatabaseInfo> = self
+                .client
+                .system_query(query.clone(), None, self.bookmark.as_deref())
+                .await?
+                .iter()
+                .map(DatabaseInfo::from_record)
+                .collect();
+
+            if let Some(error) = databases.iter().find_map(DatabaseInfo::error) {
+                return Err(AdminError::DatabaseFailed {
+                    name: self.name,
+                    error: error.to_string(),
+                });
+            }
+            if !databases.is_empty() && databases.iter().all(DatabaseInfo::is_online) {
+                return Ok(databases);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                let status = match databases.first() {
+                    Some(database) => database.current_status().to_string(),
+                    None => String::from("not found"),
+                };
+                return Err(AdminError::Timeout {
+                    name: self.name,
+                    status,
+                });
+            }
+            self.client
+                .timer()
+                .sleep(POLL_INTERVAL.min(deadline - now))
+                .await;
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
+    /// List the databases on the server with `SHOW DATABASES`.
+    #[bolt_version(min = 4)]
+    pub async fn list_databases(&mut self) -> Result<Vec<DatabaseInfo>, AdminError> {
+        let records = self.system_query("SHOW DATABASES;", None, None).await?;
+        Ok(records.iter().map(DatabaseInfo::from_record).collect())
+    }
+
+    /// Create a database with `CREATE DATABASE`. The database starts in the background, so use
+    /// [`PendingDatabase::wait_online`] to wait until it can be used.
+    ///
+    /// If the server fails the query, e.g. because the database already exists,
+    /// [`AdminError::Failed`] is returned and the client is [reset](Client::reset).
+    #[bolt_version(min = 4)]
+    pub async fn create_database(
+        &mut self,
+        name: impl Into<String>,
+    ) -> Result<PendingDatabase<'_, S>, AdminError> {
+        let name = name.into();
+        let query = format!("CREATE DATABASE {};", escape_name(&name));
+        self.system_query(query, None, None).await?;
+        let bookmark = self.last_bookmark().map(String::from);
+        Ok(PendingDatabase {
+            client: self,
+            name,
+            bookmark,
+        })
+    }
+
+    /// Drop a database with `DROP DATABASE`.
+    ///
+    /// If the server fails the query, e.g. because the database doesn't exist,
+    /// [`AdminError::Failed`] is returned and the client is [reset](Client::reset).
+    #[bolt_version(min = 4)]
+    pub async fn drop_database(&mut self, name: impl AsRef<str>) -> Result<(), AdminError> {
+        let query = format!("DROP DATABASE {};", escape_name(name.as_ref()));
+        self.system_query(query, None, None).await?;
+        Ok(())
+    }
+
+    /// List the users on the server with `SHOW USERS`.
+    #[bolt_version(min = 4)]
+    pub async fn list_users(&mut self) -> Result<Vec<UserInfo>, AdminError> {
+        let records = self.system_query("SHOW USERS;", None, None).await?;
+        Ok(records.iter().map(UserInfo::from_record).collect())
+    }
+
+    /// Create a user with `CREATE USER`, with the given password. If `change_required` is
+    /// `true`, the user must change the password the first time they log in.
+    ///
+    /// If the server fails the query, e.g. because the user already exists,
+    /// [`AdminError::Failed`] is returned and the client is [reset](Client::reset).
+    #[bolt_version(min = 4)]
+    pub async fn create_user(
+        &mut self,
+        name: impl AsRef<str>,
+        password: impl Into<String>,
+        change_required: bool,
+    ) -> Result<(), AdminError> {
+        let query = format!(
+            "CREATE USER {} SET PASSWORD $password {};",
+            escape_name(name.as_ref()),
+            change_clause(change_required)
+        );
+        let params = Params::from_iter([("password", password.into())]);
+        self.system_query(query, Some(params), None).await?;
+        Ok(())
+    }
+
+    /// Set the password of a user with `ALTER USER`. If `change_required` is `true`, the user
+    /// must change the password the next time they log in.
+    ///
+    /// If the server fails the query, e.g. because the user doesn't exist,
+    /// [`AdminError::Failed`] is returned and the client is [reset](Client::reset).
+    #[bolt_version(min = 4)]
+    pub async fn alter_user_password(
+        &mut self,
+        name: impl AsRef<str>,
+        password: impl Into<String>,
+        change_required: bool,
+    ) -> Result<(), AdminError> {
+        let query = format!(
+            "ALTER USER {} SET PASSWORD $password {};",
+            escape_name(name.as_ref()),
+            change_clause(change_required)
+        );
+        let params = Params::from_iter([("password", password.into())]);
+        self.system_query(query, Some(params), None).await?;
+        Ok(())
+    }
+
+    /// Drop a user with `DROP USER`.
+    ///
+    /// If the server fails the query, e.g. because the user doesn't exist,
+    /// [`AdminError::Failed`] is returned and the client is [reset](Client::reset).
+    #[bolt_version(min = 4)]
+    pub async fn drop_user(&mut self, name: impl AsRef<str>) -> Result<(), AdminError> {
+        let query = format!("DROP USER {};", escape_name(name.as_ref()));
+        self.system_query(query, None, None).await?;
+        Ok(())
+    }
+
+    /// Grant a role to a user with `GRANT ROLE`. _(Enterprise Edition only.)_
+    ///
+    /// If the server fails the query, e.g. because the role doesn't exist,
+    /// [`AdminError::Failed`] is returned and the client is [reset](Client::reset).
+    #[bolt_version(min = 4)]
+    pub async fn grant_role(
+        &mut self,
+        role: impl AsRef<str>,
+        user: impl AsRef<str>,
+    ) -> Result<(), AdminError> {
+        let query = format!(
+            "GRANT ROLE {} TO {};",
+            escape_name(role.as_ref()),
+            escape_name(user.as_ref())
+        );
+        self.system_query(query, None, None).await?;
+        Ok(())
+    }
+
+    /// Revoke a role from a user with `REVOKE ROLE`. _(Enterprise Edition only.)_
+    ///
+    /// If the server fails the query, e.g. because the role doesn't exist,
+    /// [`AdminError::Failed`] is returned and the client is [reset](Client::reset).
+    #[bolt_version(min = 4)]
+    pub async fn revoke_role(
+        &mut self,
+        role: impl AsRef<str>,
+        user: impl AsRef<str>,
+    ) -> Result<(), AdminError> {
+        let query = format!(
+            "REVOKE ROLE {} FROM {};",
+            escape_name(role.as_ref()),
+            escape_name(user.as_ref())
+        );
+        self.system_query(query, None, None).await?;
+        Ok(())
+    }
+
+    // Run a query against the system database and pull all of its records
+    async fn system_query(
+        &mut self,
+        query: impl Into<String>,
+        params: Option<Params>,
+        bookmark: Option<&str>,
+    ) -> Result<Vec<NamedRecord>, AdminError> {
+        let mut metadata = Metadata::default().with_db("system");
+        if let Some(bookmark) = bookmark {
+            metadata = metadata.with_bookmarks([bookmark]);
+        }
+
+        match self.run(query, params, Some(metadata)).await? {
+            Message::Success(_) => {}
+            Message::Failure(failure) => return Err(self.fail_admin_query(failure).await),
+            response => return Err(self.invalid_response(response)),
+        }
+        match self
+            .pull_named(Some(Metadata::from_iter([("n", -1)])))
+            .await?
+        {
+            (records, Message::Success(_)) => Ok(records),
+            (_, Message::Failure(failure)) => Err(self.fail_admin_query(failure).await),
+            (_, response) => Err(self.invalid_response(response)),
+        }
+    }
+
+    // Reset the client after a failed query, so it can be used again
+    async fn fail_admin_query(&mut self, failure: Failure) -> AdminError {
+        match self.reset().await {
+            Ok(Message::Success(_)) => AdminError::Failed(failure),
+            Ok(response) => self.invalid_response(response),
+            Err(error) => error.into(),
+        }
+    }
+
+    fn invalid_response(&self, response: Message) -> AdminError {
+        CommunicationError::InvalidResponse {
+            state: self.server_state(),
+            request: None,
+            response,
+        }
+        .into()
+    }
+}
+
+// Quote a database, user, or role name, so that names containing special characters are handled
+// correctly
+fn escape_name(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+fn change_clause(change_required: bool) -> &'static str {
+    match change_required {
+        true => "CHANGE REQUIRED",
+        false => "CHANGE NOT REQUIRED",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn escape_name() {
+        assert_eq!(super::escape_name("reports"), "`reports`");
+        assert_eq!(super::escape_name("my-db"), "`my-db`");
+        assert_eq!(super::escape_name("a`b"), "`a``b`");
+    }
+
+    #[test]
+    fn database_info() {
+        let fields: Arc<[String]> = ["name", "address", "currentStatus", "error", "default"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let record = NamedRecord::new(
+            fields,
+            bolt_proto::message::Record::new(vec![
+                Value::from("neo4j"),
+                Value::from("localhost:7687"),
+                Value::from("online"),
+                Value::from(""),
+                Value::from(true),
+            ]),
+        );
+        let database = DatabaseInfo::from_record(&record);
+        assert_eq!(database.name(), "neo4j");
+        assert_eq!(database.address(), Some("localhost:7687"));
+        assert_eq!(database.role(), None);
+        assert_eq!(database.requested_status(), "");
+        assert!(database.is_online());
+        assert_eq!(database.error(), None);
+        assert!(database.is_default());
+        assert!(!database.is_home());
+    }
+
+    #[test]
+    fn user_info() {
+        let fields: Arc<[String]> = ["user", "roles", "passwordChangeRequired", "suspended"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let record = NamedRecord::new(
+            Arc::clone(&fields),
+            bolt_proto::message::Record::new(vec![
+                Value::from("alice"),
+                Value::from(vec!["PUBLIC", "reader"]),
+                Value::from(true),
+                Value::from(false),
+            ]),
+        );
+        let user = UserInfo::from_record(&record);
+        assert_eq!(user.name(), "alice");
+        assert_eq!(
+            user.roles(),
+            Some(&[String::from("PUBLIC"), String::from("reader")][..])
+        );
+        assert!(user.password_change_required());
+        assert_eq!(user.is_suspended(), Some(false));
+        assert_eq!(user.home_database(), None);
+
+        // Community Edition has no roles or suspension
+        let record = NamedRecord::new(
+            fields,
+            bolt_proto::message::Record::new(vec![
+                Value::from("bob"),
+                Value::Null,
+                Value::from(false),
+                Value::Null,
+            ]),
+        );
+        let user = UserInfo::from_record(&record);
+        assert_eq!(user.roles(), None);
+        assert_eq!(user.is_suspended(), None);
+    }
+}
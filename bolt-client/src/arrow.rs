@@ -0,0 +1,532 @@
+//! Conversion of query results into Arrow [`RecordBatch`]es, for use with columnar data tools.
+//!
+//! Each field of a result becomes a column, whose type is chosen from the values it contains:
+//!
+//! | Bolt value | Arrow type |
+//! |------------|------------|
+//! | [`Boolean`](Value::Boolean) | [`Boolean`](DataType::Boolean) |
+//! | [`Integer`](Value::Integer) | [`Int64`](DataType::Int64), or [`Float64`](DataType::Float64) if mixed with floats |
+//! | [`Float`](Value::Float) | [`Float64`](DataType::Float64) |
+//! | [`String`](Value::String) | [`Utf8`](DataType::Utf8) |
+//! | [`Bytes`](Value::Bytes) | [`Binary`](DataType::Binary) |
+//! | [`Date`](Value::Date) | [`Date32`](DataType::Date32) |
+//! | [`LocalTime`](Value::LocalTime) | [`Time64`](DataType::Time64) in nanoseconds |
+//! | [`Time`](Value::Time) | [`Struct`](DataType::Struct) of `time` ([`Time64`](DataType::Time64)) and `offset_seconds` ([`Int32`](DataType::Int32)) |
+//! | [`LocalDateTime`](Value::LocalDateTime) | [`Timestamp`](DataType::Timestamp) in nanoseconds, without a time zone |
+//! | [`DateTimeOffset`](Value::DateTimeOffset), [`DateTimeZoned`](Value::DateTimeZoned) | [`Timestamp`](DataType::Timestamp) in nanoseconds, with a time zone |
+//! | [`Duration`](Value::Duration) | [`Interval`](DataType::Interval) of months, days, and nanoseconds |
+//! | [`Point2D`](Value::Point2D), [`Point3D`](Value::Point3D) | [`Struct`](DataType::Struct) of `srid` ([`Int32`](DataType::Int32)) and `x`, `y`, (`z`) ([`Float64`](DataType::Float64)) |
+//!
+//! [`Null`](Value::Null) values become nulls, and a column of only nulls has the
+//! [`Null`](DataType::Null) type. Date-times keep their instant in time. If all date-times in a
+//! column have the same offset or time zone, it becomes the time zone of the column, otherwise
+//! the column is in UTC.
+//!
+//! Other values, like lists, maps, and graph structures, have no single Arrow representation and
+//! fail the conversion, as do columns mixing types not listed together above. Such values can be
+//! converted in the query instead, e.g. with Cypher's `toString` function.
+//!
+//! ```
+//! # use bolt_client::arrow::RecordBatchBuilder;
+//! # use bolt_proto::{message::Record, Value};
+//! // These would usually be the `fields` from the `SUCCESS` message returned by
+//! // `Client::run`, and the records returned by `Client::pull` or `Client::pull_stream`.
+//! let fields = ["name", "age"];
+//! let records = vec![
+//!     Record::new(vec![Value::from("Alice"), Value::from(32)]),
+//!     Record::new(vec![Value::from("Bob"), Value::Null]),
+//! ];
+//!
+//! let mut builder = RecordBatchBuilder::new(fields);
+//! for record in records {
+//!     builder.push(record)?;
+//! }
+//! let batch = builder.finish()?;
+//! assert_eq!(batch.num_rows(), 2);
+//! assert_eq!(batch.schema().field(1).data_type(), &bolt_client::arrow_schema::DataType::Int64);
+//! # Ok::<(), bolt_client::error::ArrowConversionError>(())
+//! ```
+use std::sync::Arc;
+
+use arrow_array::{
+    types::IntervalMonthDayNano, ArrayRef, BinaryArray, BooleanArray, Date32Array, Float64Array,
+    Int32Array, Int64Array, IntervalMonthDayNanoArray, NullArray, RecordBatch, StringArray,
+    StructArray, Time64NanosecondArray, TimestampNanosecondArray,
+};
+use arrow_buffer::NullBuffer;
+#[cfg(doc)]
+use arrow_schema::DataType;
+use arrow_schema::{Field, Fields, Schema};
+use bolt_proto::{message::Record, Value};
+use chrono::{DateTime, NaiveTime};
+
+use crate::error::ArrowConversionError;
+
+type Result<T> = std::result::Result<T, ArrowConversionError>;
+
+/// Collects the records of a result and converts them into a [`RecordBatch`].
+///
+/// Records are buffered until [`RecordBatchBuilder::finish`] is called, since the type of each
+/// column depends on all of its values.
+#[derive(Debug, Clone)]
+pub struct RecordBatchBuilder {
+    fields: Vec<String>,
+    columns: Vec<Vec<Value>>,
+}
+
+impl RecordBatchBuilder {
+    /// Create a builder for records with the given field names.
+    pub fn new(fields: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let fields: Vec<String> = fields.into_iter().map(Into::into).collect();
+        let columns = vec![Vec::new(); fields.len()];
+        Self { fields, columns }
+    }
+
+    /// Add a record, which must have one value per field.
+    pub fn push(&mut self, record: Record) -> Result<()> {
+        let values = record.fields();
+        if values.len() != self.fields.len() {
+            return Err(ArrowConversionError::FieldCount {
+                expected: self.fields.len(),
+                found: values.len(),
+            });
+        }
+        for (column, value) in self.columns.iter_mut().zip(values) {
+            column.push(value.clone());
+        }
+        Ok(())
+    }
+
+    /// Get the number of records added so far.
+    pub fn len(&self) -> usize {
+        self.columns.first().map(Vec::len).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Convert the added records into a [`RecordBatch`].
+    pub fn finish(self) -> Result<RecordBatch> {
+        let len = self.len();
+        let (fields, arrays): (Vec<Field>, Vec<ArrayRef>) = self
+            .fields
+            .into_iter()
+            .zip(self.columns)
+            .map(|(name, values)| column(name, &values))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+        let schema = Arc::new(Schema::new(fields));
+        let options = arrow_array::RecordBatchOptions::new().with_row_count(Some(len));
+        Ok(RecordBatch::try_new_with_options(schema, arrays, &options)?)
+    }
+}
+
+/// Convert records with the given field names into a [`RecordBatch`]. See [`RecordBatchBuilder`]
+/// for building one from records as they are received.
+pub fn record_batch(
+    fields: impl IntoIterator<Item = impl Into<String>>,
+    records: impl IntoIterator<Item = Record>,
+) -> Result<RecordBatch> {
+    let mut builder = RecordBatchBuilder::new(fields);
+    for record in records {
+        builder.push(record)?;
+    }
+    builder.finish()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Null,
+    Boolean,
+    Integer,
+    Float,
+    String,
+    Bytes,
+    Date,
+    Time,
+    LocalTime,
+    LocalDateTime,
+    DateTime,
+    Duration,
+    Point2D,
+    Point3D,
+}
+
+impl Kind {
+    fn of(value: &Value) -> std::result::Result<Self, &'static str> {
+        match value {
+            Value::Null => Ok(Kind::Null),
+            Value::Boolean(_) => Ok(Kind::Boolean),
+            Value::Integer(_) => Ok(Kind::Integer),
+            Value::Float(_) => Ok(Kind::Float),
+            Value::String(_) => Ok(Kind::String),
+            Value::Bytes(_) => Ok(Kind::Bytes),
+            Value::Date(_) => Ok(Kind::Date),
+            Value::Time(_, _) => Ok(Kind::Time),
+            Value::LocalTime(_) => Ok(Kind::LocalTime),
+            Value::LocalDateTime(_) => Ok(Kind::LocalDateTime),
+            Value::DateTimeOffset(_) | Value::DateTimeZoned(_) => Ok(Kind::DateTime),
+            Value::Duration(_) => Ok(Kind::Duration),
+            Value::Point2D(_) => Ok(Kind::Point2D),
+            Value::Point3D(_) => Ok(Kind::Point3D),
+            Value::List(_) | Value::FloatArray(_) => Err("list"),
+            Value::Map(_) => Err("map"),
+            Value::Node(_) => Err("node"),
+            Value::Relationship(_) => Err("relationship"),
+            Value::Path(_) => Err("path"),
+            Value::UnboundRelationship(_) => Err("unbound relationship"),
+            Value::Custom(_, _) => Err("custom structure"),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Null => "null",
+            Kind::Boolean => "boolean",
+            Kind::Integer => "integer",
+            Kind::Float => "float",
+            Kind::String => "string",
+            Kind::Bytes => "bytes",
+            Kind::Date => "date",
+            Kind::Time => "time",
+            Kind::LocalTime => "local time",
+            Kind::LocalDateTime => "local date-time",
+            Kind::DateTime => "date-time",
+            Kind::Duration => "duration",
+            Kind::Point2D => "2D point",
+            Kind::Point3D => "3D point",
+        }
+    }
+}
+
+fn column_kind(name: &str, values: &[Value]) -> Result<Kind> {
+    let mut column_kind = Kind::Null;
+    for value in values {
+        let kind = Kind::of(value).map_err(|kind| ArrowConversionError::Unsupported {
+            column: name.to_string(),
+            kind,
+        })?;
+        column_kind = match (column_kind, kind) {
+            (current, Kind::Null) => current,
+            (Kind::Null, kind) => kind,
+            (Kind::Integer, Kind::Float) | (Kind::Float, Kind::Integer) => Kind::Float,
+            (current, kind) if current == kind => current,
+            (current, kind) => {
+                return Err(ArrowConversionError::MixedTypes {
+                    column: name.to_string(),
+                    first: current.name(),
+                    second: kind.name(),
+                })
+            }
+        };
+    }
+    Ok(column_kind)
+}
+
+// Convert each non-null value, failing if a conversion is out of range
+fn convert<'a, T>(
+    name: &str,
+    values: &'a [Value],
+    convert: impl Fn(&'a Value) -> Option<T>,
+) -> Result<Vec<Option<T>>> {
+    values
+        .iter()
+        .map(|value| match value {
+            Value::Null => Ok(None),
+            value => convert(value)
+                .map(Some)
+                .ok_or_else(|| ArrowConversionError::OutOfRange {
+                    column: name.to_string(),
+                }),
+        })
+        .collect()
+}
+
+fn nanos_since_midnight(time: &NaiveTime) -> Option<i64> {
+    time.signed_duration_since(NaiveTime::MIN).num_nanoseconds()
+}
+
+// Build a struct column, with default child values in null rows
+fn struct_array(values: &[Value], children: Vec<(&str, ArrayRef)>) -> Result<ArrayRef> {
+    let (fields, arrays): (Vec<Field>, Vec<ArrayRef>) = children
+        .into_iter()
+        .map(|(name, array)| (Field::new(name, array.data_type().clone(), false), array))
+        .unzip();
+    let nulls = NullBuffer::from_iter(values.iter().map(|value| *value != Value::Null));
+    Ok(Arc::new(StructArray::try_new(
+        Fields::from(fields),
+        arrays,
+        Some(nulls),
+    )?))
+}
+
+fn column(name: String, values: &[Value]) -> Result<(Field, ArrayRef)> {
+    let array: ArrayRef = match column_kind(&name, values)? {
+        Kind::Null => Arc::new(NullArray::new(values.len())),
+        Kind::Boolean => Arc::new(BooleanArray::from(convert(
+            &name,
+            values,
+            |value| match value {
+                Value::Boolean(boolean) => Some(*boolean),
+                _ => None,
+            },
+        )?)),
+        Kind::Integer => Arc::new(Int64Array::from(convert(
+            &name,
+            values,
+            |value| match value {
+                Value::Integer(integer) => Some(*integer),
+                _ => None,
+            },
+        )?)),
+        Kind::Float => Arc::new(Float64Array::from(convert(
+            &name,
+            values,
+            |value| match value {
+                Value::Integer(integer) => Some(*integer as f64),
+                Value::Float(float) => Some(*float),
+                _ => None,
+            },
+        )?)),
+        Kind::String => Arc::new(StringArray::from_iter(convert(
+            &name,
+            values,
+            |value| match value {
+                Value::String(string) => Some(string.as_str()),
+                _ => None,
+            },
+        )?)),
+        Kind::Bytes => Arc::new(BinaryArray::from_iter(convert(
+            &name,
+            values,
+            |value| match value {
+                Value::Bytes(bytes) => Some(bytes.as_slice()),
+                _ => None,
+            },
+        )?)),
+        Kind::Date => Arc::new(Date32Array::from(convert(
+            &name,
+            values,
+            |value| match value {
+                Value::Date(date) => date
+                    .signed_duration_since(DateTime::UNIX_EPOCH.date_naive())
+                    .num_days()
+                    .try_into()
+                    .ok(),
+                _ => None,
+            },
+        )?)),
+        Kind::LocalTime => Arc::new(Time64NanosecondArray::from(convert(
+            &name,
+            values,
+            |value| match value {
+                Value::LocalTime(time) => nanos_since_midnight(time),
+                _ => None,
+            },
+        )?)),
+        Kind::Time => {
+            let times = convert(&name, values, |value| match value {
+                Value::Time(time, offset) => {
+                    Some((nanos_since_midnight(time)?, offset.local_minus_utc()))
+                }
+                _ => None,
+            })?;
+            let (times, offsets): (Vec<i64>, Vec<i32>) =
+                times.into_iter().map(Option::unwrap_or_default).unzip();
+            struct_array(
+                values,
+                vec![
+                    ("time", Arc::new(Time64NanosecondArray::from(times))),
+                    ("offset_seconds", Arc::new(Int32Array::from(offsets))),
+                ],
+            )?
+        }
+        Kind::LocalDateTime => Arc::new(TimestampNanosecondArray::from(convert(
+            &name,
+            values,
+            |value| match value {
+                Value::LocalDateTime(date_time) => date_time.and_utc().timestamp_nanos_opt(),
+                _ => None,
+            },
+        )?)),
+        Kind::DateTime => {
+            let timestamps = convert(&name, values, |value| match value {
+                Value::DateTimeOffset(date_time) => Some((
+                    date_time.timestamp_nanos_opt()?,
+                    date_time.offset().to_string(),
+                )),
+                Value::DateTimeZoned(date_time) => Some((
+                    date_time.timestamp_nanos_opt()?,
+                    date_time.timezone().to_string(),
+                )),
+                _ => None,
+            })?;
+            let mut time_zones = timestamps.iter().flatten().map(|(_, zone)| zone);
+            let time_zone = match time_zones.next() {
+                Some(first) if time_zones.all(|zone| zone == first) => first.clone(),
+                _ => String::from("UTC"),
+            };
+            let timestamps: Vec<Option<i64>> = timestamps
+                .into_iter()
+                .map(|timestamp| timestamp.map(|(nanos, _)| nanos))
+                .collect();
+            Arc::new(TimestampNanosecondArray::from(timestamps).with_timezone(time_zone))
+        }
+        Kind::Duration => Arc::new(IntervalMonthDayNanoArray::from(convert(
+            &name,
+            values,
+            |value| match value {
+                Value::Duration(duration) => Some(IntervalMonthDayNano::new(
+                    duration.months().try_into().ok()?,
+                    duration.days().try_into().ok()?,
+                    duration
+                        .seconds()
+                        .checked_mul(1_000_000_000)?
+                        .checked_add(duration.nanos().into())?,
+                )),
+                _ => None,
+            },
+        )?)),
+        kind @ (Kind::Point2D | Kind::Point3D) => {
+            let points: Vec<(i32, [f64; 3])> = values
+                .iter()
+                .map(|value| match value {
+                    Value::Point2D(point) => (point.srid(), [point.x(), point.y(), 0.0]),
+                    Value::Point3D(point) => (point.srid(), [point.x(), point.y(), point.z()]),
+                    _ => Default::default(),
+                })
+                .collect();
+            let dimensions = if kind == Kind::Point3D { 3 } else { 2 };
+            let mut children: Vec<(&str, ArrayRef)> = vec![(
+                "srid",
+                Arc::new(Int32Array::from_iter_values(
+                    points.iter().map(|(srid, _)| *srid),
+                )),
+            )];
+            for (index, name) in ["x", "y", "z"].into_iter().enumerate().take(dimensions) {
+                children.push((
+                    name,
+                    Arc::new(Float64Array::from_iter_values(
+                        points.iter().map(|(_, coordinates)| coordinates[index]),
+                    )),
+                ));
+            }
+            struct_array(values, children)?
+        }
+    };
+    let field = Field::new(name, array.data_type().clone(), true);
+    Ok((field, array))
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{cast::AsArray, types::*, Array};
+    use arrow_schema::DataType;
+    use bolt_proto::value::{Duration, Point2D};
+    use chrono::{FixedOffset, NaiveDate, TimeZone};
+
+    use super::*;
+
+    #[test]
+    fn scalars() {
+        let batch = record_batch(
+            ["b", "n", "s", "empty"],
+            vec![
+                Record::new(vec![
+                    Value::from(true),
+                    Value::from(1),
+                    Value::from("a"),
+                    Value::Null,
+                ]),
+                Record::new(vec![
+                    Value::Null,
+                    Value::from(2.5),
+                    Value::Null,
+                    Value::Null,
+                ]),
+            ],
+        )
+        .unwrap();
+        assert_eq!(batch.num_rows(), 2);
+
+        let schema = batch.schema();
+        let types: Vec<&DataType> = schema.fields().iter().map(|f| f.data_type()).collect();
+        assert_eq!(
+            types,
+            [
+                &DataType::Boolean,
+                &DataType::Float64,
+                &DataType::Utf8,
+                &DataType::Null
+            ]
+        );
+        assert!(batch.column(0).as_boolean().value(0));
+        assert!(batch.column(0).is_null(1));
+        assert_eq!(
+            batch.column(1).as_primitive::<Float64Type>().values(),
+            &[1.0, 2.5]
+        );
+        assert_eq!(batch.column(2).as_string::<i32>().value(0), "a");
+    }
+
+    #[test]
+    fn temporal_and_spatial() {
+        let offset = FixedOffset::east_opt(3600).unwrap();
+        let date_time = offset.with_ymd_and_hms(1970, 1, 1, 1, 0, 1).unwrap();
+        let batch = record_batch(
+            ["date", "date_time", "duration", "point"],
+            vec![Record::new(vec![
+                Value::from(NaiveDate::from_ymd_opt(1970, 1, 3).unwrap()),
+                Value::from(date_time),
+                Value::from(Duration::new(1, 2, 3, 4)),
+                Value::from(Point2D::new(7203, 1.0, 2.0)),
+            ])],
+        )
+        .unwrap();
+
+        assert_eq!(batch.column(0).as_primitive::<Date32Type>().value(0), 2);
+
+        let timestamps = batch.column(1).as_primitive::<TimestampNanosecondType>();
+        assert_eq!(timestamps.value(0), 1_000_000_000);
+        assert_eq!(timestamps.timezone(), Some("+01:00"));
+
+        let durations = batch.column(2).as_primitive::<IntervalMonthDayNanoType>();
+        assert_eq!(
+            durations.value(0),
+            IntervalMonthDayNano::new(1, 2, 3_000_000_004)
+        );
+
+        let points = batch.column(3).as_struct();
+        assert_eq!(points.column_names(), ["srid", "x", "y"]);
+        assert_eq!(points.column(0).as_primitive::<Int32Type>().value(0), 7203);
+    }
+
+    #[test]
+    fn errors() {
+        let mut builder = RecordBatchBuilder::new(["a"]);
+        assert!(matches!(
+            builder.push(Record::new(vec![])),
+            Err(ArrowConversionError::FieldCount {
+                expected: 1,
+                found: 0
+            })
+        ));
+        builder.push(Record::new(vec![Value::from(1)])).unwrap();
+        builder.push(Record::new(vec![Value::from("1")])).unwrap();
+        assert!(matches!(
+            builder.finish(),
+            Err(ArrowConversionError::MixedTypes {
+                first: "integer",
+                second: "string",
+                ..
+            })
+        ));
+
+        assert!(matches!(
+            record_batch(["a"], vec![Record::new(vec![Value::List(vec![])])]),
+            Err(ArrowConversionError::Unsupported { kind: "list", .. })
+        ));
+    }
+}
@@ -0,0 +1,336 @@
+use std::{
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use crate::error::BookmarkError;
+
+const LEGACY_PREFIX: &str = "neo4j:bookmark:v1:tx";
+
+/// A bookmark returned by the server after a transaction is committed, which can be passed to later
+/// transactions so that they see its changes.
+///
+/// Bookmarks are opaque to clients, with one exception: Neo4j 3.5 uses the format
+/// `neo4j:bookmark:v1:tx<id>`, where `<id>` is the ID of the committed transaction. Bookmarks in
+/// this format are checked to have a valid ID, which is available from
+/// [`Bookmark::transaction_id`]. Other bookmarks, like the ones used by Neo4j 4+, only need to be
+/// non-empty and free of whitespace.
+///
+/// ```
+/// # use bolt_client::Bookmark;
+/// let bookmark: Bookmark = "neo4j:bookmark:v1:tx42".parse().unwrap();
+/// assert_eq!(bookmark.transaction_id(), Some(42));
+/// assert!(Bookmark::parse("neo4j:bookmark:v1:txabc").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Bookmark(String);
+
+impl Bookmark {
+    pub fn parse(bookmark: impl Into<String>) -> Result<Self, BookmarkError> {
+        let bookmark = bookmark.into();
+        if bookmark.is_empty() {
+            return Err(BookmarkError::Empty);
+        }
+        if bookmark.contains(char::is_whitespace) {
+            return Err(BookmarkError::Malformed(bookmark));
+        }
+        if let Some(id) = bookmark.strip_prefix(LEGACY_PREFIX) {
+            if id.parse::<u64>().is_err() {
+                return Err(BookmarkError::Malformed(bookmark));
+            }
+        }
+        Ok(Self(bookmark))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Get the transaction ID of a bookmark in the Neo4j 3.5 format, `neo4j:bookmark:v1:tx<id>`.
+    pub fn transaction_id(&self) -> Option<u64> {
+        self.0
+            .strip_prefix(LEGACY_PREFIX)
+            .and_then(|id| id.parse().ok())
+    }
+}
+
+impl FromStr for Bookmark {
+    type Err = BookmarkError;
+
+    fn from_str(bookmark: &str) -> Result<Self, Self::Err> {
+        Self::parse(bookmark)
+    }
+}
+
+impl TryFrom<String> for Bookmark {
+    type Error = BookmarkError;
+
+    fn try_from(bookmark: String) -> Result<Self, Self::Error> {
+        Self::parse(bookmark)
+    }
+}
+
+impl fmt::Display for Bookmark {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<Bookmark> for String {
+    fn from(bookmark: Bookmark) -> Self {
+        bookmark.0
+    }
+}
+
+/// A set of [`Bookmark`]s, for transactions that should see the changes of several earlier ones.
+///
+/// Bookmarks are kept in the order they were added, without duplicates. Since a Neo4j 3.5 bookmark
+/// includes the changes of all earlier transactions, only the one with the highest
+/// [transaction ID](Bookmark::transaction_id) is kept when several are added.
+///
+/// `Bookmarks` can be passed wherever a list of bookmark strings is accepted, e.g. to
+/// [`Metadata::with_bookmarks`](crate::Metadata::with_bookmarks) or
+/// [`Client::route`](crate::Client::route).
+/// ```
+/// # use bolt_client::{Bookmark, Bookmarks, Metadata};
+/// let mut bookmarks = Bookmarks::new();
+/// bookmarks.insert("FB:kcwQhRyDJvPKTnSn8dV0dNMhdzmQ".parse().unwrap());
+/// bookmarks.merge(Bookmarks::from_iter([
+///     Bookmark::parse("neo4j:bookmark:v1:tx2").unwrap(),
+///     Bookmark::parse("neo4j:bookmark:v1:tx5").unwrap(),
+/// ]));
+/// assert_eq!(bookmarks.len(), 2);
+///
+/// let metadata = Metadata::default().with_bookmarks(bookmarks);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bookmarks(Vec<Bookmark>);
+
+impl Bookmarks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a bookmark, unless it is already in the set or is superseded by one that is.
+    pub fn insert(&mut self, bookmark: Bookmark) {
+        if self.0.contains(&bookmark) {
+            return;
+        }
+        if let Some(id) = bookmark.transaction_id() {
+            match self.0.iter().position(|b| b.transaction_id().is_some()) {
+                Some(index) if self.0[index].transaction_id() >= Some(id) => return,
+                Some(index) => {
+                    self.0.remove(index);
+                }
+                None => {}
+            }
+        }
+        self.0.push(bookmark);
+    }
+
+    /// Add all bookmarks from another set.
+    pub fn merge(&mut self, other: Bookmarks) {
+        for bookmark in other {
+            self.insert(bookmark);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, bookmark: &Bookmark) -> bool {
+        self.0.contains(bookmark)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Bookmark> {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<Bookmark> for Bookmarks {
+    fn from_iter<T: IntoIterator<Item = Bookmark>>(iter: T) -> Self {
+        let mut bookmarks = Self::new();
+        for bookmark in iter {
+            bookmarks.insert(bookmark);
+        }
+        bookmarks
+    }
+}
+
+impl Extend<Bookmark> for Bookmarks {
+    fn extend<T: IntoIterator<Item = Bookmark>>(&mut self, iter: T) {
+        for bookmark in iter {
+            self.insert(bookmark);
+        }
+    }
+}
+
+impl IntoIterator for Bookmarks {
+    type Item = Bookmark;
+    type IntoIter = std::vec::IntoIter<Bookmark>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Bookmarks {
+    type Item = &'a Bookmark;
+    type IntoIter = std::slice::Iter<'a, Bookmark>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl From<Bookmark> for Bookmarks {
+    fn from(bookmark: Bookmark) -> Self {
+        Self(vec![bookmark])
+    }
+}
+
+impl From<Bookmarks> for Vec<String> {
+    fn from(bookmarks: Bookmarks) -> Self {
+        bookmarks.into_iter().map(String::from).collect()
+    }
+}
+
+/// A set of [`Bookmarks`] shared by several clients, so that each transaction sees the changes
+/// committed before it on any of them.
+///
+/// A client with a bookmark manager (see [`Client::set_bookmark_manager`](crate::Client::set_bookmark_manager))
+/// sends its bookmarks with every [`Client::begin`](crate::Client::begin) whose metadata has no
+/// `bookmarks` entry. Once the transaction commits, the bookmark the server returns replaces the
+/// bookmarks it was started with. Clones of a manager share the same bookmarks, so giving one
+/// manager to every connection of a pool (e.g. with
+/// [`ClientBuilder::with_bookmark_manager`](crate::ClientBuilder::with_bookmark_manager)) lets a
+/// read transaction see a write committed on another connection.
+///
+/// ```
+/// # use bolt_client::{Bookmark, BookmarkManager, Bookmarks};
+/// let manager = BookmarkManager::with_bookmarks(Bookmarks::from_iter([
+///     Bookmark::parse("FB:kcwQhRyDJvPKTnSn8dV0dNMhdzmQ").unwrap(),
+/// ]));
+/// let shared = manager.clone();
+///
+/// // A transaction that started with the manager's bookmarks has committed
+/// let used = shared.bookmarks();
+/// shared.update(&used, Bookmark::parse("FB:kcwQhRyDJvPKTnSn8dV0dNMhdzmR").unwrap());
+/// assert_eq!(
+///     Vec::<String>::from(manager.bookmarks()),
+///     vec!["FB:kcwQhRyDJvPKTnSn8dV0dNMhdzmR"]
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BookmarkManager {
+    bookmarks: Arc<Mutex<Bookmarks>>,
+}
+
+impl BookmarkManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a manager starting with the given bookmarks, e.g. ones passed on from another
+    /// process.
+    pub fn with_bookmarks(bookmarks: Bookmarks) -> Self {
+        Self {
+            bookmarks: Arc::new(Mutex::new(bookmarks)),
+        }
+    }
+
+    /// Get the current bookmarks.
+    pub fn bookmarks(&self) -> Bookmarks {
+        self.lock().clone()
+    }
+
+    /// Record the bookmark of a committed transaction, replacing the bookmarks it was started with,
+    /// which the new bookmark includes. Other bookmarks, e.g. from transactions that committed on
+    /// other connections in the meantime, are kept.
+    pub fn update(&self, used: &Bookmarks, bookmark: Bookmark) {
+        let mut bookmarks = self.lock();
+        bookmarks.0.retain(|b| !used.contains(b));
+        bookmarks.insert(bookmark);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Bookmarks> {
+        self.bookmarks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(bookmark: &str) -> Bookmark {
+        Bookmark::parse(bookmark).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(bookmark("neo4j:bookmark:v1:tx7").transaction_id(), Some(7));
+        assert_eq!(
+            bookmark("FB:kcwQhRyDJvPKTnSn8dV0dNMhdzmQ").transaction_id(),
+            None
+        );
+        assert!(matches!(Bookmark::parse(""), Err(BookmarkError::Empty)));
+        for malformed in ["neo4j:bookmark:v1:tx", "neo4j:bookmark:v1:tx-1", "a b"] {
+            assert!(
+                matches!(Bookmark::parse(malformed), Err(BookmarkError::Malformed(_))),
+                "{}",
+                malformed
+            );
+        }
+    }
+
+    #[test]
+    fn merge() {
+        let mut bookmarks = Bookmarks::from_iter([
+            bookmark("neo4j:bookmark:v1:tx3"),
+            bookmark("FB:a"),
+            bookmark("FB:a"),
+        ]);
+        bookmarks.merge(Bookmarks::from_iter([
+            bookmark("neo4j:bookmark:v1:tx1"),
+            bookmark("FB:b"),
+        ]));
+        assert_eq!(
+            Vec::<String>::from(bookmarks.clone()),
+            vec!["neo4j:bookmark:v1:tx3", "FB:a", "FB:b"]
+        );
+
+        bookmarks.insert(bookmark("neo4j:bookmark:v1:tx10"));
+        assert_eq!(
+            Vec::<String>::from(bookmarks),
+            vec!["FB:a", "FB:b", "neo4j:bookmark:v1:tx10"]
+        );
+    }
+
+    #[test]
+    fn bookmark_manager() {
+        let manager = BookmarkManager::new();
+        let other = manager.clone();
+        assert!(manager.bookmarks().is_empty());
+
+        // Two transactions start without bookmarks and commit on different connections
+        manager.update(&Bookmarks::new(), bookmark("FB:a"));
+        other.update(&Bookmarks::new(), bookmark("FB:b"));
+        assert_eq!(
+            Vec::<String>::from(manager.bookmarks()),
+            vec!["FB:a", "FB:b"]
+        );
+
+        // A transaction that waited for both replaces them
+        let used = other.bookmarks();
+        other.update(&used, bookmark("FB:c"));
+        assert_eq!(Vec::<String>::from(manager.bookmarks()), vec!["FB:c"]);
+    }
+}
@@ -7,22 +7,48 @@
 //
 // The aforementioned documentation comments are thus licensed under CC BY-NC-SA 4.0.
 
-use std::{collections::VecDeque, io};
+use std::{io, sync::Arc};
 
 use bytes::*;
 use futures_util::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 use bolt_client_macros::*;
 use bolt_proto::{
-    error::Error as ProtocolError, message::*, version::*, Message, ServerState, ServerState::*,
+    error::{DeserializationError, Error as ProtocolError},
+    message::*,
+    value::Utf8Mode,
+    version::*,
+    Message, ServerState,
+    ServerState::*,
     Value,
 };
 
 use crate::{
     error::{CommunicationError, CommunicationResult, ConnectionError, ConnectionResult},
-    Metadata, Params, RoutingContext,
+    timer::default_timer,
+    Bookmark, BookmarkManager, Bookmarks, ClientInfo, Feature, Metadata, NamedRecord,
+    ParamRedaction, Params, PreparedQuery, QueryHeader, RetryPolicy, RoutingContext, Timer,
+    TransactionStatus,
 };
 
+mod batch;
+mod exchanges;
+mod framing;
+mod interceptor;
+mod keep_alive;
+mod metadata;
+mod open_streams;
+#[cfg(feature = "tokio-stream")]
+mod reconnect;
+mod record_stream;
+mod session;
+mod split;
+mod state_change;
+mod stats;
+mod transaction;
+#[cfg(test)]
+mod transports;
+mod tx_streams;
 mod v1;
 mod v2;
 mod v3;
@@ -32,7 +58,29 @@ mod v4_2;
 mod v4_3;
 mod v4_4;
 
+pub use batch::BatchSummary;
+pub use exchanges::Exchange;
+use framing::{ReadBuffer, WriteBuffer};
+pub use interceptor::Interceptor;
+use metadata::{default_n, remove_notification_config};
+pub(crate) use metadata::{shape_metadata, MetadataTarget, ShapedMetadata};
+pub use record_stream::RecordStream;
+use session::{check_supported, is_closed, Session};
+pub use split::{ClientReader, ClientWriter, ReuniteError};
+pub use state_change::StateChange;
+use state_change::StateChangeHook;
+pub use stats::ClientStats;
+use stats::StatsTracker;
+pub use tx_streams::{Transaction, TxRecordStream};
+
 const PREAMBLE: [u8; 4] = [0x60, 0x60, 0xB0, 0x17];
+// The number of records requested in each batch of a stream, unless another is given
+const DEFAULT_BATCH_SIZE: usize = 1000;
+
+/// Get the major and minor version of a negotiated version, ignoring any other bits.
+fn packed_version(version: u32) -> Version {
+    Version::new(version as u8, (version >> 8) as u8)
+}
 
 /// Return whether a version is compatible with version specifier.
 fn is_compatible(version: u32, specifier: u32) -> bool {
@@ -47,14 +95,120 @@ fn is_compatible(version: u32, specifier: u32) -> bool {
         && (specified_minor.saturating_sub(range)..=specified_minor).contains(&minor)
 }
 
+/// Read the 4 bytes of the server's handshake response, which may arrive in several parts.
+async fn read_handshake_response(
+    stream: &mut (impl AsyncRead + Unpin),
+) -> ConnectionResult<[u8; 4]> {
+    let mut response = [0; 4];
+    let mut received = 0;
+    while received < response.len() {
+        match stream.read(&mut response[received..]).await {
+            Ok(0) => {
+                return Err(ConnectionError::TruncatedHandshakeResponse {
+                    received: response[..received].to_vec(),
+                })
+            }
+            Ok(n) => received += n,
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Ok(response)
+}
+
+/// Recognize handshake responses that aren't a protocol version. Versions only use the low three
+/// bytes, so a response with a nonzero first byte came from something other than a Bolt server.
+fn check_handshake_response(response: [u8; 4]) -> ConnectionResult<()> {
+    match response {
+        [b'H', b'T', b'T', b'P'] => Err(ConnectionError::HttpResponse),
+        // A TLS alert or handshake record, for TLS 1.0 - 1.3
+        [0x15 | 0x16, 0x03, 0x01..=0x04, _] => Err(ConnectionError::TlsResponse),
+        [0, ..] => Ok(()),
+        _ => Err(ConnectionError::MalformedHandshakeResponse { response }),
+    }
+}
+
+/// Details of the version negotiation performed when a [`Client`] was created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandshakeInfo {
+    version_specifiers: [u32; 4],
+    response: [u8; 4],
+}
+
+impl HandshakeInfo {
+    /// Get the version specifiers that were proposed to the server.
+    pub fn version_specifiers(&self) -> [u32; 4] {
+        self.version_specifiers
+    }
+
+    /// Get the raw bytes the server sent in response to the proposed version specifiers.
+    pub fn response(&self) -> [u8; 4] {
+        self.response
+    }
+
+    /// Get the protocol version the server agreed to use.
+    pub fn version(&self) -> u32 {
+        u32::from_be_bytes(self.response)
+    }
+}
+
+/// How a [`Client`] handles metadata entries that are not supported by its protocol version.
+///
+/// Servers silently ignore metadata they do not understand, which can be surprising: a `db` entry
+/// sent to a Bolt v3 server, for example, would cause the query to run against the default
+/// database. This applies to the metadata passed to [`Client::run`], [`Client::begin`], and
+/// [`Client::route`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum MetadataValidation {
+    /// Return an [`UnsupportedMetadata`](CommunicationError::UnsupportedMetadata) error without
+    /// sending the message.
+    #[default]
+    Reject,
+    /// Log a warning and send the message anyway. Warnings are logged with `tracing` if the
+    /// `tracing` feature is enabled; otherwise this behaves like [`Ignore`](Self::Ignore).
+    Warn,
+    /// Send the message without checking its metadata.
+    Ignore,
+}
+
 /// An asynchronous client for Bolt servers.
+///
+/// # Cancellation
+/// The futures returned by the client's methods can be dropped at any point, e.g. when they lose a
+/// [`tokio::select!`](https://docs.rs/tokio/latest/tokio/macro.select.html) race or time out,
+/// without corrupting the connection. A message that was only partly read is kept until the rest
+/// of it arrives, and a request that was only partly written is finished before anything else is
+/// sent or received.
+///
+/// Requests that were sent before their future was dropped are still answered by the server,
+/// though. Before sending its next request, the client reads and discards any such responses, so
+/// that they are never mistaken for the responses to a later request, and the server state they
+/// leave behind is the one the new request is checked against. For example, after a
+/// [`Client::run`] is dropped, a second `run` fails with an
+/// [`InvalidState`](CommunicationError::InvalidState) error, since the first query's result is
+/// still open. Skipping the responses to a large result can take a while, so to abandon it
+/// instead, call [`Client::reset`], which makes the server stop sending it.
+///
+/// The exception is [`Client::pull_stream`], which picks up the result of a dropped
+/// [`RecordStream`] where it left off.
 #[derive(Debug)]
 pub struct Client<S: AsyncRead + AsyncWrite + Unpin> {
     stream: S,
     version: u32,
-    server_state: ServerState,
-    sent_queue: VecDeque<Message>,
-    open_tx_streams: usize,
+    handshake: HandshakeInfo,
+    session: Session,
+    metadata_validation: MetadataValidation,
+    client_info: ClientInfo,
+    retry_policy: RetryPolicy,
+    param_redaction: ParamRedaction,
+    max_in_flight: Option<usize>,
+    timer: Box<dyn Timer>,
+    decoder: MessageDecoder,
+    read_buffer: ReadBuffer,
+    write_buffer: WriteBuffer,
+    #[cfg(feature = "tokio-stream")]
+    builder: Option<Arc<crate::ClientBuilder>>,
 }
 
 impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
@@ -62,472 +216,474 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     /// with the provided protocol version specifiers, and, if this succeeds, a Client will be
     /// returned.
     pub async fn new(mut stream: S, version_specifiers: &[u32; 4]) -> ConnectionResult<Self> {
-        let mut version_specifiers_bytes = BytesMut::with_capacity(16);
+        let mut handshake = BytesMut::with_capacity(PREAMBLE.len() + 16);
+        handshake.put_slice(&PREAMBLE);
         version_specifiers
             .iter()
-            .for_each(|&v| version_specifiers_bytes.put_u32(v));
-        stream.write_all(&PREAMBLE).await?;
-        stream.write_all(&version_specifiers_bytes).await?;
+            .for_each(|&v| handshake.put_u32(v));
+        stream.write_all(&handshake).await?;
         stream.flush().await?;
 
-        let mut u32_bytes = [0, 0, 0, 0];
-        stream.read_exact(&mut u32_bytes).await?;
-        let version = u32::from_be_bytes(u32_bytes);
+        let response = read_handshake_response(&mut stream).await?;
+        check_handshake_response(response)?;
+        let version = u32::from_be_bytes(response);
 
         if version > 0 {
             for &specifier in version_specifiers {
                 if is_compatible(version, specifier) {
+                    let mut stats = StatsTracker::default();
+                    stats.stats.bytes_sent = handshake.len() as u64;
+                    stats.stats.bytes_received = response.len() as u64;
+
                     return Ok(Self {
                         stream,
                         version,
-                        server_state: Connected,
-                        sent_queue: VecDeque::default(),
-                        open_tx_streams: 0,
+                        handshake: HandshakeInfo {
+                            version_specifiers: *version_specifiers,
+                            response,
+                        },
+                        session: Session::new(stats),
+                        metadata_validation: MetadataValidation::default(),
+                        client_info: ClientInfo::default(),
+                        retry_policy: RetryPolicy::default(),
+                        param_redaction: ParamRedaction::default(),
+                        max_in_flight: None,
+                        timer: default_timer(),
+                        decoder: MessageDecoder::new(),
+                        read_buffer: ReadBuffer::default(),
+                        write_buffer: WriteBuffer::default(),
+                        #[cfg(feature = "tokio-stream")]
+                        builder: None,
                     });
                 }
             }
         }
-        Err(ConnectionError::HandshakeFailed(*version_specifiers))
+        if version == 0 {
+            Err(ConnectionError::HandshakeFailed {
+                specifiers: *version_specifiers,
+                response,
+            })
+        } else {
+            Err(ConnectionError::IncompatibleVersion {
+                specifiers: *version_specifiers,
+                response,
+            })
+        }
     }
 
-    /// Get the current version of this client.
-    pub fn version(&self) -> u32 {
+    /// Get the protocol version the client negotiated with the server.
+    pub fn version(&self) -> Version {
+        packed_version(self.version)
+    }
+
+    /// Get the protocol version the client negotiated with the server, packed into a `u32` like
+    /// the constants in [`bolt_proto::version`].
+    pub fn raw_version(&self) -> u32 {
         self.version
     }
 
+    /// Check whether the protocol version of this client supports the given [`Feature`].
+    pub fn supports(&self, feature: Feature) -> bool {
+        feature.is_supported_by(self.version)
+    }
+
+    /// Get the details of the handshake that was performed when this client was created.
+    pub fn handshake(&self) -> &HandshakeInfo {
+        &self.handshake
+    }
+
     /// Get the current server state for this client.
     pub fn server_state(&self) -> ServerState {
-        self.server_state
+        self.session.server_state
     }
 
-    pub(crate) async fn read_message(&mut self) -> CommunicationResult<Message> {
-        let message = Message::from_stream(&mut self.stream)
-            .await
-            .map_err(ProtocolError::from)?;
+    /// Get whether the client is in an explicit transaction, derived from the current server
+    /// state.
+    pub fn transaction_status(&self) -> TransactionStatus {
+        self.session.server_state.into()
+    }
 
-        #[cfg(test)]
-        println!("<<< {:?}\n", message);
+    /// Check whether the client is in an explicit transaction started with [`Client::begin`],
+    /// which has not failed.
+    pub fn in_transaction(&self) -> bool {
+        self.transaction_status() == TransactionStatus::Active
+    }
 
-        match (self.server_state, self.sent_queue.pop_front(), message) {
-            // CONNECTED
-            (Connected, Some(Message::Init(_)), Message::Success(success)) => {
-                self.server_state = Ready;
-                Ok(Message::Success(success))
-            }
-            (Connected, Some(Message::Init(_)), Message::Failure(failure)) => {
-                self.server_state = Defunct;
-                Ok(Message::Failure(failure))
-            }
-            (Connected, Some(Message::Hello(_)), Message::Success(success)) => {
-                self.server_state = Ready;
-                Ok(Message::Success(success))
-            }
-            (Connected, Some(Message::Hello(_)), Message::Failure(failure)) => {
-                self.server_state = Defunct;
-                Ok(Message::Failure(failure))
-            }
+    /// Get the number of results that have not been fully consumed with [`Client::pull`] or
+    /// [`Client::discard`]. Outside an explicit transaction, at most one result is open at a
+    /// time.
+    pub fn open_result_streams(&self) -> usize {
+        match self.session.server_state {
+            Streaming => 1,
+            TxStreaming => self.session.open_tx_streams.len(),
+            _ => 0,
+        }
+    }
 
-            // READY
-            (Ready, Some(Message::Run(_)), Message::Success(success)) => {
-                self.server_state = Streaming;
-                Ok(Message::Success(success))
-            }
-            (Ready, Some(Message::Run(_)), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
-            (Ready, Some(Message::RunWithMetadata(_)), Message::Success(success)) => {
-                self.server_state = Streaming;
-                Ok(Message::Success(success))
-            }
-            (Ready, Some(Message::RunWithMetadata(_)), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
-            (Ready, Some(Message::Begin(_)), Message::Success(success)) => {
-                self.server_state = TxReady;
-                Ok(Message::Success(success))
-            }
-            (Ready, Some(Message::Begin(_)), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
-            (Ready, Some(Message::Route(_)), Message::Success(success)) => {
-                self.server_state = Ready;
-                Ok(Message::Success(success))
-            }
-            (Ready, Some(Message::Route(_)), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
-            (Ready, Some(Message::RouteWithMetadata(_)), Message::Success(success)) => {
-                self.server_state = Ready;
-                Ok(Message::Success(success))
-            }
-            (Ready, Some(Message::RouteWithMetadata(_)), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
+    /// Get the information used to identify this client to the server.
+    pub fn client_info(&self) -> &ClientInfo {
+        &self.client_info
+    }
 
-            // STREAMING
-            (Streaming, Some(Message::PullAll), Message::Success(success)) => {
-                self.server_state = Ready;
-                Ok(Message::Success(success))
-            }
-            (Streaming, Some(Message::PullAll), Message::Record(record)) => {
-                self.server_state = Streaming;
-                // Put the PULL_ALL message back so we can keep consuming records
-                self.sent_queue.push_front(Message::PullAll);
-                Ok(Message::Record(record))
-            }
-            (Streaming, Some(Message::PullAll), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
-            (Streaming, Some(Message::Pull(_)), Message::Success(success)) => {
-                self.server_state = match success.metadata().get("has_more") {
-                    Some(&Value::Boolean(true)) => Streaming,
-                    _ => Ready,
-                };
-                Ok(Message::Success(success))
-            }
-            (Streaming, Some(Message::Pull(pull)), Message::Record(record)) => {
-                self.server_state = Streaming;
-                // Put the PULL message back so we can keep consuming records
-                self.sent_queue.push_front(Message::Pull(pull));
-                Ok(Message::Record(record))
-            }
-            (Streaming, Some(Message::Pull(_)), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
-            (Streaming, Some(Message::DiscardAll), Message::Success(success)) => {
-                self.server_state = Ready;
-                Ok(Message::Success(success))
-            }
-            (Streaming, Some(Message::DiscardAll), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
-            (Streaming, Some(Message::Discard(_)), Message::Success(success)) => {
-                self.server_state = match success.metadata().get("has_more") {
-                    Some(&Value::Boolean(true)) => Streaming,
-                    _ => Ready,
-                };
-                Ok(Message::Success(success))
-            }
-            (Streaming, Some(Message::Discard(_)), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
+    /// Set the information used to identify this client to the server. This is used to fill in
+    /// any entries missing from the metadata passed to [`Client::hello`].
+    pub fn set_client_info(&mut self, client_info: ClientInfo) {
+        self.client_info = client_info;
+    }
 
-            // TX_READY
-            (TxReady, Some(Message::RunWithMetadata(_)), Message::Success(success)) => {
-                self.open_tx_streams += 1;
-                self.server_state = TxStreaming;
-                Ok(Message::Success(success))
-            }
-            (TxReady, Some(Message::RunWithMetadata(_)), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
-            (TxReady, Some(Message::Commit), Message::Success(success)) => {
-                self.server_state = Ready;
-                Ok(Message::Success(success))
-            }
-            (TxReady, Some(Message::Commit), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
-            (TxReady, Some(Message::Rollback), Message::Success(success)) => {
-                self.server_state = Ready;
-                Ok(Message::Success(success))
-            }
-            (TxReady, Some(Message::Rollback), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
+    /// Get the server agent string (e.g. `"Neo4j/4.3.0"`) the server sent after a successful
+    /// [`Client::hello`].
+    pub fn server_agent(&self) -> Option<&str> {
+        self.session.server_agent.as_deref()
+    }
 
-            // TX_STREAMING
-            (TxStreaming, Some(Message::RunWithMetadata(_)), Message::Success(success)) => {
-                self.open_tx_streams += 1;
-                self.server_state = TxStreaming;
-                Ok(Message::Success(success))
-            }
-            (TxStreaming, Some(Message::RunWithMetadata(_)), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
-            (TxStreaming, Some(Message::PullAll), Message::Success(success)) => {
-                self.open_tx_streams -= 1;
-                self.server_state = TxReady;
-                Ok(Message::Success(success))
-            }
-            (TxStreaming, Some(Message::PullAll), Message::Record(record)) => {
-                self.server_state = TxStreaming;
-                // Put the PULL_ALL message back so we can keep consuming records
-                self.sent_queue.push_front(Message::PullAll);
-                Ok(Message::Record(record))
-            }
-            (TxStreaming, Some(Message::PullAll), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
-            (TxStreaming, Some(Message::Pull(_)), Message::Success(success)) => {
-                self.server_state = match success.metadata().get("has_more") {
-                    Some(&Value::Boolean(true)) => TxStreaming,
-                    _ => {
-                        self.open_tx_streams -= 1;
-                        if self.open_tx_streams > 0 {
-                            TxStreaming
-                        } else {
-                            TxReady
-                        }
-                    }
-                };
-                Ok(Message::Success(success))
-            }
-            (TxStreaming, Some(Message::Pull(pull)), Message::Record(record)) => {
-                self.server_state = TxStreaming;
-                // Put the PULL message back so we can keep consuming records
-                self.sent_queue.push_front(Message::Pull(pull));
-                Ok(Message::Record(record))
-            }
-            (TxStreaming, Some(Message::Pull(_)), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
-            (TxStreaming, Some(Message::DiscardAll), Message::Success(success)) => {
-                self.open_tx_streams -= 1;
-                self.server_state = TxReady;
-                Ok(Message::Success(success))
-            }
-            (TxStreaming, Some(Message::DiscardAll), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
-            (TxStreaming, Some(Message::Discard(_)), Message::Success(success)) => {
-                self.server_state = match success.metadata().get("has_more") {
-                    Some(&Value::Boolean(true)) => TxStreaming,
-                    _ => {
-                        self.open_tx_streams -= 1;
-                        if self.open_tx_streams > 0 {
-                            TxStreaming
-                        } else {
-                            TxReady
-                        }
-                    }
-                };
-                Ok(Message::Success(success))
-            }
-            (TxStreaming, Some(Message::Discard(_)), Message::Failure(failure)) => {
-                self.server_state = Failed;
-                Ok(Message::Failure(failure))
-            }
+    /// Get the unique identifier of this connection (e.g. `"bolt-61"`) the server sent after a
+    /// successful [`Client::hello`]. _(Bolt v3+ only.)_
+    pub fn connection_id(&self) -> Option<&str> {
+        self.session.connection_id.as_deref()
+    }
 
-            // FAILED
-            (Failed, Some(Message::Run(_)), Message::Ignored) => {
-                self.server_state = Failed;
-                Ok(Message::Ignored)
-            }
-            (Failed, Some(Message::RunWithMetadata(_)), Message::Ignored) => {
-                self.server_state = Failed;
-                Ok(Message::Ignored)
-            }
-            (Failed, Some(Message::PullAll), Message::Ignored) => {
-                self.server_state = Failed;
-                Ok(Message::Ignored)
-            }
-            (Failed, Some(Message::Pull(_)), Message::Ignored) => {
-                self.server_state = Failed;
-                Ok(Message::Ignored)
-            }
-            (Failed, Some(Message::DiscardAll), Message::Ignored) => {
-                self.server_state = Failed;
-                Ok(Message::Ignored)
-            }
-            (Failed, Some(Message::Discard(_)), Message::Ignored) => {
-                self.server_state = Failed;
-                Ok(Message::Ignored)
-            }
-            (Failed, Some(Message::Route(_)), Message::Ignored) => {
-                self.server_state = Failed;
-                Ok(Message::Ignored)
-            }
-            (Failed, Some(Message::RouteWithMetadata(_)), Message::Ignored) => {
-                self.server_state = Failed;
-                Ok(Message::Ignored)
-            }
-            (Failed, Some(Message::AckFailure), Message::Success(success)) => {
-                self.server_state = Ready;
-                Ok(Message::Success(success))
-            }
-            (Failed, Some(Message::AckFailure), Message::Failure(failure)) => {
-                self.server_state = Defunct;
-                Ok(Message::Failure(failure))
-            }
+    /// Get the bookmark sent by the server in the last response that contained one, e.g. after a
+    /// [`Client::commit`]. _(Bolt v3+ only.)_
+    pub fn last_bookmark(&self) -> Option<&str> {
+        self.session.last_bookmark.as_deref()
+    }
 
-            // INTERRUPTED
-            (Interrupted, Some(Message::Run(_)), _) => {
-                self.server_state = Interrupted;
-                Ok(Message::Ignored)
-            }
-            (Interrupted, Some(Message::RunWithMetadata(_)), _) => {
-                self.server_state = Interrupted;
-                Ok(Message::Ignored)
-            }
-            (Interrupted, Some(Message::PullAll), Message::Record(_)) => {
-                self.server_state = Interrupted;
-                // Put the PULL_ALL message back so we can keep consuming records
-                self.sent_queue.push_front(Message::PullAll);
-                Ok(Message::Ignored)
-            }
-            (Interrupted, Some(Message::PullAll), _) => {
-                self.server_state = Interrupted;
-                Ok(Message::Ignored)
-            }
-            (Interrupted, Some(Message::Pull(pull)), Message::Record(_)) => {
-                self.server_state = Interrupted;
-                // Put the PULL message back so we can keep consuming records
-                self.sent_queue.push_front(Message::Pull(pull));
-                Ok(Message::Ignored)
-            }
-            (Interrupted, Some(Message::Pull(_)), _) => {
-                self.server_state = Interrupted;
-                Ok(Message::Ignored)
-            }
-            (Interrupted, Some(Message::DiscardAll), _) => {
-                self.server_state = Interrupted;
-                Ok(Message::Ignored)
-            }
-            (Interrupted, Some(Message::Discard(_)), _) => {
-                self.server_state = Interrupted;
-                Ok(Message::Ignored)
-            }
-            (Interrupted, Some(Message::Begin(_)), _) => {
-                self.server_state = Interrupted;
-                Ok(Message::Ignored)
-            }
-            (Interrupted, Some(Message::Commit), _) => {
-                self.server_state = Interrupted;
-                Ok(Message::Ignored)
-            }
-            (Interrupted, Some(Message::Rollback), _) => {
-                self.server_state = Interrupted;
-                Ok(Message::Ignored)
-            }
-            (Interrupted, Some(Message::AckFailure), _) => {
-                self.server_state = Interrupted;
-                Ok(Message::Ignored)
-            }
-            (Interrupted, Some(Message::Route(_)), _) => {
-                self.server_state = Interrupted;
-                Ok(Message::Ignored)
-            }
-            (Interrupted, Some(Message::RouteWithMetadata(_)), _) => {
-                self.server_state = Interrupted;
-                Ok(Message::Ignored)
-            }
-            (Interrupted, Some(Message::Reset), Message::Success(success)) => {
-                self.open_tx_streams = 0;
-                self.server_state = Ready;
-                Ok(Message::Success(success))
-            }
-            (Interrupted, Some(Message::Reset), Message::Failure(failure)) => {
-                self.server_state = Defunct;
-                Ok(Message::Failure(failure))
-            }
-            (state, request, response) => {
-                self.server_state = Defunct;
-                Err(CommunicationError::InvalidResponse {
-                    state,
-                    request,
-                    response,
-                })
-            }
-        }
+    /// Get the [`BookmarkManager`] shared with other clients, if any.
+    pub fn bookmark_manager(&self) -> Option<&BookmarkManager> {
+        self.session.bookmark_manager.as_ref()
     }
 
-    pub(crate) async fn send_message(&mut self, message: Message) -> CommunicationResult<()> {
-        match (self.server_state, &message) {
-            (Connected, Message::Init(_)) => {}
-            (Connected, Message::Hello(_)) => {}
-            (Ready, Message::Run(_)) => {}
-            (Ready, Message::RunWithMetadata(_)) => {}
-            (Ready, Message::Begin(_)) => {}
-            (Ready, Message::Route(_)) => {}
-            (Ready, Message::RouteWithMetadata(_)) => {}
-            (Ready, Message::Reset) => {}
-            (Ready, Message::Goodbye) => {}
-            (Streaming, Message::PullAll) => {}
-            (Streaming, Message::Pull(_)) => {}
-            (Streaming, Message::DiscardAll) => {}
-            (Streaming, Message::Discard(_)) => {}
-            (Streaming, Message::Reset) => {}
-            (Streaming, Message::Goodbye) => {}
-            (TxReady, Message::RunWithMetadata(_)) => {}
-            (TxReady, Message::Commit) => {}
-            (TxReady, Message::Rollback) => {}
-            (TxReady, Message::Reset) => {}
-            (TxReady, Message::Goodbye) => {}
-            (TxStreaming, Message::RunWithMetadata(_)) => {}
-            (TxStreaming, Message::PullAll) => {}
-            (TxStreaming, Message::Pull(_)) => {}
-            (TxStreaming, Message::DiscardAll) => {}
-            (TxStreaming, Message::Discard(_)) => {}
-            (TxStreaming, Message::Reset) => {}
-            (TxStreaming, Message::Goodbye) => {}
-            (Failed, Message::Run(_)) => {}
-            (Failed, Message::RunWithMetadata(_)) => {}
-            (Failed, Message::PullAll) => {}
-            (Failed, Message::Pull(_)) => {}
-            (Failed, Message::DiscardAll) => {}
-            (Failed, Message::Discard(_)) => {}
-            (Failed, Message::AckFailure) => {}
-            (Failed, Message::Reset) => {}
-            (Failed, Message::Goodbye) => {}
-            (Interrupted, Message::Run(_)) => {}
-            (Interrupted, Message::RunWithMetadata(_)) => {}
-            (Interrupted, Message::PullAll) => {}
-            (Interrupted, Message::Pull(_)) => {}
-            (Interrupted, Message::DiscardAll) => {}
-            (Interrupted, Message::Discard(_)) => {}
-            (Interrupted, Message::AckFailure) => {}
-            (Interrupted, Message::Begin(_)) => {}
-            (Interrupted, Message::Commit) => {}
-            (Interrupted, Message::Rollback) => {}
-            (Interrupted, Message::Reset) => {}
-            (Interrupted, Message::Goodbye) => {}
-            (state, message) => {
-                self.server_state = Defunct;
-                return Err(CommunicationError::InvalidState {
-                    state,
-                    message: message.clone(),
-                });
-            }
+    /// Share a [`BookmarkManager`] with other clients. Transactions started with
+    /// [`Client::begin`] without a `bookmarks` metadata entry then wait for the manager's
+    /// bookmarks, and the bookmarks returned by the server are stored in the manager, so that
+    /// transactions on the other clients see this client's changes. Defaults to [`None`].
+    pub fn set_bookmark_manager(&mut self, bookmark_manager: Option<BookmarkManager>) {
+        self.session.bookmark_manager = bookmark_manager;
+    }
+
+    /// Get the field names sent by the server in the `SUCCESS` response to the last `RUN`. Records
+    /// returned by [`Client::pull_named`] share these names.
+    pub fn last_fields(&self) -> Option<&Arc<[String]>> {
+        self.session.last_fields.as_ref()
+    }
+
+    /// Get the [`QueryHeader`] of the `SUCCESS` response to the last `RUN`, holding its field
+    /// names, `t_first`, and `qid`.
+    pub fn last_query_header(&self) -> Option<&QueryHeader> {
+        self.session.last_query_header.as_ref()
+    }
+
+    /// Create a span describing this connection, containing the protocol version, and the server
+    /// agent and connection ID if they are known.
+    #[cfg(feature = "tracing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tracing")))]
+    pub fn span(&self) -> tracing::Span {
+        let span = tracing::info_span!(
+            "bolt_connection",
+            version = self.version,
+            server_agent = tracing::field::Empty,
+            connection_id = tracing::field::Empty,
+        );
+        if let Some(server_agent) = &self.session.server_agent {
+            span.record("server_agent", server_agent.as_str());
+        }
+        if let Some(connection_id) = &self.session.connection_id {
+            span.record("connection_id", connection_id.as_str());
         }
+        span
+    }
 
-        #[cfg(test)]
-        println!(">>> {:?}", message);
+    /// Get a snapshot of the traffic this client has sent and received.
+    pub fn stats(&self) -> ClientStats {
+        self.session.stats.stats
+    }
 
-        let chunks = message.clone().into_chunks().map_err(ProtocolError::from)?;
+    /// Set a hook that is called whenever the server state changes, e.g. to observe when the
+    /// connection becomes [`Failed`](ServerState::Failed) or [`Defunct`](ServerState::Defunct).
+    /// This replaces any previous hook.
+    pub fn on_state_change(&mut self, hook: impl FnMut(&StateChange) + Send + Sync + 'static) {
+        self.session.state_change_hook = Some(StateChangeHook(Box::new(hook)));
+    }
+
+    /// Remove the hook set with [`Client::on_state_change`].
+    pub fn clear_on_state_change(&mut self) {
+        self.session.state_change_hook = None;
+    }
+
+    /// Add an [`Interceptor`] that is called with every message this client sends and receives,
+    /// after any interceptors added before it.
+    pub fn add_interceptor(&mut self, interceptor: impl Interceptor + 'static) {
+        self.session.interceptors.0.push(Box::new(interceptor));
+    }
+
+    /// Remove all interceptors added with [`Client::add_interceptor`].
+    pub fn clear_interceptors(&mut self) {
+        self.session.interceptors.0.clear();
+    }
+
+    /// Get the number of summary messages kept for [`Client::recent_exchanges`].
+    pub fn exchange_capacity(&self) -> usize {
+        self.session.exchanges.capacity()
+    }
+
+    /// Set the number of summary messages kept for [`Client::recent_exchanges`], dropping the
+    /// oldest ones that no longer fit. Defaults to 0, which records nothing.
+    pub fn set_exchange_capacity(&mut self, capacity: usize) {
+        self.session.exchanges.set_capacity(capacity);
+    }
+
+    /// Get the most recent [`SUCCESS`](Message::Success), [`FAILURE`](Message::Failure), and
+    /// [`IGNORED`](Message::Ignored) messages received by this client, oldest first, each with the
+    /// kind of request it answered and the time it was received. Only as many as the
+    /// [exchange capacity](Client::set_exchange_capacity) are kept, e.g. to inspect the metadata
+    /// of the responses leading up to unexpected query behavior.
+    pub fn recent_exchanges(&self) -> impl ExactSizeIterator<Item = &Exchange> {
+        self.session.exchanges.iter()
+    }
+
+    /// Get how this client handles metadata that is not supported by its version.
+    pub fn metadata_validation(&self) -> MetadataValidation {
+        self.metadata_validation
+    }
+
+    /// Set how this client handles metadata that is not supported by its version. Defaults to
+    /// [`MetadataValidation::Reject`].
+    pub fn set_metadata_validation(&mut self, metadata_validation: MetadataValidation) {
+        self.metadata_validation = metadata_validation;
+    }
+
+    /// Get how this client deserializes strings that are not valid UTF-8.
+    pub fn utf8_mode(&self) -> Utf8Mode {
+        self.decoder.utf8_mode()
+    }
+
+    /// Set how this client deserializes strings that are not valid UTF-8. Defaults to
+    /// [`Utf8Mode::Strict`], which fails the whole message containing the string.
+    pub fn set_utf8_mode(&mut self, utf8_mode: Utf8Mode) {
+        self.decoder = std::mem::take(&mut self.decoder).with_utf8_mode(utf8_mode);
+    }
+
+    /// Get the maximum size in bytes of a message received from the server, if any.
+    pub fn max_message_size(&self) -> Option<usize> {
+        self.decoder.max_message_size()
+    }
+
+    /// Set the maximum size in bytes of a message received from the server. A larger message fails
+    /// with a [`MessageTooLarge`](bolt_proto::error::DeserializationError::MessageTooLarge) error
+    /// before it has been read in full, and the connection becomes
+    /// [`Defunct`](ServerState::Defunct). Defaults to [`None`], for no limit.
+    pub fn set_max_message_size(&mut self, max_message_size: Option<usize>) {
+        self.decoder = std::mem::take(&mut self.decoder).with_max_message_size(max_message_size);
+    }
 
-        for chunk in chunks {
-            self.stream.write_all(&chunk).await?;
+    /// Get the maximum size of a value received from the server, if any.
+    pub fn max_value_size(&self) -> Option<usize> {
+        self.decoder.max_value_size()
+    }
+
+    /// Set the maximum size of a value received from the server: the number of bytes in a string or
+    /// byte array, or the number of entries in a list or map. A message containing a larger value
+    /// fails with a [`ValueTooLarge`](bolt_proto::error::DeserializationError::ValueTooLarge) error
+    /// before the value is allocated, and the connection becomes
+    /// [`Defunct`](ServerState::Defunct). Defaults to [`None`], for no limit.
+    pub fn set_max_value_size(&mut self, max_value_size: Option<usize>) {
+        self.decoder = std::mem::take(&mut self.decoder).with_max_value_size(max_value_size);
+    }
+
+    /// Check whether lists of floats received from the server are deserialized as float arrays.
+    pub fn float_arrays(&self) -> bool {
+        self.decoder.float_arrays()
+    }
+
+    /// Set whether lists that only contain floats, e.g. embedding vectors returned by vector
+    /// queries, are deserialized as [`Value::FloatArray`](bolt_proto::Value::FloatArray) instead of
+    /// [`Value::List`](bolt_proto::Value::List), which takes a fraction of the memory. Defaults to
+    /// `false`.
+    pub fn set_float_arrays(&mut self, float_arrays: bool) {
+        self.decoder = std::mem::take(&mut self.decoder).with_float_arrays(float_arrays);
+    }
+
+    /// Get how [`Client::read_transaction`] and [`Client::write_transaction`] retry failed
+    /// transactions.
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Set how [`Client::read_transaction`] and [`Client::write_transaction`] retry failed
+    /// transactions. Defaults to [`RetryPolicy::default`].
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    /// Get which query parameters are shown in clear when queries are logged.
+    pub fn param_redaction(&self) -> &ParamRedaction {
+        &self.param_redaction
+    }
+
+    /// Set which query parameters are shown in clear when queries are logged with [`tracing`].
+    /// Defaults to [`ParamRedaction::default`], which hides all parameters.
+    pub fn set_param_redaction(&mut self, param_redaction: ParamRedaction) {
+        self.param_redaction = param_redaction;
+    }
+
+    /// Get the maximum number of requests [`Client::pipeline`] keeps awaiting a response, if any.
+    pub fn max_in_flight(&self) -> Option<usize> {
+        self.max_in_flight
+    }
+
+    /// Set the maximum number of requests [`Client::pipeline`] keeps awaiting a response. Once
+    /// that many are outstanding, the pipeline reads responses before sending more requests.
+    /// Defaults to [`None`], which sends all of the requests at once. A limit of 0 is treated as 1.
+    pub fn set_max_in_flight(&mut self, max_in_flight: Option<usize>) {
+        self.max_in_flight = max_in_flight;
+    }
+
+    /// Get the [`Timer`] the client uses to wait between transaction retries and keepalives.
+    pub fn timer(&self) -> &dyn Timer {
+        self.timer.as_ref()
+    }
+
+    /// Set the [`Timer`] the client uses to wait between transaction retries and keepalives.
+    /// Defaults to [`TokioTimer`](crate::TokioTimer) if the `tokio` feature is enabled, then
+    /// [`AsyncStdTimer`](crate::AsyncStdTimer) if the `async-std` feature is enabled, and
+    /// [`ThreadTimer`](crate::ThreadTimer) otherwise.
+    pub fn set_timer(&mut self, timer: impl Timer + 'static) {
+        self.timer = Box::new(timer);
+    }
+
+    // Log a query at the debug level, with its parameters redacted
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn log_query(&self, query: &str, parameters: &Params) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            parent: &self.span(),
+            query,
+            params = %parameters.redacted_with(&self.param_redaction),
+            "running query"
+        );
+    }
+
+    pub(crate) fn validate_metadata(&self, keys: Vec<String>) -> CommunicationResult<()> {
+        if self.metadata_validation == MetadataValidation::Ignore || keys.is_empty() {
+            return Ok(());
+        }
+
+        match self.metadata_validation {
+            MetadataValidation::Reject => Err(CommunicationError::UnsupportedMetadata {
+                keys,
+                version: self.version,
+            }),
+            _ => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    parent: &self.span(),
+                    ?keys,
+                    "metadata is not supported by the server and will be ignored"
+                );
+                Ok(())
+            }
         }
-        self.stream.flush().await?;
+    }
+
+    pub(crate) async fn read_message(&mut self) -> CommunicationResult<Message> {
+        // Finish sending a request whose send was cancelled, or its response would never arrive
+        self.finish_write().await?;
+        let bytes_received = &mut self.session.stats.stats.bytes_received;
+        let message = match framing::read_message(
+            &mut self.stream,
+            &mut self.decoder,
+            &mut self.read_buffer,
+            |read| *bytes_received += read as u64,
+        )
+        .await
+        {
+            Ok(message) => message,
+            Err(DeserializationError::IoError(error)) if is_closed(&error) => {
+                return Err(self.session.closed_while_reading(error));
+            }
+            Err(error) if framing::exceeds_limit(&error) => {
+                return Err(self.session.exceeded_limit(error));
+            }
+            Err(error) => return Err(ProtocolError::from(error).into()),
+        };
+        self.session.received(message)
+    }
 
-        // Immediate state changes
+    // Read the next message like read_message, leaving the fields of a RECORD undeserialized
+    async fn read_lazy_message(&mut self) -> CommunicationResult<Option<LazyMessage>> {
+        self.finish_write().await?;
+        let bytes_received = &mut self.session.stats.stats.bytes_received;
+        let message = match framing::read_lazy_message(
+            &mut self.stream,
+            &mut self.decoder,
+            &mut self.read_buffer,
+            |read| *bytes_received += read as u64,
+        )
+        .await
+        {
+            Ok(message) => message,
+            Err(DeserializationError::IoError(error)) if is_closed(&error) => {
+                return Err(self.session.closed_while_reading(error));
+            }
+            Err(error) if framing::exceeds_limit(&error) => {
+                return Err(self.session.exceeded_limit(error));
+            }
+            Err(error) => return Err(ProtocolError::from(error).into()),
+        };
         match message {
-            Message::Reset => self.server_state = Interrupted,
-            Message::Goodbye => self.server_state = Disconnected,
-            _ => {}
+            LazyMessage::Record(record) => {
+                Ok(self.session.received_lazy(record)?.map(LazyMessage::Record))
+            }
+            LazyMessage::Other(message) => self
+                .session
+                .received(message)
+                .map(|message| Some(LazyMessage::Other(message))),
+        }
+    }
+
+    // Write and flush any requests that haven't been yet
+    async fn finish_write(&mut self) -> CommunicationResult<()> {
+        match self.write_buffer.write_to(&mut self.stream).await {
+            Err(error) if is_closed(&error) => Err(self.session.closed_while_writing(error)),
+            result => Ok(result?),
         }
+    }
 
-        self.sent_queue.push_back(message);
+    // Read and discard the responses to requests whose futures were dropped before reading them,
+    // which would otherwise be returned as the responses to the next request. A RESET or GOODBYE
+    // makes the server skip its work anyway, and the reset method skips the responses itself.
+    async fn skip_abandoned(&mut self, next: Option<&Message>) -> CommunicationResult<()> {
+        if matches!(next, Some(Message::Reset | Message::Goodbye)) {
+            return Ok(());
+        }
+        while !self.session.sent_queue.is_empty() {
+            self.read_message().await?;
+        }
         Ok(())
     }
 
+    pub(crate) async fn send_message(&mut self, mut message: Message) -> CommunicationResult<()> {
+        self.finish_write().await?;
+        self.skip_abandoned(Some(&message)).await?;
+        self.session.interceptors.before_send(&mut message);
+        check_supported(self.version, &message)?;
+        let expected = self.session.expected_state();
+        self.session.check_request(expected, &message)?;
+
+        #[cfg(test)]
+        println!(">>> {:?}", message);
+
+        let chunks = message.clone().into_chunks().map_err(ProtocolError::from)?;
+
+        // The message counts as sent once it's buffered, since the rest of it is written by the
+        // next request if this future is dropped before it finishes
+        let bytes_sent = self.write_buffer.push(&chunks);
+        self.session.stats.sent(bytes_sent, 1);
+        self.session.sent(message);
+        self.finish_write().await
+    }
+
     /// Send a [`HELLO`](Message::Hello) (or [`INIT`](Message::Init)) message to the server.
     /// _(Sends `INIT` for Bolt v1 - v2, and `HELLO` for Bolt v3+.)_
     ///
@@ -545,10 +701,13 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     /// should establish a new connection.
     ///
     /// # Fields
-    /// `metadata` should contain at least two entries:
+    /// `metadata` may be passed by value, by reference, or in an [`Arc`], so that metadata shared
+    /// between many connections is only copied into the message. It should contain at least two
+    /// entries:
     /// - `user_agent`, which should conform to the format `"Name/Version"`, for example
     ///   `"Example/1.0.0"` (see
-    ///   [here](https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/User-Agent)).
+    ///   [here](https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/User-Agent)). If this is
+    ///   missing, the user agent from the client's [`ClientInfo`] is used.
     /// - `scheme` is the authentication scheme. Predefined schemes are `"none"`, `"basic"`, or
     ///   `"kerberos"`.
     ///
@@ -558,6 +717,10 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     ///   unspecified indicates that the server should not carry out any routing.
     ///   _(Bolt v4.1+ only.)_
     ///
+    /// The `notifications_minimum_severity` and `notifications_disabled_categories` entries (see
+    /// [`Metadata::with_notifications_minimum_severity`]) are only sent to servers that support
+    /// them. _(Bolt v5.2+ only.)_
+    ///
     /// Further entries in `metadata` are passed to the implementation of the chosen authentication
     /// scheme. Their names, types, and defaults depend on that choice. For example, the scheme
     /// `"basic"` requires `metadata` to contain the username and password in the form
@@ -581,9 +744,12 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     ///   [`Defunct`](ServerState::Defunct) state. The server may choose to include metadata
     ///   describing the nature of the failure but will immediately close the connection after the
     ///   failure has been sent.
-    #[bolt_version(1, 2, 3, 4, 4.1, 4.2, 4.3, 4.4)]
-    pub async fn hello(&mut self, mut metadata: Metadata) -> CommunicationResult<Message> {
-        let message = match self.version() {
+    #[bolt_version(min = 1)]
+    pub async fn hello(&mut self, metadata: impl Into<Metadata>) -> CommunicationResult<Message> {
+        let mut metadata = metadata.into();
+        self.client_info.apply(&mut metadata.value);
+        remove_notification_config(self.version, &mut metadata.value);
+        let message = match self.version {
             V1_0 | V2_0 => {
                 let user_agent: String = metadata
                     .value
@@ -626,11 +792,14 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     ///   `["bkmk-transaction:1", "bkmk-transaction:2"]`. Default is `[]`.
     /// - `metadata`, a map which can contain the following optional entries:
     ///   - `db`, a string containing the name of the database for which this command should be
-    ///     run. [`null`](Value::Null) denotes the server-side configured default database.
+    ///     run. [`null`](bolt_proto::Value::Null) denotes the server-side configured default database.
     ///   - `imp_user`, a string specifying the impersonated user for the purposes of resolving
-    ///     their home database. [`null`](Value::Null) denotes no impersonation (i.e., execution
+    ///     their home database. [`null`](bolt_proto::Value::Null) denotes no impersonation (i.e., execution
     ///     takes place as the current user). _(Bolt v4.4+ only.)_
     ///
+    ///   Other `metadata` entries, and entries not supported by the client's version, are handled
+    ///   according to the client's [`MetadataValidation`] setting.
+    ///
     /// # Response
     /// - [`Message::Success`] - the routing table has been successfully retrieved and the server
     ///   has entered the [`Ready`](ServerState::Ready) state. The server sends the following
@@ -648,37 +817,25 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     /// - [`Message::Failure`] - the request could not be processed successfully and the server has
     ///   entered the [`Failed`](ServerState::Failed) state. The server may attach metadata to the
     ///   message to provide more detail on the nature of the failure.
-    #[bolt_version(4.3, 4.4)]
+    #[bolt_version(min = 4.3)]
     pub async fn route(
         &mut self,
         context: RoutingContext,
         bookmarks: impl Into<Vec<String>>,
         metadata: Option<Metadata>,
     ) -> CommunicationResult<Message> {
-        let mut metadata = metadata.unwrap_or_default().value;
-        let message = match self.version() {
-            V4_3 => {
-                let database = match metadata.remove("db") {
-                    Some(value) => match value {
-                        Value::String(string) => Some(string),
-                        Value::Null => None,
-                        _ => {
-                            return Err(io::Error::new(
-                                io::ErrorKind::InvalidInput,
-                                "db must be either a string or null",
-                            )
-                            .into())
-                        }
-                    },
-                    None => None,
-                };
-
-                Message::Route(Route::new(context.value, bookmarks.into(), database))
-            }
+        let shaped = shape_metadata(
+            self.version,
+            MetadataTarget::Route,
+            metadata.unwrap_or_default().value,
+        )?;
+        self.validate_metadata(shaped.unsupported)?;
+        let message = match self.version {
+            V4_3 => Message::Route(Route::new(context.value, bookmarks.into(), shaped.database)),
             _ => Message::RouteWithMetadata(RouteWithMetadata::new(
                 context.value,
                 bookmarks.into(),
-                metadata,
+                shaped.metadata,
             )),
         };
 
@@ -687,7 +844,7 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     }
 
     /// Send a [`RUN`](Message::Run) message to the server.
-    /// _(Bolt v1+. For Bolt v1 - v2, the `metadata` parameter is ignored.)_
+    /// _(Bolt v1+. For Bolt v1 - v2, the `metadata` parameter is not supported.)_
     ///
     /// # Description
     /// A `RUN` message submits a new query for execution, the result of which will be consumed by
@@ -714,12 +871,18 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     /// - `mode`, a string which specifies what kind of server should be used for this transaction.
     ///   For write access, use `"w"` and for read access use `"r"`. Default is `"w"`.
     /// - `db`, a string containing the name of the database where the transaction should take
-    ///   place. [`null`](Value::Null) and `""` denote the server-side configured default database.
+    ///   place. [`null`](bolt_proto::Value::Null) and `""` denote the server-side configured default database.
     ///   _(Bolt v4+ only.)_
     /// - `imp_user`, a string specifying the impersonated user which executes this transaction.
-    ///   [`null`](Value::Null) denotes no impersonation (i.e., execution takes place as the
+    ///   [`null`](bolt_proto::Value::Null) denotes no impersonation (i.e., execution takes place as the
     ///   current user). _(Bolt v4.4+ only.)_
     ///
+    /// The [`Metadata::with_bookmarks`], [`Metadata::with_tx_timeout`], etc. setters can be used
+    /// to build these entries.
+    ///
+    /// Other `metadata` entries, and entries not supported by the client's version, are handled
+    /// according to the client's [`MetadataValidation`] setting.
+    ///
     /// # Response
     /// - [`Message::Success`] - the request has been successfully received and the server has
     ///   entered the [`Streaming`](ServerState::Streaming) state. Clients should not consider a
@@ -739,21 +902,27 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     /// - [`Message::Failure`] - the request could not be processed successfully or is invalid, and
     ///   the server has entered the [`Failed`](ServerState::Failed) state. The server may attach
     ///   metadata to the message to provide more detail on the nature of the failure.
-    #[bolt_version(1, 2, 3, 4, 4.1, 4.2, 4.3, 4.4)]
+    #[bolt_version(min = 1)]
     pub async fn run(
         &mut self,
         query: impl Into<String>,
         parameters: Option<Params>,
         metadata: Option<Metadata>,
     ) -> CommunicationResult<Message> {
-        let message = match self.version() {
-            V1_0 | V2_0 => {
-                Message::Run(Run::new(query.into(), parameters.unwrap_or_default().value))
-            }
+        let shaped = shape_metadata(
+            self.version,
+            MetadataTarget::Run,
+            metadata.unwrap_or_default().value,
+        )?;
+        self.validate_metadata(shaped.unsupported)?;
+        let (query, parameters) = (query.into(), parameters.unwrap_or_default());
+        self.log_query(&query, &parameters);
+        let message = match self.version {
+            V1_0 | V2_0 => Message::Run(Run::new(query, parameters.value)),
             _ => Message::RunWithMetadata(RunWithMetadata::new(
-                query.into(),
-                parameters.unwrap_or_default().value,
-                metadata.unwrap_or_default().value,
+                query,
+                parameters.value,
+                shaped.metadata,
             )),
         };
 
@@ -761,6 +930,24 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
         self.read_message().await
     }
 
+    /// Send a [`RUN`](Message::Run) message for a [`PreparedQuery`] to the server, using its
+    /// stored metadata. See [`Client::run`] for details.
+    #[bolt_version(min = 1)]
+    pub async fn run_prepared(
+        &mut self,
+        query: &PreparedQuery,
+        parameters: Option<Params>,
+    ) -> CommunicationResult<Message> {
+        let shaped = query.shaped_metadata(self.version)?;
+        self.validate_metadata(shaped.unsupported.clone())?;
+        let parameters = parameters.unwrap_or_default();
+        self.log_query(query.query(), &parameters);
+        let message = query.message(self.version, parameters, &shaped.metadata);
+
+        self.send_message(message).await?;
+        self.read_message().await
+    }
+
     /// Send a [`PULL`](Message::Pull) (or [`PULL_ALL`](Message::PullAll)) message to the server.
     /// _(Sends `PULL_ALL` for Bolt v1 - v3, and `PULL` for Bolt v4+. For Bolt v1 - v3, the
     /// `metadata` parameter is ignored.)_
@@ -785,8 +972,8 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     ///
     /// # Fields
     /// For Bolt v4+, additional metadata is passed along with this message:
-    /// - `n` is an integer specifying how many records to fetch. `-1` will fetch all records. `n`
-    ///   has no default and must be present.
+    /// - `n` is an integer specifying how many records to fetch. `-1` will fetch all records. The
+    ///   server requires `n`, so `-1` is sent if it is not given.
     /// - `qid` is an integer that specifies for which statement the `PULL` operation should be
     ///   carried out within an explicit transaction. `-1` is the default, which denotes the last
     ///   executed statement.
@@ -803,8 +990,10 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     ///   - `t_last`, supercedes `result_consumed_after`. _(Bolt v3+ only.)_
     ///   - `bookmark` (e.g. `"bookmark:1234"`). _(Bolt v3+ only.)_
     ///   - `stats`, a map containing counter information, such as DB hits, etc. _(Bolt v3+ only.)_
-    ///   - `plan`, a map containing the query plan result. _(Bolt v3+ only.)_
-    ///   - `profile`, a map containing the query profile result. _(Bolt v3+ only.)_
+    ///   - `plan`, a map containing the query plan result, read by
+    ///     [`Plan::from_success`](crate::Plan::from_success). _(Bolt v3+ only.)_
+    ///   - `profile`, a map containing the query profile result, read by
+    ///     [`ProfiledPlan::from_success`](crate::ProfiledPlan::from_success). _(Bolt v3+ only.)_
     ///   - `notifications`, a map containing any notifications generated during execution of the
     ///     query. _(Bolt v3+ only.)_
     ///   - `db`, a string containing the name of the database where the query was executed.
@@ -819,18 +1008,16 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     ///   metadata to the message to provide more detail on the nature of the failure. Failure may
     ///   occur at any time during result streaming, so any records returned in the response should
     ///   be considered invalid.
-    #[bolt_version(1, 2, 3, 4, 4.1, 4.2, 4.3, 4.4)]
+    #[bolt_version(min = 1)]
     pub async fn pull(
         &mut self,
         metadata: Option<Metadata>,
     ) -> CommunicationResult<(Vec<Record>, Message)> {
-        match self.version() {
-            V1_0 | V2_0 | V3_0 => self.send_message(Message::PullAll).await?,
-            _ => {
-                self.send_message(Message::Pull(Pull::new(metadata.unwrap_or_default().value)))
-                    .await?
-            }
-        }
+        let message = match self.supports(Feature::PullN) {
+            true => Message::Pull(Pull::new(default_n(metadata.unwrap_or_default().value))),
+            false => Message::PullAll,
+        };
+        self.send_message(message).await?;
         let mut records = vec![];
         loop {
             match self.read_message().await? {
@@ -843,29 +1030,132 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
         }
     }
 
-    /// Send a [`DISCARD`](Message::Discard) (or [`DISCARD_ALL`](Message::DiscardAll)) message to
-    /// the server.
-    /// _(Sends a `DISCARD_ALL` for Bolt v1 - v3, and `DISCARD` for Bold v4+. For Bolt v1 - v3, the
-    /// `metadata` parameter is ignored.)_
+    /// Send a `PULL` (or `PULL_ALL`) message like [`Client::pull`], pairing each record with the
+    /// field names of the result. The names are taken from the `SUCCESS` response to the last
+    /// `RUN` (see [`Client::last_fields`]), and are shared by all of the records.
     ///
-    /// # Description
-    /// The `DISCARD` message issues a request to discard the outstanding result and return to the
-    /// [`Ready`](ServerState::Ready) state. A receiving server will not abort the request but
-    /// continue to process it without streaming any detail messages to the client.
+    /// If no `RUN` has succeeded yet, the records have no field names.
+    pub async fn pull_named(
+        &mut self,
+        metadata: Option<Metadata>,
+    ) -> CommunicationResult<(Vec<NamedRecord>, Message)> {
+        let (records, response) = self.pull(metadata).await?;
+        let keys = self.session.last_fields.clone().unwrap_or_default();
+        let records = records
+            .into_iter()
+            .map(|record| NamedRecord::new(Arc::clone(&keys), record))
+            .collect();
+        Ok((records, response))
+    }
+
+    /// Send a `PULL` (or `PULL_ALL`) message like [`Client::pull`], returning each record as a
+    /// [`LazyRecord`] whose fields are only deserialized when they are accessed. This saves work
+    /// for wide records where only some of the fields are used. The field names of the result are
+    /// set on each record, as with [`Client::pull_named`].
     ///
-    /// The server must be in the [`Streaming`](ServerState::Streaming) or
-    /// [`TxStreaming`](ServerState::TxStreaming) state to be able to successfully process a
-    /// `DISCARD` request. If the server is in the [`Failed`](ServerState::Failed) state or
-    /// [`Interrupted`](ServerState::Interrupted) state, the response will be
-    /// [`IGNORED`](Message::Ignored). For any other states, receipt of a `DISCARD` request will be
-    /// considered a protocol violation and will lead to connection closure.
+    /// Lazy records are not passed to [interceptors](Client::add_interceptor).
     ///
-    /// # Fields
-    /// For Bolt v4+, additional metadata is passed along with this message:
-    /// - `n` is an integer specifying how many records to discard. `-1` will discard all records.
-    ///   `n` has no default and must be present.
-    /// - `qid` is an integer that specifies for which statement the `DISCARD` operation should be
-    ///   carried out within an explicit transaction. `-1` is the default, which denotes the last
+    /// ```no_run
+    /// # use bolt_client::{Client, Metadata};
+    /// # async fn example(client: &mut Client<tokio_util::compat::Compat<tokio::net::TcpStream>>)
+    /// #     -> Result<(), Box<dyn std::error::Error>> {
+    /// client.run("MATCH (p:Person) RETURN p.name AS name, p.bio AS bio;", None, None).await?;
+    /// let (records, _) = client.pull_lazy(Some(Metadata::from_iter(vec![("n", -1)]))).await?;
+    /// for record in records {
+    ///     // Only the name is deserialized
+    ///     println!("{:?}", record.get_named("name").transpose()?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[bolt_version(min = 1)]
+    pub async fn pull_lazy(
+        &mut self,
+        metadata: Option<Metadata>,
+    ) -> CommunicationResult<(Vec<LazyRecord>, Message)> {
+        let message = match self.supports(Feature::PullN) {
+            true => Message::Pull(Pull::new(default_n(metadata.unwrap_or_default().value))),
+            false => Message::PullAll,
+        };
+        self.send_message(message).await?;
+        let keys = self.session.last_fields.clone().unwrap_or_default();
+        let mut records = vec![];
+        loop {
+            match self.read_lazy_message().await? {
+                Some(LazyMessage::Record(record)) => {
+                    records.push(record.with_keys(Arc::clone(&keys)))
+                }
+                Some(LazyMessage::Other(Message::Ignored)) | None => {
+                    return Ok((vec![], Message::Ignored))
+                }
+                Some(LazyMessage::Other(message)) => return Ok((records, message)),
+            }
+        }
+    }
+
+    /// Send a `PULL` message requesting the next `n` records of the most recent result, or a
+    /// `PULL_ALL` message for Bolt v1 - v3, where `n` is ignored and all records are returned.
+    /// `n` of `-1` requests all remaining records.
+    ///
+    /// See [`Client::pull`] for the possible responses.
+    pub async fn pull_n(&mut self, n: i64) -> CommunicationResult<(Vec<Record>, Message)> {
+        self.pull(Some(Metadata::from_iter([("n", n)]))).await
+    }
+
+    /// Send a `PULL` (or `PULL_ALL`) message requesting all remaining records of the most recent
+    /// result.
+    ///
+    /// See [`Client::pull`] for the possible responses.
+    pub async fn pull_all(&mut self) -> CommunicationResult<(Vec<Record>, Message)> {
+        self.pull_n(-1).await
+    }
+
+    /// Stream the records of the current result as they are consumed, instead of collecting them
+    /// like [`Client::pull`] does.
+    ///
+    /// For Bolt v4+, `metadata` is sent with each `PULL` message, so `n` sets the number of records
+    /// requested in each batch, and `qid` selects the result to stream. If `n` is not given,
+    /// records are requested in batches of 1000, so a slow consumer doesn't have the whole result
+    /// sent at once. The next batch is only requested once the previous one has been consumed. For
+    /// Bolt v1 - v3, a single `PULL_ALL` message is sent and the `metadata` parameter is ignored,
+    /// but records are still only read from the connection as the stream is polled.
+    ///
+    /// See [`RecordStream`] for details on how the result summary is returned.
+    pub fn pull_stream(&mut self, metadata: Option<Metadata>) -> RecordStream<'_, S>
+    where
+        S: Send,
+    {
+        let mut metadata = metadata.unwrap_or_default();
+        metadata
+            .value
+            .entry(String::from("n"))
+            .or_insert_with(|| Value::from(DEFAULT_BATCH_SIZE as i64));
+        RecordStream::new(self, metadata)
+    }
+
+    /// Send a [`DISCARD`](Message::Discard) (or [`DISCARD_ALL`](Message::DiscardAll)) message to
+    /// the server.
+    /// _(Sends a `DISCARD_ALL` for Bolt v1 - v3, and `DISCARD` for Bold v4+. For Bolt v1 - v3, the
+    /// `metadata` parameter is ignored.)_
+    ///
+    /// # Description
+    /// The `DISCARD` message issues a request to discard the outstanding result and return to the
+    /// [`Ready`](ServerState::Ready) state. A receiving server will not abort the request but
+    /// continue to process it without streaming any detail messages to the client.
+    ///
+    /// The server must be in the [`Streaming`](ServerState::Streaming) or
+    /// [`TxStreaming`](ServerState::TxStreaming) state to be able to successfully process a
+    /// `DISCARD` request. If the server is in the [`Failed`](ServerState::Failed) state or
+    /// [`Interrupted`](ServerState::Interrupted) state, the response will be
+    /// [`IGNORED`](Message::Ignored). For any other states, receipt of a `DISCARD` request will be
+    /// considered a protocol violation and will lead to connection closure.
+    ///
+    /// # Fields
+    /// For Bolt v4+, additional metadata is passed along with this message:
+    /// - `n` is an integer specifying how many records to discard. `-1` will discard all records.
+    ///   The server requires `n`, so `-1` is sent if it is not given.
+    /// - `qid` is an integer that specifies for which statement the `DISCARD` operation should be
+    ///   carried out within an explicit transaction. `-1` is the default, which denotes the last
     ///   executed statement.
     ///
     /// # Response
@@ -889,16 +1179,36 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     /// - [`Message::Failure`] - the request could not be processed successfully and the server has
     ///   entered the [`Failed`](ServerState::Failed) state. The server may attach metadata to the
     ///   message to provide more detail on the nature of the failure.
-    #[bolt_version(1, 2, 3, 4, 4.1, 4.2, 4.3, 4.4)]
+    #[bolt_version(min = 1)]
     pub async fn discard(&mut self, metadata: Option<Metadata>) -> CommunicationResult<Message> {
-        let message = match self.version() {
-            V1_0 | V2_0 | V3_0 => Message::DiscardAll,
-            _ => Message::Discard(Discard::new(metadata.unwrap_or_default().value)),
+        let message = match self.supports(Feature::PullN) {
+            true => Message::Discard(Discard::new(default_n(metadata.unwrap_or_default().value))),
+            false => Message::DiscardAll,
         };
         self.send_message(message).await?;
         self.read_message().await
     }
 
+    /// Send a `DISCARD` message discarding the next `n` records of the result with the given
+    /// `qid` (or the most recent result if not given), or a `DISCARD_ALL` message for Bolt v1 -
+    /// v3, where `n` and `qid` are ignored and all records are discarded. `n` of `-1` discards all
+    /// remaining records.
+    ///
+    /// See [`Client::discard`] for the possible responses.
+    pub async fn discard_n(&mut self, n: i64, qid: Option<i64>) -> CommunicationResult<Message> {
+        let metadata =
+            Metadata::from_iter(std::iter::once(("n", n)).chain(qid.map(|qid| ("qid", qid))));
+        self.discard(Some(metadata)).await
+    }
+
+    /// Send a `DISCARD` (or `DISCARD_ALL`) message discarding all remaining records of the most
+    /// recent result.
+    ///
+    /// See [`Client::discard`] for the possible responses.
+    pub async fn discard_all(&mut self) -> CommunicationResult<Message> {
+        self.discard_n(-1, None).await
+    }
+
     /// Send a [`BEGIN`](Message::Begin) message to the server.
     /// _(Bolt v3+ only.)_
     ///
@@ -923,12 +1233,18 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     /// - `mode`, a string which specifies what kind of server should be used for this transaction.
     ///   For write access, use `"w"` and for read access use `"r"`. Default is `"w"`.
     /// - `db`, a string containing the name of the database where the transaction should take
-    ///   place. [`null`](Value::Null) and `""` denote the server-side configured default database.
+    ///   place. [`null`](bolt_proto::Value::Null) and `""` denote the server-side configured default database.
     ///   _(Bolt v4+ only.)_
     /// - `imp_user`, a string specifying the impersonated user which executes this transaction.
-    ///   [`null`](Value::Null) denotes no impersonation (i.e., execution takes place as the
+    ///   [`null`](bolt_proto::Value::Null) denotes no impersonation (i.e., execution takes place as the
     ///   current user). _(Bolt v4.4+ only.)_
     ///
+    /// The [`Metadata::with_bookmarks`], [`Metadata::with_tx_timeout`], etc. setters can be used
+    /// to build these entries.
+    ///
+    /// Other `metadata` entries, and entries not supported by the client's version, are handled
+    /// according to the client's [`MetadataValidation`] setting.
+    ///
     /// # Response
     /// - [`Message::Success`] - the transaction has been successfully started and the server has
     ///   entered the [`TxReady`](ServerState::Ready) state.
@@ -938,9 +1254,29 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     /// - [`Message::Failure`] - the request could not be processed successfully and the server has
     ///   entered the [`Failed`](ServerState::Failed) state. The server may attach metadata to the
     ///   message to provide more detail on the nature of the failure.
-    #[bolt_version(3, 4, 4.1, 4.2, 4.3, 4.4)]
+    #[bolt_version(min = 3)]
     pub async fn begin(&mut self, metadata: Option<Metadata>) -> CommunicationResult<Message> {
-        let begin_msg = Begin::new(metadata.unwrap_or_default().value);
+        let mut metadata = metadata.unwrap_or_default();
+        if let Some(manager) = &self.session.bookmark_manager {
+            let bookmarks = match metadata.value.get("bookmarks") {
+                Some(Value::List(bookmarks)) => bookmarks
+                    .iter()
+                    .filter_map(|bookmark| Bookmark::parse(bookmark.as_str()?).ok())
+                    .collect(),
+                Some(_) => Bookmarks::new(),
+                None => {
+                    let bookmarks = manager.bookmarks();
+                    if !bookmarks.is_empty() {
+                        metadata = metadata.with_bookmarks(bookmarks.clone());
+                    }
+                    bookmarks
+                }
+            };
+            self.session.tx_bookmarks = bookmarks;
+        }
+        let shaped = shape_metadata(self.version, MetadataTarget::Begin, metadata.value)?;
+        self.validate_metadata(shaped.unsupported)?;
+        let begin_msg = Begin::new(shaped.metadata);
         self.send_message(Message::Begin(begin_msg)).await?;
         self.read_message().await
     }
@@ -972,7 +1308,7 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     /// - [`Message::Failure`] - the request could not be processed successfully and the server has
     ///   entered the [`Failed`](ServerState::Failed) state. The server may attach metadata to the
     ///   message to provide more detail on the nature of the failure.
-    #[bolt_version(3, 4, 4.1, 4.2, 4.3, 4.4)]
+    #[bolt_version(min = 3)]
     pub async fn commit(&mut self) -> CommunicationResult<Message> {
         self.send_message(Message::Commit).await?;
         self.read_message().await
@@ -1005,7 +1341,7 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     /// - [`Message::Failure`] - the request could not be processed successfully and the server has
     ///   entered the [`Failed`](ServerState::Failed) state. The server may attach metadata to the
     ///   message to provide more detail on the nature of the failure.
-    #[bolt_version(3, 4, 4.1, 4.2, 4.3, 4.4)]
+    #[bolt_version(min = 3)]
     pub async fn rollback(&mut self) -> CommunicationResult<Message> {
         self.send_message(Message::Rollback).await?;
         self.read_message().await
@@ -1030,7 +1366,7 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     ///   entered the [`Defunct`](ServerState::Defunct) state. The server may choose to include
     ///   metadata describing the nature of the failure but will immediately close the connection
     ///   after the failure has been sent.
-    #[bolt_version(1, 2)]
+    #[bolt_version(1..=2)]
     pub async fn ack_failure(&mut self) -> CommunicationResult<Message> {
         self.send_message(Message::AckFailure).await?;
         self.read_message().await
@@ -1063,15 +1399,15 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     ///   entered the [`Defunct`](ServerState::Defunct) state. The server may choose to include
     ///   metadata describing the nature of the failure but will immediately close the connection
     ///   after the failure has been sent.
-    #[bolt_version(1, 2, 3, 4, 4.1, 4.2, 4.3, 4.4)]
+    #[bolt_version(min = 1)]
     pub async fn reset(&mut self) -> CommunicationResult<Message> {
         self.send_message(Message::Reset).await?;
+        // Skip the responses to any earlier requests, e.g. the records of a PULL whose future was
+        // dropped before it finished
         loop {
-            match self.read_message().await? {
-                Message::Success(success) => return Ok(Message::Success(success)),
-                Message::Failure(failure) => return Ok(Message::Failure(failure)),
-                Message::Ignored => {}
-                _ => unreachable!(),
+            let response = self.read_message().await?;
+            if self.session.sent_queue.is_empty() {
+                return Ok(response);
             }
         }
     }
@@ -1084,10 +1420,10 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     /// receipt of this message, the server will immediately shut down the socket on its side
     /// without sending a response. A client may shut down the socket at any time after sending the
     /// `GOODBYE` message. This message interrupts the server's current work, if any.
-    #[bolt_version(3, 4, 4.1, 4.2, 4.3, 4.4)]
+    #[bolt_version(min = 3)]
     pub async fn goodbye(&mut self) -> CommunicationResult<()> {
         self.send_message(Message::Goodbye).await?;
-        self.server_state = Defunct;
+        self.session.server_state = Defunct;
         Ok(self.stream.close().await?)
     }
 
@@ -1117,33 +1453,1952 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     /// message to the server. Until the server receives the `RESET`/`ACK_FAILURE` message, it will
     /// send an [`IGNORED`](Message::Ignored) message in response to any other message from the
     /// client, including messages that were sent in a pipeline.
-    pub async fn pipeline(&mut self, messages: Vec<Message>) -> CommunicationResult<Vec<Message>> {
+    ///
+    /// If any of the messages, as rewritten by the client's interceptors, doesn't exist in the
+    /// client's version, an [`UnsupportedOperation`](CommunicationError::UnsupportedOperation)
+    /// error is returned without sending any of them.
+    ///
+    /// # Flow Control
+    /// Servers may close connections that have too many requests outstanding. To avoid this, use
+    /// [`Client::set_max_in_flight`] to limit the number of requests awaiting a response. Once the
+    /// limit is reached, the pipeline reads the responses to the earliest requests before sending
+    /// the next ones, so the requests are still sent, and the responses returned, in order.
+    pub async fn pipeline(
+        &mut self,
+        mut messages: Vec<Message>,
+    ) -> CommunicationResult<Vec<Message>> {
         // This Vec is too small if we're expecting some RECORD messages, so there's no "good" size
         let mut responses = Vec::with_capacity(messages.len());
 
-        for message in &messages {
+        self.finish_write().await?;
+        self.skip_abandoned(messages.first()).await?;
+        let mut chunks = Vec::with_capacity(messages.len());
+        for message in &mut messages {
+            self.session.interceptors.before_send(message);
+            check_supported(self.version, message)?;
             #[cfg(test)]
             println!(">>> {:?}", message);
 
-            let chunks = message.clone().into_chunks().map_err(ProtocolError::from)?;
+            chunks.push(message.clone().into_chunks().map_err(ProtocolError::from)?);
+        }
+        let max_in_flight = self.max_in_flight.unwrap_or(usize::MAX).max(1);
+        for (message, chunks) in messages.into_iter().zip(chunks) {
+            // Wait for the window to open, which also sends the requests buffered so far
+            while self.session.sent_queue.len() >= max_in_flight {
+                responses.push(self.read_message().await?);
+            }
+            let bytes_sent = self.write_buffer.push(&chunks);
+            self.session.stats.sent(bytes_sent, 1);
+            self.session.sent(message);
+        }
+        self.finish_write().await?;
+
+        while !self.session.sent_queue.is_empty() {
+            responses.push(self.read_message().await?);
+        }
+        Ok(responses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+    use bolt_proto::Value;
+    use tokio::io::{duplex, AsyncReadExt as _, AsyncWriteExt as _, DuplexStream};
+    use tokio_util::compat::*;
 
-            for chunk in chunks {
-                self.stream.write_all(&chunk).await?;
+    use super::*;
+    use crate::error::CommunicationErrorKind;
+
+    async fn handshake(
+        version_specifiers: [u32; 4],
+        response: [u8; 4],
+    ) -> ConnectionResult<Client<Compat<DuplexStream>>> {
+        let (client_stream, mut server_stream) = duplex(64);
+        let server = tokio::spawn(async move {
+            let mut request = [0; 20];
+            server_stream.read_exact(&mut request).await.unwrap();
+            assert_eq!(request[..4], PREAMBLE);
+            server_stream.write_all(&response).await.unwrap();
+            server_stream
+        });
+        let result = Client::new(client_stream.compat(), &version_specifiers).await;
+        server.await.unwrap();
+        result
+    }
+
+    // Create a client connected to a server that replies to each received message in order
+    async fn mock_client(version: u32, replies: Vec<Message>) -> Client<Compat<DuplexStream>> {
+        mock_client_with_batches(version, replies.into_iter().map(|r| vec![r]).collect()).await
+    }
+
+    // Create a client connected to a server that replies to each received message with a batch of
+    // messages, in order
+    async fn mock_client_with_batches(
+        version: u32,
+        replies: Vec<Vec<Message>>,
+    ) -> Client<Compat<DuplexStream>> {
+        let (client_stream, server_stream) = duplex(1024);
+        tokio::spawn(async move {
+            let mut server_stream = server_stream.compat();
+            let mut request = [0; 20];
+            futures_util::io::AsyncReadExt::read_exact(&mut server_stream, &mut request)
+                .await
+                .unwrap();
+            futures_util::io::AsyncWriteExt::write_all(&mut server_stream, &version.to_be_bytes())
+                .await
+                .unwrap();
+            for batch in replies {
+                Message::from_stream(&mut server_stream).await.unwrap();
+                for reply in batch {
+                    for chunk in reply.into_chunks().unwrap() {
+                        futures_util::io::AsyncWriteExt::write_all(&mut server_stream, &chunk)
+                            .await
+                            .unwrap();
+                    }
+                }
             }
+        });
+        Client::new(client_stream.compat(), &[version, 0, 0, 0])
+            .await
+            .unwrap()
+    }
+
+    // Create a client connected to a server that is driven by the test, with the given buffer size
+    // in each direction
+    async fn mock_connection(
+        version: u32,
+        capacity: usize,
+    ) -> (Client<Compat<DuplexStream>>, Compat<DuplexStream>) {
+        let (client_stream, server_stream) = duplex(capacity);
+        let server = tokio::spawn(async move {
+            let mut server_stream = server_stream.compat();
+            let mut request = [0; 20];
+            futures_util::io::AsyncReadExt::read_exact(&mut server_stream, &mut request)
+                .await
+                .unwrap();
+            futures_util::io::AsyncWriteExt::write_all(&mut server_stream, &version.to_be_bytes())
+                .await
+                .unwrap();
+            server_stream
+        });
+        let client = Client::new(client_stream.compat(), &[version, 0, 0, 0])
+            .await
+            .unwrap();
+        (client, server.await.unwrap())
+    }
 
-            // Immediate state changes
-            match message {
-                Message::Reset => self.server_state = Interrupted,
-                Message::Goodbye => self.server_state = Disconnected,
-                _ => {}
+    #[tokio::test]
+    async fn handshake_info() {
+        let client = handshake([V4_4, V4_3, 0, 0], [0, 0, 3, 4]).await.unwrap();
+        assert_eq!(client.version(), Version::V4_3);
+        assert_eq!(client.handshake().version(), V4_3);
+        assert_eq!(client.handshake().response(), [0, 0, 3, 4]);
+        assert_eq!(client.handshake().version_specifiers(), [V4_4, V4_3, 0, 0]);
+        assert!(client.supports(Feature::RouteMessage));
+        assert!(!client.supports(Feature::Impersonation));
+    }
+
+    #[tokio::test]
+    async fn handshake_no_supported_version() {
+        let result = handshake([V4_4, V4_3, 0, 0], [0, 0, 0, 0]).await;
+        match result {
+            Err(ConnectionError::HandshakeFailed {
+                specifiers,
+                response,
+            }) => {
+                assert_eq!(specifiers, [V4_4, V4_3, 0, 0]);
+                assert_eq!(response, [0, 0, 0, 0]);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn handshake_unexpected_version() {
+        let result = handshake([V4_4, V4_3, 0, 0], [0, 0, 0, 3]).await;
+        match result {
+            Err(err @ ConnectionError::IncompatibleVersion { .. }) => {
+                assert!(matches!(
+                    err,
+                    ConnectionError::IncompatibleVersion {
+                        response: [0, 0, 0, 3],
+                        ..
+                    }
+                ));
+                assert!(err.to_string().contains("version 3.0 ([00, 00, 00, 03])"));
             }
+            other => panic!("unexpected result {:?}", other),
         }
-        self.stream.flush().await?;
-        self.sent_queue.extend(messages);
+    }
 
-        while !self.sent_queue.is_empty() {
-            responses.push(self.read_message().await?);
+    #[tokio::test]
+    async fn handshake_partial_response() {
+        // Send the response one byte at a time, then only part of it before closing
+        for (response, closed) in [(&[0, 0, 4, 4][..], false), (&[0, 0][..], true)] {
+            let (client_stream, mut server_stream) = duplex(64);
+            tokio::spawn(async move {
+                let mut request = [0; 20];
+                server_stream.read_exact(&mut request).await.unwrap();
+                for &byte in response {
+                    server_stream.write_all(&[byte]).await.unwrap();
+                    tokio::task::yield_now().await;
+                }
+                if !closed {
+                    std::future::pending::<()>().await;
+                }
+            });
+            let result = Client::new(client_stream.compat(), &[V4_4, 0, 0, 0]).await;
+            match result {
+                Ok(client) if !closed => assert_eq!(client.version(), Version::V4_4),
+                Err(ConnectionError::TruncatedHandshakeResponse { received }) if closed => {
+                    assert_eq!(received, [0, 0]);
+                }
+                other => panic!("unexpected result {:?}", other),
+            }
         }
-        Ok(responses)
+    }
+
+    #[tokio::test]
+    async fn handshake_malformed_response() {
+        match handshake([V4_4, 0, 0, 0], *b"HTTP").await {
+            Err(err @ ConnectionError::HttpResponse) => {
+                assert!(err.to_string().contains("7474"));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(matches!(
+            handshake([V4_4, 0, 0, 0], [0x15, 0x03, 0x03, 0x00]).await,
+            Err(ConnectionError::TlsResponse)
+        ));
+        match handshake([V4_4, 0, 0, 0], *b"SSH-").await {
+            Err(err @ ConnectionError::MalformedHandshakeResponse { .. }) => {
+                assert!(matches!(
+                    err,
+                    ConnectionError::MalformedHandshakeResponse { response } if &response == b"SSH-"
+                ));
+                assert!(err.to_string().contains("[53, 53, 48, 2d]"));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn reject_unsupported_metadata() {
+        let mut client = handshake([V3_0, 0, 0, 0], [0, 0, 0, 3]).await.unwrap();
+        assert_eq!(client.metadata_validation(), MetadataValidation::Reject);
+        let metadata = Metadata::from_iter(vec![("db", "neo4j"), ("mode", "r")]);
+        match client.run("RETURN 1;", None, Some(metadata.clone())).await {
+            Err(CommunicationError::UnsupportedMetadata { keys, version }) => {
+                assert_eq!(keys, vec![String::from("db")]);
+                assert_eq!(version, V3_0);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(matches!(
+            client.begin(Some(metadata.clone())).await,
+            Err(CommunicationError::UnsupportedMetadata { .. })
+        ));
+        // Supported metadata passes validation, but the client has not been initialized
+        assert!(matches!(
+            client
+                .run(
+                    "RETURN 1;",
+                    None,
+                    Some(Metadata::from_iter(vec![("mode", "r")]))
+                )
+                .await,
+            Err(CommunicationError::InvalidState { .. })
+        ));
+
+        let mut client = handshake([V3_0, 0, 0, 0], [0, 0, 0, 3]).await.unwrap();
+        client.set_metadata_validation(MetadataValidation::Ignore);
+        assert!(matches!(
+            client.run("RETURN 1;", None, Some(metadata)).await,
+            Err(CommunicationError::InvalidState { .. })
+        ));
+
+        let mut client = handshake([V4_3, 0, 0, 0], [0, 0, 3, 4]).await.unwrap();
+        match client
+            .route(
+                RoutingContext::default(),
+                vec![],
+                Some(Metadata::from_iter(vec![("imp_user", "neo4j")])),
+            )
+            .await
+        {
+            Err(err @ CommunicationError::UnsupportedMetadata { .. }) => {
+                assert_eq!(
+                    err.to_string(),
+                    "unsupported metadata for client with version = 4.3: imp_user"
+                );
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn connection_info() {
+        let success = Success::new(HashMap::from_iter(vec![
+            (String::from("server"), Value::from("Neo4j/4.4.0")),
+            (String::from("connection_id"), Value::from("bolt-61")),
+        ]));
+        let mut client = mock_client(V4_4, vec![Message::Success(success)]).await;
+        assert_eq!(client.server_agent(), None);
+        assert_eq!(client.connection_id(), None);
+
+        let response = client.hello(Metadata::default()).await.unwrap();
+        assert!(Success::try_from(response).is_ok());
+        assert_eq!(client.server_agent(), Some("Neo4j/4.4.0"));
+        assert_eq!(client.connection_id(), Some("bolt-61"));
+    }
+
+    #[tokio::test]
+    async fn stats() {
+        let replies = vec![
+            Message::Success(Success::new(Default::default())),
+            Message::Ignored,
+            Message::Success(Success::new(Default::default())),
+        ];
+        let mut client = mock_client(V4_4, replies).await;
+        assert_eq!(client.stats().bytes_sent(), 20);
+        assert_eq!(client.stats().bytes_received(), 4);
+        assert_eq!(client.stats().last_round_trip(), None);
+
+        client.hello(Metadata::default()).await.unwrap();
+        let stats = client.stats();
+        assert_eq!((stats.messages_sent(), stats.messages_received()), (1, 1));
+        // HELLO with the default user agent and bolt agent, and an empty SUCCESS
+        assert!(stats.bytes_sent() > 20);
+        assert_eq!(stats.bytes_received(), 4 + 2 + 3 + 2);
+        assert!(stats.last_round_trip().is_some());
+
+        client
+            .pipeline(vec![
+                Message::RunWithMetadata(RunWithMetadata::new(
+                    String::from("RETURN 1;"),
+                    Default::default(),
+                    Default::default(),
+                )),
+                Message::Reset,
+            ])
+            .await
+            .unwrap();
+        let stats = client.stats();
+        assert_eq!((stats.messages_sent(), stats.messages_received()), (3, 3));
+        assert_eq!(stats.records_received(), 0);
+        assert_eq!(stats.bytes_received(), 4 + (2 + 3 + 2) * 2 + (2 + 2 + 2));
+    }
+
+    #[tokio::test]
+    async fn utf8_mode() {
+        // A SUCCESS with the metadata {"server": "f\xFF"}
+        let reply: &[u8] = &[
+            0x00, 0x0D, 0xB1, 0x70, 0xA1, 0x86, b's', b'e', b'r', b'v', b'e', b'r', 0x82, b'f',
+            0xFF, 0x00, 0x00,
+        ];
+        let (client_stream, mut server_stream) = duplex(1024);
+        tokio::spawn(async move {
+            let mut request = [0; 20];
+            server_stream.read_exact(&mut request).await.unwrap();
+            server_stream.write_all(&V4_4.to_be_bytes()).await.unwrap();
+            let mut server_stream = server_stream.compat();
+            for _ in 0..2 {
+                Message::from_stream(&mut server_stream).await.unwrap();
+                futures_util::io::AsyncWriteExt::write_all(&mut server_stream, reply)
+                    .await
+                    .unwrap();
+            }
+        });
+        let mut client = Client::new(client_stream.compat(), &[V4_4, 0, 0, 0])
+            .await
+            .unwrap();
+        assert_eq!(client.utf8_mode(), Utf8Mode::Strict);
+        client.set_utf8_mode(Utf8Mode::Bytes);
+
+        let (mut writer, mut reader) = client.split();
+        writer
+            .send(Message::Hello(Hello::new(Default::default())))
+            .await
+            .unwrap();
+        let response = Success::try_from(reader.receive().await.unwrap()).unwrap();
+        assert_eq!(
+            response.metadata().get("server"),
+            Some(&Value::Bytes(vec![b'f', 0xFF]))
+        );
+
+        let mut client = reader.reunite(writer).unwrap();
+        client.set_utf8_mode(Utf8Mode::Lossy);
+        let response = Success::try_from(client.reset().await.unwrap()).unwrap();
+        assert_eq!(
+            response.metadata().get("server"),
+            Some(&Value::from("f\u{FFFD}"))
+        );
+    }
+
+    #[tokio::test]
+    async fn float_arrays() {
+        let embedding = vec![0.25; 100];
+        let success = || Message::Success(Success::new(Default::default()));
+        let record = Message::Record(Record::new(vec![
+            Value::from(embedding.clone()),
+            Value::from(vec![1]),
+        ]));
+        let replies = vec![vec![success()], vec![success()], vec![record, success()]];
+        let mut client = mock_client_with_batches(V4_4, replies).await;
+        assert!(!client.float_arrays());
+        client.set_float_arrays(true);
+        assert!(client.float_arrays());
+        client.hello(Metadata::default()).await.unwrap();
+        client
+            .run(
+                "RETURN $embedding, [1];",
+                Some(Params::from_iter([(
+                    "embedding",
+                    Value::FloatArray(embedding.clone()),
+                )])),
+                None,
+            )
+            .await
+            .unwrap();
+        let (records, _) = client.pull(None).await.unwrap();
+        assert_eq!(records[0].fields()[0], Value::FloatArray(embedding));
+        assert_eq!(records[0].fields()[1], Value::from(vec![1]));
+    }
+
+    #[tokio::test]
+    async fn bookmark_manager() {
+        let success = |bookmark: Option<&str>| {
+            Message::Success(Success::new(
+                bookmark
+                    .map(|b| (String::from("bookmark"), Value::from(b)))
+                    .into_iter()
+                    .collect(),
+            ))
+        };
+        let replies = vec![
+            success(None),
+            success(None),
+            success(None),
+            success(None),
+            success(Some("FB:b")),
+        ];
+        let manager =
+            BookmarkManager::with_bookmarks(Bookmarks::from_iter([
+                Bookmark::parse("FB:a").unwrap()
+            ]));
+        let mut writer = mock_client(V4_4, replies).await;
+        let mut reader = mock_client(V4_4, vec![success(None), success(None)]).await;
+        writer.set_bookmark_manager(Some(manager.clone()));
+        reader.set_bookmark_manager(Some(manager.clone()));
+
+        // The writer's transaction waits for the manager's bookmarks, and the bookmark it commits
+        // with replaces them
+        writer.hello(Metadata::default()).await.unwrap();
+        writer.begin(None).await.unwrap();
+        assert_eq!(
+            Vec::<String>::from(writer.session.tx_bookmarks.clone()),
+            vec!["FB:a"]
+        );
+        writer.run("CREATE (n);", None, None).await.unwrap();
+        writer.discard(None).await.unwrap();
+        writer.commit().await.unwrap();
+        assert_eq!(Vec::<String>::from(manager.bookmarks()), vec!["FB:b"]);
+
+        // So a transaction on another connection sees the write
+        reader.hello(Metadata::default()).await.unwrap();
+        reader.begin(None).await.unwrap();
+        assert_eq!(
+            Vec::<String>::from(reader.session.tx_bookmarks.clone()),
+            vec!["FB:b"]
+        );
+    }
+
+    #[tokio::test]
+    async fn size_limits() {
+        let record = || Message::Record(Record::new(vec![Value::from("a".repeat(100))]));
+        let replies = vec![
+            Message::Success(Success::new(Default::default())),
+            Message::Success(Success::new(Default::default())),
+            record(),
+        ];
+
+        let mut client = mock_client(V4_4, replies.clone()).await;
+        client.set_max_message_size(Some(50));
+        assert_eq!(client.max_message_size(), Some(50));
+        client.hello(Metadata::default()).await.unwrap();
+        client.run("RETURN 1;", None, None).await.unwrap();
+        match client.pull(None).await {
+            Err(CommunicationError::ProtocolError(ProtocolError::DeserializationError(
+                DeserializationError::MessageTooLarge { size: 105, max: 50 },
+            ))) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(client.server_state(), Defunct);
+
+        let mut client = mock_client(V4_4, replies).await;
+        client.set_max_value_size(Some(50));
+        assert_eq!(client.max_value_size(), Some(50));
+        client.hello(Metadata::default()).await.unwrap();
+        client.run("RETURN 1;", None, None).await.unwrap();
+        match client.pull(None).await {
+            Err(CommunicationError::ProtocolError(ProtocolError::DeserializationError(
+                DeserializationError::InvalidMessage { source, .. },
+            ))) => assert!(matches!(
+                *source,
+                DeserializationError::ValueTooLarge { size: 100, max: 50 }
+            )),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(client.server_state(), Defunct);
+    }
+
+    #[tokio::test]
+    async fn pull_named() {
+        let record = |n: i64| Message::Record(Record::new(vec![Value::from(n), Value::Null]));
+        let success = || Message::Success(Success::new(Default::default()));
+        let fields = Message::Success(Success::new(HashMap::from([(
+            String::from("fields"),
+            Value::from(vec!["a", "b"]),
+        )])));
+        let replies = vec![
+            vec![success()],
+            vec![fields],
+            vec![record(1), record(2), success()],
+        ];
+        let mut client = mock_client_with_batches(V4_4, replies).await;
+        client.hello(Metadata::default()).await.unwrap();
+        assert!(client.last_fields().is_none());
+
+        client
+            .run("RETURN 1 AS a, null AS b;", None, None)
+            .await
+            .unwrap();
+        assert_eq!(client.last_query_header().unwrap().fields(), ["a", "b"]);
+        let (records, _) = client
+            .pull_named(Some(Metadata::from_iter([("n", -1)])))
+            .await
+            .unwrap();
+        assert_eq!(client.last_fields().unwrap().as_ref(), ["a", "b"]);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].get("a"), Some(&Value::from(2)));
+        assert_eq!(records[1]["b"], Value::Null);
+        assert!(Arc::ptr_eq(records[0].keys(), records[1].keys()));
+    }
+
+    #[tokio::test]
+    async fn pull_lazy() {
+        let record = |n: i64| Message::Record(Record::new(vec![Value::from(n), Value::from("x")]));
+        let success = || Message::Success(Success::new(Default::default()));
+        let fields = Message::Success(Success::new(HashMap::from([(
+            String::from("fields"),
+            Value::from(vec!["a", "b"]),
+        )])));
+        let replies = vec![
+            vec![success()],
+            vec![fields],
+            vec![record(1), record(2), success()],
+        ];
+        let mut client = mock_client_with_batches(V4_4, replies).await;
+        client.hello(Metadata::default()).await.unwrap();
+        client
+            .run("RETURN 1 AS a, 'x' AS b;", None, None)
+            .await
+            .unwrap();
+        let (records, response) = client
+            .pull_lazy(Some(Metadata::from_iter([("n", -1)])))
+            .await
+            .unwrap();
+        assert!(matches!(response, Message::Success(_)));
+        assert_eq!(client.server_state(), Ready);
+        assert_eq!(client.stats().records_received(), 2);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].get_named("a").unwrap().unwrap(), Value::from(2));
+        assert_eq!(
+            records[0].clone().into_record().unwrap(),
+            Record::new(vec![Value::from(1), Value::from("x")])
+        );
+    }
+
+    #[tokio::test]
+    async fn admin() {
+        let success = || Message::Success(Success::new(Default::default()));
+        let fields = || {
+            Message::Success(Success::new(HashMap::from([(
+                String::from("fields"),
+                Value::from(vec!["name", "currentStatus", "error"]),
+            )])))
+        };
+        let database = |status: &str| {
+            Message::Record(Record::new(vec![
+                Value::from("reports"),
+                Value::from(status),
+                Value::from(""),
+            ]))
+        };
+        let bookmark = Message::Success(Success::new(HashMap::from([(
+            String::from("bookmark"),
+            Value::from("FB:system"),
+        )])));
+        let failure = Message::Failure(Failure::new(HashMap::from([(
+            String::from("code"),
+            Value::from("Neo.ClientError.Database.ExistingDatabaseFound"),
+        )])));
+        let replies = vec![
+            vec![success()],
+            // CREATE DATABASE
+            vec![fields()],
+            vec![bookmark],
+            // SHOW DATABASE, until the database is online
+            vec![fields()],
+            vec![database("initial"), success()],
+            vec![fields()],
+            vec![database("online"), success()],
+            // CREATE DATABASE for an existing database
+            vec![failure],
+            vec![success()],
+        ];
+        let mut client = mock_client_with_batches(V4_4, replies).await;
+        client.hello(Metadata::default()).await.unwrap();
+
+        let pending = client.create_database("reports").await.unwrap();
+        assert_eq!(pending.bookmark(), Some("FB:system"));
+        let databases = pending
+            .wait_online(std::time::Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(databases.len(), 1);
+        assert_eq!(databases[0].name(), "reports");
+        assert!(databases[0].is_online());
+
+        match client.create_database("reports").await {
+            Err(crate::error::AdminError::Failed(failure)) => assert_eq!(
+                failure.metadata().get("code"),
+                Some(&Value::from(
+                    "Neo.ClientError.Database.ExistingDatabaseFound"
+                ))
+            ),
+            other => panic!("expected a failure, got {:?}", other.map(|_| ())),
+        }
+        assert_eq!(client.server_state(), Ready);
+
+        let mut client = mock_client(V3_0, vec![success()]).await;
+        client.hello(Metadata::default()).await.unwrap();
+        assert!(matches!(
+            client.list_databases().await,
+            Err(crate::error::AdminError::CommunicationError(error))
+                if matches!(
+                    *error,
+                    CommunicationError::UnsupportedOperation {
+                        operation: "list_databases",
+                        version: V3_0
+                    }
+                )
+        ));
+    }
+
+    #[tokio::test]
+    async fn user_admin() {
+        let success = || Message::Success(Success::new(Default::default()));
+        let fields = Message::Success(Success::new(HashMap::from([(
+            String::from("fields"),
+            Value::from(vec!["user", "roles", "passwordChangeRequired"]),
+        )])));
+        let user = Message::Record(Record::new(vec![
+            Value::from("reporter"),
+            Value::from(vec!["PUBLIC"]),
+            Value::from(true),
+        ]));
+        let failure = Message::Failure(Failure::new(HashMap::from([(
+            String::from("code"),
+            Value::from("Neo.ClientError.Statement.UnsupportedAdministrationCommand"),
+        )])));
+        let replies = vec![
+            vec![success()],
+            // CREATE USER
+            vec![success()],
+            vec![success()],
+            // SHOW USERS
+            vec![fields],
+            vec![user, success()],
+            // GRANT ROLE on Community Edition
+            vec![failure],
+            vec![success()],
+        ];
+        let mut client = mock_client_with_batches(V4_4, replies).await;
+        client.hello(Metadata::default()).await.unwrap();
+
+        client
+            .create_user("reporter", "secret", true)
+            .await
+            .unwrap();
+        let users = client.list_users().await.unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].name(), "reporter");
+        assert_eq!(users[0].roles(), Some(&[String::from("PUBLIC")][..]));
+        assert!(users[0].password_change_required());
+
+        assert!(matches!(
+            client.grant_role("reader", "reporter").await,
+            Err(crate::error::AdminError::Failed(_))
+        ));
+        assert_eq!(client.server_state(), Ready);
+    }
+
+    #[tokio::test]
+    async fn keep_alive() {
+        let success = || Message::Success(Success::new(Default::default()));
+
+        // The server skips NOOP chunks, and keeps the connection open waiting for another message
+        let mut client = mock_client(V4_4, vec![success(), success(), success()]).await;
+        client.hello(Metadata::default()).await.unwrap();
+        client.begin(None).await.unwrap();
+        let stats = client.stats();
+        client.keep_alive().await.unwrap();
+        assert_eq!(client.stats().bytes_sent(), stats.bytes_sent() + 2);
+        assert_eq!(client.stats().messages_sent(), stats.messages_sent());
+        assert_eq!(client.server_state(), TxReady);
+        assert!(client.idle_time() < std::time::Duration::from_secs(1));
+
+        // Older versions send RESET, but only when it doesn't interrupt anything
+        let mut client = mock_client(V4_0, vec![success(), success(), success()]).await;
+        client.keep_alive().await.unwrap();
+        assert_eq!(client.stats().messages_sent(), 0);
+        client.hello(Metadata::default()).await.unwrap();
+        client.keep_alive().await.unwrap();
+        assert_eq!(client.stats().messages_sent(), 2);
+        assert_eq!(client.server_state(), Ready);
+        client.begin(None).await.unwrap();
+        client.keep_alive().await.unwrap();
+        assert_eq!(client.stats().messages_sent(), 3);
+        assert_eq!(client.server_state(), TxReady);
+    }
+
+    #[tokio::test]
+    async fn cancelled_keep_alive() {
+        use futures_util::FutureExt;
+
+        let success = || Message::Success(Success::new(Default::default()));
+        let fields = Message::Success(Success::new(HashMap::from([(
+            String::from("fields"),
+            Value::from(vec!["n"]),
+        )])));
+        let replies = vec![vec![success()], vec![success()], vec![fields.clone()]];
+        let mut client = mock_client_with_batches(V4_0, replies).await;
+        client.hello(Metadata::default()).await.unwrap();
+
+        // The RESET is sent, but the keepalive is dropped before its response is read
+        assert!(client.keep_alive().now_or_never().is_none());
+        assert_eq!(client.server_state(), Interrupted);
+
+        // The response to the RESET is read first, so it isn't mistaken for the response to the RUN
+        assert_eq!(
+            client.run("RETURN 1 AS n;", None, None).await.unwrap(),
+            fields
+        );
+        assert_eq!(client.server_state(), Streaming);
+    }
+
+    #[tokio::test]
+    async fn state_change_hook() {
+        let replies = vec![
+            Message::Success(Success::new(Default::default())),
+            Message::Failure(Failure::new(Default::default())),
+            Message::Success(Success::new(Default::default())),
+        ];
+        let mut client = mock_client(V4_4, replies).await;
+        let changes = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = std::sync::Arc::clone(&changes);
+        client.on_state_change(move |change| recorded.lock().unwrap().push(*change));
+
+        client.hello(Metadata::default()).await.unwrap();
+        client.run("RETURN 1;", None, None).await.unwrap();
+        client.reset().await.unwrap();
+        // Sending a message that is invalid for the current state makes the connection defunct
+        assert!(client.commit().await.is_err());
+
+        let changes: Vec<_> = changes
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|change| {
+                (
+                    change.previous(),
+                    change.current(),
+                    change.request(),
+                    change.response(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (Connected, Ready, Some("HELLO"), Some("SUCCESS")),
+                (Ready, Failed, Some("RUN"), Some("FAILURE")),
+                (Failed, Interrupted, Some("RESET"), None),
+                (Interrupted, Ready, Some("RESET"), Some("SUCCESS")),
+                (Ready, Defunct, Some("COMMIT"), None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn interceptors() {
+        #[derive(Default)]
+        struct Log(std::sync::Arc<std::sync::Mutex<Vec<String>>>);
+
+        impl Interceptor for Log {
+            fn before_send(&mut self, message: &mut Message) {
+                let entry = match message {
+                    Message::RunWithMetadata(run) => format!(">>> RUN {:?}", run.metadata()),
+                    message => format!(">>> {}", message.name()),
+                };
+                self.0.lock().unwrap().push(entry);
+            }
+
+            fn after_receive(&mut self, message: &mut Message) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("<<< {}", message.name()));
+            }
+        }
+
+        // Runs every query against a specific database
+        struct UseDatabase;
+
+        impl Interceptor for UseDatabase {
+            fn before_send(&mut self, message: &mut Message) {
+                if let Message::RunWithMetadata(run) = message {
+                    let mut metadata = run.metadata().clone();
+                    metadata.insert(String::from("db"), Value::from("neo4j"));
+                    *message = Message::RunWithMetadata(RunWithMetadata::new(
+                        run.statement().to_string(),
+                        run.parameters().clone(),
+                        metadata,
+                    ));
+                }
+            }
+        }
+
+        let replies = vec![
+            Message::Success(Success::new(Default::default())),
+            Message::Success(Success::new(Default::default())),
+            Message::Success(Success::new(Default::default())),
+            Message::Success(Success::new(Default::default())),
+        ];
+        let mut client = mock_client(V4_4, replies).await;
+        let log = Log::default();
+        let messages = std::sync::Arc::clone(&log.0);
+        client.add_interceptor(UseDatabase);
+        client.add_interceptor(log);
+
+        client.hello(Metadata::default()).await.unwrap();
+        client.run("RETURN 1;", None, None).await.unwrap();
+        let pull = Message::Pull(Pull::new(Metadata::from_iter([("n", -1)]).value));
+        client.pipeline(vec![pull]).await.unwrap();
+
+        let (mut writer, mut reader) = client.split();
+        writer.send(Message::Reset).await.unwrap();
+        reader.receive().await.unwrap();
+        let mut client = reader.reunite(writer).unwrap();
+        client.clear_interceptors();
+        assert!(client.session.interceptors.0.is_empty());
+
+        assert_eq!(
+            *messages.lock().unwrap(),
+            [
+                ">>> HELLO",
+                "<<< SUCCESS",
+                ">>> RUN {\"db\": String(\"neo4j\")}",
+                "<<< SUCCESS",
+                ">>> PULL",
+                "<<< SUCCESS",
+                ">>> RESET",
+                "<<< SUCCESS"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn pipeline_checks_intercepted_messages() {
+        // Replaces PULL with PULL_ALL, which doesn't exist in Bolt v4+
+        struct PullAll;
+
+        impl Interceptor for PullAll {
+            fn before_send(&mut self, message: &mut Message) {
+                if let Message::Pull(_) = message {
+                    *message = Message::PullAll;
+                }
+            }
+        }
+
+        let success = || Message::Success(Success::new(Default::default()));
+        let mut client = mock_client(V4_4, vec![success(), success()]).await;
+        client.hello(Metadata::default()).await.unwrap();
+        client.add_interceptor(PullAll);
+
+        let pull = Message::Pull(Pull::new(Metadata::from_iter([("n", -1)]).value));
+        assert!(matches!(
+            client.pipeline(vec![pull]).await,
+            Err(CommunicationError::UnsupportedOperation {
+                operation: "PULL_ALL",
+                version: V4_4
+            })
+        ));
+
+        // Nothing was sent, so the connection can still be used
+        client.clear_interceptors();
+        assert!(matches!(client.reset().await.unwrap(), Message::Success(_)));
+    }
+
+    #[tokio::test]
+    async fn run_prepared_shapes_metadata() {
+        // Records the metadata of each RUN sent
+        #[derive(Default)]
+        struct Sent(std::sync::Arc<std::sync::Mutex<Vec<HashMap<String, Value>>>>);
+
+        impl Interceptor for Sent {
+            fn before_send(&mut self, message: &mut Message) {
+                if let Message::RunWithMetadata(run) = message {
+                    self.0.lock().unwrap().push(run.metadata().clone());
+                }
+            }
+        }
+
+        let success = || Message::Success(Success::new(Default::default()));
+        let mut client = mock_client(V4_4, vec![success(), success()]).await;
+        let sent = Sent::default();
+        let metadata = std::sync::Arc::clone(&sent.0);
+        client.add_interceptor(sent);
+        client.hello(Metadata::default()).await.unwrap();
+
+        // Bolt v4.4 has no notification filtering, so the filter is dropped
+        let query = PreparedQuery::new("RETURN 1;").with_metadata(
+            Metadata::from_iter([("db", "neo4j")])
+                .with_notifications_minimum_severity(crate::NotificationSeverity::Off),
+        );
+        client.run_prepared(&query, None).await.unwrap();
+        assert_eq!(
+            *metadata.lock().unwrap(),
+            [Metadata::from_iter([("db", "neo4j")]).value]
+        );
+
+        let query = PreparedQuery::new("RETURN 1;").with_metadata(Metadata::from_iter([("db", 1)]));
+        assert!(client.run_prepared(&query, None).await.is_err());
+        assert_eq!(metadata.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn pull_default_n() {
+        // Records the metadata of each PULL and DISCARD sent
+        #[derive(Default)]
+        struct Sent(std::sync::Arc<std::sync::Mutex<Vec<HashMap<String, Value>>>>);
+
+        impl Interceptor for Sent {
+            fn before_send(&mut self, message: &mut Message) {
+                match message {
+                    Message::Pull(pull) => self.0.lock().unwrap().push(pull.metadata().clone()),
+                    Message::Discard(discard) => {
+                        self.0.lock().unwrap().push(discard.metadata().clone())
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let success = || Message::Success(Success::new(Default::default()));
+        let replies = vec![success(), success(), success(), success(), success()];
+        let mut client = mock_client(V4_4, replies).await;
+        let sent = Sent::default();
+        let metadata = std::sync::Arc::clone(&sent.0);
+        client.add_interceptor(sent);
+
+        client.hello(Metadata::default()).await.unwrap();
+        client.run("RETURN 1;", None, None).await.unwrap();
+        client.pull(None).await.unwrap();
+        client.run("RETURN 1;", None, None).await.unwrap();
+        client
+            .discard(Some(Metadata::from_iter([("qid", -1)])))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *metadata.lock().unwrap(),
+            [
+                Metadata::from_iter([("n", -1)]).value,
+                Metadata::from_iter([("n", -1), ("qid", -1)]).value,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn pull_and_discard_n() {
+        // Records each PULL and DISCARD sent, with its metadata
+        #[derive(Default)]
+        struct Sent(std::sync::Arc<std::sync::Mutex<Vec<Message>>>);
+
+        impl Interceptor for Sent {
+            fn before_send(&mut self, message: &mut Message) {
+                if matches!(
+                    message,
+                    Message::Pull(_) | Message::PullAll | Message::Discard(_) | Message::DiscardAll
+                ) {
+                    self.0.lock().unwrap().push(message.clone());
+                }
+            }
+        }
+
+        let success = || Message::Success(Success::new(Default::default()));
+        for version in [V3_0, V4_4] {
+            let replies = std::iter::repeat_with(success).take(9).collect();
+            let mut client = mock_client(version, replies).await;
+            let sent = Sent::default();
+            let messages = std::sync::Arc::clone(&sent.0);
+            client.add_interceptor(sent);
+
+            client.hello(Metadata::default()).await.unwrap();
+            client.run("RETURN 1;", None, None).await.unwrap();
+            client.pull_n(10).await.unwrap();
+            client.run("RETURN 1;", None, None).await.unwrap();
+            client.pull_all().await.unwrap();
+            client.run("RETURN 1;", None, None).await.unwrap();
+            client.discard_n(5, Some(2)).await.unwrap();
+            client.run("RETURN 1;", None, None).await.unwrap();
+            client.discard_all().await.unwrap();
+
+            let expected = if version == V3_0 {
+                vec![
+                    Message::PullAll,
+                    Message::PullAll,
+                    Message::DiscardAll,
+                    Message::DiscardAll,
+                ]
+            } else {
+                vec![
+                    Message::Pull(Pull::new(Metadata::from_iter([("n", 10)]).value)),
+                    Message::Pull(Pull::new(Metadata::from_iter([("n", -1)]).value)),
+                    Message::Discard(Discard::new(
+                        Metadata::from_iter([("n", 5), ("qid", 2)]).value,
+                    )),
+                    Message::Discard(Discard::new(Metadata::from_iter([("n", -1)]).value)),
+                ]
+            };
+            assert_eq!(*messages.lock().unwrap(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn unsupported_message() {
+        let success = || Message::Success(Success::new(Default::default()));
+        let mut client = mock_client(V4_2, vec![success(), success()]).await;
+        client.hello(Metadata::default()).await.unwrap();
+
+        let route = Route::new(Default::default(), vec![], None);
+        let error = client
+            .send_message(Message::Route(route))
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), CommunicationErrorKind::UnsupportedOperation);
+        assert!(matches!(
+            error,
+            CommunicationError::UnsupportedOperation {
+                operation: "ROUTE",
+                version: V4_2
+            }
+        ));
+        assert!(matches!(
+            client
+                .pipeline(vec![
+                    Message::RunWithMetadata(RunWithMetadata::new(
+                        "RETURN 1;".into(),
+                        Default::default(),
+                        Default::default(),
+                    )),
+                    Message::PullAll,
+                ])
+                .await,
+            Err(CommunicationError::UnsupportedOperation {
+                operation: "PULL_ALL",
+                version: V4_2
+            })
+        ));
+        let route = Route::new(Default::default(), vec![], None);
+        assert!(matches!(
+            client.pipeline(vec![Message::Route(route)]).await,
+            Err(CommunicationError::UnsupportedOperation {
+                operation: "ROUTE",
+                version: V4_2
+            })
+        ));
+
+        // Nothing was sent, so the connection can still be used
+        assert_eq!(client.server_state(), Ready);
+        assert!(matches!(client.reset().await.unwrap(), Message::Success(_)));
+    }
+
+    #[tokio::test]
+    async fn server_closed_connection() {
+        let replies = vec![
+            vec![Message::Success(Success::new(Default::default()))],
+            vec![Message::Success(Success::new(Default::default()))],
+            // The server closes the connection partway through the result
+            vec![Message::Record(Record::new(vec![Value::from(1)]))],
+        ];
+        let mut client = mock_client_with_batches(V4_4, replies).await;
+        let changes = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = std::sync::Arc::clone(&changes);
+        client.on_state_change(move |change| recorded.lock().unwrap().push(*change));
+
+        client.hello(Metadata::default()).await.unwrap();
+        client.run("RETURN 1;", None, None).await.unwrap();
+        assert!(matches!(
+            client.pull(None).await,
+            Err(CommunicationError::ServerClosedConnection {
+                during: MessageKind::Pull
+            })
+        ));
+        assert_eq!(client.server_state(), Defunct);
+        assert!(client.session.sent_queue.is_empty());
+
+        let last = *changes.lock().unwrap().last().unwrap();
+        assert_eq!(
+            (
+                last.previous(),
+                last.current(),
+                last.request(),
+                last.response()
+            ),
+            (Streaming, Defunct, Some("PULL"), None)
+        );
+    }
+
+    #[tokio::test]
+    async fn server_closed_connection_before_response() {
+        let mut client = mock_client(V4_4, vec![]).await;
+        assert!(matches!(
+            client.hello(Metadata::default()).await,
+            Err(CommunicationError::ServerClosedConnection {
+                during: MessageKind::Hello
+            })
+        ));
+        assert_eq!(client.server_state(), Defunct);
+    }
+
+    #[tokio::test]
+    async fn interleaved_tx_streams() {
+        let success = |entries: Vec<(&str, Value)>| {
+            Message::Success(Success::new(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), value))
+                    .collect(),
+            ))
+        };
+        let replies = vec![
+            success(vec![]),
+            success(vec![]),
+            success(vec![("qid", Value::from(0))]),
+            success(vec![("qid", Value::from(1))]),
+            success(vec![("qid", Value::from(2))]),
+            success(vec![("has_more", Value::from(true))]),
+            success(vec![]),
+            success(vec![]),
+            success(vec![("qid", Value::from(3))]),
+            success(vec![]),
+            success(vec![]),
+            success(vec![]),
+        ];
+        let mut client = mock_client(V4_4, replies).await;
+        let qid = |qid: i64| Some(Metadata::from_iter(vec![("n", -1), ("qid", qid)]));
+
+        client.hello(Metadata::default()).await.unwrap();
+        client.begin(None).await.unwrap();
+        for _ in 0..3 {
+            client.run("RETURN 1;", None, None).await.unwrap();
+        }
+        assert_eq!(client.session.open_tx_streams.len(), 3);
+
+        // A partial pull leaves the stream open
+        client.pull(qid(1)).await.unwrap();
+        assert_eq!(client.session.open_tx_streams.len(), 3);
+        client.pull(qid(1)).await.unwrap();
+        assert_eq!(client.session.open_tx_streams.len(), 2);
+        assert_eq!(client.server_state(), TxStreaming);
+        // Without a qid, the most recent query's stream is consumed
+        client.discard(None).await.unwrap();
+        assert_eq!(client.session.open_tx_streams.len(), 1);
+        assert_eq!(client.server_state(), TxStreaming);
+
+        client.run("RETURN 1;", None, None).await.unwrap();
+        client.discard(qid(0)).await.unwrap();
+        assert_eq!(client.server_state(), TxStreaming);
+        client.pull(qid(-1)).await.unwrap();
+        assert!(client.session.open_tx_streams.is_empty());
+        assert_eq!(client.server_state(), TxReady);
+
+        client.commit().await.unwrap();
+        assert_eq!(client.server_state(), Ready);
+    }
+
+    #[tokio::test]
+    async fn recent_exchanges() {
+        let success = || Message::Success(Success::new(Default::default()));
+        let failure = Failure::new(HashMap::from([(
+            String::from("code"),
+            Value::from("Neo.ClientError.Statement.SyntaxError"),
+        )]));
+        let replies = vec![
+            success(),
+            Message::Failure(failure.clone()),
+            Message::Ignored,
+        ];
+        let mut client = mock_client(V4_4, replies).await;
+        client.set_exchange_capacity(2);
+
+        client.hello(Metadata::default()).await.unwrap();
+        client.run("RETURN;", None, None).await.unwrap();
+        client.pull(None).await.unwrap();
+        let exchanges: Vec<_> = client
+            .recent_exchanges()
+            .map(|exchange| (exchange.request(), exchange.response().clone()))
+            .collect();
+        assert_eq!(
+            exchanges,
+            [
+                (
+                    Some(MessageKind::RunWithMetadata),
+                    Message::Failure(failure)
+                ),
+                (Some(MessageKind::Pull), Message::Ignored),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn transaction_status() {
+        let success = || Message::Success(Success::new(Default::default()));
+        let failure = Message::Failure(Failure::new(Default::default()));
+        let replies = vec![
+            success(),
+            success(),
+            success(),
+            success(),
+            success(),
+            success(),
+            failure,
+        ];
+        let mut client = mock_client(V4_4, replies).await;
+        assert_eq!(client.transaction_status(), TransactionStatus::Idle);
+
+        client.hello(Metadata::default()).await.unwrap();
+        client.run("RETURN 1;", None, None).await.unwrap();
+        assert!(!client.in_transaction());
+        assert_eq!(client.open_result_streams(), 1);
+        client.discard(None).await.unwrap();
+        assert_eq!(client.open_result_streams(), 0);
+
+        client.begin(None).await.unwrap();
+        assert!(client.in_transaction());
+        assert_eq!(client.open_result_streams(), 0);
+        client.run("RETURN 1;", None, None).await.unwrap();
+        client.run("RETURN 2;", None, None).await.unwrap();
+        assert_eq!(client.transaction_status(), TransactionStatus::Active);
+        assert_eq!(client.open_result_streams(), 2);
+
+        client.run("RETURN;", None, None).await.unwrap();
+        assert!(!client.in_transaction());
+        assert_eq!(client.transaction_status(), TransactionStatus::Failed);
+        assert_eq!(client.open_result_streams(), 0);
+    }
+
+    #[tokio::test]
+    async fn split_pipelines_while_streaming() {
+        let record = |n: i64| Message::Record(Record::new(vec![Value::from(n)]));
+        let success = || Message::Success(Success::new(Default::default()));
+        let replies = vec![
+            vec![success()],
+            vec![success()],
+            vec![record(1), record(2), success()],
+            vec![success()],
+            vec![record(3), success()],
+        ];
+        let mut client = mock_client_with_batches(V4_4, replies).await;
+        client.hello(Metadata::default()).await.unwrap();
+
+        let run = || {
+            Message::RunWithMetadata(RunWithMetadata::new(
+                String::from("RETURN 1;"),
+                Default::default(),
+                Default::default(),
+            ))
+        };
+        let pull_all = || {
+            Message::Pull(Pull::new(HashMap::from([(
+                String::from("n"),
+                Value::from(-1),
+            )])))
+        };
+        let (mut writer, mut reader) = client.split();
+        writer.send(run()).await.unwrap();
+        writer.send(pull_all()).await.unwrap();
+        assert!(matches!(
+            reader.receive().await.unwrap(),
+            Message::Success(_)
+        ));
+        assert_eq!(reader.receive().await.unwrap(), record(1));
+
+        // The next query can be sent while the records of the previous one are still arriving
+        assert_eq!(writer.server_state(), Streaming);
+        assert_eq!(writer.expected_server_state(), Ready);
+        writer.send(run()).await.unwrap();
+        writer.send(pull_all()).await.unwrap();
+        assert_eq!(reader.pending(), 3);
+
+        let mut responses = Vec::new();
+        while reader.pending() > 0 {
+            responses.push(reader.receive().await.unwrap());
+        }
+        assert_eq!(responses.len(), 5);
+        assert_eq!(responses[0], record(2));
+        assert_eq!(responses[3], record(3));
+
+        let client = reader.reunite(writer).unwrap();
+        assert_eq!(client.server_state(), Ready);
+        assert_eq!(client.stats().messages_sent(), 5);
+        assert_eq!(client.stats().records_received(), 3);
+    }
+
+    #[tokio::test]
+    async fn split_rejects_invalid_requests() {
+        // The RUN and PULL are never answered
+        let replies = vec![
+            vec![Message::Success(Success::new(Default::default()))],
+            vec![],
+            vec![],
+        ];
+        let mut client = mock_client_with_batches(V4_4, replies).await;
+        client.hello(Metadata::default()).await.unwrap();
+
+        let (mut writer, _reader) = client.split();
+        writer
+            .send(Message::RunWithMetadata(RunWithMetadata::new(
+                String::from("RETURN 1;"),
+                Default::default(),
+                Default::default(),
+            )))
+            .await
+            .unwrap();
+        writer
+            .send(Message::Pull(Pull::new(HashMap::from([(
+                String::from("n"),
+                Value::from(1),
+            )]))))
+            .await
+            .unwrap();
+        // The PULL might leave records in the result, so the server may still be streaming
+        assert!(matches!(
+            writer
+                .send(Message::Begin(Begin::new(Default::default())))
+                .await,
+            Err(CommunicationError::InvalidState {
+                state: Streaming,
+                ..
+            })
+        ));
+        assert_eq!(writer.server_state(), Defunct);
+    }
+
+    // A timer that records the delays it is asked to wait for, without waiting
+    #[derive(Debug, Clone, Default)]
+    struct RecordingTimer(Arc<Mutex<Vec<Duration>>>);
+
+    impl Timer for RecordingTimer {
+        fn sleep(&self, duration: Duration) -> futures_util::future::BoxFuture<'static, ()> {
+            self.0.lock().unwrap().push(duration);
+            Box::pin(futures_util::future::ready(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn transaction_function_retries() {
+        let message = |entries: Vec<(&str, &str)>| {
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), Value::from(value)))
+                .collect::<HashMap<_, _>>()
+        };
+        let success = |entries| Message::Success(Success::new(message(entries)));
+        let failure = |code| Message::Failure(Failure::new(message(vec![("code", code)])));
+        let replies = vec![
+            success(vec![]),
+            // Deadlock in the first attempt, which is reset
+            success(vec![]),
+            failure("Neo.TransientError.Transaction.DeadlockDetected"),
+            success(vec![]),
+            // Second attempt succeeds
+            success(vec![]),
+            success(vec![]),
+            success(vec![]),
+            success(vec![("bookmark", "bookmark:1")]),
+            // Syntax errors are not retried
+            success(vec![]),
+            failure("Neo.ClientError.Statement.SyntaxError"),
+            success(vec![]),
+        ];
+        let mut client = mock_client(V4_4, replies).await;
+        client.set_retry_policy(
+            RetryPolicy::new()
+                .with_initial_delay(Duration::from_millis(1))
+                .with_jitter(0.0),
+        );
+        let timer = RecordingTimer::default();
+        client.set_timer(timer.clone());
+        client.hello(Metadata::default()).await.unwrap();
+
+        let mut attempts = 0;
+        let result = client
+            .write_transaction(None, async |tx| {
+                attempts += 1;
+                let response = tx.run("CREATE (n);", None, None).await?;
+                Success::try_from(response).map_err(ProtocolError::from)?;
+                tx.discard(Some(Metadata::from_iter([("n", -1)]))).await?;
+                Ok::<_, CommunicationError>(attempts)
+            })
+            .await;
+        assert_eq!(result.unwrap(), 2);
+        assert_eq!(client.last_bookmark(), Some("bookmark:1"));
+        assert_eq!(client.server_state(), Ready);
+
+        let result = client
+            .read_transaction(None, async |tx| {
+                attempts += 1;
+                tx.run("RETURN;", None, None).await.map(|_| ())
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(crate::error::TransactionError::Failed(_))
+        ));
+        assert_eq!(attempts, 3);
+        assert_eq!(client.server_state(), Ready);
+        assert_eq!(*timer.0.lock().unwrap(), [Duration::from_millis(1)]);
+    }
+
+    #[tokio::test]
+    async fn run_in_tx() {
+        let success = || Message::Success(Success::new(Default::default()));
+        let failure = || {
+            Message::Failure(Failure::new(HashMap::from([(
+                String::from("code"),
+                Value::from("Neo.TransientError.Transaction.DeadlockDetected"),
+            )])))
+        };
+        let replies = vec![
+            success(),
+            // BEGIN, RUN, DISCARD, and COMMIT
+            success(),
+            success(),
+            success(),
+            success(),
+            // BEGIN, RUN, and ROLLBACK once the function fails
+            success(),
+            success(),
+            success(),
+            // BEGIN, a failed RUN, and RESET, without retrying
+            success(),
+            failure(),
+            success(),
+        ];
+        let mut client = mock_client(V4_4, replies).await;
+        client.hello(Metadata::default()).await.unwrap();
+
+        let result = client
+            .run_in_tx(async |tx| {
+                tx.run("CREATE (n);", None, None).await?;
+                tx.discard(Some(Metadata::from_iter([("n", -1)]))).await?;
+                Ok::<_, CommunicationError>(1)
+            })
+            .await;
+        assert_eq!(result.unwrap(), 1);
+        assert_eq!(client.server_state(), Ready);
+
+        let result = client
+            .run_in_tx(async |tx| {
+                tx.run("CREATE (n);", None, None).await?;
+                Err::<(), _>(CommunicationError::from(std::io::Error::other("cancelled")))
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(crate::error::TransactionError::Work(_))
+        ));
+        assert_eq!(client.server_state(), Ready);
+
+        let mut calls = 0;
+        let result = client
+            .run_in_tx(async |tx| {
+                calls += 1;
+                tx.run("CREATE (n);", None, None).await.map(|_| ())
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(crate::error::TransactionError::Failed(_))
+        ));
+        assert_eq!(calls, 1);
+        assert_eq!(client.server_state(), Ready);
+    }
+
+    #[tokio::test]
+    async fn run_batched() {
+        let success = || Message::Success(Success::new(Default::default()));
+        let pull_success = |created: i64| {
+            let stats = HashMap::from([("nodes-created", Value::from(created))]);
+            Message::Success(Success::new(HashMap::from([(
+                String::from("stats"),
+                Value::from(stats),
+            )])))
+        };
+        let mut replies = vec![success()];
+        for created in [2, 2, 1] {
+            // BEGIN, RUN, PULL, and COMMIT
+            replies.extend([success(), success(), pull_success(created), success()]);
+        }
+        let mut client = mock_client(V4_4, replies).await;
+        client.hello(Metadata::default()).await.unwrap();
+
+        let mut progress = vec![];
+        let summary = client
+            .run_batched(
+                "UNWIND $batch AS row CREATE (:Item {id: row.id});",
+                (0..5).map(|id| Params::from_iter([("id", id)])),
+                2,
+                None,
+                |summary| progress.push(summary.rows()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(progress, vec![2, 4, 5]);
+        assert_eq!((summary.batches(), summary.rows()), (3, 5));
+        assert_eq!(summary.counters().get("nodes-created"), Some(&5));
+        assert_eq!(client.server_state(), Ready);
+    }
+
+    #[tokio::test]
+    async fn pull_stream() {
+        use futures_util::StreamExt;
+
+        let record = |n: i64| Message::Record(Record::new(vec![Value::from(n)]));
+        let success = |has_more: bool| {
+            let mut metadata = HashMap::new();
+            if has_more {
+                metadata.insert(String::from("has_more"), Value::from(true));
+            }
+            Message::Success(Success::new(metadata))
+        };
+        let replies = vec![
+            vec![success(false)],
+            vec![success(false)],
+            vec![record(1), record(2), success(true)],
+            vec![record(3), success(false)],
+        ];
+        let mut client = mock_client_with_batches(V4_4, replies).await;
+        client.hello(Metadata::default()).await.unwrap();
+        client.run("RETURN 1;", None, None).await.unwrap();
+
+        let metadata = Metadata::from_iter(vec![("n", 2)]);
+        let mut stream = client.pull_stream(Some(metadata.clone()));
+        let first: Vec<Record> = stream
+            .by_ref()
+            .take(2)
+            .map(|record| record.unwrap())
+            .collect()
+            .await;
+        assert_eq!(first.len(), 2);
+        assert!(stream.summary().is_none());
+        assert!(stream.stream_summary().is_none());
+        drop(stream);
+        // The next batch has not been requested yet
+        assert_eq!(client.stats().messages_sent(), 3);
+        assert_eq!(client.server_state(), Streaming);
+
+        let mut stream = client.pull_stream(Some(metadata));
+        let rest: Vec<Record> = stream
+            .by_ref()
+            .map(|record| record.unwrap())
+            .collect()
+            .await;
+        assert_eq!(rest, vec![Record::new(vec![Value::from(3)])]);
+        assert!(matches!(stream.summary(), Some(Message::Success(_))));
+        assert!(!stream.stream_summary().unwrap().has_more());
+        drop(stream);
+        assert_eq!(client.stats().messages_sent(), 4);
+        assert_eq!(client.server_state(), Ready);
+    }
+
+    #[tokio::test]
+    async fn pull_stream_batch_size() {
+        use futures_util::StreamExt;
+
+        struct Sent(std::sync::Arc<std::sync::Mutex<Vec<HashMap<String, Value>>>>);
+
+        impl Interceptor for Sent {
+            fn before_send(&mut self, message: &mut Message) {
+                if let Message::Pull(pull) = message {
+                    self.0.lock().unwrap().push(pull.metadata().clone());
+                }
+            }
+        }
+
+        let success = || Message::Success(Success::new(HashMap::new()));
+        let replies = vec![vec![success()], vec![success()], vec![success()]];
+        let mut client = mock_client_with_batches(V4_4, replies).await;
+        let pulls = std::sync::Arc::default();
+        client.add_interceptor(Sent(std::sync::Arc::clone(&pulls)));
+        client.hello(Metadata::default()).await.unwrap();
+        client.run("RETURN 1;", None, None).await.unwrap();
+
+        assert_eq!(client.pull_stream(None).count().await, 0);
+        assert_eq!(
+            *pulls.lock().unwrap(),
+            [Metadata::from_iter([("n", 1000)]).value]
+        );
+    }
+
+    #[tokio::test]
+    async fn transaction_streams() {
+        use futures_util::StreamExt;
+
+        // Records the metadata of each PULL sent
+        #[derive(Default)]
+        struct Sent(std::sync::Arc<std::sync::Mutex<Vec<HashMap<String, Value>>>>);
+
+        impl Interceptor for Sent {
+            fn before_send(&mut self, message: &mut Message) {
+                if let Message::Pull(pull) = message {
+                    self.0.lock().unwrap().push(pull.metadata().clone());
+                }
+            }
+        }
+
+        let record = |value: Value| Message::Record(Record::new(vec![value]));
+        let success = |entries: Vec<(&str, Value)>| {
+            Message::Success(Success::new(Metadata::from_iter(entries).value))
+        };
+        let has_more = || success(vec![("has_more", Value::from(true))]);
+        let replies = vec![
+            vec![success(vec![])],
+            vec![success(vec![])],
+            vec![success(vec![("qid", Value::from(0))])],
+            vec![success(vec![("qid", Value::from(1))])],
+            vec![record(Value::from(1)), has_more()],
+            vec![record(Value::from("a")), has_more()],
+            vec![record(Value::from(2)), success(vec![])],
+            vec![
+                record(Value::from("b")),
+                success(vec![("type", Value::from("r"))]),
+            ],
+            vec![success(vec![])],
+        ];
+        let mut client = mock_client_with_batches(V4_4, replies).await;
+        let sent = Sent::default();
+        let pulls = std::sync::Arc::clone(&sent.0);
+        client.add_interceptor(sent);
+        client.hello(Metadata::default()).await.unwrap();
+        client.begin(None).await.unwrap();
+
+        let tx = Transaction::new(&mut client).with_batch_size(0);
+        assert_eq!(tx.batch_size(), 1);
+        tx.run("UNWIND [1, 2] AS n RETURN n;", None, None)
+            .await
+            .unwrap();
+        tx.run("UNWIND ['a', 'b'] AS l RETURN l;", None, None)
+            .await
+            .unwrap();
+        let (mut numbers, mut letters) = (tx.stream(0), tx.stream(1));
+        let pairs: Vec<(Record, Record)> = numbers
+            .by_ref()
+            .zip(letters.by_ref())
+            .map(|(number, letter)| (number.unwrap(), letter.unwrap()))
+            .collect()
+            .await;
+        assert_eq!(
+            pairs,
+            [
+                (
+                    Record::new(vec![Value::from(1)]),
+                    Record::new(vec![Value::from("a")])
+                ),
+                (
+                    Record::new(vec![Value::from(2)]),
+                    Record::new(vec![Value::from("b")])
+                ),
+            ]
+        );
+        assert!(numbers.stream_summary().is_some());
+        assert_eq!(letters.stream_summary().unwrap().query_type(), Some("r"));
+        drop((numbers, letters));
+
+        assert!(matches!(tx.commit().await.unwrap(), Message::Success(_)));
+        assert_eq!(
+            *pulls.lock().unwrap(),
+            [0, 1, 0, 1].map(|qid| Metadata::from_iter([("n", 1), ("qid", qid)]).value)
+        );
+        assert_eq!(client.server_state(), Ready);
+    }
+
+    #[tokio::test]
+    async fn tx_stream_dropped_mid_batch() {
+        use futures_util::{FutureExt, StreamExt};
+
+        let record = |value: Value| Message::Record(Record::new(vec![value]));
+        let success = |entries: Vec<(&str, Value)>| {
+            Message::Success(Success::new(Metadata::from_iter(entries).value))
+        };
+        let replies = vec![
+            vec![success(vec![])],
+            vec![success(vec![])],
+            vec![success(vec![("qid", Value::from(0))])],
+            vec![success(vec![("qid", Value::from(1))])],
+            vec![
+                record(Value::from(1)),
+                record(Value::from(2)),
+                success(vec![("has_more", Value::from(true))]),
+            ],
+            vec![record(Value::from("a")), success(vec![])],
+        ];
+        let mut client = mock_client_with_batches(V4_4, replies).await;
+        client.hello(Metadata::default()).await.unwrap();
+        client.begin(None).await.unwrap();
+
+        let tx = Transaction::new(&mut client).with_batch_size(2);
+        tx.run("UNWIND [1, 2, 3] AS n RETURN n;", None, None)
+            .await
+            .unwrap();
+        tx.run("UNWIND ['a'] AS l RETURN l;", None, None)
+            .await
+            .unwrap();
+
+        // The first stream sends its PULL, but is dropped before reading the batch
+        let mut numbers = tx.stream(0);
+        assert!(numbers.next().now_or_never().is_none());
+        drop(numbers);
+
+        // The rest of the batch is read and discarded instead of going to the other stream
+        let letters: Vec<Record> = tx.stream(1).map(|letter| letter.unwrap()).collect().await;
+        assert_eq!(letters, [Record::new(vec![Value::from("a")])]);
+        assert_eq!(tx.into_inner().server_state(), TxStreaming);
+    }
+
+    #[tokio::test]
+    async fn cancelled_read() {
+        use futures_util::{io::AsyncWriteExt, FutureExt};
+
+        let (mut client, mut server) = mock_connection(V4_4, 1024).await;
+        let response = Message::Success(Success::new(HashMap::from([(
+            String::from("server"),
+            Value::from("Neo4j/4.4.0"),
+        )])));
+        let bytes = response.clone().into_chunks().unwrap().concat();
+        let (first, rest) = bytes.split_at(bytes.len() / 2);
+
+        // Only part of the response has arrived when the future is dropped
+        server.write_all(first).await.unwrap();
+        assert!(client.hello(Metadata::default()).now_or_never().is_none());
+        assert_eq!(client.server_state(), Connected);
+
+        server.write_all(rest).await.unwrap();
+        assert_eq!(client.read_message().await.unwrap(), response);
+        assert_eq!(client.server_state(), Ready);
+        assert_eq!(client.server_agent(), Some("Neo4j/4.4.0"));
+        assert_eq!(client.stats().bytes_received(), (4 + bytes.len()) as u64);
+    }
+
+    #[tokio::test]
+    async fn pipeline_max_in_flight() {
+        use futures_util::FutureExt;
+
+        async fn reply_success(server: &mut Compat<DuplexStream>) {
+            for chunk in Message::Success(Success::new(HashMap::new()))
+                .into_chunks()
+                .unwrap()
+            {
+                futures_util::io::AsyncWriteExt::write_all(server, &chunk)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let (mut client, mut server) = mock_connection(V4_4, 1024).await;
+        client.set_max_in_flight(Some(2));
+        let server = tokio::spawn(async move {
+            // HELLO
+            Message::from_stream(&mut server).await.unwrap();
+            reply_success(&mut server).await;
+
+            let (mut received, mut replied) = (0, 0);
+            while replied < 6 {
+                if received < 6 && received - replied < 2 {
+                    Message::from_stream(&mut server).await.unwrap();
+                    received += 1;
+                    continue;
+                }
+                // The client must wait for a response before sending more requests
+                if received < 6 {
+                    tokio::task::yield_now().await;
+                    assert!(Message::from_stream(&mut server).now_or_never().is_none());
+                }
+                reply_success(&mut server).await;
+                replied += 1;
+            }
+        });
+
+        client.hello(Metadata::default()).await.unwrap();
+        let run = Message::RunWithMetadata(RunWithMetadata::new(
+            "RETURN 1;".into(),
+            HashMap::new(),
+            HashMap::new(),
+        ));
+        let pull = Message::Pull(Pull::new(Metadata::from_iter([("n", -1)]).value));
+        let responses = client
+            .pipeline(std::iter::repeat_n([run, pull], 3).flatten().collect())
+            .await
+            .unwrap();
+        assert_eq!(responses.len(), 6);
+        assert!(responses
+            .iter()
+            .all(|response| matches!(response, Message::Success(_))));
+        assert_eq!(client.server_state(), Ready);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancelled_write() {
+        use futures_util::FutureExt;
+
+        // The message doesn't fit in the buffer, so writing it stalls until the server reads
+        let (mut client, mut server) = mock_connection(V4_4, 32).await;
+        let user_agent = "a".repeat(200);
+        let hello = client.hello(Metadata::from_iter(vec![(
+            "user_agent",
+            user_agent.as_str(),
+        )]));
+        assert!(hello.now_or_never().is_none());
+
+        let server = tokio::spawn(async move {
+            let request = Message::from_stream(&mut server).await.unwrap();
+            for chunk in Message::Success(Success::new(HashMap::new()))
+                .into_chunks()
+                .unwrap()
+            {
+                futures_util::io::AsyncWriteExt::write_all(&mut server, &chunk)
+                    .await
+                    .unwrap();
+            }
+            request
+        });
+        // Reading the response finishes sending the request first
+        assert!(matches!(
+            client.read_message().await.unwrap(),
+            Message::Success(_)
+        ));
+        assert_eq!(client.server_state(), Ready);
+        match server.await.unwrap() {
+            Message::Hello(hello) => {
+                assert_eq!(
+                    hello.metadata().get("user_agent"),
+                    Some(&Value::from(user_agent))
+                );
+            }
+            other => panic!("unexpected request {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn reset_after_cancelled_pull() {
+        use futures_util::FutureExt;
+
+        let record = |n: i64| Message::Record(Record::new(vec![Value::from(n)]));
+        let success = || Message::Success(Success::new(HashMap::new()));
+        let replies = vec![
+            vec![success()],
+            vec![success()],
+            vec![record(1), record(2), success()],
+            vec![success()],
+        ];
+        let mut client = mock_client_with_batches(V4_4, replies).await;
+        client.hello(Metadata::default()).await.unwrap();
+        client
+            .run("UNWIND [1, 2] AS n RETURN n;", None, None)
+            .await
+            .unwrap();
+
+        // The PULL is sent, but none of its responses are read
+        assert!(client.pull(None).now_or_never().is_none());
+        assert_eq!(client.server_state(), Streaming);
+
+        assert!(matches!(client.reset().await.unwrap(), Message::Success(_)));
+        assert_eq!(client.server_state(), Ready);
+        assert!(client.session.sent_queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn run_after_cancelled_pull() {
+        use futures_util::FutureExt;
+
+        let record = |n: i64| Message::Record(Record::new(vec![Value::from(n)]));
+        let fields = |field: &str| {
+            Message::Success(Success::new(HashMap::from([(
+                String::from("fields"),
+                Value::from(vec![field]),
+            )])))
+        };
+        let replies = vec![
+            vec![Message::Success(Success::new(HashMap::new()))],
+            vec![fields("n")],
+            vec![
+                record(1),
+                record(2),
+                Message::Success(Success::new(HashMap::new())),
+            ],
+            vec![fields("m")],
+        ];
+        let mut client = mock_client_with_batches(V4_4, replies).await;
+        client.hello(Metadata::default()).await.unwrap();
+        client
+            .run("UNWIND [1, 2] AS n RETURN n;", None, None)
+            .await
+            .unwrap();
+        assert!(client.pull(None).now_or_never().is_none());
+
+        // The records of the dropped PULL are skipped instead of answering the next RUN
+        let response = client.run("RETURN 1 AS m;", None, None).await.unwrap();
+        assert_eq!(response, fields("m"));
+        assert_eq!(client.server_state(), Streaming);
+        assert!(client.session.sent_queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn run_after_cancelled_run() {
+        use futures_util::FutureExt;
+
+        let replies = vec![
+            vec![Message::Success(Success::new(HashMap::new()))],
+            vec![Message::Success(Success::new(HashMap::new()))],
+        ];
+        let mut client = mock_client_with_batches(V4_4, replies).await;
+        client.hello(Metadata::default()).await.unwrap();
+        assert!(client.run("RETURN 1;", None, None).now_or_never().is_none());
+
+        // The first query's result is still open, which the second RUN is checked against
+        assert!(matches!(
+            client.run("RETURN 2;", None, None).await,
+            Err(CommunicationError::InvalidState {
+                state: Streaming,
+                ..
+            })
+        ));
     }
 }
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use bolt_proto::{Message, Value};
+use futures_util::io::{AsyncRead, AsyncWrite};
+
+use crate::{
+    error::{CommunicationError, TransactionError},
+    Client, Metadata, Params,
+};
+
+/// The totals for the batches run so far by [`Client::run_batched`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchSummary {
+    batches: u64,
+    rows: u64,
+    counters: HashMap<String, i64>,
+}
+
+impl BatchSummary {
+    /// Get the number of batches committed.
+    pub fn batches(&self) -> u64 {
+        self.batches
+    }
+
+    /// Get the number of parameter sets in the committed batches.
+    pub fn rows(&self) -> u64 {
+        self.rows
+    }
+
+    /// Get the sum of each integer counter in the `stats` metadata the server returned for the
+    /// committed batches, e.g. `nodes-created` or `properties-set`.
+    pub fn counters(&self) -> &HashMap<String, i64> {
+        &self.counters
+    }
+
+    fn add(&mut self, rows: usize, summary: &Message) {
+        self.batches += 1;
+        self.rows += rows as u64;
+        let stats = match summary {
+            Message::Success(success) => success.metadata().get("stats"),
+            _ => None,
+        };
+        if let Some(Value::Map(stats)) = stats {
+            for (key, value) in stats {
+                if let Value::Integer(count) = value {
+                    *self.counters.entry(key.clone()).or_default() += count;
+                }
+            }
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
+    /// Run a query once for many sets of parameters, sending them in batches. _(Bolt v3+ only.)_
+    ///
+    /// # Description
+    /// The parameter sets are split into batches of `batch_size` (a size of 0 is treated as 1).
+    /// Each batch is sent as a list of maps in the `batch` parameter of `query`, which should
+    /// `UNWIND` it, e.g. `UNWIND $batch AS row CREATE (:Person {name: row.name})`. This is much
+    /// faster than running the query once for each parameter set.
+    ///
+    /// Each batch is run in its own transaction with [`Client::write_transaction`], using the
+    /// given `metadata`, so batches that fail with a transient error are retried. Once a batch is
+    /// committed, `on_progress` is called with the totals so far. If a batch fails, the error is
+    /// returned and the following batches are not run, but earlier batches stay committed.
+    ///
+    /// ```
+    /// # use std::env;
+    /// # use bolt_client::*;
+    /// # use bolt_proto::version::*;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let result = ClientBuilder::new(env::var("BOLT_TEST_ADDR")?)
+    /// #     .with_domain(env::var("BOLT_TEST_DOMAIN").ok())
+    /// #     .with_versions([V4_4, V4_3, V4_2, V4_1])
+    /// #     .with_basic_auth(env::var("BOLT_TEST_USERNAME")?, env::var("BOLT_TEST_PASSWORD")?)
+    /// #     .connect()
+    /// #     .await;
+    /// # skip_if_handshake_failed!(result, Ok(()));
+    /// # let mut client = result.unwrap();
+    /// let params = (0..10_000).map(|i| Params::from_iter([("id", i)]));
+    /// let summary = client
+    ///     .run_batched(
+    ///         "UNWIND $batch AS row CREATE (:Item {id: row.id});",
+    ///         params,
+    ///         1000,
+    ///         None,
+    ///         |progress| println!("{} rows inserted", progress.rows()),
+    ///     )
+    ///     .await?;
+    /// assert_eq!(summary.batches(), 10);
+    /// assert_eq!(summary.counters().get("nodes-created"), Some(&10_000));
+    /// # client.run("MATCH (n:Item) DELETE n;", None, None).await?;
+    /// # client.pull(None).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn run_batched(
+        &mut self,
+        query: impl Into<String>,
+        params: impl IntoIterator<Item = Params>,
+        batch_size: usize,
+        metadata: Option<Metadata>,
+        mut on_progress: impl FnMut(&BatchSummary),
+    ) -> Result<BatchSummary, TransactionError<CommunicationError>> {
+        let query = query.into();
+        let batch_size = batch_size.max(1);
+        let mut params = params.into_iter().peekable();
+        let mut summary = BatchSummary::default();
+
+        while params.peek().is_some() {
+            let batch: Vec<Value> = params
+                .by_ref()
+                .take(batch_size)
+                .map(|params| Value::Map(params.value))
+                .collect();
+            let rows = batch.len();
+            let batch = Params::from_iter([("batch", batch)]);
+
+            let response = self
+                .write_transaction(metadata.clone(), async |tx| {
+                    tx.run(query.as_str(), Some(batch.clone()), None).await?;
+                    let (_, response) = tx.pull(Some(Metadata::from_iter([("n", -1)]))).await?;
+                    Ok(response)
+                })
+                .await?;
+            summary.add(rows, &response);
+            on_progress(&summary);
+        }
+        Ok(summary)
+    }
+}
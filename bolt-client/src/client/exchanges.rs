@@ -0,0 +1,109 @@
+use std::{collections::VecDeque, time::SystemTime};
+
+use bolt_proto::{message::MessageKind, Message};
+
+/// A summary message received by a [`Client`](crate::Client), recorded along with the request it
+/// answered. See [`Client::recent_exchanges`](crate::Client::recent_exchanges).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exchange {
+    pub(crate) request: Option<MessageKind>,
+    pub(crate) response: Message,
+    pub(crate) received_at: SystemTime,
+}
+
+impl Exchange {
+    /// Get the kind of request the response answered, or [`None`] if no request was awaiting a
+    /// response.
+    pub fn request(&self) -> Option<MessageKind> {
+        self.request
+    }
+
+    /// Get the [`SUCCESS`](Message::Success), [`FAILURE`](Message::Failure), or
+    /// [`IGNORED`](Message::Ignored) message, with its metadata as the server sent it.
+    pub fn response(&self) -> &Message {
+        &self.response
+    }
+
+    /// Get the time at which the response was received.
+    pub fn received_at(&self) -> SystemTime {
+        self.received_at
+    }
+}
+
+/// A bounded log of the most recent exchanges, which records nothing with a capacity of 0.
+#[derive(Debug, Default)]
+pub(crate) struct ExchangeLog {
+    capacity: usize,
+    exchanges: VecDeque<Exchange>,
+}
+
+impl ExchangeLog {
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the capacity, dropping the oldest exchanges that no longer fit.
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.truncate();
+    }
+
+    /// Record a response to the given request, if it is a summary message.
+    pub(crate) fn record(&mut self, request: Option<&Message>, response: &Message) {
+        if self.capacity == 0 || matches!(response, Message::Record(_)) {
+            return;
+        }
+        self.exchanges.push_back(Exchange {
+            request: request.map(Message::kind),
+            response: response.clone(),
+            received_at: SystemTime::now(),
+        });
+        self.truncate();
+    }
+
+    pub(crate) fn iter(&self) -> impl ExactSizeIterator<Item = &Exchange> {
+        self.exchanges.iter()
+    }
+
+    fn truncate(&mut self) {
+        let excess = self.exchanges.len().saturating_sub(self.capacity);
+        self.exchanges.drain(..excess);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bolt_proto::message::{Record, Success};
+
+    use super::*;
+
+    #[test]
+    fn keeps_most_recent() {
+        let success = |n: i64| {
+            Message::Success(Success::new(
+                [(String::from("n"), n.into())].into_iter().collect(),
+            ))
+        };
+        let mut log = ExchangeLog::default();
+        log.record(Some(&Message::PullAll), &success(0));
+        assert_eq!(log.iter().len(), 0);
+
+        log.set_capacity(2);
+        log.record(
+            Some(&Message::PullAll),
+            &Message::Record(Record::new(vec![])),
+        );
+        for n in 1..=3 {
+            log.record(Some(&Message::PullAll), &success(n));
+        }
+        let responses: Vec<_> = log.iter().map(Exchange::response).cloned().collect();
+        assert_eq!(responses, [success(2), success(3)]);
+
+        log.set_capacity(1);
+        assert_eq!(log.iter().next().unwrap().response(), &success(3));
+        assert_eq!(
+            log.iter().next().unwrap().request(),
+            Some(MessageKind::PullAll)
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use std::io;
+
+use bolt_proto::{
+    error::{DeserializationError, DeserializeResult},
+    message::{LazyMessage, MessageDecoder},
+    Message,
+};
+use bytes::{Buf, Bytes, BytesMut};
+use futures_util::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const READ_SIZE: usize = 8 * 1024;
+
+/// Read the next message from a stream, calling `on_read` with the number of bytes read each time
+/// the stream is read from.
+///
+/// Bytes are handed to the decoder as soon as they are read, and any bytes past the end of the
+/// message are kept for the next call, so this is cancellation safe: if the returned future is
+/// dropped partway through a message, the next call picks up where it left off.
+pub(crate) async fn read_message(
+    stream: &mut (impl AsyncRead + Unpin),
+    decoder: &mut MessageDecoder,
+    buffer: &mut ReadBuffer,
+    on_read: impl FnMut(usize),
+) -> DeserializeResult<Message> {
+    read_with(stream, decoder, buffer, on_read, MessageDecoder::feed).await
+}
+
+/// Read the next message from a stream like [`read_message`], returning a `RECORD` as a
+/// [`LazyRecord`](bolt_proto::message::LazyRecord).
+pub(crate) async fn read_lazy_message(
+    stream: &mut (impl AsyncRead + Unpin),
+    decoder: &mut MessageDecoder,
+    buffer: &mut ReadBuffer,
+    on_read: impl FnMut(usize),
+) -> DeserializeResult<LazyMessage> {
+    read_with(stream, decoder, buffer, on_read, MessageDecoder::feed_lazy).await
+}
+
+async fn read_with<T>(
+    stream: &mut (impl AsyncRead + Unpin),
+    decoder: &mut MessageDecoder,
+    ReadBuffer(buf): &mut ReadBuffer,
+    mut on_read: impl FnMut(usize),
+    feed: impl Fn(&mut MessageDecoder, &[u8]) -> DeserializeResult<Option<T>>,
+) -> DeserializeResult<T> {
+    // Check for a message left over from the last read before reading more
+    if let Some(message) = feed(decoder, &[])? {
+        return Ok(message);
+    }
+    loop {
+        let read = stream.read(buf).await?;
+        if read == 0 {
+            return Err(DeserializationError::IoError(
+                io::ErrorKind::UnexpectedEof.into(),
+            ));
+        }
+        on_read(read);
+        if let Some(message) = feed(decoder, &buf[..read])? {
+            return Ok(message);
+        }
+    }
+}
+
+/// The buffer bytes are read into before they're handed to a [`MessageDecoder`]. It's kept next to
+/// the decoder so that it's only allocated once per connection, rather than once per message.
+#[derive(Debug)]
+pub(crate) struct ReadBuffer(Box<[u8]>);
+
+impl Default for ReadBuffer {
+    fn default() -> Self {
+        Self(vec![0; READ_SIZE].into_boxed_slice())
+    }
+}
+
+/// Return whether a message was rejected for exceeding the size limits of its decoder.
+pub(crate) fn exceeds_limit(error: &DeserializationError) -> bool {
+    match error {
+        DeserializationError::MessageTooLarge { .. }
+        | DeserializationError::ValueTooLarge { .. } => true,
+        DeserializationError::InvalidMessage { source, .. } => exceeds_limit(source),
+        _ => false,
+    }
+}
+
+/// The bytes of sent messages that have not been written to the stream yet.
+///
+/// A message is added here in full before any of it is written, so if a send is cancelled partway
+/// through writing a message, the rest of it is written by the next call to
+/// [`WriteBuffer::write_to`] instead of leaving a partial message on the stream.
+#[derive(Debug, Default)]
+pub(crate) struct WriteBuffer {
+    bytes: BytesMut,
+    unflushed: bool,
+}
+
+impl WriteBuffer {
+    /// Add the chunks of a message, returning the number of bytes added.
+    pub(crate) fn push(&mut self, chunks: &[Bytes]) -> usize {
+        let len = self.bytes.len();
+        for chunk in chunks {
+            self.bytes.extend_from_slice(chunk);
+        }
+        self.bytes.len() - len
+    }
+
+    /// Write and flush everything that hasn't been yet.
+    pub(crate) async fn write_to(
+        &mut self,
+        stream: &mut (impl AsyncWrite + Unpin),
+    ) -> io::Result<()> {
+        while !self.bytes.is_empty() {
+            let written = stream.write(&self.bytes).await?;
+            if written == 0 {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+            self.bytes.advance(written);
+            self.unflushed = true;
+        }
+        if self.unflushed {
+            stream.flush().await?;
+            self.unflushed = false;
+        }
+        Ok(())
+    }
+}
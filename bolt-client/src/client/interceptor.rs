@@ -0,0 +1,74 @@
+use std::fmt;
+
+use bolt_proto::Message;
+
+/// A hook into every message a [`Client`](crate::Client) sends and receives, added with
+/// [`Client::add_interceptor`](crate::Client::add_interceptor). Interceptors can observe messages,
+/// e.g. to collect metrics or log queries, or modify them, e.g. to add a `db` entry to every
+/// `RUN`.
+///
+/// Interceptors are called in the order they were added. Messages are intercepted before they are
+/// checked against the server state, so a modified message is handled as if it had been sent as
+/// is, and received messages are intercepted before they update the server state. Both halves of a
+/// [split](crate::Client::split) client share the same interceptors.
+///
+/// ```
+/// # use std::time::{Duration, Instant};
+/// # use bolt_client::Interceptor;
+/// # use bolt_proto::Message;
+/// /// Logs queries that take longer than a threshold to return their first response.
+/// struct SlowQueryLog {
+///     threshold: Duration,
+///     running: Option<(String, Instant)>,
+/// }
+///
+/// impl Interceptor for SlowQueryLog {
+///     fn before_send(&mut self, message: &mut Message) {
+///         if let Message::RunWithMetadata(run) = message {
+///             self.running = Some((run.statement().to_string(), Instant::now()));
+///         }
+///     }
+///
+///     fn after_receive(&mut self, _message: &mut Message) {
+///         if let Some((query, start)) = self.running.take() {
+///             if start.elapsed() > self.threshold {
+///                 eprintln!("slow query: {}", query);
+///             }
+///         }
+///     }
+/// }
+/// ```
+pub trait Interceptor: Send + Sync {
+    /// Called with each message before it is sent.
+    fn before_send(&mut self, message: &mut Message) {
+        let _ = message;
+    }
+
+    /// Called with each message received from the server, before it is returned.
+    fn after_receive(&mut self, message: &mut Message) {
+        let _ = message;
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Interceptors(pub(crate) Vec<Box<dyn Interceptor>>);
+
+impl Interceptors {
+    pub(crate) fn before_send(&mut self, message: &mut Message) {
+        for interceptor in &mut self.0 {
+            interceptor.before_send(message);
+        }
+    }
+
+    pub(crate) fn after_receive(&mut self, message: &mut Message) {
+        for interceptor in &mut self.0 {
+            interceptor.after_receive(message);
+        }
+    }
+}
+
+impl fmt::Debug for Interceptors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Interceptors({})", self.0.len())
+    }
+}
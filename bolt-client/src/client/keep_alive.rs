@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use bolt_proto::{version::*, Message, ServerState::*};
+use bytes::Bytes;
+use futures_util::io::{AsyncRead, AsyncWrite};
+
+use crate::{
+    error::{CommunicationError, CommunicationResult},
+    Client,
+};
+
+// An empty chunk, which servers skip over when reading messages
+const NOOP: [u8; 2] = [0, 0];
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
+    /// Get the time since the client last sent or received anything, including keepalives.
+    pub fn idle_time(&self) -> Duration {
+        self.session.stats.idle_time()
+    }
+
+    /// Send a keepalive to the server, so that the connection isn't considered idle by the
+    /// network in between, e.g. by a NAT gateway or firewall that drops idle connections.
+    ///
+    /// For Bolt v4.1+, this is a `NOOP` chunk, which the server skips over without responding.
+    /// This can be sent in any server state. For older versions, a [`RESET`](Message::Reset) is
+    /// sent instead and its response is read, but only in the
+    /// [`Ready`](bolt_proto::ServerState::Ready) state with no responses pending, where it leaves
+    /// the server state unchanged. In other states, nothing is sent, so that keepalives never
+    /// interrupt a query or transaction. If the future is dropped after sending the `RESET`, e.g.
+    /// by [`Client::keep_alive_while_idle`] losing a race, its response is read before the next
+    /// request is sent.
+    pub async fn keep_alive(&mut self) -> CommunicationResult<()> {
+        match self.version {
+            V1_0 | V2_0 | V3_0 | V4_0 => {
+                if self.session.server_state != Ready || !self.session.sent_queue.is_empty() {
+                    return Ok(());
+                }
+                match self.reset().await? {
+                    Message::Success(_) => Ok(()),
+                    response => Err(CommunicationError::InvalidResponse {
+                        state: self.session.server_state,
+                        request: Some(Message::Reset),
+                        response,
+                    }),
+                }
+            }
+            _ => {
+                let bytes_sent = self.write_buffer.push(&[Bytes::from_static(&NOOP)]);
+                self.session.stats.sent_noop(bytes_sent);
+                self.finish_write().await
+            }
+        }
+    }
+
+    /// Keep the connection alive while it isn't otherwise used, sending a
+    /// [keepalive](Client::keep_alive) whenever the client has been [idle](Client::idle_time) for
+    /// `interval`. This only returns if sending a keepalive fails.
+    ///
+    /// Since this borrows the client mutably, keepalives can't be interleaved with other requests.
+    /// To use the client again, drop this future, e.g. by racing it against incoming work:
+    /// ```
+    /// # use std::{env, time::Duration};
+    /// # use bolt_client::*;
+    /// # use bolt_proto::version::*;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let result = ClientBuilder::new(env::var("BOLT_TEST_ADDR")?)
+    /// #     .with_domain(env::var("BOLT_TEST_DOMAIN").ok())
+    /// #     .with_versions([V4_4, V4_3, V4_2, V4_1])
+    /// #     .with_basic_auth(env::var("BOLT_TEST_USERNAME")?, env::var("BOLT_TEST_PASSWORD")?)
+    /// #     .connect()
+    /// #     .await;
+    /// # skip_if_handshake_failed!(result, Ok(()));
+    /// # let mut client = result.unwrap();
+    /// let (sender, mut receiver) = tokio::sync::mpsc::channel::<String>(1);
+    /// # drop(sender);
+    /// loop {
+    ///     let query = tokio::select! {
+    ///         query = receiver.recv() => query,
+    ///         result = client.keep_alive_while_idle(Duration::from_secs(60)) => {
+    ///             return Err(result.unwrap_err().into());
+    ///         }
+    ///     };
+    ///     let Some(query) = query else { break };
+    ///     client.run(query, None, None).await?;
+    ///     client.pull(None).await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn keep_alive_while_idle(
+        &mut self,
+        interval: Duration,
+    ) -> CommunicationResult<std::convert::Infallible> {
+        loop {
+            let idle_time = self.idle_time();
+            if idle_time < interval {
+                self.timer.sleep(interval - idle_time).await;
+            } else {
+                self.keep_alive().await?;
+                // Older versions only send keepalives when ready, so don't retry immediately
+                if self.idle_time() >= interval {
+                    self.timer.sleep(interval).await;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use std::{collections::HashMap, io};
+
+use bolt_proto::{version::*, Value};
+
+use crate::{error::CommunicationResult, notifications::NOTIFICATION_KEYS, Feature};
+
+/// The kind of message that metadata is sent with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MetadataTarget {
+    Run,
+    Begin,
+    Route,
+}
+
+/// Metadata arranged in the form expected by a particular protocol version.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ShapedMetadata {
+    /// The entries to send in the message's metadata map. Unsupported entries are kept, since
+    /// servers ignore them.
+    pub(crate) metadata: HashMap<String, Value>,
+    /// The database, for messages that send it as a separate field instead of in the metadata.
+    pub(crate) database: Option<String>,
+    /// The keys not supported by the version, in sorted order.
+    pub(crate) unsupported: Vec<String>,
+}
+
+/// Get the metadata keys supported by a message for a version.
+fn supported_keys(version: u32, target: MetadataTarget) -> &'static [&'static str] {
+    match (target, version) {
+        (MetadataTarget::Run | MetadataTarget::Begin, V1_0 | V2_0) => &[],
+        (MetadataTarget::Run | MetadataTarget::Begin, V3_0) => {
+            &["bookmarks", "tx_timeout", "tx_metadata", "mode"]
+        }
+        (MetadataTarget::Run | MetadataTarget::Begin, V4_0 | V4_1 | V4_2 | V4_3) => {
+            &["bookmarks", "tx_timeout", "tx_metadata", "mode", "db"]
+        }
+        (MetadataTarget::Run | MetadataTarget::Begin, _) => &[
+            "bookmarks",
+            "tx_timeout",
+            "tx_metadata",
+            "mode",
+            "db",
+            "imp_user",
+        ],
+        (MetadataTarget::Route, V1_0 | V2_0 | V3_0 | V4_0 | V4_1 | V4_2) => &[],
+        (MetadataTarget::Route, V4_3) => &["db"],
+        (MetadataTarget::Route, _) => &["db", "imp_user"],
+    }
+}
+
+/// Get the keys in the metadata that are not supported by a message for a version, in sorted
+/// order.
+fn unsupported_keys(
+    version: u32,
+    target: MetadataTarget,
+    metadata: &HashMap<String, Value>,
+) -> Vec<String> {
+    let supported = supported_keys(version, target);
+    let mut keys: Vec<String> = metadata
+        .keys()
+        .filter(|key| !supported.contains(&key.as_str()))
+        .cloned()
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// Arrange metadata for a message in the form expected by a version.
+///
+/// - Bolt v1 - v2 have no `RUN` metadata, so all entries are dropped.
+/// - `ROUTE` in Bolt v4.3 has no metadata map, so `db` is moved to [`ShapedMetadata::database`]
+///   and other entries are dropped.
+/// - `db` and `imp_user` must be strings or [`null`](Value::Null) wherever they are supported.
+/// - Notification filtering entries are always dropped, see [`remove_notification_config`].
+pub(crate) fn shape_metadata(
+    version: u32,
+    target: MetadataTarget,
+    mut metadata: HashMap<String, Value>,
+) -> CommunicationResult<ShapedMetadata> {
+    remove_notification_config(version, &mut metadata);
+    let unsupported = unsupported_keys(version, target, &metadata);
+    for key in ["db", "imp_user"] {
+        if metadata.contains_key(key) && !unsupported.iter().any(|k| k == key) {
+            check_string_or_null(key, &metadata[key])?;
+        }
+    }
+
+    match (target, version) {
+        (MetadataTarget::Run, V1_0 | V2_0) => Ok(ShapedMetadata {
+            unsupported,
+            ..Default::default()
+        }),
+        (MetadataTarget::Route, V4_3) => {
+            let database = match metadata.remove("db") {
+                Some(Value::String(string)) => Some(string),
+                _ => None,
+            };
+            Ok(ShapedMetadata {
+                metadata: HashMap::new(),
+                database,
+                unsupported,
+            })
+        }
+        _ => Ok(ShapedMetadata {
+            metadata,
+            database: None,
+            unsupported,
+        }),
+    }
+}
+
+/// Remove the notification filtering entries from metadata for versions that don't support them.
+/// These were added in Bolt v5.2, which is newer than any version supported by this crate, so the
+/// entries are currently removed for every version. Since older servers send all notifications,
+/// dropping the filter is harmless, and the entries aren't reported as unsupported.
+pub(crate) fn remove_notification_config(version: u32, metadata: &mut HashMap<String, Value>) {
+    if Feature::NotificationFiltering.is_supported_by(version) {
+        return;
+    }
+    for key in NOTIFICATION_KEYS {
+        metadata.remove(key);
+    }
+}
+
+/// Fill in the `n` entry of `PULL` or `DISCARD` metadata with `-1` (all records) if it's missing,
+/// since the server requires it.
+pub(crate) fn default_n(mut metadata: HashMap<String, Value>) -> HashMap<String, Value> {
+    metadata
+        .entry(String::from("n"))
+        .or_insert_with(|| Value::from(-1));
+    metadata
+}
+
+fn check_string_or_null(key: &str, value: &Value) -> CommunicationResult<()> {
+    match value {
+        Value::String(_) | Value::Null => Ok(()),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} must be either a string or null", key),
+        )
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::CommunicationError, Metadata, NotificationCategory, NotificationSeverity};
+
+    use super::*;
+
+    const ALL_VERSIONS: [u32; 8] = [V1_0, V2_0, V3_0, V4_0, V4_1, V4_2, V4_3, V4_4];
+
+    fn all_keys() -> HashMap<String, Value> {
+        Metadata::from_iter(vec![
+            ("bookmarks", Value::from(vec!["bookmark:1"])),
+            ("tx_timeout", Value::from(1000)),
+            ("tx_metadata", Value::from(HashMap::<String, Value>::new())),
+            ("mode", Value::from("r")),
+            ("db", Value::from("neo4j")),
+            ("imp_user", Value::from("alice")),
+            ("unknown", Value::from(true)),
+        ])
+        .value
+    }
+
+    fn keys(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|key| key.to_string()).collect()
+    }
+
+    #[test]
+    fn default_n() {
+        assert_eq!(
+            super::default_n(HashMap::new()),
+            Metadata::from_iter([("n", -1)]).value
+        );
+        let metadata = Metadata::from_iter([("n", 10), ("qid", 1)]).value;
+        assert_eq!(super::default_n(metadata.clone()), metadata);
+        assert_eq!(
+            super::default_n(Metadata::from_iter([("qid", 1)]).value),
+            Metadata::from_iter([("n", -1), ("qid", 1)]).value
+        );
+    }
+
+    #[test]
+    fn run() {
+        for version in ALL_VERSIONS {
+            let shaped = shape_metadata(version, MetadataTarget::Run, all_keys()).unwrap();
+            let expected_unsupported = match version {
+                V1_0 | V2_0 => keys(&[
+                    "bookmarks",
+                    "db",
+                    "imp_user",
+                    "mode",
+                    "tx_metadata",
+                    "tx_timeout",
+                    "unknown",
+                ]),
+                V3_0 => keys(&["db", "imp_user", "unknown"]),
+                V4_0 | V4_1 | V4_2 | V4_3 => keys(&["imp_user", "unknown"]),
+                _ => keys(&["unknown"]),
+            };
+            assert_eq!(shaped.unsupported, expected_unsupported, "{:#x}", version);
+            assert_eq!(shaped.database, None);
+            match version {
+                V1_0 | V2_0 => assert!(shaped.metadata.is_empty()),
+                _ => assert_eq!(shaped.metadata, all_keys(), "{:#x}", version),
+            }
+        }
+    }
+
+    #[test]
+    fn begin() {
+        for version in ALL_VERSIONS {
+            let shaped = shape_metadata(version, MetadataTarget::Begin, all_keys()).unwrap();
+            let expected_unsupported = match version {
+                V1_0 | V2_0 => keys(&[
+                    "bookmarks",
+                    "db",
+                    "imp_user",
+                    "mode",
+                    "tx_metadata",
+                    "tx_timeout",
+                    "unknown",
+                ]),
+                V3_0 => keys(&["db", "imp_user", "unknown"]),
+                V4_0 | V4_1 | V4_2 | V4_3 => keys(&["imp_user", "unknown"]),
+                _ => keys(&["unknown"]),
+            };
+            assert_eq!(shaped.unsupported, expected_unsupported, "{:#x}", version);
+            assert_eq!(shaped.metadata, all_keys(), "{:#x}", version);
+            assert_eq!(shaped.database, None);
+        }
+    }
+
+    #[test]
+    fn route() {
+        for version in ALL_VERSIONS {
+            let shaped = shape_metadata(version, MetadataTarget::Route, all_keys()).unwrap();
+            match version {
+                V4_3 => {
+                    assert_eq!(
+                        shaped.unsupported,
+                        keys(&[
+                            "bookmarks",
+                            "imp_user",
+                            "mode",
+                            "tx_metadata",
+                            "tx_timeout",
+                            "unknown"
+                        ])
+                    );
+                    assert!(shaped.metadata.is_empty());
+                    assert_eq!(shaped.database, Some(String::from("neo4j")));
+                }
+                V4_4 => {
+                    assert_eq!(
+                        shaped.unsupported,
+                        keys(&["bookmarks", "mode", "tx_metadata", "tx_timeout", "unknown"])
+                    );
+                    assert_eq!(shaped.metadata, all_keys());
+                    assert_eq!(shaped.database, None);
+                }
+                _ => assert_eq!(shaped.unsupported.len(), all_keys().len(), "{:#x}", version),
+            }
+        }
+    }
+
+    #[test]
+    fn route_null_database() {
+        let metadata = Metadata::from_iter(vec![("db", Value::Null)]).value;
+        let shaped = shape_metadata(V4_3, MetadataTarget::Route, metadata).unwrap();
+        assert!(shaped.unsupported.is_empty());
+        assert_eq!(shaped.database, None);
+    }
+
+    #[test]
+    fn notification_config_removed() {
+        let notification_config = Metadata::default()
+            .with_notifications_minimum_severity(NotificationSeverity::Off)
+            .with_notifications_disabled_categories([NotificationCategory::Hint])
+            .value;
+        for version in ALL_VERSIONS {
+            for target in [MetadataTarget::Run, MetadataTarget::Begin] {
+                let mut metadata = all_keys();
+                metadata.extend(notification_config.clone());
+                let shaped = shape_metadata(version, target, metadata).unwrap();
+                assert!(NOTIFICATION_KEYS
+                    .iter()
+                    .all(|key| !shaped.metadata.contains_key(*key)));
+                assert!(!shaped
+                    .unsupported
+                    .iter()
+                    .any(|key| key.starts_with("notifications")));
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_string_entries() {
+        for (version, target) in [
+            (V4_0, MetadataTarget::Run),
+            (V4_3, MetadataTarget::Route),
+            (V4_4, MetadataTarget::Begin),
+            (V4_4, MetadataTarget::Route),
+        ] {
+            let metadata = Metadata::from_iter(vec![("db", 1)]).value;
+            assert!(matches!(
+                shape_metadata(version, target, metadata),
+                Err(CommunicationError::IoError(err)) if err.kind() == io::ErrorKind::InvalidInput
+            ));
+        }
+
+        let metadata = Metadata::from_iter(vec![("imp_user", 1)]).value;
+        assert!(shape_metadata(V4_4, MetadataTarget::Run, metadata.clone()).is_err());
+        // Unsupported entries are not checked
+        assert!(shape_metadata(V4_3, MetadataTarget::Run, metadata).is_ok());
+    }
+}
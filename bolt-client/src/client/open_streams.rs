@@ -0,0 +1,93 @@
+use bolt_proto::{message::Success, Value};
+
+/// The result streams that are open in an explicit transaction, identified by the query ID (qid)
+/// the server assigned to each `RUN` message.
+///
+/// `PULL` and `DISCARD` messages without a qid, or with a qid of -1, refer to the most recent
+/// query. Servers using Bolt v3 do not assign query IDs, so only one stream is tracked at a time.
+#[derive(Debug, Default)]
+pub(crate) struct OpenStreams {
+    qids: Vec<Option<i64>>,
+    last: Option<i64>,
+}
+
+impl OpenStreams {
+    /// Record a stream opened by a `RUN` message, using the qid from its `SUCCESS` response.
+    pub(crate) fn opened(&mut self, success: &Success) {
+        let qid = match success.metadata().get("qid") {
+            Some(&Value::Integer(qid)) => Some(qid),
+            _ => None,
+        };
+        self.qids.push(qid);
+        self.last = qid;
+    }
+
+    /// Record that the stream with the given qid has been fully consumed.
+    pub(crate) fn closed(&mut self, qid: Option<&Value>) {
+        let qid = match qid {
+            Some(&Value::Integer(qid)) if qid >= 0 => Some(qid),
+            _ => self.last,
+        };
+        if let Some(index) = self.qids.iter().position(|&open| open == qid) {
+            self.qids.remove(index);
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.qids.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.qids.is_empty()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.qids.clear();
+        self.last = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn run_success(qid: Option<i64>) -> Success {
+        let mut metadata = HashMap::new();
+        if let Some(qid) = qid {
+            metadata.insert(String::from("qid"), Value::from(qid));
+        }
+        Success::new(metadata)
+    }
+
+    #[test]
+    fn close_by_qid() {
+        let mut streams = OpenStreams::default();
+        streams.opened(&run_success(Some(0)));
+        streams.opened(&run_success(Some(1)));
+        streams.opened(&run_success(Some(2)));
+        assert_eq!(streams.len(), 3);
+
+        streams.closed(Some(&Value::from(1)));
+        assert_eq!(streams.qids, vec![Some(0), Some(2)]);
+        // Closing a stream that is not open has no effect
+        streams.closed(Some(&Value::from(1)));
+        assert_eq!(streams.len(), 2);
+        // -1 refers to the most recent query
+        streams.closed(Some(&Value::from(-1)));
+        assert_eq!(streams.qids, vec![Some(0)]);
+        streams.closed(None);
+        assert_eq!(streams.len(), 1);
+        streams.closed(Some(&Value::from(0)));
+        assert!(streams.is_empty());
+    }
+
+    #[test]
+    fn close_without_qids() {
+        let mut streams = OpenStreams::default();
+        streams.opened(&run_success(None));
+        streams.closed(None);
+        assert!(streams.is_empty());
+    }
+}
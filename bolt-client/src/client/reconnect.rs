@@ -0,0 +1,82 @@
+use std::{io, sync::Arc};
+
+use bolt_proto::version::*;
+use tokio::io::BufStream;
+use tokio_util::compat::Compat;
+
+use crate::{
+    error::{ConnectionError, Result},
+    Client, ClientBuilder, Stream,
+};
+
+use super::Session;
+
+impl Client<Compat<BufStream<Stream>>> {
+    /// Get the builder this client was created with, if it was created with
+    /// [`ClientBuilder::connect`].
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio-stream")))]
+    pub fn builder(&self) -> Option<&ClientBuilder> {
+        self.builder.as_deref()
+    }
+
+    pub(crate) fn set_builder(&mut self, builder: Arc<ClientBuilder>) {
+        self.builder = Some(builder);
+    }
+
+    /// Replace the connection of this client with a new one, opened with the
+    /// [builder](Client::builder) the client was created with. This is useful once the connection
+    /// has become [`Defunct`](bolt_proto::ServerState::Defunct), e.g. after a network error.
+    ///
+    /// The new connection performs the handshake and sends `HELLO` like [`ClientBuilder::connect`]
+    /// does, so the client is left in the [`Ready`](bolt_proto::ServerState::Ready) state, with
+    /// fresh [stats](Client::stats). The client's settings, [state change
+    /// hook](Client::on_state_change), [interceptors](Client::add_interceptor), and [last
+    /// bookmark](Client::last_bookmark) are kept. The old connection is dropped without sending
+    /// `GOODBYE`.
+    ///
+    /// If the client was not created with a builder, an
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) error is returned. If connecting fails, the
+    /// client is left unchanged.
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio-stream")))]
+    pub async fn reconnect(&mut self) -> Result<()> {
+        let builder = self.builder.as_ref().ok_or_else(|| {
+            ConnectionError::from(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "client was not created with a ClientBuilder",
+            ))
+        })?;
+        let mut client = ClientBuilder::connect_shared(builder).await?;
+
+        let Session {
+            last_bookmark,
+            bookmark_manager,
+            state_change_hook,
+            interceptors,
+            ..
+        } = &mut self.session;
+        client.session.last_bookmark = last_bookmark.take();
+        client.session.bookmark_manager = bookmark_manager.take();
+        client.session.state_change_hook = state_change_hook.take();
+        client.session.interceptors = std::mem::take(interceptors);
+
+        let previous = self.session.server_state;
+        self.stream = client.stream;
+        self.decoder = client
+            .decoder
+            .with_utf8_mode(self.decoder.utf8_mode())
+            .with_max_message_size(self.decoder.max_message_size())
+            .with_max_value_size(self.decoder.max_value_size())
+            .with_float_arrays(self.decoder.float_arrays());
+        self.write_buffer = client.write_buffer;
+        self.version = client.version;
+        self.handshake = client.handshake;
+        self.session = client.session;
+        let request = match self.version {
+            V1_0 | V2_0 => "INIT",
+            _ => "HELLO",
+        };
+        self.session
+            .notify_state_change(previous, Some(request), Some("SUCCESS"));
+        Ok(())
+    }
+}
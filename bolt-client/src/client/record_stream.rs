@@ -0,0 +1,151 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bolt_proto::{
+    message::{Pull, Record},
+    Message, Value,
+};
+use futures_util::{
+    future::BoxFuture,
+    io::{AsyncRead, AsyncWrite},
+    stream::{FusedStream, Stream},
+    FutureExt,
+};
+
+use crate::{error::CommunicationResult, Client, Feature, Metadata, StreamSummary};
+
+type Response<'a, S> = (&'a mut Client<S>, CommunicationResult<Message>);
+
+enum State<'a, S: AsyncRead + AsyncWrite + Unpin> {
+    Idle {
+        client: &'a mut Client<S>,
+        pull: bool,
+    },
+    Reading(BoxFuture<'a, Response<'a, S>>),
+    Done,
+}
+
+/// A [`Stream`] of the records in a result, created by [`Client::pull_stream`].
+///
+/// Records are read from the connection only as the stream is polled. For Bolt v4+, a new `PULL`
+/// message requesting the next batch of records is only sent once the consumer polls past the end
+/// of the previous batch, so a slow consumer slows down the server instead of letting records pile
+/// up in the connection's buffers.
+///
+/// The stream ends once the server sends a summary message, which is then available from
+/// [`RecordStream::summary`]. As with [`Client::pull`], records received before a
+/// [`FAILURE`](Message::Failure) summary should be considered invalid.
+pub struct RecordStream<'a, S: AsyncRead + AsyncWrite + Unpin> {
+    state: State<'a, S>,
+    metadata: Metadata,
+    summary: Option<Message>,
+}
+
+impl<'a, S: AsyncRead + AsyncWrite + Unpin + Send> RecordStream<'a, S> {
+    pub(crate) fn new(client: &'a mut Client<S>, metadata: Metadata) -> Self {
+        // Keep reading the current batch if a previous stream was dropped before consuming it
+        let pull = !client
+            .session
+            .sent_queue
+            .iter()
+            .any(|message| matches!(message, Message::Pull(_) | Message::PullAll));
+        Self {
+            state: State::Idle { client, pull },
+            metadata,
+            summary: None,
+        }
+    }
+
+    /// Get the summary message the server sent at the end of the result, if the stream has ended.
+    /// This is [`None`] if the stream ended with an error.
+    pub fn summary(&self) -> Option<&Message> {
+        self.summary.as_ref()
+    }
+
+    /// Get the [`StreamSummary`] of the result, if the stream has ended with a
+    /// [`SUCCESS`](Message::Success) message.
+    pub fn stream_summary(&self) -> Option<StreamSummary> {
+        match &self.summary {
+            Some(Message::Success(success)) => Some(StreamSummary::from_success(success)),
+            _ => None,
+        }
+    }
+
+    /// Consume the stream, returning the summary message the server sent at the end of the result,
+    /// if the stream has ended.
+    pub fn into_summary(self) -> Option<Message> {
+        self.summary
+    }
+}
+
+fn read_next<S: AsyncRead + AsyncWrite + Unpin + Send>(
+    client: &mut Client<S>,
+    pull: Option<Message>,
+) -> BoxFuture<'_, Response<'_, S>> {
+    async move {
+        let result = async {
+            if let Some(pull) = pull {
+                client.send_message(pull).await?;
+            }
+            client.read_message().await
+        }
+        .await;
+        (client, result)
+    }
+    .boxed()
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> Stream for RecordStream<'_, S> {
+    type Item = CommunicationResult<Record>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            match std::mem::replace(&mut this.state, State::Done) {
+                State::Idle { client, pull } => {
+                    let pull = pull.then(|| match client.supports(Feature::PullN) {
+                        true => Message::Pull(Pull::new(this.metadata.value.clone())),
+                        false => Message::PullAll,
+                    });
+                    this.state = State::Reading(read_next(client, pull));
+                }
+                State::Reading(mut future) => match future.poll_unpin(cx) {
+                    Poll::Pending => {
+                        this.state = State::Reading(future);
+                        return Poll::Pending;
+                    }
+                    Poll::Ready((client, result)) => match result {
+                        Ok(Message::Record(record)) => {
+                            this.state = State::Idle {
+                                client,
+                                pull: false,
+                            };
+                            return Poll::Ready(Some(Ok(record)));
+                        }
+                        Ok(Message::Success(success))
+                            if success.metadata().get("has_more")
+                                == Some(&Value::Boolean(true)) =>
+                        {
+                            // The consumer is waiting for the next record, so request another batch
+                            this.state = State::Idle { client, pull: true };
+                        }
+                        Ok(summary) => {
+                            this.summary = Some(summary);
+                            return Poll::Ready(None);
+                        }
+                        Err(error) => return Poll::Ready(Some(Err(error))),
+                    },
+                },
+                State::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> FusedStream for RecordStream<'_, S> {
+    fn is_terminated(&self) -> bool {
+        matches!(self.state, State::Done)
+    }
+}
@@ -0,0 +1,406 @@
+use std::{collections::VecDeque, io, sync::Arc};
+
+use bolt_proto::{
+    error::{DeserializationError, Error as ProtocolError},
+    message::{Failure, LazyRecord, MessageKind, Record, Success},
+    version::*,
+    Message, ServerState,
+    ServerState::*,
+    Value,
+};
+
+use crate::{
+    error::{CommunicationError, CommunicationResult},
+    Bookmark, BookmarkManager, Bookmarks, Feature, QueryHeader,
+};
+
+use super::{
+    exchanges::ExchangeLog, interceptor::Interceptors, open_streams::OpenStreams,
+    state_change::StateChangeHook, StateChange, StatsTracker,
+};
+
+/// The protocol state of a connection: the state of the server, the requests awaiting a response,
+/// and everything learned from the responses so far. This is kept apart from the stream so that it
+/// can be shared by the two halves of a split client.
+#[derive(Debug)]
+pub(crate) struct Session {
+    pub(crate) server_state: ServerState,
+    pub(crate) sent_queue: VecDeque<Message>,
+    pub(crate) open_tx_streams: OpenStreams,
+    pub(crate) server_agent: Option<String>,
+    pub(crate) connection_id: Option<String>,
+    pub(crate) last_bookmark: Option<String>,
+    pub(crate) last_fields: Option<Arc<[String]>>,
+    pub(crate) last_query_header: Option<QueryHeader>,
+    pub(crate) bookmark_manager: Option<BookmarkManager>,
+    // The bookmarks the current explicit transaction was started with
+    pub(crate) tx_bookmarks: Bookmarks,
+    pub(crate) last_failure: Option<Failure>,
+    pub(crate) stats: StatsTracker,
+    pub(crate) state_change_hook: Option<StateChangeHook>,
+    pub(crate) interceptors: Interceptors,
+    pub(crate) exchanges: ExchangeLog,
+}
+
+impl Session {
+    pub(crate) fn new(stats: StatsTracker) -> Self {
+        Self {
+            server_state: Connected,
+            sent_queue: VecDeque::default(),
+            open_tx_streams: OpenStreams::default(),
+            server_agent: None,
+            connection_id: None,
+            last_bookmark: None,
+            last_fields: None,
+            last_query_header: None,
+            bookmark_manager: None,
+            tx_bookmarks: Bookmarks::new(),
+            last_failure: None,
+            stats,
+            state_change_hook: None,
+            interceptors: Interceptors::default(),
+            exchanges: ExchangeLog::default(),
+        }
+    }
+
+    pub(crate) fn notify_state_change(
+        &mut self,
+        previous: ServerState,
+        request: Option<&'static str>,
+        response: Option<&'static str>,
+    ) {
+        if previous == self.server_state {
+            return;
+        }
+
+        if let Some(StateChangeHook(hook)) = &mut self.state_change_hook {
+            hook(&StateChange {
+                previous,
+                current: self.server_state,
+                request,
+                response,
+            });
+        }
+    }
+
+    fn store_connection_info(&mut self, success: &Success) {
+        let get_string = |key| match success.metadata().get(key) {
+            Some(Value::String(string)) => Some(string.clone()),
+            _ => None,
+        };
+        self.server_agent = get_string("server");
+        self.connection_id = get_string("connection_id");
+    }
+
+    // A committed transaction's bookmark replaces the ones it started with, while the bookmark of an
+    // auto-commit transaction is added to the others
+    fn update_bookmark_manager(&mut self, bookmark: &str) {
+        let (Some(manager), Ok(bookmark)) = (&self.bookmark_manager, Bookmark::parse(bookmark))
+        else {
+            return;
+        };
+        let used = match self.sent_queue.front() {
+            Some(Message::Commit) => std::mem::take(&mut self.tx_bookmarks),
+            _ => Bookmarks::new(),
+        };
+        manager.update(&used, bookmark);
+    }
+
+    fn store_query_header(&mut self, success: &Success) {
+        let header = QueryHeader::from_success(success);
+        if success.metadata().contains_key("fields") {
+            self.last_fields = Some(header.fields().into());
+        }
+        self.last_query_header = Some(header);
+    }
+
+    // Mark the connection as unusable after the server closed it, e.g. after a FAILURE in
+    // response to HELLO or an idle timeout.
+    pub(crate) fn close_by_server(
+        &mut self,
+        during: MessageKind,
+        request: &'static str,
+    ) -> CommunicationError {
+        let previous = self.server_state;
+        self.server_state = Defunct;
+        self.sent_queue.clear();
+        self.open_tx_streams.clear();
+        self.notify_state_change(previous, Some(request), None);
+        CommunicationError::ServerClosedConnection { during }
+    }
+
+    /// Mark the connection as unusable after a response exceeded the client's size limits. The
+    /// rest of the response may still be waiting on the stream, so nothing read from it afterwards
+    /// could be trusted.
+    pub(crate) fn exceeded_limit(&mut self, error: DeserializationError) -> CommunicationError {
+        let previous = self.server_state;
+        let request = self.sent_queue.front().map(Message::name);
+        self.server_state = Defunct;
+        self.sent_queue.clear();
+        self.open_tx_streams.clear();
+        self.notify_state_change(previous, request, None);
+        ProtocolError::from(error).into()
+    }
+
+    /// Check that a request can be sent while the server is in the given state. If it can't, the
+    /// connection is marked as unusable.
+    pub(crate) fn check_request(
+        &mut self,
+        state: ServerState,
+        message: &Message,
+    ) -> CommunicationResult<()> {
+        if accepts(state, message) {
+            return Ok(());
+        }
+
+        let previous = self.server_state;
+        self.server_state = Defunct;
+        self.notify_state_change(previous, Some(message.name()), None);
+        Err(CommunicationError::InvalidState {
+            state,
+            message: message.clone(),
+        })
+    }
+
+    /// Record a request that has been sent, to be matched up with its response.
+    pub(crate) fn sent(&mut self, message: Message) {
+        // Immediate state changes
+        let previous = self.server_state;
+        match message {
+            Message::Reset => self.server_state = Interrupted,
+            Message::Goodbye => self.server_state = Disconnected,
+            _ => {}
+        }
+        self.notify_state_change(previous, Some(message.name()), None);
+
+        self.sent_queue.push_back(message);
+    }
+
+    /// Handle a response from the server, updating the server state accordingly.
+    pub(crate) fn received(&mut self, mut message: Message) -> CommunicationResult<Message> {
+        self.stats.received(matches!(message, Message::Record(_)));
+        self.interceptors.after_receive(&mut message);
+
+        #[cfg(test)]
+        println!("<<< {:?}\n", message);
+
+        match &message {
+            Message::Success(success) => {
+                if let Some(Value::String(bookmark)) = success.metadata().get("bookmark") {
+                    self.last_bookmark = Some(bookmark.clone());
+                    self.update_bookmark_manager(bookmark);
+                }
+                if let Some(Message::Run(_) | Message::RunWithMetadata(_)) = self.sent_queue.front()
+                {
+                    self.store_query_header(success);
+                }
+            }
+            Message::Failure(failure) => self.last_failure = Some(failure.clone()),
+            _ => {}
+        }
+        self.exchanges.record(self.sent_queue.front(), &message);
+
+        let previous = self.server_state;
+        let request = self.sent_queue.front().map(Message::name);
+        let response = message.name();
+        let result = self.handle_response(message);
+        self.notify_state_change(previous, request, Some(response));
+        result
+    }
+
+    /// Handle a `RECORD` whose fields have not been deserialized, updating the server state like
+    /// [`Session::received`]. Returns [`None`] if the record was ignored because of a `RESET`.
+    /// Interceptors are not called, since they need the deserialized message.
+    pub(crate) fn received_lazy(
+        &mut self,
+        record: LazyRecord,
+    ) -> CommunicationResult<Option<LazyRecord>> {
+        self.stats.received(true);
+
+        #[cfg(test)]
+        println!("<<< {:?}\n", record);
+
+        // The state changes don't depend on the fields, so a placeholder stands in for the record
+        let previous = self.server_state;
+        let request = self.sent_queue.front().map(Message::name);
+        let result = self.handle_response(Message::Record(Record::new(Vec::new())));
+        self.notify_state_change(previous, request, Some("RECORD"));
+        match result? {
+            Message::Ignored => Ok(None),
+            _ => Ok(Some(record)),
+        }
+    }
+
+    /// Handle the server closing the connection while a response was being read.
+    pub(crate) fn closed_while_reading(&mut self, error: io::Error) -> CommunicationError {
+        match self.sent_queue.front() {
+            Some(request) => {
+                let (during, request) = (request.kind(), request.name());
+                self.close_by_server(during, request)
+            }
+            None => CommunicationError::from(error),
+        }
+    }
+
+    /// Handle the server closing the connection while requests were being written.
+    pub(crate) fn closed_while_writing(&mut self, error: io::Error) -> CommunicationError {
+        match self.sent_queue.back() {
+            Some(request) => {
+                let (during, request) = (request.kind(), request.name());
+                self.close_by_server(during, request)
+            }
+            None => CommunicationError::from(error),
+        }
+    }
+
+    /// Get the state the server will be in once it has successfully processed every request
+    /// awaiting a response. `PULL` and `DISCARD` requests are only assumed to finish their result
+    /// if they ask for all remaining records, with an `n` of -1.
+    pub(crate) fn expected_state(&self) -> ServerState {
+        let mut open_tx_streams = self.open_tx_streams.len();
+        let mut state = self.server_state;
+        for message in &self.sent_queue {
+            let consumes_all = match message {
+                Message::PullAll | Message::DiscardAll => true,
+                Message::Pull(pull) => pull.metadata().get("n") == Some(&Value::Integer(-1)),
+                Message::Discard(discard) => {
+                    discard.metadata().get("n") == Some(&Value::Integer(-1))
+                }
+                _ => false,
+            };
+
+            state = match (state, message) {
+                (Connected, Message::Init(_) | Message::Hello(_)) => Ready,
+                (Ready, Message::Run(_) | Message::RunWithMetadata(_)) => Streaming,
+                (Ready, Message::Begin(_)) => TxReady,
+                (Streaming, _) if consumes_all => Ready,
+                (TxReady | TxStreaming, Message::RunWithMetadata(_)) => {
+                    open_tx_streams += 1;
+                    TxStreaming
+                }
+                (TxStreaming, _) if consumes_all => {
+                    open_tx_streams = open_tx_streams.saturating_sub(1);
+                    if open_tx_streams > 0 {
+                        TxStreaming
+                    } else {
+                        TxReady
+                    }
+                }
+                (TxReady, Message::Commit | Message::Rollback) => Ready,
+                (Failed, Message::AckFailure) => Ready,
+                (_, Message::Reset) => {
+                    open_tx_streams = 0;
+                    Ready
+                }
+                (_, Message::Goodbye) => Disconnected,
+                (state, _) => state,
+            };
+        }
+        state
+    }
+
+    fn handle_response(&mut self, message: Message) -> CommunicationResult<Message> {
+        let state = self.server_state;
+        let request = self.sent_queue.pop_front();
+        let next = request
+            .as_ref()
+            .and_then(|request| state.transition(request, &message));
+        let (request, mut next) = match (request, next) {
+            (Some(request), Some(next)) => (request, next),
+            (request, _) => {
+                self.server_state = Defunct;
+                return Err(CommunicationError::InvalidResponse {
+                    state,
+                    request,
+                    response: message,
+                });
+            }
+        };
+
+        match (&request, &message) {
+            (Message::Init(_) | Message::Hello(_), Message::Success(success)) => {
+                self.store_connection_info(success);
+            }
+            (Message::RunWithMetadata(_), Message::Success(success)) if next == TxStreaming => {
+                self.open_tx_streams.opened(success);
+            }
+            (Message::PullAll | Message::DiscardAll, Message::Success(_)) if next == TxReady => {
+                self.open_tx_streams.closed(None);
+            }
+            (Message::Pull(pull), Message::Success(_)) if next == TxReady => {
+                self.open_tx_streams.closed(pull.metadata().get("qid"));
+            }
+            (Message::Discard(discard), Message::Success(_)) if next == TxReady => {
+                self.open_tx_streams.closed(discard.metadata().get("qid"));
+            }
+            (Message::Reset, Message::Success(_)) => self.open_tx_streams.clear(),
+            _ => {}
+        }
+        // Other results in the transaction may still be open
+        if next == TxReady && state == TxStreaming && !self.open_tx_streams.is_empty() {
+            next = TxStreaming;
+        }
+        self.server_state = next;
+
+        let ignored = state == Interrupted && !matches!(request, Message::Reset);
+        if let (Message::PullAll | Message::Pull(_), Message::Record(_)) = (&request, &message) {
+            // Put the request back so we can keep consuming records
+            self.sent_queue.push_front(request);
+        }
+        if ignored {
+            Ok(Message::Ignored)
+        } else {
+            Ok(message)
+        }
+    }
+}
+
+/// Return whether an IO error indicates that the other end of the connection was closed.
+pub(crate) fn is_closed(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+    )
+}
+
+/// Return whether a request is valid while the server is in the given state.
+fn accepts(state: ServerState, message: &Message) -> bool {
+    state.valid_requests().contains(&message.kind())
+}
+
+/// Reject a message that doesn't exist in the given protocol version before it is sent, since the
+/// server would close the connection. Server responses are left to the state machine to reject.
+pub(crate) fn check_supported(version: u32, message: &Message) -> CommunicationResult<()> {
+    let supported = match message {
+        Message::Init(_) | Message::Run(_) | Message::AckFailure => {
+            !Feature::ExplicitTx.is_supported_by(version)
+        }
+        Message::PullAll | Message::DiscardAll => !Feature::PullN.is_supported_by(version),
+        Message::Hello(_)
+        | Message::Goodbye
+        | Message::RunWithMetadata(_)
+        | Message::Begin(_)
+        | Message::Commit
+        | Message::Rollback => Feature::ExplicitTx.is_supported_by(version),
+        Message::Pull(_) | Message::Discard(_) => Feature::PullN.is_supported_by(version),
+        Message::Route(_) => version == V4_3,
+        Message::RouteWithMetadata(_) => {
+            Feature::RouteMessage.is_supported_by(version) && version != V4_3
+        }
+        Message::Reset
+        | Message::Record(_)
+        | Message::Success(_)
+        | Message::Failure(_)
+        | Message::Ignored => true,
+    };
+    match supported {
+        true => Ok(()),
+        false => Err(CommunicationError::UnsupportedOperation {
+            operation: message.name(),
+            version,
+        }),
+    }
+}
@@ -0,0 +1,302 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use bolt_proto::{
+    error::{DeserializationError, Error as ProtocolError},
+    message::MessageDecoder,
+    version::Version,
+    Message, ServerState,
+};
+use futures_util::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadHalf, WriteHalf};
+
+use crate::{error::CommunicationResult, ClientInfo, ParamRedaction, RetryPolicy, Timer};
+
+use super::{
+    check_supported,
+    framing::{self, ReadBuffer, WriteBuffer},
+    is_closed, packed_version, Client, HandshakeInfo, MetadataValidation, Session,
+};
+
+fn lock(session: &Mutex<Session>) -> MutexGuard<'_, Session> {
+    // The session is only left in an inconsistent state if a state change hook panics, in which
+    // case it's still better to keep going than to poison both halves
+    session.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The sending half of a [`Client`], created by [`Client::split`].
+///
+/// Requests are checked against the state the server will be in once it has successfully
+/// processed every request sent so far, rather than its current state. For example, a `RUN` can be
+/// sent right after a `PULL` with an `n` of -1, while the [`ClientReader`] is still receiving the
+/// records for that `PULL`. `PULL` and `DISCARD` requests for a limited number of records are
+/// assumed to leave the rest of the result open. As with [`Client::pipeline`], if one of the
+/// requests fails, the server ignores the requests sent after it until the failure is cleared.
+#[derive(Debug)]
+pub struct ClientWriter<S: AsyncRead + AsyncWrite + Unpin> {
+    stream: WriteHalf<S>,
+    write_buffer: WriteBuffer,
+    version: u32,
+    session: Arc<Mutex<Session>>,
+    // Kept so the client can be put back together
+    metadata_validation: MetadataValidation,
+    client_info: ClientInfo,
+    retry_policy: RetryPolicy,
+    param_redaction: ParamRedaction,
+    max_in_flight: Option<usize>,
+    timer: Box<dyn Timer>,
+    #[cfg(feature = "tokio-stream")]
+    builder: Option<Arc<crate::ClientBuilder>>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> ClientWriter<S> {
+    /// Get the protocol version of the client this half was split from.
+    pub fn version(&self) -> Version {
+        packed_version(self.version)
+    }
+
+    /// Get the protocol version of the client this half was split from, packed into a `u32` like
+    /// the constants in [`bolt_proto::version`].
+    pub fn raw_version(&self) -> u32 {
+        self.version
+    }
+
+    /// Get the current server state. This only changes once responses are received by the
+    /// [`ClientReader`], or when a [`RESET`](Message::Reset) or [`GOODBYE`](Message::Goodbye) is
+    /// sent.
+    pub fn server_state(&self) -> ServerState {
+        lock(&self.session).server_state
+    }
+
+    /// Get the state the server will be in once it has successfully processed every request sent
+    /// so far. This is the state new requests are checked against.
+    pub fn expected_server_state(&self) -> ServerState {
+        lock(&self.session).expected_state()
+    }
+
+    /// Send a message to the server without waiting for a response, which is received by the
+    /// [`ClientReader`] instead. Returns an
+    /// [`InvalidState`](crate::error::CommunicationError::InvalidState) error if the message is not valid in the
+    /// [expected server state](ClientWriter::expected_server_state), or an
+    /// [`UnsupportedOperation`](crate::error::CommunicationError::UnsupportedOperation) error if
+    /// it doesn't exist in the client's version.
+    ///
+    /// If the returned future is dropped before the message has been written, the rest of it is
+    /// written by the next call to [`ClientWriter::send`] or [`ClientWriter::flush`].
+    pub async fn send(&mut self, mut message: Message) -> CommunicationResult<()> {
+        self.flush().await?;
+        {
+            let mut session = lock(&self.session);
+            session.interceptors.before_send(&mut message);
+            check_supported(self.version, &message)?;
+            let expected = session.expected_state();
+            session.check_request(expected, &message)?;
+            let chunks = message.clone().into_chunks().map_err(ProtocolError::from)?;
+
+            #[cfg(test)]
+            println!(">>> {:?}", message);
+
+            // Queue the message before sending it, so the reader can't receive its response first
+            let bytes_sent = self.write_buffer.push(&chunks);
+            session.stats.sent(bytes_sent, 1);
+            session.sent(message);
+        }
+        self.flush().await
+    }
+
+    /// Finish writing a message whose [`send`](ClientWriter::send) was cancelled, i.e. whose
+    /// future was dropped before it completed. This does nothing if every message has been sent.
+    pub async fn flush(&mut self) -> CommunicationResult<()> {
+        match self.write_buffer.write_to(&mut self.stream).await {
+            Err(error) if is_closed(&error) => Err(lock(&self.session).closed_while_writing(error)),
+            result => Ok(result?),
+        }
+    }
+}
+
+/// The receiving half of a [`Client`], created by [`Client::split`].
+///
+/// Each response is matched up with the request it answers, updating the server state just as a
+/// [`Client`] does.
+#[derive(Debug)]
+pub struct ClientReader<S: AsyncRead + AsyncWrite + Unpin> {
+    stream: ReadHalf<S>,
+    decoder: MessageDecoder,
+    read_buffer: ReadBuffer,
+    version: u32,
+    handshake: HandshakeInfo,
+    session: Arc<Mutex<Session>>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> ClientReader<S> {
+    /// Get the protocol version of the client this half was split from.
+    pub fn version(&self) -> Version {
+        packed_version(self.version)
+    }
+
+    /// Get the protocol version of the client this half was split from, packed into a `u32` like
+    /// the constants in [`bolt_proto::version`].
+    pub fn raw_version(&self) -> u32 {
+        self.version
+    }
+
+    /// Get the current server state.
+    pub fn server_state(&self) -> ServerState {
+        lock(&self.session).server_state
+    }
+
+    /// Get the number of requests the server has not finished responding to.
+    pub fn pending(&self) -> usize {
+        lock(&self.session).sent_queue.len()
+    }
+
+    /// Receive the next message from the server. This reads from the connection whether or not any
+    /// requests are [pending](ClientReader::pending), so with none pending, it waits until the
+    /// server sends something, e.g. a response to a request the [`ClientWriter`] sends in the
+    /// meantime, or closes the connection.
+    ///
+    /// Like [`Client::pipeline`], this returns [`RECORD`](Message::Record) messages one at a time,
+    /// followed by the summary message for the request that produced them.
+    ///
+    /// This is cancellation safe: if the returned future is dropped partway through a message, the
+    /// bytes read so far are kept, and the next call returns the message once the rest arrives.
+    pub async fn receive(&mut self) -> CommunicationResult<Message> {
+        let session = &self.session;
+        let result = framing::read_message(
+            &mut self.stream,
+            &mut self.decoder,
+            &mut self.read_buffer,
+            |read| lock(session).stats.stats.bytes_received += read as u64,
+        )
+        .await;
+
+        let mut session = lock(&self.session);
+        match result {
+            Ok(message) => session.received(message),
+            Err(DeserializationError::IoError(error)) if is_closed(&error) => {
+                Err(session.closed_while_reading(error))
+            }
+            Err(error) if framing::exceeds_limit(&error) => Err(session.exceeded_limit(error)),
+            Err(error) => Err(ProtocolError::from(error).into()),
+        }
+    }
+
+    /// Put this half back together with the [`ClientWriter`] it was split from. If the halves come
+    /// from different clients, they are returned in a [`ReuniteError`].
+    pub fn reunite(self, writer: ClientWriter<S>) -> Result<Client<S>, ReuniteError<S>> {
+        if !Arc::ptr_eq(&self.session, &writer.session) {
+            return Err(ReuniteError(self, writer));
+        }
+
+        let Self {
+            stream,
+            decoder,
+            read_buffer,
+            version,
+            handshake,
+            session,
+        } = self;
+        let ClientWriter {
+            stream: write_half,
+            write_buffer,
+            session: writer_session,
+            metadata_validation,
+            client_info,
+            retry_policy,
+            param_redaction,
+            max_in_flight,
+            timer,
+            #[cfg(feature = "tokio-stream")]
+            builder,
+            ..
+        } = writer;
+        drop(writer_session);
+        let stream = stream
+            .reunite(write_half)
+            .expect("halves sharing a session come from the same stream");
+        let session = Arc::try_unwrap(session)
+            .expect("session is only shared by the two halves")
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        Ok(Client {
+            stream,
+            version,
+            handshake,
+            session,
+            metadata_validation,
+            client_info,
+            retry_policy,
+            param_redaction,
+            max_in_flight,
+            timer,
+            decoder,
+            read_buffer,
+            write_buffer,
+            #[cfg(feature = "tokio-stream")]
+            builder,
+        })
+    }
+}
+
+/// The error returned by [`ClientReader::reunite`] when the two halves come from different
+/// clients. It contains the halves that were passed in.
+pub struct ReuniteError<S: AsyncRead + AsyncWrite + Unpin>(
+    pub ClientReader<S>,
+    pub ClientWriter<S>,
+);
+
+impl<S: AsyncRead + AsyncWrite + Unpin> fmt::Debug for ReuniteError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ReuniteError").finish()
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> fmt::Display for ReuniteError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("tried to reunite halves that are not from the same client")
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> std::error::Error for ReuniteError<S> {}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
+    /// Split this client into a [`ClientWriter`] for sending requests and a [`ClientReader`] for
+    /// receiving responses, which can be used concurrently. This allows new requests to be sent
+    /// while the results of earlier ones are still being received, e.g. sending the next `RUN`
+    /// while still consuming the records from a `PULL`.
+    ///
+    /// The halves share the server state, so the state machine is still enforced: the writer
+    /// checks each request against the state the server is expected to be in once it has
+    /// processed the earlier requests, and the reader updates the state as responses arrive. Use
+    /// [`ClientReader::reunite`] to get the client back.
+    pub fn split(self) -> (ClientWriter<S>, ClientReader<S>) {
+        let (read_half, write_half) = self.stream.split();
+        let session = Arc::new(Mutex::new(self.session));
+        (
+            ClientWriter {
+                stream: write_half,
+                write_buffer: self.write_buffer,
+                version: self.version,
+                session: Arc::clone(&session),
+                metadata_validation: self.metadata_validation,
+                client_info: self.client_info,
+                retry_policy: self.retry_policy,
+                param_redaction: self.param_redaction,
+                max_in_flight: self.max_in_flight,
+                timer: self.timer,
+                #[cfg(feature = "tokio-stream")]
+                builder: self.builder,
+            },
+            ClientReader {
+                stream: read_half,
+                decoder: self.decoder,
+                read_buffer: self.read_buffer,
+                version: self.version,
+                handshake: self.handshake,
+                session,
+            },
+        )
+    }
+}
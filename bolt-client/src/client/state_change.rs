@@ -0,0 +1,43 @@
+use std::fmt;
+
+use bolt_proto::ServerState;
+
+/// A change in the [`ServerState`] of a [`Client`](crate::Client), passed to the hook set with
+/// [`Client::on_state_change`](crate::Client::on_state_change).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateChange {
+    pub(crate) previous: ServerState,
+    pub(crate) current: ServerState,
+    pub(crate) request: Option<&'static str>,
+    pub(crate) response: Option<&'static str>,
+}
+
+impl StateChange {
+    pub fn previous(&self) -> ServerState {
+        self.previous
+    }
+
+    pub fn current(&self) -> ServerState {
+        self.current
+    }
+
+    /// Get the name (e.g. `"RESET"`) of the message sent by the client that caused the change, or
+    /// that the server was responding to.
+    pub fn request(&self) -> Option<&'static str> {
+        self.request
+    }
+
+    /// Get the name (e.g. `"FAILURE"`) of the server response that caused the change, if the
+    /// change was caused by a response.
+    pub fn response(&self) -> Option<&'static str> {
+        self.response
+    }
+}
+
+pub(crate) struct StateChangeHook(pub(crate) Box<dyn FnMut(&StateChange) + Send + Sync>);
+
+impl fmt::Debug for StateChangeHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StateChangeHook")
+    }
+}
@@ -0,0 +1,92 @@
+use std::time::{Duration, Instant};
+
+/// A snapshot of the traffic a [`Client`](crate::Client) has sent and received.
+///
+/// Byte counts include the handshake and the chunk headers of each message.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClientStats {
+    pub(crate) bytes_sent: u64,
+    pub(crate) bytes_received: u64,
+    pub(crate) messages_sent: u64,
+    pub(crate) messages_received: u64,
+    pub(crate) records_received: u64,
+    pub(crate) last_round_trip: Option<Duration>,
+}
+
+impl ClientStats {
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received
+    }
+
+    pub fn messages_sent(&self) -> u64 {
+        self.messages_sent
+    }
+
+    pub fn messages_received(&self) -> u64 {
+        self.messages_received
+    }
+
+    /// Get the number of [`RECORD`](bolt_proto::Message::Record) messages received. These are
+    /// also counted in [`ClientStats::messages_received`].
+    pub fn records_received(&self) -> u64 {
+        self.records_received
+    }
+
+    /// Get the time between the most recent flush of sent messages and the first message received
+    /// after it, if any messages have been exchanged since the client was created.
+    pub fn last_round_trip(&self) -> Option<Duration> {
+        self.last_round_trip
+    }
+}
+
+/// Tracks the start of the current round trip and the last activity alongside [`ClientStats`].
+#[derive(Debug)]
+pub(crate) struct StatsTracker {
+    pub(crate) stats: ClientStats,
+    awaiting_since: Option<Instant>,
+    last_activity: Instant,
+}
+
+impl Default for StatsTracker {
+    fn default() -> Self {
+        Self {
+            stats: ClientStats::default(),
+            awaiting_since: None,
+            last_activity: Instant::now(),
+        }
+    }
+}
+
+impl StatsTracker {
+    pub(crate) fn sent(&mut self, bytes: usize, messages: usize) {
+        self.stats.bytes_sent += bytes as u64;
+        self.stats.messages_sent += messages as u64;
+        self.awaiting_since = Some(Instant::now());
+        self.last_activity = Instant::now();
+    }
+
+    /// Count a `NOOP` chunk, which isn't a message and has no response.
+    pub(crate) fn sent_noop(&mut self, bytes: usize) {
+        self.stats.bytes_sent += bytes as u64;
+        self.last_activity = Instant::now();
+    }
+
+    pub(crate) fn received(&mut self, is_record: bool) {
+        self.last_activity = Instant::now();
+        self.stats.messages_received += 1;
+        if is_record {
+            self.stats.records_received += 1;
+        }
+        if let Some(start) = self.awaiting_since.take() {
+            self.stats.last_round_trip = Some(start.elapsed());
+        }
+    }
+
+    pub(crate) fn idle_time(&self) -> Duration {
+        self.last_activity.elapsed()
+    }
+}
@@ -0,0 +1,240 @@
+use std::time::Instant;
+
+use bolt_proto::{Message, ServerState::*};
+use futures_util::io::{AsyncRead, AsyncWrite};
+
+use crate::{
+    error::{CommunicationError, CommunicationResult, TransactionError},
+    retry::is_retryable,
+    AccessMode, Client, Metadata,
+};
+
+// The outcome of a failed attempt, and whether the transaction can be tried again
+type AttemptError<E> = (TransactionError<E>, bool);
+
+fn fatal<E>(error: CommunicationError) -> AttemptError<E> {
+    (error.into(), false)
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
+    /// Run a function inside a read transaction, retrying it on transient errors.
+    /// _(Bolt v3+ only.)_
+    ///
+    /// This is the same as [`Client::write_transaction`], except that the transaction is started
+    /// with the `mode` metadata entry set to `"r"`, so that a cluster can route it to a read
+    /// replica.
+    pub async fn read_transaction<T, E>(
+        &mut self,
+        metadata: Option<Metadata>,
+        work: impl AsyncFnMut(&mut Self) -> Result<T, E>,
+    ) -> Result<T, TransactionError<E>> {
+        self.run_transaction(AccessMode::Read, metadata, work).await
+    }
+
+    /// Run a function inside a write transaction, retrying it on transient errors.
+    /// _(Bolt v3+ only.)_
+    ///
+    /// # Description
+    /// A transaction is started with [`Client::begin`], using the given `metadata` with the `mode`
+    /// entry set to `"w"`. If `metadata` has no `bookmarks` entry, the client's
+    /// [last bookmark](Client::last_bookmark) is used, so the transaction sees the changes of the
+    /// last one committed on this connection, or the bookmarks of the client's
+    /// [bookmark manager](Client::set_bookmark_manager) if it has one. The function is then called
+    /// with the client to run its queries, and the transaction is committed once it returns
+    /// successfully.
+    ///
+    /// If the server fails the transaction with a transient error (e.g. a deadlock, or a leader
+    /// switch in a cluster), the transaction is rolled back and the function is called again in a
+    /// new transaction, with the same bookmarks, after waiting as configured by the client's
+    /// [`RetryPolicy`](crate::RetryPolicy). The function may therefore be called several times,
+    /// and should not have side effects outside the transaction. Other errors are returned
+    /// immediately, after rolling back the transaction.
+    ///
+    /// Server failures are detected from the [`FAILURE`](Message::Failure) responses the client
+    /// receives, so the function can return them as errors of any type `E`. If the function
+    /// returns successfully after a failure, the transaction is treated as failed anyway.
+    ///
+    /// ```
+    /// # use std::env;
+    /// # use bolt_client::*;
+    /// # use bolt_proto::{message::*, version::*};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let result = ClientBuilder::new(env::var("BOLT_TEST_ADDR")?)
+    /// #     .with_domain(env::var("BOLT_TEST_DOMAIN").ok())
+    /// #     .with_versions([V4_4, V4_3, V4_2, V4_1])
+    /// #     .with_basic_auth(env::var("BOLT_TEST_USERNAME")?, env::var("BOLT_TEST_PASSWORD")?)
+    /// #     .connect()
+    /// #     .await;
+    /// # skip_if_handshake_failed!(result, Ok(()));
+    /// # let mut client = result.unwrap();
+    /// let created = client
+    ///     .write_transaction(None, async |tx| {
+    ///         tx.run("CREATE (:Counter {value: 0});", None, None).await?;
+    ///         let (_, response) = tx.pull(Some(Metadata::from_iter([("n", -1)]))).await?;
+    ///         Ok::<_, error::CommunicationError>(matches!(response, Message::Success(_)))
+    ///     })
+    ///     .await?;
+    /// assert!(created);
+    /// assert!(client.last_bookmark().is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn write_transaction<T, E>(
+        &mut self,
+        metadata: Option<Metadata>,
+        work: impl AsyncFnMut(&mut Self) -> Result<T, E>,
+    ) -> Result<T, TransactionError<E>> {
+        self.run_transaction(AccessMode::Write, metadata, work)
+            .await
+    }
+
+    /// Run a function inside an explicit transaction, committing it if the function returns
+    /// successfully and rolling it back otherwise. _(Bolt v3+ only.)_
+    ///
+    /// Unlike [`Client::write_transaction`], the transaction is started with no metadata other than
+    /// the bookmarks of the client's [bookmark manager](Client::set_bookmark_manager), and is never
+    /// retried, so the function is called exactly once. This suits functions with side effects
+    /// outside the transaction. As with [`Client::write_transaction`], a server failure
+    /// fails the transaction even if the function ignores it and returns successfully.
+    ///
+    /// ```
+    /// # use std::env;
+    /// # use bolt_client::*;
+    /// # use bolt_proto::{message::*, version::*};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let result = ClientBuilder::new(env::var("BOLT_TEST_ADDR")?)
+    /// #     .with_domain(env::var("BOLT_TEST_DOMAIN").ok())
+    /// #     .with_versions([V4_4, V4_3, V4_2, V4_1])
+    /// #     .with_basic_auth(env::var("BOLT_TEST_USERNAME")?, env::var("BOLT_TEST_PASSWORD")?)
+    /// #     .connect()
+    /// #     .await;
+    /// # skip_if_handshake_failed!(result, Ok(()));
+    /// # let mut client = result.unwrap();
+    /// client
+    ///     .run_in_tx(async |tx| {
+    ///         for query in ["CREATE (:Account {id: 1});", "CREATE (:Account {id: 2});"] {
+    ///             tx.run(query, None, None).await?;
+    ///             tx.discard(Some(Metadata::from_iter([("n", -1)]))).await?;
+    ///         }
+    ///         Ok::<_, error::CommunicationError>(())
+    ///     })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn run_in_tx<T, E>(
+        &mut self,
+        work: impl AsyncFnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, TransactionError<E>> {
+        self.attempt_transaction(&Metadata::default(), work)
+            .await
+            .map_err(|(error, _)| error)
+    }
+
+    async fn run_transaction<T, E>(
+        &mut self,
+        mode: AccessMode,
+        metadata: Option<Metadata>,
+        mut work: impl AsyncFnMut(&mut Self) -> Result<T, E>,
+    ) -> Result<T, TransactionError<E>> {
+        let mut metadata = metadata.unwrap_or_default().with_mode(mode);
+        // With a bookmark manager, its bookmarks are added by `begin` instead
+        if !metadata.value.contains_key("bookmarks") && self.session.bookmark_manager.is_none() {
+            if let Some(bookmark) = self.last_bookmark() {
+                metadata = metadata.with_bookmarks([bookmark]);
+            }
+        }
+
+        let started = Instant::now();
+        let mut retry = 0;
+        loop {
+            let (error, retryable) = match self.attempt_transaction(&metadata, &mut work).await {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+
+            let delay = self.retry_policy.jittered_delay(retry);
+            if !retryable || started.elapsed() + delay > self.retry_policy.max_retry_time() {
+                return Err(error);
+            }
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!(parent: &self.span(), retry, ?delay, "retrying failed transaction");
+
+            self.timer.sleep(delay).await;
+            retry += 1;
+        }
+    }
+
+    async fn attempt_transaction<T, E>(
+        &mut self,
+        metadata: &Metadata,
+        work: impl AsyncFnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, AttemptError<E>> {
+        self.session.last_failure = None;
+        match self.begin(Some(metadata.clone())).await.map_err(fatal)? {
+            Message::Success(_) => {}
+            response => return Err(self.abort(response).await),
+        }
+
+        let result = work(self).await;
+        if let Some(failure) = self.session.last_failure.take() {
+            self.clean_up().await.map_err(fatal)?;
+            let retryable = is_retryable(&failure);
+            return match result {
+                Ok(_) => Err((TransactionError::Failed(failure), retryable)),
+                Err(error) => Err((TransactionError::Work(error), retryable)),
+            };
+        }
+        let value = match result {
+            Ok(value) => value,
+            Err(error) => {
+                self.clean_up().await.map_err(fatal)?;
+                return Err((TransactionError::Work(error), false));
+            }
+        };
+
+        match self.commit().await.map_err(fatal)? {
+            Message::Success(_) => Ok(value),
+            response => Err(self.abort(response).await),
+        }
+    }
+
+    // Handle a response other than SUCCESS to BEGIN or COMMIT
+    async fn abort<E>(&mut self, response: Message) -> AttemptError<E> {
+        let state = self.session.server_state;
+        if let Err(error) = self.clean_up().await {
+            return fatal(error);
+        }
+        match response {
+            Message::Failure(failure) => {
+                let retryable = is_retryable(&failure);
+                (TransactionError::Failed(failure), retryable)
+            }
+            response => fatal(CommunicationError::InvalidResponse {
+                state,
+                request: None,
+                response,
+            }),
+        }
+    }
+
+    // Bring the server back to the Ready state after a failed attempt, cancelling the transaction
+    async fn clean_up(&mut self) -> CommunicationResult<()> {
+        let response = match self.session.server_state {
+            Ready | Defunct | Disconnected => return Ok(()),
+            TxReady => self.rollback().await?,
+            _ => self.reset().await?,
+        };
+        match response {
+            Message::Success(_) => Ok(()),
+            response => Err(CommunicationError::InvalidResponse {
+                state: self.session.server_state,
+                request: None,
+                response,
+            }),
+        }
+    }
+}
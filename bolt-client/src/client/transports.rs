@@ -0,0 +1,261 @@
+//! Client scenarios run over each kind of stream the client is used with, to catch behavior that
+//! only works with tokio's in-memory streams. The mock server runs in the same task as the client,
+//! so the scenarios don't depend on any runtime's task spawning.
+//!
+//! Each scenario in [`transport_tests!`] becomes a test in the `duplex`, `tokio_tcp`, and
+//! `async_std_tcp` modules. Run them with `cargo test -p bolt-client --features tokio-stream --lib
+//! transports`.
+
+use std::{collections::HashMap, fmt::Debug, future::Future};
+
+use bolt_proto::{message::*, version::*, Message, ServerState::*, Value};
+use futures_util::{
+    future,
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+use crate::{error::CommunicationError, Client, Metadata};
+
+trait Connection: AsyncRead + AsyncWrite + Unpin + Send + Debug {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + Debug> Connection for T {}
+
+type BoxedConnection = Box<dyn Connection>;
+
+// The client and server ends of a connection
+type Pair = (BoxedConnection, BoxedConnection);
+
+async fn duplex() -> Pair {
+    let (client, server) = tokio::io::duplex(1024);
+    (Box::new(client.compat()), Box::new(server.compat()))
+}
+
+async fn tokio_tcp() -> Pair {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (client, server) =
+        future::join(tokio::net::TcpStream::connect(addr), listener.accept()).await;
+    (
+        Box::new(client.unwrap().compat()),
+        Box::new(server.unwrap().0.compat()),
+    )
+}
+
+async fn async_std_tcp() -> Pair {
+    let listener = async_std::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (client, server) =
+        future::join(async_std::net::TcpStream::connect(addr), listener.accept()).await;
+    (Box::new(client.unwrap()), Box::new(server.unwrap().0))
+}
+
+// Serve the handshake, then reply to each received message with a batch of messages, in order.
+// The connection is closed once every batch has been sent.
+async fn serve(mut stream: BoxedConnection, version: u32, replies: Vec<Vec<Message>>) {
+    let mut request = [0; 20];
+    stream.read_exact(&mut request).await.unwrap();
+    stream.write_all(&version.to_be_bytes()).await.unwrap();
+    for batch in replies {
+        Message::from_stream(&mut stream).await.unwrap();
+        for reply in batch {
+            for chunk in reply.into_chunks().unwrap() {
+                stream.write_all(&chunk).await.unwrap();
+            }
+        }
+    }
+    stream.flush().await.unwrap();
+}
+
+// Run a test with a client connected to a mock server, driving both in the same task
+async fn run<F, Fut>((client, server): Pair, replies: Vec<Vec<Message>>, test: F)
+where
+    F: FnOnce(Client<BoxedConnection>) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let client = async move {
+        let client = Client::new(client, &[V4_4, 0, 0, 0]).await.unwrap();
+        test(client).await;
+    };
+    future::join(serve(server, V4_4, replies), client).await;
+}
+
+fn success() -> Message {
+    Message::Success(Success::new(Default::default()))
+}
+
+fn fields(names: &[&str]) -> Message {
+    Message::Success(Success::new(HashMap::from([(
+        String::from("fields"),
+        Value::from(names.to_vec()),
+    )])))
+}
+
+async fn hello(pair: Pair) {
+    run(pair, vec![vec![success()]], |mut client| async move {
+        assert_eq!(client.version(), Version::new(4, 4));
+        let response = client.hello(Metadata::default()).await.unwrap();
+        assert!(matches!(response, Message::Success(_)));
+        assert_eq!(client.server_state(), Ready);
+    })
+    .await;
+}
+
+// Records that span many reads and chunks
+async fn pull_large_records(pair: Pair) {
+    let record = |n: i64| {
+        Message::Record(Record::new(vec![
+            Value::from(n),
+            Value::from("x".repeat(20_000)),
+        ]))
+    };
+    let mut records: Vec<_> = (0..50).map(record).collect();
+    records.push(success());
+    let replies = vec![vec![success()], vec![fields(&["n", "s"])], records];
+
+    run(pair, replies, |mut client| async move {
+        client.hello(Metadata::default()).await.unwrap();
+        client
+            .run("UNWIND range(0, 49) AS n RETURN n, 's' AS s;", None, None)
+            .await
+            .unwrap();
+        let (records, response) = client
+            .pull(Some(Metadata::from_iter([("n", -1)])))
+            .await
+            .unwrap();
+        assert!(matches!(response, Message::Success(_)));
+        assert_eq!(records.len(), 50);
+        assert_eq!(records[49].fields()[0], Value::from(49));
+        assert_eq!(client.server_state(), Ready);
+    })
+    .await;
+}
+
+// The replies to a RUN and a PULL of one record
+fn query_replies() -> Vec<Vec<Message>> {
+    vec![
+        vec![fields(&["n"])],
+        vec![
+            Message::Record(Record::new(vec![Value::from(1)])),
+            success(),
+        ],
+    ]
+}
+
+fn query() -> Vec<Message> {
+    vec![
+        Message::RunWithMetadata(RunWithMetadata::new(
+            String::from("RETURN 1 AS n;"),
+            Default::default(),
+            Default::default(),
+        )),
+        Message::Pull(Pull::new(HashMap::from([(
+            String::from("n"),
+            Value::from(-1),
+        )]))),
+    ]
+}
+
+async fn pipeline_max_in_flight(pair: Pair) {
+    let mut replies = vec![vec![success()]];
+    replies.extend((0..10).flat_map(|_| query_replies()));
+    run(pair, replies, |mut client| async move {
+        client.set_max_in_flight(Some(3));
+        let mut messages = vec![Message::Hello(Hello::new(Default::default()))];
+        messages.extend((0..10).flat_map(|_| query()));
+        let responses = client.pipeline(messages).await.unwrap();
+        assert_eq!(responses.len(), 1 + 10 * 3);
+        assert!(matches!(responses[29], Message::Record(_)));
+        assert!(matches!(responses[30], Message::Success(_)));
+        assert_eq!(client.server_state(), Ready);
+    })
+    .await;
+}
+
+// Send and receive at the same time through the halves of a split client
+async fn split(pair: Pair) {
+    let mut replies = vec![vec![success()]];
+    replies.extend((0..10).flat_map(|_| query_replies()));
+    run(pair, replies, |client| async move {
+        let (mut writer, mut reader) = client.split();
+        let send = async {
+            writer
+                .send(Message::Hello(Hello::new(Default::default())))
+                .await
+                .unwrap();
+            for message in (0..10).flat_map(|_| query()) {
+                writer.send(message).await.unwrap();
+            }
+            writer
+        };
+        let receive = async {
+            let mut responses = vec![];
+            while responses.len() < 1 + 10 * 3 {
+                responses.push(reader.receive().await.unwrap());
+            }
+            assert!(matches!(responses[30], Message::Success(_)));
+            reader
+        };
+        let (writer, reader) = future::join(send, receive).await;
+        let client = reader.reunite(writer).unwrap();
+        assert_eq!(client.server_state(), Ready);
+    })
+    .await;
+}
+
+async fn server_closed_connection(pair: Pair) {
+    run(pair, vec![vec![success()]], |mut client| async move {
+        client.hello(Metadata::default()).await.unwrap();
+        assert!(matches!(
+            client.reset().await,
+            Err(CommunicationError::ServerClosedConnection {
+                during: MessageKind::Reset
+            })
+        ));
+        assert_eq!(client.server_state(), Defunct);
+    })
+    .await;
+}
+
+macro_rules! transport_tests {
+    ($($scenario:ident),* $(,)?) => {
+        mod duplex {
+            $(
+                #[tokio::test]
+                async fn $scenario() {
+                    super::$scenario(super::duplex().await).await;
+                }
+            )*
+        }
+
+        mod tokio_tcp {
+            $(
+                #[tokio::test]
+                async fn $scenario() {
+                    super::$scenario(super::tokio_tcp().await).await;
+                }
+            )*
+        }
+
+        mod async_std_tcp {
+            $(
+                #[test]
+                fn $scenario() {
+                    async_std::task::block_on(async {
+                        super::$scenario(super::async_std_tcp().await).await;
+                    });
+                }
+            )*
+        }
+    };
+}
+
+transport_tests!(
+    hello,
+    pull_large_records,
+    pipeline_max_in_flight,
+    split,
+    server_closed_connection,
+);
@@ -0,0 +1,224 @@
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bolt_proto::{message::Record, Message, Value};
+use futures_util::{
+    future::BoxFuture,
+    io::{AsyncRead, AsyncWrite},
+    lock::Mutex,
+    stream::{FusedStream, Stream},
+    FutureExt,
+};
+
+use super::DEFAULT_BATCH_SIZE;
+use crate::{error::CommunicationResult, Client, Metadata, Params, StreamSummary};
+
+/// An explicit transaction whose results can be streamed independently of each other.
+/// _(Bolt v4+ only.)_
+///
+/// Bolt v4+ servers let a transaction have several results open at once, each identified by the
+/// `qid` entry of the [`SUCCESS`](Message::Success) response to its query. A `Transaction` wraps a
+/// client that has started a transaction with [`Client::begin`], and hands out a
+/// [`TxRecordStream`] for each result with [`Transaction::stream`]. Each stream requests its
+/// records in batches with `PULL` messages tagged with its `qid`.
+///
+/// The streams share the client, which they only hold while sending a `PULL` message and reading
+/// the batch of records it returns. They can therefore be polled concurrently, e.g. with
+/// [`join`](futures_util::future::join) or [`zip`](futures_util::StreamExt::zip), and consume
+/// their results in interleaved batches.
+///
+/// ```
+/// # use std::env;
+/// # use bolt_client::*;
+/// # use bolt_proto::{message::*, version::*, Message};
+/// # use futures_util::StreamExt;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let result = ClientBuilder::new(env::var("BOLT_TEST_ADDR")?)
+/// #     .with_domain(env::var("BOLT_TEST_DOMAIN").ok())
+/// #     .with_versions([V4_4, V4_3, V4_2, V4_1])
+/// #     .with_basic_auth(env::var("BOLT_TEST_USERNAME")?, env::var("BOLT_TEST_PASSWORD")?)
+/// #     .connect()
+/// #     .await;
+/// # skip_if_handshake_failed!(result, Ok(()));
+/// # let mut client = result.unwrap();
+/// client.begin(None).await?;
+/// let tx = Transaction::new(&mut client).with_batch_size(10);
+///
+/// let qid = |response: Message| match response {
+///     Message::Success(success) => QueryHeader::from_success(&success).qid(),
+///     _ => None,
+/// };
+/// let numbers = qid(tx.run("UNWIND range(1, 100) AS n RETURN n;", None, None).await?);
+/// let letters = qid(tx.run("UNWIND ['a', 'b', 'c'] AS l RETURN l;", None, None).await?);
+/// let (numbers, letters) = numbers.zip(letters).expect("missing qid");
+///
+/// let pairs: Vec<_> = tx.stream(numbers).zip(tx.stream(letters)).collect().await;
+/// assert_eq!(pairs.len(), 3);
+/// tx.rollback().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Transaction<'a, S: AsyncRead + AsyncWrite + Unpin> {
+    client: Mutex<&'a mut Client<S>>,
+    batch_size: usize,
+}
+
+impl<'a, S: AsyncRead + AsyncWrite + Unpin + Send> Transaction<'a, S> {
+    /// Share a client that has started a transaction with [`Client::begin`]. Results are
+    /// streamed in batches of 1000 records by default.
+    pub fn new(client: &'a mut Client<S>) -> Self {
+        Self {
+            client: Mutex::new(client),
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Set the number of records requested by each `PULL` message of a stream (a size of 0 is
+    /// treated as 1).
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Run a query in the transaction with [`Client::run`], once no stream is reading from the
+    /// connection. The `qid` of the result is in the [`QueryHeader`](crate::QueryHeader) of a
+    /// [`SUCCESS`](Message::Success) response.
+    pub async fn run(
+        &self,
+        query: impl Into<String>,
+        parameters: Option<Params>,
+        metadata: Option<Metadata>,
+    ) -> CommunicationResult<Message> {
+        let mut client = self.client.lock().await;
+        client.run(query, parameters, metadata).await
+    }
+
+    /// Stream the records of the result with the given `qid`.
+    pub fn stream(&self, qid: i64) -> TxRecordStream<'_, 'a, S> {
+        TxRecordStream {
+            tx: self,
+            qid,
+            state: State::Idle,
+            records: VecDeque::new(),
+            summary: None,
+        }
+    }
+
+    /// Commit the transaction with [`Client::commit`]. Any results that have not been consumed
+    /// are discarded by the server.
+    pub async fn commit(self) -> CommunicationResult<Message> {
+        self.client.into_inner().commit().await
+    }
+
+    /// Roll back the transaction with [`Client::rollback`].
+    pub async fn rollback(self) -> CommunicationResult<Message> {
+        self.client.into_inner().rollback().await
+    }
+
+    /// Get the client back, e.g. to send other messages in the transaction.
+    pub fn into_inner(self) -> &'a mut Client<S> {
+        self.client.into_inner()
+    }
+}
+
+enum State<'t> {
+    Idle,
+    Pulling(BoxFuture<'t, CommunicationResult<(Vec<Record>, Message)>>),
+    Done,
+}
+
+/// A [`Stream`] of the records in one result of a [`Transaction`], created by
+/// [`Transaction::stream`].
+///
+/// A batch of records is requested once the consumer polls past the end of the previous batch.
+/// The stream ends once the server sends a summary message without a `has_more` entry, which is
+/// then available from [`TxRecordStream::summary`]. As with [`Client::pull`], records received
+/// before a [`FAILURE`](Message::Failure) summary should be considered invalid.
+///
+/// If the stream is dropped while it's reading a batch, the rest of the batch is read and
+/// discarded before the client sends its next request, so its records never reach another stream.
+pub struct TxRecordStream<'t, 'a, S: AsyncRead + AsyncWrite + Unpin> {
+    tx: &'t Transaction<'a, S>,
+    qid: i64,
+    state: State<'t>,
+    records: VecDeque<Record>,
+    summary: Option<Message>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> TxRecordStream<'_, '_, S> {
+    pub fn qid(&self) -> i64 {
+        self.qid
+    }
+
+    /// Get the summary message the server sent at the end of the result, if the stream has ended.
+    /// This is [`None`] if the stream ended with an error.
+    pub fn summary(&self) -> Option<&Message> {
+        self.summary.as_ref()
+    }
+
+    /// Get the [`StreamSummary`] of the result, if the stream has ended with a
+    /// [`SUCCESS`](Message::Success) message.
+    pub fn stream_summary(&self) -> Option<StreamSummary> {
+        match &self.summary {
+            Some(Message::Success(success)) => Some(StreamSummary::from_success(success)),
+            _ => None,
+        }
+    }
+}
+
+impl<'t, S: AsyncRead + AsyncWrite + Unpin + Send> Stream for TxRecordStream<'t, '_, S> {
+    type Item = CommunicationResult<Record>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if let Some(record) = this.records.pop_front() {
+                return Poll::Ready(Some(Ok(record)));
+            }
+            match std::mem::replace(&mut this.state, State::Done) {
+                State::Idle => {
+                    let tx: &'t Transaction<'_, S> = this.tx;
+                    let metadata =
+                        Metadata::from_iter([("n", tx.batch_size as i64), ("qid", this.qid)]);
+                    this.state = State::Pulling(
+                        async move { tx.client.lock().await.pull(Some(metadata)).await }.boxed(),
+                    );
+                }
+                State::Pulling(mut future) => match future.poll_unpin(cx) {
+                    Poll::Pending => {
+                        this.state = State::Pulling(future);
+                        return Poll::Pending;
+                    }
+                    Poll::Ready(Ok((records, response))) => {
+                        this.records.extend(records);
+                        match &response {
+                            Message::Success(success)
+                                if success.metadata().get("has_more")
+                                    == Some(&Value::Boolean(true)) =>
+                            {
+                                this.state = State::Idle;
+                            }
+                            _ => this.summary = Some(response),
+                        }
+                    }
+                    Poll::Ready(Err(error)) => return Poll::Ready(Some(Err(error))),
+                },
+                State::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> FusedStream for TxRecordStream<'_, '_, S> {
+    fn is_terminated(&self) -> bool {
+        matches!(self.state, State::Done) && self.records.is_empty()
+    }
+}
@@ -1,40 +1,59 @@
 #[cfg(test)]
 pub(crate) mod tests {
-    use std::env;
-
     use bolt_proto::{message::*, value::*, version::*, ServerState::*};
     use tokio::io::BufStream;
     use tokio_util::compat::*;
 
     use crate::{
         error::{CommunicationError, CommunicationResult, ConnectionResult, Result},
-        skip_if_handshake_failed, stream, Client, Metadata, Params,
+        skip_if_handshake_failed, stream, Client, Metadata, MetadataValidation, Params,
     };
 
     type Stream = Compat<BufStream<stream::Stream>>;
 
-    pub(crate) async fn new_client(version: u32) -> ConnectionResult<Client<Stream>> {
-        Client::new(
-            BufStream::new(
-                stream::Stream::connect(
-                    env::var("BOLT_TEST_ADDR").unwrap(),
-                    env::var("BOLT_TEST_DOMAIN").ok(),
-                )
-                .await?,
+    // The address, domain, username and password of the server to test the version against
+    async fn test_server(version: u32) -> (String, Option<String>, String, String) {
+        #[cfg(feature = "test-harness")]
+        {
+            let server = crate::test_harness::TestServer::for_version(version).await;
+            (
+                server.addr().to_string(),
+                server.domain().map(String::from),
+                server.username().to_string(),
+                server.password().to_string(),
             )
-            .compat(),
+        }
+        #[cfg(not(feature = "test-harness"))]
+        {
+            let _ = version;
+            (
+                std::env::var("BOLT_TEST_ADDR").unwrap(),
+                std::env::var("BOLT_TEST_DOMAIN").ok(),
+                std::env::var("BOLT_TEST_USERNAME").unwrap(),
+                std::env::var("BOLT_TEST_PASSWORD").unwrap(),
+            )
+        }
+    }
+
+    pub(crate) async fn new_client(version: u32) -> ConnectionResult<Client<Stream>> {
+        let (addr, domain, _, _) = test_server(version).await;
+        let mut client = Client::new(
+            BufStream::new(stream::Stream::connect(addr, domain).await?).compat(),
             &[version, 0, 0, 0],
         )
-        .await
+        .await?;
+        // The test queries send a `some_key` entry that no version supports
+        client.set_metadata_validation(MetadataValidation::Ignore);
+        Ok(client)
     }
 
     pub(crate) async fn initialize_client(
         client: &mut Client<Stream>,
         succeed: bool,
     ) -> CommunicationResult<Message> {
-        let username = env::var("BOLT_TEST_USERNAME").unwrap();
+        let (_, _, username, password) = test_server(client.raw_version()).await;
         let password = if succeed {
-            env::var("BOLT_TEST_PASSWORD").unwrap()
+            password
         } else {
             String::from("invalid")
         };
@@ -88,6 +107,7 @@ pub(crate) mod tests {
         let response = initialize_client(&mut client, true).await.unwrap();
         assert!(Success::try_from(response).is_ok());
         assert_eq!(client.server_state(), Ready);
+        assert!(client.server_agent().is_some());
     }
 
     #[tokio::test]
@@ -398,7 +418,7 @@ pub(crate) mod tests {
         let mut client = client.unwrap();
         assert!(matches!(
             client.commit().await,
-            Err(CommunicationError::UnsupportedOperation(V1_0))
+            Err(CommunicationError::UnsupportedOperation { version: V1_0, .. })
         ));
     }
 
@@ -408,14 +428,9 @@ pub(crate) mod tests {
         skip_if_handshake_failed!(client);
         let mut client = client.unwrap();
         let begin = Begin::new(Default::default());
-        client.send_message(Message::Begin(begin)).await.unwrap();
         assert!(matches!(
-            client.read_message().await,
-            Err(CommunicationError::ProtocolError(
-                bolt_proto::error::Error::DeserializationError(
-                    bolt_proto::error::DeserializationError::IoError(_)
-                )
-            ))
+            client.send_message(Message::Begin(begin)).await,
+            Err(CommunicationError::UnsupportedOperation { version: V1_0, .. })
         ));
     }
 }
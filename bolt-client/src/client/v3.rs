@@ -13,6 +13,8 @@ mod tests {
         let response = initialize_client(&mut client, true).await.unwrap();
         assert!(Success::try_from(response).is_ok());
         assert_eq!(client.server_state(), Ready);
+        assert!(client.server_agent().is_some());
+        assert!(client.connection_id().is_some());
     }
 
     #[tokio::test]
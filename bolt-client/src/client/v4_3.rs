@@ -18,6 +18,8 @@ mod tests {
         let response = initialize_client(&mut client, true).await.unwrap();
         assert!(Success::try_from(response).is_ok());
         assert_eq!(client.server_state(), Ready);
+        assert!(client.server_agent().is_some());
+        assert!(client.connection_id().is_some());
     }
 
     #[tokio::test]
@@ -377,7 +379,7 @@ mod tests {
             }
         }
 
-        assert_eq!(client.open_tx_streams, NUM_STREAMS);
+        assert_eq!(client.session.open_tx_streams.len(), NUM_STREAMS);
 
         for (n, qid) in qids {
             assert_eq!(client.server_state(), TxStreaming);
@@ -393,7 +395,7 @@ mod tests {
         }
 
         assert_eq!(client.server_state(), TxReady);
-        assert_eq!(client.open_tx_streams, 0);
+        assert_eq!(client.session.open_tx_streams.len(), 0);
     }
 
     #[tokio::test]
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use bolt_proto::Value;
+
+const DEFAULT_AGENT: &str = concat!("bolt-client/", env!("CARGO_PKG_VERSION"));
+
+/// Information identifying a client to the server, sent with [`Client::hello`](crate::Client::hello).
+///
+/// By default, this describes this crate, along with the target and compiler it was built with.
+/// Applications can override any of the fields to identify themselves instead:
+/// ```
+/// # use bolt_client::ClientInfo;
+/// let info = ClientInfo::new().with_user_agent("my-app/1.0.0");
+/// assert_eq!(info.user_agent(), "my-app/1.0.0");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientInfo {
+    user_agent: String,
+    product: String,
+    platform: Option<String>,
+    language: Option<String>,
+    language_details: Option<String>,
+}
+
+impl Default for ClientInfo {
+    fn default() -> Self {
+        let non_empty = |string: &str| (!string.is_empty()).then(|| string.to_string());
+        let rustc_version = env!("BOLT_CLIENT_RUSTC_VERSION");
+
+        Self {
+            user_agent: String::from(DEFAULT_AGENT),
+            product: String::from(DEFAULT_AGENT),
+            platform: non_empty(env!("BOLT_CLIENT_TARGET")),
+            // Version output looks like "rustc 1.75.0 (82e1608df 2023-12-21)"
+            language: Some(match rustc_version.split_whitespace().nth(1) {
+                Some(version) => format!("Rust/{}", version),
+                None => String::from("Rust"),
+            }),
+            language_details: non_empty(rustc_version),
+        }
+    }
+}
+
+impl ClientInfo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the user agent, which should conform to the format `"Name/Version"`.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Set the name and version of the driver, in the format `"Name/Version"`.
+    pub fn with_product(mut self, product: impl Into<String>) -> Self {
+        self.product = product.into();
+        self
+    }
+
+    pub fn with_platform(mut self, platform: Option<impl Into<String>>) -> Self {
+        self.platform = platform.map(Into::into);
+        self
+    }
+
+    pub fn with_language(mut self, language: Option<impl Into<String>>) -> Self {
+        self.language = language.map(Into::into);
+        self
+    }
+
+    pub fn with_language_details(mut self, language_details: Option<impl Into<String>>) -> Self {
+        self.language_details = language_details.map(Into::into);
+        self
+    }
+
+    pub fn user_agent(&self) -> &str {
+        &self.user_agent
+    }
+
+    pub fn product(&self) -> &str {
+        &self.product
+    }
+
+    pub fn platform(&self) -> Option<&str> {
+        self.platform.as_deref()
+    }
+
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    pub fn language_details(&self) -> Option<&str> {
+        self.language_details.as_deref()
+    }
+
+    /// Get the structured `bolt_agent` map describing this client, as defined for Bolt v5.3+.
+    pub fn bolt_agent(&self) -> HashMap<String, Value> {
+        let mut bolt_agent = HashMap::from_iter(vec![(
+            String::from("product"),
+            Value::from(self.product.as_str()),
+        )]);
+        let optional_fields = [
+            ("platform", &self.platform),
+            ("language", &self.language),
+            ("language_details", &self.language_details),
+        ];
+        for (key, value) in optional_fields {
+            if let Some(value) = value {
+                bolt_agent.insert(String::from(key), Value::from(value.as_str()));
+            }
+        }
+        bolt_agent
+    }
+
+    // Fill in any missing HELLO metadata. The bolt_agent entry is only understood by Bolt v5.3+,
+    // which is newer than any version supported by this crate, so only user_agent is added.
+    pub(crate) fn apply(&self, metadata: &mut HashMap<String, Value>) {
+        metadata
+            .entry(String::from("user_agent"))
+            .or_insert_with(|| Value::from(self.user_agent.as_str()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults() {
+        let info = ClientInfo::default();
+        assert_eq!(info.user_agent(), DEFAULT_AGENT);
+        assert_eq!(info.product(), DEFAULT_AGENT);
+        assert!(info.language().unwrap().starts_with("Rust"));
+
+        let bolt_agent = info.bolt_agent();
+        assert_eq!(bolt_agent.get("product"), Some(&Value::from(DEFAULT_AGENT)));
+        assert_eq!(
+            bolt_agent.contains_key("platform"),
+            info.platform().is_some()
+        );
+    }
+
+    #[test]
+    fn apply() {
+        let info = ClientInfo::new()
+            .with_user_agent("my-app/1.0.0")
+            .with_platform(None::<String>);
+        assert_eq!(info.bolt_agent().get("platform"), None);
+
+        let mut metadata = HashMap::new();
+        info.apply(&mut metadata);
+        assert_eq!(
+            metadata.get("user_agent"),
+            Some(&Value::from("my-app/1.0.0"))
+        );
+
+        // Explicit metadata takes priority
+        let mut metadata = HashMap::from_iter(vec![(
+            String::from("user_agent"),
+            Value::from("other/2.0.0"),
+        )]);
+        info.apply(&mut metadata);
+        assert_eq!(
+            metadata.get("user_agent"),
+            Some(&Value::from("other/2.0.0"))
+        );
+    }
+}
@@ -0,0 +1,316 @@
+//! Version-independent operations for libraries built on top of the client.
+//!
+//! The [`Client`] methods map directly onto Bolt messages, so some of them are only available for
+//! certain protocol versions, and others behave differently depending on the version negotiated
+//! with the server. The functions in this module work with every version the client supports,
+//! so that a library using them doesn't need its own version matrix:
+//!
+//! | Operation    | Bolt v1 - v2                    | Bolt v3                  | Bolt v4+                   |
+//! |--------------|---------------------------------|--------------------------|----------------------------|
+//! | [`init`]     | `INIT`                          | `HELLO`                  | `HELLO`                    |
+//! | [`run`]      | `RUN` (no metadata)             | `RUN` with metadata      | `RUN` with metadata        |
+//! | [`pull`]     | `PULL_ALL` (`n` ignored)        | `PULL_ALL` (`n` ignored) | `PULL` with `n`            |
+//! | [`discard`]  | `DISCARD_ALL`                   | `DISCARD_ALL`            | `DISCARD` with `n` of `-1` |
+//! | [`begin`]    | `RUN "BEGIN"` and `PULL_ALL`    | `BEGIN`                  | `BEGIN`                    |
+//! | [`commit`]   | `RUN "COMMIT"` and `PULL_ALL`   | `COMMIT`                 | `COMMIT`                   |
+//! | [`rollback`] | `RUN "ROLLBACK"` and `PULL_ALL` | `ROLLBACK`               | `ROLLBACK`                 |
+//! | [`reset`]    | `RESET`                         | `RESET`                  | `RESET`                    |
+//! | [`close`]    | nothing                         | `GOODBYE`                | `GOODBYE`                  |
+//!
+//! Metadata entries that the negotiated version doesn't support (e.g. `db` before Bolt v4, or
+//! anything but `bookmarks` for [`begin`] before Bolt v3) are handled according to the client's
+//! [`MetadataValidation`](crate::MetadataValidation) setting, as they are by the [`Client`]
+//! methods.
+//!
+//! For Bolt v1 - v2, explicit transactions are managed with `BEGIN`, `COMMIT`, and `ROLLBACK`
+//! queries, so the server stays in the [`Ready`](bolt_proto::ServerState::Ready) state during the
+//! transaction instead of entering the [`TxReady`](bolt_proto::ServerState::TxReady) state.
+//!
+//! ```
+//! # use std::env;
+//! # use bolt_client::*;
+//! # use bolt_proto::{message::*, version::*};
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let result = ClientBuilder::new(env::var("BOLT_TEST_ADDR")?)
+//! #     .with_domain(env::var("BOLT_TEST_DOMAIN").ok())
+//! #     .with_versions([V4_4, V3_0, V2_0, V1_0])
+//! #     .connect()
+//! #     .await;
+//! # skip_if_handshake_failed!(result, Ok(()));
+//! # let mut client = result.unwrap();
+//! # let metadata = Metadata::from_iter(vec![
+//! #     ("user_agent", "bolt-client/X.Y.Z"),
+//! #     ("scheme", "basic"),
+//! #     ("principal", &env::var("BOLT_TEST_USERNAME")?),
+//! #     ("credentials", &env::var("BOLT_TEST_PASSWORD")?),
+//! # ]);
+//! compat::init(&mut client, metadata).await?;
+//!
+//! compat::begin(&mut client, None).await?;
+//! compat::run(&mut client, "CREATE (:Counter {value: 0});", None, None).await?;
+//! compat::discard(&mut client).await?;
+//! let response = compat::commit(&mut client).await?;
+//! assert!(matches!(response, Message::Success(_)));
+//!
+//! compat::close(&mut client).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use bolt_proto::{message::Record, Message, Value};
+use futures_util::io::{AsyncRead, AsyncWrite};
+
+use crate::{error::CommunicationResult, Client, Feature, Metadata, Params};
+
+/// Initialize the connection, by sending an `INIT` message for Bolt v1 - v2 or a `HELLO` message
+/// otherwise. See [`Client::hello`].
+pub async fn init<S: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut Client<S>,
+    metadata: impl Into<Metadata>,
+) -> CommunicationResult<Message> {
+    client.hello(metadata).await
+}
+
+/// Run a query, sending `metadata` only if the version supports it. See [`Client::run`].
+pub async fn run<S: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut Client<S>,
+    query: impl Into<String>,
+    parameters: Option<Params>,
+    metadata: Option<Metadata>,
+) -> CommunicationResult<Message> {
+    client.run(query, parameters, metadata).await
+}
+
+/// Pull the next `n` records of the most recent result, or all of them if `n` is `-1` or the
+/// version is older than Bolt v4. See [`Client::pull_n`].
+pub async fn pull<S: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut Client<S>,
+    n: i64,
+) -> CommunicationResult<(Vec<Record>, Message)> {
+    client.pull_n(n).await
+}
+
+/// Discard all remaining records of the most recent result. See [`Client::discard_all`].
+pub async fn discard<S: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut Client<S>,
+) -> CommunicationResult<Message> {
+    client.discard_all().await
+}
+
+/// Start an explicit transaction. See [`Client::begin`].
+///
+/// For Bolt v1 - v2, a `BEGIN` query is run instead, with the `bookmarks` entry of `metadata` (if
+/// any) passed as the `bookmarks` parameter, and its last bookmark as the `bookmark` parameter.
+/// The response to the `RUN` message is returned if it isn't a `SUCCESS`, otherwise the response
+/// to the `PULL_ALL` message is returned.
+pub async fn begin<S: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut Client<S>,
+    metadata: Option<Metadata>,
+) -> CommunicationResult<Message> {
+    if client.supports(Feature::ExplicitTx) {
+        return client.begin(metadata).await;
+    }
+
+    let mut metadata = metadata.unwrap_or_default().value;
+    let bookmarks = metadata.remove("bookmarks");
+    client.validate_metadata(metadata.into_keys().collect())?;
+
+    let mut parameters = Params::default();
+    if let Some(Value::List(bookmarks)) = bookmarks {
+        if let Some(bookmark) = bookmarks.last() {
+            parameters
+                .value
+                .insert(String::from("bookmark"), bookmark.clone());
+        }
+        parameters
+            .value
+            .insert(String::from("bookmarks"), Value::List(bookmarks));
+    }
+    run_tx_query(client, "BEGIN", Some(parameters)).await
+}
+
+/// Commit the current explicit transaction. See [`Client::commit`].
+///
+/// For Bolt v1 - v2, a `COMMIT` query is run instead, as for [`begin`].
+pub async fn commit<S: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut Client<S>,
+) -> CommunicationResult<Message> {
+    match client.supports(Feature::ExplicitTx) {
+        true => client.commit().await,
+        false => run_tx_query(client, "COMMIT", None).await,
+    }
+}
+
+/// Roll back the current explicit transaction. See [`Client::rollback`].
+///
+/// For Bolt v1 - v2, a `ROLLBACK` query is run instead, as for [`begin`].
+pub async fn rollback<S: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut Client<S>,
+) -> CommunicationResult<Message> {
+    match client.supports(Feature::ExplicitTx) {
+        true => client.rollback().await,
+        false => run_tx_query(client, "ROLLBACK", None).await,
+    }
+}
+
+/// Reset the connection, discarding any pending results and rolling back the current transaction.
+/// This also clears a failure, for all versions. See [`Client::reset`].
+pub async fn reset<S: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut Client<S>,
+) -> CommunicationResult<Message> {
+    client.reset().await
+}
+
+/// Notify the server that the connection is about to be closed, if the version supports it. See
+/// [`Client::goodbye`].
+pub async fn close<S: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut Client<S>,
+) -> CommunicationResult<()> {
+    match client.supports(Feature::ExplicitTx) {
+        true => client.goodbye().await,
+        false => Ok(()),
+    }
+}
+
+async fn run_tx_query<S: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut Client<S>,
+    query: &str,
+    parameters: Option<Params>,
+) -> CommunicationResult<Message> {
+    match client.run(query, parameters, None).await? {
+        Message::Success(_) => Ok(client.pull_all().await?.1),
+        response => Ok(response),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use bolt_proto::{message::*, version::*};
+    use tokio::{
+        io::{duplex, DuplexStream},
+        task::JoinHandle,
+    };
+    use tokio_util::compat::*;
+
+    use crate::error::CommunicationError;
+
+    use super::*;
+
+    type MockClient = Client<Compat<DuplexStream>>;
+
+    // Create a client connected to a server that replies to each received message with SUCCESS.
+    // The server returns the messages it received once the client is dropped.
+    async fn mock_client(version: u32) -> (MockClient, JoinHandle<Vec<Message>>) {
+        let (client_stream, server_stream) = duplex(1024);
+        let server = tokio::spawn(async move {
+            let mut server_stream = server_stream.compat();
+            let mut request = [0; 20];
+            futures_util::io::AsyncReadExt::read_exact(&mut server_stream, &mut request)
+                .await
+                .unwrap();
+            futures_util::io::AsyncWriteExt::write_all(&mut server_stream, &version.to_be_bytes())
+                .await
+                .unwrap();
+            let mut received = Vec::new();
+            while let Ok(message) = Message::from_stream(&mut server_stream).await {
+                received.push(message);
+                let reply = Message::Success(Success::new(Default::default()));
+                for chunk in reply.into_chunks().unwrap() {
+                    // The client doesn't wait for a reply to GOODBYE
+                    let _ = futures_util::io::AsyncWriteExt::write_all(&mut server_stream, &chunk)
+                        .await;
+                }
+            }
+            received
+        });
+        let mut client = Client::new(client_stream.compat(), &[version, 0, 0, 0])
+            .await
+            .unwrap();
+        init(&mut client, Metadata::default()).await.unwrap();
+        (client, server)
+    }
+
+    // Close the client's connection and get the messages the server received after initialization
+    async fn received(client: MockClient, server: JoinHandle<Vec<Message>>) -> Vec<Message> {
+        drop(client);
+        server.await.unwrap().split_off(1)
+    }
+
+    #[tokio::test]
+    async fn emulated_transaction() {
+        let (mut client, server) = mock_client(V2_0).await;
+        let metadata = Metadata::from_iter([("bookmarks", vec!["bk:1", "bk:2"])]);
+        begin(&mut client, Some(metadata)).await.unwrap();
+        run(&mut client, "RETURN 1;", None, None).await.unwrap();
+        pull(&mut client, 10).await.unwrap();
+        commit(&mut client).await.unwrap();
+        begin(&mut client, None).await.unwrap();
+        rollback(&mut client).await.unwrap();
+        close(&mut client).await.unwrap();
+
+        let run = |query: &str, parameters: Params| {
+            Message::Run(Run::new(query.into(), parameters.value))
+        };
+        let bookmarks = Value::from(vec!["bk:1", "bk:2"]);
+        assert_eq!(
+            received(client, server).await,
+            [
+                run(
+                    "BEGIN",
+                    Params::from_iter([
+                        ("bookmark", Value::from("bk:2")),
+                        ("bookmarks", bookmarks)
+                    ]),
+                ),
+                Message::PullAll,
+                run("RETURN 1;", Params::default()),
+                Message::PullAll,
+                run("COMMIT", Params::default()),
+                Message::PullAll,
+                run("BEGIN", Params::default()),
+                Message::PullAll,
+                run("ROLLBACK", Params::default()),
+                Message::PullAll,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn explicit_transaction() {
+        let (mut client, server) = mock_client(V4_4).await;
+        begin(&mut client, None).await.unwrap();
+        run(&mut client, "RETURN 1;", None, None).await.unwrap();
+        discard(&mut client).await.unwrap();
+        commit(&mut client).await.unwrap();
+        close(&mut client).await.unwrap();
+
+        assert_eq!(
+            received(client, server).await,
+            [
+                Message::Begin(Begin::new(HashMap::new())),
+                Message::RunWithMetadata(RunWithMetadata::new(
+                    "RETURN 1;".into(),
+                    HashMap::new(),
+                    HashMap::new(),
+                )),
+                Message::Discard(Discard::new(Metadata::from_iter([("n", -1)]).value)),
+                Message::Commit,
+                Message::Goodbye,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn unsupported_begin_metadata() {
+        let (mut client, server) = mock_client(V1_0).await;
+        let metadata = Metadata::from_iter([("mode", "r")]);
+        assert!(matches!(
+            begin(&mut client, Some(metadata)).await,
+            Err(CommunicationError::UnsupportedMetadata { keys, .. }) if keys == ["mode"]
+        ));
+        assert!(received(client, server).await.is_empty());
+    }
+}
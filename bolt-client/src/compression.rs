@@ -0,0 +1,400 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
+use futures_util::io::{AsyncRead, AsyncWrite};
+
+const BUF_SIZE: usize = 8 * 1024;
+
+/// A transparent compression layer over a stream, using the zlib format.
+///
+/// Bytes written to a `CompressedStream` are compressed before being passed to the inner stream,
+/// and bytes read from it are decompressed. Each flush finishes a zlib sync block, so a full Bolt
+/// message can always be decoded by the peer as soon as the client has sent it.
+///
+/// Servers do not understand compressed Bolt connections, so the remote end must be something
+/// that decompresses the traffic before it reaches the server, like a tunnel or proxy.
+///
+/// `CompressedStream` implements the [`futures_util::io`] traits when the inner stream does, and
+/// also the [`tokio::io`] traits when the `tokio-stream` feature is enabled. This means it can be
+/// placed directly around a [`Stream`](crate::Stream), whether or not TLS is used:
+/// ```no_run
+/// # use bolt_client::*;
+/// # use tokio::io::BufStream;
+/// # use tokio_util::compat::*;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let stream = Stream::connect("127.0.0.1:7687", Some("db.example.com")).await?;
+/// let stream = BufStream::new(CompressedStream::new(stream)).compat();
+/// let mut client = Client::new(stream, &[bolt_proto::version::V4_4, 0, 0, 0]).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+#[derive(Debug)]
+pub struct CompressedStream<S> {
+    inner: S,
+    compress: Compress,
+    decompress: Decompress,
+    // Compressed bytes waiting to be written to the inner stream
+    write_buf: Vec<u8>,
+    write_pos: usize,
+    // Compressed bytes read from the inner stream that have not been decompressed yet
+    read_buf: Vec<u8>,
+    read_pos: usize,
+    needs_flush: bool,
+    finished: bool,
+}
+
+impl<S> CompressedStream<S> {
+    /// Wrap a stream using the default compression level.
+    pub fn new(inner: S) -> Self {
+        Self::with_compression(inner, Compression::default())
+    }
+
+    /// Wrap a stream using the given compression level, from 0 (no compression) to 9 (best
+    /// compression).
+    pub fn with_level(inner: S, level: u32) -> Self {
+        Self::with_compression(inner, Compression::new(level))
+    }
+
+    fn with_compression(inner: S, compression: Compression) -> Self {
+        Self {
+            inner,
+            compress: Compress::new(compression, true),
+            decompress: Decompress::new(true),
+            write_buf: Vec::with_capacity(BUF_SIZE),
+            write_pos: 0,
+            read_buf: Vec::with_capacity(BUF_SIZE),
+            read_pos: 0,
+            needs_flush: false,
+            finished: false,
+        }
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Consume the `CompressedStream`, returning the inner stream. Any buffered data that has not
+    /// been flushed is lost.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+// The compression logic is shared between the futures and tokio IO traits, which only differ in
+// how the inner stream is polled.
+impl<S: Unpin> CompressedStream<S> {
+    fn poll_read_with<R>(
+        &mut self,
+        cx: &mut Context<'_>,
+        out: &mut [u8],
+        mut read: R,
+    ) -> Poll<io::Result<usize>>
+    where
+        R: FnMut(Pin<&mut S>, &mut Context<'_>, &mut [u8]) -> Poll<io::Result<usize>>,
+    {
+        if out.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        loop {
+            // The decompressor may still hold output from input it has already consumed, so this
+            // is done even if there is no new input
+            let (total_in, total_out) = (self.decompress.total_in(), self.decompress.total_out());
+            let status = self
+                .decompress
+                .decompress(&self.read_buf[self.read_pos..], out, FlushDecompress::None)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            let consumed = (self.decompress.total_in() - total_in) as usize;
+            let produced = (self.decompress.total_out() - total_out) as usize;
+            self.read_pos += consumed;
+
+            if produced > 0 {
+                return Poll::Ready(Ok(produced));
+            }
+            if status == Status::StreamEnd {
+                return Poll::Ready(Ok(0));
+            }
+            if consumed > 0 {
+                continue;
+            }
+
+            // More compressed input is needed before anything can be decompressed
+            self.read_buf.drain(..self.read_pos);
+            self.read_pos = 0;
+            let filled = self.read_buf.len();
+            self.read_buf.resize(filled + BUF_SIZE, 0);
+            let result = read(Pin::new(&mut self.inner), cx, &mut self.read_buf[filled..]);
+            let bytes_read = match result {
+                Poll::Ready(Ok(bytes_read)) => bytes_read,
+                other => {
+                    self.read_buf.truncate(filled);
+                    return other;
+                }
+            };
+            self.read_buf.truncate(filled + bytes_read);
+
+            if bytes_read == 0 {
+                return Poll::Ready(Ok(0));
+            }
+        }
+    }
+
+    fn poll_write_with<W>(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+        mut write: W,
+    ) -> Poll<io::Result<usize>>
+    where
+        W: FnMut(Pin<&mut S>, &mut Context<'_>, &[u8]) -> Poll<io::Result<usize>>,
+    {
+        // Avoid buffering an unbounded amount of compressed data if the inner stream is slow
+        if self.write_buf.len() - self.write_pos >= BUF_SIZE {
+            ready!(self.poll_drain(cx, &mut write))?;
+        }
+
+        let total_in = self.compress.total_in();
+        while self.compress.total_in() == total_in && !buf.is_empty() {
+            self.write_buf.reserve(buf.len() / 2 + 64);
+            self.compress
+                .compress_vec(buf, &mut self.write_buf, FlushCompress::None)
+                .map_err(io::Error::other)?;
+        }
+        self.needs_flush = true;
+
+        Poll::Ready(Ok((self.compress.total_in() - total_in) as usize))
+    }
+
+    fn poll_flush_with<W, F>(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut write: W,
+        flush: F,
+    ) -> Poll<io::Result<()>>
+    where
+        W: FnMut(Pin<&mut S>, &mut Context<'_>, &[u8]) -> Poll<io::Result<usize>>,
+        F: FnOnce(Pin<&mut S>, &mut Context<'_>) -> Poll<io::Result<()>>,
+    {
+        if self.needs_flush {
+            self.compress_remaining(FlushCompress::Sync)?;
+            self.needs_flush = false;
+        }
+        ready!(self.poll_drain(cx, &mut write))?;
+        flush(Pin::new(&mut self.inner), cx)
+    }
+
+    fn poll_close_with<W, C>(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut write: W,
+        close: C,
+    ) -> Poll<io::Result<()>>
+    where
+        W: FnMut(Pin<&mut S>, &mut Context<'_>, &[u8]) -> Poll<io::Result<usize>>,
+        C: FnOnce(Pin<&mut S>, &mut Context<'_>) -> Poll<io::Result<()>>,
+    {
+        if !self.finished {
+            self.compress_remaining(FlushCompress::Finish)?;
+            self.needs_flush = false;
+            self.finished = true;
+        }
+        ready!(self.poll_drain(cx, &mut write))?;
+        close(Pin::new(&mut self.inner), cx)
+    }
+
+    // Move all pending output from the compressor into the write buffer.
+    fn compress_remaining(&mut self, flush: FlushCompress) -> io::Result<()> {
+        loop {
+            self.write_buf.reserve(BUF_SIZE);
+            let status = self
+                .compress
+                .compress_vec(&[], &mut self.write_buf, flush)
+                .map_err(io::Error::other)?;
+            // The compressor is done once it stops filling all the available space
+            if status == Status::StreamEnd
+                || (flush != FlushCompress::Finish
+                    && self.write_buf.len() < self.write_buf.capacity())
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    fn poll_drain<W>(&mut self, cx: &mut Context<'_>, write: &mut W) -> Poll<io::Result<()>>
+    where
+        W: FnMut(Pin<&mut S>, &mut Context<'_>, &[u8]) -> Poll<io::Result<usize>>,
+    {
+        while self.write_pos < self.write_buf.len() {
+            let bytes_written = ready!(write(
+                Pin::new(&mut self.inner),
+                cx,
+                &self.write_buf[self.write_pos..]
+            ))?;
+            if bytes_written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.write_pos += bytes_written;
+        }
+
+        self.write_buf.clear();
+        self.write_pos = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CompressedStream<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut()
+            .poll_read_with(cx, buf, |inner, cx, buf| inner.poll_read(cx, buf))
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CompressedStream<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut()
+            .poll_write_with(cx, buf, |inner, cx, buf| inner.poll_write(cx, buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_flush_with(
+            cx,
+            |inner, cx, buf| inner.poll_write(cx, buf),
+            |inner, cx| inner.poll_flush(cx),
+        )
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_close_with(
+            cx,
+            |inner, cx, buf| inner.poll_write(cx, buf),
+            |inner, cx| inner.poll_close(cx),
+        )
+    }
+}
+
+#[cfg(feature = "tokio-stream")]
+impl<S: tokio::io::AsyncRead + Unpin> tokio::io::AsyncRead for CompressedStream<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let bytes_read = ready!(self.get_mut().poll_read_with(
+            cx,
+            buf.initialize_unfilled(),
+            |inner, cx, buf| {
+                let mut buf = tokio::io::ReadBuf::new(buf);
+                ready!(inner.poll_read(cx, &mut buf))?;
+                Poll::Ready(Ok(buf.filled().len()))
+            }
+        ))?;
+        buf.advance(bytes_read);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio-stream")]
+impl<S: tokio::io::AsyncWrite + Unpin> tokio::io::AsyncWrite for CompressedStream<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut()
+            .poll_write_with(cx, buf, |inner, cx, buf| inner.poll_write(cx, buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_flush_with(
+            cx,
+            |inner, cx, buf| inner.poll_write(cx, buf),
+            |inner, cx| inner.poll_flush(cx),
+        )
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_close_with(
+            cx,
+            |inner, cx, buf| inner.poll_write(cx, buf),
+            |inner, cx| inner.poll_shutdown(cx),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_util::compat::*;
+
+    use bolt_proto::{message::*, Message, Value};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn round_trip() {
+        let (client, server) = tokio::io::duplex(1024);
+        let mut client = CompressedStream::new(client.compat());
+        let mut server = CompressedStream::new(server.compat());
+
+        let message = Message::Record(Record::new(vec![Value::from("abc".repeat(50_000))]));
+        let chunks = message.clone().into_chunks().unwrap();
+        let bytes = chunks.concat();
+
+        let len = bytes.len();
+        let send = tokio::spawn(async move {
+            for chunk in chunks {
+                client.write_all(&chunk).await.unwrap();
+            }
+            client.flush().await.unwrap();
+            client
+        });
+        let mut received = vec![0; len];
+        server.read_exact(&mut received).await.unwrap();
+        assert_eq!(received, bytes);
+        let mut client = send.await.unwrap();
+
+        // The reply is readable after a flush, without closing the stream
+        server.write_all(&[0, 2, 0xB0, 0x0F, 0, 0]).await.unwrap();
+        server.flush().await.unwrap();
+        assert_eq!(
+            Message::from_stream(&mut client).await.unwrap(),
+            Message::Reset
+        );
+
+        client.close().await.unwrap();
+        let mut rest = Vec::new();
+        server.read_to_end(&mut rest).await.unwrap();
+        assert!(rest.is_empty());
+    }
+
+    #[tokio::test]
+    async fn invalid_data() {
+        let (mut client, server) = tokio::io::duplex(1024);
+        let mut server = CompressedStream::new(server.compat());
+        tokio::io::AsyncWriteExt::write_all(&mut client, &[0xFF; 32])
+            .await
+            .unwrap();
+
+        let mut buf = [0; 32];
+        let err = server.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
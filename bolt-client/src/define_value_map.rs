@@ -4,11 +4,88 @@ macro_rules! define_value_map {
     ($T:ident) => {
         /// A map from [`String`]s to [`Value`](bolt_proto::Value)s used primarily to provide
         /// supplementary information to [`Client`] methods.
-        #[derive(Debug, Default, Clone)]
+        ///
+        /// The [`Debug`](::std::fmt::Debug) output lists entries in order of their keys, and hides
+        /// the values of keys such as `credentials` and `password`, including in nested maps, so
+        /// that it can be logged safely.
+        #[derive(Default, Clone)]
         pub struct $T {
             pub(crate) value: ::std::collections::HashMap<std::string::String, ::bolt_proto::Value>,
         }
 
+        impl $T {
+            pub fn get(&self, key: &str) -> ::std::option::Option<&::bolt_proto::Value> {
+                self.value.get(key)
+            }
+
+            pub fn contains_key(&self, key: &str) -> bool {
+                self.value.contains_key(key)
+            }
+
+            pub fn len(&self) -> usize {
+                self.value.len()
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.value.is_empty()
+            }
+
+            /// Iterate over the entries, in arbitrary order.
+            pub fn iter(
+                &self,
+            ) -> ::std::collections::hash_map::Iter<'_, ::std::string::String, ::bolt_proto::Value>
+            {
+                self.value.iter()
+            }
+        }
+
+        impl ::std::fmt::Debug for $T {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.debug_tuple(::std::stringify!($T))
+                    .field(&$crate::redact::RedactedMap(&self.value))
+                    .finish()
+            }
+        }
+
+        /// Serialized as a map with its entries in order of their keys, with values serialized as
+        /// described in [`Value`](bolt_proto::Value)'s `Serialize` implementation. Temporal values
+        /// are serialized as strings, so they are deserialized as strings.
+        #[cfg(feature = "serde")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+        impl ::serde::Serialize for $T {
+            fn serialize<S: ::serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> ::std::result::Result<S::Ok, S::Error> {
+                let mut entries: ::std::vec::Vec<_> = self.value.iter().collect();
+                entries.sort_unstable_by_key(|(key, _)| *key);
+                serializer.collect_map(entries)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+        impl<'de> ::serde::Deserialize<'de> for $T {
+            fn deserialize<D: ::serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> ::std::result::Result<Self, D::Error> {
+                <::std::collections::HashMap<_, _> as ::serde::Deserialize>::deserialize(
+                    deserializer,
+                )
+                .map(|value| Self { value })
+            }
+        }
+
+        impl<'a> ::std::iter::IntoIterator for &'a $T {
+            type Item = (&'a ::std::string::String, &'a ::bolt_proto::Value);
+            type IntoIter =
+                ::std::collections::hash_map::Iter<'a, ::std::string::String, ::bolt_proto::Value>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.value.iter()
+            }
+        }
+
         impl<K, V, S> ::std::convert::From<::std::collections::HashMap<K, V, S>> for $T
         where
             K: ::std::convert::Into<::std::string::String>,
@@ -22,6 +99,18 @@ macro_rules! define_value_map {
             }
         }
 
+        impl ::std::convert::From<&$T> for $T {
+            fn from(map: &$T) -> Self {
+                map.clone()
+            }
+        }
+
+        impl ::std::convert::From<::std::sync::Arc<$T>> for $T {
+            fn from(map: ::std::sync::Arc<$T>) -> Self {
+                ::std::sync::Arc::unwrap_or_clone(map)
+            }
+        }
+
         impl<K, V> ::std::iter::FromIterator<(K, V)> for $T
         where
             K: Eq + ::std::hash::Hash + ::std::convert::Into<std::string::String>,
@@ -37,3 +126,54 @@ macro_rules! define_value_map {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use bolt_proto::Value;
+
+    use crate::{Metadata, Params};
+
+    #[test]
+    fn accessors() {
+        let params = Params::from_iter([("a", 1), ("b", 2)]);
+        assert_eq!(params.len(), 2);
+        assert!(!params.is_empty());
+        assert!(params.contains_key("a"));
+        assert!(!params.contains_key("c"));
+        assert_eq!(params.get("b"), Some(&Value::from(2)));
+        assert_eq!(params.iter().count(), 2);
+        assert_eq!((&params).into_iter().count(), 2);
+        assert!(Params::default().is_empty());
+    }
+
+    #[test]
+    fn debug() {
+        let metadata = Metadata::from_iter([
+            ("scheme", Value::from("basic")),
+            ("principal", Value::from("neo4j")),
+            ("credentials", Value::from("password")),
+            (
+                "auth",
+                Value::from(std::collections::HashMap::from([("Token", "secret")])),
+            ),
+        ]);
+        assert_eq!(
+            format!("{:?}", metadata),
+            r#"Metadata({"auth": Map({"Token": <redacted>}), "credentials": <redacted>, "principal": String("neo4j"), "scheme": String("basic")})"#
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let metadata = Metadata::from_iter([
+            ("scheme", Value::from("basic")),
+            ("tags", Value::from(vec!["a", "b"])),
+            ("n", Value::from(1)),
+        ]);
+        let json = serde_json::to_string(&metadata).unwrap();
+        assert_eq!(json, r#"{"n":1,"scheme":"basic","tags":["a","b"]}"#);
+        let deserialized: Metadata = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.value, metadata.value);
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use futures_util::future::BoxFuture;
+
+/// A connection pool that can be drained when the application shuts down. The pool adapters
+/// ([bb8-bolt](https://crates.io/crates/bb8-bolt),
+/// [deadpool-bolt](https://crates.io/crates/deadpool-bolt), and
+/// [mobc-bolt](https://crates.io/crates/mobc-bolt)) each provide a `DrainHandle` implementing this
+/// trait, so that a framework's shutdown sequence can drain any of them the same way.
+///
+/// Once a pool is draining, connections that are in use can finish their queries, but are closed
+/// instead of being returned to the pool. Idle connections are closed after sending `GOODBYE`, and
+/// requests for new connections fail with a
+/// [`Draining`](crate::error::ConnectionError::Draining) error.
+pub trait Drain: Send + Sync {
+    /// Start draining the pool, returning once its idle connections have been closed. Draining
+    /// can't be undone.
+    fn drain(&self) -> BoxFuture<'_, ()>;
+
+    /// Check whether the pool has started draining.
+    fn is_draining(&self) -> bool;
+}
+
+/// A flag shared by a pool's connection manager and the `DrainHandle` that drains the pool, which
+/// the manager checks before opening or handing out a connection. Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct DrainSignal(Arc<AtomicBool>);
+
+impl DrainSignal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark the pool as draining.
+    pub fn start(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drain_signal() {
+        let signal = DrainSignal::new();
+        let shared = signal.clone();
+        assert!(!shared.is_draining());
+        signal.start();
+        assert!(shared.is_draining());
+        assert!(!DrainSignal::new().is_draining());
+    }
+}
@@ -1,4 +1,9 @@
-use bolt_proto::{error::Error as ProtocolError, Message, ServerState};
+use bolt_proto::{
+    error::{Error as ProtocolError, ErrorKind as ProtocolErrorKind},
+    message::{Failure, MessageKind},
+    version::Version,
+    Message, ServerState,
+};
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -6,6 +11,7 @@ pub type ConnectionResult<T> = std::result::Result<T, ConnectionError>;
 pub type CommunicationResult<T> = std::result::Result<T, CommunicationError>;
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
     #[error(transparent)]
     ConnectionError(#[from] ConnectionError),
@@ -13,6 +19,34 @@ pub enum Error {
     CommunicationError(Box<CommunicationError>),
     #[error(transparent)]
     ProtocolError(#[from] ProtocolError),
+    #[error(transparent)]
+    UriError(#[from] UriError),
+    #[error(transparent)]
+    BookmarkError(#[from] BookmarkError),
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::ConnectionError(error) => ErrorKind::ConnectionError(error.kind()),
+            Error::CommunicationError(error) => ErrorKind::CommunicationError(error.kind()),
+            Error::ProtocolError(error) => ErrorKind::ProtocolError(error.kind()),
+            Error::UriError(error) => ErrorKind::UriError(error.kind()),
+            Error::BookmarkError(error) => ErrorKind::BookmarkError(error.kind()),
+        }
+    }
+}
+
+/// The kind of an [`Error`], without any of its contents. Unlike the errors themselves, kinds are
+/// cheap to copy and compare, e.g. to decide whether to retry or to count errors by kind.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    ConnectionError(ConnectionErrorKind),
+    CommunicationError(CommunicationErrorKind),
+    ProtocolError(ProtocolErrorKind),
+    UriError(UriErrorKind),
+    BookmarkError(BookmarkErrorKind),
 }
 
 impl From<CommunicationError> for Error {
@@ -22,14 +56,110 @@ impl From<CommunicationError> for Error {
 }
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum ConnectionError {
-    #[error("handshake with server failed for versions [{}]", format_versions(.0))]
-    HandshakeFailed([u32; 4]),
+    /// The server supports none of the proposed protocol versions, and responded to the
+    /// handshake with zero.
+    #[error(
+        "handshake with server failed for versions [{}] (server responded with no supported \
+         version)",
+        format_versions(.specifiers)
+    )]
+    HandshakeFailed {
+        specifiers: [u32; 4],
+        response: [u8; 4],
+    },
+    /// The server chose a protocol version that matches none of the proposed version specifiers.
+    #[error(
+        "server chose version {} ({:02x?}), which is not one of the proposed versions [{}]",
+        format_version(u32::from_be_bytes(*.response)),
+        .response,
+        format_versions(.specifiers)
+    )]
+    IncompatibleVersion {
+        specifiers: [u32; 4],
+        response: [u8; 4],
+    },
+    /// The server closed the connection before sending all 4 bytes of its handshake response.
+    #[error(
+        "server closed the connection after sending {} of 4 handshake response bytes {:02x?}",
+        .received.len(),
+        .received
+    )]
+    TruncatedHandshakeResponse { received: Vec<u8> },
+    /// The server responded to the handshake with HTTP, so the address is likely the server's HTTP
+    /// port, such as the one serving Neo4j Browser, instead of its Bolt port.
+    #[error(
+        "server responded to the handshake with HTTP; this looks like an HTTP port (e.g. Neo4j \
+         Browser on 7474), connect to the Bolt port instead (7687 by default)"
+    )]
+    HttpResponse,
+    /// The server responded to the handshake with a TLS record, so it likely only accepts
+    /// encrypted connections.
+    #[error(
+        "server responded to the handshake with TLS; it likely requires an encrypted connection, \
+         set a domain (or use a +s URI) to connect with TLS"
+    )]
+    TlsResponse,
+    /// The server's handshake response is not a protocol version, so the address is likely not a
+    /// Bolt server.
+    #[error(
+        "server gave malformed handshake response {response:02x?}; check that the address is a \
+         Bolt server"
+    )]
+    MalformedHandshakeResponse { response: [u8; 4] },
+    /// The server chose a protocol version older than the minimum set with
+    /// [`ClientBuilder::with_min_version`](crate::ClientBuilder::with_min_version).
+    #[error("server chose version {version}, but at least version {min_version} is required")]
+    VersionTooOld {
+        version: Version,
+        min_version: Version,
+    },
+    /// The pool is draining, so it no longer opens or hands out connections. See
+    /// [`Drain`](crate::Drain).
+    #[error("connection pool is draining")]
+    Draining,
     #[error(transparent)]
     IoError(#[from] std::io::Error),
 }
 
+impl ConnectionError {
+    pub fn kind(&self) -> ConnectionErrorKind {
+        match self {
+            ConnectionError::HandshakeFailed { .. } => ConnectionErrorKind::HandshakeFailed,
+            ConnectionError::IncompatibleVersion { .. } => ConnectionErrorKind::IncompatibleVersion,
+            ConnectionError::TruncatedHandshakeResponse { .. } => {
+                ConnectionErrorKind::TruncatedHandshakeResponse
+            }
+            ConnectionError::HttpResponse => ConnectionErrorKind::HttpResponse,
+            ConnectionError::TlsResponse => ConnectionErrorKind::TlsResponse,
+            ConnectionError::MalformedHandshakeResponse { .. } => {
+                ConnectionErrorKind::MalformedHandshakeResponse
+            }
+            ConnectionError::VersionTooOld { .. } => ConnectionErrorKind::VersionTooOld,
+            ConnectionError::Draining => ConnectionErrorKind::Draining,
+            ConnectionError::IoError(error) => ConnectionErrorKind::IoError(error.kind()),
+        }
+    }
+}
+
+/// The kind of a [`ConnectionError`], without any of its contents.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ConnectionErrorKind {
+    HandshakeFailed,
+    IncompatibleVersion,
+    TruncatedHandshakeResponse,
+    HttpResponse,
+    TlsResponse,
+    MalformedHandshakeResponse,
+    VersionTooOld,
+    Draining,
+    IoError(std::io::ErrorKind),
+}
+
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum CommunicationError {
     #[error(
         "server gave unexpected response while in {state:?} state.
@@ -46,14 +176,258 @@ response: {response:?}"
         state: ServerState,
         message: Message,
     },
-    #[error("unsupported operation for client with version = {}", format_version(*.0))]
-    UnsupportedOperation(u32),
+    /// A method or message is not supported by the negotiated protocol version.
+    #[error(
+        "unsupported operation {operation} for client with version = {}",
+        format_version(*.version)
+    )]
+    UnsupportedOperation {
+        /// The name of the [`Client`](crate::Client) method, or of the message, e.g. `"BEGIN"`.
+        operation: &'static str,
+        version: u32,
+    },
+    #[error(
+        "unsupported metadata for client with version = {}: {}",
+        format_version(*.version),
+        .keys.join(", ")
+    )]
+    UnsupportedMetadata { keys: Vec<String>, version: u32 },
+    #[error("server closed the connection while handling {during:?}")]
+    ServerClosedConnection { during: MessageKind },
     #[error(transparent)]
     ProtocolError(#[from] ProtocolError),
     #[error(transparent)]
     IoError(#[from] std::io::Error),
 }
 
+impl CommunicationError {
+    pub fn kind(&self) -> CommunicationErrorKind {
+        match self {
+            CommunicationError::InvalidResponse { .. } => CommunicationErrorKind::InvalidResponse,
+            CommunicationError::InvalidState { .. } => CommunicationErrorKind::InvalidState,
+            CommunicationError::UnsupportedOperation { .. } => {
+                CommunicationErrorKind::UnsupportedOperation
+            }
+            CommunicationError::UnsupportedMetadata { .. } => {
+                CommunicationErrorKind::UnsupportedMetadata
+            }
+            CommunicationError::ServerClosedConnection { .. } => {
+                CommunicationErrorKind::ServerClosedConnection
+            }
+            CommunicationError::ProtocolError(error) => {
+                CommunicationErrorKind::ProtocolError(error.kind())
+            }
+            CommunicationError::IoError(error) => CommunicationErrorKind::IoError(error.kind()),
+        }
+    }
+}
+
+/// The kind of a [`CommunicationError`], without any of its contents.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum CommunicationErrorKind {
+    InvalidResponse,
+    InvalidState,
+    UnsupportedOperation,
+    UnsupportedMetadata,
+    ServerClosedConnection,
+    ProtocolError(ProtocolErrorKind),
+    IoError(std::io::ErrorKind),
+}
+
+/// An error from [`Client::read_transaction`](crate::Client::read_transaction) or
+/// [`Client::write_transaction`](crate::Client::write_transaction), returned once the transaction
+/// can't be retried.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TransactionError<E> {
+    /// The transaction function returned an error.
+    #[error("transaction function failed: {0}")]
+    Work(E),
+    /// The server failed the transaction, including when the transaction function ignored a
+    /// failure and returned successfully.
+    #[error("server failed the transaction: {0}")]
+    Failed(Failure),
+    #[error(transparent)]
+    CommunicationError(Box<CommunicationError>),
+}
+
+impl<E> From<CommunicationError> for TransactionError<E> {
+    fn from(error: CommunicationError) -> Self {
+        TransactionError::CommunicationError(Box::new(error))
+    }
+}
+
+/// An error from one of the database administration methods in the [`admin`](crate::admin)
+/// module.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum AdminError {
+    /// The server failed the administration query.
+    #[error("server failed the administration query: {0}")]
+    Failed(Failure),
+    /// The database reported an error while changing its status.
+    #[error("database {name} failed: {error}")]
+    DatabaseFailed { name: String, error: String },
+    /// The database did not come online in time.
+    #[error("database {name} did not come online in time (status: {status})")]
+    Timeout { name: String, status: String },
+    #[error(transparent)]
+    CommunicationError(Box<CommunicationError>),
+}
+
+impl From<CommunicationError> for AdminError {
+    fn from(error: CommunicationError) -> Self {
+        AdminError::CommunicationError(Box::new(error))
+    }
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum UriError {
+    #[error("unsupported URI scheme: {0}")]
+    UnsupportedScheme(String),
+    #[error("URI has no host")]
+    MissingHost,
+    #[error("invalid port in URI: {0}")]
+    InvalidPort(String),
+    #[error("routing context is only supported for neo4j URIs")]
+    UnexpectedRoutingContext,
+    #[error("invalid protocol version in URI: {0}")]
+    InvalidVersion(String),
+    #[error("malformed URI: {0}")]
+    Malformed(String),
+}
+
+impl UriError {
+    pub fn kind(&self) -> UriErrorKind {
+        match self {
+            UriError::UnsupportedScheme(_) => UriErrorKind::UnsupportedScheme,
+            UriError::MissingHost => UriErrorKind::MissingHost,
+            UriError::InvalidPort(_) => UriErrorKind::InvalidPort,
+            UriError::UnexpectedRoutingContext => UriErrorKind::UnexpectedRoutingContext,
+            UriError::InvalidVersion(_) => UriErrorKind::InvalidVersion,
+            UriError::Malformed(_) => UriErrorKind::Malformed,
+        }
+    }
+}
+
+/// The kind of a [`UriError`], without any of its contents.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum UriErrorKind {
+    UnsupportedScheme,
+    MissingHost,
+    InvalidPort,
+    UnexpectedRoutingContext,
+    InvalidVersion,
+    Malformed,
+}
+
+/// An error from a [`QueryService`](crate::QueryService).
+#[cfg(feature = "tower")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ServiceError {
+    /// The server failed the query. The connection has been reset, so it can run the next query.
+    #[error("server failed the query: {0}")]
+    Failed(Failure),
+    #[error(transparent)]
+    CommunicationError(Box<CommunicationError>),
+}
+
+#[cfg(feature = "tower")]
+impl From<CommunicationError> for ServiceError {
+    fn from(error: CommunicationError) -> Self {
+        ServiceError::CommunicationError(Box::new(error))
+    }
+}
+
+/// An error from a [`TypeRegistry`](crate::graph::TypeRegistry) deserializing a node.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum GraphError {
+    #[error("node {node_identity} has no registered label (labels: {labels:?})")]
+    UnknownLabel {
+        node_identity: i64,
+        labels: Vec<String>,
+    },
+    #[error("failed to deserialize node {node_identity} with label {label}: {source}")]
+    Deserialize {
+        node_identity: i64,
+        label: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum BookmarkError {
+    #[error("bookmark is empty")]
+    Empty,
+    #[error("malformed bookmark: {0}")]
+    Malformed(String),
+}
+
+impl BookmarkError {
+    pub fn kind(&self) -> BookmarkErrorKind {
+        match self {
+            BookmarkError::Empty => BookmarkErrorKind::Empty,
+            BookmarkError::Malformed(_) => BookmarkErrorKind::Malformed,
+        }
+    }
+}
+
+/// The kind of a [`BookmarkError`], without any of its contents.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum BookmarkErrorKind {
+    Empty,
+    Malformed,
+}
+
+#[cfg(feature = "arrow")]
+#[cfg_attr(docsrs, doc(cfg(feature = "arrow")))]
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ArrowConversionError {
+    #[error("record has {found} fields, expected {expected}")]
+    FieldCount { expected: usize, found: usize },
+    #[error("column {column} contains both {first} and {second} values")]
+    MixedTypes {
+        column: String,
+        first: &'static str,
+        second: &'static str,
+    },
+    #[error("column {column} contains {kind} values, which have no Arrow representation")]
+    Unsupported { column: String, kind: &'static str },
+    #[error("value in column {column} is out of range for its Arrow type")]
+    OutOfRange { column: String },
+    #[error(transparent)]
+    ArrowError(#[from] arrow_schema::ArrowError),
+}
+
+#[cfg(feature = "export")]
+#[cfg_attr(docsrs, doc(cfg(feature = "export")))]
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ExportError {
+    #[error("record has {found} fields, expected {expected}")]
+    FieldCount { expected: usize, found: usize },
+    #[error("invalid temporal format: {format}")]
+    InvalidTemporalFormat { format: String },
+    #[error(transparent)]
+    CommunicationError(Box<CommunicationError>),
+}
+
+#[cfg(feature = "export")]
+impl From<CommunicationError> for ExportError {
+    fn from(error: CommunicationError) -> Self {
+        ExportError::CommunicationError(Box::new(error))
+    }
+}
+
 fn format_version(version: u32) -> String {
     let (major, minor, range) = (version & 0xff, version >> 8 & 0xff, version >> 16 & 0xff);
     if range > 0 {
@@ -0,0 +1,595 @@
+//! Conversion of query results into [CSV](https://www.rfc-editor.org/rfc/rfc4180) rows or
+//! [JSON Lines](https://jsonlines.org/), for exporting them to files or other tools.
+//!
+//! Each record becomes one CSV row or one JSON object keyed by field name, with values in the order
+//! of the fields. Values are written as follows:
+//!
+//! | Bolt value | CSV | JSON |
+//! |------------|-----|------|
+//! | [`Null`](Value::Null) | empty | `null` |
+//! | [`Boolean`](Value::Boolean), [`Integer`](Value::Integer), [`Float`](Value::Float) | as is | number or boolean, or `null` for non-finite floats |
+//! | [`String`](Value::String) | as is | string |
+//! | Temporal values | formatted with the [`TemporalFormat`] | string, formatted the same way |
+//! | [`Duration`](Value::Duration) | ISO 8601 duration, e.g. `P14M3DT3600.5S` | string |
+//! | Everything else | JSON-encoded | see below |
+//!
+//! [`Bytes`](Value::Bytes) become arrays of numbers, [`Map`](Value::Map)s become objects with
+//! sorted keys, points become objects with `srid`, `x`, `y`, and (`z`) keys, and graph structures
+//! become objects with their identities, labels or types, and properties. Paths have `nodes`,
+//! `relationships`, and `sequence` keys, like the structure they are sent as.
+//!
+//! ```
+//! # use bolt_client::export::{ExportFormat, Exporter};
+//! # use bolt_proto::{message::Record, Value};
+//! // These would usually be the `fields` from the `SUCCESS` message returned by `Client::run`,
+//! // and the records returned by `Client::pull`.
+//! let exporter = Exporter::new(ExportFormat::Csv, ["name", "tags"]);
+//! let record = Record::new(vec![
+//!     Value::from("Alice, Jr."),
+//!     Value::from(vec![Value::from("admin")]),
+//! ]);
+//!
+//! assert_eq!(exporter.header().as_deref(), Some("name,tags\r\n"));
+//! assert_eq!(exporter.write_record(&record)?, "\"Alice, Jr.\",\"[\"\"admin\"\"]\"\r\n");
+//!
+//! let exporter = Exporter::new(ExportFormat::JsonLines, ["name", "tags"]);
+//! assert_eq!(
+//!     exporter.write_record(&record)?,
+//!     "{\"name\":\"Alice, Jr.\",\"tags\":[\"admin\"]}\n",
+//! );
+//! # Ok::<(), bolt_client::error::ExportError>(())
+//! ```
+//!
+//! Results can also be exported as they are received, by passing a [`RecordStream`] to
+//! [`Exporter::into_stream`] or [`Exporter::into_async_read`]:
+//!
+//! ```no_run
+//! # use bolt_client::{export::{ExportFormat, Exporter}, Client};
+//! # use futures_util::io::{AsyncRead, AsyncWrite};
+//! # async fn export<S: AsyncRead + AsyncWrite + Unpin + Send>(
+//! #     client: &mut Client<S>,
+//! #     mut file: impl futures_util::io::AsyncWrite + Unpin,
+//! # ) -> Result<(), Box<dyn std::error::Error>> {
+//! client.run("MATCH (p:Person) RETURN p.name, p.born;", None, None).await?;
+//! let fields = client.last_fields().cloned().unwrap_or_default();
+//! let exporter = Exporter::new(ExportFormat::Csv, fields.iter().cloned());
+//! let mut reader = exporter.into_async_read(client.pull_stream(None));
+//! futures_util::io::copy(&mut reader, &mut file).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`RecordStream`]: crate::RecordStream
+
+use std::{fmt::Write, io};
+
+use bolt_proto::{
+    message::Record,
+    value::{Duration, Node, UnboundRelationship},
+    Value,
+};
+use futures_util::{
+    io::AsyncRead,
+    stream::{self, Stream, StreamExt, TryStreamExt},
+};
+use serde_json::{json, Map, Value as Json};
+
+use crate::error::{CommunicationResult, ExportError};
+
+type Result<T> = std::result::Result<T, ExportError>;
+
+/// The format of exported records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma-separated values as described in [RFC 4180](https://www.rfc-editor.org/rfc/rfc4180),
+    /// with rows ending in `\r\n` and fields quoted only if needed.
+    Csv,
+    /// One JSON object per line, with lines ending in `\n`.
+    JsonLines,
+}
+
+/// The [`strftime`](chrono::format::strftime) format strings used for temporal values. The
+/// defaults are the ISO 8601 formats Neo4j uses for the values' string representations, without
+/// time zone IDs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemporalFormat {
+    date: String,
+    time: String,
+    date_time: String,
+    local_date_time: String,
+}
+
+impl TemporalFormat {
+    pub fn new() -> Self {
+        Self {
+            date: String::from("%Y-%m-%d"),
+            time: String::from("%H:%M:%S%.f"),
+            date_time: String::from("%Y-%m-%dT%H:%M:%S%.f%:z"),
+            local_date_time: String::from("%Y-%m-%dT%H:%M:%S%.f"),
+        }
+    }
+
+    /// Set the format of [`Date`](Value::Date)s. Defaults to `%Y-%m-%d`.
+    pub fn with_date(mut self, format: impl Into<String>) -> Self {
+        self.date = format.into();
+        self
+    }
+
+    /// Set the format of [`LocalTime`](Value::LocalTime)s, which is also used for
+    /// [`Time`](Value::Time)s, followed by their offset from UTC. Defaults to `%H:%M:%S%.f`.
+    pub fn with_time(mut self, format: impl Into<String>) -> Self {
+        self.time = format.into();
+        self
+    }
+
+    /// Set the format of [`DateTimeOffset`](Value::DateTimeOffset)s and
+    /// [`DateTimeZoned`](Value::DateTimeZoned)s. Defaults to `%Y-%m-%dT%H:%M:%S%.f%:z`.
+    pub fn with_date_time(mut self, format: impl Into<String>) -> Self {
+        self.date_time = format.into();
+        self
+    }
+
+    /// Set the format of [`LocalDateTime`](Value::LocalDateTime)s. Defaults to
+    /// `%Y-%m-%dT%H:%M:%S%.f`.
+    pub fn with_local_date_time(mut self, format: impl Into<String>) -> Self {
+        self.local_date_time = format.into();
+        self
+    }
+}
+
+impl Default for TemporalFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes records with the given field names as CSV rows or JSON Lines.
+#[derive(Debug, Clone)]
+pub struct Exporter {
+    format: ExportFormat,
+    fields: Vec<String>,
+    header: bool,
+    temporal_format: TemporalFormat,
+}
+
+impl Exporter {
+    /// Create an exporter for records with the given field names.
+    pub fn new(format: ExportFormat, fields: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            format,
+            fields: fields.into_iter().map(Into::into).collect(),
+            header: true,
+            temporal_format: TemporalFormat::new(),
+        }
+    }
+
+    /// Set whether CSV output starts with a row of field names. Defaults to `true`.
+    pub fn with_header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    pub fn with_temporal_format(mut self, temporal_format: TemporalFormat) -> Self {
+        self.temporal_format = temporal_format;
+        self
+    }
+
+    pub fn format(&self) -> ExportFormat {
+        self.format
+    }
+
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    /// Get the row of field names that starts CSV output, if enabled.
+    pub fn header(&self) -> Option<String> {
+        match self.format {
+            ExportFormat::Csv if self.header => {
+                let mut row = String::new();
+                for (i, field) in self.fields.iter().enumerate() {
+                    if i > 0 {
+                        row.push(',');
+                    }
+                    push_csv_field(&mut row, field);
+                }
+                row.push_str("\r\n");
+                Some(row)
+            }
+            _ => None,
+        }
+    }
+
+    /// Write a record, which must have one value per field, as a CSV row or JSON line, including
+    /// the line ending.
+    pub fn write_record(&self, record: &Record) -> Result<String> {
+        let values = record.fields();
+        if values.len() != self.fields.len() {
+            return Err(ExportError::FieldCount {
+                expected: self.fields.len(),
+                found: values.len(),
+            });
+        }
+
+        match self.format {
+            ExportFormat::Csv => {
+                let mut row = String::new();
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        row.push(',');
+                    }
+                    match value {
+                        Value::Null => {}
+                        Value::List(_)
+                        | Value::FloatArray(_)
+                        | Value::Map(_)
+                        | Value::Bytes(_)
+                        | Value::Node(_)
+                        | Value::Relationship(_)
+                        | Value::UnboundRelationship(_)
+                        | Value::Path(_)
+                        | Value::Point2D(_)
+                        | Value::Point3D(_)
+                        | Value::Custom(..) => {
+                            push_csv_field(&mut row, &self.to_json(value)?.to_string())
+                        }
+                        Value::String(string) => push_csv_field(&mut row, string),
+                        value => push_csv_field(&mut row, &self.to_text(value)?),
+                    }
+                }
+                row.push_str("\r\n");
+                Ok(row)
+            }
+            ExportFormat::JsonLines => {
+                let object = self
+                    .fields
+                    .iter()
+                    .zip(values)
+                    .map(|(field, value)| Ok((field.clone(), self.to_json(value)?)))
+                    .collect::<Result<Map<_, _>>>()?;
+                let mut line = Json::Object(object).to_string();
+                line.push('\n');
+                Ok(line)
+            }
+        }
+    }
+
+    /// Convert a stream of records, e.g. a [`RecordStream`](crate::RecordStream), into a stream
+    /// of CSV rows or JSON lines, starting with the header if there is one. The stream ends after
+    /// the first error.
+    pub fn into_stream<'a>(
+        self,
+        records: impl Stream<Item = CommunicationResult<Record>> + 'a,
+    ) -> impl Stream<Item = Result<String>> + 'a {
+        let header = self.header().map(Ok);
+        let rows = records.map(move |record| self.write_record(&record?));
+        stream::iter(header).chain(rows).scan(false, |failed, row| {
+            let row = (!*failed).then(|| {
+                *failed = row.is_err();
+                row
+            });
+            async move { row }
+        })
+    }
+
+    /// Convert a stream of records, e.g. a [`RecordStream`](crate::RecordStream), into a reader of
+    /// CSV or JSON Lines output, starting with the header if there is one. Errors are returned from
+    /// the reader as [`io::Error`]s wrapping an [`ExportError`].
+    pub fn into_async_read<'a>(
+        self,
+        records: impl Stream<Item = CommunicationResult<Record>> + 'a,
+    ) -> impl AsyncRead + 'a {
+        Box::pin(self.into_stream(records).map_err(io::Error::other)).into_async_read()
+    }
+
+    fn to_text(&self, value: &Value) -> Result<String> {
+        let format = &self.temporal_format;
+        let text = match value {
+            Value::Boolean(boolean) => boolean.to_string(),
+            Value::Integer(integer) => integer.to_string(),
+            Value::Float(float) => float.to_string(),
+            Value::String(string) => string.clone(),
+            Value::Date(date) => format_temporal(&format.date, date.format(&format.date))?,
+            Value::LocalTime(time) => format_temporal(&format.time, time.format(&format.time))?,
+            Value::Time(time, offset) => {
+                format_temporal(&format.time, time.format(&format.time))? + &offset.to_string()
+            }
+            Value::DateTimeOffset(date_time) => {
+                format_temporal(&format.date_time, date_time.format(&format.date_time))?
+            }
+            Value::DateTimeZoned(date_time) => {
+                format_temporal(&format.date_time, date_time.format(&format.date_time))?
+            }
+            Value::LocalDateTime(date_time) => format_temporal(
+                &format.local_date_time,
+                date_time.format(&format.local_date_time),
+            )?,
+            Value::Duration(duration) => format_duration(duration),
+            _ => return Ok(self.to_json(value)?.to_string()),
+        };
+        Ok(text)
+    }
+
+    fn to_json(&self, value: &Value) -> Result<Json> {
+        let json = match value {
+            Value::Null => Json::Null,
+            Value::Boolean(boolean) => Json::Bool(*boolean),
+            Value::Integer(integer) => Json::from(*integer),
+            Value::Float(float) => Json::from(*float),
+            Value::String(string) => Json::String(string.clone()),
+            Value::Bytes(bytes) => Json::from(bytes.as_slice()),
+            Value::List(list) => Json::Array(
+                list.iter()
+                    .map(|value| self.to_json(value))
+                    .collect::<Result<_>>()?,
+            ),
+            Value::FloatArray(floats) => Json::from(floats.as_slice()),
+            Value::Map(map) => Json::Object(self.to_json_map(map)?),
+            Value::Node(node) => self.node_to_json(node)?,
+            Value::Relationship(rel) => json!({
+                "identity": rel.rel_identity(),
+                "start": rel.start_node_identity(),
+                "end": rel.end_node_identity(),
+                "type": rel.rel_type(),
+                "properties": self.to_json_map(rel.properties())?,
+            }),
+            Value::UnboundRelationship(rel) => self.unbound_relationship_to_json(rel)?,
+            Value::Path(path) => json!({
+                "nodes": path
+                    .nodes()
+                    .iter()
+                    .map(|node| self.node_to_json(node))
+                    .collect::<Result<Vec<_>>>()?,
+                "relationships": path
+                    .relationships()
+                    .iter()
+                    .map(|rel| self.unbound_relationship_to_json(rel))
+                    .collect::<Result<Vec<_>>>()?,
+                "sequence": path.sequence(),
+            }),
+            Value::Point2D(point) => json!({
+                "srid": point.srid(),
+                "x": point.x(),
+                "y": point.y(),
+            }),
+            Value::Point3D(point) => json!({
+                "srid": point.srid(),
+                "x": point.x(),
+                "y": point.y(),
+                "z": point.z(),
+            }),
+            Value::Custom(signature, fields) => json!({
+                "signature": signature,
+                "fields": fields
+                    .iter()
+                    .map(|value| self.to_json(value))
+                    .collect::<Result<Vec<_>>>()?,
+            }),
+            Value::Date(_)
+            | Value::Time(..)
+            | Value::DateTimeOffset(_)
+            | Value::DateTimeZoned(_)
+            | Value::LocalTime(_)
+            | Value::LocalDateTime(_)
+            | Value::Duration(_) => Json::String(self.to_text(value)?),
+        };
+        Ok(json)
+    }
+
+    fn to_json_map<'v>(
+        &self,
+        map: impl IntoIterator<Item = (&'v String, &'v Value)>,
+    ) -> Result<Map<String, Json>> {
+        let mut entries: Vec<_> = map.into_iter().collect();
+        entries.sort_unstable_by_key(|(key, _)| *key);
+        entries
+            .into_iter()
+            .map(|(key, value)| Ok((key.clone(), self.to_json(value)?)))
+            .collect()
+    }
+
+    fn node_to_json(&self, node: &Node) -> Result<Json> {
+        Ok(json!({
+            "identity": node.node_identity(),
+            "labels": node.labels(),
+            "properties": self.to_json_map(node.properties())?,
+        }))
+    }
+
+    fn unbound_relationship_to_json(&self, rel: &UnboundRelationship) -> Result<Json> {
+        Ok(json!({
+            "identity": rel.rel_identity(),
+            "type": rel.rel_type(),
+            "properties": self.to_json_map(rel.properties())?,
+        }))
+    }
+}
+
+fn push_csv_field(row: &mut String, field: &str) {
+    if field.contains([',', '"', '\r', '\n']) {
+        row.push('"');
+        row.push_str(&field.replace('"', "\"\""));
+        row.push('"');
+    } else {
+        row.push_str(field);
+    }
+}
+
+fn format_temporal(format: &str, formatted: impl std::fmt::Display) -> Result<String> {
+    // Invalid format strings fail when the value is written
+    let mut text = String::new();
+    write!(text, "{}", formatted).map_err(|_| ExportError::InvalidTemporalFormat {
+        format: format.to_string(),
+    })?;
+    Ok(text)
+}
+
+fn format_duration(duration: &Duration) -> String {
+    let nanos = i128::from(duration.seconds()) * 1_000_000_000 + i128::from(duration.nanos());
+    let sign = if nanos < 0 { "-" } else { "" };
+    let (seconds, fraction) = (nanos.abs() / 1_000_000_000, nanos.abs() % 1_000_000_000);
+    let mut text = format!(
+        "P{}M{}DT{}{}",
+        duration.months(),
+        duration.days(),
+        sign,
+        seconds
+    );
+    if fraction > 0 {
+        let fraction = format!("{:09}", fraction);
+        text.push('.');
+        text.push_str(fraction.trim_end_matches('0'));
+    }
+    text.push('S');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use bolt_proto::value::{Path, Point2D, Relationship};
+    use bolt_proto::{Message, ServerState};
+    use chrono::{FixedOffset, NaiveDate, NaiveTime, TimeZone};
+    use futures_util::io::AsyncReadExt;
+
+    use super::*;
+    use crate::error::CommunicationError;
+
+    fn record(values: Vec<Value>) -> Record {
+        Record::new(values)
+    }
+
+    #[test]
+    fn csv() {
+        let exporter = Exporter::new(ExportFormat::Csv, ["a", "b,c", "d", "e"]);
+        assert_eq!(exporter.header().unwrap(), "a,\"b,c\",d,e\r\n");
+        assert_eq!(
+            exporter
+                .write_record(&record(vec![
+                    Value::Null,
+                    Value::from("say \"hi\"\nbye"),
+                    Value::from(1.5),
+                    Value::from(HashMap::from([
+                        (String::from("k"), vec![1, 2]),
+                        (String::from("a"), vec![]),
+                    ])),
+                ]))
+                .unwrap(),
+            ",\"say \"\"hi\"\"\nbye\",1.5,\"{\"\"a\"\":[],\"\"k\"\":[1,2]}\"\r\n",
+        );
+        assert!(exporter.clone().with_header(false).header().is_none());
+        assert!(matches!(
+            exporter.write_record(&record(vec![])),
+            Err(ExportError::FieldCount {
+                expected: 4,
+                found: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn json_lines() {
+        let node = Node::new(
+            1,
+            vec!["Person".into()],
+            HashMap::from([(String::from("age"), 30)]),
+        );
+        let rel = UnboundRelationship::new(2, "KNOWS".into(), HashMap::<String, Value>::new());
+        let exporter = Exporter::new(ExportFormat::JsonLines, ["path", "rel", "point", "nan"]);
+        assert!(exporter.header().is_none());
+        assert_eq!(
+            exporter
+                .write_record(&record(vec![
+                    Value::from(Path::new(vec![node.clone(), node], vec![rel], vec![1, 1])),
+                    Value::from(Relationship::new(
+                        2,
+                        1,
+                        1,
+                        "KNOWS".into(),
+                        HashMap::from([(String::from("since"), Value::Null)]),
+                    )),
+                    Value::from(Point2D::new(4326, 1.0, -2.5)),
+                    Value::from(f64::NAN),
+                ]))
+                .unwrap(),
+            concat!(
+                r#"{"path":{"nodes":[{"identity":1,"labels":["Person"],"properties":{"age":30}},"#,
+                r#"{"identity":1,"labels":["Person"],"properties":{"age":30}}],"#,
+                r#""relationships":[{"identity":2,"type":"KNOWS","properties":{}}],"#,
+                r#""sequence":[1,1]},"#,
+                r#""rel":{"identity":2,"start":1,"end":1,"type":"KNOWS","#,
+                r#""properties":{"since":null}},"#,
+                r#""point":{"srid":4326,"x":1.0,"y":-2.5},"nan":null}"#,
+                "\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn temporal() {
+        let offset = FixedOffset::east_opt(3600).unwrap();
+        let date = NaiveDate::from_ymd_opt(2021, 3, 14).unwrap();
+        let time = NaiveTime::from_hms_milli_opt(12, 30, 15, 250).unwrap();
+        let values = vec![
+            Value::from(date),
+            Value::from((time, offset)),
+            Value::from(offset.from_local_datetime(&date.and_time(time)).unwrap()),
+            Value::from(date.and_time(time)),
+            Value::from(Duration::new(14, 3, -1, 500_000_000)),
+        ];
+        let exporter = Exporter::new(ExportFormat::Csv, ["d", "t", "dt", "ldt", "dur"]);
+        assert_eq!(
+            exporter.write_record(&record(values.clone())).unwrap(),
+            "2021-03-14,12:30:15.250+01:00,2021-03-14T12:30:15.250+01:00,\
+             2021-03-14T12:30:15.250,P14M3DT-0.5S\r\n",
+        );
+
+        let exporter = exporter.with_temporal_format(
+            TemporalFormat::new()
+                .with_date("%d/%m/%Y")
+                .with_time("%H:%M"),
+        );
+        assert_eq!(
+            exporter.write_record(&record(values.clone())).unwrap(),
+            "14/03/2021,12:30+01:00,2021-03-14T12:30:15.250+01:00,\
+             2021-03-14T12:30:15.250,P14M3DT-0.5S\r\n",
+        );
+
+        let exporter = exporter.with_temporal_format(TemporalFormat::new().with_date("%Q"));
+        assert!(matches!(
+            exporter.write_record(&record(values)),
+            Err(ExportError::InvalidTemporalFormat { format }) if format == "%Q"
+        ));
+    }
+
+    #[tokio::test]
+    async fn stream() {
+        let records = stream::iter([
+            Ok(record(vec![Value::from(1)])),
+            Ok(record(vec![Value::from(2)])),
+            Err(CommunicationError::InvalidState {
+                state: ServerState::Defunct,
+                message: Message::Reset,
+            }),
+            Ok(record(vec![Value::from(3)])),
+        ]);
+        let rows: Vec<_> = Exporter::new(ExportFormat::Csv, ["n"])
+            .into_stream(records)
+            .collect()
+            .await;
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0].as_ref().unwrap(), "n\r\n");
+        assert_eq!(rows[2].as_ref().unwrap(), "2\r\n");
+        assert!(matches!(rows[3], Err(ExportError::CommunicationError(_))));
+
+        let records = stream::iter([Ok(record(vec![Value::from("x")]))]);
+        let mut output = String::new();
+        Exporter::new(ExportFormat::JsonLines, ["s"])
+            .into_async_read(records)
+            .read_to_string(&mut output)
+            .await
+            .unwrap();
+        assert_eq!(output, "{\"s\":\"x\"}\n");
+    }
+}
@@ -0,0 +1,88 @@
+/// A protocol capability that depends on the Bolt version negotiated with the server.
+///
+/// Use [`Client::supports`](crate::Client::supports) to check whether a client can use a feature,
+/// instead of comparing its [version](crate::Client::version) against the version constants.
+/// ```
+/// # use bolt_client::Feature;
+/// # use bolt_proto::version::*;
+/// assert!(Feature::MultiDb.is_supported_by(V4_1));
+/// assert!(!Feature::Impersonation.is_supported_by(V4_3));
+/// assert_eq!(Feature::Routing.min_version(), (4, 1));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Feature {
+    /// Temporal and spatial values, such as [`Value::Date`](bolt_proto::Value::Date) and
+    /// [`Value::Point2D`](bolt_proto::Value::Point2D). _(Bolt v2+.)_
+    TemporalSpatialTypes,
+    /// Explicit transactions with [`Client::begin`](crate::Client::begin),
+    /// [`Client::commit`](crate::Client::commit), and
+    /// [`Client::rollback`](crate::Client::rollback), and transaction configuration (bookmarks,
+    /// timeouts, etc.) in the metadata of `RUN`. _(Bolt v3+.)_
+    ExplicitTx,
+    /// Selecting a database with the `db` metadata entry. _(Bolt v4+.)_
+    MultiDb,
+    /// Pulling or discarding a limited number of records with the `n` metadata entry, and
+    /// selecting a result with `qid`. _(Bolt v4+.)_
+    PullN,
+    /// Sending a routing context in the `HELLO` message. _(Bolt v4.1+.)_
+    Routing,
+    /// Fetching routing tables with [`Client::route`](crate::Client::route). _(Bolt v4.3+.)_
+    RouteMessage,
+    /// Running transactions as another user with the `imp_user` metadata entry. _(Bolt v4.4+.)_
+    Impersonation,
+    /// String element IDs on nodes and relationships. _(Bolt v5+, which is newer than any version
+    /// supported by this crate.)_
+    ElementIds,
+    /// Filtering notifications with the `notifications_minimum_severity` and
+    /// `notifications_disabled_categories` metadata entries. _(Bolt v5.2+, which is newer than any
+    /// version supported by this crate.)_
+    NotificationFiltering,
+}
+
+impl Feature {
+    /// Get the first Bolt version that supports this feature, as a `(major, minor)` pair.
+    pub fn min_version(self) -> (u32, u32) {
+        match self {
+            Feature::TemporalSpatialTypes => (2, 0),
+            Feature::ExplicitTx => (3, 0),
+            Feature::MultiDb | Feature::PullN => (4, 0),
+            Feature::Routing => (4, 1),
+            Feature::RouteMessage => (4, 3),
+            Feature::Impersonation => (4, 4),
+            Feature::ElementIds => (5, 0),
+            Feature::NotificationFiltering => (5, 2),
+        }
+    }
+
+    /// Check whether the given protocol version (e.g. [`V4_4`](bolt_proto::version::V4_4))
+    /// supports this feature.
+    pub fn is_supported_by(self, version: u32) -> bool {
+        (version & 0xff, version >> 8 & 0xff) >= self.min_version()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bolt_proto::version::*;
+
+    use super::*;
+
+    #[test]
+    fn is_supported_by() {
+        assert!(!Feature::TemporalSpatialTypes.is_supported_by(V1_0));
+        assert!(Feature::TemporalSpatialTypes.is_supported_by(V2_0));
+        assert!(!Feature::ExplicitTx.is_supported_by(V2_0));
+        assert!(Feature::ExplicitTx.is_supported_by(V4_4));
+        assert!(!Feature::PullN.is_supported_by(V3_0));
+        assert!(Feature::PullN.is_supported_by(V4_0));
+        assert!(!Feature::Routing.is_supported_by(V4_0));
+        assert!(Feature::RouteMessage.is_supported_by(V4_3));
+        assert!(!Feature::Impersonation.is_supported_by(V4_3));
+        assert!(Feature::Impersonation.is_supported_by(V4_4));
+        assert!(!Feature::ElementIds.is_supported_by(V4_4));
+        assert!(Feature::ElementIds.is_supported_by(0x0005));
+        assert!(!Feature::NotificationFiltering.is_supported_by(0x0105));
+        assert!(Feature::NotificationFiltering.is_supported_by(0x0205));
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::{HashMap, HashSet};
+
+use bolt_proto::{
+    message::Record,
+    value::{Node, Path, Relationship},
+    Value,
+};
+use futures_util::stream::{Stream, StreamExt};
+
+mod registry;
+
+pub use registry::{TypeRegistry, TypedGraphCollector};
+
+/// An in-memory graph of the nodes and relationships contained in a query result.
+///
+/// Nodes and relationships are keyed by their identity, so values that appear more than once in a
+/// result are only stored once. Nodes and relationships are collected from anywhere in a
+/// [`Record`], including inside lists, maps, and [`Path`]s. Relationships from paths are resolved
+/// into full [`Relationship`]s using the direction information stored in the path.
+///
+/// A graph can be built from any collection of records:
+/// ```
+/// # use bolt_client::graph::Graph;
+/// # use bolt_proto::message::Record;
+/// # let records: Vec<Record> = vec![];
+/// let graph: Graph = records.into_iter().collect();
+/// ```
+///
+/// or from a stream of records, such as a [`RecordStream`](crate::RecordStream), adding each
+/// record as it arrives instead of collecting the rows first:
+/// ```no_run
+/// # use bolt_client::{graph::Graph, Client};
+/// # async fn example(
+/// #     client: &mut Client<impl futures_util::io::AsyncRead + futures_util::io::AsyncWrite + Unpin + Send>,
+/// # ) -> Result<(), Box<dyn std::error::Error>> {
+/// client.run("MATCH p = (:Person)-[:KNOWS]->() RETURN p;", None, None).await?;
+/// let graph = Graph::from_stream(client.pull_stream(None)).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Graph {
+    nodes: HashMap<i64, Node>,
+    relationships: HashMap<i64, Relationship>,
+    labels: HashMap<String, HashSet<i64>>,
+    rel_types: HashMap<String, HashSet<i64>>,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a graph from a stream of records, like [`Graph::extend_from_stream`].
+    pub async fn from_stream<E>(stream: impl Stream<Item = Result<Record, E>>) -> Result<Self, E> {
+        let mut graph = Graph::new();
+        graph.extend_from_stream(stream).await?;
+        Ok(graph)
+    }
+
+    /// Add all nodes and relationships in each record of the stream to the graph, as the records
+    /// arrive. Stops at the first error, keeping what was added from the records before it.
+    pub async fn extend_from_stream<E>(
+        &mut self,
+        stream: impl Stream<Item = Result<Record, E>>,
+    ) -> Result<(), E> {
+        let mut stream = std::pin::pin!(stream);
+        while let Some(record) = stream.next().await {
+            self.insert_record(record?);
+        }
+        Ok(())
+    }
+
+    /// Add all nodes and relationships in the given record to the graph.
+    pub fn insert_record(&mut self, record: Record) {
+        for value in record.into_fields() {
+            self.insert_value(value);
+        }
+    }
+
+    /// Add all nodes and relationships in the given value to the graph.
+    pub fn insert_value(&mut self, value: Value) {
+        match value {
+            Value::Node(node) => self.insert_node(node),
+            Value::Relationship(rel) => self.insert_relationship(rel),
+            Value::Path(path) => self.insert_path(path),
+            Value::List(list) => list.into_iter().for_each(|value| self.insert_value(value)),
+            Value::Map(map) => map.into_values().for_each(|value| self.insert_value(value)),
+            _ => {}
+        }
+    }
+
+    pub fn insert_node(&mut self, node: Node) {
+        for label in node.labels() {
+            self.labels
+                .entry(label.clone())
+                .or_default()
+                .insert(node.node_identity());
+        }
+        self.nodes.insert(node.node_identity(), node);
+    }
+
+    pub fn insert_relationship(&mut self, rel: Relationship) {
+        self.rel_types
+            .entry(rel.rel_type().to_string())
+            .or_default()
+            .insert(rel.rel_identity());
+        self.relationships.insert(rel.rel_identity(), rel);
+    }
+
+    /// Add all nodes in the path to the graph, along with its relationships, resolving the start
+    /// and end node of each relationship.
+    pub fn insert_path(&mut self, path: Path) {
+        let nodes = path.nodes();
+        let rels = path.relationships();
+
+        if let Some(first) = nodes.first() {
+            let mut previous = first.node_identity();
+            // The sequence alternates between a relationship index and a node index. Relationship
+            // indices start at 1 and are negative if the relationship is traversed in reverse.
+            for step in path.sequence().chunks_exact(2) {
+                let (rel_index, node_index) = (step[0], step[1]);
+                let (Some(rel), Some(next)) = (
+                    (rel_index.unsigned_abs() as usize)
+                        .checked_sub(1)
+                        .and_then(|index| rels.get(index)),
+                    nodes.get(node_index as usize),
+                ) else {
+                    break;
+                };
+                let next = next.node_identity();
+                let (start, end) = if rel_index > 0 {
+                    (previous, next)
+                } else {
+                    (next, previous)
+                };
+
+                self.insert_relationship(Relationship::new(
+                    rel.rel_identity(),
+                    start,
+                    end,
+                    rel.rel_type().to_string(),
+                    rel.properties().clone(),
+                ));
+                previous = next;
+            }
+        }
+
+        for node in nodes {
+            self.insert_node(node.clone());
+        }
+    }
+
+    pub fn node(&self, node_identity: i64) -> Option<&Node> {
+        self.nodes.get(&node_identity)
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.values()
+    }
+
+    pub fn relationship(&self, rel_identity: i64) -> Option<&Relationship> {
+        self.relationships.get(&rel_identity)
+    }
+
+    pub fn relationships(&self) -> impl Iterator<Item = &Relationship> {
+        self.relationships.values()
+    }
+
+    /// Get the nodes in the graph that have the given label.
+    pub fn nodes_with_label<'a>(&'a self, label: &str) -> impl Iterator<Item = &'a Node> {
+        self.labels
+            .get(label)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.nodes.get(id))
+    }
+
+    /// Get the relationships in the graph that have the given type.
+    pub fn relationships_with_type<'a>(
+        &'a self,
+        rel_type: &str,
+    ) -> impl Iterator<Item = &'a Relationship> {
+        self.rel_types
+            .get(rel_type)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.relationships.get(id))
+    }
+
+    /// Get the start and end nodes of a relationship, if both are present in the graph.
+    pub fn endpoints(&self, rel: &Relationship) -> Option<(&Node, &Node)> {
+        Some((
+            self.nodes.get(&rel.start_node_identity())?,
+            self.nodes.get(&rel.end_node_identity())?,
+        ))
+    }
+
+    /// Get the relationships in the graph that start at the given node.
+    pub fn outgoing(&self, node_identity: i64) -> impl Iterator<Item = &Relationship> {
+        self.relationships
+            .values()
+            .filter(move |rel| rel.start_node_identity() == node_identity)
+    }
+
+    /// Get the relationships in the graph that end at the given node.
+    pub fn incoming(&self, node_identity: i64) -> impl Iterator<Item = &Relationship> {
+        self.relationships
+            .values()
+            .filter(move |rel| rel.end_node_identity() == node_identity)
+    }
+}
+
+impl Extend<Record> for Graph {
+    fn extend<T: IntoIterator<Item = Record>>(&mut self, iter: T) {
+        iter.into_iter()
+            .for_each(|record| self.insert_record(record));
+    }
+}
+
+impl FromIterator<Record> for Graph {
+    fn from_iter<T: IntoIterator<Item = Record>>(iter: T) -> Self {
+        let mut graph = Graph::new();
+        graph.extend(iter);
+        graph
+    }
+}
+
+impl From<Vec<Record>> for Graph {
+    fn from(records: Vec<Record>) -> Self {
+        records.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bolt_proto::value::UnboundRelationship;
+
+    use super::*;
+
+    fn node(id: i64, label: &str) -> Node {
+        Node::new(id, vec![label.to_string()], HashMap::<_, Value>::new())
+    }
+
+    fn unbound_rel(id: i64, rel_type: &str) -> UnboundRelationship {
+        UnboundRelationship::new(id, rel_type.to_string(), HashMap::<_, Value>::new())
+    }
+
+    #[test]
+    fn collect_records() {
+        let alice = node(1, "Person");
+        let bob = node(2, "Person");
+        let rust = node(3, "Language");
+        let knows = Relationship::new(10, 1, 2, "KNOWS".to_string(), HashMap::<_, Value>::new());
+        let records = vec![
+            Record::new(vec![
+                Value::from(alice.clone()),
+                Value::from(knows.clone()),
+                Value::from(bob.clone()),
+            ]),
+            // Duplicates are only stored once
+            Record::new(vec![Value::from(alice.clone())]),
+            Record::new(vec![Value::from(vec![Value::from(rust.clone())])]),
+            Record::new(vec![Value::from(1), Value::Null]),
+        ];
+
+        let graph = Graph::from(records);
+        assert_eq!(graph.nodes().count(), 3);
+        assert_eq!(graph.relationships().count(), 1);
+        assert_eq!(graph.node(3), Some(&rust));
+        assert_eq!(graph.nodes_with_label("Person").count(), 2);
+        assert_eq!(graph.nodes_with_label("Language").next(), Some(&rust));
+        assert_eq!(graph.nodes_with_label("Missing").count(), 0);
+        assert_eq!(graph.relationships_with_type("KNOWS").next(), Some(&knows));
+        assert_eq!(graph.endpoints(&knows), Some((&alice, &bob)));
+        assert_eq!(graph.outgoing(1).next(), Some(&knows));
+        assert_eq!(graph.incoming(1).count(), 0);
+    }
+
+    #[test]
+    fn collect_stream() {
+        use futures_util::{stream, FutureExt};
+
+        let records: Vec<Result<Record, ()>> = vec![
+            Ok(Record::new(vec![Value::from(node(1, "Person"))])),
+            Ok(Record::new(vec![Value::from(node(2, "Person"))])),
+        ];
+        let graph = Graph::from_stream(stream::iter(records))
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(graph.nodes_with_label("Person").count(), 2);
+
+        let mut graph = Graph::new();
+        let records = vec![
+            Ok(Record::new(vec![Value::from(node(1, "Person"))])),
+            Err("connection closed"),
+            Ok(Record::new(vec![Value::from(node(2, "Person"))])),
+        ];
+        let result = graph
+            .extend_from_stream(stream::iter(records))
+            .now_or_never()
+            .unwrap();
+        assert_eq!(result, Err("connection closed"));
+        assert_eq!(graph.nodes().count(), 1);
+    }
+
+    #[test]
+    fn expand_path() {
+        // (a)-[:X]->(b)<-[:Y]-(c)
+        let path = Path::new(
+            vec![node(1, "A"), node(2, "B"), node(3, "C")],
+            vec![unbound_rel(10, "X"), unbound_rel(11, "Y")],
+            vec![1, 1, -2, 2],
+        );
+
+        let graph: Graph = vec![Record::new(vec![Value::from(path)])]
+            .into_iter()
+            .collect();
+        assert_eq!(graph.nodes().count(), 3);
+
+        let x = graph.relationship(10).unwrap();
+        assert_eq!(x.rel_type(), "X");
+        assert_eq!((x.start_node_identity(), x.end_node_identity()), (1, 2));
+
+        let y = graph.relationship(11).unwrap();
+        assert_eq!((y.start_node_identity(), y.end_node_identity()), (3, 2));
+        let (start, end) = graph.endpoints(y).unwrap();
+        assert_eq!(start.labels(), &["C"]);
+        assert_eq!(end.labels(), &["B"]);
+    }
+}
@@ -0,0 +1,268 @@
+use std::{collections::HashMap, error::Error as StdError, fmt};
+
+use bolt_proto::{message::Record, value::Node, Value};
+
+use crate::{error::GraphError, graph::Graph};
+
+type Deserializer<T> =
+    Box<dyn Fn(&Node) -> Result<T, Box<dyn StdError + Send + Sync>> + Send + Sync>;
+
+/// A mapping from node labels to functions that deserialize nodes with those labels into a common
+/// type `T`, e.g. an enum with a variant per label, or `Box<dyn Any>`.
+///
+/// A node with several labels is deserialized with the function registered for the first of its
+/// labels, in the order the server sent them, that has one.
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use bolt_client::{error::GraphError, graph::TypeRegistry};
+/// # use bolt_proto::value::Node;
+/// #[derive(Debug, PartialEq)]
+/// enum Entity {
+///     Person(String),
+///     Language(String),
+/// }
+///
+/// fn name(node: &Node) -> Result<String, &'static str> {
+///     match node.properties().get("name").and_then(|name| name.as_str()) {
+///         Some(name) => Ok(name.to_string()),
+///         None => Err("missing name"),
+///     }
+/// }
+///
+/// let registry = TypeRegistry::new()
+///     .with_label("Person", |node| name(node).map(Entity::Person))
+///     .with_label("Language", |node| name(node).map(Entity::Language));
+///
+/// let rust = Node::new(1, vec!["Language".into()], HashMap::from([("name".into(), "Rust")]));
+/// assert_eq!(registry.deserialize(&rust)?, Entity::Language(String::from("Rust")));
+///
+/// let city = Node::new(2, vec!["City".into()], HashMap::from([("name".into(), "Oslo")]));
+/// assert!(matches!(registry.deserialize(&city), Err(GraphError::UnknownLabel { .. })));
+/// # Ok::<_, GraphError>(())
+/// ```
+pub struct TypeRegistry<T> {
+    deserializers: HashMap<String, Deserializer<T>>,
+}
+
+impl<T> TypeRegistry<T> {
+    pub fn new() -> Self {
+        Self {
+            deserializers: HashMap::new(),
+        }
+    }
+
+    /// Deserialize nodes with the given label using the given function, replacing any function
+    /// previously registered for the label.
+    pub fn with_label<E>(
+        mut self,
+        label: impl Into<String>,
+        deserialize: impl Fn(&Node) -> Result<T, E> + Send + Sync + 'static,
+    ) -> Self
+    where
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        self.deserializers.insert(
+            label.into(),
+            Box::new(move |node| deserialize(node).map_err(Into::into)),
+        );
+        self
+    }
+
+    /// Check whether a function is registered for the given label.
+    pub fn contains_label(&self, label: &str) -> bool {
+        self.deserializers.contains_key(label)
+    }
+
+    /// Get the first label of the node that has a registered function.
+    pub fn label_of<'n>(&self, node: &'n Node) -> Option<&'n str> {
+        node.labels()
+            .iter()
+            .map(String::as_str)
+            .find(|label| self.contains_label(label))
+    }
+
+    /// Deserialize a node with the function registered for its label. Returns
+    /// [`GraphError::UnknownLabel`] if none of its labels are registered.
+    pub fn deserialize(&self, node: &Node) -> Result<T, GraphError> {
+        self.deserialize_labeled(node).map(|(_, value)| value)
+    }
+
+    fn deserialize_labeled<'n>(&self, node: &'n Node) -> Result<(&'n str, T), GraphError> {
+        let label = self
+            .label_of(node)
+            .ok_or_else(|| GraphError::UnknownLabel {
+                node_identity: node.node_identity(),
+                labels: node.labels().to_vec(),
+            })?;
+        match self.deserializers[label](node) {
+            Ok(value) => Ok((label, value)),
+            Err(source) => Err(GraphError::Deserialize {
+                node_identity: node.node_identity(),
+                label: label.to_string(),
+                source,
+            }),
+        }
+    }
+}
+
+impl<T> Default for TypeRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for TypeRegistry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypeRegistry")
+            .field("labels", &self.deserializers.keys())
+            .finish()
+    }
+}
+
+/// Collects the nodes in query results and deserializes them with a [`TypeRegistry`], grouped by
+/// the label each node was deserialized for.
+///
+/// Nodes are collected the same way as in a [`Graph`]: from anywhere in a record, including inside
+/// lists, maps, and paths, and only once per identity. They are deserialized by
+/// [`TypedGraphCollector::finish`], in order of identity.
+///
+/// ```
+/// # use std::{any::Any, collections::HashMap};
+/// # use bolt_client::graph::{TypeRegistry, TypedGraphCollector};
+/// # use bolt_proto::{message::Record, value::Node, Value};
+/// struct Person {
+///     age: i64,
+/// }
+///
+/// let registry = TypeRegistry::<Box<dyn Any>>::new().with_label("Person", |node| {
+///     match node.properties().get("age").and_then(Value::as_i64) {
+///         Some(age) => Ok(Box::new(Person { age }) as Box<dyn Any>),
+///         None => Err("missing age"),
+///     }
+/// });
+///
+/// let alice = Node::new(1, vec!["Person".into()], HashMap::from([("age".into(), 30)]));
+/// let mut collector = TypedGraphCollector::new(&registry);
+/// collector.insert_record(Record::new(vec![Value::from(alice.clone()), Value::from(alice)]));
+///
+/// let slots = collector.finish()?;
+/// let people = &slots["Person"];
+/// assert_eq!(people.len(), 1);
+/// assert_eq!(people[0].downcast_ref::<Person>().map(|person| person.age), Some(30));
+/// # Ok::<_, bolt_client::error::GraphError>(())
+/// ```
+#[derive(Debug)]
+pub struct TypedGraphCollector<'r, T> {
+    registry: &'r TypeRegistry<T>,
+    graph: Graph,
+}
+
+impl<'r, T> TypedGraphCollector<'r, T> {
+    pub fn new(registry: &'r TypeRegistry<T>) -> Self {
+        Self {
+            registry,
+            graph: Graph::new(),
+        }
+    }
+
+    /// Add all nodes in the given record to the collector.
+    pub fn insert_record(&mut self, record: Record) {
+        self.graph.insert_record(record);
+    }
+
+    /// Add all nodes in the given value to the collector.
+    pub fn insert_value(&mut self, value: Value) {
+        self.graph.insert_value(value);
+    }
+
+    /// Get the graph of everything collected so far, including relationships.
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    /// Deserialize the collected nodes, keyed by the label each node was deserialized for. Fails
+    /// on the first node that has no registered label, or that can't be deserialized.
+    pub fn finish(self) -> Result<HashMap<String, Vec<T>>, GraphError> {
+        let mut nodes: Vec<&Node> = self.graph.nodes().collect();
+        nodes.sort_unstable_by_key(|node| node.node_identity());
+
+        let mut slots: HashMap<String, Vec<T>> = HashMap::new();
+        for node in nodes {
+            let (label, value) = self.registry.deserialize_labeled(node)?;
+            slots.entry(label.to_string()).or_default().push(value);
+        }
+        Ok(slots)
+    }
+}
+
+impl<T> Extend<Record> for TypedGraphCollector<'_, T> {
+    fn extend<I: IntoIterator<Item = Record>>(&mut self, records: I) {
+        self.graph.extend(records);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Entity {
+        Person(i64),
+        Company(i64),
+    }
+
+    fn node(id: i64, labels: &[&str]) -> Node {
+        let labels = labels.iter().map(|label| label.to_string()).collect();
+        Node::new(id, labels, HashMap::<_, Value>::new())
+    }
+
+    fn registry() -> TypeRegistry<Entity> {
+        TypeRegistry::new()
+            .with_label("Person", |node| {
+                Ok::<_, &str>(Entity::Person(node.node_identity()))
+            })
+            .with_label("Company", |node| match node.node_identity() {
+                0.. => Ok(Entity::Company(node.node_identity())),
+                _ => Err("negative identity"),
+            })
+    }
+
+    #[test]
+    fn collect_by_label() {
+        let registry = registry();
+        let mut collector = TypedGraphCollector::new(&registry);
+        collector.extend([
+            Record::new(vec![
+                Value::from(node(2, &["Person"])),
+                Value::from(vec![Value::from(node(1, &["Person"]))]),
+            ]),
+            // The first registered label is used
+            Record::new(vec![Value::from(node(
+                3,
+                &["Employer", "Company", "Person"],
+            ))]),
+        ]);
+
+        let slots = collector.finish().unwrap();
+        assert_eq!(slots["Person"], [Entity::Person(1), Entity::Person(2)]);
+        assert_eq!(slots["Company"], [Entity::Company(3)]);
+    }
+
+    #[test]
+    fn errors() {
+        let registry = registry();
+        let mut collector = TypedGraphCollector::new(&registry);
+        collector.insert_value(Value::from(node(4, &["Person"])));
+        collector.insert_value(Value::from(node(5, &["City"])));
+        assert!(matches!(
+            collector.finish(),
+            Err(GraphError::UnknownLabel { node_identity: 5, labels }) if labels == ["City"]
+        ));
+
+        assert!(matches!(
+            registry.deserialize(&node(-1, &["Company"])),
+            Err(GraphError::Deserialize { node_identity: -1, label, .. }) if label == "Company"
+        ));
+    }
+}
@@ -1,4 +1,5 @@
 #![warn(rust_2018_idioms)]
+#![allow(clippy::result_large_err)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 //! This crate contains a runtime-agnostic asynchronous client for graph database servers that
@@ -14,10 +15,33 @@
 //! [mobc-bolt](https://crates.io/crates/mobc-bolt).
 //!
 //! If you'd rather manage your own connections, an asynchronous TCP/TLS [`Stream`] wrapper is also
-//! available, if you're using the [tokio](https://tokio.rs/) runtime.
+//! available, if you're using the [tokio](https://tokio.rs/) runtime. A [`ClientBuilder`] can be
+//! used to open a [`Stream`] and return an authenticated [`Client`] in a single step.
 //!
 //! # Features
-//! - `tokio-stream` - enables the [`Stream`] type
+//! - `arrow` - enables the [`arrow`] module, for converting query results into Arrow
+//!   record batches
+//! - `tokio-stream` - enables the [`Stream`] and [`ClientBuilder`] types
+//! - `tracing` - makes [`MetadataValidation::Warn`] log unsupported metadata, and logs
+//!   queries with their parameters redacted according to [`ParamRedaction`]
+//! - `test-harness` - enables the [`test_harness`] module, for testing against Neo4j servers in
+//!   Docker containers
+//! - `compression` - enables the [`CompressedStream`] type, for compressing traffic over slow links
+//! - `export` - enables the [`export`] module, for converting query results into CSV or JSON Lines
+//! - `proxy` - enables connecting through HTTP `CONNECT` and SOCKS5 proxies with the [`Proxy`]
+//!   type
+//! - `otel` - enables the [`OtelInterceptor`] type, for creating [tracing](https://docs.rs/tracing)
+//!   spans for queries that follow the OpenTelemetry semantic conventions
+//! - `query-stats` - enables the [`QueryStatsRegistry`] type, for recording query latencies
+//!   by query fingerprint and exporting them to Prometheus
+//! - `serde` - enables the `serde` feature of [`bolt_proto`], for deserializing records into
+//!   user types
+//! - `tower` - enables the [`QueryService`] type, which runs queries as a
+//!   [tower](https://docs.rs/tower) `Service`
+//! - `tokio` - enables the [`TokioTimer`] type, which clients use by default to wait between
+//!   transaction retries and keepalives. This is also enabled by `tokio-stream`.
+//! - `async-std` - enables the [`AsyncStdTimer`] type, which clients use by default if the
+//!   `tokio` feature is not enabled. Without either feature, clients use a [`ThreadTimer`].
 //!
 //! # Example
 //! The below example demonstrates how to communicate with a Neo4j server using Bolt protocol
@@ -225,19 +249,100 @@
 //! See the documentation of the [`Client`] struct for information on transaction management, error
 //! handling, and more.
 #[doc(inline)]
-pub use self::client::Client;
+pub use self::client::{
+    BatchSummary, Client, ClientReader, ClientStats, ClientWriter, Exchange, HandshakeInfo,
+    Interceptor, MetadataValidation, RecordStream, ReuniteError, StateChange, Transaction,
+    TxRecordStream,
+};
 
+pub mod admin;
+mod bookmark;
 mod client;
+mod client_info;
+pub mod compat;
+#[cfg(feature = "compression")]
+mod compression;
 mod define_value_map;
+mod drain;
 pub mod error;
+mod feature;
+pub mod graph;
+mod named_record;
+mod notifications;
+mod pool_config;
+mod prepared;
+mod redact;
+mod retry;
+mod routing;
+mod summary;
+mod timer;
+mod tx_config;
+mod uri;
 
 pub use bolt_proto;
+pub use bookmark::{Bookmark, BookmarkManager, Bookmarks};
+pub use client_info::ClientInfo;
+pub use drain::{Drain, DrainSignal};
+pub use feature::Feature;
+pub use named_record::NamedRecord;
+pub use notifications::{NotificationCategory, NotificationSeverity};
+pub use pool_config::PoolConfig;
+pub use prepared::PreparedQuery;
+pub use redact::{ParamRedaction, Redacted};
+pub use retry::RetryPolicy;
+pub use routing::{RoutingTable, ServerRole};
+pub use summary::{Counters, Plan, ProfiledPlan, QueryHeader, StreamSummary};
+#[cfg(feature = "async-std")]
+pub use timer::AsyncStdTimer;
+#[cfg(feature = "tokio")]
+pub use timer::TokioTimer;
+pub use timer::{ThreadTimer, Timer};
+pub use tx_config::{AccessMode, TransactionStatus};
+pub use uri::{BoltUri, TlsMode, UriScheme};
 
+#[cfg(feature = "tokio-stream")]
+mod builder;
+#[cfg(feature = "otel")]
+mod otel;
+#[cfg(feature = "proxy")]
+mod proxy;
+#[cfg(feature = "query-stats")]
+mod query_stats;
+#[cfg(feature = "tower")]
+mod service;
 #[cfg(feature = "tokio-stream")]
 mod stream;
 
+#[cfg(feature = "arrow")]
+#[cfg_attr(docsrs, doc(cfg(feature = "arrow")))]
+pub mod arrow;
+#[cfg(feature = "export")]
+#[cfg_attr(docsrs, doc(cfg(feature = "export")))]
+pub mod export;
+#[cfg(feature = "test-harness")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-harness")))]
+pub mod test_harness;
+
+#[cfg(feature = "arrow")]
+pub use arrow_array;
+#[cfg(feature = "arrow")]
+pub use arrow_schema;
+#[cfg(feature = "tokio-stream")]
+pub use builder::ClientBuilder;
+#[cfg(feature = "compression")]
+pub use compression::CompressedStream;
+#[cfg(feature = "otel")]
+pub use otel::OtelInterceptor;
+#[cfg(feature = "proxy")]
+pub use proxy::{Proxy, ProxyKind};
+#[cfg(feature = "query-stats")]
+pub use query_stats::{QueryStats, QueryStatsInterceptor, QueryStatsRegistry, OTHER_QUERIES};
+#[cfg(feature = "tower")]
+pub use service::{QueryService, RunRequest};
+#[cfg(feature = "tokio-stream")]
+pub use stream::{Resolver, Stream, StreamConfig};
 #[cfg(feature = "tokio-stream")]
-pub use stream::Stream;
+pub use tokio_rustls::rustls;
 
 // TODO: Convert Client methods to return a builder-type object so we don't need these anymore
 define_value_map!(Metadata);
@@ -0,0 +1,86 @@
+use std::{ops::Index, sync::Arc};
+
+use bolt_proto::{message::Record, Value};
+
+/// A [`Record`] paired with the names of its fields, returned by [`Client::pull_named`].
+///
+/// The field names come from the `fields` entry of the [`SUCCESS`](bolt_proto::message::Success)
+/// response to `RUN`, and are shared by every record in the result, so naming a record doesn't
+/// copy them.
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use bolt_client::NamedRecord;
+/// # use bolt_proto::{message::Record, Value};
+/// let fields: Arc<[String]> = Arc::from([String::from("name"), String::from("age")]);
+/// let record = NamedRecord::new(
+///     fields,
+///     Record::new(vec![Value::from("Alice"), Value::from(42)]),
+/// );
+/// assert_eq!(record.get("age"), Some(&Value::from(42)));
+/// assert_eq!(record["name"], Value::from("Alice"));
+/// assert_eq!(record.get("email"), None);
+/// ```
+///
+/// [`Client::pull_named`]: crate::Client::pull_named
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedRecord {
+    keys: Arc<[String]>,
+    record: Record,
+}
+
+impl NamedRecord {
+    pub fn new(keys: Arc<[String]>, record: Record) -> Self {
+        Self { keys, record }
+    }
+
+    /// Get the field names, in the same order as the [values](NamedRecord::values).
+    pub fn keys(&self) -> &Arc<[String]> {
+        &self.keys
+    }
+
+    pub fn values(&self) -> &[Value] {
+        self.record.fields()
+    }
+
+    /// Get the value of the field with the given name.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        let index = self.keys.iter().position(|k| k == key)?;
+        self.record.fields().get(index)
+    }
+
+    /// Iterate over the field names and values of the record.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.keys
+            .iter()
+            .map(String::as_str)
+            .zip(self.record.fields())
+    }
+
+    pub fn record(&self) -> &Record {
+        &self.record
+    }
+
+    pub fn into_record(self) -> Record {
+        self.record
+    }
+}
+
+impl Index<&str> for NamedRecord {
+    type Output = Value;
+
+    /// Get the value of the field with the given name.
+    ///
+    /// # Panics
+    /// Panics if the record has no field with the given name.
+    fn index(&self, key: &str) -> &Self::Output {
+        self.get(key)
+            .unwrap_or_else(|| panic!("record has no field named {:?}", key))
+    }
+}
+
+impl From<NamedRecord> for Record {
+    fn from(record: NamedRecord) -> Self {
+        record.record
+    }
+}
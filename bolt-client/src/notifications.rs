@@ -0,0 +1,132 @@
+use bolt_proto::Value;
+
+use crate::Metadata;
+
+/// The least severe notifications a server should send, sent as the
+/// `notifications_minimum_severity` metadata entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NotificationSeverity {
+    /// Only send `WARNING` notifications.
+    Warning,
+    /// Send both `WARNING` and `INFORMATION` notifications.
+    Information,
+    /// Send no notifications at all.
+    Off,
+}
+
+impl NotificationSeverity {
+    fn as_str(self) -> &'static str {
+        match self {
+            NotificationSeverity::Warning => "WARNING",
+            NotificationSeverity::Information => "INFORMATION",
+            NotificationSeverity::Off => "OFF",
+        }
+    }
+}
+
+/// A category of notifications a server should not send, listed in the
+/// `notifications_disabled_categories` metadata entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum NotificationCategory {
+    Hint,
+    Unrecognized,
+    Unsupported,
+    Performance,
+    Deprecation,
+    Generic,
+}
+
+impl NotificationCategory {
+    fn as_str(self) -> &'static str {
+        match self {
+            NotificationCategory::Hint => "HINT",
+            NotificationCategory::Unrecognized => "UNRECOGNIZED",
+            NotificationCategory::Unsupported => "UNSUPPORTED",
+            NotificationCategory::Performance => "PERFORMANCE",
+            NotificationCategory::Deprecation => "DEPRECATION",
+            NotificationCategory::Generic => "GENERIC",
+        }
+    }
+}
+
+/// The metadata keys used to filter notifications.
+pub(crate) const NOTIFICATION_KEYS: [&str; 2] = [
+    "notifications_minimum_severity",
+    "notifications_disabled_categories",
+];
+
+/// Setters for the notification filtering entries accepted by
+/// [`Client::hello`](crate::Client::hello), [`Client::begin`](crate::Client::begin), and
+/// [`Client::run`](crate::Client::run). _(Bolt v5.2+ only.)_
+///
+/// Notification filtering is newer than any protocol version supported by this crate, so these
+/// entries are currently removed before messages are sent. Servers that don't understand them send
+/// all notifications, as if no filter was set.
+///
+/// ```
+/// # use bolt_client::{Metadata, NotificationCategory, NotificationSeverity};
+/// let metadata = Metadata::default()
+///     .with_notifications_minimum_severity(NotificationSeverity::Warning)
+///     .with_notifications_disabled_categories([
+///         NotificationCategory::Hint,
+///         NotificationCategory::Generic,
+///     ]);
+/// ```
+impl Metadata {
+    /// Set the `notifications_minimum_severity` entry, which is sent as `"WARNING"`,
+    /// `"INFORMATION"`, or `"OFF"`.
+    pub fn with_notifications_minimum_severity(mut self, severity: NotificationSeverity) -> Self {
+        self.value.insert(
+            String::from("notifications_minimum_severity"),
+            Value::from(severity.as_str()),
+        );
+        self
+    }
+
+    /// Set the `notifications_disabled_categories` entry, which is sent as a list of category
+    /// names, e.g. `"HINT"`. Duplicate categories are only sent once.
+    pub fn with_notifications_disabled_categories(
+        mut self,
+        categories: impl IntoIterator<Item = NotificationCategory>,
+    ) -> Self {
+        let mut categories: Vec<NotificationCategory> = categories.into_iter().collect();
+        categories.sort();
+        categories.dedup();
+        let categories: Vec<&str> = categories.into_iter().map(|c| c.as_str()).collect();
+        self.value.insert(
+            String::from("notifications_disabled_categories"),
+            Value::from(categories),
+        );
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setters() {
+        let metadata = Metadata::default()
+            .with_notifications_minimum_severity(NotificationSeverity::Information)
+            .with_notifications_disabled_categories([
+                NotificationCategory::Generic,
+                NotificationCategory::Hint,
+                NotificationCategory::Generic,
+            ]);
+        assert_eq!(
+            metadata.value.get("notifications_minimum_severity"),
+            Some(&Value::from("INFORMATION"))
+        );
+        assert_eq!(
+            metadata.value.get("notifications_disabled_categories"),
+            Some(&Value::from(vec!["HINT", "GENERIC"]))
+        );
+
+        let metadata = Metadata::default().with_notifications_disabled_categories([]);
+        assert_eq!(
+            metadata.value.get("notifications_disabled_categories"),
+            Some(&Value::from(Vec::<&str>::new()))
+        );
+    }
+}
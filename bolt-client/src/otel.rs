@@ -0,0 +1,529 @@
+use std::collections::{HashMap, VecDeque};
+
+use bolt_proto::{
+    message::{Failure, Success},
+    version::Version,
+    Message, Value,
+};
+use tracing::{field::Empty, Span};
+
+use crate::{redact::redact_literals, Interceptor};
+
+/// An [`Interceptor`] that creates a [`tracing`] span for each request of each query a client
+/// runs, following the OpenTelemetry semantic conventions for database clients. The spans can be
+/// exported to OpenTelemetry with [tracing-opentelemetry](https://docs.rs/tracing-opentelemetry).
+///
+/// Each `RUN` has a span named after the query's operation (e.g. `MATCH`), with these attributes:
+/// - `db.system`: `neo4j`
+/// - `db.statement`: the query, with string and number literals replaced by `?`
+/// - `db.operation`: the query's first keyword
+/// - `db.name`: the database, if one was given
+/// - `server.address` and `server.port`, if they were [set](OtelInterceptor::with_server_address)
+/// - `bolt.version`: the protocol version, e.g. `4.4`
+///
+/// The span of a `RUN` stays open until its results have been pulled or discarded. Each `PULL` and
+/// `DISCARD` has its own span, linked to the span of the `RUN` whose results it fetches, with the
+/// number of records received in `db.response.returned_rows`. Requests that fail set
+/// `otel.status_code` to `ERROR`, with the server's error code in `error.type`.
+///
+/// Spans are created at the `INFO` level, as children of the current span. The interceptor must
+/// be added before the client sends any requests, as it matches responses to requests by their
+/// order. [`ClientBuilder::with_otel_spans`](crate::ClientBuilder::with_otel_spans) adds one to
+/// each new client.
+/// ```
+/// # use bolt_client::{Client, OtelInterceptor};
+/// # use futures_util::io::{AsyncRead, AsyncWrite};
+/// fn instrument<S: AsyncRead + AsyncWrite + Unpin>(client: &mut Client<S>) {
+///     let interceptor =
+///         OtelInterceptor::new(client.version()).with_server_address("db.example.com:7687");
+///     client.add_interceptor(interceptor);
+/// }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "otel")))]
+#[derive(Debug)]
+pub struct OtelInterceptor {
+    version: Version,
+    server_address: Option<String>,
+    server_port: Option<u16>,
+    // The requests awaiting a summary response, in the order they were sent
+    pending: VecDeque<Request>,
+    // The queries whose results are still open, oldest first
+    queries: VecDeque<Query>,
+    next_key: u64,
+}
+
+#[derive(Debug)]
+enum Request {
+    Run,
+    Fetch {
+        span: Span,
+        query: Option<u64>,
+        rows: u64,
+    },
+    Other,
+}
+
+#[derive(Debug)]
+struct Query {
+    key: u64,
+    span: Span,
+    // Set once the server has responded to the RUN
+    answered: bool,
+    qid: Option<i64>,
+}
+
+impl OtelInterceptor {
+    /// Create an interceptor for a client using the given protocol version.
+    pub fn new(version: Version) -> Self {
+        Self {
+            version,
+            server_address: None,
+            server_port: None,
+            pending: VecDeque::new(),
+            queries: VecDeque::new(),
+            next_key: 0,
+        }
+    }
+
+    /// Set the address of the server, in `"host:port"` form, recorded in the `server.address` and
+    /// `server.port` attributes.
+    pub fn with_server_address(mut self, addr: &str) -> Self {
+        let (host, port) = match addr.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() => match port.parse() {
+                Ok(port) => (host, Some(port)),
+                Err(_) => (addr, None),
+            },
+            _ => (addr, None),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        self.server_address = Some(host.to_string());
+        self.server_port = port;
+        self
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn server_address(&self) -> Option<&str> {
+        self.server_address.as_deref()
+    }
+
+    pub fn server_port(&self) -> Option<u16> {
+        self.server_port
+    }
+
+    fn run(&mut self, query: &str, database: Option<&str>) {
+        let statement = redact_literals(query);
+        let operation = operation(&statement);
+        let span = tracing::info_span!(
+            "bolt_run",
+            otel.name = operation.unwrap_or("RUN"),
+            otel.kind = "client",
+            otel.status_code = Empty,
+            otel.status_description = Empty,
+            "error.type" = Empty,
+            db.system = "neo4j",
+            db.statement = statement.as_str(),
+            db.operation = operation,
+            db.name = database,
+            server.address = self.server_address.as_deref(),
+            server.port = self.server_port,
+            bolt.version = %self.version,
+        );
+
+        self.queries.push_back(Query {
+            key: self.next_key,
+            span,
+            answered: false,
+            qid: None,
+        });
+        self.next_key += 1;
+        self.pending.push_back(Request::Run);
+    }
+
+    fn fetch(&mut self, name: &'static str, metadata: Option<&HashMap<String, Value>>) {
+        let span = tracing::info_span!(
+            "bolt_fetch",
+            otel.name = name,
+            otel.kind = "client",
+            otel.status_code = Empty,
+            otel.status_description = Empty,
+            "error.type" = Empty,
+            db.system = "neo4j",
+            db.response.returned_rows = Empty,
+            server.address = self.server_address.as_deref(),
+            server.port = self.server_port,
+            bolt.version = %self.version,
+        );
+
+        // A qid of -1 refers to the last query
+        let qid = metadata
+            .and_then(|metadata| metadata.get("qid"))
+            .and_then(Value::as_i64)
+            .filter(|&qid| qid != -1);
+        let query = match qid {
+            Some(qid) => self.queries.iter().find(|query| query.qid == Some(qid)),
+            None => self.queries.back(),
+        };
+        if let Some(query) = query {
+            span.follows_from(&query.span);
+        }
+
+        self.pending.push_back(Request::Fetch {
+            span,
+            query: query.map(|query| query.key),
+            rows: 0,
+        });
+    }
+
+    fn finish(&mut self, response: Response<'_>) {
+        match self.pending.pop_front() {
+            Some(Request::Run) => {
+                let Some(index) = self.queries.iter().position(|query| !query.answered) else {
+                    return;
+                };
+                match response {
+                    Response::Success(success) => {
+                        let query = &mut self.queries[index];
+                        query.answered = true;
+                        query.qid = success.metadata().get("qid").and_then(Value::as_i64);
+                    }
+                    Response::Failure(failure) => {
+                        record_failure(&self.queries[index].span, failure);
+                        self.queries.remove(index);
+                    }
+                    Response::Ignored => {
+                        self.queries.remove(index);
+                    }
+                }
+            }
+            Some(Request::Fetch { span, query, rows }) => {
+                span.record("db.response.returned_rows", rows);
+                match response {
+                    Response::Success(success) if has_more(success) => return,
+                    Response::Failure(failure) => record_failure(&span, failure),
+                    _ => {}
+                }
+                // The query's results have been consumed, or are no longer available
+                if let Some(key) = query {
+                    self.queries.retain(|query| query.key != key);
+                }
+            }
+            Some(Request::Other) | None => {}
+        }
+    }
+}
+
+impl Interceptor for OtelInterceptor {
+    fn before_send(&mut self, message: &mut Message) {
+        match message {
+            Message::Run(run) => self.run(run.query(), None),
+            Message::RunWithMetadata(run) => {
+                let database = run.metadata().get("db").and_then(Value::as_str);
+                self.run(run.statement(), database);
+            }
+            Message::Pull(pull) => self.fetch("PULL", Some(pull.metadata())),
+            Message::PullAll => self.fetch("PULL", None),
+            Message::Discard(discard) => self.fetch("DISCARD", Some(discard.metadata())),
+            Message::DiscardAll => self.fetch("DISCARD", None),
+            // The server sends no response, and closes the connection
+            Message::Goodbye => {
+                self.pending.clear();
+                self.queries.clear();
+            }
+            // Any open results are discarded
+            Message::Reset | Message::Commit | Message::Rollback => {
+                self.queries.clear();
+                self.pending.push_back(Request::Other);
+            }
+            _ => self.pending.push_back(Request::Other),
+        }
+    }
+
+    fn after_receive(&mut self, message: &mut Message) {
+        match message {
+            Message::Record(_) => {
+                if let Some(Request::Fetch { rows, .. }) = self.pending.front_mut() {
+                    *rows += 1;
+                }
+            }
+            Message::Success(success) => self.finish(Response::Success(success)),
+            Message::Failure(failure) => self.finish(Response::Failure(failure)),
+            Message::Ignored => self.finish(Response::Ignored),
+            _ => {}
+        }
+    }
+}
+
+enum Response<'a> {
+    Success(&'a Success),
+    Failure(&'a Failure),
+    Ignored,
+}
+
+// Get the first keyword of a query, e.g. `MATCH` or `CALL`
+fn operation(statement: &str) -> Option<&'static str> {
+    const OPERATIONS: [&str; 19] = [
+        "CALL", "CREATE", "DELETE", "DETACH", "DROP", "EXPLAIN", "FOREACH", "LOAD", "MATCH",
+        "MERGE", "OPTIONAL", "PROFILE", "REMOVE", "RETURN", "SET", "SHOW", "UNWIND", "USE", "WITH",
+    ];
+    let keyword = statement
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .find(|word| !word.is_empty())?;
+    OPERATIONS
+        .into_iter()
+        .find(|operation| operation.eq_ignore_ascii_case(keyword))
+}
+
+fn record_failure(span: &Span, failure: &Failure) {
+    span.record("otel.status_code", "ERROR");
+    if let Some(code) = failure.code() {
+        span.record("error.type", code);
+    }
+    if let Some(message) = failure.message() {
+        span.record("otel.status_description", message);
+    }
+}
+
+fn has_more(success: &Success) -> bool {
+    success.metadata().get("has_more").and_then(Value::as_bool) == Some(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fmt,
+        sync::{Arc, Mutex},
+    };
+
+    use bolt_proto::message::{Pull, Record, RunWithMetadata};
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record as SpanRecord},
+        Event, Metadata, Subscriber,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct RecordedSpan {
+        fields: HashMap<String, String>,
+        follows_from: Vec<u64>,
+        refs: usize,
+    }
+
+    // Records the fields and links of every span, identifying spans by their creation order
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<RecordedSpan>>>);
+
+    impl Recorder {
+        fn field(&self, span: u64, name: &str) -> Option<String> {
+            self.0.lock().unwrap()[span as usize - 1]
+                .fields
+                .get(name)
+                .cloned()
+        }
+
+        fn follows_from(&self, span: u64) -> Vec<u64> {
+            self.0.lock().unwrap()[span as usize - 1]
+                .follows_from
+                .clone()
+        }
+
+        fn is_closed(&self, span: u64) -> bool {
+            self.0.lock().unwrap()[span as usize - 1].refs == 0
+        }
+    }
+
+    struct Fields<'a>(&'a mut HashMap<String, String>);
+
+    impl Visit for Fields<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+            let mut spans = self.0.lock().unwrap();
+            let mut span = RecordedSpan {
+                refs: 1,
+                ..Default::default()
+            };
+            attributes.record(&mut Fields(&mut span.fields));
+            spans.push(span);
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, id: &Id, values: &SpanRecord<'_>) {
+            let mut spans = self.0.lock().unwrap();
+            values.record(&mut Fields(&mut spans[id.into_u64() as usize - 1].fields));
+        }
+
+        fn record_follows_from(&self, id: &Id, follows: &Id) {
+            self.0.lock().unwrap()[id.into_u64() as usize - 1]
+                .follows_from
+                .push(follows.into_u64());
+        }
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+
+        fn clone_span(&self, id: &Id) -> Id {
+            self.0.lock().unwrap()[id.into_u64() as usize - 1].refs += 1;
+            id.clone()
+        }
+
+        fn try_close(&self, id: Id) -> bool {
+            let mut spans = self.0.lock().unwrap();
+            let span = &mut spans[id.into_u64() as usize - 1];
+            span.refs -= 1;
+            span.refs == 0
+        }
+    }
+
+    fn success(entries: Vec<(&str, Value)>) -> Message {
+        Message::Success(Success::new(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        ))
+    }
+
+    fn run(interceptor: &mut OtelInterceptor, query: &str, database: Option<&str>) {
+        let metadata = database
+            .map(|database| (String::from("db"), Value::from(database)))
+            .into_iter()
+            .collect();
+        interceptor.before_send(&mut Message::RunWithMetadata(RunWithMetadata::new(
+            query.to_string(),
+            Default::default(),
+            metadata,
+        )));
+    }
+
+    fn pull(interceptor: &mut OtelInterceptor, n: i64) {
+        interceptor.before_send(&mut Message::Pull(Pull::new(HashMap::from([(
+            String::from("n"),
+            Value::from(n),
+        )]))));
+    }
+
+    fn receive(interceptor: &mut OtelInterceptor, mut message: Message) {
+        interceptor.after_receive(&mut message);
+    }
+
+    #[test]
+    fn spans() {
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let mut interceptor =
+                OtelInterceptor::new(Version::V4_4).with_server_address("db.example.com:7687");
+            interceptor.before_send(&mut Message::Reset);
+            receive(&mut interceptor, success(vec![]));
+
+            run(
+                &mut interceptor,
+                "MATCH (n {name: 'Alice'}) RETURN n LIMIT 10;",
+                Some("movies"),
+            );
+            pull(&mut interceptor, 1);
+            receive(
+                &mut interceptor,
+                success(vec![("fields", Value::from(vec!["n"]))]),
+            );
+            receive(&mut interceptor, Message::Record(Record::new(vec![])));
+            receive(
+                &mut interceptor,
+                success(vec![("has_more", Value::from(true))]),
+            );
+            assert!(!recorder.is_closed(1));
+            assert!(recorder.is_closed(2));
+
+            pull(&mut interceptor, -1);
+            receive(&mut interceptor, Message::Record(Record::new(vec![])));
+            receive(&mut interceptor, Message::Record(Record::new(vec![])));
+            receive(&mut interceptor, success(vec![]));
+            assert!(recorder.is_closed(1));
+            assert!(recorder.is_closed(3));
+
+            run(&mut interceptor, "RETURN x", None);
+            pull(&mut interceptor, -1);
+            receive(
+                &mut interceptor,
+                Message::Failure(Failure::new(HashMap::from([
+                    (
+                        String::from("code"),
+                        Value::from("Neo.ClientError.Statement.SyntaxError"),
+                    ),
+                    (
+                        String::from("message"),
+                        Value::from("Variable `x` not defined"),
+                    ),
+                ]))),
+            );
+            receive(&mut interceptor, Message::Ignored);
+            assert!(recorder.is_closed(4));
+            assert!(recorder.is_closed(5));
+        });
+
+        let field = |span, name| recorder.field(span, name);
+        assert_eq!(field(1, "otel.name").as_deref(), Some("MATCH"));
+        assert_eq!(field(1, "otel.kind").as_deref(), Some("client"));
+        assert_eq!(field(1, "db.system").as_deref(), Some("neo4j"));
+        assert_eq!(
+            field(1, "db.statement").as_deref(),
+            Some("MATCH (n {name: ?}) RETURN n LIMIT ?")
+        );
+        assert_eq!(field(1, "db.operation").as_deref(), Some("MATCH"));
+        assert_eq!(field(1, "db.name").as_deref(), Some("movies"));
+        assert_eq!(
+            field(1, "server.address").as_deref(),
+            Some("db.example.com")
+        );
+        assert_eq!(field(1, "server.port").as_deref(), Some("7687"));
+        assert_eq!(field(1, "bolt.version").as_deref(), Some("4.4"));
+        assert_eq!(field(1, "otel.status_code"), None);
+
+        assert_eq!(field(2, "otel.name").as_deref(), Some("PULL"));
+        assert_eq!(field(2, "db.response.returned_rows").as_deref(), Some("1"));
+        assert_eq!(recorder.follows_from(2), vec![1]);
+        assert_eq!(field(3, "db.response.returned_rows").as_deref(), Some("2"));
+        assert_eq!(recorder.follows_from(3), vec![1]);
+
+        assert_eq!(field(4, "otel.name").as_deref(), Some("RETURN"));
+        assert_eq!(field(4, "db.name"), None);
+        assert_eq!(field(4, "otel.status_code").as_deref(), Some("ERROR"));
+        assert_eq!(
+            field(4, "error.type").as_deref(),
+            Some("Neo.ClientError.Statement.SyntaxError")
+        );
+        assert_eq!(recorder.follows_from(5), vec![4]);
+        assert_eq!(field(5, "otel.status_code"), None);
+    }
+
+    #[test]
+    fn server_address() {
+        let interceptor = OtelInterceptor::new(Version::V4_4).with_server_address("[::1]:7687");
+        assert_eq!(interceptor.server_address(), Some("::1"));
+        assert_eq!(interceptor.server_port(), Some(7687));
+
+        let interceptor = OtelInterceptor::new(Version::V4_4).with_server_address("localhost");
+        assert_eq!(interceptor.server_address(), Some("localhost"));
+        assert_eq!(interceptor.server_port(), None);
+    }
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+const DEFAULT_MAX_SIZE: u32 = 10;
+const DEFAULT_CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connection pool settings understood by each of the pool adapters
+/// ([bb8-bolt](https://crates.io/crates/bb8-bolt),
+/// [deadpool-bolt](https://crates.io/crates/deadpool-bolt), and
+/// [mobc-bolt](https://crates.io/crates/mobc-bolt)), so that pools can be configured the same way
+/// regardless of which one is used.
+///
+/// Pools built from a `PoolConfig` open their [`min_idle`](PoolConfig::min_idle) connections
+/// before they are returned, performing the handshake and sending `HELLO` for each one. This means
+/// an unreachable server or invalid credentials are reported when the pool is built, rather than
+/// when the first connection is requested.
+/// ```
+/// # use std::time::Duration;
+/// # use bolt_client::PoolConfig;
+/// let config = PoolConfig::new()
+///     .with_max_size(20)
+///     .with_min_idle(5)
+///     .with_connection_timeout(Duration::from_secs(10));
+/// assert_eq!(config.min_idle(), 5);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolConfig {
+    max_size: u32,
+    min_idle: u32,
+    connection_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_size: DEFAULT_MAX_SIZE,
+            min_idle: 0,
+            connection_timeout: DEFAULT_CONNECTION_TIMEOUT,
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Create a configuration for pools of at most 10 connections, with no connections opened in
+    /// advance and a 30 second connection timeout.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of connections managed by the pool, including those in use.
+    pub fn with_max_size(mut self, max_size: u32) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Set the number of connections to open when the pool is built. For pools that support it,
+    /// this many idle connections are also maintained afterwards.
+    pub fn with_min_idle(mut self, min_idle: u32) -> Self {
+        self.min_idle = min_idle;
+        self
+    }
+
+    /// Set how long to wait for a connection to become available when requesting one from the
+    /// pool.
+    pub fn with_connection_timeout(mut self, connection_timeout: Duration) -> Self {
+        self.connection_timeout = connection_timeout;
+        self
+    }
+
+    pub fn max_size(&self) -> u32 {
+        self.max_size
+    }
+
+    /// Get the number of connections to open when the pool is built. This never exceeds the
+    /// [maximum size](PoolConfig::max_size) of the pool.
+    pub fn min_idle(&self) -> u32 {
+        self.min_idle.min(self.max_size)
+    }
+
+    pub fn connection_timeout(&self) -> Duration {
+        self.connection_timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn min_idle_capped_at_max_size() {
+        let config = PoolConfig::new().with_min_idle(15);
+        assert_eq!(config.min_idle(), DEFAULT_MAX_SIZE);
+        let config = config.with_max_size(20);
+        assert_eq!(config.min_idle(), 15);
+    }
+}
@@ -0,0 +1,161 @@
+use std::{borrow::Cow, collections::HashMap, sync::OnceLock};
+
+use bolt_proto::{message::*, version::*, Message, Value};
+
+use crate::{
+    client::{shape_metadata, MetadataTarget, ShapedMetadata},
+    error::CommunicationResult,
+    Metadata, Params,
+};
+
+/// A query that is meant to be run many times with different parameters.
+///
+/// Bolt has no server-side prepared statements, so the query text is still sent with every `RUN`
+/// message. A `PreparedQuery` holds the query along with the metadata to send with it (bookmarks,
+/// transaction configuration, database, etc.), so these only need to be assembled once. The
+/// metadata is also arranged in the form expected by the protocol version the query is first run
+/// with, and reused for every later run with that version. Use
+/// [`Client::run_prepared`](crate::Client::run_prepared) to run it, or
+/// [`PreparedQuery::to_message`] to add it to a [`Client::pipeline`](crate::Client::pipeline).
+///
+/// ```
+/// # use bolt_client::{Metadata, Params, PreparedQuery};
+/// let query = PreparedQuery::new("MATCH (p:Person {name: $name}) RETURN p;")
+///     .with_metadata(Metadata::from_iter(vec![("mode", "r")]));
+/// let message = query.to_message(
+///     bolt_proto::version::V4_4,
+///     Some(Params::from_iter(vec![("name", "Alice")])),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct PreparedQuery {
+    query: String,
+    metadata: HashMap<String, Value>,
+    // The metadata shaped for the first version it was needed for, with that version
+    shaped: OnceLock<(u32, ShapedMetadata)>,
+}
+
+impl PreparedQuery {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            metadata: HashMap::new(),
+            shaped: OnceLock::new(),
+        }
+    }
+
+    /// Set the metadata sent with each run of the query. See [`Client::run`](crate::Client::run)
+    /// for the supported entries.
+    pub fn with_metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = metadata.value;
+        self.shaped = OnceLock::new();
+        self
+    }
+
+    /// Set the bookmarks sent with each run of the query, replacing any previous bookmarks.
+    pub fn set_bookmarks(&mut self, bookmarks: impl IntoIterator<Item = impl Into<String>>) {
+        let bookmarks: Vec<String> = bookmarks.into_iter().map(Into::into).collect();
+        self.metadata
+            .insert(String::from("bookmarks"), Value::from(bookmarks));
+        self.shaped = OnceLock::new();
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn metadata(&self) -> &HashMap<String, Value> {
+        &self.metadata
+    }
+
+    /// Create the `RUN` message for this query with the given parameters, in the form expected by
+    /// a server using the given protocol version. The metadata is arranged as
+    /// [`Client::run_prepared`](crate::Client::run_prepared) would, and omitted for Bolt v1 - v2.
+    /// If it can't be, e.g. because `db` isn't a string, it is sent as is.
+    pub fn to_message(&self, version: u32, parameters: Option<Params>) -> Message {
+        let parameters = parameters.unwrap_or_default();
+        match self.shaped_metadata(version) {
+            Ok(shaped) => self.message(version, parameters, &shaped.metadata),
+            Err(_) => self.message(version, parameters, &self.metadata),
+        }
+    }
+
+    /// Get the metadata in the form expected by a version, shaping it only once for the first
+    /// version it's needed for.
+    pub(crate) fn shaped_metadata(
+        &self,
+        version: u32,
+    ) -> CommunicationResult<Cow<'_, ShapedMetadata>> {
+        if let Some((shaped_version, shaped)) = self.shaped.get() {
+            if *shaped_version == version {
+                return Ok(Cow::Borrowed(shaped));
+            }
+        }
+        let shaped = shape_metadata(version, MetadataTarget::Run, self.metadata.clone())?;
+        match self.shaped.set((version, shaped)) {
+            Ok(()) => Ok(Cow::Borrowed(&self.shaped.get().unwrap().1)),
+            Err((_, shaped)) => Ok(Cow::Owned(shaped)),
+        }
+    }
+
+    pub(crate) fn message(
+        &self,
+        version: u32,
+        parameters: Params,
+        metadata: &HashMap<String, Value>,
+    ) -> Message {
+        match version {
+            V1_0 | V2_0 => Message::Run(Run::new(self.query.clone(), parameters.value)),
+            _ => Message::RunWithMetadata(RunWithMetadata::new(
+                self.query.clone(),
+                parameters.value,
+                metadata.clone(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_message() {
+        let mut query = PreparedQuery::new("RETURN $n;")
+            .with_metadata(Metadata::from_iter(vec![("db", "neo4j")]));
+        query.set_bookmarks(vec!["bookmark:1"]);
+
+        let params = Params::from_iter(vec![("n", 1)]);
+        let run = RunWithMetadata::try_from(query.to_message(V4_4, Some(params.clone()))).unwrap();
+        assert_eq!(run.statement(), "RETURN $n;");
+        assert_eq!(run.parameters(), &params.value);
+        assert_eq!(run.metadata().get("db"), Some(&Value::from("neo4j")));
+        assert_eq!(
+            run.metadata().get("bookmarks"),
+            Some(&Value::from(vec!["bookmark:1"]))
+        );
+
+        let run = Run::try_from(query.to_message(V2_0, Some(params.clone()))).unwrap();
+        assert_eq!(run.query(), "RETURN $n;");
+        assert_eq!(run.parameters(), &params.value);
+    }
+
+    #[test]
+    fn shaped_metadata() {
+        let mut query = PreparedQuery::new("RETURN 1;")
+            .with_metadata(Metadata::from_iter(vec![("db", "neo4j")]));
+        assert!(matches!(query.shaped_metadata(V4_4), Ok(Cow::Borrowed(_))));
+        assert!(matches!(query.shaped_metadata(V4_4), Ok(Cow::Borrowed(_))));
+        // Only the first version is cached
+        assert!(matches!(query.shaped_metadata(V4_3), Ok(Cow::Owned(_))));
+
+        // Changing the metadata clears the cache
+        query.set_bookmarks(vec!["bookmark:1"]);
+        let shaped = query.shaped_metadata(V4_3).unwrap();
+        assert!(matches!(shaped, Cow::Borrowed(_)));
+        assert_eq!(
+            shaped.metadata.get("bookmarks"),
+            Some(&Value::from(vec!["bookmark:1"]))
+        );
+    }
+}
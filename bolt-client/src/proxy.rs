@@ -0,0 +1,440 @@
+use std::{io, net::IpAddr};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::StreamConfig;
+
+const SOCKS_VERSION: u8 = 0x05;
+const SOCKS_AUTH_NONE: u8 = 0x00;
+const SOCKS_AUTH_PASSWORD: u8 = 0x02;
+const SOCKS_AUTH_UNACCEPTABLE: u8 = 0xFF;
+const SOCKS_COMMAND_CONNECT: u8 = 0x01;
+const SOCKS_ADDR_IPV4: u8 = 0x01;
+const SOCKS_ADDR_DOMAIN: u8 = 0x03;
+const SOCKS_ADDR_IPV6: u8 = 0x04;
+
+// Limit on the size of an HTTP CONNECT response header, to avoid reading forever from a
+// misbehaving proxy
+const MAX_HTTP_RESPONSE_LEN: usize = 8192;
+
+/// The protocol used to communicate with a [`Proxy`].
+#[cfg_attr(docsrs, doc(cfg(feature = "proxy")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyKind {
+    /// An HTTP proxy that supports the `CONNECT` method.
+    HttpConnect,
+    /// A SOCKS5 proxy.
+    Socks5,
+}
+
+/// A proxy through which connections to a server are tunneled.
+///
+/// The proxy only carries the connection, so TLS is still negotiated with the server itself,
+/// and the server's certificate is verified against the server's domain. Use
+/// [`Stream::connect_with_proxy`](crate::Stream::connect_with_proxy) or
+/// [`ClientBuilder::with_proxy`](crate::ClientBuilder::with_proxy) to connect through a proxy.
+///
+/// ```
+/// # use bolt_client::Proxy;
+/// let proxy = Proxy::socks5("127.0.0.1:1080").with_auth("user", "password");
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "proxy")))]
+#[derive(Clone, PartialEq, Eq)]
+pub struct Proxy {
+    kind: ProxyKind,
+    addr: String,
+    auth: Option<(String, String)>,
+}
+
+impl Proxy {
+    /// Create a proxy of the given kind at the given address, in `"host:port"` form.
+    pub fn new(kind: ProxyKind, addr: impl Into<String>) -> Self {
+        Self {
+            kind,
+            addr: addr.into(),
+            auth: None,
+        }
+    }
+
+    /// Create an HTTP proxy that supports the `CONNECT` method, at the given address.
+    pub fn http(addr: impl Into<String>) -> Self {
+        Self::new(ProxyKind::HttpConnect, addr)
+    }
+
+    /// Create a SOCKS5 proxy at the given address.
+    pub fn socks5(addr: impl Into<String>) -> Self {
+        Self::new(ProxyKind::Socks5, addr)
+    }
+
+    /// Authenticate with the proxy using the given username and password. HTTP proxies receive
+    /// these with the `Basic` scheme, and SOCKS5 proxies with username/password authentication.
+    pub fn with_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.auth = Some((username.into(), password.into()));
+        self
+    }
+
+    pub fn kind(&self) -> ProxyKind {
+        self.kind
+    }
+
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    pub fn username(&self) -> Option<&str> {
+        self.auth.as_ref().map(|(username, _)| username.as_str())
+    }
+
+    /// Connect to the proxy, and ask it to open a connection to the target address.
+    pub(crate) async fn tunnel(
+        &self,
+        target: &str,
+        config: &StreamConfig,
+    ) -> io::Result<TcpStream> {
+        let mut stream = config.connect_tcp(&self.addr).await?;
+        match self.kind {
+            ProxyKind::HttpConnect => self.http_connect(&mut stream, target).await?,
+            ProxyKind::Socks5 => self.socks5_connect(&mut stream, target).await?,
+        }
+        Ok(stream)
+    }
+
+    async fn http_connect(&self, stream: &mut TcpStream, target: &str) -> io::Result<()> {
+        let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", target);
+        if let Some((username, password)) = &self.auth {
+            let credentials = base64_encode(format!("{}:{}", username, password).as_bytes());
+            request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).await?;
+        stream.flush().await?;
+
+        // Read one byte at a time so none of the tunneled data is consumed
+        let mut response = Vec::new();
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() >= MAX_HTTP_RESPONSE_LEN {
+                return Err(proxy_error("HTTP proxy response is too large"));
+            }
+            response.push(stream.read_u8().await?);
+        }
+
+        let response = String::from_utf8_lossy(&response);
+        let status_line = response.lines().next().unwrap_or_default();
+        match status_line.split_whitespace().nth(1) {
+            Some(status) if status.starts_with('2') => Ok(()),
+            _ => Err(proxy_error(format!(
+                "HTTP proxy refused connection: {}",
+                status_line
+            ))),
+        }
+    }
+
+    async fn socks5_connect(&self, stream: &mut TcpStream, target: &str) -> io::Result<()> {
+        let (host, port) = split_host_port(target)?;
+
+        let method = match self.auth {
+            Some(_) => SOCKS_AUTH_PASSWORD,
+            None => SOCKS_AUTH_NONE,
+        };
+        stream.write_all(&[SOCKS_VERSION, 1, method]).await?;
+        let mut reply = [0; 2];
+        stream.read_exact(&mut reply).await?;
+        match reply {
+            [SOCKS_VERSION, SOCKS_AUTH_UNACCEPTABLE] => {
+                return Err(proxy_error("SOCKS5 proxy rejected authentication method"))
+            }
+            [SOCKS_VERSION, selected] if selected == method => {}
+            _ => return Err(proxy_error("invalid SOCKS5 greeting response")),
+        }
+
+        if let Some((username, password)) = &self.auth {
+            let mut request = vec![0x01];
+            for field in [username, password] {
+                let len = u8::try_from(field.len()).map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "SOCKS5 credentials must be at most 255 bytes",
+                    )
+                })?;
+                request.push(len);
+                request.extend_from_slice(field.as_bytes());
+            }
+            stream.write_all(&request).await?;
+            stream.read_exact(&mut reply).await?;
+            if reply[1] != 0x00 {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "SOCKS5 proxy authentication failed",
+                ));
+            }
+        }
+
+        let mut request = vec![SOCKS_VERSION, SOCKS_COMMAND_CONNECT, 0x00];
+        match host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => {
+                request.push(SOCKS_ADDR_IPV4);
+                request.extend_from_slice(&ip.octets());
+            }
+            Ok(IpAddr::V6(ip)) => {
+                request.push(SOCKS_ADDR_IPV6);
+                request.extend_from_slice(&ip.octets());
+            }
+            Err(_) => {
+                let len = u8::try_from(host.len()).map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "host name is too long")
+                })?;
+                request.push(SOCKS_ADDR_DOMAIN);
+                request.push(len);
+                request.extend_from_slice(host.as_bytes());
+            }
+        }
+        request.extend_from_slice(&port.to_be_bytes());
+        stream.write_all(&request).await?;
+
+        let mut reply = [0; 4];
+        stream.read_exact(&mut reply).await?;
+        if reply[0] != SOCKS_VERSION {
+            return Err(proxy_error("invalid SOCKS5 connect response"));
+        }
+        if reply[1] != 0x00 {
+            return Err(proxy_error(format!(
+                "SOCKS5 proxy refused connection: {}",
+                socks5_reply_message(reply[1])
+            )));
+        }
+
+        // Discard the address the proxy bound for the connection
+        let bound_addr_len = match reply[3] {
+            SOCKS_ADDR_IPV4 => 4,
+            SOCKS_ADDR_IPV6 => 16,
+            SOCKS_ADDR_DOMAIN => stream.read_u8().await? as usize,
+            _ => return Err(proxy_error("invalid SOCKS5 address type")),
+        };
+        let mut bound_addr = vec![0; bound_addr_len + 2];
+        stream.read_exact(&mut bound_addr).await?;
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for Proxy {
+    // Avoid printing the proxy password
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Proxy")
+            .field("kind", &self.kind)
+            .field("addr", &self.addr)
+            .field("username", &self.username())
+            .finish()
+    }
+}
+
+fn proxy_error(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionRefused, message.into())
+}
+
+fn split_host_port(addr: &str) -> io::Result<(&str, u16)> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidInput, addr.to_string());
+    let (host, port) = addr.rsplit_once(':').ok_or_else(invalid)?;
+    let host = host
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host);
+    Ok((host, port.parse().map_err(|_| invalid())?))
+}
+
+fn socks5_reply_message(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused by destination host",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0_u32, |group, (i, &byte)| {
+            group | (byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    // Accept a single connection, check the proxy request, and then echo back any data
+    async fn mock_proxy<F, Fut>(handshake: F) -> String
+    where
+        F: FnOnce(TcpStream) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = Option<TcpStream>> + Send,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            if let Some(mut stream) = handshake(stream).await {
+                let mut buf = [0; 4];
+                stream.read_exact(&mut buf).await.unwrap();
+                stream.write_all(&buf).await.unwrap();
+            }
+        });
+        addr
+    }
+
+    async fn echo(mut stream: TcpStream) {
+        stream.write_all(b"ping").await.unwrap();
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+    }
+
+    #[test]
+    fn encode_base64() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"user:pass"), "dXNlcjpwYXNz");
+    }
+
+    #[test]
+    fn host_port() {
+        assert_eq!(
+            split_host_port("db.example.com:7687").unwrap(),
+            ("db.example.com", 7687)
+        );
+        assert_eq!(split_host_port("[::1]:7687").unwrap(), ("::1", 7687));
+        assert!(split_host_port("db.example.com").is_err());
+    }
+
+    #[tokio::test]
+    async fn http_connect() {
+        let addr = mock_proxy(|mut stream| async move {
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                request.push(stream.read_u8().await.unwrap());
+            }
+            assert_eq!(
+                String::from_utf8(request).unwrap(),
+                "CONNECT db.example.com:7687 HTTP/1.1\r\nHost: db.example.com:7687\r\n\
+                 Proxy-Authorization: Basic dXNlcjpwYXNz\r\n\r\n"
+            );
+            stream
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                .await
+                .unwrap();
+            Some(stream)
+        })
+        .await;
+
+        let proxy = Proxy::http(addr).with_auth("user", "pass");
+        echo(
+            proxy
+                .tunnel("db.example.com:7687", &StreamConfig::default())
+                .await
+                .unwrap(),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn http_connect_refused() {
+        let addr = mock_proxy(|mut stream| async move {
+            stream
+                .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+                .await
+                .unwrap();
+            None
+        })
+        .await;
+
+        let err = Proxy::http(addr)
+            .tunnel("db.example.com:7687", &StreamConfig::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("407"));
+    }
+
+    #[tokio::test]
+    async fn socks5() {
+        let addr = mock_proxy(|mut stream| async move {
+            let mut greeting = [0; 3];
+            stream.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [5, 1, SOCKS_AUTH_PASSWORD]);
+            stream.write_all(&[5, SOCKS_AUTH_PASSWORD]).await.unwrap();
+
+            let mut auth = [0; 11];
+            stream.read_exact(&mut auth).await.unwrap();
+            assert_eq!(&auth, b"\x01\x04user\x04pass");
+            stream.write_all(&[1, 0]).await.unwrap();
+
+            let mut request = [0; 21];
+            stream.read_exact(&mut request).await.unwrap();
+            assert_eq!(&request, b"\x05\x01\x00\x03\x0edb.example.com\x1e\x07");
+            stream
+                .write_all(&[5, 0, 0, SOCKS_ADDR_IPV4, 127, 0, 0, 1, 0x1e, 0x07])
+                .await
+                .unwrap();
+            Some(stream)
+        })
+        .await;
+
+        let proxy = Proxy::socks5(addr).with_auth("user", "pass");
+        echo(
+            proxy
+                .tunnel("db.example.com:7687", &StreamConfig::default())
+                .await
+                .unwrap(),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn socks5_refused() {
+        let addr = mock_proxy(|mut stream| async move {
+            let mut greeting = [0; 3];
+            stream.read_exact(&mut greeting).await.unwrap();
+            stream.write_all(&[5, SOCKS_AUTH_NONE]).await.unwrap();
+
+            let mut request = [0; 10];
+            stream.read_exact(&mut request).await.unwrap();
+            assert_eq!(request[3], SOCKS_ADDR_IPV4);
+            stream
+                .write_all(&[5, 0x05, 0, SOCKS_ADDR_IPV4, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+            None
+        })
+        .await;
+
+        let err = Proxy::socks5(addr)
+            .tunnel("10.0.0.1:7687", &StreamConfig::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+        assert!(err
+            .to_string()
+            .contains("connection refused by destination host"));
+    }
+}
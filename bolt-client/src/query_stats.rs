@@ -0,0 +1,520 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use bolt_proto::{message::Success, Message, Value};
+
+use crate::{redact::redact_literals, Interceptor};
+
+/// The fingerprint that queries are recorded under once a registry holds its maximum number of
+/// distinct fingerprints.
+pub const OTHER_QUERIES: &str = "<other>";
+
+const DEFAULT_BUCKETS: [Duration; 11] = [
+    Duration::from_millis(5),
+    Duration::from_millis(10),
+    Duration::from_millis(25),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_millis(2500),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+];
+const DEFAULT_MAX_QUERIES: usize = 1000;
+
+/// Latency statistics for the queries with the same fingerprint, as recorded by a
+/// [`QueryStatsRegistry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryStats {
+    bounds: Arc<[Duration]>,
+    // The number of queries in each bucket, not including those in smaller buckets
+    buckets: Vec<u64>,
+    count: u64,
+    failures: u64,
+    total_time: Duration,
+}
+
+impl QueryStats {
+    fn new(bounds: Arc<[Duration]>) -> Self {
+        Self {
+            buckets: vec![0; bounds.len()],
+            bounds,
+            count: 0,
+            failures: 0,
+            total_time: Duration::ZERO,
+        }
+    }
+
+    fn record(&mut self, elapsed: Duration, failed: bool) {
+        if let Some(bucket) = self.bounds.iter().position(|&bound| elapsed <= bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.total_time += elapsed;
+        if failed {
+            self.failures += 1;
+        }
+    }
+
+    /// Get the number of queries recorded, including failed ones.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Get the number of queries that the server failed.
+    pub fn failures(&self) -> u64 {
+        self.failures
+    }
+
+    /// Get the sum of the latencies of all recorded queries.
+    pub fn total_time(&self) -> Duration {
+        self.total_time
+    }
+
+    /// Get the upper bound of each histogram bucket, with the number of queries that took at most
+    /// that long. As in a Prometheus histogram, the counts are cumulative.
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+        self.bounds
+            .iter()
+            .zip(&self.buckets)
+            .scan(0, |total, (&bound, &count)| {
+                *total += count;
+                Some((bound, *total))
+            })
+    }
+}
+
+/// A registry of query latencies, kept as a histogram and count for each query fingerprint. Cloned
+/// registries share the same statistics, so a single registry can collect latencies from any
+/// number of clients, each with an interceptor from [`QueryStatsRegistry::interceptor`].
+///
+/// A query's latency is the time from sending its `RUN` until receiving the `SUCCESS` that ends its
+/// result, or the `FAILURE` that fails it, so it includes the time taken to stream the records.
+/// Queries are grouped by their [fingerprint](QueryStatsRegistry::fingerprint), which ignores
+/// literal values and formatting, so the statistics can be exported with
+/// [`QueryStatsRegistry::render_prometheus`] without creating a time series per literal value.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use bolt_client::QueryStatsRegistry;
+/// let registry = QueryStatsRegistry::new();
+/// // Add this to each client with `client.add_interceptor(registry.interceptor())`
+/// let _interceptor = registry.interceptor();
+///
+/// registry.record("MATCH (n {id: 1}) RETURN n", Duration::from_millis(20), false);
+/// registry.record("MATCH (n {id: 2})\n  RETURN n;", Duration::from_millis(40), true);
+///
+/// let stats = registry.get("MATCH (n {id: 3}) RETURN n").unwrap();
+/// assert_eq!(stats.count(), 2);
+/// assert_eq!(stats.failures(), 1);
+/// assert!(registry.render_prometheus().contains(
+///     r#"bolt_query_duration_seconds_count{query="MATCH (n {id: ?}) RETURN n"} 2"#
+/// ));
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "query-stats")))]
+#[derive(Debug, Clone)]
+pub struct QueryStatsRegistry {
+    bounds: Arc<[Duration]>,
+    max_queries: usize,
+    queries: Arc<Mutex<HashMap<String, QueryStats>>>,
+}
+
+impl QueryStatsRegistry {
+    /// Create an empty registry, with histogram buckets from 5 milliseconds to 10 seconds and room
+    /// for 1000 distinct fingerprints.
+    pub fn new() -> Self {
+        Self {
+            bounds: Arc::from(DEFAULT_BUCKETS.as_slice()),
+            max_queries: DEFAULT_MAX_QUERIES,
+            queries: Default::default(),
+        }
+    }
+
+    /// Set the upper bounds of the histogram buckets. Queries that take longer than the largest
+    /// bound are only counted in the implicit `+Inf` bucket. This clears any recorded statistics.
+    pub fn with_buckets(mut self, bounds: impl IntoIterator<Item = Duration>) -> Self {
+        let mut bounds: Vec<Duration> = bounds.into_iter().collect();
+        bounds.sort();
+        bounds.dedup();
+        self.bounds = Arc::from(bounds);
+        self.queries = Default::default();
+        self
+    }
+
+    /// Set the maximum number of distinct fingerprints to keep statistics for. Once it is reached,
+    /// queries with new fingerprints are recorded under [`OTHER_QUERIES`].
+    pub fn with_max_queries(mut self, max_queries: usize) -> Self {
+        self.max_queries = max_queries;
+        self
+    }
+
+    /// Create an [`Interceptor`] that records the latency of each query a client runs in this
+    /// registry.
+    pub fn interceptor(&self) -> QueryStatsInterceptor {
+        QueryStatsInterceptor {
+            registry: self.clone(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Normalize a query into its fingerprint, replacing string and number literals with `?`,
+    /// removing comments, collapsing whitespace, and dropping a trailing `;`. Parameters,
+    /// identifiers, and keywords are kept as is.
+    ///
+    /// ```
+    /// # use bolt_client::QueryStatsRegistry;
+    /// assert_eq!(
+    ///     QueryStatsRegistry::fingerprint(
+    ///         "MATCH (n:Person {name: 'Alice'})  // find Alice\nWHERE n.age > 30 RETURN n;"
+    ///     ),
+    ///     "MATCH (n:Person {name: ?}) WHERE n.age > ? RETURN n"
+    /// );
+    /// ```
+    pub fn fingerprint(query: &str) -> String {
+        redact_literals(query)
+    }
+
+    /// Record the latency of a query, e.g. one that was run without an interceptor from this
+    /// registry.
+    pub fn record(&self, query: &str, elapsed: Duration, failed: bool) {
+        self.record_fingerprint(Self::fingerprint(query), elapsed, failed);
+    }
+
+    fn record_fingerprint(&self, fingerprint: String, elapsed: Duration, failed: bool) {
+        let mut queries = self.lock();
+        let fingerprint = if queries.contains_key(&fingerprint) || queries.len() < self.max_queries
+        {
+            fingerprint
+        } else {
+            String::from(OTHER_QUERIES)
+        };
+        queries
+            .entry(fingerprint)
+            .or_insert_with(|| QueryStats::new(self.bounds.clone()))
+            .record(elapsed, failed);
+    }
+
+    /// Get the statistics for the fingerprint of the given query, if any queries with it have been
+    /// recorded.
+    pub fn get(&self, query: &str) -> Option<QueryStats> {
+        self.lock().get(&Self::fingerprint(query)).cloned()
+    }
+
+    /// Get the statistics for every recorded fingerprint, sorted by fingerprint.
+    pub fn snapshot(&self) -> Vec<(String, QueryStats)> {
+        let mut snapshot: Vec<_> = self
+            .lock()
+            .iter()
+            .map(|(fingerprint, stats)| (fingerprint.clone(), stats.clone()))
+            .collect();
+        snapshot.sort_by(|(a, _), (b, _)| a.cmp(b));
+        snapshot
+    }
+
+    /// Remove all recorded statistics.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Render the recorded statistics in the Prometheus text exposition format, as a
+    /// `bolt_query_duration_seconds` histogram and a `bolt_query_failures_total` counter, each
+    /// labelled with the query fingerprint.
+    pub fn render_prometheus(&self) -> String {
+        let snapshot = self.snapshot();
+        let mut output = String::new();
+
+        output.push_str(
+            "# HELP bolt_query_duration_seconds Time from sending a query until its result was \
+             consumed.\n# TYPE bolt_query_duration_seconds histogram\n",
+        );
+        for (fingerprint, stats) in &snapshot {
+            let label = escape_label(fingerprint);
+            for (bound, count) in stats.buckets() {
+                let _ = writeln!(
+                    output,
+                    "bolt_query_duration_seconds_bucket{{query=\"{label}\",le=\"{}\"}} {count}",
+                    bound.as_secs_f64()
+                );
+            }
+            let _ = writeln!(
+                output,
+                "bolt_query_duration_seconds_bucket{{query=\"{label}\",le=\"+Inf\"}} {}",
+                stats.count
+            );
+            let _ = writeln!(
+                output,
+                "bolt_query_duration_seconds_sum{{query=\"{label}\"}} {}",
+                stats.total_time.as_secs_f64()
+            );
+            let _ = writeln!(
+                output,
+                "bolt_query_duration_seconds_count{{query=\"{label}\"}} {}",
+                stats.count
+            );
+        }
+
+        output.push_str(
+            "# HELP bolt_query_failures_total Queries that the server failed.\n\
+             # TYPE bolt_query_failures_total counter\n",
+        );
+        for (fingerprint, stats) in &snapshot {
+            let _ = writeln!(
+                output,
+                "bolt_query_failures_total{{query=\"{}\"}} {}",
+                escape_label(fingerprint),
+                stats.failures
+            );
+        }
+        output
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, QueryStats>> {
+        self.queries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for QueryStatsRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+struct PendingQuery {
+    fingerprint: String,
+    start: Instant,
+    // Whether the server has accepted the RUN, so the next SUCCESS without `has_more` ends it
+    streaming: bool,
+}
+
+/// An [`Interceptor`] that records the latency of each query a client runs in a
+/// [`QueryStatsRegistry`], created with [`QueryStatsRegistry::interceptor`].
+#[cfg_attr(docsrs, doc(cfg(feature = "query-stats")))]
+#[derive(Debug)]
+pub struct QueryStatsInterceptor {
+    registry: QueryStatsRegistry,
+    pending: VecDeque<PendingQuery>,
+}
+
+impl Interceptor for QueryStatsInterceptor {
+    fn before_send(&mut self, message: &mut Message) {
+        let query = match message {
+            Message::Run(run) => run.query(),
+            Message::RunWithMetadata(run) => run.statement(),
+            // Queries interrupted by a reset or by closing the connection are not recorded
+            Message::Reset | Message::Goodbye => {
+                self.pending.clear();
+                return;
+            }
+            _ => return,
+        };
+        self.pending.push_back(PendingQuery {
+            fingerprint: QueryStatsRegistry::fingerprint(query),
+            start: Instant::now(),
+            streaming: false,
+        });
+    }
+
+    fn after_receive(&mut self, message: &mut Message) {
+        match message {
+            // The response to RUN is the only SUCCESS that lists the result's fields
+            Message::Success(success) if success.metadata().contains_key("fields") => {
+                if let Some(query) = self.pending.iter_mut().find(|query| !query.streaming) {
+                    query.streaming = true;
+                }
+            }
+            Message::Success(success)
+                if !has_more(success)
+                    && self.pending.front().is_some_and(|query| query.streaming) =>
+            {
+                let query = self.pending.pop_front().unwrap();
+                self.registry
+                    .record_fingerprint(query.fingerprint, query.start.elapsed(), false);
+            }
+            Message::Failure(_) => {
+                // Any other pipelined queries will be ignored by the server
+                if let Some(query) = self.pending.pop_front() {
+                    self.registry.record_fingerprint(
+                        query.fingerprint,
+                        query.start.elapsed(),
+                        true,
+                    );
+                }
+                self.pending.clear();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn has_more(success: &Success) -> bool {
+    matches!(
+        success.metadata().get("has_more"),
+        Some(Value::Boolean(true))
+    )
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use bolt_proto::message::{Begin, Failure, RunWithMetadata};
+
+    use super::*;
+
+    fn success(entries: Vec<(&str, Value)>) -> Message {
+        Message::Success(Success::new(
+            entries
+                .into_iter()
+                .map(|(key, value)| (String::from(key), value))
+                .collect(),
+        ))
+    }
+
+    fn run(query: &str) -> Message {
+        Message::RunWithMetadata(RunWithMetadata::new(
+            String::from(query),
+            Default::default(),
+            Default::default(),
+        ))
+    }
+
+    #[test]
+    fn fingerprint() {
+        for (query, expected) in [
+            ("RETURN 1;", "RETURN ?"),
+            ("  MATCH (n)\n\tRETURN   n ; ", "MATCH (n) RETURN n"),
+            (
+                r#"CREATE (:Person {name: "O\"Brien", nick: 'Bob'})"#,
+                "CREATE (:Person {name: ?, nick: ?})",
+            ),
+            ("RETURN 1.5e3, -0x1F, [1, 2]", "RETURN ?, -?, [?, ?]"),
+            ("UNWIND range(1..10) AS i", "UNWIND range(?..?) AS i"),
+            (
+                "MATCH (n:Label2 {id: $id2}) RETURN n.x1",
+                "MATCH (n:Label2 {id: $id2}) RETURN n.x1",
+            ),
+            (
+                "MATCH (`weird 'name' 1`) RETURN 1",
+                "MATCH (`weird 'name' 1`) RETURN ?",
+            ),
+            ("RETURN 1 /* one */ + 2 // two", "RETURN ? + ?"),
+        ] {
+            assert_eq!(QueryStatsRegistry::fingerprint(query), expected, "{query}");
+        }
+    }
+
+    #[test]
+    fn buckets() {
+        let registry = QueryStatsRegistry::new().with_buckets([
+            Duration::from_secs(1),
+            Duration::from_millis(100),
+            Duration::from_secs(1),
+        ]);
+        for millis in [50, 100, 500, 2000] {
+            registry.record("RETURN 1", Duration::from_millis(millis), false);
+        }
+        let stats = registry.get("RETURN 2").unwrap();
+        assert_eq!(stats.count(), 4);
+        assert_eq!(stats.failures(), 0);
+        assert_eq!(stats.total_time(), Duration::from_millis(2650));
+        assert_eq!(
+            stats.buckets().collect::<Vec<_>>(),
+            [(Duration::from_millis(100), 2), (Duration::from_secs(1), 3)]
+        );
+        assert!(registry.get("RETURN x").is_none());
+
+        registry.clear();
+        assert!(registry.snapshot().is_empty());
+    }
+
+    #[test]
+    fn max_queries() {
+        let registry = QueryStatsRegistry::new().with_max_queries(2);
+        for query in ["RETURN 1", "RETURN 2 AS x", "RETURN 3 AS y", "RETURN 4"] {
+            registry.record(query, Duration::from_millis(1), false);
+        }
+        let snapshot: Vec<_> = registry
+            .snapshot()
+            .into_iter()
+            .map(|(fingerprint, stats)| (fingerprint, stats.count()))
+            .collect();
+        assert_eq!(
+            snapshot,
+            [
+                (String::from(OTHER_QUERIES), 1),
+                (String::from("RETURN ?"), 2),
+                (String::from("RETURN ? AS x"), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn interceptor() {
+        let registry = QueryStatsRegistry::new();
+        let mut interceptor = registry.interceptor();
+        let fields = || ("fields", Value::from(Vec::<Value>::new()));
+
+        // Pipelined BEGIN and RUN, and a result pulled in two batches
+        interceptor.before_send(&mut Message::Begin(Begin::new(Default::default())));
+        interceptor.before_send(&mut run("RETURN 1"));
+        interceptor.after_receive(&mut success(vec![]));
+        interceptor.after_receive(&mut success(vec![fields()]));
+        interceptor.after_receive(&mut success(vec![("has_more", Value::from(true))]));
+        assert!(registry.snapshot().is_empty());
+        interceptor.after_receive(&mut success(vec![]));
+        assert_eq!(registry.get("RETURN 1").unwrap().count(), 1);
+
+        // A failed query, with a pipelined one that is ignored
+        interceptor.before_send(&mut run("RETURN x"));
+        interceptor.before_send(&mut run("RETURN 2"));
+        interceptor.after_receive(&mut Message::Failure(Failure::new(Default::default())));
+        interceptor.after_receive(&mut Message::Ignored);
+        let stats = registry.get("RETURN x").unwrap();
+        assert_eq!((stats.count(), stats.failures()), (1, 1));
+        assert_eq!(registry.get("RETURN 1").unwrap().count(), 1);
+
+        // A query interrupted by RESET
+        interceptor.before_send(&mut run("RETURN y"));
+        interceptor.after_receive(&mut success(vec![fields()]));
+        interceptor.before_send(&mut Message::Reset);
+        interceptor.after_receive(&mut success(vec![]));
+        assert!(registry.get("RETURN y").is_none());
+    }
+
+    #[test]
+    fn render_prometheus() {
+        let registry = QueryStatsRegistry::new().with_buckets([Duration::from_millis(250)]);
+        registry.record(r#"RETURN "a\b""#, Duration::from_millis(100), false);
+        registry.record(r"MATCH (`a\b`) RETURN 1", Duration::from_secs(1), true);
+        assert_eq!(
+            registry.render_prometheus(),
+            "# HELP bolt_query_duration_seconds Time from sending a query until its result was \
+             consumed.\n\
+             # TYPE bolt_query_duration_seconds histogram\n\
+             bolt_query_duration_seconds_bucket{query=\"MATCH (`a\\\\b`) RETURN ?\",le=\"0.25\"} 0\n\
+             bolt_query_duration_seconds_bucket{query=\"MATCH (`a\\\\b`) RETURN ?\",le=\"+Inf\"} 1\n\
+             bolt_query_duration_seconds_sum{query=\"MATCH (`a\\\\b`) RETURN ?\"} 1\n\
+             bolt_query_duration_seconds_count{query=\"MATCH (`a\\\\b`) RETURN ?\"} 1\n\
+             bolt_query_duration_seconds_bucket{query=\"RETURN ?\",le=\"0.25\"} 1\n\
+             bolt_query_duration_seconds_bucket{query=\"RETURN ?\",le=\"+Inf\"} 1\n\
+             bolt_query_duration_seconds_sum{query=\"RETURN ?\"} 0.1\n\
+             bolt_query_duration_seconds_count{query=\"RETURN ?\"} 1\n\
+             # HELP bolt_query_failures_total Queries that the server failed.\n\
+             # TYPE bolt_query_failures_total counter\n\
+             bolt_query_failures_total{query=\"MATCH (`a\\\\b`) RETURN ?\"} 1\n\
+             bolt_query_failures_total{query=\"RETURN ?\"} 0\n"
+        );
+    }
+}
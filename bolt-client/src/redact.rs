@@ -0,0 +1,283 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use bolt_proto::Value;
+
+use crate::Params;
+
+/// Which query parameters are shown in clear when parameters are logged. All other parameters are
+/// replaced with a placeholder describing their type, e.g. `String(8 chars)`, so that credentials
+/// and personal data don't end up in logs.
+///
+/// A client's redaction is used when it logs queries with [`tracing`](https://docs.rs/tracing), and
+/// can be set with [`Client::set_param_redaction`](crate::Client::set_param_redaction). By
+/// default, no parameters are shown.
+/// ```
+/// # use bolt_client::{ParamRedaction, Params};
+/// # use bolt_proto::Value;
+/// let params = Params::from_iter([
+///     ("limit", Value::from(10)),
+///     ("password", Value::from("hunter22")),
+/// ]);
+/// let redaction = ParamRedaction::new().with_allowed_keys(["limit"]);
+/// assert_eq!(
+///     params.redacted_with(&redaction).to_string(),
+///     r#"{"limit": Integer(10), "password": String(8 chars)}"#,
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParamRedaction {
+    allowed_keys: HashSet<String>,
+}
+
+impl ParamRedaction {
+    /// Create a redaction that hides all parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show the parameters with the given names in clear.
+    pub fn with_allowed_keys(mut self, keys: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.allowed_keys.extend(keys.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn allowed_keys(&self) -> &HashSet<String> {
+        &self.allowed_keys
+    }
+
+    pub fn is_allowed(&self, key: &str) -> bool {
+        self.allowed_keys.contains(key)
+    }
+}
+
+/// A view of [`Params`] for logging, created by [`Params::redacted`] or [`Params::redacted_with`].
+/// Parameters are listed in order of their names, with their values hidden according to a
+/// [`ParamRedaction`].
+pub struct Redacted<'a> {
+    params: &'a HashMap<String, Value>,
+    redaction: Option<&'a ParamRedaction>,
+}
+
+impl Params {
+    /// Get a view of these parameters for logging, with every value replaced by a placeholder.
+    pub fn redacted(&self) -> Redacted<'_> {
+        Redacted {
+            params: &self.value,
+            redaction: None,
+        }
+    }
+
+    /// Get a view of these parameters for logging, with values hidden according to `redaction`.
+    pub fn redacted_with<'a>(&'a self, redaction: &'a ParamRedaction) -> Redacted<'a> {
+        Redacted {
+            params: &self.value,
+            redaction: Some(redaction),
+        }
+    }
+}
+
+struct Placeholder<'a>(&'a Value);
+
+impl fmt::Debug for Placeholder<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Value::Boolean(_) => f.write_str("Boolean"),
+            Value::Integer(_) => f.write_str("Integer"),
+            Value::Float(_) => f.write_str("Float"),
+            Value::Bytes(bytes) => write!(f, "Bytes({} bytes)", bytes.len()),
+            Value::List(list) => write!(f, "List({} items)", list.len()),
+            Value::FloatArray(floats) => write!(f, "FloatArray({} items)", floats.len()),
+            Value::Map(map) => write!(f, "Map({} entries)", map.len()),
+            Value::Null => f.write_str("Null"),
+            Value::String(string) => write!(f, "String({} chars)", string.chars().count()),
+            Value::Node(_) => f.write_str("Node"),
+            Value::Relationship(_) => f.write_str("Relationship"),
+            Value::Path(_) => f.write_str("Path"),
+            Value::UnboundRelationship(_) => f.write_str("UnboundRelationship"),
+            Value::Date(_) => f.write_str("Date"),
+            Value::Time(_, _) => f.write_str("Time"),
+            Value::DateTimeOffset(_) => f.write_str("DateTimeOffset"),
+            Value::DateTimeZoned(_) => f.write_str("DateTimeZoned"),
+            Value::LocalTime(_) => f.write_str("LocalTime"),
+            Value::LocalDateTime(_) => f.write_str("LocalDateTime"),
+            Value::Duration(_) => f.write_str("Duration"),
+            Value::Point2D(_) => f.write_str("Point2D"),
+            Value::Point3D(_) => f.write_str("Point3D"),
+            Value::Custom(signature, _) => write!(f, "Custom({:#04X})", signature),
+        }
+    }
+}
+
+// Keys whose values are hidden when a value map is formatted with Debug
+const SENSITIVE_KEYS: [&str; 4] = ["credentials", "password", "secret", "token"];
+
+/// Formats a map in order of its keys, with the values of sensitive keys replaced by
+/// `<redacted>`. Unlike parameter placeholders, this doesn't reveal the type or length of the value.
+pub(crate) struct RedactedMap<'a>(pub(crate) &'a HashMap<String, Value>);
+
+impl fmt::Debug for RedactedMap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut entries: Vec<_> = self.0.iter().collect();
+        entries.sort_unstable_by_key(|(key, _)| *key);
+
+        let mut map = f.debug_map();
+        for (key, value) in entries {
+            match value {
+                _ if SENSITIVE_KEYS.iter().any(|k| k.eq_ignore_ascii_case(key)) => {
+                    map.entry(key, &format_args!("<redacted>"))
+                }
+                Value::Map(inner) => map.entry(key, &NestedMap(inner)),
+                _ => map.entry(key, value),
+            };
+        }
+        map.finish()
+    }
+}
+
+struct NestedMap<'a>(&'a HashMap<String, Value>);
+
+impl fmt::Debug for NestedMap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Map").field(&RedactedMap(self.0)).finish()
+    }
+}
+
+impl fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut params: Vec<_> = self.params.iter().collect();
+        params.sort_unstable_by_key(|(key, _)| *key);
+
+        let mut map = f.debug_map();
+        for (key, value) in params {
+            match self.redaction {
+                Some(redaction) if redaction.is_allowed(key) => map.entry(key, value),
+                _ => map.entry(key, &Placeholder(value)),
+            };
+        }
+        map.finish()
+    }
+}
+
+impl fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+// Replace the string and number literals in a query with `?`, remove comments, collapse
+// whitespace, and drop a trailing `;`
+#[cfg(any(feature = "otel", feature = "query-stats"))]
+pub(crate) fn redact_literals(query: &str) -> String {
+    let mut redacted = String::with_capacity(query.len());
+    let mut space = false;
+    let mut chars = query.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                space = true;
+                continue;
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                chars.find(|&c| c == '\n');
+                space = true;
+                continue;
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = None;
+                for c in chars.by_ref() {
+                    if previous == Some('*') && c == '/' {
+                        break;
+                    }
+                    previous = Some(c);
+                }
+                space = true;
+                continue;
+            }
+            _ => {}
+        }
+
+        if space && !redacted.is_empty() {
+            redacted.push(' ');
+        }
+        space = false;
+        match c {
+            '\'' | '"' => {
+                while let Some(next) = chars.next() {
+                    match next {
+                        '\\' => {
+                            chars.next();
+                        }
+                        next if next == c => break,
+                        _ => {}
+                    }
+                }
+                redacted.push('?');
+            }
+            '`' => {
+                redacted.push(c);
+                for next in chars.by_ref() {
+                    redacted.push(next);
+                    if next == '`' {
+                        break;
+                    }
+                }
+            }
+            c if c.is_ascii_digit() && !redacted.ends_with(is_identifier_char) => {
+                // Consume the rest of the literal, e.g. `1.5e3` or `0x1F`, but not the `..` of
+                // a range
+                while let Some(&next) = chars.peek() {
+                    let mut lookahead = chars.clone();
+                    lookahead.next();
+                    let decimal_point =
+                        next == '.' && lookahead.peek().is_some_and(|c| c.is_ascii_digit());
+                    if !(is_identifier_char(next) || decimal_point) {
+                        break;
+                    }
+                    chars.next();
+                }
+                redacted.push('?');
+            }
+            c => redacted.push(c),
+        }
+    }
+    while redacted.ends_with(';') {
+        redacted.pop();
+        redacted.truncate(redacted.trim_end().len());
+    }
+    redacted
+}
+
+#[cfg(any(feature = "otel", feature = "query-stats"))]
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacted() {
+        let params = Params::from_iter([
+            ("name", Value::from("Zoë")),
+            ("tags", Value::from(vec!["a", "b"])),
+            ("token", Value::Bytes(vec![1, 2, 3])),
+            ("nothing", Value::Null),
+        ]);
+        assert_eq!(
+            params.redacted().to_string(),
+            r#"{"name": String(3 chars), "nothing": Null, "tags": List(2 items), "token": Bytes(3 bytes)}"#
+        );
+
+        let redaction = ParamRedaction::new().with_allowed_keys(["tags", "missing"]);
+        assert_eq!(
+            params.redacted_with(&redaction).to_string(),
+            r#"{"name": String(3 chars), "nothing": Null, "tags": List([String("a"), String("b")]), "token": Bytes(3 bytes)}"#
+        );
+        assert_eq!(Params::default().redacted().to_string(), "{}");
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use bolt_proto::{message::Failure, Value};
+
+const DEFAULT_MAX_RETRY_TIME: Duration = Duration::from_secs(30);
+const DEFAULT_INITIAL_DELAY: Duration = Duration::from_secs(1);
+const DEFAULT_MULTIPLIER: f64 = 2.0;
+const DEFAULT_JITTER: f64 = 0.2;
+
+/// How [`Client::read_transaction`](crate::Client::read_transaction) and
+/// [`Client::write_transaction`](crate::Client::write_transaction) retry transactions that fail
+/// with a transient error.
+///
+/// After each failed attempt, the client waits for a delay before trying again. The first delay is
+/// [`initial_delay`](RetryPolicy::initial_delay), and each following delay is
+/// [`multiplier`](RetryPolicy::multiplier) times longer than the last. Each delay is randomly
+/// shortened or lengthened by up to [`jitter`](RetryPolicy::jitter) of itself, so that clients
+/// that failed together do not all retry at the same time. No more attempts are made once
+/// [`max_retry_time`](RetryPolicy::max_retry_time) has passed since the first one.
+/// ```
+/// # use std::time::Duration;
+/// # use bolt_client::RetryPolicy;
+/// let policy = RetryPolicy::new()
+///     .with_max_retry_time(Duration::from_secs(10))
+///     .with_initial_delay(Duration::from_millis(100))
+///     .with_multiplier(1.5)
+///     .with_jitter(0.1);
+/// assert_eq!(policy.multiplier(), 1.5);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_retry_time: Duration,
+    initial_delay: Duration,
+    multiplier: f64,
+    jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retry_time: DEFAULT_MAX_RETRY_TIME,
+            initial_delay: DEFAULT_INITIAL_DELAY,
+            multiplier: DEFAULT_MULTIPLIER,
+            jitter: DEFAULT_JITTER,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a policy that retries for up to 30 seconds, starting with a 1 second delay that
+    /// doubles after each attempt, with 20% jitter.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a policy that never retries.
+    pub fn no_retry() -> Self {
+        Self::default().with_max_retry_time(Duration::ZERO)
+    }
+
+    /// Set how long after the first attempt new attempts can be started.
+    pub fn with_max_retry_time(mut self, max_retry_time: Duration) -> Self {
+        self.max_retry_time = max_retry_time;
+        self
+    }
+
+    /// Set the delay before the first retry.
+    pub fn with_initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    /// Set the factor each delay is multiplied by to get the next one. Values below 1 are treated
+    /// as 1.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Set the largest fraction of each delay it can be randomly shortened or lengthened by, from 0
+    /// (no jitter) to 1.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn max_retry_time(&self) -> Duration {
+        self.max_retry_time
+    }
+
+    pub fn initial_delay(&self) -> Duration {
+        self.initial_delay
+    }
+
+    pub fn multiplier(&self) -> f64 {
+        self.multiplier.max(1.0)
+    }
+
+    pub fn jitter(&self) -> f64 {
+        self.jitter.clamp(0.0, 1.0)
+    }
+
+    /// Get the delay before the given retry, counting from 0, without jitter.
+    pub(crate) fn delay(&self, retry: u32) -> Duration {
+        let factor = self.multiplier().powi(retry.min(i32::MAX as u32) as i32);
+        Duration::try_from_secs_f64(self.initial_delay.as_secs_f64() * factor)
+            .unwrap_or(self.max_retry_time)
+            .min(self.max_retry_time)
+    }
+
+    /// Get the delay before the given retry, counting from 0, with jitter applied.
+    pub(crate) fn jittered_delay(&self, retry: u32) -> Duration {
+        // A number between -1 and 1, random enough to spread out retries
+        let random =
+            RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64 * 2.0 - 1.0;
+        self.delay(retry).mul_f64(1.0 + self.jitter() * random)
+    }
+}
+
+/// Return whether a transaction that failed with the given failure is worth retrying, e.g. after a
+/// deadlock or a cluster leader switch.
+pub(crate) fn is_retryable(failure: &Failure) -> bool {
+    match failure.metadata().get("code") {
+        Some(Value::String(code)) => match code.as_str() {
+            // These are caused by the client, so retrying would fail again
+            "Neo.TransientError.Transaction.Terminated"
+            | "Neo.TransientError.Transaction.LockClientStopped" => false,
+            "Neo.ClientError.Cluster.NotALeader"
+            | "Neo.ClientError.General.ForbiddenOnReadOnlyDatabase" => true,
+            code => code.starts_with("Neo.TransientError."),
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn failure(code: &str) -> Failure {
+        Failure::new(HashMap::from([(String::from("code"), Value::from(code))]))
+    }
+
+    #[test]
+    fn delays() {
+        let policy = RetryPolicy::new()
+            .with_max_retry_time(Duration::from_secs(5))
+            .with_jitter(0.5);
+        assert_eq!(policy.delay(0), Duration::from_secs(1));
+        assert_eq!(policy.delay(2), Duration::from_secs(4));
+        assert_eq!(policy.delay(3), Duration::from_secs(5));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(5));
+        for _ in 0..100 {
+            let delay = policy.jittered_delay(1);
+            assert!(delay >= Duration::from_secs(1) && delay <= Duration::from_secs(3));
+        }
+        assert_eq!(
+            RetryPolicy::new().with_jitter(0.0).jittered_delay(1),
+            Duration::from_secs(2)
+        );
+    }
+
+    #[test]
+    fn retryable_failures() {
+        assert!(is_retryable(&failure(
+            "Neo.TransientError.Transaction.DeadlockDetected"
+        )));
+        assert!(is_retryable(&failure("Neo.ClientError.Cluster.NotALeader")));
+        assert!(!is_retryable(&failure(
+            "Neo.TransientError.Transaction.Terminated"
+        )));
+        assert!(!is_retryable(&failure(
+            "Neo.ClientError.Statement.SyntaxError"
+        )));
+        assert!(!is_retryable(&Failure::new(HashMap::new())));
+    }
+}
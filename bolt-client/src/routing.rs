@@ -0,0 +1,240 @@
+use std::{collections::HashMap, time::Duration};
+
+use bolt_proto::{error::ConversionError, message::Success, Value};
+
+use crate::{BoltUri, RoutingContext};
+
+/// The role of a server in a cluster, as listed in a [`RoutingTable`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ServerRole {
+    /// Serves routing tables.
+    Route,
+    /// Runs read transactions.
+    Read,
+    /// Runs write transactions.
+    Write,
+}
+
+/// A routing table returned by the server in response to
+/// [`Client::route`](crate::Client::route), listing the addresses of the servers in a cluster by
+/// their role.
+///
+/// ```
+/// # use std::{collections::HashMap, time::Duration};
+/// # use bolt_client::{RoutingTable, ServerRole};
+/// # use bolt_proto::{message::Success, Value};
+/// let server = |role: &str, address: &str| {
+///     Value::from(HashMap::from([
+///         (String::from("role"), Value::from(role)),
+///         (String::from("addresses"), Value::from(vec![address])),
+///     ]))
+/// };
+/// let rt = HashMap::from([
+///     (String::from("ttl"), Value::from(300)),
+///     (
+///         String::from("servers"),
+///         Value::from(vec![
+///             server("ROUTE", "core1:7687"),
+///             server("READ", "replica1:7687"),
+///             server("WRITE", "core1:7687"),
+///         ]),
+///     ),
+/// ]);
+/// let success = Success::new(HashMap::from([(String::from("rt"), Value::from(rt))]));
+///
+/// let table = RoutingTable::from_success(&success).unwrap();
+/// assert_eq!(table.ttl(), Duration::from_secs(300));
+/// assert_eq!(table.servers(ServerRole::Read), ["replica1:7687"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoutingTable {
+    ttl: Duration,
+    database: Option<String>,
+    routers: Vec<String>,
+    readers: Vec<String>,
+    writers: Vec<String>,
+}
+
+impl RoutingTable {
+    /// Read the routing table from the `rt` entry of a `SUCCESS` response to `ROUTE`. Servers with
+    /// an unknown role are skipped.
+    pub fn from_success(success: &Success) -> Result<Self, ConversionError> {
+        let rt = match success.metadata().get("rt") {
+            Some(Value::Map(rt)) => rt,
+            Some(other) => return Err(ConversionError::FromValue(other.clone())),
+            None => return Err(ConversionError::FromValue(Value::Null)),
+        };
+        Self::from_map(rt).ok_or_else(|| ConversionError::FromValue(Value::from(rt.clone())))
+    }
+
+    fn from_map(rt: &HashMap<String, Value>) -> Option<Self> {
+        let ttl = match rt.get("ttl")? {
+            Value::Integer(ttl) => Duration::from_secs(u64::try_from(*ttl).ok()?),
+            _ => return None,
+        };
+        let database = match rt.get("db") {
+            Some(Value::String(database)) => Some(database.clone()),
+            _ => None,
+        };
+
+        let mut table = Self {
+            ttl,
+            database,
+            ..Default::default()
+        };
+        let Value::List(servers) = rt.get("servers")? else {
+            return None;
+        };
+        for server in servers {
+            let Value::Map(server) = server else {
+                return None;
+            };
+            let (Some(Value::String(role)), Some(Value::List(addresses))) =
+                (server.get("role"), server.get("addresses"))
+            else {
+                return None;
+            };
+            let list = match role.as_str() {
+                "ROUTE" => &mut table.routers,
+                "READ" => &mut table.readers,
+                "WRITE" => &mut table.writers,
+                _ => continue,
+            };
+            for address in addresses {
+                match address {
+                    Value::String(address) => list.push(address.clone()),
+                    _ => return None,
+                }
+            }
+        }
+        Some(table)
+    }
+
+    /// Get how long the routing table should be considered valid after it was received.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Get the database the routing table is for. _(Bolt v4.4+ only.)_
+    pub fn database(&self) -> Option<&str> {
+        self.database.as_deref()
+    }
+
+    /// Get the addresses of the servers with the given role, in `"host:port"` form.
+    pub fn servers(&self, role: ServerRole) -> &[String] {
+        match role {
+            ServerRole::Route => &self.routers,
+            ServerRole::Read => &self.readers,
+            ServerRole::Write => &self.writers,
+        }
+    }
+}
+
+impl RoutingContext {
+    /// Create the routing context for a URI: the entries of its query string, other than
+    /// `max_version`, and an `address` entry with the host and port it names.
+    pub fn from_uri(uri: &BoltUri) -> Self {
+        uri.routing_context().clone().with_address(uri.addr())
+    }
+
+    /// Set the `address` entry, which tells the server the address the client used to reach it,
+    /// as [`Client::route`](crate::Client::route) requires.
+    pub fn with_address(mut self, address: impl Into<String>) -> Self {
+        self.value
+            .insert(String::from("address"), Value::from(address.into()));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn success(rt: Value) -> Success {
+        Success::new(HashMap::from([(String::from("rt"), rt)]))
+    }
+
+    fn server(role: &str, addresses: Vec<&str>) -> Value {
+        Value::from(HashMap::from([
+            (String::from("role"), Value::from(role)),
+            (String::from("addresses"), Value::from(addresses)),
+        ]))
+    }
+
+    #[test]
+    fn from_success() {
+        let rt = HashMap::from([
+            (String::from("ttl"), Value::from(10)),
+            (String::from("db"), Value::from("neo4j")),
+            (
+                String::from("servers"),
+                Value::from(vec![
+                    server("ROUTE", vec!["a:7687", "b:7687"]),
+                    server("WRITE", vec!["a:7687"]),
+                    server("READ", vec!["b:7687", "c:7687"]),
+                    server("UNKNOWN", vec!["d:7687"]),
+                ]),
+            ),
+        ]);
+        let table = RoutingTable::from_success(&success(Value::from(rt))).unwrap();
+        assert_eq!(table.ttl(), Duration::from_secs(10));
+        assert_eq!(table.database(), Some("neo4j"));
+        assert_eq!(table.servers(ServerRole::Route), ["a:7687", "b:7687"]);
+        assert_eq!(table.servers(ServerRole::Write), ["a:7687"]);
+        assert_eq!(table.servers(ServerRole::Read), ["b:7687", "c:7687"]);
+    }
+
+    #[test]
+    fn with_address() {
+        let context = RoutingContext::from_iter([("policy", "europe")]).with_address("a:7687");
+        assert_eq!(context.value.get("address"), Some(&Value::from("a:7687")));
+        assert_eq!(context.value.get("policy"), Some(&Value::from("europe")));
+    }
+
+    #[test]
+    fn from_uri() {
+        let uri =
+            BoltUri::parse("neo4j+s://[::1]:7688/movies?policy=europe&max_version=4.4").unwrap();
+        let context = RoutingContext::from_uri(&uri);
+        assert_eq!(context.value.len(), 2);
+        assert_eq!(
+            context.value.get("address"),
+            Some(&Value::from("[::1]:7688"))
+        );
+        assert_eq!(context.value.get("policy"), Some(&Value::from("europe")));
+
+        let uri = BoltUri::parse("bolt://localhost").unwrap();
+        assert_eq!(
+            RoutingContext::from_uri(&uri).value,
+            HashMap::from([(String::from("address"), Value::from("localhost:7687"))])
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(RoutingTable::from_success(&Success::new(HashMap::new())).is_err());
+        assert!(RoutingTable::from_success(&success(Value::from(1))).is_err());
+        for rt in [
+            HashMap::from([(String::from("servers"), Value::from(Vec::<Value>::new()))]),
+            HashMap::from([
+                (String::from("ttl"), Value::from(-1)),
+                (String::from("servers"), Value::from(Vec::<Value>::new())),
+            ]),
+            HashMap::from([
+                (String::from("ttl"), Value::from(10)),
+                (
+                    String::from("servers"),
+                    Value::from(vec![Value::from(HashMap::from([(
+                        String::from("role"),
+                        Value::from("READ"),
+                    )]))]),
+                ),
+            ]),
+        ] {
+            assert!(matches!(
+                RoutingTable::from_success(&success(Value::from(rt))),
+                Err(ConversionError::FromValue(Value::Map(_)))
+            ));
+        }
+    }
+}
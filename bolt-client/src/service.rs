@@ -0,0 +1,262 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use bolt_proto::{message::Record, Message};
+use futures_util::{
+    io::{AsyncRead, AsyncWrite},
+    lock::Mutex,
+};
+use tower_service::Service;
+
+use crate::{
+    error::{CommunicationError, ServiceError},
+    Client, Metadata, Params, QueryHeader, StreamSummary,
+};
+
+/// A query to run with a [`QueryService`].
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+#[derive(Debug, Clone)]
+pub struct RunRequest {
+    query: String,
+    params: Option<Params>,
+    metadata: Option<Metadata>,
+}
+
+impl RunRequest {
+    /// Create a request to run a query without parameters or metadata.
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            params: None,
+            metadata: None,
+        }
+    }
+
+    /// Set the parameters of the query.
+    pub fn with_params(mut self, params: impl Into<Params>) -> Self {
+        self.params = Some(params.into());
+        self
+    }
+
+    /// Set the metadata sent with the query. See [`Client::run`] for the supported entries.
+    pub fn with_metadata(mut self, metadata: impl Into<Metadata>) -> Self {
+        self.metadata = Some(metadata.into());
+        self
+    }
+
+    /// Get the query.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Get the parameters of the query, if any.
+    pub fn params(&self) -> Option<&Params> {
+        self.params.as_ref()
+    }
+
+    /// Get the metadata sent with the query, if any.
+    pub fn metadata(&self) -> Option<&Metadata> {
+        self.metadata.as_ref()
+    }
+}
+
+/// A [tower](https://docs.rs/tower) `Service` that runs queries on a [`Client`], so they can be
+/// wrapped in standard middleware for timeouts, rate limiting, retries, and so on.
+///
+/// Each request is run as an auto-commit transaction with [`Client::run`], and all of its records
+/// are pulled. The response holds the [header](QueryHeader) of the result, its records, and its
+/// [summary](StreamSummary). If
+/// the server fails the query, the connection is [reset](Client::reset) before the failure is
+/// returned as a [`ServiceError::Failed`], so the service can be used for the next request.
+///
+/// The service is always ready. Clones of it share the same client, and run their requests one at
+/// a time, so a separate `QueryService` should be created for each connection that requests are
+/// spread across.
+///
+/// ```no_run
+/// # use bolt_client::*;
+/// # use tower_service::Service;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = ClientBuilder::new("127.0.0.1:7687")
+///     .with_basic_auth("neo4j", "password")
+///     .connect()
+///     .await?;
+/// let mut service = QueryService::new(client);
+///
+/// let request = RunRequest::new("RETURN $n;").with_params(Params::from_iter([("n", 1)]));
+/// let (header, records, summary) = service.call(request).await?;
+/// assert_eq!(header.fields(), ["$n"]);
+/// assert_eq!(records.len(), 1);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+#[derive(Debug)]
+pub struct QueryService<S: AsyncRead + AsyncWrite + Unpin> {
+    client: Arc<Mutex<Client<S>>>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> QueryService<S> {
+    /// Create a service that runs queries on the given client, which must already be
+    /// initialized with [`Client::hello`].
+    pub fn new(client: Client<S>) -> Self {
+        Self {
+            client: Arc::new(Mutex::new(client)),
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Clone for QueryService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            client: Arc::clone(&self.client),
+        }
+    }
+}
+
+impl<S> Service<RunRequest> for QueryService<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    type Response = (QueryHeader, Vec<Record>, StreamSummary);
+    type Error = ServiceError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RunRequest) -> Self::Future {
+        let client = Arc::clone(&self.client);
+        Box::pin(async move {
+            let mut client = client.lock().await;
+            run(&mut client, request).await
+        })
+    }
+}
+
+async fn run<S: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut Client<S>,
+    request: RunRequest,
+) -> Result<(QueryHeader, Vec<Record>, StreamSummary), ServiceError> {
+    let header = match client
+        .run(request.query, request.params, request.metadata)
+        .await?
+    {
+        Message::Success(success) => QueryHeader::from_success(&success),
+        response => return Err(fail(client, response).await),
+    };
+    match client.pull_all().await? {
+        (records, Message::Success(success)) => {
+            Ok((header, records, StreamSummary::from_success(&success)))
+        }
+        (_, response) => Err(fail(client, response).await),
+    }
+}
+
+// Reset the connection after a response other than SUCCESS, and turn the response into an error
+async fn fail<S: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut Client<S>,
+    response: Message,
+) -> ServiceError {
+    if let Err(error) = client.reset().await {
+        return error.into();
+    }
+    match response {
+        Message::Failure(failure) => ServiceError::Failed(failure),
+        response => CommunicationError::InvalidResponse {
+            state: client.server_state(),
+            request: None,
+            response,
+        }
+        .into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bolt_proto::{message::*, version::*, Value};
+    use tokio::io::{duplex, DuplexStream};
+    use tokio_util::compat::*;
+
+    use super::*;
+
+    // Create a service for a client connected to a server that replies to each received message
+    // with a batch of messages, in order
+    async fn mock_service(replies: Vec<Vec<Message>>) -> QueryService<Compat<DuplexStream>> {
+        let (client_stream, server_stream) = duplex(1024);
+        tokio::spawn(async move {
+            let mut server_stream = server_stream.compat();
+            let mut request = [0; 20];
+            futures_util::io::AsyncReadExt::read_exact(&mut server_stream, &mut request)
+                .await
+                .unwrap();
+            futures_util::io::AsyncWriteExt::write_all(&mut server_stream, &V4_4.to_be_bytes())
+                .await
+                .unwrap();
+            for batch in replies {
+                Message::from_stream(&mut server_stream).await.unwrap();
+                for reply in batch {
+                    for chunk in reply.into_chunks().unwrap() {
+                        futures_util::io::AsyncWriteExt::write_all(&mut server_stream, &chunk)
+                            .await
+                            .unwrap();
+                    }
+                }
+            }
+        });
+        let mut client = Client::new(client_stream.compat(), &[V4_4, 0, 0, 0])
+            .await
+            .unwrap();
+        client.hello(Metadata::default()).await.unwrap();
+        QueryService::new(client)
+    }
+
+    fn success(metadata: impl IntoIterator<Item = (&'static str, Value)>) -> Message {
+        Message::Success(Success::new(Metadata::from_iter(metadata).value))
+    }
+
+    #[tokio::test]
+    async fn call() {
+        let mut service = mock_service(vec![
+            vec![success([])],
+            vec![success([("fields", Value::from(vec!["$n"]))])],
+            vec![
+                Message::Record(Record::new(vec![Value::from(1)])),
+                success([("type", Value::from("r"))]),
+            ],
+        ])
+        .await;
+
+        let request = RunRequest::new("RETURN $n;").with_params(Params::from_iter([("n", 1)]));
+        let (header, records, summary) = service.call(request).await.unwrap();
+        assert_eq!(header.fields(), ["$n"]);
+        assert_eq!(records, [Record::new(vec![Value::from(1)])]);
+        assert_eq!(summary.query_type(), Some("r"));
+    }
+
+    #[tokio::test]
+    async fn failure_resets_connection() {
+        let failure = Failure::new(Metadata::from_iter([("code", "Neo.ClientError")]).value);
+        let mut service = mock_service(vec![
+            vec![success([])],
+            vec![Message::Failure(failure.clone())],
+            vec![success([])],
+            vec![success([])],
+            vec![success([])],
+        ])
+        .await;
+
+        assert!(matches!(
+            service.call(RunRequest::new("RETURN;")).await,
+            Err(ServiceError::Failed(f)) if f == failure
+        ));
+        let (_, records, _) = service.call(RunRequest::new("RETURN 1;")).await.unwrap();
+        assert!(records.is_empty());
+    }
+}
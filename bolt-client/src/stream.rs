@@ -1,22 +1,283 @@
 use std::{
-    fmt::Debug,
+    fmt::{self, Debug},
+    future::Future,
     io,
+    net::SocketAddr,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
+    time::{Duration, SystemTime},
 };
 
+use futures_util::{
+    future::{self, BoxFuture, Either},
+    stream::FuturesUnordered,
+    FutureExt, StreamExt,
+};
 use pin_project::pin_project;
+use socket2::{SockRef, TcpKeepalive};
 use tokio::{
     io::{AsyncRead, AsyncWrite, ReadBuf},
-    net::{TcpStream, ToSocketAddrs},
+    net::{lookup_host, TcpSocket, TcpStream, ToSocketAddrs},
 };
 use tokio_rustls::{
     client::TlsStream,
-    rustls::{ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName},
+    rustls::{
+        client::{ServerCertVerified, ServerCertVerifier},
+        Certificate, ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName,
+    },
     TlsConnector,
 };
 
+#[cfg(feature = "proxy")]
+use crate::Proxy;
+
+// The delay recommended by RFC 8305 before trying the next address
+const DEFAULT_FALLBACK_DELAY: Duration = Duration::from_millis(250);
+
+/// Resolves a `"host:port"` address to the socket addresses to connect to, used by
+/// [`ClientBuilder::with_resolver`](crate::ClientBuilder::with_resolver) in place of the system's
+/// DNS resolver, e.g. to look up servers in a service registry.
+///
+/// This is implemented for functions taking the address and returning a future of the resolved
+/// addresses.
+/// ```
+/// # use std::{io, net::SocketAddr, sync::Arc};
+/// # use bolt_client::{ClientBuilder, Resolver};
+/// let resolver = |addr: &str| {
+///     let addr = addr.to_string();
+///     async move {
+///         match addr.as_str() {
+///             "neo4j.service:7687" => Ok(vec![
+///                 SocketAddr::from(([10, 0, 0, 1], 7687)),
+///                 SocketAddr::from(([10, 0, 0, 2], 7687)),
+///             ]),
+///             _ => tokio::net::lookup_host(addr).await.map(Iterator::collect),
+///         }
+///     }
+/// };
+/// let builder = ClientBuilder::new("neo4j.service:7687")
+///     .with_resolver(Some(Arc::new(resolver)));
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-stream")))]
+pub trait Resolver: Send + Sync {
+    /// Resolve the address to the socket addresses to try, in order of preference.
+    fn resolve(&self, addr: &str) -> BoxFuture<'static, io::Result<Vec<SocketAddr>>>;
+}
+
+impl<F, Fut> Resolver for F
+where
+    F: Fn(&str) -> Fut + Send + Sync,
+    Fut: Future<Output = io::Result<Vec<SocketAddr>>> + Send + 'static,
+{
+    fn resolve(&self, addr: &str) -> BoxFuture<'static, io::Result<Vec<SocketAddr>>> {
+        self(addr).boxed()
+    }
+}
+
+impl Debug for dyn Resolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Resolver")
+    }
+}
+
+/// Socket options for the TCP connection underlying a [`Stream`], used by
+/// [`Stream::connect_with_config`] and [`ClientBuilder::with_stream_config`](crate::ClientBuilder::with_stream_config).
+///
+/// By default, the operating system's defaults are used for every option.
+///
+/// When an address resolves to several IP addresses, they are tried in turn, alternating between
+/// IPv6 and IPv4 addresses, as described by [RFC 8305](https://www.rfc-editor.org/rfc/rfc8305)
+/// ("Happy Eyeballs"). If an attempt has neither succeeded nor failed after the
+/// [fallback delay](StreamConfig::with_fallback_delay), the next address is tried alongside it,
+/// and the first connection to be established is used.
+/// ```
+/// # use std::time::Duration;
+/// # use bolt_client::StreamConfig;
+/// let config = StreamConfig::new()
+///     .with_nodelay(true)
+///     .with_keepalive(Some(Duration::from_secs(60)))
+///     .with_keepalive_interval(Some(Duration::from_secs(10)))
+///     .with_connect_timeout(Some(Duration::from_secs(5)))
+///     .with_fallback_delay(Some(Duration::from_millis(100)));
+/// assert!(config.nodelay());
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-stream")))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamConfig {
+    nodelay: bool,
+    keepalive: Option<Duration>,
+    keepalive_interval: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    bind_addr: Option<SocketAddr>,
+    fallback_delay: Option<Duration>,
+}
+
+impl StreamConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether to disable Nagle's algorithm with `TCP_NODELAY`, so that small messages are
+    /// sent right away instead of being buffered.
+    pub fn with_nodelay(mut self, nodelay: bool) -> Self {
+        self.nodelay = nodelay;
+        self
+    }
+
+    /// Enable `SO_KEEPALIVE`, sending keepalive probes once the connection has been idle for the
+    /// given time. This lets dead connections be detected, and keeps firewalls from dropping idle
+    /// ones.
+    pub fn with_keepalive(mut self, time: Option<Duration>) -> Self {
+        self.keepalive = time;
+        self
+    }
+
+    /// Set the time between keepalive probes, if [keepalive](StreamConfig::with_keepalive) is
+    /// enabled. This is ignored on platforms that don't support setting it.
+    pub fn with_keepalive_interval(mut self, interval: Option<Duration>) -> Self {
+        self.keepalive_interval = interval;
+        self
+    }
+
+    /// Set how long to wait for the TCP connection to be established, including resolving the
+    /// address.
+    pub fn with_connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Set the local address to bind the socket to before connecting.
+    pub fn with_bind_addr(mut self, addr: Option<SocketAddr>) -> Self {
+        self.bind_addr = addr;
+        self
+    }
+
+    /// Set how long to wait for a connection attempt before also trying the next resolved
+    /// address. Defaults to 250 milliseconds.
+    pub fn with_fallback_delay(mut self, delay: Option<Duration>) -> Self {
+        self.fallback_delay = delay;
+        self
+    }
+
+    pub fn nodelay(&self) -> bool {
+        self.nodelay
+    }
+
+    pub fn keepalive(&self) -> Option<Duration> {
+        self.keepalive
+    }
+
+    pub fn keepalive_interval(&self) -> Option<Duration> {
+        self.keepalive_interval
+    }
+
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout
+    }
+
+    pub fn bind_addr(&self) -> Option<SocketAddr> {
+        self.bind_addr
+    }
+
+    pub fn fallback_delay(&self) -> Duration {
+        self.fallback_delay.unwrap_or(DEFAULT_FALLBACK_DELAY)
+    }
+
+    /// Open a TCP connection with these options.
+    pub(crate) async fn connect_tcp(&self, addr: impl ToSocketAddrs) -> io::Result<TcpStream> {
+        let stream = match self.connect_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.open(addr))
+                .await
+                .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??,
+            None => self.open(addr).await?,
+        };
+
+        if self.nodelay {
+            stream.set_nodelay(true)?;
+        }
+        if let Some(time) = self.keepalive {
+            #[allow(unused_mut)]
+            let mut keepalive = TcpKeepalive::new().with_time(time);
+            #[cfg(any(
+                target_os = "android",
+                target_os = "freebsd",
+                target_os = "ios",
+                target_os = "linux",
+                target_os = "macos",
+                target_os = "netbsd",
+                target_os = "windows",
+            ))]
+            if let Some(interval) = self.keepalive_interval {
+                keepalive = keepalive.with_interval(interval);
+            }
+            SockRef::from(&stream).set_tcp_keepalive(&keepalive)?;
+        }
+        Ok(stream)
+    }
+
+    async fn open(&self, addr: impl ToSocketAddrs) -> io::Result<TcpStream> {
+        let mut addrs = interleave(lookup_host(addr).await?.collect()).into_iter();
+        let mut attempts = FuturesUnordered::new();
+        attempts.extend(addrs.next().map(|addr| self.open_addr(addr)));
+
+        let mut last_error = None;
+        while !attempts.is_empty() {
+            let result = if addrs.len() == 0 {
+                attempts.next().await
+            } else {
+                let delay = Box::pin(tokio::time::sleep(self.fallback_delay()));
+                match future::select(attempts.next(), delay).await {
+                    Either::Left((result, _)) => result,
+                    Either::Right(_) => None,
+                }
+            };
+            match result {
+                Some(Ok(stream)) => return Ok(stream),
+                Some(Err(error)) => last_error = Some(error),
+                // The fallback delay elapsed
+                None => {}
+            }
+            attempts.extend(addrs.next().map(|addr| self.open_addr(addr)));
+        }
+        Err(last_error.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any address",
+            )
+        }))
+    }
+
+    async fn open_addr(&self, addr: SocketAddr) -> io::Result<TcpStream> {
+        let socket = match addr {
+            SocketAddr::V4(_) => TcpSocket::new_v4()?,
+            SocketAddr::V6(_) => TcpSocket::new_v6()?,
+        };
+        if let Some(bind_addr) = self.bind_addr {
+            socket.bind(bind_addr)?;
+        }
+        socket.connect(addr).await
+    }
+}
+
+// Order addresses to alternate between address families, starting with the family of the first
+// address
+fn interleave(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let first_is_ipv6 = addrs.first().is_some_and(SocketAddr::is_ipv6);
+    let (preferred, other): (Vec<_>, Vec<_>) = addrs
+        .into_iter()
+        .partition(|addr| addr.is_ipv6() == first_is_ipv6);
+
+    let mut interleaved = Vec::with_capacity(preferred.len() + other.len());
+    let (mut preferred, mut other) = (preferred.into_iter(), other.into_iter());
+    loop {
+        match (preferred.next(), other.next()) {
+            (None, None) => return interleaved,
+            (first, second) => interleaved.extend(first.into_iter().chain(second)),
+        }
+    }
+}
+
 /// A convenient wrapper around a [`TcpStream`](tokio::net::TcpStream) or a
 /// [`TlsStream`](tokio_rustls::client::TlsStream).
 #[cfg_attr(docsrs, doc(cfg(feature = "tokio-stream")))]
@@ -29,44 +290,137 @@ pub enum Stream {
 
 impl Stream {
     /// Establish a connection with a remote socket. If a domain is provided, TLS negotiation will
-    /// be attempted.
+    /// be attempted, trusting the root certificates from [`webpki_roots`].
     #[cfg_attr(docsrs, doc(cfg(feature = "tokio-stream")))]
     pub async fn connect(
         addr: impl ToSocketAddrs,
         domain: Option<impl AsRef<str>>,
     ) -> io::Result<Self> {
+        Self::connect_with_config(addr, domain, None, &StreamConfig::default()).await
+    }
+
+    /// Establish a TLS connection with a remote socket, using the given TLS configuration.
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio-stream")))]
+    pub async fn connect_with_tls_config(
+        addr: impl ToSocketAddrs,
+        domain: impl AsRef<str>,
+        config: Arc<ClientConfig>,
+    ) -> io::Result<Self> {
+        Self::connect_with_config(addr, Some(domain), Some(config), &StreamConfig::default()).await
+    }
+
+    /// Establish a connection with a remote socket, using the given socket options. If a domain is
+    /// provided, TLS negotiation will be attempted using the given TLS configuration, or with the
+    /// root certificates from [`webpki_roots`] if no configuration is given.
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio-stream")))]
+    pub async fn connect_with_config(
+        addr: impl ToSocketAddrs,
+        domain: Option<impl AsRef<str>>,
+        tls_config: Option<Arc<ClientConfig>>,
+        config: &StreamConfig,
+    ) -> io::Result<Self> {
+        let stream = config.connect_tcp(addr).await?;
         match domain {
             Some(domain) => {
-                let mut root_cert_store = RootCertStore::empty();
-                root_cert_store.add_server_trust_anchors(
-                    webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|anchor| {
-                        OwnedTrustAnchor::from_subject_spki_name_constraints(
-                            anchor.subject,
-                            anchor.spki,
-                            anchor.name_constraints,
-                        )
-                    }),
-                );
-
-                let config = ClientConfig::builder()
-                    .with_safe_defaults()
-                    .with_root_certificates(root_cert_store)
-                    .with_no_client_auth();
-
-                let server_name = ServerName::try_from(domain.as_ref())
-                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, domain.as_ref()))?;
-
-                let stream = TcpStream::connect(addr).await?;
-
-                Ok(Stream::SecureTcp(Box::new(
-                    TlsConnector::from(Arc::new(config))
-                        .connect(server_name, stream)
-                        .await?,
-                )))
+                let tls_config = tls_config.unwrap_or_else(|| Arc::new(default_tls_config()));
+                Self::secure(stream, domain.as_ref(), tls_config).await
             }
-            None => Ok(Stream::Tcp(TcpStream::connect(addr).await?)),
+            None => Ok(Stream::Tcp(stream)),
         }
     }
+
+    /// Establish a connection with a remote socket through a proxy. The address is resolved by the
+    /// proxy, so it should be given in `"host:port"` form. If a domain is provided, TLS
+    /// negotiation will be attempted with the remote socket using the given TLS configuration, or
+    /// with the root certificates from [`webpki_roots`] if no configuration is given.
+    #[cfg(feature = "proxy")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "proxy")))]
+    pub async fn connect_with_proxy(
+        proxy: &Proxy,
+        addr: impl AsRef<str>,
+        domain: Option<impl AsRef<str>>,
+        config: Option<Arc<ClientConfig>>,
+    ) -> io::Result<Self> {
+        Self::connect_with_proxy_and_config(proxy, addr, domain, config, &StreamConfig::default())
+            .await
+    }
+
+    /// Like [`Stream::connect_with_proxy`], using the given socket options for the connection to
+    /// the proxy.
+    #[cfg(feature = "proxy")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "proxy")))]
+    pub async fn connect_with_proxy_and_config(
+        proxy: &Proxy,
+        addr: impl AsRef<str>,
+        domain: Option<impl AsRef<str>>,
+        config: Option<Arc<ClientConfig>>,
+        stream_config: &StreamConfig,
+    ) -> io::Result<Self> {
+        let stream = proxy.tunnel(addr.as_ref(), stream_config).await?;
+        match domain {
+            Some(domain) => {
+                let config = config.unwrap_or_else(|| Arc::new(default_tls_config()));
+                Self::secure(stream, domain.as_ref(), config).await
+            }
+            None => Ok(Stream::Tcp(stream)),
+        }
+    }
+
+    async fn secure(
+        stream: TcpStream,
+        domain: &str,
+        config: Arc<ClientConfig>,
+    ) -> io::Result<Self> {
+        let server_name = ServerName::try_from(domain)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, domain))?;
+
+        Ok(Stream::SecureTcp(Box::new(
+            TlsConnector::from(config)
+                .connect(server_name, stream)
+                .await?,
+        )))
+    }
+}
+
+fn default_tls_config() -> ClientConfig {
+    let mut root_cert_store = RootCertStore::empty();
+    root_cert_store.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            anchor.subject,
+            anchor.spki,
+            anchor.name_constraints,
+        )
+    }));
+
+    ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_cert_store)
+        .with_no_client_auth()
+}
+
+/// Create a TLS configuration that accepts any server certificate, including self-signed ones.
+/// This protects against eavesdropping, but not against an attacker impersonating the server.
+pub(crate) fn trust_all_tls_config() -> ClientConfig {
+    ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(TrustAll))
+        .with_no_client_auth()
+}
+
+struct TrustAll;
+
+impl ServerCertVerifier for TrustAll {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
 }
 
 impl AsyncRead for Stream {
@@ -108,3 +462,87 @@ impl AsyncWrite for Stream {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn connect_with_config() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let bind_addr = SocketAddr::from((Ipv4Addr::LOCALHOST, 0));
+        let config = StreamConfig::new()
+            .with_nodelay(true)
+            .with_keepalive(Some(Duration::from_secs(30)))
+            .with_keepalive_interval(Some(Duration::from_secs(5)))
+            .with_connect_timeout(Some(Duration::from_secs(5)))
+            .with_bind_addr(Some(bind_addr));
+
+        let stream = Stream::connect_with_config(addr, None::<&str>, None, &config)
+            .await
+            .unwrap();
+        let (_, peer_addr) = listener.accept().await.unwrap();
+        match stream {
+            Stream::Tcp(stream) => {
+                assert_eq!(stream.local_addr().unwrap(), peer_addr);
+                assert!(stream.nodelay().unwrap());
+                assert!(SockRef::from(&stream).keepalive().unwrap());
+            }
+            Stream::SecureTcp(_) => panic!("expected an unencrypted stream"),
+        }
+
+        let stream = Stream::connect_with_config(addr, None::<&str>, None, &StreamConfig::new())
+            .await
+            .unwrap();
+        match stream {
+            Stream::Tcp(stream) => assert!(!SockRef::from(&stream).keepalive().unwrap()),
+            Stream::SecureTcp(_) => panic!("expected an unencrypted stream"),
+        }
+    }
+
+    #[tokio::test]
+    async fn fallback() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        // Nothing is listening on this address once the listener is dropped
+        let closed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let closed_addr = closed.local_addr().unwrap();
+        drop(closed);
+
+        let addrs = [closed_addr, addr];
+        let config = StreamConfig::new().with_fallback_delay(Some(Duration::from_secs(60)));
+        let stream = Stream::connect_with_config(&addrs[..], None::<&str>, None, &config)
+            .await
+            .unwrap();
+        let (_, peer_addr) = listener.accept().await.unwrap();
+        match stream {
+            Stream::Tcp(stream) => assert_eq!(stream.local_addr().unwrap(), peer_addr),
+            Stream::SecureTcp(_) => panic!("expected an unencrypted stream"),
+        }
+
+        let error = Stream::connect_with_config(&[closed_addr][..], None::<&str>, None, &config)
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionRefused);
+    }
+
+    #[test]
+    fn interleave() {
+        let v4 = |n: u8| SocketAddr::from(([10, 0, 0, n], 7687));
+        let v6 = |n: u16| SocketAddr::from(([0xfd00, 0, 0, 0, 0, 0, 0, n], 7687));
+        assert_eq!(
+            super::interleave(vec![v6(1), v6(2), v6(3), v4(1), v4(2)]),
+            vec![v6(1), v4(1), v6(2), v4(2), v6(3)]
+        );
+        assert_eq!(
+            super::interleave(vec![v4(1), v4(2), v6(1)]),
+            vec![v4(1), v6(1), v4(2)]
+        );
+        assert!(super::interleave(vec![]).is_empty());
+    }
+}
@@ -0,0 +1,622 @@
+use std::collections::HashMap;
+
+use bolt_proto::{message::Success, Value};
+
+/// The update counters in the `stats` entry of a result's summary, counting the changes a query
+/// made to the database.
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use bolt_client::Counters;
+/// # use bolt_proto::Value;
+/// let stats = HashMap::from([
+///     (String::from("nodes-created"), Value::from(2)),
+///     (String::from("properties-set"), Value::from(4)),
+/// ]);
+/// let counters = Counters::from_stats(&stats);
+/// assert_eq!(counters.nodes_created(), 2);
+/// assert_eq!(counters.relationships_created(), 0);
+/// assert!(counters.contains_updates());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counters {
+    nodes_created: i64,
+    nodes_deleted: i64,
+    relationships_created: i64,
+    relationships_deleted: i64,
+    properties_set: i64,
+    labels_added: i64,
+    labels_removed: i64,
+    indexes_added: i64,
+    indexes_removed: i64,
+    constraints_added: i64,
+    constraints_removed: i64,
+    system_updates: i64,
+    contains_updates: bool,
+    contains_system_updates: bool,
+}
+
+impl Counters {
+    /// Read the counters from the `stats` map of a summary. Missing counters are 0. If the map has
+    /// no `contains-updates` or `contains-system-updates` flag, as is the case for older servers,
+    /// the flag is set if any of the corresponding counters is nonzero.
+    pub fn from_stats(stats: &HashMap<String, Value>) -> Self {
+        let count = |key| match stats.get(key) {
+            Some(Value::Integer(count)) => *count,
+            _ => 0,
+        };
+        let flag = |key| match stats.get(key) {
+            Some(Value::Boolean(flag)) => Some(*flag),
+            _ => None,
+        };
+
+        let mut counters = Self {
+            nodes_created: count("nodes-created"),
+            nodes_deleted: count("nodes-deleted"),
+            relationships_created: count("relationships-created"),
+            relationships_deleted: count("relationships-deleted"),
+            properties_set: count("properties-set"),
+            labels_added: count("labels-added"),
+            labels_removed: count("labels-removed"),
+            indexes_added: count("indexes-added"),
+            indexes_removed: count("indexes-removed"),
+            constraints_added: count("constraints-added"),
+            constraints_removed: count("constraints-removed"),
+            system_updates: count("system-updates"),
+            contains_updates: false,
+            contains_system_updates: false,
+        };
+        counters.contains_updates = flag("contains-updates").unwrap_or_else(|| {
+            [
+                counters.nodes_created,
+                counters.nodes_deleted,
+                counters.relationships_created,
+                counters.relationships_deleted,
+                counters.properties_set,
+                counters.labels_added,
+                counters.labels_removed,
+                counters.indexes_added,
+                counters.indexes_removed,
+                counters.constraints_added,
+                counters.constraints_removed,
+            ]
+            .iter()
+            .any(|&count| count > 0)
+        });
+        counters.contains_system_updates =
+            flag("contains-system-updates").unwrap_or(counters.system_updates > 0);
+        counters
+    }
+
+    pub fn nodes_created(&self) -> i64 {
+        self.nodes_created
+    }
+
+    pub fn nodes_deleted(&self) -> i64 {
+        self.nodes_deleted
+    }
+
+    pub fn relationships_created(&self) -> i64 {
+        self.relationships_created
+    }
+
+    pub fn relationships_deleted(&self) -> i64 {
+        self.relationships_deleted
+    }
+
+    pub fn properties_set(&self) -> i64 {
+        self.properties_set
+    }
+
+    pub fn labels_added(&self) -> i64 {
+        self.labels_added
+    }
+
+    pub fn labels_removed(&self) -> i64 {
+        self.labels_removed
+    }
+
+    pub fn indexes_added(&self) -> i64 {
+        self.indexes_added
+    }
+
+    pub fn indexes_removed(&self) -> i64 {
+        self.indexes_removed
+    }
+
+    pub fn constraints_added(&self) -> i64 {
+        self.constraints_added
+    }
+
+    pub fn constraints_removed(&self) -> i64 {
+        self.constraints_removed
+    }
+
+    /// Get the number of updates made to the `system` database, e.g. by creating a database or a
+    /// user.
+    pub fn system_updates(&self) -> i64 {
+        self.system_updates
+    }
+
+    /// Check whether the query changed any data or schema in the database it ran against.
+    pub fn contains_updates(&self) -> bool {
+        self.contains_updates
+    }
+
+    /// Check whether the query changed the `system` database.
+    pub fn contains_system_updates(&self) -> bool {
+        self.contains_system_updates
+    }
+}
+
+/// The header of a result, read from the metadata of the [`SUCCESS`](bolt_proto::Message::Success)
+/// response to `RUN` (or `RUN_WITH_METADATA`).
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use bolt_client::QueryHeader;
+/// # use bolt_proto::{message::Success, Value};
+/// let success = Success::new(HashMap::from([
+///     (String::from("fields"), Value::from(vec!["name", "age"])),
+///     (String::from("t_first"), Value::from(3)),
+///     (String::from("qid"), Value::from(1)),
+/// ]));
+/// let header = QueryHeader::from_success(&success);
+/// assert_eq!(header.fields(), ["name", "age"]);
+/// assert_eq!(header.t_first(), Some(3));
+/// assert_eq!(header.qid(), Some(1));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryHeader {
+    fields: Vec<String>,
+    t_first: Option<i64>,
+    qid: Option<i64>,
+}
+
+impl QueryHeader {
+    pub fn from_success(success: &Success) -> Self {
+        let metadata = success.metadata();
+        let integer = |key| match metadata.get(key) {
+            Some(Value::Integer(integer)) => Some(*integer),
+            _ => None,
+        };
+        let fields = match metadata.get("fields") {
+            Some(Value::List(fields)) => fields
+                .iter()
+                .map(|field| match field {
+                    Value::String(field) => field.clone(),
+                    _ => String::new(),
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Self {
+            fields,
+            t_first: integer("t_first").or_else(|| integer("result_available_after")),
+            qid: integer("qid"),
+        }
+    }
+
+    /// Get the names of the fields in each record of the result.
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    /// Get the time in milliseconds after which the first record of the result was available,
+    /// from the `t_first` (or `result_available_after`, for Bolt v1 - v2) entry.
+    pub fn t_first(&self) -> Option<i64> {
+        self.t_first
+    }
+
+    /// Get the ID of the result within its transaction, used to pull records from it while other
+    /// results are open. _(Bolt v4+ only, and only in explicit transactions.)_
+    pub fn qid(&self) -> Option<i64> {
+        self.qid
+    }
+}
+
+impl From<&Success> for QueryHeader {
+    fn from(success: &Success) -> Self {
+        Self::from_success(success)
+    }
+}
+
+/// The summary of a result, read from the metadata of the [`SUCCESS`](bolt_proto::Message::Success)
+/// message that ends a `PULL` or `DISCARD` (or `PULL_ALL` or `DISCARD_ALL`).
+///
+/// Entries that the server did not send, e.g. because they are not supported by the protocol
+/// version, are [`None`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamSummary {
+    query_type: Option<String>,
+    bookmark: Option<String>,
+    database: Option<String>,
+    result_consumed_after: Option<i64>,
+    has_more: bool,
+    counters: Counters,
+}
+
+impl StreamSummary {
+    pub fn from_success(success: &Success) -> Self {
+        let metadata = success.metadata();
+        let string = |key| match metadata.get(key) {
+            Some(Value::String(value)) => Some(value.clone()),
+            _ => None,
+        };
+        let consumed_after = ["t_last", "result_consumed_after"]
+            .into_iter()
+            .find_map(|key| match metadata.get(key) {
+                Some(Value::Integer(millis)) => Some(*millis),
+                _ => None,
+            });
+        let counters = match metadata.get("stats") {
+            Some(Value::Map(stats)) => Counters::from_stats(stats),
+            _ => Counters::default(),
+        };
+
+        Self {
+            query_type: string("type"),
+            bookmark: string("bookmark"),
+            database: string("db"),
+            result_consumed_after: consumed_after,
+            has_more: matches!(metadata.get("has_more"), Some(Value::Boolean(true))),
+            counters,
+        }
+    }
+
+    /// Get the type of the query: read-only (`"r"`), write-only (`"w"`), read-write (`"rw"`), or
+    /// schema (`"s"`).
+    pub fn query_type(&self) -> Option<&str> {
+        self.query_type.as_deref()
+    }
+
+    /// Get the bookmark of the transaction, if the result ended an auto-commit transaction.
+    /// _(Bolt v3+ only.)_
+    pub fn bookmark(&self) -> Option<&str> {
+        self.bookmark.as_deref()
+    }
+
+    /// Get the name of the database the query ran against. _(Bolt v4+ only.)_
+    pub fn database(&self) -> Option<&str> {
+        self.database.as_deref()
+    }
+
+    /// Get the time in milliseconds after which the last record of the result was available, from
+    /// the `t_last` (or `result_consumed_after`, for Bolt v1 - v2) entry.
+    pub fn result_consumed_after(&self) -> Option<i64> {
+        self.result_consumed_after
+    }
+
+    /// Check whether there are records left in the result, after a `PULL` or `DISCARD` for a
+    /// limited number of records. _(Bolt v4+ only, `false` for older versions.)_
+    pub fn has_more(&self) -> bool {
+        self.has_more
+    }
+
+    /// Get the update counters from the `stats` entry. These are all 0 if the server sent no
+    /// `stats`, e.g. for read-only queries.
+    pub fn counters(&self) -> &Counters {
+        &self.counters
+    }
+}
+
+impl From<&Success> for StreamSummary {
+    fn from(success: &Success) -> Self {
+        Self::from_success(success)
+    }
+}
+
+/// The plan of a query run with `EXPLAIN`, read from the `plan` entry of the
+/// [`SUCCESS`](bolt_proto::Message::Success) message that ends its result. Each plan is a tree of
+/// operators, and the records of an operator's children are its input.
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use bolt_client::Plan;
+/// # use bolt_proto::{message::Success, Value};
+/// let scan = HashMap::from([
+///     (String::from("operatorType"), Value::from("AllNodesScan@neo4j")),
+///     (String::from("identifiers"), Value::from(vec!["n"])),
+///     (String::from("args"), Value::from(HashMap::from([("EstimatedRows", 10.0)]))),
+/// ]);
+/// let result = HashMap::from([
+///     (String::from("operatorType"), Value::from("ProduceResults@neo4j")),
+///     (String::from("identifiers"), Value::from(vec!["n"])),
+///     (String::from("children"), Value::from(vec![Value::from(scan)])),
+/// ]);
+/// let success = Success::new(HashMap::from([(String::from("plan"), Value::from(result))]));
+///
+/// let plan = Plan::from_success(&success).unwrap();
+/// assert_eq!(plan.operator_type(), "ProduceResults@neo4j");
+/// assert_eq!(plan.children()[0].operator_type(), "AllNodesScan@neo4j");
+/// assert_eq!(plan.children()[0].identifiers(), ["n"]);
+/// assert_eq!(plan.children()[0].arguments()["EstimatedRows"], Value::from(10.0));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Plan {
+    operator_type: String,
+    identifiers: Vec<String>,
+    arguments: HashMap<String, Value>,
+    children: Vec<Plan>,
+}
+
+impl Plan {
+    /// Read the plan from the `plan` entry of a summary, if it has one.
+    pub fn from_success(success: &Success) -> Option<Self> {
+        match success.metadata().get("plan") {
+            Some(Value::Map(plan)) => Some(Self::from_map(plan)),
+            _ => None,
+        }
+    }
+
+    /// Read a plan from its map of `operatorType`, `identifiers`, `args`, and `children` entries.
+    /// Missing entries are left empty.
+    pub fn from_map(plan: &HashMap<String, Value>) -> Self {
+        Self {
+            operator_type: operator_type(plan),
+            identifiers: identifiers(plan),
+            arguments: arguments(plan),
+            children: children(plan).map(Self::from_map).collect(),
+        }
+    }
+
+    /// Get the name of the operator, e.g. `"NodeByLabelScan@neo4j"`.
+    pub fn operator_type(&self) -> &str {
+        &self.operator_type
+    }
+
+    /// Get the variables the operator's records contain.
+    pub fn identifiers(&self) -> &[String] {
+        &self.identifiers
+    }
+
+    /// Get the operator's arguments, such as `EstimatedRows` or the `Details` of what it does.
+    pub fn arguments(&self) -> &HashMap<String, Value> {
+        &self.arguments
+    }
+
+    /// Get the operators whose records are the input of this one.
+    pub fn children(&self) -> &[Plan] {
+        &self.children
+    }
+}
+
+/// The plan of a query run with `PROFILE`, read from the `profile` entry of the
+/// [`SUCCESS`](bolt_proto::Message::Success) message that ends its result. This is a [`Plan`] that
+/// also counts the work each operator did while the query ran.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProfiledPlan {
+    operator_type: String,
+    identifiers: Vec<String>,
+    arguments: HashMap<String, Value>,
+    children: Vec<ProfiledPlan>,
+    db_hits: i64,
+    rows: i64,
+}
+
+impl ProfiledPlan {
+    /// Read the profiled plan from the `profile` entry of a summary, if it has one.
+    pub fn from_success(success: &Success) -> Option<Self> {
+        match success.metadata().get("profile") {
+            Some(Value::Map(profile)) => Some(Self::from_map(profile)),
+            _ => None,
+        }
+    }
+
+    /// Read a profiled plan from its map of entries, which are those of a [`Plan`] along with
+    /// `dbHits` and `rows`. Missing entries are left empty, and missing counts are 0.
+    pub fn from_map(profile: &HashMap<String, Value>) -> Self {
+        let count = |key| match profile.get(key) {
+            Some(Value::Integer(count)) => *count,
+            _ => 0,
+        };
+
+        Self {
+            operator_type: operator_type(profile),
+            identifiers: identifiers(profile),
+            arguments: arguments(profile),
+            children: children(profile).map(Self::from_map).collect(),
+            db_hits: count("dbHits"),
+            rows: count("rows"),
+        }
+    }
+
+    /// Get the name of the operator, e.g. `"NodeByLabelScan@neo4j"`.
+    pub fn operator_type(&self) -> &str {
+        &self.operator_type
+    }
+
+    /// Get the variables the operator's records contain.
+    pub fn identifiers(&self) -> &[String] {
+        &self.identifiers
+    }
+
+    /// Get the operator's arguments, such as `EstimatedRows` or the `Details` of what it does.
+    pub fn arguments(&self) -> &HashMap<String, Value> {
+        &self.arguments
+    }
+
+    /// Get the operators whose records are the input of this one.
+    pub fn children(&self) -> &[ProfiledPlan] {
+        &self.children
+    }
+
+    /// Get the number of times the operator accessed the database.
+    pub fn db_hits(&self) -> i64 {
+        self.db_hits
+    }
+
+    /// Get the number of records the operator produced.
+    pub fn rows(&self) -> i64 {
+        self.rows
+    }
+}
+
+fn operator_type(plan: &HashMap<String, Value>) -> String {
+    match plan.get("operatorType") {
+        Some(Value::String(operator_type)) => operator_type.clone(),
+        _ => String::new(),
+    }
+}
+
+fn identifiers(plan: &HashMap<String, Value>) -> Vec<String> {
+    match plan.get("identifiers") {
+        Some(Value::List(identifiers)) => identifiers
+            .iter()
+            .filter_map(|identifier| identifier.as_str().map(String::from))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn arguments(plan: &HashMap<String, Value>) -> HashMap<String, Value> {
+    match plan.get("args") {
+        Some(Value::Map(arguments)) => arguments.clone(),
+        _ => HashMap::new(),
+    }
+}
+
+fn children(plan: &HashMap<String, Value>) -> impl Iterator<Item = &HashMap<String, Value>> {
+    let children = match plan.get("children") {
+        Some(Value::List(children)) => children.as_slice(),
+        _ => &[],
+    };
+    children.iter().filter_map(|child| match child {
+        Value::Map(child) => Some(child),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters() {
+        let stats = HashMap::from_iter(
+            [
+                ("nodes-created", Value::from(3)),
+                ("relationships-deleted", Value::from(1)),
+                ("labels-added", Value::from(3)),
+                ("unknown", Value::from(5)),
+            ]
+            .map(|(key, value)| (String::from(key), value)),
+        );
+        let counters = Counters::from_stats(&stats);
+        assert_eq!(counters.nodes_created(), 3);
+        assert_eq!(counters.relationships_deleted(), 1);
+        assert_eq!(counters.labels_added(), 3);
+        assert_eq!(counters.properties_set(), 0);
+        assert!(counters.contains_updates());
+        assert!(!counters.contains_system_updates());
+
+        let counters = Counters::from_stats(&HashMap::new());
+        assert_eq!(counters, Counters::default());
+        assert!(!counters.contains_updates());
+    }
+
+    #[test]
+    fn counter_flags() {
+        let stats = HashMap::from_iter(
+            [
+                ("system-updates", Value::from(1)),
+                ("contains-updates", Value::from(false)),
+                ("contains-system-updates", Value::from(true)),
+            ]
+            .map(|(key, value)| (String::from(key), value)),
+        );
+        let counters = Counters::from_stats(&stats);
+        assert_eq!(counters.system_updates(), 1);
+        assert!(!counters.contains_updates());
+        assert!(counters.contains_system_updates());
+    }
+
+    #[test]
+    fn query_header() {
+        let success = Success::new(HashMap::from_iter(
+            [
+                ("fields", Value::from(vec![Value::from("n"), Value::Null])),
+                ("result_available_after", Value::from(5)),
+            ]
+            .map(|(key, value)| (String::from(key), value)),
+        ));
+        let header = QueryHeader::from(&success);
+        assert_eq!(header.fields(), ["n", ""]);
+        assert_eq!(header.t_first(), Some(5));
+        assert_eq!(header.qid(), None);
+
+        let header = QueryHeader::from_success(&Success::new(HashMap::new()));
+        assert_eq!(header, QueryHeader::default());
+    }
+
+    #[test]
+    fn stream_summary() {
+        let stats = HashMap::from([(String::from("properties-set"), Value::from(2))]);
+        let success = Success::new(HashMap::from_iter(
+            [
+                ("type", Value::from("w")),
+                ("bookmark", Value::from("FB:kcwQ")),
+                ("db", Value::from("neo4j")),
+                ("t_last", Value::from(12)),
+                ("stats", Value::from(stats)),
+            ]
+            .map(|(key, value)| (String::from(key), value)),
+        ));
+        let summary = StreamSummary::from(&success);
+        assert_eq!(summary.query_type(), Some("w"));
+        assert_eq!(summary.bookmark(), Some("FB:kcwQ"));
+        assert_eq!(summary.database(), Some("neo4j"));
+        assert_eq!(summary.result_consumed_after(), Some(12));
+        assert!(!summary.has_more());
+        assert_eq!(summary.counters().properties_set(), 2);
+        assert!(summary.counters().contains_updates());
+
+        let summary = StreamSummary::from_success(&Success::new(HashMap::from([
+            (String::from("result_consumed_after"), Value::from(5)),
+            (String::from("has_more"), Value::from(true)),
+        ])));
+        assert_eq!(summary.result_consumed_after(), Some(5));
+        assert!(summary.has_more());
+        assert_eq!(summary.counters(), &Counters::default());
+    }
+
+    #[test]
+    fn profiled_plan() {
+        let map = |entries: Vec<(&str, Value)>| {
+            Value::from(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (String::from(key), value))
+                    .collect::<HashMap<_, _>>(),
+            )
+        };
+        let scan = map(vec![
+            ("operatorType", Value::from("NodeByLabelScan@neo4j")),
+            ("identifiers", Value::from(vec!["p"])),
+            ("dbHits", Value::from(11)),
+            ("rows", Value::from(10)),
+        ]);
+        let profile = map(vec![
+            ("operatorType", Value::from("ProduceResults@neo4j")),
+            ("args", map(vec![("Details", Value::from("p"))])),
+            ("rows", Value::from(10)),
+            ("children", Value::from(vec![scan, Value::Null])),
+        ]);
+        let success = Success::new(HashMap::from([(String::from("profile"), profile)]));
+
+        let profile = ProfiledPlan::from_success(&success).unwrap();
+        assert_eq!(profile.operator_type(), "ProduceResults@neo4j");
+        assert!(profile.identifiers().is_empty());
+        assert_eq!(profile.arguments()["Details"], Value::from("p"));
+        assert_eq!((profile.db_hits(), profile.rows()), (0, 10));
+        assert_eq!(profile.children().len(), 1);
+        let scan = &profile.children()[0];
+        assert_eq!(scan.identifiers(), ["p"]);
+        assert_eq!((scan.db_hits(), scan.rows()), (11, 10));
+        assert!(scan.children().is_empty());
+
+        assert!(Plan::from_success(&success).is_none());
+        assert!(ProfiledPlan::from_success(&Success::new(HashMap::new())).is_none());
+    }
+}
@@ -0,0 +1,375 @@
+//! Utilities for testing against Neo4j servers running in Docker containers.
+//!
+//! Each [`ServerVersion`] describes a Neo4j release and the Bolt protocol versions it supports.
+//! [`for_each_version`] starts a container for each server in a matrix, and runs a test with a
+//! client for every supported protocol version, so a test suite does not need a server to be
+//! provisioned beforehand. [`TestServer::for_version`] gets a server for tests that need one of
+//! their own, sharing one container per server across the whole test process.
+//!
+//! If `BOLT_TEST_SKIP_UNAVAILABLE` is set, servers whose containers cannot be started are skipped
+//! instead of failing the test, for environments without Docker.
+//!
+//! ```no_run
+//! use bolt_client::test_harness::*;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! for_each_version(MATRIX, |mut client, _| async move {
+//!     client.run("RETURN 1;", None, None).await.unwrap();
+//!     let (records, _) = client.pull(None).await.unwrap();
+//!     assert_eq!(records.len(), 1);
+//! })
+//! .await;
+//! # }
+//! ```
+use std::{
+    collections::HashMap,
+    env,
+    future::Future,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use bolt_proto::version::*;
+use testcontainers::{
+    core::{IntoContainerPort, WaitFor},
+    runners::AsyncRunner,
+    ContainerAsync, GenericImage, ImageExt,
+};
+use tokio::{io::BufStream, runtime::Runtime};
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+
+use crate::{
+    error::{ConnectionError, Result},
+    Client, ClientBuilder, Stream,
+};
+
+pub use testcontainers::TestcontainersError;
+
+const BOLT_PORT: u16 = 7687;
+const SKIP_UNAVAILABLE: &str = "BOLT_TEST_SKIP_UNAVAILABLE";
+const USERNAME: &str = "neo4j";
+// Neo4j 5 requires passwords to be at least 8 characters long
+const PASSWORD: &str = "bolt-rs-test";
+
+/// The type of client created by a [`Neo4jContainer`].
+pub type TestClient = Client<Compat<BufStream<Stream>>>;
+
+/// A Neo4j release that can be run in a container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerVersion {
+    tag: &'static str,
+    bolt_versions: &'static [u32],
+}
+
+impl ServerVersion {
+    /// Describe a Neo4j release by its Docker image tag, and the Bolt protocol versions it
+    /// supports.
+    pub const fn new(tag: &'static str, bolt_versions: &'static [u32]) -> Self {
+        Self { tag, bolt_versions }
+    }
+
+    pub fn tag(&self) -> &'static str {
+        self.tag
+    }
+
+    pub fn bolt_versions(&self) -> &'static [u32] {
+        self.bolt_versions
+    }
+}
+
+pub const NEO4J_3_5: ServerVersion = ServerVersion::new("3.5", &[V1_0, V2_0, V3_0]);
+pub const NEO4J_4_4: ServerVersion =
+    ServerVersion::new("4.4", &[V3_0, V4_0, V4_1, V4_2, V4_3, V4_4]);
+pub const NEO4J_5: ServerVersion = ServerVersion::new("5", &[V4_4]);
+
+/// The servers used to test every protocol version supported by this crate.
+pub const MATRIX: &[ServerVersion] = &[NEO4J_3_5, NEO4J_4_4, NEO4J_5];
+
+/// A running Neo4j container. The container is removed when this is dropped.
+#[derive(Debug)]
+pub struct Neo4jContainer {
+    container: ContainerAsync<GenericImage>,
+    server_version: ServerVersion,
+    addr: String,
+}
+
+impl Neo4jContainer {
+    /// Start a container for the given server version, waiting until it accepts Bolt
+    /// connections.
+    pub async fn start(
+        server_version: ServerVersion,
+    ) -> std::result::Result<Self, TestcontainersError> {
+        let container = GenericImage::new("neo4j", server_version.tag)
+            .with_exposed_port(BOLT_PORT.tcp())
+            .with_wait_for(WaitFor::message_on_stdout("Bolt enabled on"))
+            .with_env_var("NEO4J_AUTH", format!("{}/{}", USERNAME, PASSWORD))
+            .start()
+            .await?;
+        let host = container.get_host().await?;
+        let port = container.get_host_port_ipv4(BOLT_PORT).await?;
+
+        Ok(Self {
+            container,
+            server_version,
+            addr: format!("{}:{}", host, port),
+        })
+    }
+
+    pub fn server_version(&self) -> ServerVersion {
+        self.server_version
+    }
+
+    /// Get the address at which the container accepts Bolt connections, in `"host:port"` form.
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    pub fn username(&self) -> &str {
+        USERNAME
+    }
+
+    pub fn password(&self) -> &str {
+        PASSWORD
+    }
+
+    /// Get the underlying container.
+    pub fn container(&self) -> &ContainerAsync<GenericImage> {
+        &self.container
+    }
+
+    /// Get a [`ClientBuilder`] for the server, configured to authenticate with the container's
+    /// credentials.
+    pub fn builder(&self, version: u32) -> ClientBuilder {
+        ClientBuilder::new(&self.addr)
+            .with_versions([version, 0, 0, 0])
+            .with_basic_auth(USERNAME, PASSWORD)
+    }
+
+    /// Connect to the server using the given protocol version, without initializing the client.
+    pub async fn connect(&self, version: u32) -> Result<TestClient> {
+        let stream = Stream::connect(&self.addr, None::<String>)
+            .await
+            .map_err(ConnectionError::from)?;
+        Ok(Client::new(BufStream::new(stream).compat(), &[version, 0, 0, 0]).await?)
+    }
+
+    /// Connect to the server using the given protocol version, and authenticate the client.
+    pub async fn client(&self, version: u32) -> Result<TestClient> {
+        self.builder(version).connect().await
+    }
+}
+
+// Containers shared by every test in the process. They are started on a runtime of their own,
+// since the Docker client that manages a container can only be used on the runtime that created
+// it, and each test runs on its own runtime.
+struct SharedContainers {
+    runtime: Runtime,
+    containers: Mutex<HashMap<&'static str, Arc<Neo4jContainer>>>,
+    starting: tokio::sync::Mutex<()>,
+}
+
+static SHARED_CONTAINERS: OnceLock<SharedContainers> = OnceLock::new();
+
+fn shared_containers() -> &'static SharedContainers {
+    SHARED_CONTAINERS.get_or_init(|| {
+        // SAFETY: remove_shared_containers is a plain function that does not unwind.
+        unsafe { libc::atexit(remove_shared_containers) };
+        SharedContainers {
+            runtime: tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(1)
+                .enable_all()
+                .build()
+                .expect("failed to build the shared container runtime"),
+            containers: Mutex::default(),
+            starting: tokio::sync::Mutex::const_new(()),
+        }
+    })
+}
+
+extern "C" fn remove_shared_containers() {
+    let Some(shared) = SHARED_CONTAINERS.get() else {
+        return;
+    };
+    let containers = match shared.containers.lock() {
+        Ok(mut containers) => std::mem::take(&mut *containers),
+        Err(_) => return,
+    };
+    shared.runtime.block_on(async {
+        for container in containers.into_values() {
+            match Arc::try_unwrap(container) {
+                Ok(container) => {
+                    let _ = container.container.rm().await;
+                }
+                Err(container) => {
+                    let _ = container.container.stop().await;
+                }
+            }
+        }
+    });
+}
+
+/// Get a container for the given server version that is shared by every caller in the process,
+/// starting it if this is the first call for that version. Shared containers are removed when the
+/// process exits.
+pub async fn shared_container(
+    server_version: ServerVersion,
+) -> std::result::Result<Arc<Neo4jContainer>, TestcontainersError> {
+    let shared = shared_containers();
+    let _starting = shared.starting.lock().await;
+    if let Some(container) = shared.containers.lock().unwrap().get(server_version.tag) {
+        return Ok(Arc::clone(container));
+    }
+
+    let container = shared
+        .runtime
+        .spawn(Neo4jContainer::start(server_version))
+        .await
+        .expect("failed to start container")?;
+    let container = Arc::new(container);
+    shared
+        .containers
+        .lock()
+        .unwrap()
+        .insert(server_version.tag, Arc::clone(&container));
+    Ok(container)
+}
+
+/// The address and credentials of a Neo4j server to run a test against.
+#[derive(Debug, Clone)]
+pub struct TestServer {
+    addr: String,
+    domain: Option<String>,
+    username: String,
+    password: String,
+}
+
+impl TestServer {
+    /// Get a server to test the given protocol version against.
+    ///
+    /// If `BOLT_TEST_ADDR` is set, this is the server it points to, using `BOLT_TEST_DOMAIN`,
+    /// `BOLT_TEST_USERNAME` and `BOLT_TEST_PASSWORD`. Otherwise, it's the [shared
+    /// container](shared_container) for the newest server in [`MATRIX`] that supports the
+    /// version.
+    ///
+    /// # Panics
+    /// Panics if `BOLT_TEST_ADDR` is not set and the container cannot be started.
+    pub async fn for_version(version: u32) -> Self {
+        if let Ok(addr) = env::var("BOLT_TEST_ADDR") {
+            return Self {
+                addr,
+                domain: env::var("BOLT_TEST_DOMAIN").ok(),
+                username: env::var("BOLT_TEST_USERNAME").unwrap(),
+                password: env::var("BOLT_TEST_PASSWORD").unwrap(),
+            };
+        }
+
+        // Versions no server supports exactly, like ranges, fail the handshake or negotiate
+        // the newest server's version
+        let server_version = MATRIX
+            .iter()
+            .rev()
+            .find(|server| server.bolt_versions.contains(&version))
+            .unwrap_or(&MATRIX[MATRIX.len() - 1]);
+        let container = shared_container(*server_version)
+            .await
+            .unwrap_or_else(|err| {
+                panic!(
+                    "failed to start Neo4j {}, and BOLT_TEST_ADDR is not set: {}",
+                    server_version.tag, err
+                )
+            });
+        Self {
+            addr: container.addr.clone(),
+            domain: None,
+            username: String::from(USERNAME),
+            password: String::from(PASSWORD),
+        }
+    }
+
+    /// Get the address at which the server accepts Bolt connections, in `"host:port"` form.
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    /// Get the domain to validate the server's TLS certificate against, if it uses TLS.
+    pub fn domain(&self) -> Option<&str> {
+        self.domain.as_deref()
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+}
+
+/// Get the [shared container](shared_container) for each server in the matrix, and run the test
+/// once for each protocol version it supports, with a new initialized client.
+///
+/// # Panics
+/// Panics if a container cannot be started, for example because Docker is not available, unless
+/// `BOLT_TEST_SKIP_UNAVAILABLE` is set, in which case that server is skipped.
+pub async fn for_each_version<F, Fut>(matrix: &[ServerVersion], mut test: F)
+where
+    F: FnMut(TestClient, u32) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    for &server_version in matrix {
+        let container = match shared_container(server_version).await {
+            Ok(container) => container,
+            Err(err) if env::var_os(SKIP_UNAVAILABLE).is_some() => {
+                println!("Skipping Neo4j {}: {}", server_version.tag, err);
+                continue;
+            }
+            Err(err) => panic!(
+                "failed to start Neo4j {} (set {} to skip it): {}",
+                server_version.tag, SKIP_UNAVAILABLE, err
+            ),
+        };
+
+        for &version in server_version.bolt_versions {
+            let client = container.client(version).await.unwrap_or_else(|err| {
+                panic!(
+                    "failed to connect to Neo4j {} with version {:#x}: {}",
+                    server_version.tag, version, err
+                )
+            });
+            test(client, version).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bolt_proto::{message::*, Value};
+
+    use crate::Metadata;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn run_matrix() {
+        for_each_version(MATRIX, |mut client, version| async move {
+            assert_eq!(client.raw_version(), version);
+            let response = client
+                .run(
+                    "RETURN $n AS n;",
+                    Some(crate::Params::from_iter(vec![("n", 1)])),
+                    None,
+                )
+                .await
+                .unwrap();
+            assert!(Success::try_from(response).is_ok());
+
+            let (records, response) = client
+                .pull(Some(Metadata::from_iter(vec![("n", -1)])))
+                .await
+                .unwrap();
+            assert!(Success::try_from(response).is_ok());
+            assert_eq!(records[0].fields(), &[Value::from(1)]);
+        })
+        .await;
+    }
+}
@@ -0,0 +1,109 @@
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex, PoisonError},
+    task::{Poll, Waker},
+    thread,
+    time::Duration,
+};
+
+use futures_util::{
+    future::{poll_fn, BoxFuture},
+    FutureExt,
+};
+
+/// A source of delays, used by a [`Client`](crate::Client) to wait between transaction retries,
+/// keepalives, and other polling, without depending on a specific async runtime.
+///
+/// Implementations are provided for [tokio](https://tokio.rs/) ([`TokioTimer`]) and
+/// [async-std](https://async.rs/) ([`AsyncStdTimer`]), behind the features of the same names.
+/// [`ThreadTimer`] works with any executor. To use another runtime's timer, implement this trait
+/// and pass it to [`Client::set_timer`](crate::Client::set_timer).
+pub trait Timer: Debug + Send + Sync {
+    /// Return a future that completes once the given duration has elapsed.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// A [`Timer`] that uses [tokio](https://tokio.rs/)'s timer, which must be driven by a tokio
+/// runtime with the time driver enabled. This is the default timer when the `tokio` feature is
+/// enabled.
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioTimer;
+
+#[cfg(feature = "tokio")]
+impl Timer for TokioTimer {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        tokio::time::sleep(duration).boxed()
+    }
+}
+
+/// A [`Timer`] that uses [async-std](https://async.rs/)'s timer. This is the default timer when
+/// the `async-std` feature is enabled and the `tokio` feature is not.
+#[cfg(feature = "async-std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async-std")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsyncStdTimer;
+
+#[cfg(feature = "async-std")]
+impl Timer for AsyncStdTimer {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        async_std::task::sleep(duration).boxed()
+    }
+}
+
+/// A [`Timer`] that waits on a separate thread for each delay, so it works with any executor.
+/// This is the default timer when neither the `tokio` nor the `async-std` feature is enabled.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadTimer;
+
+impl Timer for ThreadTimer {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        // Whether the delay has elapsed, and the waker of the task waiting for it
+        let state = Arc::new(Mutex::new((false, None::<Waker>)));
+        let thread_state = Arc::clone(&state);
+        thread::spawn(move || {
+            thread::sleep(duration);
+            let mut state = thread_state.lock().unwrap_or_else(PoisonError::into_inner);
+            state.0 = true;
+            if let Some(waker) = state.1.take() {
+                waker.wake();
+            }
+        });
+
+        poll_fn(move |cx| {
+            let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+            if state.0 {
+                Poll::Ready(())
+            } else {
+                state.1 = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+        .boxed()
+    }
+}
+
+pub(crate) fn default_timer() -> Box<dyn Timer> {
+    #[cfg(feature = "tokio")]
+    let timer = TokioTimer;
+    #[cfg(all(feature = "async-std", not(feature = "tokio")))]
+    let timer = AsyncStdTimer;
+    #[cfg(not(any(feature = "tokio", feature = "async-std")))]
+    let timer = ThreadTimer;
+    Box::new(timer)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn thread_timer() {
+        let started = Instant::now();
+        ThreadTimer.sleep(Duration::from_millis(20)).await;
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+}
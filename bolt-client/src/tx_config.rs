@@ -0,0 +1,165 @@
+use std::{collections::HashMap, time::Duration};
+
+use bolt_proto::{ServerState, Value};
+
+use crate::Metadata;
+
+/// The kind of access a transaction needs, sent as the `mode` metadata entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AccessMode {
+    Read,
+    #[default]
+    Write,
+}
+
+impl AccessMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            AccessMode::Read => "r",
+            AccessMode::Write => "w",
+        }
+    }
+}
+
+/// Whether a connection is in an explicit transaction, as reported by
+/// [`Client::transaction_status`](crate::Client::transaction_status). This is derived from the
+/// [`ServerState`] of the connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransactionStatus {
+    /// No explicit transaction is open. Queries run in auto-commit transactions, and the result of
+    /// the last one may still be open.
+    Idle,
+    /// An explicit transaction was started with [`Client::begin`](crate::Client::begin) and can
+    /// run queries, be committed, or be rolled back.
+    Active,
+    /// A request failed or the connection was interrupted, and the connection must be
+    /// [reset](crate::Client::reset) before it can be used again. Resetting rolls back any open
+    /// transaction.
+    Failed,
+    /// The connection has been closed, and can no longer be used.
+    Closed,
+}
+
+impl From<ServerState> for TransactionStatus {
+    fn from(state: ServerState) -> Self {
+        match state {
+            ServerState::Connected | ServerState::Ready | ServerState::Streaming => {
+                TransactionStatus::Idle
+            }
+            ServerState::TxReady | ServerState::TxStreaming => TransactionStatus::Active,
+            ServerState::Failed | ServerState::Interrupted => TransactionStatus::Failed,
+            ServerState::Disconnected | ServerState::Defunct => TransactionStatus::Closed,
+        }
+    }
+}
+
+/// Setters for the transaction configuration entries accepted by
+/// [`Client::begin`](crate::Client::begin) and [`Client::run`](crate::Client::run), which take
+/// care of encoding each entry the way the server expects.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use bolt_client::{AccessMode, Metadata};
+/// let metadata = Metadata::default()
+///     .with_bookmarks(vec!["bookmark:1"])
+///     .with_tx_timeout(Duration::from_secs(30))
+///     .with_mode(AccessMode::Read)
+///     .with_db("neo4j");
+/// ```
+impl Metadata {
+    /// Set the `bookmarks` entry to a list of strings.
+    pub fn with_bookmarks(
+        mut self,
+        bookmarks: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        let bookmarks: Vec<String> = bookmarks.into_iter().map(Into::into).collect();
+        self.value
+            .insert(String::from("bookmarks"), Value::from(bookmarks));
+        self
+    }
+
+    /// Set the `tx_timeout` entry, which is sent as an integer number of milliseconds.
+    pub fn with_tx_timeout(mut self, timeout: Duration) -> Self {
+        let millis = i64::try_from(timeout.as_millis()).unwrap_or(i64::MAX);
+        self.value
+            .insert(String::from("tx_timeout"), Value::from(millis));
+        self
+    }
+
+    /// Set the `tx_metadata` entry, a map of metadata attached to the transaction, mainly used for
+    /// logging.
+    pub fn with_tx_metadata<K, V>(mut self, tx_metadata: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        let tx_metadata: HashMap<String, Value> = tx_metadata
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        self.value
+            .insert(String::from("tx_metadata"), Value::from(tx_metadata));
+        self
+    }
+
+    /// Set the `mode` entry, which is sent as `"r"` or `"w"`.
+    pub fn with_mode(mut self, mode: AccessMode) -> Self {
+        self.value
+            .insert(String::from("mode"), Value::from(mode.as_str()));
+        self
+    }
+
+    /// Set the `db` entry, the name of the database the transaction should use.
+    /// _(Bolt v4+ only.)_
+    pub fn with_db(mut self, db: impl Into<String>) -> Self {
+        self.value
+            .insert(String::from("db"), Value::from(db.into()));
+        self
+    }
+
+    /// Set the `imp_user` entry, the user to impersonate for the transaction.
+    /// _(Bolt v4.4+ only.)_
+    pub fn with_imp_user(mut self, imp_user: impl Into<String>) -> Self {
+        self.value
+            .insert(String::from("imp_user"), Value::from(imp_user.into()));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setters() {
+        let metadata = Metadata::default()
+            .with_bookmarks(vec!["bookmark:1", "bookmark:2"])
+            .with_tx_timeout(Duration::from_millis(1500))
+            .with_tx_metadata(vec![("app", "test")])
+            .with_mode(AccessMode::Read)
+            .with_db("neo4j")
+            .with_imp_user("alice");
+
+        assert_eq!(
+            metadata.value.get("bookmarks"),
+            Some(&Value::from(vec!["bookmark:1", "bookmark:2"]))
+        );
+        assert_eq!(metadata.value.get("tx_timeout"), Some(&Value::from(1500)));
+        assert_eq!(
+            metadata.value.get("tx_metadata"),
+            Some(&Value::from(HashMap::from([("app", "test")])))
+        );
+        assert_eq!(metadata.value.get("mode"), Some(&Value::from("r")));
+        assert_eq!(metadata.value.get("db"), Some(&Value::from("neo4j")));
+        assert_eq!(metadata.value.get("imp_user"), Some(&Value::from("alice")));
+
+        let metadata = Metadata::default()
+            .with_mode(AccessMode::Write)
+            .with_tx_timeout(Duration::MAX);
+        assert_eq!(metadata.value.get("mode"), Some(&Value::from("w")));
+        assert_eq!(
+            metadata.value.get("tx_timeout"),
+            Some(&Value::from(i64::MAX))
+        );
+    }
+}
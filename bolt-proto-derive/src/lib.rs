@@ -2,75 +2,229 @@
 
 use proc_macro::TokenStream;
 
-use syn::{AttributeArgs, Fields, Generics, Ident, ItemStruct, NestedMeta, WhereClause};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::{
+    spanned::Spanned, AttributeArgs, Error, Fields, Index, ItemStruct, Member, Meta, NestedMeta,
+    Result, Type,
+};
 
-use quote::{format_ident, quote};
+use quote::{format_ident, quote, quote_spanned};
 
 pub(crate) const MARKER_TINY_STRUCT: u8 = 0xB0;
 pub(crate) const MARKER_SMALL_STRUCT: u8 = 0xDC;
 pub(crate) const MARKER_MEDIUM_STRUCT: u8 = 0xDD;
 
-fn get_struct_info(
-    structure: ItemStruct,
-    args: AttributeArgs,
-) -> (Ident, Generics, Option<WhereClause>, Fields, u8, NestedMeta) {
-    let name = structure.ident;
-    let type_args = structure.generics;
-    let where_clause = type_args.where_clause.clone();
-    let fields = structure.fields;
-
-    let marker = match fields.len() {
-        0..=15 => MARKER_TINY_STRUCT | fields.len() as u8,
-        16..=255 => MARKER_SMALL_STRUCT,
-        256..=65535 => MARKER_MEDIUM_STRUCT,
-        _ => panic!("struct has too many fields"),
-    };
+/// How a field is handled during serialization and deserialization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldMode {
+    /// The field is serialized, and must be present when deserializing.
+    Normal,
+    /// The field is not serialized, and is set to its default value when deserializing.
+    Skip,
+    /// The field is serialized, and is set to its default value when deserializing a null value.
+    Default,
+}
+
+struct FieldInfo {
+    member: Member,
+    var_name: proc_macro2::Ident,
+    ty: Type,
+    mode: FieldMode,
+}
+
+/// Remove the `#[bolt(...)]` attributes from each field, returning how each field should be
+/// handled.
+fn take_field_info(fields: &mut Fields) -> Result<Vec<FieldInfo>> {
+    let mut infos = Vec::with_capacity(fields.len());
+
+    for (index, field) in fields.iter_mut().enumerate() {
+        let mut mode = FieldMode::Normal;
+        let mut error: Option<Error> = None;
+
+        field.attrs.retain(|attr| {
+            if !attr.path.is_ident("bolt") {
+                return true;
+            }
+
+            let result = parse_field_mode(attr.parse_meta()).and_then(|new_mode| {
+                if mode != FieldMode::Normal {
+                    Err(Error::new(attr.span(), "duplicate bolt attribute"))
+                } else {
+                    mode = new_mode;
+                    Ok(())
+                }
+            });
+            if let Err(err) = result {
+                match error.as_mut() {
+                    Some(existing) => existing.combine(err),
+                    None => error = Some(err),
+                }
+            }
+            false
+        });
+
+        if let Some(err) = error {
+            return Err(err);
+        }
 
-    let signature = args.into_iter().next().expect("signature is required");
+        let (member, var_name) = match &field.ident {
+            Some(ident) => (Member::Named(ident.clone()), format_ident!("{}", ident)),
+            None => (
+                Member::Unnamed(Index {
+                    index: index as u32,
+                    span: field.span(),
+                }),
+                format_ident!("field_{}", index),
+            ),
+        };
 
-    (name, type_args, where_clause, fields, marker, signature)
+        infos.push(FieldInfo {
+            member,
+            var_name,
+            ty: field.ty.clone(),
+            mode,
+        });
+    }
+
+    Ok(infos)
 }
 
-#[proc_macro_attribute]
-pub fn bolt_structure(attr_args: TokenStream, item: TokenStream) -> TokenStream {
-    let structure = syn::parse_macro_input!(item as ItemStruct);
-    let args = syn::parse_macro_input!(attr_args as AttributeArgs);
-    let (name, type_args, where_clause, fields, marker, signature) =
-        get_struct_info(structure.clone(), args);
+fn parse_field_mode(meta: Result<Meta>) -> Result<FieldMode> {
+    let meta = meta?;
+    let list = match &meta {
+        Meta::List(list) if list.nested.len() == 1 => list,
+        _ => {
+            return Err(Error::new(
+                meta.span(),
+                "expected #[bolt(skip)] or #[bolt(default)]",
+            ))
+        }
+    };
+
+    match &list.nested[0] {
+        NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => Ok(FieldMode::Skip),
+        NestedMeta::Meta(Meta::Path(path)) if path.is_ident("default") => Ok(FieldMode::Default),
+        other => Err(Error::new(
+            other.span(),
+            "unknown bolt attribute, expected `skip` or `default`",
+        )),
+    }
+}
+
+fn expand(mut structure: ItemStruct, args: AttributeArgs) -> Result<TokenStream2> {
+    let signature = match args.as_slice() {
+        [signature] => signature,
+        [] => {
+            return Err(Error::new(
+                Span::call_site(),
+                "signature is required, e.g. #[bolt_structure(SIGNATURE)]",
+            ))
+        }
+        [_, extra, ..] => return Err(Error::new(extra.span(), "unexpected argument")),
+    };
 
-    let field_names: Vec<Ident> = fields.into_iter().map(|f| f.ident.unwrap()).collect();
-    let byte_var_names: Vec<Ident> = field_names
+    let fields = take_field_info(&mut structure.fields)?;
+    let serialized: Vec<&FieldInfo> = fields
         .iter()
-        .map(|name| format_ident!("{}_bytes", name))
+        .filter(|field| field.mode != FieldMode::Skip)
         .collect();
 
-    let byte_var_defs = byte_var_names.iter()
-        .zip(field_names.iter())
-        .map(|(var_name, field_name)| {
-            quote!(let #var_name = crate::Value::from(self.#field_name).serialize()?;)
+    let size = serialized.len();
+    let (marker, size_bytes) = match size {
+        0..=15 => (MARKER_TINY_STRUCT | size as u8, quote!()),
+        16..=255 => {
+            let size = size as u8;
+            (MARKER_SMALL_STRUCT, quote!(result_bytes_mut.put_u8(#size);))
+        }
+        256..=65535 => {
+            let size = size as u16;
+            (
+                MARKER_MEDIUM_STRUCT,
+                quote!(result_bytes_mut.put_u16(#size);),
+            )
+        }
+        _ => {
+            return Err(Error::new(
+                structure.fields.span(),
+                "struct has too many fields",
+            ))
+        }
+    };
+
+    let byte_var_names: Vec<proc_macro2::Ident> = serialized
+        .iter()
+        .map(|field| format_ident!("{}_bytes", field.var_name))
+        .collect();
+
+    let byte_var_defs = byte_var_names
+        .iter()
+        .zip(serialized.iter())
+        .map(|(var_name, field)| {
+            let (member, ty) = (&field.member, &field.ty);
+            // Errors about unsupported field types point at the field's type
+            let value = quote_spanned!(ty.span()=>
+                <#ty as ::core::convert::Into<crate::Value>>::into(self.#member)
+            );
+            quote!(let #var_name = #value.serialize()?;)
         });
 
-    let deserialize_var_defs = field_names.iter().map(|name| {
-        quote!(
-            let (#name, remaining) = crate::Value::deserialize(bytes)?;
-            bytes = remaining;
-        )
+    let deserialize_var_defs = fields.iter().map(|field| {
+        let (var_name, ty) = (&field.var_name, &field.ty);
+        let try_from = |value| {
+            quote_spanned!(ty.span()=>
+                <#ty as ::core::convert::TryFrom<crate::Value>>::try_from(#value)?
+            )
+        };
+        let default = quote_spanned!(ty.span()=> <#ty as ::core::default::Default>::default());
+        match field.mode {
+            FieldMode::Normal => {
+                let value = try_from(quote!(#var_name));
+                quote!(
+                    let (#var_name, remaining) = crate::Value::deserialize_with(bytes, config)?;
+                    bytes = remaining;
+                    let #var_name: #ty = #value;
+                )
+            }
+            FieldMode::Default => {
+                let value = try_from(quote!(value));
+                quote!(
+                    let (#var_name, remaining) = crate::Value::deserialize_with(bytes, config)?;
+                    bytes = remaining;
+                    let #var_name: #ty = match #var_name {
+                        crate::Value::Null => #default,
+                        value => #value,
+                    };
+                )
+            }
+            FieldMode::Skip => quote!(let #var_name: #ty = #default;),
+        }
     });
 
-    let deserialize_fields = field_names
-        .iter()
-        .map(|name| quote!(#name: #name.try_into()?,));
+    let var_names = fields.iter().map(|field| &field.var_name);
+    let construct = match &structure.fields {
+        Fields::Named(_) => {
+            let members = fields.iter().map(|field| &field.member);
+            quote!(Self { #(#members: #var_names,)* })
+        }
+        Fields::Unnamed(_) => quote!(Self(#(#var_names,)*)),
+        Fields::Unit => quote!(Self),
+    };
+
+    let name = &structure.ident;
+    let (impl_generics, type_generics, where_clause) = structure.generics.split_for_impl();
 
-    quote!(
+    Ok(quote!(
         #structure
 
-        impl #type_args crate::serialization::BoltValue for #name #type_args
+        impl #impl_generics crate::serialization::BoltValue for #name #type_generics
         #where_clause
         {
             fn marker(&self) -> crate::error::SerializeResult<u8> {
                 Ok(#marker)
             }
 
+            // Fields that are already values are converted too
+            #[allow(clippy::useless_conversion)]
             fn serialize(self) -> crate::error::SerializeResult<::bytes::Bytes> {
                 use ::bytes::BufMut;
                 use crate::serialization::{BoltStructure, BoltValue};
@@ -79,31 +233,74 @@ pub fn bolt_structure(attr_args: TokenStream, item: TokenStream) -> TokenStream
                 let signature = self.signature();
                 #(#byte_var_defs)*
 
-                // Marker byte, signature byte, then the rest of the data
+                // Marker byte, size bytes (if any), signature byte, then the rest of the data
                 let mut result_bytes_mut = ::bytes::BytesMut::with_capacity(
-                    std::mem::size_of::<u8>() * 2 #(+ #byte_var_names.len())*
+                    ::core::mem::size_of::<u8>() * 2
+                        + ::core::mem::size_of::<u16>()
+                        #(+ #byte_var_names.len())*
                 );
                 result_bytes_mut.put_u8(marker);
+                #size_bytes
                 result_bytes_mut.put_u8(signature);
                 #(result_bytes_mut.put(#byte_var_names);)*
                 Ok(result_bytes_mut.freeze())
             }
 
-            fn deserialize<B>(mut bytes: B) -> crate::error::DeserializeResult<(Self, B)>
-            where B: ::bytes::Buf + ::std::panic::UnwindSafe
+            #[allow(unused_mut, unused_variables, clippy::useless_conversion)]
+            fn deserialize_with<B>(
+                mut bytes: B,
+                config: &crate::serialization::DecodeConfig,
+            ) -> crate::error::DeserializeResult<(Self, B)>
+            where B: ::bytes::Buf + ::core::panic::UnwindSafe
             {
                 #(#deserialize_var_defs)*
-                Ok((Self { #(#deserialize_fields)* }, bytes))
+                Ok((#construct, bytes))
             }
         }
 
-        impl #type_args crate::serialization::BoltStructure for #name #type_args
+        impl #impl_generics crate::serialization::BoltStructure for #name #type_generics
         #where_clause
         {
             fn signature(&self) -> u8 {
                 #signature
             }
         }
-    )
-    .into()
+    ))
+}
+
+/// Implement serialization for a struct as a Bolt structure with the given signature.
+///
+/// Named-field, tuple, and unit structs are supported, along with generic parameters. Fields are
+/// serialized in declaration order, and the following field attributes are available:
+/// - `#[bolt(skip)]` - the field is not serialized, and is set to its default value when
+///   deserializing
+/// - `#[bolt(default)]` - the field is set to its default value when deserializing a null value
+///
+/// The type of each serialized field must implement `Into<Value>` and `TryFrom<Value>`, and
+/// skipped or defaulted fields must implement `Default`. Generic parameters used as field types
+/// need these bounds in the struct's `where` clause.
+#[proc_macro_attribute]
+pub fn bolt_structure(attr_args: TokenStream, item: TokenStream) -> TokenStream {
+    let structure = syn::parse_macro_input!(item as ItemStruct);
+    let args = syn::parse_macro_input!(attr_args as AttributeArgs);
+    expand(structure, args)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn too_many_fields() {
+        // Too large for a UI test, so the expansion is checked directly
+        let fields: Vec<String> = (0..=u16::MAX as usize)
+            .map(|index| format!("field_{}: i64", index))
+            .collect();
+        let structure = syn::parse_str(&format!("struct Large {{ {} }}", fields.join(", ")));
+        let signature = syn::parse_str("0x01").unwrap();
+        let error = expand(structure.unwrap(), vec![signature]).unwrap_err();
+        assert_eq!(error.to_string(), "struct has too many fields");
+    }
 }
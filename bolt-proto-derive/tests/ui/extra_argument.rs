@@ -0,0 +1,8 @@
+use bolt_proto_derive::bolt_structure;
+
+#[bolt_structure(0x01, 0x02)]
+struct Point {
+    x: i64,
+}
+
+fn main() {}
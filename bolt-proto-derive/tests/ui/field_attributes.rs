@@ -0,0 +1,22 @@
+use bolt_proto_derive::bolt_structure;
+
+#[bolt_structure(0x01)]
+struct Unknown {
+    #[bolt(rename)]
+    x: i64,
+}
+
+#[bolt_structure(0x02)]
+struct Duplicate {
+    #[bolt(skip)]
+    #[bolt(default)]
+    x: i64,
+}
+
+#[bolt_structure(0x03)]
+struct Malformed {
+    #[bolt = "skip"]
+    x: i64,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+include!("support/shim.rs");
+
+use bolt_proto_derive::bolt_structure;
+
+#[bolt_structure(0x01)]
+struct Wrapper<T> {
+    value: T,
+}
+
+fn main() {}
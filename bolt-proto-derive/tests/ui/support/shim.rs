@@ -0,0 +1,76 @@
+// Stand-ins for the bolt-proto items that the generated code refers to, so that the only errors
+// are the ones caused by the struct under test
+
+mod error {
+    #[derive(Debug)]
+    pub struct Error;
+
+    impl From<core::convert::Infallible> for Error {
+        fn from(error: core::convert::Infallible) -> Self {
+            match error {}
+        }
+    }
+
+    pub type SerializeResult<T> = Result<T, Error>;
+    pub type DeserializeResult<T> = Result<T, Error>;
+}
+
+mod serialization {
+    use crate::error::{DeserializeResult, SerializeResult};
+
+    pub trait BoltValue: Sized {
+        fn marker(&self) -> SerializeResult<u8>;
+
+        fn serialize(self) -> SerializeResult<bytes::Bytes>;
+
+        fn deserialize_with<B: bytes::Buf + core::panic::UnwindSafe>(
+            bytes: B,
+            config: &DecodeConfig,
+        ) -> DeserializeResult<(Self, B)>;
+    }
+
+    pub struct DecodeConfig;
+
+    pub trait BoltStructure: BoltValue {
+        fn signature(&self) -> u8;
+    }
+}
+
+use serialization::BoltValue;
+
+pub enum Value {
+    Integer(i64),
+}
+
+impl BoltValue for Value {
+    fn marker(&self) -> error::SerializeResult<u8> {
+        unimplemented!()
+    }
+
+    fn serialize(self) -> error::SerializeResult<bytes::Bytes> {
+        unimplemented!()
+    }
+
+    fn deserialize_with<B: bytes::Buf + core::panic::UnwindSafe>(
+        _bytes: B,
+        _config: &serialization::DecodeConfig,
+    ) -> error::DeserializeResult<(Self, B)> {
+        unimplemented!()
+    }
+}
+
+impl From<i64> for Value {
+    fn from(integer: i64) -> Self {
+        Value::Integer(integer)
+    }
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = error::Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Integer(integer) => Ok(integer),
+        }
+    }
+}
@@ -0,0 +1,11 @@
+include!("support/shim.rs");
+
+use bolt_proto_derive::bolt_structure;
+
+#[bolt_structure(0x01)]
+struct Labeled {
+    id: i64,
+    label: String,
+}
+
+fn main() {}
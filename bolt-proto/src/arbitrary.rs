@@ -0,0 +1,307 @@
+//! [`proptest`] strategies for generating arbitrary [`Value`]s and [`Message`]s, for property-based
+//! testing of code that handles them.
+//!
+//! Generated values round-trip through serialization: floats are never NaN (which is not equal to
+//! itself), temporal values are within the range supported by [`chrono`], and
+//! [custom structures](Value::Custom) only use signatures that are not part of the protocol.
+//!
+//! ```
+//! use bolt_proto::{arbitrary, message::Record, Message};
+//! use proptest::prelude::*;
+//!
+//! proptest!(|(message in any::<Message>())| {
+//!     let chunks = message.into_chunks().unwrap();
+//!     prop_assert_eq!(chunks.last().unwrap().as_ref(), &[0, 0]);
+//! });
+//!
+//! proptest!(|(value in arbitrary::value_with(1, 4))| {
+//!     let record = Message::Record(Record::new(vec![value]));
+//!     prop_assert!(record.into_chunks().is_ok());
+//! });
+//! ```
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, TimeZone};
+use chrono_tz::{Tz, TZ_VARIANTS};
+use proptest::{
+    arbitrary::Arbitrary,
+    collection::{hash_map, vec},
+    num::f64,
+    option,
+    prelude::*,
+    sample::select,
+};
+
+use crate::{
+    collections::HashMap,
+    message::*,
+    value::{Duration, Node, Path, Point2D, Point3D, Relationship, UnboundRelationship},
+    Message, Value,
+};
+
+// 0001-01-01T00:00:00Z to 9999-12-31T23:59:59Z, in seconds since the UNIX epoch
+const MIN_TIMESTAMP: i64 = -62_135_596_800;
+const MAX_TIMESTAMP: i64 = 253_402_300_799;
+// The largest UTC offset in use, in seconds
+const MAX_OFFSET: i32 = 18 * 60 * 60;
+
+const DEFAULT_DEPTH: u32 = 3;
+const DEFAULT_SIZE: usize = 8;
+
+/// Generate values nested up to 3 levels deep, with up to 8 items in each list, map, or
+/// structure.
+pub fn value() -> BoxedStrategy<Value> {
+    value_with(DEFAULT_DEPTH, DEFAULT_SIZE)
+}
+
+/// Generate values nested up to `depth` levels deep, with up to `size` items in each list, map, or
+/// structure.
+pub fn value_with(depth: u32, size: usize) -> BoxedStrategy<Value> {
+    scalar_value(size)
+        .prop_recursive(depth, (size * size) as u32, size as u32, move |inner| {
+            prop_oneof![
+                vec(inner.clone(), 0..=size).prop_map(Value::List),
+                map(inner.clone(), size).prop_map(Value::Map),
+                node(inner.clone(), size).prop_map(Value::Node),
+                relationship(inner.clone(), size).prop_map(Value::Relationship),
+                unbound_relationship(inner.clone(), size).prop_map(Value::UnboundRelationship),
+                path(inner.clone(), size).prop_map(Value::Path),
+                (0x80..=0xFF_u8, vec(inner, 0..=size))
+                    .prop_map(|(signature, fields)| Value::Custom(signature, fields)),
+            ]
+        })
+        .boxed()
+}
+
+/// Generate values that contain no other values.
+pub fn scalar_value(size: usize) -> BoxedStrategy<Value> {
+    prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Boolean),
+        any::<i64>().prop_map(Value::Integer),
+        float().prop_map(Value::Float),
+        vec(any::<u8>(), 0..=size).prop_map(Value::Bytes),
+        any::<String>().prop_map(Value::String),
+        temporal_value(),
+        (any::<i64>(), any::<i64>(), any::<i64>(), any::<i32>()).prop_map(
+            |(months, days, seconds, nanos)| Value::Duration(Duration::new(
+                months, days, seconds, nanos
+            ))
+        ),
+        (any::<i32>(), float(), float())
+            .prop_map(|(srid, x, y)| Value::Point2D(Point2D::new(srid, x, y))),
+        (any::<i32>(), float(), float(), float())
+            .prop_map(|(srid, x, y, z)| Value::Point3D(Point3D::new(srid, x, y, z))),
+    ]
+    .boxed()
+}
+
+/// Generate messages of every kind, with values in their fields generated by [`value`].
+pub fn message() -> BoxedStrategy<Message> {
+    message_with(value())
+}
+
+/// Generate messages of every kind, with values in their fields generated by the given strategy.
+pub fn message_with(value: BoxedStrategy<Value>) -> BoxedStrategy<Message> {
+    let size = DEFAULT_SIZE;
+    let metadata = {
+        let value = value.clone();
+        move || map(value.clone(), size)
+    };
+    let strings = || vec(any::<String>(), 0..=size);
+
+    let requests = prop_oneof![
+        (any::<String>(), metadata()).prop_map(|(user_agent, auth_token)| {
+            Message::Init(Init::new(user_agent, auth_token))
+        }),
+        (any::<String>(), metadata())
+            .prop_map(|(query, parameters)| Message::Run(Run::new(query, parameters))),
+        metadata().prop_map(|metadata| Message::Hello(Hello::new(metadata))),
+        (any::<String>(), metadata(), metadata()).prop_map(|(statement, parameters, metadata)| {
+            Message::RunWithMetadata(RunWithMetadata::new(statement, parameters, metadata))
+        }),
+        metadata().prop_map(|metadata| Message::Begin(Begin::new(metadata))),
+        metadata().prop_map(|metadata| Message::Discard(Discard::new(metadata))),
+        metadata().prop_map(|metadata| Message::Pull(Pull::new(metadata))),
+        (metadata(), strings(), option::of(any::<String>())).prop_map(
+            |(context, bookmarks, database)| Message::Route(Route::new(
+                context, bookmarks, database
+            ))
+        ),
+        (metadata(), strings(), metadata()).prop_map(|(context, bookmarks, metadata)| {
+            Message::RouteWithMetadata(RouteWithMetadata::new(context, bookmarks, metadata))
+        }),
+    ];
+    let responses = prop_oneof![
+        vec(value, 0..=size).prop_map(|fields| Message::Record(Record::new(fields))),
+        metadata().prop_map(|metadata| Message::Success(Success::new(metadata))),
+        metadata().prop_map(|metadata| Message::Failure(Failure::new(metadata))),
+    ];
+    let empty = select(vec![
+        Message::DiscardAll,
+        Message::PullAll,
+        Message::AckFailure,
+        Message::Reset,
+        Message::Ignored,
+        Message::Goodbye,
+        Message::Commit,
+        Message::Rollback,
+    ]);
+
+    prop_oneof![requests, responses, empty].boxed()
+}
+
+impl Arbitrary for Value {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        value()
+    }
+}
+
+impl Arbitrary for Message {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        message()
+    }
+}
+
+// Any float except NaN
+fn float() -> impl Strategy<Value = f64> {
+    f64::POSITIVE | f64::NEGATIVE | f64::NORMAL | f64::SUBNORMAL | f64::ZERO | f64::INFINITE
+}
+
+fn map(value: BoxedStrategy<Value>, size: usize) -> BoxedStrategy<HashMap<String, Value>> {
+    hash_map(any::<String>(), value, 0..=size)
+        .prop_map(|map| map.into_iter().collect())
+        .boxed()
+}
+
+fn temporal_value() -> BoxedStrategy<Value> {
+    let timestamp = || (MIN_TIMESTAMP..=MAX_TIMESTAMP, 0..1_000_000_000_u32);
+    let offset =
+        || (-MAX_OFFSET..=MAX_OFFSET).prop_map(|secs| FixedOffset::east_opt(secs).unwrap());
+    let time = || {
+        (0..86_400_u32, 0..1_000_000_000_u32).prop_map(|(secs, nanos)| {
+            NaiveTime::from_num_seconds_from_midnight_opt(secs, nanos).unwrap()
+        })
+    };
+
+    prop_oneof![
+        (1..=3_652_059_i32)
+            .prop_map(|days| Value::Date(NaiveDate::from_num_days_from_ce_opt(days).unwrap())),
+        (time(), offset()).prop_map(|(time, offset)| Value::Time(time, offset)),
+        (timestamp(), offset()).prop_map(|((secs, nanos), offset)| {
+            Value::DateTimeOffset(offset.timestamp_opt(secs, nanos).unwrap())
+        }),
+        (timestamp(), select(TZ_VARIANTS.as_slice())).prop_map(|((secs, nanos), tz): (_, Tz)| {
+            Value::DateTimeZoned(tz.timestamp_opt(secs, nanos).unwrap())
+        }),
+        time().prop_map(Value::LocalTime),
+        timestamp().prop_map(|(secs, nanos)| {
+            Value::LocalDateTime(DateTime::from_timestamp(secs, nanos).unwrap().naive_utc())
+        }),
+    ]
+    .boxed()
+}
+
+fn node(value: BoxedStrategy<Value>, size: usize) -> impl Strategy<Value = Node> {
+    (
+        any::<i64>(),
+        vec(any::<String>(), 0..=size),
+        map(value, size),
+    )
+        .prop_map(|(identity, labels, properties)| Node::new(identity, labels, properties))
+}
+
+fn relationship(value: BoxedStrategy<Value>, size: usize) -> impl Strategy<Value = Relationship> {
+    (
+        any::<i64>(),
+        any::<i64>(),
+        any::<i64>(),
+        any::<String>(),
+        map(value, size),
+    )
+        .prop_map(|(identity, start, end, rel_type, properties)| {
+            Relationship::new(identity, start, end, rel_type, properties)
+        })
+}
+
+fn unbound_relationship(
+    value: BoxedStrategy<Value>,
+    size: usize,
+) -> impl Strategy<Value = UnboundRelationship> {
+    (any::<i64>(), any::<String>(), map(value, size)).prop_map(
+        |(identity, rel_type, properties)| UnboundRelationship::new(identity, rel_type, properties),
+    )
+}
+
+fn path(value: BoxedStrategy<Value>, size: usize) -> impl Strategy<Value = Path> {
+    (
+        vec(node(value.clone(), size), 0..=size),
+        vec(unbound_relationship(value, size), 0..=size),
+        vec(any::<i64>(), 0..=size),
+    )
+        .prop_map(|(nodes, relationships, sequence)| Path::new(nodes, relationships, sequence))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::serialization::BoltValue;
+
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn value_round_trip(value in any::<Value>()) {
+            let bytes = value.clone().serialize().unwrap();
+            let (deserialized, remaining) = Value::deserialize(bytes).unwrap();
+            prop_assert_eq!(deserialized, value);
+            prop_assert_eq!(remaining.len(), 0);
+        }
+
+        #[test]
+        fn message_round_trip(message in any::<Message>(), piece_size in 1_usize..64) {
+            let bytes: Vec<u8> = message.clone().into_chunks().unwrap().concat();
+
+            // Feed the chunks to a decoder in small pieces, as if read from a slow connection
+            let mut decoder = MessageDecoder::new();
+            let mut decoded = None;
+            for piece in bytes.chunks(piece_size) {
+                prop_assert!(decoded.is_none());
+                decoded = decoder.feed(piece).unwrap();
+            }
+            prop_assert_eq!(decoded, Some(message));
+            prop_assert!(decoder.is_empty());
+        }
+
+        #[test]
+        fn chunks(len in 0_usize..40_000) {
+            let message = Message::Record(Record::new(vec![Value::Bytes(vec![0xAB; len])]));
+            let serialized_len = message.clone().serialize().unwrap().len();
+            let chunks = message.clone().into_chunks().unwrap();
+
+            let (footer, chunks) = chunks.split_last().unwrap();
+            prop_assert_eq!(footer.as_ref(), &[0, 0]);
+            let mut total = 0;
+            for chunk in chunks {
+                let chunk_len = u16::from_be_bytes([chunk[0], chunk[1]]) as usize;
+                prop_assert!(chunk_len > 0);
+                prop_assert_eq!(chunk_len, chunk.len() - 2);
+                total += chunk_len;
+            }
+            prop_assert_eq!(total, serialized_len);
+            // All chunks but the last are full
+            if let Some((_, full)) = chunks.split_last() {
+                prop_assert!(full.iter().all(|chunk| chunk.len() == chunks[0].len()));
+            }
+
+            let mut decoder = MessageDecoder::new();
+            let bytes: Vec<u8> = chunks.concat();
+            prop_assert_eq!(decoder.feed(&bytes).unwrap(), None);
+            prop_assert_eq!(decoder.feed(footer).unwrap(), Some(message));
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! Collection types used in [`Value`](crate::Value)s and messages.
+
+/// The map type used for [`Value::Map`](crate::Value::Map) and for message metadata. This is
+/// [`std::collections::HashMap`] when the `std` feature is enabled, and a
+/// [hashbrown](https://docs.rs/hashbrown) map otherwise.
+#[cfg(feature = "std")]
+pub use std::collections::HashMap;
+
+/// The map type used for [`Value::Map`](crate::Value::Map) and for message metadata. This is
+/// [`std::collections::HashMap`] when the `std` feature is enabled, and a
+/// [hashbrown](https://docs.rs/hashbrown) map otherwise.
+///
+/// Without `std` there is no source of randomness, so maps are hashed with fixed seeds. Anyone who
+/// controls the keys of a map, e.g. a peer sending a message with a large map, can then choose keys
+/// that all collide and make inserting them take quadratic time. When deserializing messages from
+/// untrusted peers, limit the size of incoming maps with
+/// [`MessageDecoder::with_max_value_size`](crate::message::MessageDecoder::with_max_value_size).
+#[cfg(not(feature = "std"))]
+pub type HashMap<K, V, S = ahash::RandomState> = hashbrown::HashMap<K, V, S>;
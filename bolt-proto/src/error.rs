@@ -1,13 +1,14 @@
 use thiserror::Error;
 
-use crate::{Message, Value};
+use crate::{prelude::*, Message, Value};
 
-pub type Result<T> = std::result::Result<T, Error>;
-pub type ConversionResult<T> = std::result::Result<T, ConversionError>;
-pub type SerializeResult<T> = std::result::Result<T, SerializationError>;
-pub type DeserializeResult<T> = std::result::Result<T, DeserializationError>;
+pub type Result<T> = core::result::Result<T, Error>;
+pub type ConversionResult<T> = core::result::Result<T, ConversionError>;
+pub type SerializeResult<T> = core::result::Result<T, SerializationError>;
+pub type DeserializeResult<T> = core::result::Result<T, DeserializationError>;
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
     #[error(transparent)]
     ConversionError(#[from] ConversionError),
@@ -15,27 +16,143 @@ pub enum Error {
     SerializationError(#[from] SerializationError),
     #[error(transparent)]
     DeserializationError(#[from] DeserializationError),
+    #[error(transparent)]
+    BuildError(#[from] BuildError),
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::ConversionError(error) => ErrorKind::ConversionError(error.kind()),
+            Error::SerializationError(error) => ErrorKind::SerializationError(error.kind()),
+            Error::DeserializationError(error) => ErrorKind::DeserializationError(error.kind()),
+            Error::BuildError(error) => ErrorKind::BuildError(error.kind()),
+        }
+    }
+}
+
+/// The kind of an [`Error`], without any of its contents. Unlike the errors themselves, kinds are
+/// cheap to copy and compare, e.g. to count errors by kind.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    ConversionError(ConversionErrorKind),
+    SerializationError(SerializationErrorKind),
+    DeserializationError(DeserializationErrorKind),
+    BuildError(BuildErrorKind),
 }
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum ConversionError {
     #[error("invalid conversion from value {0:?}")]
     FromValue(Value),
     #[error("invalid conversion from message {0:?}")]
     FromMessage(Message),
+    /// An integer value doesn't fit in the target integer type.
+    #[error("integer {value} is out of range for {target}")]
+    IntegerOutOfRange { value: i64, target: &'static str },
+    /// A string is not the name of a [`ServerState`](crate::ServerState).
+    #[error("invalid server state: {0}")]
+    InvalidServerState(String),
     #[error(transparent)]
-    TryFromIntError(#[from] std::num::TryFromIntError),
+    TryFromIntError(#[from] core::num::TryFromIntError),
     #[error(transparent)]
-    Infallible(#[from] std::convert::Infallible),
+    Infallible(#[from] core::convert::Infallible),
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    #[error("{0}")]
+    Serde(String),
+}
+
+impl ConversionError {
+    pub fn kind(&self) -> ConversionErrorKind {
+        match self {
+            ConversionError::FromValue(_) => ConversionErrorKind::FromValue,
+            ConversionError::FromMessage(_) => ConversionErrorKind::FromMessage,
+            ConversionError::IntegerOutOfRange { .. } => ConversionErrorKind::IntegerOutOfRange,
+            ConversionError::InvalidServerState(_) => ConversionErrorKind::InvalidServerState,
+            ConversionError::TryFromIntError(_) => ConversionErrorKind::TryFromIntError,
+            ConversionError::Infallible(_) => ConversionErrorKind::Infallible,
+            #[cfg(feature = "serde")]
+            ConversionError::Serde(_) => ConversionErrorKind::Serde,
+        }
+    }
+}
+
+/// The kind of a [`ConversionError`], without any of its contents.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ConversionErrorKind {
+    FromValue,
+    FromMessage,
+    IntegerOutOfRange,
+    InvalidServerState,
+    TryFromIntError,
+    Infallible,
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    Serde,
+}
+
+/// An invalid message was configured with one of the builders in [`message`](crate::message).
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum BuildError {
+    #[error("missing required field: {0}")]
+    MissingField(&'static str),
+    #[error("invalid {field}: {reason}")]
+    InvalidField {
+        field: &'static str,
+        reason: &'static str,
+    },
+}
+
+impl BuildError {
+    pub fn kind(&self) -> BuildErrorKind {
+        match self {
+            BuildError::MissingField(_) => BuildErrorKind::MissingField,
+            BuildError::InvalidField { .. } => BuildErrorKind::InvalidField,
+        }
+    }
+}
+
+/// The kind of a [`BuildError`], without any of its contents.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum BuildErrorKind {
+    MissingField,
+    InvalidField,
 }
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum SerializationError {
     #[error("value too large (size: {0})")]
     ValueTooLarge(usize),
+    #[error("invalid maximum chunk size: {0} (must be between 1 and {max})", max = u16::MAX)]
+    InvalidChunkSize(usize),
+}
+
+impl SerializationError {
+    pub fn kind(&self) -> SerializationErrorKind {
+        match self {
+            SerializationError::ValueTooLarge(_) => SerializationErrorKind::ValueTooLarge,
+            SerializationError::InvalidChunkSize(_) => SerializationErrorKind::InvalidChunkSize,
+        }
+    }
+}
+
+/// The kind of a [`SerializationError`], without any of its contents.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum SerializationErrorKind {
+    ValueTooLarge,
+    InvalidChunkSize,
 }
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum DeserializationError {
     #[error("panicked during deserialization")]
     Panicked,
@@ -45,12 +162,85 @@ pub enum DeserializationError {
     InvalidSignatureByte(u8),
     #[error("invalid size ({size} fields) for signature byte {signature:x}")]
     InvalidSize { size: usize, signature: u8 },
+    /// The input ended in the middle of a value, e.g. a string is shorter than its declared size.
+    #[error("unexpected end of input ({needed} bytes needed, {remaining} remaining)")]
+    UnexpectedEnd { needed: usize, remaining: usize },
+    /// A message is larger than the limit set with
+    /// [`MessageDecoder::with_max_message_size`](crate::message::MessageDecoder::with_max_message_size).
+    #[error("message too large ({size} bytes, limit is {max})")]
+    MessageTooLarge { size: usize, max: usize },
+    /// A string or byte array has more bytes, or a list or map has more entries, than the limit
+    /// set with
+    /// [`MessageDecoder::with_max_value_size`](crate::message::MessageDecoder::with_max_value_size).
+    #[error("value too large (size: {size}, limit is {max})")]
+    ValueTooLarge { size: usize, max: usize },
     #[error("string deserialization failed: {0}")]
-    InvalidUTF8(#[from] std::string::FromUtf8Error),
+    InvalidUTF8(#[from] alloc::string::FromUtf8Error),
     #[error(transparent)]
     ConversionError(#[from] ConversionError),
+    #[error("{source} (at byte {offset} of {length}: {snippet})")]
+    InvalidMessage {
+        /// The position of the byte being decoded when the error occurred.
+        offset: usize,
+        /// The total size of the message.
+        length: usize,
+        /// A hexdump of the bytes around `offset`, with the byte at `offset` in brackets.
+        snippet: String,
+        source: Box<DeserializationError>,
+    },
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
     #[error(transparent)]
     IoError(#[from] std::io::Error),
     #[error(transparent)]
-    Infallible(#[from] std::convert::Infallible),
+    Infallible(#[from] core::convert::Infallible),
+}
+
+impl DeserializationError {
+    /// Get the kind of this error. An [`InvalidMessage`](DeserializationError::InvalidMessage) error
+    /// only adds the position of the error in the message, so it has the kind of its source.
+    pub fn kind(&self) -> DeserializationErrorKind {
+        match self {
+            DeserializationError::Panicked => DeserializationErrorKind::Panicked,
+            DeserializationError::InvalidMarkerByte(_) => {
+                DeserializationErrorKind::InvalidMarkerByte
+            }
+            DeserializationError::InvalidSignatureByte(_) => {
+                DeserializationErrorKind::InvalidSignatureByte
+            }
+            DeserializationError::InvalidSize { .. } => DeserializationErrorKind::InvalidSize,
+            DeserializationError::UnexpectedEnd { .. } => DeserializationErrorKind::UnexpectedEnd,
+            DeserializationError::MessageTooLarge { .. } => {
+                DeserializationErrorKind::MessageTooLarge
+            }
+            DeserializationError::ValueTooLarge { .. } => DeserializationErrorKind::ValueTooLarge,
+            DeserializationError::InvalidUTF8(_) => DeserializationErrorKind::InvalidUTF8,
+            DeserializationError::ConversionError(error) => {
+                DeserializationErrorKind::ConversionError(error.kind())
+            }
+            DeserializationError::InvalidMessage { source, .. } => source.kind(),
+            #[cfg(feature = "std")]
+            DeserializationError::IoError(error) => DeserializationErrorKind::IoError(error.kind()),
+            DeserializationError::Infallible(_) => DeserializationErrorKind::Infallible,
+        }
+    }
+}
+
+/// The kind of a [`DeserializationError`], without any of its contents.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum DeserializationErrorKind {
+    Panicked,
+    InvalidMarkerByte,
+    InvalidSignatureByte,
+    InvalidSize,
+    UnexpectedEnd,
+    MessageTooLarge,
+    ValueTooLarge,
+    InvalidUTF8,
+    ConversionError(ConversionErrorKind),
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    IoError(std::io::ErrorKind),
+    Infallible,
 }
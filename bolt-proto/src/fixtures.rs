@@ -0,0 +1,516 @@
+//! Canonical wire encodings of [`Message`]s and [`Value`]s, for testing code that reads or writes
+//! the protocol without a server, e.g. proxies, mock servers, or other implementations.
+//!
+//! Each fixture pairs a message or value with its exact bytes, as laid out in the
+//! [Bolt](https://neo4j.com/docs/bolt/current/bolt/message/) and
+//! [PackStream](https://neo4j.com/docs/bolt/current/packstream/) specifications. Message bytes are
+//! the full chunked encoding, from the first chunk header to the `00 00` end marker, and each
+//! message fixture lists the protocol versions in which that message exists. Maps have at most one
+//! entry, since the order of entries in an encoded map is unspecified. Date-times with an offset or
+//! time zone use the encoding of protocol versions up to 4.4, with seconds since the Unix epoch in
+//! UTC, the same as [`Value`]s are serialized by this crate.
+//!
+//! ```
+//! use bolt_proto::{fixtures, message::MessageDecoder};
+//!
+//! for fixture in fixtures::messages() {
+//!     let mut decoder = MessageDecoder::new();
+//!     assert_eq!(decoder.feed(fixture.bytes()).unwrap().as_ref(), Some(fixture.message()));
+//! }
+//! ```
+
+use chrono::{FixedOffset, NaiveDate, NaiveTime, TimeZone};
+use chrono_tz::Europe::Oslo;
+
+use crate::{
+    collections::HashMap,
+    message::*,
+    value::{Duration, Node, Path, Point2D, Point3D, Relationship, UnboundRelationship},
+    version::Version,
+    Message, Value,
+};
+
+const ALL_VERSIONS: &[Version] = &[
+    Version::V1_0,
+    Version::V2_0,
+    Version::V3_0,
+    Version::V4_0,
+    Version::V4_1,
+    Version::V4_2,
+    Version::V4_3,
+    Version::V4_4,
+];
+const V1_TO_V2: &[Version] = &[Version::V1_0, Version::V2_0];
+const V1_TO_V3: &[Version] = &[Version::V1_0, Version::V2_0, Version::V3_0];
+const V3_ONWARD: &[Version] = ALL_VERSIONS.split_at(2).1;
+const V4_ONWARD: &[Version] = ALL_VERSIONS.split_at(3).1;
+
+/// A [`Message`] and its chunked encoding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageFixture {
+    name: &'static str,
+    versions: &'static [Version],
+    message: Message,
+    bytes: &'static [u8],
+}
+
+impl MessageFixture {
+    /// Get a short, unique description of the fixture.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Get the protocol versions in which the message can be sent or received.
+    pub fn versions(&self) -> &'static [Version] {
+        self.versions
+    }
+
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    /// Get the encoded message, including chunk headers and the end marker.
+    pub fn bytes(&self) -> &'static [u8] {
+        self.bytes
+    }
+}
+
+/// A [`Value`] and its PackStream encoding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueFixture {
+    name: &'static str,
+    value: Value,
+    bytes: &'static [u8],
+}
+
+impl ValueFixture {
+    /// Get a short, unique description of the fixture.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Get the encoded value, as it appears inside a message.
+    pub fn bytes(&self) -> &'static [u8] {
+        self.bytes
+    }
+}
+
+fn map<const N: usize>(entries: [(&str, Value); N]) -> HashMap<String, Value> {
+    entries
+        .into_iter()
+        .map(|(key, value)| (String::from(key), value))
+        .collect()
+}
+
+fn routing_context() -> HashMap<String, Value> {
+    map([("address", Value::from("localhost:7687"))])
+}
+
+/// Get a fixture for every message type.
+pub fn messages() -> Vec<MessageFixture> {
+    let fixture = |name, versions, message, bytes| MessageFixture {
+        name,
+        versions,
+        message,
+        bytes,
+    };
+    vec![
+        fixture(
+            "init",
+            V1_TO_V2,
+            Message::Init(Init::new(
+                String::from("bolt-rs/1.0"),
+                map([("scheme", Value::from("none"))]),
+            )),
+            &[
+                0x00, 0x1b, 0xb2, 0x01, 0x8b, 0x62, 0x6f, 0x6c, 0x74, 0x2d, 0x72, 0x73, 0x2f, 0x31,
+                0x2e, 0x30, 0xa1, 0x86, 0x73, 0x63, 0x68, 0x65, 0x6d, 0x65, 0x84, 0x6e, 0x6f, 0x6e,
+                0x65, 0x00, 0x00,
+            ],
+        ),
+        fixture(
+            "run",
+            V1_TO_V2,
+            Message::Run(Run::new(
+                String::from("RETURN $n;"),
+                map([("n", Value::from(1))]),
+            )),
+            &[
+                0x00, 0x11, 0xb2, 0x10, 0x8a, 0x52, 0x45, 0x54, 0x55, 0x52, 0x4e, 0x20, 0x24, 0x6e,
+                0x3b, 0xa1, 0x81, 0x6e, 0x01, 0x00, 0x00,
+            ],
+        ),
+        fixture(
+            "discard_all",
+            V1_TO_V3,
+            Message::DiscardAll,
+            &[0x00, 0x02, 0xb0, 0x2f, 0x00, 0x00],
+        ),
+        fixture(
+            "pull_all",
+            V1_TO_V3,
+            Message::PullAll,
+            &[0x00, 0x02, 0xb0, 0x3f, 0x00, 0x00],
+        ),
+        fixture(
+            "ack_failure",
+            V1_TO_V2,
+            Message::AckFailure,
+            &[0x00, 0x02, 0xb0, 0x0e, 0x00, 0x00],
+        ),
+        fixture(
+            "reset",
+            ALL_VERSIONS,
+            Message::Reset,
+            &[0x00, 0x02, 0xb0, 0x0f, 0x00, 0x00],
+        ),
+        fixture(
+            "record",
+            ALL_VERSIONS,
+            Message::Record(Record::new(vec![
+                Value::from(1),
+                Value::from("a"),
+                Value::Null,
+            ])),
+            &[
+                0x00, 0x07, 0xb1, 0x71, 0x93, 0x01, 0x81, 0x61, 0xc0, 0x00, 0x00,
+            ],
+        ),
+        fixture(
+            "success",
+            ALL_VERSIONS,
+            Message::Success(Success::new(map([(
+                "fields",
+                Value::from(vec![Value::from("n")]),
+            )]))),
+            &[
+                0x00, 0x0d, 0xb1, 0x70, 0xa1, 0x86, 0x66, 0x69, 0x65, 0x6c, 0x64, 0x73, 0x91, 0x81,
+                0x6e, 0x00, 0x00,
+            ],
+        ),
+        fixture(
+            "failure",
+            ALL_VERSIONS,
+            Message::Failure(Failure::new(map([(
+                "code",
+                Value::from("Neo.ClientError.Statement.SyntaxError"),
+            )]))),
+            &[
+                0x00, 0x2f, 0xb1, 0x7f, 0xa1, 0x84, 0x63, 0x6f, 0x64, 0x65, 0xd0, 0x25, 0x4e, 0x65,
+                0x6f, 0x2e, 0x43, 0x6c, 0x69, 0x65, 0x6e, 0x74, 0x45, 0x72, 0x72, 0x6f, 0x72, 0x2e,
+                0x53, 0x74, 0x61, 0x74, 0x65, 0x6d, 0x65, 0x6e, 0x74, 0x2e, 0x53, 0x79, 0x6e, 0x74,
+                0x61, 0x78, 0x45, 0x72, 0x72, 0x6f, 0x72, 0x00, 0x00,
+            ],
+        ),
+        fixture(
+            "ignored",
+            ALL_VERSIONS,
+            Message::Ignored,
+            &[0x00, 0x02, 0xb0, 0x7e, 0x00, 0x00],
+        ),
+        fixture(
+            "hello",
+            V3_ONWARD,
+            Message::Hello(Hello::new(map([(
+                "user_agent",
+                Value::from("bolt-rs/1.0"),
+            )]))),
+            &[
+                0x00, 0x1a, 0xb1, 0x01, 0xa1, 0x8a, 0x75, 0x73, 0x65, 0x72, 0x5f, 0x61, 0x67, 0x65,
+                0x6e, 0x74, 0x8b, 0x62, 0x6f, 0x6c, 0x74, 0x2d, 0x72, 0x73, 0x2f, 0x31, 0x2e, 0x30,
+                0x00, 0x00,
+            ],
+        ),
+        fixture(
+            "goodbye",
+            V3_ONWARD,
+            Message::Goodbye,
+            &[0x00, 0x02, 0xb0, 0x02, 0x00, 0x00],
+        ),
+        fixture(
+            "run_with_metadata",
+            V3_ONWARD,
+            Message::RunWithMetadata(RunWithMetadata::new(
+                String::from("RETURN 1;"),
+                HashMap::new(),
+                map([("mode", Value::from("r"))]),
+            )),
+            &[
+                0x00, 0x15, 0xb3, 0x10, 0x89, 0x52, 0x45, 0x54, 0x55, 0x52, 0x4e, 0x20, 0x31, 0x3b,
+                0xa0, 0xa1, 0x84, 0x6d, 0x6f, 0x64, 0x65, 0x81, 0x72, 0x00, 0x00,
+            ],
+        ),
+        fixture(
+            "begin",
+            V3_ONWARD,
+            Message::Begin(Begin::new(map([("mode", Value::from("r"))]))),
+            &[
+                0x00, 0x0a, 0xb1, 0x11, 0xa1, 0x84, 0x6d, 0x6f, 0x64, 0x65, 0x81, 0x72, 0x00, 0x00,
+            ],
+        ),
+        fixture(
+            "commit",
+            V3_ONWARD,
+            Message::Commit,
+            &[0x00, 0x02, 0xb0, 0x12, 0x00, 0x00],
+        ),
+        fixture(
+            "rollback",
+            V3_ONWARD,
+            Message::Rollback,
+            &[0x00, 0x02, 0xb0, 0x13, 0x00, 0x00],
+        ),
+        fixture(
+            "discard",
+            V4_ONWARD,
+            Message::Discard(Discard::new(map([("n", Value::from(-1))]))),
+            &[0x00, 0x06, 0xb1, 0x2f, 0xa1, 0x81, 0x6e, 0xff, 0x00, 0x00],
+        ),
+        fixture(
+            "pull",
+            V4_ONWARD,
+            Message::Pull(Pull::new(map([("n", Value::from(1000))]))),
+            &[
+                0x00, 0x08, 0xb1, 0x3f, 0xa1, 0x81, 0x6e, 0xc9, 0x03, 0xe8, 0x00, 0x00,
+            ],
+        ),
+        fixture(
+            "route",
+            &[Version::V4_3],
+            Message::Route(Route::new(
+                routing_context(),
+                vec![String::from("bm:1")],
+                Some(String::from("neo4j")),
+            )),
+            &[
+                0x00, 0x26, 0xb3, 0x66, 0xa1, 0x87, 0x61, 0x64, 0x64, 0x72, 0x65, 0x73, 0x73, 0x8e,
+                0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x68, 0x6f, 0x73, 0x74, 0x3a, 0x37, 0x36, 0x38, 0x37,
+                0x91, 0x84, 0x62, 0x6d, 0x3a, 0x31, 0x85, 0x6e, 0x65, 0x6f, 0x34, 0x6a, 0x00, 0x00,
+            ],
+        ),
+        fixture(
+            "route_with_metadata",
+            &[Version::V4_4],
+            Message::RouteWithMetadata(RouteWithMetadata::new(
+                routing_context(),
+                vec![],
+                map([("db", Value::from("neo4j"))]),
+            )),
+            &[
+                0x00, 0x25, 0xb3, 0x66, 0xa1, 0x87, 0x61, 0x64, 0x64, 0x72, 0x65, 0x73, 0x73, 0x8e,
+                0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x68, 0x6f, 0x73, 0x74, 0x3a, 0x37, 0x36, 0x38, 0x37,
+                0x90, 0xa1, 0x82, 0x64, 0x62, 0x85, 0x6e, 0x65, 0x6f, 0x34, 0x6a, 0x00, 0x00,
+            ],
+        ),
+    ]
+}
+
+/// Get a fixture for every value type, and for each size of integer, string, list, and map
+/// marker.
+pub fn values() -> Vec<ValueFixture> {
+    let fixture = |name, value, bytes| ValueFixture { name, value, bytes };
+    let offset = FixedOffset::east_opt(3600).unwrap();
+    let date = NaiveDate::from_ymd_opt(2021, 3, 14).unwrap();
+    let time = NaiveTime::from_hms_nano_opt(12, 30, 15, 500).unwrap();
+    let node = |id, label: &str| {
+        Node::new(
+            id,
+            vec![String::from(label)],
+            map([("name", Value::from("Alice"))]),
+        )
+    };
+    vec![
+        fixture("null", Value::Null, &[0xc0]),
+        fixture("true", Value::Boolean(true), &[0xc3]),
+        fixture("false", Value::Boolean(false), &[0xc2]),
+        fixture("tiny_int", Value::Integer(42), &[0x2a]),
+        fixture("tiny_int_negative", Value::Integer(-16), &[0xf0]),
+        fixture("int_8", Value::Integer(-17), &[0xc8, 0xef]),
+        fixture("int_16", Value::Integer(1000), &[0xc9, 0x03, 0xe8]),
+        fixture(
+            "int_32",
+            Value::Integer(100_000),
+            &[0xca, 0x00, 0x01, 0x86, 0xa0],
+        ),
+        fixture(
+            "int_64",
+            Value::Integer(5_000_000_000),
+            &[0xcb, 0x00, 0x00, 0x00, 0x01, 0x2a, 0x05, 0xf2, 0x00],
+        ),
+        fixture(
+            "float",
+            Value::Float(1.5),
+            &[0xc1, 0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        ),
+        fixture(
+            "tiny_string",
+            Value::from("hello"),
+            &[0x85, 0x68, 0x65, 0x6c, 0x6c, 0x6f],
+        ),
+        fixture(
+            "string_8",
+            Value::from("The quick brown fox jumps over the lazy dog"),
+            &[
+                0xd0, 0x2b, 0x54, 0x68, 0x65, 0x20, 0x71, 0x75, 0x69, 0x63, 0x6b, 0x20, 0x62, 0x72,
+                0x6f, 0x77, 0x6e, 0x20, 0x66, 0x6f, 0x78, 0x20, 0x6a, 0x75, 0x6d, 0x70, 0x73, 0x20,
+                0x6f, 0x76, 0x65, 0x72, 0x20, 0x74, 0x68, 0x65, 0x20, 0x6c, 0x61, 0x7a, 0x79, 0x20,
+                0x64, 0x6f, 0x67,
+            ],
+        ),
+        fixture(
+            "bytes",
+            Value::Bytes(vec![1, 2, 3]),
+            &[0xcc, 0x03, 0x01, 0x02, 0x03],
+        ),
+        fixture(
+            "list",
+            Value::List(vec![Value::from(1), Value::from("a"), Value::Null]),
+            &[0x93, 0x01, 0x81, 0x61, 0xc0],
+        ),
+        fixture(
+            "map",
+            Value::Map(map([("a", Value::from(1))])),
+            &[0xa1, 0x81, 0x61, 0x01],
+        ),
+        fixture(
+            "node",
+            Value::Node(node(1, "Person")),
+            &[
+                0xb3, 0x4e, 0x01, 0x91, 0x86, 0x50, 0x65, 0x72, 0x73, 0x6f, 0x6e, 0xa1, 0x84, 0x6e,
+                0x61, 0x6d, 0x65, 0x85, 0x41, 0x6c, 0x69, 0x63, 0x65,
+            ],
+        ),
+        fixture(
+            "relationship",
+            Value::Relationship(Relationship::new(
+                7,
+                1,
+                2,
+                String::from("KNOWS"),
+                HashMap::<String, Value>::new(),
+            )),
+            &[
+                0xb5, 0x52, 0x07, 0x01, 0x02, 0x85, 0x4b, 0x4e, 0x4f, 0x57, 0x53, 0xa0,
+            ],
+        ),
+        fixture(
+            "path",
+            Value::Path(Path::new(
+                vec![node(1, "Person"), node(2, "Person")],
+                vec![UnboundRelationship::new(
+                    7,
+                    String::from("KNOWS"),
+                    HashMap::<String, Value>::new(),
+                )],
+                vec![1, 1],
+            )),
+            &[
+                0xb3, 0x50, 0x92, 0xb3, 0x4e, 0x01, 0x91, 0x86, 0x50, 0x65, 0x72, 0x73, 0x6f, 0x6e,
+                0xa1, 0x84, 0x6e, 0x61, 0x6d, 0x65, 0x85, 0x41, 0x6c, 0x69, 0x63, 0x65, 0xb3, 0x4e,
+                0x02, 0x91, 0x86, 0x50, 0x65, 0x72, 0x73, 0x6f, 0x6e, 0xa1, 0x84, 0x6e, 0x61, 0x6d,
+                0x65, 0x85, 0x41, 0x6c, 0x69, 0x63, 0x65, 0x91, 0xb3, 0x72, 0x07, 0x85, 0x4b, 0x4e,
+                0x4f, 0x57, 0x53, 0xa0, 0x92, 0x01, 0x01,
+            ],
+        ),
+        fixture("date", Value::Date(date), &[0xb1, 0x44, 0xc9, 0x49, 0x0c]),
+        fixture(
+            "time",
+            Value::Time(time, offset),
+            &[
+                0xb2, 0x54, 0xcb, 0x00, 0x00, 0x28, 0xf0, 0xdf, 0x15, 0xa7, 0xf4, 0xc9, 0x0e, 0x10,
+            ],
+        ),
+        fixture(
+            "local_time",
+            Value::LocalTime(time),
+            &[
+                0xb1, 0x74, 0xcb, 0x00, 0x00, 0x28, 0xf0, 0xdf, 0x15, 0xa7, 0xf4,
+            ],
+        ),
+        fixture(
+            "date_time_offset",
+            Value::DateTimeOffset(offset.from_local_datetime(&date.and_time(time)).unwrap()),
+            &[
+                0xb3, 0x46, 0xca, 0x60, 0x4d, 0xf3, 0xc7, 0xc9, 0x01, 0xf4, 0xc9, 0x0e, 0x10,
+            ],
+        ),
+        fixture(
+            "date_time_zoned",
+            Value::DateTimeZoned(Oslo.from_local_datetime(&date.and_time(time)).unwrap()),
+            &[
+                0xb3, 0x66, 0xca, 0x60, 0x4d, 0xf3, 0xc7, 0xc9, 0x01, 0xf4, 0x8b, 0x45, 0x75, 0x72,
+                0x6f, 0x70, 0x65, 0x2f, 0x4f, 0x73, 0x6c, 0x6f,
+            ],
+        ),
+        fixture(
+            "local_date_time",
+            Value::LocalDateTime(date.and_time(time)),
+            &[0xb2, 0x64, 0xca, 0x60, 0x4e, 0x01, 0xd7, 0xc9, 0x01, 0xf4],
+        ),
+        fixture(
+            "duration",
+            Value::Duration(Duration::new(14, 3, 3600, 500)),
+            &[0xb4, 0x45, 0x0e, 0x03, 0xc9, 0x0e, 0x10, 0xc9, 0x01, 0xf4],
+        ),
+        fixture(
+            "point_2d",
+            Value::Point2D(Point2D::new(7203, 1.0, 2.5)),
+            &[
+                0xb3, 0x58, 0xc9, 0x1c, 0x23, 0xc1, 0x3f, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0xc1, 0x40, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ],
+        ),
+        fixture(
+            "point_3d",
+            Value::Point3D(Point3D::new(9157, 1.0, 2.5, -3.0)),
+            &[
+                0xb4, 0x59, 0xc9, 0x23, 0xc5, 0xc1, 0x3f, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0xc1, 0x40, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc1, 0xc0, 0x08, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00,
+            ],
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Buf, Bytes};
+
+    use super::*;
+    use crate::serialization::BoltValue;
+
+    #[test]
+    fn messages_round_trip() {
+        for fixture in messages() {
+            let chunks = fixture.message().clone().into_chunks().unwrap();
+            assert_eq!(chunks.concat(), fixture.bytes(), "{}", fixture.name());
+
+            let mut decoder = MessageDecoder::new();
+            let message = decoder.feed(fixture.bytes()).unwrap();
+            assert_eq!(
+                message.as_ref(),
+                Some(fixture.message()),
+                "{}",
+                fixture.name()
+            );
+            assert!(decoder.is_empty(), "{}", fixture.name());
+        }
+    }
+
+    #[test]
+    fn values_round_trip() {
+        for fixture in values() {
+            let bytes = fixture.value().clone().serialize().unwrap();
+            assert_eq!(bytes, fixture.bytes(), "{}", fixture.name());
+
+            let (value, remaining) =
+                Value::deserialize(Bytes::from_static(fixture.bytes())).unwrap();
+            assert_eq!(&value, fixture.value(), "{}", fixture.name());
+            assert!(!remaining.has_remaining(), "{}", fixture.name());
+        }
+    }
+}
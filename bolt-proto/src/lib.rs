@@ -1,15 +1,51 @@
 #![warn(rust_2018_idioms)]
+#![allow(clippy::result_large_err)]
+#![cfg_attr(docsrs, feature(doc_cfg))]
+#![cfg_attr(not(feature = "std"), no_std)]
 
 //! This crate contains the primitives used in the [Bolt](https://neo4j.com/docs/bolt/current)
 //! protocol. The [`Message`] and [`Value`] enums are of particular importance, and are the primary
 //! units of information sent and consumed by Bolt clients/servers.
+//!
+//! # Features
+//! - `serde` - enables deserializing user types from [`Value`]s with
+//!   [`value::from_value`], and from [`Record`](message::Record)s with
+//!   [`Record::deserialize_with_fields`](message::Record::deserialize_with_fields), as well as
+//!   serializing and deserializing [`Value`]s and [`Record`](message::Record)s themselves, e.g.
+//!   to send them to a frontend as JSON
+//! - `std` (enabled by default) - depends on the standard library, and enables reading messages
+//!   from streams with [`Message::from_stream`] and registering custom structure deserializers
+//!   with [`value::register_structure`]
+//! - `test-utils` - enables the [`arbitrary`] module, with [proptest](https://docs.rs/proptest)
+//!   strategies for generating arbitrary [`Value`]s and [`Message`]s, and the [`fixtures`] module,
+//!   with the encodings of one of each kind of [`Value`] and [`Message`]
+//! - `alloc` - lets the crate be used without the standard library by disabling the `std`
+//!   feature, using only `alloc` and `bytes`. Maps are then [hashbrown](https://docs.rs/hashbrown)
+//!   maps with fixed hash seeds (see [`collections::HashMap`] for what this means for untrusted
+//!   input). Truncated or malformed input is reported with a
+//!   [`DeserializationError`](error::DeserializationError) rather than a panic, so messages from
+//!   untrusted peers can be deserialized without the standard library.
+
+#[cfg(not(any(feature = "std", feature = "alloc")))]
+compile_error!("bolt-proto requires either the `std` or the `alloc` feature");
+
+extern crate alloc;
 
 pub use message::Message;
 pub use server_state::ServerState;
 pub use value::Value;
 
+#[cfg(any(test, feature = "test-utils"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-utils")))]
+pub mod arbitrary;
+pub mod collections;
 pub mod error;
+#[cfg(any(test, feature = "test-utils"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-utils")))]
+pub mod fixtures;
 pub mod message;
+mod prelude;
+pub mod schema;
 mod serialization;
 mod server_state;
 pub mod value;
@@ -21,14 +57,17 @@ macro_rules! impl_message_with_metadata {
     ($T:path) => {
         impl $T {
             pub fn new(
-                metadata: ::std::collections::HashMap<::std::string::String, $crate::value::Value>,
+                metadata: $crate::collections::HashMap<
+                    ::alloc::string::String,
+                    $crate::value::Value,
+                >,
             ) -> Self {
                 Self { metadata }
             }
 
             pub fn metadata(
                 &self,
-            ) -> &::std::collections::HashMap<::std::string::String, $crate::value::Value> {
+            ) -> &$crate::collections::HashMap<::alloc::string::String, $crate::value::Value> {
                 &self.metadata
             }
         }
@@ -39,7 +78,7 @@ macro_rules! impl_message_with_metadata {
 #[macro_export]
 macro_rules! impl_try_from_message {
     ($T:path, $V:ident) => {
-        impl ::std::convert::TryFrom<$crate::Message> for $T {
+        impl ::core::convert::TryFrom<$crate::Message> for $T {
             type Error = $crate::error::ConversionError;
 
             fn try_from(message: $crate::Message) -> $crate::error::ConversionResult<Self> {
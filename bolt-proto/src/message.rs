@@ -1,16 +1,21 @@
-use std::{
-    mem,
-    panic::{catch_unwind, UnwindSafe},
-};
+use alloc::collections::VecDeque;
+use core::{mem, panic::UnwindSafe};
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
+#[cfg(feature = "std")]
 use futures_util::io::{AsyncRead, AsyncReadExt};
 
+#[cfg(feature = "std")]
+use crate::value::Utf8Mode;
+
 pub use begin::Begin;
+pub use builder::{BeginBuilder, HelloBuilder, RunBuilder, StreamBuilder};
+pub use decoder::{LazyMessage, MessageDecoder};
 pub use discard::Discard;
 pub use failure::Failure;
 pub use hello::Hello;
 pub use init::Init;
+pub use lazy_record::LazyRecord;
 pub use pull::Pull;
 pub use record::Record;
 pub use route::Route;
@@ -19,13 +24,16 @@ pub use run::Run;
 pub use run_with_metadata::RunWithMetadata;
 pub use success::Success;
 
-use crate::{error::*, serialization::*, value::MARKER_TINY_STRUCT};
+use crate::{error::*, prelude::*, serialization::*, value::MARKER_TINY_STRUCT};
 
 pub(crate) mod begin;
+pub(crate) mod builder;
+pub(crate) mod decoder;
 pub(crate) mod discard;
 pub(crate) mod failure;
 pub(crate) mod hello;
 pub(crate) mod init;
+pub(crate) mod lazy_record;
 pub(crate) mod pull;
 pub(crate) mod record;
 pub(crate) mod route;
@@ -34,28 +42,35 @@ pub(crate) mod run;
 pub(crate) mod run_with_metadata;
 pub(crate) mod success;
 
-pub(crate) const SIGNATURE_INIT: u8 = 0x01;
-pub(crate) const SIGNATURE_RUN: u8 = 0x10;
-pub(crate) const SIGNATURE_DISCARD_ALL: u8 = 0x2F;
-pub(crate) const SIGNATURE_PULL_ALL: u8 = 0x3F;
-pub(crate) const SIGNATURE_ACK_FAILURE: u8 = 0x0E;
-pub(crate) const SIGNATURE_RESET: u8 = 0x0F;
-pub(crate) const SIGNATURE_RECORD: u8 = 0x71;
-pub(crate) const SIGNATURE_SUCCESS: u8 = 0x70;
-pub(crate) const SIGNATURE_FAILURE: u8 = 0x7F;
-pub(crate) const SIGNATURE_IGNORED: u8 = 0x7E;
-pub(crate) const SIGNATURE_HELLO: u8 = 0x01;
-pub(crate) const SIGNATURE_GOODBYE: u8 = 0x02;
-pub(crate) const SIGNATURE_RUN_WITH_METADATA: u8 = 0x10;
-pub(crate) const SIGNATURE_BEGIN: u8 = 0x11;
-pub(crate) const SIGNATURE_COMMIT: u8 = 0x12;
-pub(crate) const SIGNATURE_ROLLBACK: u8 = 0x13;
-pub(crate) const SIGNATURE_DISCARD: u8 = 0x2F;
-pub(crate) const SIGNATURE_PULL: u8 = 0x3F;
-pub(crate) const SIGNATURE_ROUTE: u8 = 0x66;
-
-// This is the default maximum chunk size in the official driver, minus header length
-const CHUNK_SIZE: usize = 16383 - mem::size_of::<u16>();
+// Signature bytes of each message structure. Some are shared between versions of a message, which
+// are then told apart by their number of fields.
+pub const SIGNATURE_INIT: u8 = 0x01;
+pub const SIGNATURE_RUN: u8 = 0x10;
+pub const SIGNATURE_DISCARD_ALL: u8 = 0x2F;
+pub const SIGNATURE_PULL_ALL: u8 = 0x3F;
+pub const SIGNATURE_ACK_FAILURE: u8 = 0x0E;
+pub const SIGNATURE_RESET: u8 = 0x0F;
+pub const SIGNATURE_RECORD: u8 = 0x71;
+pub const SIGNATURE_SUCCESS: u8 = 0x70;
+pub const SIGNATURE_FAILURE: u8 = 0x7F;
+pub const SIGNATURE_IGNORED: u8 = 0x7E;
+pub const SIGNATURE_HELLO: u8 = 0x01;
+pub const SIGNATURE_GOODBYE: u8 = 0x02;
+pub const SIGNATURE_RUN_WITH_METADATA: u8 = 0x10;
+pub const SIGNATURE_BEGIN: u8 = 0x11;
+pub const SIGNATURE_COMMIT: u8 = 0x12;
+pub const SIGNATURE_ROLLBACK: u8 = 0x13;
+pub const SIGNATURE_DISCARD: u8 = 0x2F;
+pub const SIGNATURE_PULL: u8 = 0x3F;
+pub const SIGNATURE_ROUTE: u8 = 0x66;
+
+/// The maximum payload size of the chunks created by [`Message::into_chunks`]. This is the
+/// default maximum chunk size in the official driver, minus the 2-byte chunk header.
+pub const DEFAULT_MAX_CHUNK_SIZE: usize = 16383 - mem::size_of::<u16>();
+
+/// The largest chunk payload allowed by the protocol, since its size must fit in the 2-byte chunk
+/// header.
+pub const MAX_CHUNK_SIZE: usize = u16::MAX as usize;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Message {
@@ -90,9 +105,112 @@ pub enum Message {
     RouteWithMetadata(RouteWithMetadata),
 }
 
+/// The kind of a [`Message`], without any of its contents.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum MessageKind {
+    Init,
+    Run,
+    DiscardAll,
+    PullAll,
+    AckFailure,
+    Reset,
+    Record,
+    Success,
+    Failure,
+    Ignored,
+    Hello,
+    Goodbye,
+    RunWithMetadata,
+    Begin,
+    Commit,
+    Rollback,
+    Discard,
+    Pull,
+    Route,
+    RouteWithMetadata,
+}
+
 impl Message {
-    pub async fn from_stream(mut stream: impl AsyncRead + Unpin) -> DeserializeResult<Message> {
-        let mut bytes = BytesMut::new();
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            Message::Init(_) => MessageKind::Init,
+            Message::Run(_) => MessageKind::Run,
+            Message::DiscardAll => MessageKind::DiscardAll,
+            Message::PullAll => MessageKind::PullAll,
+            Message::AckFailure => MessageKind::AckFailure,
+            Message::Reset => MessageKind::Reset,
+            Message::Record(_) => MessageKind::Record,
+            Message::Success(_) => MessageKind::Success,
+            Message::Failure(_) => MessageKind::Failure,
+            Message::Ignored => MessageKind::Ignored,
+            Message::Hello(_) => MessageKind::Hello,
+            Message::Goodbye => MessageKind::Goodbye,
+            Message::RunWithMetadata(_) => MessageKind::RunWithMetadata,
+            Message::Begin(_) => MessageKind::Begin,
+            Message::Commit => MessageKind::Commit,
+            Message::Rollback => MessageKind::Rollback,
+            Message::Discard(_) => MessageKind::Discard,
+            Message::Pull(_) => MessageKind::Pull,
+            Message::Route(_) => MessageKind::Route,
+            Message::RouteWithMetadata(_) => MessageKind::RouteWithMetadata,
+        }
+    }
+
+    /// Get the signature byte of this message's structure.
+    pub fn signature(&self) -> u8 {
+        BoltStructure::signature(self)
+    }
+
+    /// Get the marker byte of this message's structure, which encodes its number of fields.
+    pub fn marker(&self) -> SerializeResult<u8> {
+        BoltValue::marker(self)
+    }
+
+    /// Get the name of this message, as used in the protocol documentation (e.g. `"PULL_ALL"`).
+    /// Versions of a message that differ only in their fields share the same name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Message::Init(_) => "INIT",
+            Message::Run(_) | Message::RunWithMetadata(_) => "RUN",
+            Message::DiscardAll => "DISCARD_ALL",
+            Message::PullAll => "PULL_ALL",
+            Message::AckFailure => "ACK_FAILURE",
+            Message::Reset => "RESET",
+            Message::Record(_) => "RECORD",
+            Message::Success(_) => "SUCCESS",
+            Message::Failure(_) => "FAILURE",
+            Message::Ignored => "IGNORED",
+            Message::Hello(_) => "HELLO",
+            Message::Goodbye => "GOODBYE",
+            Message::Begin(_) => "BEGIN",
+            Message::Commit => "COMMIT",
+            Message::Rollback => "ROLLBACK",
+            Message::Discard(_) => "DISCARD",
+            Message::Pull(_) => "PULL",
+            Message::Route(_) | Message::RouteWithMetadata(_) => "ROUTE",
+        }
+    }
+
+    /// Read a chunked message from a stream, skipping any no-op chunks before it.
+    ///
+    /// Each chunk is kept in its own buffer and deserialized in place, so the message is never
+    /// copied into one contiguous buffer. Peak memory use while reading a large message is about
+    /// the size of the message plus the deserialized values.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub async fn from_stream(stream: impl AsyncRead + Unpin) -> DeserializeResult<Message> {
+        Message::from_stream_with(stream, Utf8Mode::Strict).await
+    }
+
+    /// Read a message from a stream like [`Message::from_stream`], deserializing any strings that
+    /// are not valid UTF-8 as specified by `utf8_mode`.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub async fn from_stream_with(
+        mut stream: impl AsyncRead + Unpin,
+        utf8_mode: Utf8Mode,
+    ) -> DeserializeResult<Message> {
+        let mut chunks = VecDeque::new();
         let mut chunk_len = 0;
         // Ignore any no-op messages
         while chunk_len == 0 {
@@ -102,28 +220,85 @@ impl Message {
         }
         // Messages end in a 0_u16
         while chunk_len > 0 {
-            let mut buf = vec![0; chunk_len as usize];
-            stream.read_exact(&mut buf).await?;
-            bytes.put_slice(&buf);
+            let mut chunk = vec![0; chunk_len as usize];
+            stream.read_exact(&mut chunk).await?;
+            chunks.push_back(Bytes::from(chunk));
             let mut u16_bytes = [0, 0];
             stream.read_exact(&mut u16_bytes).await?;
             chunk_len = u16::from_be_bytes(u16_bytes);
         }
-        let (message, remaining) = Message::deserialize(bytes)?;
-        debug_assert_eq!(remaining.len(), 0);
+        let config = DecodeConfig {
+            utf8_mode,
+            ..Default::default()
+        };
+        Message::deserialize_complete(chunks, &config)
+    }
 
-        Ok(message)
+    /// Deserialize a complete message from the data of its chunks, adding the position of any
+    /// failure to the error.
+    pub(crate) fn deserialize_complete(
+        chunks: VecDeque<Bytes>,
+        config: &DecodeConfig,
+    ) -> DeserializeResult<Message> {
+        let length = chunks.iter().map(Bytes::len).sum();
+        let mut message_bytes = MessageBytes {
+            chunks: chunks.clone(),
+            remaining: length,
+        };
+
+        match Message::deserialize_with(TrackedBytes(&mut message_bytes), config) {
+            Ok((message, remaining)) => {
+                debug_assert_eq!(remaining.remaining(), 0);
+                Ok(message)
+            }
+            Err(error) => {
+                let bytes = chunks.iter().fold(BytesMut::new(), |mut bytes, chunk| {
+                    bytes.put_slice(chunk);
+                    bytes
+                });
+                let consumed = length - message_bytes.remaining;
+                // These are returned after the invalid byte has been read
+                let offset = match error {
+                    DeserializationError::InvalidMarkerByte(_)
+                    | DeserializationError::InvalidSignatureByte(_) => consumed.saturating_sub(1),
+                    _ => consumed,
+                };
+
+                Err(DeserializationError::InvalidMessage {
+                    offset,
+                    length: bytes.len(),
+                    snippet: hex_snippet(&bytes, offset),
+                    source: Box::new(error),
+                })
+            }
+        }
     }
 
+    /// Serialize the message and split it into chunks for sending, each with a payload of at most
+    /// [`DEFAULT_MAX_CHUNK_SIZE`] bytes. The last chunk is the empty `0x00 0x00` chunk that ends
+    /// every message.
     pub fn into_chunks(self) -> SerializeResult<Vec<Bytes>> {
+        self.into_chunks_with_size(DEFAULT_MAX_CHUNK_SIZE)
+    }
+
+    /// Serialize the message and split it into chunks like [`Message::into_chunks`], each with a
+    /// payload of at most `max_size` bytes. Every chunk but the last two is exactly `max_size`
+    /// bytes long.
+    ///
+    /// Returns [`SerializationError::InvalidChunkSize`] if `max_size` is 0 or larger than
+    /// [`MAX_CHUNK_SIZE`].
+    pub fn into_chunks_with_size(self, max_size: usize) -> SerializeResult<Vec<Bytes>> {
+        if !(1..=MAX_CHUNK_SIZE).contains(&max_size) {
+            return Err(SerializationError::InvalidChunkSize(max_size));
+        }
         let bytes = self.serialize()?;
 
         // Big enough to hold all the chunks, plus a partial chunk, plus the message footer
-        let mut result: Vec<Bytes> = Vec::with_capacity(bytes.len() / CHUNK_SIZE + 2);
-        for slice in bytes.chunks(CHUNK_SIZE) {
+        let mut result: Vec<Bytes> = Vec::with_capacity(bytes.len() / max_size + 2);
+        for slice in bytes.chunks(max_size) {
             // 16-bit size, then the chunk data
             let mut chunk = BytesMut::with_capacity(mem::size_of::<u16>() + slice.len());
-            // Length of slice is at most CHUNK_SIZE, which can fit in a u16
+            // Length of slice is at most MAX_CHUNK_SIZE, which can fit in a u16
             chunk.put_u16(slice.len() as u16);
             chunk.put(slice);
             result.push(chunk.freeze());
@@ -135,9 +310,116 @@ impl Message {
     }
 }
 
+// The data of a message's chunks, read in place.
+struct MessageBytes {
+    chunks: VecDeque<Bytes>,
+    remaining: usize,
+}
+
+// Reads the data of a message through a reference, so how much was read is still known after the
+// deserializer fails or panics. A panic can only leave the position short of the read that failed,
+// and the position is only used to report where deserialization stopped.
+struct TrackedBytes<'a>(&'a mut MessageBytes);
+
+impl UnwindSafe for TrackedBytes<'_> {}
+
+impl Buf for TrackedBytes<'_> {
+    fn remaining(&self) -> usize {
+        self.0.remaining
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self.0.chunk()
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        self.0.advance(cnt)
+    }
+
+    fn copy_to_bytes(&mut self, len: usize) -> Bytes {
+        self.0.copy_to_bytes(len)
+    }
+}
+
+impl Buf for MessageBytes {
+    fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self.chunks.front().map_or(&[], |chunk| chunk)
+    }
+
+    fn advance(&mut self, mut cnt: usize) {
+        assert!(
+            cnt <= self.remaining,
+            "cannot advance past the end of the message"
+        );
+        self.remaining -= cnt;
+        while cnt > 0 {
+            let chunk = self.chunks.front_mut().unwrap();
+            if cnt < chunk.len() {
+                chunk.advance(cnt);
+                return;
+            }
+            cnt -= chunk.len();
+            self.chunks.pop_front();
+        }
+    }
+
+    fn copy_to_bytes(&mut self, len: usize) -> Bytes {
+        match self.chunks.front_mut() {
+            Some(chunk) if len <= chunk.len() => {
+                let bytes = chunk.split_to(len);
+                if chunk.is_empty() {
+                    self.chunks.pop_front();
+                }
+                self.remaining -= len;
+                bytes
+            }
+            // Values that span several chunks have to be copied
+            _ => {
+                assert!(
+                    len <= self.remaining,
+                    "cannot read past the end of the message"
+                );
+                let mut bytes = BytesMut::with_capacity(len);
+                bytes.put(Buf::take(&mut *self, len));
+                bytes.freeze()
+            }
+        }
+    }
+}
+
+const SNIPPET_CONTEXT: usize = 8;
+
+// Format the bytes surrounding an offset as hex, e.g. "... b1 71 [91] 81 61".
+fn hex_snippet(bytes: &[u8], offset: usize) -> String {
+    let start = offset.saturating_sub(SNIPPET_CONTEXT);
+    let end = bytes.len().min(offset + SNIPPET_CONTEXT + 1);
+
+    let mut parts = Vec::with_capacity(end.saturating_sub(start) + 3);
+    if start > 0 {
+        parts.push(String::from("..."));
+    }
+    for (index, byte) in bytes.iter().enumerate().take(end).skip(start) {
+        if index == offset {
+            parts.push(format!("[{:02x}]", byte));
+        } else {
+            parts.push(format!("{:02x}", byte));
+        }
+    }
+    if offset >= bytes.len() {
+        parts.push(String::from("[end]"));
+    } else if end < bytes.len() {
+        parts.push(String::from("..."));
+    }
+    parts.join(" ")
+}
+
 macro_rules! deserialize_struct {
-    ($name:ident, $bytes:ident) => {{
-        let (message, remaining) = $name::deserialize($bytes)?;
+    ($name:ident, $bytes:ident, $config:ident) => {{
+        let (message, remaining) = $name::deserialize_with($bytes, $config)?;
         $bytes = remaining;
         Ok((Message::$name(message), $bytes))
     }};
@@ -180,9 +462,12 @@ impl BoltValue for Message {
         }
     }
 
-    fn deserialize<B: Buf + UnwindSafe>(mut bytes: B) -> DeserializeResult<(Self, B)> {
-        catch_unwind(move || {
-            let marker = bytes.get_u8();
+    fn deserialize_with<B: Buf + UnwindSafe>(
+        mut bytes: B,
+        config: &DecodeConfig,
+    ) -> DeserializeResult<(Self, B)> {
+        catch_panic(move || {
+            let marker = bytes.read_u8()?;
             let (size, signature) = get_structure_info(marker, &mut bytes)?;
 
             match signature {
@@ -190,8 +475,8 @@ impl BoltValue for Message {
                     // Conflicting signatures, so we have to check for metadata.
                     // HELLO has 1 field, while INIT has 2.
                     match size {
-                        1 => deserialize_struct!(Hello, bytes),
-                        2 => deserialize_struct!(Init, bytes),
+                        1 => deserialize_struct!(Hello, bytes, config),
+                        2 => deserialize_struct!(Init, bytes, config),
                         _ => Err(DeserializationError::InvalidSize { size, signature }),
                     }
                 }
@@ -199,8 +484,8 @@ impl BoltValue for Message {
                     // Conflicting signatures, so we have to check for metadata.
                     // RUN has 2 fields, while RUN_WITH_METADATA has 3.
                     match size {
-                        2 => deserialize_struct!(Run, bytes),
-                        3 => deserialize_struct!(RunWithMetadata, bytes),
+                        2 => deserialize_struct!(Run, bytes, config),
+                        3 => deserialize_struct!(RunWithMetadata, bytes, config),
                         _ => Err(DeserializationError::InvalidSize { size, signature }),
                     }
                 }
@@ -209,7 +494,7 @@ impl BoltValue for Message {
                     // DISCARD_ALL has 0 fields, while DISCARD has 1.
                     match size {
                         0 => Ok((Message::DiscardAll, bytes)),
-                        1 => deserialize_struct!(Discard, bytes),
+                        1 => deserialize_struct!(Discard, bytes, config),
                         _ => Err(DeserializationError::InvalidSize { size, signature }),
                     }
                 }
@@ -218,38 +503,35 @@ impl BoltValue for Message {
                     // PULL_ALL has 0 fields, while PULL has 1.
                     match size {
                         0 => Ok((Message::PullAll, bytes)),
-                        1 => deserialize_struct!(Pull, bytes),
+                        1 => deserialize_struct!(Pull, bytes, config),
                         _ => Err(DeserializationError::InvalidSize { size, signature }),
                     }
                 }
                 SIGNATURE_ACK_FAILURE => Ok((Message::AckFailure, bytes)),
                 SIGNATURE_RESET => Ok((Message::Reset, bytes)),
-                SIGNATURE_RECORD => deserialize_struct!(Record, bytes),
-                SIGNATURE_SUCCESS => deserialize_struct!(Success, bytes),
-                SIGNATURE_FAILURE => deserialize_struct!(Failure, bytes),
+                SIGNATURE_RECORD => deserialize_struct!(Record, bytes, config),
+                SIGNATURE_SUCCESS => deserialize_struct!(Success, bytes, config),
+                SIGNATURE_FAILURE => deserialize_struct!(Failure, bytes, config),
                 SIGNATURE_IGNORED => Ok((Message::Ignored, bytes)),
                 SIGNATURE_GOODBYE => Ok((Message::Goodbye, bytes)),
-                SIGNATURE_BEGIN => deserialize_struct!(Begin, bytes),
+                SIGNATURE_BEGIN => deserialize_struct!(Begin, bytes, config),
                 SIGNATURE_COMMIT => Ok((Message::Commit, bytes)),
                 SIGNATURE_ROLLBACK => Ok((Message::Rollback, bytes)),
-                SIGNATURE_ROUTE => match RouteWithMetadata::deserialize(bytes.chunk()) {
-                    Ok(_) => {
-                        // Actually consume the bytes
-                        let (message, remaining) = RouteWithMetadata::deserialize(bytes)?;
-                        bytes = remaining;
-                        Ok((Message::RouteWithMetadata(message), bytes))
-                    }
-                    Err(_) => {
+                SIGNATURE_ROUTE => {
+                    // The fields may span several chunks, so copy them to try both versions
+                    let fields = bytes.copy_to_bytes(bytes.remaining());
+                    match RouteWithMetadata::deserialize_with(fields.clone(), config) {
+                        Ok((message, _)) => Ok((Message::RouteWithMetadata(message), bytes)),
                         // Fall back to v4.3-compatible ROUTE message
-                        let (message, remaining) = Route::deserialize(bytes)?;
-                        bytes = remaining;
-                        Ok((Message::Route(message), bytes))
+                        Err(_) => {
+                            let (message, _) = Route::deserialize_with(fields, config)?;
+                            Ok((Message::Route(message), bytes))
+                        }
                     }
-                },
+                }
                 _ => Err(DeserializationError::InvalidSignatureByte(signature)),
             }
         })
-        .map_err(|_| DeserializationError::Panicked)?
     }
 }
 
@@ -278,3 +560,180 @@ impl BoltStructure for Message {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+    #[cfg(feature = "std")]
+    use futures_util::FutureExt;
+
+    use super::*;
+    use crate::{collections::HashMap, value::Node, Value};
+
+    // Create a message that serializes to exactly `size` bytes, for `size` >= 5
+    fn message_of_size(size: usize) -> Message {
+        let bytes_marker_len = |len: usize| match len {
+            0..=0xFF => 2,
+            0x100..=0xFFFF => 3,
+            _ => 5,
+        };
+        // Some sizes can't be reached by a byte array alone, so pad the record with nulls
+        for nulls in 0..=3 {
+            for marker_len in [2, 3, 5] {
+                // Structure marker and signature, and list marker
+                let Some(len) = size.checked_sub(3 + marker_len + nulls) else {
+                    continue;
+                };
+                if bytes_marker_len(len) == marker_len {
+                    let mut fields = vec![Value::Bytes(vec![0xAB; len])];
+                    fields.extend((0..nulls).map(|_| Value::Null));
+                    let message = Message::Record(Record::new(fields));
+                    assert_eq!(message.clone().serialize().unwrap().len(), size);
+                    return message;
+                }
+            }
+        }
+        unreachable!("no message of size {}", size)
+    }
+
+    // Check the chunks of a message, then decode them
+    fn check_chunks(message: Message, max_size: usize) {
+        let size = message.clone().serialize().unwrap().len();
+        let chunks = message.clone().into_chunks_with_size(max_size).unwrap();
+
+        let (footer, chunks) = chunks.split_last().unwrap();
+        assert_eq!(footer.as_ref(), &[0, 0]);
+        assert_eq!(chunks.len(), size.div_ceil(max_size), "size {}", size);
+        for (i, chunk) in chunks.iter().enumerate() {
+            let chunk_len = u16::from_be_bytes([chunk[0], chunk[1]]) as usize;
+            assert_eq!(chunk_len, chunk.len() - 2);
+            match i == chunks.len() - 1 {
+                true => assert_eq!(chunk_len, size - max_size * i),
+                false => assert_eq!(chunk_len, max_size),
+            }
+        }
+
+        let bytes: Vec<u8> = chunks.concat();
+        let mut decoder = MessageDecoder::new();
+        assert_eq!(decoder.feed(&bytes).unwrap(), None);
+        assert_eq!(decoder.feed(footer).unwrap(), Some(message));
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn chunk_boundaries() {
+        for max_size in [1, 2, 255, DEFAULT_MAX_CHUNK_SIZE, MAX_CHUNK_SIZE] {
+            for size in [
+                max_size - 1,
+                max_size,
+                max_size + 1,
+                max_size * 2 - 1,
+                max_size * 2,
+                max_size * 2 + 1,
+                max_size * 3,
+            ] {
+                if size >= 5 {
+                    check_chunks(message_of_size(size), max_size);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn default_chunk_size() {
+        let message = message_of_size(DEFAULT_MAX_CHUNK_SIZE * 2 + 1);
+        assert_eq!(
+            message.clone().into_chunks().unwrap(),
+            message
+                .into_chunks_with_size(DEFAULT_MAX_CHUNK_SIZE)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn empty_messages() {
+        for message in [
+            Message::Reset,
+            Message::Success(Success::new(Default::default())),
+        ] {
+            for max_size in [1, MAX_CHUNK_SIZE] {
+                check_chunks(message.clone(), max_size);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_chunk_size() {
+        for max_size in [0, MAX_CHUNK_SIZE + 1] {
+            assert!(matches!(
+                Message::Reset.into_chunks_with_size(max_size),
+                Err(SerializationError::InvalidChunkSize(size)) if size == max_size
+            ));
+        }
+    }
+
+    #[test]
+    fn skip_no_op_chunks() {
+        let message = message_of_size(MAX_CHUNK_SIZE + 1);
+        let mut bytes = vec![0, 0, 0, 0];
+        for chunk in message
+            .clone()
+            .into_chunks_with_size(MAX_CHUNK_SIZE)
+            .unwrap()
+        {
+            bytes.extend_from_slice(&chunk);
+        }
+        let mut decoder = MessageDecoder::new();
+        assert_eq!(decoder.feed(&bytes).unwrap(), Some(message));
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn values_across_chunks() {
+        let mut properties = HashMap::default();
+        properties.insert(String::from("name"), Value::from("a".repeat(300)));
+        let fields = vec![
+            Value::from(200),
+            Value::from(40_000),
+            Value::from(3_000_000_000_i64),
+            Value::from(1.5),
+            Value::from("b".repeat(70_000)),
+            Value::Bytes(vec![0xCD; 300]),
+            Value::Node(Node::new(1, vec![String::from("Person")], properties)),
+        ];
+        let bookmarks = vec![String::from("bookmark:1")];
+        let messages = [
+            Message::Record(Record::new(fields)),
+            Message::Route(Route::new(
+                HashMap::default(),
+                bookmarks.clone(),
+                Some(String::from("neo4j")),
+            )),
+            Message::RouteWithMetadata(RouteWithMetadata::new(
+                HashMap::default(),
+                bookmarks,
+                HashMap::default(),
+            )),
+        ];
+
+        for message in messages {
+            for max_size in [1, 3, 7, DEFAULT_MAX_CHUNK_SIZE] {
+                let bytes: Vec<u8> = message
+                    .clone()
+                    .into_chunks_with_size(max_size)
+                    .unwrap()
+                    .concat();
+                let mut decoder = MessageDecoder::new();
+                assert_eq!(decoder.feed(&bytes).unwrap(), Some(message.clone()));
+                #[cfg(feature = "std")]
+                assert_eq!(
+                    Message::from_stream(&bytes[..])
+                        .now_or_never()
+                        .unwrap()
+                        .unwrap(),
+                    message
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,448 @@
+use core::time::Duration;
+
+use crate::{
+    collections::HashMap,
+    error::BuildError,
+    message::{Begin, Discard, Hello, Pull, RunWithMetadata},
+    prelude::*,
+    Message, Value,
+};
+
+// The transaction configuration entries shared by BEGIN and RUN
+#[derive(Debug, Clone, Default, PartialEq)]
+struct TxConfig {
+    metadata: HashMap<String, Value>,
+}
+
+impl TxConfig {
+    fn insert(&mut self, key: &str, value: impl Into<Value>) {
+        self.metadata.insert(String::from(key), value.into());
+    }
+
+    fn validate(&self) -> Result<(), BuildError> {
+        for field in ["db", "imp_user"] {
+            if self.metadata.get(field) == Some(&Value::String(String::new())) {
+                return Err(BuildError::InvalidField {
+                    field,
+                    reason: "must not be empty",
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+macro_rules! tx_config_setters {
+    ($T:ident) => {
+        impl $T {
+            /// Set the `bookmarks` entry, the bookmarks the transaction must wait for.
+            pub fn with_bookmarks(
+                mut self,
+                bookmarks: impl IntoIterator<Item = impl Into<String>>,
+            ) -> Self {
+                let bookmarks: Vec<String> = bookmarks.into_iter().map(Into::into).collect();
+                self.tx_config.insert("bookmarks", bookmarks);
+                self
+            }
+
+            /// Set the `tx_timeout` entry, which is sent as an integer number of milliseconds.
+            pub fn with_tx_timeout(mut self, timeout: Duration) -> Self {
+                let millis = i64::try_from(timeout.as_millis()).unwrap_or(i64::MAX);
+                self.tx_config.insert("tx_timeout", millis);
+                self
+            }
+
+            /// Set the `tx_metadata` entry, a map of metadata attached to the transaction, mainly
+            /// used for logging.
+            pub fn with_tx_metadata<K, V>(
+                mut self,
+                tx_metadata: impl IntoIterator<Item = (K, V)>,
+            ) -> Self
+            where
+                K: Into<String>,
+                V: Into<Value>,
+            {
+                let tx_metadata: HashMap<String, Value> = tx_metadata
+                    .into_iter()
+                    .map(|(key, value)| (key.into(), value.into()))
+                    .collect();
+                self.tx_config.insert("tx_metadata", tx_metadata);
+                self
+            }
+
+            /// Set whether the transaction only reads data, which is sent as the `mode` entry. The
+            /// entry is left out for write transactions, which are the default.
+            pub fn with_read_only(mut self, read_only: bool) -> Self {
+                match read_only {
+                    true => self.tx_config.insert("mode", "r"),
+                    false => {
+                        self.tx_config.metadata.remove("mode");
+                    }
+                }
+                self
+            }
+
+            /// Set the `db` entry, the name of the database the transaction should use.
+            /// _(Bolt v4+ only.)_
+            pub fn with_db(mut self, db: impl Into<String>) -> Self {
+                self.tx_config.insert("db", db.into());
+                self
+            }
+
+            /// Set the `imp_user` entry, the user to impersonate for the transaction.
+            /// _(Bolt v4.4+ only.)_
+            pub fn with_imp_user(mut self, imp_user: impl Into<String>) -> Self {
+                self.tx_config.insert("imp_user", imp_user.into());
+                self
+            }
+
+            /// Set any other metadata entry, e.g. one added in a newer protocol version.
+            pub fn with_entry(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+                self.tx_config.metadata.insert(key.into(), value.into());
+                self
+            }
+        }
+    };
+}
+
+/// A builder for [`BEGIN`](Message::Begin) messages. _(Bolt v3+ only.)_
+///
+/// ```
+/// # use core::time::Duration;
+/// # use bolt_proto::{message::BeginBuilder, Message, Value};
+/// let message = BeginBuilder::new()
+///     .with_bookmarks(["bookmark:1"])
+///     .with_tx_timeout(Duration::from_secs(5))
+///     .with_read_only(true)
+///     .build()?;
+/// match message {
+///     Message::Begin(begin) => {
+///         assert_eq!(begin.metadata().get("tx_timeout"), Some(&Value::from(5000)));
+///         assert_eq!(begin.metadata().get("mode"), Some(&Value::from("r")));
+///     }
+///     _ => unreachable!(),
+/// }
+/// # Ok::<(), bolt_proto::error::BuildError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BeginBuilder {
+    tx_config: TxConfig,
+}
+
+impl BeginBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn build(self) -> Result<Message, BuildError> {
+        self.tx_config.validate()?;
+        Ok(Message::Begin(Begin::new(self.tx_config.metadata)))
+    }
+}
+
+tx_config_setters!(BeginBuilder);
+
+/// A builder for [`RUN`](Message::RunWithMetadata) messages with metadata. _(Bolt v3+ only.)_
+///
+/// ```
+/// # use bolt_proto::{message::RunBuilder, Message};
+/// let message = RunBuilder::new("RETURN $n;")
+///     .with_parameter("n", 1)
+///     .with_db("neo4j")
+///     .build()?;
+/// assert!(matches!(message, Message::RunWithMetadata(_)));
+/// # Ok::<(), bolt_proto::error::BuildError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunBuilder {
+    query: String,
+    parameters: HashMap<String, Value>,
+    tx_config: TxConfig,
+}
+
+impl RunBuilder {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            parameters: HashMap::default(),
+            tx_config: TxConfig::default(),
+        }
+    }
+
+    /// Set a query parameter, replacing any previous value with the same name.
+    pub fn with_parameter(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.parameters.insert(name.into(), value.into());
+        self
+    }
+
+    /// Set all query parameters, replacing any previously set.
+    pub fn with_parameters<K, V>(mut self, parameters: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        self.parameters = parameters
+            .into_iter()
+            .map(|(name, value)| (name.into(), value.into()))
+            .collect();
+        self
+    }
+
+    /// Build the message. Fails if the query is empty.
+    pub fn build(self) -> Result<Message, BuildError> {
+        if self.query.trim().is_empty() {
+            return Err(BuildError::InvalidField {
+                field: "query",
+                reason: "must not be empty",
+            });
+        }
+        self.tx_config.validate()?;
+        Ok(Message::RunWithMetadata(RunWithMetadata::new(
+            self.query,
+            self.parameters,
+            self.tx_config.metadata,
+        )))
+    }
+}
+
+tx_config_setters!(RunBuilder);
+
+/// A builder for [`PULL`](Message::Pull) and [`DISCARD`](Message::Discard) messages, which must
+/// have the number of records to stream. _(Bolt v4+ only.)_
+///
+/// ```
+/// # use bolt_proto::{error::BuildError, message::StreamBuilder, Message, Value};
+/// let message = StreamBuilder::new().with_n(1000).build_pull()?;
+/// assert!(matches!(message, Message::Pull(_)));
+///
+/// assert!(matches!(StreamBuilder::new().build_pull(), Err(BuildError::MissingField("n"))));
+/// assert!(StreamBuilder::new().with_n(0).build_discard().is_err());
+/// # Ok::<(), BuildError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamBuilder {
+    n: Option<i64>,
+    qid: Option<i64>,
+}
+
+impl StreamBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `n` entry, the number of records to pull or discard, which must be positive, or -1
+    /// for all remaining records.
+    pub fn with_n(mut self, n: i64) -> Self {
+        self.n = Some(n);
+        self
+    }
+
+    /// Stream all remaining records. This is the same as `with_n(-1)`.
+    pub fn with_all(self) -> Self {
+        self.with_n(-1)
+    }
+
+    /// Set the `qid` entry, the ID of the query in the current transaction whose records to
+    /// stream, or -1 for the last query. _(Bolt v4+ only.)_
+    pub fn with_qid(mut self, qid: i64) -> Self {
+        self.qid = Some(qid);
+        self
+    }
+
+    /// Build a [`PULL`](Message::Pull) message.
+    pub fn build_pull(self) -> Result<Message, BuildError> {
+        self.metadata()
+            .map(|metadata| Message::Pull(Pull::new(metadata)))
+    }
+
+    /// Build a [`DISCARD`](Message::Discard) message.
+    pub fn build_discard(self) -> Result<Message, BuildError> {
+        self.metadata()
+            .map(|metadata| Message::Discard(Discard::new(metadata)))
+    }
+
+    fn metadata(self) -> Result<HashMap<String, Value>, BuildError> {
+        let n = match self.n {
+            Some(n) if n > 0 || n == -1 => n,
+            Some(_) => {
+                return Err(BuildError::InvalidField {
+                    field: "n",
+                    reason: "must be positive or -1",
+                })
+            }
+            None => return Err(BuildError::MissingField("n")),
+        };
+        let mut metadata = HashMap::default();
+        metadata.insert(String::from("n"), Value::from(n));
+        match self.qid {
+            Some(qid) if qid < -1 => {
+                return Err(BuildError::InvalidField {
+                    field: "qid",
+                    reason: "must not be negative, other than -1",
+                })
+            }
+            Some(qid) => {
+                metadata.insert(String::from("qid"), Value::from(qid));
+            }
+            None => {}
+        }
+        Ok(metadata)
+    }
+}
+
+/// A builder for [`HELLO`](Message::Hello) messages, which must have a user agent.
+/// _(Bolt v3+ only.)_
+///
+/// ```
+/// # use bolt_proto::{error::BuildError, message::HelloBuilder, Message};
+/// let message = HelloBuilder::new()
+///     .with_user_agent("my-app/1.0")
+///     .with_basic_auth("neo4j", "password")
+///     .build()?;
+/// assert!(matches!(message, Message::Hello(_)));
+///
+/// assert!(matches!(
+///     HelloBuilder::new().build(),
+///     Err(BuildError::MissingField("user_agent"))
+/// ));
+/// # Ok::<(), BuildError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HelloBuilder {
+    metadata: HashMap<String, Value>,
+}
+
+impl HelloBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `user_agent` entry, which identifies the client to the server, e.g. `"my-app/1.0"`.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.insert("user_agent", user_agent.into());
+        self
+    }
+
+    /// Authenticate with a user name and password, using the `basic` scheme.
+    pub fn with_basic_auth(
+        mut self,
+        principal: impl Into<String>,
+        credentials: impl Into<String>,
+    ) -> Self {
+        self.insert("scheme", "basic");
+        self.insert("principal", principal.into());
+        self.insert("credentials", credentials.into());
+        self
+    }
+
+    /// Set the `routing` entry, the routing context of a connection to a cluster member.
+    /// _(Bolt v4.1+ only.)_
+    pub fn with_routing<K, V>(mut self, routing: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        let routing: HashMap<String, Value> = routing
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        self.insert("routing", routing);
+        self
+    }
+
+    /// Set any other metadata entry, e.g. one for another authentication scheme.
+    pub fn with_entry(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Build the message. Fails if no user agent was set.
+    pub fn build(self) -> Result<Message, BuildError> {
+        match self.metadata.get("user_agent") {
+            Some(Value::String(user_agent)) if !user_agent.is_empty() => {
+                Ok(Message::Hello(Hello::new(self.metadata)))
+            }
+            Some(_) => Err(BuildError::InvalidField {
+                field: "user_agent",
+                reason: "must be a non-empty string",
+            }),
+            None => Err(BuildError::MissingField("user_agent")),
+        }
+    }
+
+    fn insert(&mut self, key: &str, value: impl Into<Value>) {
+        self.metadata.insert(String::from(key), value.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run() {
+        let message = RunBuilder::new("RETURN $n;")
+            .with_parameters([("n", 1)])
+            .with_tx_metadata([("app", "test")])
+            .with_read_only(true)
+            .with_read_only(false)
+            .with_imp_user("alice")
+            .build()
+            .unwrap();
+        let run = RunWithMetadata::try_from(message).unwrap();
+        assert_eq!(run.statement(), "RETURN $n;");
+        assert_eq!(run.parameters().get("n"), Some(&Value::from(1)));
+        assert_eq!(run.metadata().len(), 2);
+        assert_eq!(run.metadata().get("imp_user"), Some(&Value::from("alice")));
+
+        assert!(matches!(
+            RunBuilder::new(" ").build(),
+            Err(BuildError::InvalidField { field: "query", .. })
+        ));
+        assert!(matches!(
+            RunBuilder::new("RETURN 1;").with_db("").build(),
+            Err(BuildError::InvalidField { field: "db", .. })
+        ));
+    }
+
+    #[test]
+    fn stream() {
+        let pull = Pull::try_from(
+            StreamBuilder::new()
+                .with_all()
+                .with_qid(2)
+                .build_pull()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(pull.metadata().get("n"), Some(&Value::from(-1)));
+        assert_eq!(pull.metadata().get("qid"), Some(&Value::from(2)));
+
+        assert!(StreamBuilder::new().with_n(-2).build_pull().is_err());
+        assert!(matches!(
+            StreamBuilder::new().with_n(10).with_qid(-2).build_discard(),
+            Err(BuildError::InvalidField { field: "qid", .. })
+        ));
+    }
+
+    #[test]
+    fn hello() {
+        let hello = Hello::try_from(
+            HelloBuilder::new()
+                .with_user_agent("bolt-rs/1.0")
+                .with_routing([("address", "localhost:7687")])
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(hello.metadata().len(), 2);
+
+        assert!(matches!(
+            HelloBuilder::new().with_entry("user_agent", 1).build(),
+            Err(BuildError::InvalidField {
+                field: "user_agent",
+                ..
+            })
+        ));
+    }
+}
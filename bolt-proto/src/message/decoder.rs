@@ -0,0 +1,401 @@
+use alloc::collections::VecDeque;
+use core::mem;
+
+use bytes::{Buf, Bytes, BytesMut};
+
+use crate::{
+    error::*,
+    message::{LazyRecord, SIGNATURE_RECORD},
+    prelude::*,
+    serialization::DecodeConfig,
+    value::Utf8Mode,
+    Message,
+};
+
+/// A message returned by [`MessageDecoder::decode_lazy`].
+#[derive(Debug, Clone)]
+pub enum LazyMessage {
+    Record(LazyRecord),
+    Other(Message),
+}
+
+impl TryFrom<LazyMessage> for Message {
+    type Error = DeserializationError;
+
+    /// Deserialize the fields of a lazy record, if necessary.
+    fn try_from(message: LazyMessage) -> DeserializeResult<Self> {
+        match message {
+            LazyMessage::Record(record) => record.try_into(),
+            LazyMessage::Other(message) => Ok(message),
+        }
+    }
+}
+
+/// An incremental decoder for chunked [`Message`]s.
+///
+/// Unlike [`Message::from_stream`], this does not perform any IO. Bytes are handed to the decoder
+/// as they arrive, in slices of any size, and complete messages are returned once their final
+/// chunk has been received. This makes it possible to use the protocol with completion-based IO,
+/// custom event loops, or other sans-IO designs.
+///
+/// As with [`Message::from_stream`], the chunks of a message are deserialized in place rather
+/// than being copied into one contiguous buffer.
+///
+/// By default, the decoder accepts messages and values of any size. A misbehaving server could
+/// send a message declaring a multi-gigabyte string, so when the server isn't trusted, limits can
+/// be set with [`MessageDecoder::with_max_message_size`] and [`MessageDecoder::with_max_value_size`].
+#[derive(Debug, Default)]
+pub struct MessageDecoder {
+    // Received bytes that have not yet been split into chunks
+    buffer: BytesMut,
+    // Chunk data for the message currently being received, one buffer per chunk
+    message: VecDeque<Bytes>,
+    // Total size of the chunk data in `message`
+    message_len: usize,
+    max_message_size: Option<usize>,
+    config: DecodeConfig,
+}
+
+impl MessageDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how strings that are not valid UTF-8 are deserialized. Defaults to
+    /// [`Utf8Mode::Strict`].
+    pub fn with_utf8_mode(mut self, utf8_mode: Utf8Mode) -> Self {
+        self.config.utf8_mode = utf8_mode;
+        self
+    }
+
+    /// Get how strings that are not valid UTF-8 are deserialized.
+    pub fn utf8_mode(&self) -> Utf8Mode {
+        self.config.utf8_mode
+    }
+
+    /// Set the maximum size of a message in bytes, not counting chunk headers. A message is
+    /// rejected with [`DeserializationError::MessageTooLarge`] as soon as a chunk header shows it
+    /// will be too large, before the rest of it is buffered. Defaults to [`None`], for no limit.
+    ///
+    /// After this error, the rest of the message is still on the stream, so the connection can't
+    /// be used anymore.
+    pub fn with_max_message_size(mut self, max_message_size: Option<usize>) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// Get the maximum size of a message in bytes, if any.
+    pub fn max_message_size(&self) -> Option<usize> {
+        self.max_message_size
+    }
+
+    /// Set the maximum size of a value: the number of bytes in a string or byte array, or the
+    /// number of entries in a list or map. The size a value declares is checked before anything is
+    /// allocated for it, and a value that is too large fails its message with
+    /// [`DeserializationError::ValueTooLarge`]. Defaults to [`None`], for no limit.
+    pub fn with_max_value_size(mut self, max_value_size: Option<usize>) -> Self {
+        self.config.max_value_size = max_value_size;
+        self
+    }
+
+    /// Get the maximum size of a value, if any.
+    pub fn max_value_size(&self) -> Option<usize> {
+        self.config.max_value_size
+    }
+
+    /// Set whether lists that only contain floats, e.g. embedding vectors, are deserialized as
+    /// [`Value::FloatArray`](crate::Value::FloatArray) instead of [`Value::List`](crate::Value::List).
+    /// A float array takes a fraction of the memory of the equivalent list. Defaults to `false`.
+    ///
+    /// Empty lists are still deserialized as [`Value::List`](crate::Value::List).
+    pub fn with_float_arrays(mut self, float_arrays: bool) -> Self {
+        self.config.float_arrays = float_arrays;
+        self
+    }
+
+    /// Check whether lists of floats are deserialized as float arrays.
+    pub fn float_arrays(&self) -> bool {
+        self.config.float_arrays
+    }
+
+    /// Add received bytes to the decoder, returning the next complete message if one is available.
+    ///
+    /// If `bytes` completes more than one message, only the first is returned. The rest can be
+    /// retrieved with [`MessageDecoder::decode`].
+    pub fn feed(&mut self, bytes: &[u8]) -> DeserializeResult<Option<Message>> {
+        self.buffer.extend_from_slice(bytes);
+        self.decode()
+    }
+
+    /// Return the next complete message from the bytes received so far, if one is available.
+    pub fn decode(&mut self) -> DeserializeResult<Option<Message>> {
+        match self.next_chunks()? {
+            Some(chunks) => self.deserialize(chunks).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Add received bytes to the decoder like [`MessageDecoder::feed`], returning a
+    /// [`RECORD`](Message::Record) as a [`LazyRecord`] whose fields are only deserialized when
+    /// they are accessed.
+    pub fn feed_lazy(&mut self, bytes: &[u8]) -> DeserializeResult<Option<LazyMessage>> {
+        self.buffer.extend_from_slice(bytes);
+        self.decode_lazy()
+    }
+
+    /// Return the next complete message like [`MessageDecoder::decode`], returning a
+    /// [`RECORD`](Message::Record) as a [`LazyRecord`] whose fields are only deserialized when
+    /// they are accessed. Other messages are deserialized as usual.
+    pub fn decode_lazy(&mut self) -> DeserializeResult<Option<LazyMessage>> {
+        let chunks = match self.next_chunks()? {
+            Some(chunks) => chunks,
+            None => return Ok(None),
+        };
+        let is_record = chunks
+            .front()
+            .is_some_and(|chunk| chunk.get(1) == Some(&SIGNATURE_RECORD));
+        if !is_record {
+            return self
+                .deserialize(chunks)
+                .map(|message| Some(LazyMessage::Other(message)));
+        }
+
+        let bytes = match chunks.len() {
+            1 => chunks.into_iter().next().unwrap_or_default(),
+            _ => Vec::from(chunks).concat().into(),
+        };
+        let record = LazyRecord::from_bytes(bytes)?.with_config(self.config);
+        Ok(Some(LazyMessage::Record(record)))
+    }
+
+    fn deserialize(&self, chunks: VecDeque<Bytes>) -> DeserializeResult<Message> {
+        Message::deserialize_complete(chunks, &self.config)
+    }
+
+    // Split the received bytes into chunks, returning the chunks of the next complete message
+    fn next_chunks(&mut self) -> DeserializeResult<Option<VecDeque<Bytes>>> {
+        while self.buffer.len() >= mem::size_of::<u16>() {
+            let chunk_len = u16::from_be_bytes([self.buffer[0], self.buffer[1]]) as usize;
+
+            if chunk_len == 0 {
+                self.buffer.advance(mem::size_of::<u16>());
+                // Ignore any no-op messages
+                if self.message.is_empty() {
+                    continue;
+                }
+
+                // Messages end in a 0_u16
+                self.message_len = 0;
+                return Ok(Some(mem::take(&mut self.message)));
+            }
+
+            let size = self.message_len + chunk_len;
+            if let Some(max) = self.max_message_size.filter(|&max| size > max) {
+                // The rest of the message can't be skipped without buffering it, so drop everything
+                self.buffer.clear();
+                self.message.clear();
+                self.message_len = 0;
+                return Err(DeserializationError::MessageTooLarge { size, max });
+            }
+
+            if self.buffer.len() < mem::size_of::<u16>() + chunk_len {
+                break;
+            }
+
+            self.buffer.advance(mem::size_of::<u16>());
+            self.message
+                .push_back(Bytes::copy_from_slice(&self.buffer[..chunk_len]));
+            self.message_len = size;
+            self.buffer.advance(chunk_len);
+        }
+
+        Ok(None)
+    }
+
+    /// Return whether the decoder is holding any bytes that are not yet part of a complete message.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty() && self.message.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{message::*, Value};
+
+    use super::*;
+
+    fn chunked(message: Message) -> Vec<u8> {
+        message.into_chunks().unwrap().concat()
+    }
+
+    #[test]
+    fn decode_byte_by_byte() {
+        let message = Message::Record(Record::new(vec![Value::from("a".repeat(40_000))]));
+        let bytes = chunked(message.clone());
+        let mut decoder = MessageDecoder::new();
+
+        let (last, rest) = bytes.split_last().unwrap();
+        for byte in rest {
+            assert_eq!(decoder.feed(&[*byte]).unwrap(), None);
+        }
+        assert_eq!(decoder.feed(&[*last]).unwrap(), Some(message));
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn decode_multiple_messages() {
+        let success = Message::Success(Success::new(HashMap::from_iter(vec![(
+            String::from("has_more"),
+            Value::from(false),
+        )])));
+        let mut bytes = chunked(Message::Ignored);
+        // No-op chunks between messages should be skipped
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        bytes.extend(chunked(success.clone()));
+        bytes.extend(chunked(Message::Reset));
+
+        let mut decoder = MessageDecoder::new();
+        assert_eq!(decoder.feed(&bytes).unwrap(), Some(Message::Ignored));
+        assert_eq!(decoder.decode().unwrap(), Some(success));
+        assert_eq!(decoder.decode().unwrap(), Some(Message::Reset));
+        assert_eq!(decoder.decode().unwrap(), None);
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn decode_partial_chunk() {
+        let mut decoder = MessageDecoder::new();
+        assert_eq!(decoder.feed(&[0x00, 0x02, 0xB0]).unwrap(), None);
+        assert!(!decoder.is_empty());
+        assert_eq!(decoder.feed(&[0x0F, 0x00]).unwrap(), None);
+        assert_eq!(decoder.feed(&[0x00]).unwrap(), Some(Message::Reset));
+    }
+
+    #[test]
+    fn max_message_size() {
+        let message = Message::Record(Record::new(vec![Value::from("a".repeat(100))]));
+        let bytes = chunked(message.clone());
+
+        let mut decoder = MessageDecoder::new().with_max_message_size(Some(200));
+        assert_eq!(decoder.feed(&bytes).unwrap(), Some(message));
+
+        // Rejected from the chunk header alone
+        let mut decoder = MessageDecoder::new().with_max_message_size(Some(50));
+        assert!(matches!(
+            decoder.feed(&bytes[..2]),
+            Err(DeserializationError::MessageTooLarge { size: 105, max: 50 })
+        ));
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn max_value_size() {
+        // A RECORD with a string that declares 2 GiB of data
+        let chunk = [
+            0x00, 0x08, 0xB1, 0x71, 0x91, 0xD2, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut decoder = MessageDecoder::new().with_max_value_size(Some(1024));
+        match decoder.feed(&chunk) {
+            Err(DeserializationError::InvalidMessage { source, .. }) => assert!(matches!(
+                *source,
+                DeserializationError::ValueTooLarge {
+                    size: 0x8000_0000,
+                    max: 1024
+                }
+            )),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // Lists and maps are limited by their number of entries
+        let message = Message::Record(Record::new(vec![Value::from(vec![1, 2, 3])]));
+        let mut decoder = MessageDecoder::new().with_max_value_size(Some(3));
+        assert_eq!(
+            decoder.feed(&chunked(message.clone())).unwrap(),
+            Some(message)
+        );
+        let message = Message::Record(Record::new(vec![Value::from(vec![1, 2, 3, 4])]));
+        assert!(decoder.feed(&chunked(message)).is_err());
+    }
+
+    #[test]
+    fn decode_lazy() {
+        let record = Message::Record(Record::new(vec![Value::from("a".repeat(40_000))]));
+        let mut bytes = chunked(record.clone());
+        bytes.extend(chunked(Message::Ignored));
+
+        let mut decoder = MessageDecoder::new();
+        match decoder.feed_lazy(&bytes).unwrap() {
+            Some(LazyMessage::Record(lazy)) => {
+                assert_eq!(lazy.len(), 1);
+                assert_eq!(Message::try_from(lazy).unwrap(), record);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(matches!(
+            decoder.decode_lazy().unwrap(),
+            Some(LazyMessage::Other(Message::Ignored))
+        ));
+        assert!(decoder.decode_lazy().unwrap().is_none());
+    }
+
+    #[test]
+    fn decode_invalid_message() {
+        let mut decoder = MessageDecoder::new();
+        match decoder.feed(&[0x00, 0x02, 0xB0, 0x99, 0x00, 0x00]) {
+            Err(err @ DeserializationError::InvalidMessage { .. }) => {
+                // The kind is that of the underlying error
+                assert_eq!(err.kind(), DeserializationErrorKind::InvalidSignatureByte);
+                assert_eq!(
+                    Error::from(err).kind(),
+                    ErrorKind::DeserializationError(DeserializationErrorKind::InvalidSignatureByte)
+                );
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let mut decoder = MessageDecoder::new();
+        match decoder.feed(&[0x00, 0x02, 0xB0, 0x99, 0x00, 0x00]) {
+            Err(DeserializationError::InvalidMessage {
+                offset,
+                length,
+                snippet,
+                source,
+            }) => {
+                assert_eq!((offset, length), (1, 2));
+                assert_eq!(snippet, "b0 [99]");
+                assert!(matches!(
+                    *source,
+                    DeserializationError::InvalidSignatureByte(0x99)
+                ));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decode_truncated_message() {
+        // A RECORD with a list of 3 fields, but only 1 field present
+        let mut decoder = MessageDecoder::new();
+        match decoder.feed(&[0x00, 0x04, 0xB1, 0x71, 0x93, 0x01, 0x00, 0x00]) {
+            Err(err @ DeserializationError::InvalidMessage { .. }) => {
+                assert_eq!(
+                    err.to_string(),
+                    "unexpected end of input (1 bytes needed, 0 remaining) \
+                     (at byte 4 of 4: b1 71 93 01 [end])"
+                );
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // A RECORD with a string field whose declared size is past the end of the message
+        let mut decoder = MessageDecoder::new();
+        match decoder.feed(&[0x00, 0x04, 0xB1, 0x71, 0xD0, 0xFF, 0x00, 0x00]) {
+            Err(err @ DeserializationError::InvalidMessage { .. }) => {
+                assert_eq!(err.kind(), DeserializationErrorKind::UnexpectedEnd);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
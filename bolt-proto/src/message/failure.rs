@@ -1,8 +1,11 @@
-use std::collections::HashMap;
+use core::fmt;
 
 use bolt_proto_derive::*;
 
-use crate::{impl_message_with_metadata, impl_try_from_message, message::SIGNATURE_FAILURE, Value};
+use crate::{
+    collections::HashMap, impl_message_with_metadata, impl_try_from_message,
+    message::SIGNATURE_FAILURE, prelude::*, Value,
+};
 
 #[bolt_structure(SIGNATURE_FAILURE)]
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -10,5 +13,65 @@ pub struct Failure {
     pub(crate) metadata: HashMap<String, Value>,
 }
 
+impl Failure {
+    /// Get the status code of the failure (e.g. `"Neo.ClientError.Statement.SyntaxError"`), from
+    /// the `code` metadata entry.
+    pub fn code(&self) -> Option<&str> {
+        self.metadata.get("code").and_then(Value::as_str)
+    }
+
+    /// Get the description of the failure, from the `message` metadata entry.
+    pub fn message(&self) -> Option<&str> {
+        self.metadata.get("message").and_then(Value::as_str)
+    }
+}
+
 impl_message_with_metadata!(Failure);
 impl_try_from_message!(Failure, Failure);
+
+impl fmt::Display for Failure {
+    /// Format the failure as `"<code>: <message>"`, leaving out whichever entry is missing.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.code(), self.message()) {
+            (Some(code), Some(message)) => write!(f, "{}: {}", code, message),
+            (Some(text), None) | (None, Some(text)) => f.write_str(text),
+            (None, None) => f.write_str("unknown failure"),
+        }
+    }
+}
+
+impl core::error::Error for Failure {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(entries: &[(&str, &str)]) -> Failure {
+        Failure::new(
+            entries
+                .iter()
+                .map(|&(key, value)| (String::from(key), Value::from(value)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn display() {
+        let code = ("code", "Neo.ClientError.Statement.SyntaxError");
+        let message = ("message", "Invalid input 'RETRUN'");
+        assert_eq!(
+            failure(&[code, message]).to_string(),
+            "Neo.ClientError.Statement.SyntaxError: Invalid input 'RETRUN'"
+        );
+        assert_eq!(
+            failure(&[code]).to_string(),
+            "Neo.ClientError.Statement.SyntaxError"
+        );
+        assert_eq!(
+            failure(&[message]).message(),
+            Some("Invalid input 'RETRUN'")
+        );
+        assert_eq!(failure(&[]).code(), None);
+        assert_eq!(failure(&[]).to_string(), "unknown failure");
+    }
+}
@@ -0,0 +1,213 @@
+use alloc::sync::Arc;
+
+use bytes::{Buf, Bytes};
+
+use crate::{
+    error::*,
+    message::{Record, SIGNATURE_RECORD},
+    prelude::*,
+    serialization::*,
+    value::{self, MARKER_LARGE_LIST, MARKER_MEDIUM_LIST, MARKER_SMALL_LIST, MARKER_TINY_LIST},
+    Message, Value,
+};
+
+/// A [`Record`] whose fields are only deserialized when they are accessed.
+///
+/// Each field is kept as the raw bytes it was received as, and is deserialized every time it is
+/// accessed with [`LazyRecord::get`] or [`LazyRecord::get_named`]. For wide records where only a
+/// few fields are used, this skips the work of deserializing the rest. To use every field,
+/// [`LazyRecord::into_record`] deserializes them all at once.
+///
+/// Lazy records are returned by [`MessageDecoder::decode_lazy`](crate::message::MessageDecoder::decode_lazy),
+/// and fields are deserialized with the settings of the decoder that returned them.
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use bolt_proto::{message::{LazyRecord, Record}, Message, Value};
+/// let message = Message::Record(Record::new(vec![Value::from("Alice"), Value::from(42)]));
+/// let bytes = message.into_chunks().unwrap().concat();
+/// // Remove the chunk headers
+/// let record = LazyRecord::from_bytes(bytes[2..bytes.len() - 2].to_vec().into())
+///     .unwrap()
+///     .with_keys(Arc::from([String::from("name"), String::from("age")]));
+/// assert_eq!(record.len(), 2);
+/// assert_eq!(record.get(1).unwrap().unwrap(), Value::from(42));
+/// assert_eq!(record.get_named("name").unwrap().unwrap(), Value::from("Alice"));
+/// assert!(record.get_named("email").is_none());
+/// ```
+#[derive(Debug, Clone)]
+pub struct LazyRecord {
+    fields: Vec<Bytes>,
+    keys: Option<Arc<[String]>>,
+    config: DecodeConfig,
+}
+
+impl LazyRecord {
+    /// Split a serialized `RECORD` message, without chunk headers, into its fields. The fields are
+    /// checked to be complete, but their contents are not deserialized.
+    pub fn from_bytes(bytes: Bytes) -> DeserializeResult<Self> {
+        let fields = catch_panic(move || {
+            let mut bytes = bytes;
+            let marker = bytes.read_u8()?;
+            let (size, signature) = get_structure_info(marker, &mut bytes)?;
+            if signature != SIGNATURE_RECORD {
+                return Err(DeserializationError::InvalidSignatureByte(signature));
+            }
+            if size != 1 {
+                return Err(DeserializationError::InvalidSize { size, signature });
+            }
+
+            let marker = bytes.read_u8()?;
+            let len = match marker {
+                marker if (MARKER_TINY_LIST..=(MARKER_TINY_LIST | 0x0F)).contains(&marker) => {
+                    0x0F & marker as usize
+                }
+                MARKER_SMALL_LIST => bytes.read_u8()? as usize,
+                MARKER_MEDIUM_LIST => bytes.read_u16()? as usize,
+                MARKER_LARGE_LIST => bytes.read_u32()? as usize,
+                _ => return Err(DeserializationError::InvalidMarkerByte(marker)),
+            };
+
+            // Every field takes at least one byte, so don't trust a larger declared length
+            let mut fields = Vec::with_capacity(len.min(bytes.remaining()));
+            for _ in 0..len {
+                let mut rest = &bytes[..];
+                value::skip_value(&mut rest)?;
+                fields.push(bytes.split_to(bytes.len() - rest.len()));
+            }
+            Ok(fields)
+        })?;
+
+        Ok(Self {
+            fields,
+            keys: None,
+            config: DecodeConfig::default(),
+        })
+    }
+
+    /// Set the field names used by [`LazyRecord::get_named`]. The field names are sent by the
+    /// server in the `fields` entry of the [`SUCCESS`](crate::message::Success) response to `RUN`.
+    pub fn with_keys(mut self, keys: Arc<[String]>) -> Self {
+        self.keys = Some(keys);
+        self
+    }
+
+    pub(crate) fn with_config(mut self, config: DecodeConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Get the field names, if they have been set.
+    pub fn keys(&self) -> Option<&Arc<[String]>> {
+        self.keys.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Deserialize the field at the given position. Returns [`None`] if there is no such field.
+    pub fn get(&self, index: usize) -> Option<DeserializeResult<Value>> {
+        self.fields
+            .get(index)
+            .map(|field| self.parse(field.clone()))
+    }
+
+    /// Deserialize the field with the given name. Returns [`None`] if no field names have been set
+    /// or there is no field with that name.
+    pub fn get_named(&self, key: &str) -> Option<DeserializeResult<Value>> {
+        let index = self.keys.as_ref()?.iter().position(|k| k == key)?;
+        self.get(index)
+    }
+
+    /// Iterate over the fields, deserializing each one as it is reached.
+    pub fn iter(&self) -> impl Iterator<Item = DeserializeResult<Value>> + '_ {
+        self.fields.iter().map(|field| self.parse(field.clone()))
+    }
+
+    /// Deserialize every field, returning the equivalent [`Record`].
+    pub fn into_record(self) -> DeserializeResult<Record> {
+        self.iter()
+            .collect::<DeserializeResult<_>>()
+            .map(Record::new)
+    }
+
+    fn parse(&self, field: Bytes) -> DeserializeResult<Value> {
+        let (value, _) = Value::deserialize_with(field, &self.config)?;
+        Ok(value)
+    }
+}
+
+impl TryFrom<LazyRecord> for Record {
+    type Error = DeserializationError;
+
+    fn try_from(record: LazyRecord) -> DeserializeResult<Self> {
+        record.into_record()
+    }
+}
+
+impl TryFrom<LazyRecord> for Message {
+    type Error = DeserializationError;
+
+    fn try_from(record: LazyRecord) -> DeserializeResult<Self> {
+        record.into_record().map(Message::Record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn lazy(fields: Vec<Value>) -> LazyRecord {
+        let bytes = Record::new(fields).serialize().unwrap();
+        LazyRecord::from_bytes(bytes).unwrap()
+    }
+
+    #[test]
+    fn get_fields() {
+        let fields = vec![
+            Value::from(vec![Value::from(1), Value::from("a")]),
+            Value::from(HashMap::from([("k", vec![1.5, 2.5])])),
+            Value::from(i64::MAX),
+            Value::from("b".repeat(300)),
+            Value::Null,
+        ];
+        let record = lazy(fields.clone());
+        assert_eq!(record.len(), fields.len());
+        for (i, field) in fields.iter().enumerate() {
+            assert_eq!(&record.get(i).unwrap().unwrap(), field);
+        }
+        assert!(record.get(fields.len()).is_none());
+        assert!(record.get_named("a").is_none());
+        assert_eq!(record.into_record().unwrap(), Record::new(fields));
+    }
+
+    #[test]
+    fn invalid_fields() {
+        // A RECORD with a string field that is valid, and a field that is not
+        let record = LazyRecord::from_bytes(Bytes::from_static(&[
+            0xB1, 0x71, 0x92, 0x81, b'a', 0x81, 0xFF,
+        ]))
+        .unwrap();
+        assert_eq!(record.get(0).unwrap().unwrap(), Value::from("a"));
+        assert!(matches!(
+            record.get(1),
+            Some(Err(DeserializationError::InvalidUTF8(_)))
+        ));
+        assert!(record.into_record().is_err());
+
+        // Fields that are cut off are found up front
+        assert!(matches!(
+            LazyRecord::from_bytes(Bytes::from_static(&[0xB1, 0x71, 0x91, 0x82, b'a'])),
+            Err(DeserializationError::UnexpectedEnd { .. })
+        ));
+        // Not a RECORD
+        assert!(LazyRecord::from_bytes(Bytes::from_static(&[0xB0, 0x7E])).is_err());
+    }
+}
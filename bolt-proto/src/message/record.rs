@@ -1,6 +1,11 @@
 use bolt_proto_derive::*;
 
-use crate::{impl_try_from_message, message::SIGNATURE_RECORD, Value};
+#[cfg(feature = "serde")]
+use crate::error::ConversionResult;
+
+use crate::{
+    collections::HashMap, impl_try_from_message, message::SIGNATURE_RECORD, prelude::*, Value,
+};
 
 #[bolt_structure(SIGNATURE_RECORD)]
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -16,6 +21,177 @@ impl Record {
     pub fn fields(&self) -> &[Value] {
         &self.fields
     }
+
+    pub fn into_fields(self) -> Vec<Value> {
+        self.fields
+    }
+
+    /// Create a record from a map, taking the value of each field in the given order. Fields that
+    /// are missing from the map are [`Value::Null`], and entries that are not listed in `fields`
+    /// are dropped.
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use bolt_proto::{message::Record, Value};
+    /// let fields = vec![String::from("name"), String::from("age")];
+    /// let map = HashMap::from([(String::from("name"), Value::from("Alice"))]);
+    /// let record = Record::from_map(map, &fields);
+    /// assert_eq!(record.fields(), &[Value::from("Alice"), Value::Null]);
+    /// ```
+    pub fn from_map(mut map: HashMap<String, Value>, fields: &[String]) -> Self {
+        let fields = fields
+            .iter()
+            .map(|field| map.remove(field).unwrap_or(Value::Null))
+            .collect();
+        Self { fields }
+    }
+
+    /// Convert the record into a map, pairing each value with the field name at the same
+    /// position. The field names are sent by the server in the `fields` entry of the
+    /// [`SUCCESS`](crate::message::Success) response to `RUN`. If there are more values than field
+    /// names or vice versa, the extra ones are dropped.
+    ///
+    /// ```
+    /// # use bolt_proto::{message::Record, Value};
+    /// let fields = vec![String::from("name"), String::from("age")];
+    /// let record = Record::new(vec![Value::from("Alice"), Value::from(42)]);
+    /// let map = record.into_map(&fields);
+    /// assert_eq!(map.get("age"), Some(&Value::from(42)));
+    /// ```
+    pub fn into_map(self, fields: &[String]) -> HashMap<String, Value> {
+        fields.iter().cloned().zip(self.fields).collect()
+    }
+
+    /// Deserialize the record into a type, pairing each value with the field name at the same
+    /// position. The field names are sent by the server in the `fields` entry of the
+    /// [`SUCCESS`](crate::message::Success) response to `RUN`.
+    ///
+    /// ```
+    /// # use bolt_proto::{message::Record, Value};
+    /// #[derive(serde::Deserialize)]
+    /// struct Person {
+    ///     name: String,
+    ///     age: i64,
+    /// }
+    ///
+    /// // RETURN p.name AS name, p.age AS age
+    /// let fields = vec![String::from("name"), String::from("age")];
+    /// let record = Record::new(vec![Value::from("Alice"), Value::from(42)]);
+    /// let person: Person = record.deserialize_with_fields(&fields).unwrap();
+    /// assert_eq!(person.name, "Alice");
+    /// ```
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn deserialize_with_fields<T: serde::de::DeserializeOwned>(
+        self,
+        fields: &[String],
+    ) -> ConversionResult<T> {
+        if fields.len() != self.fields.len() {
+            return Err(serde::de::Error::invalid_length(
+                self.fields.len(),
+                &format!("{} fields", fields.len()).as_str(),
+            ));
+        }
+
+        crate::value::from_value(Value::Map(self.into_map(fields)))
+    }
 }
 
 impl_try_from_message!(Record, Record);
+
+/// Records serialize as a sequence of their values, and deserialize from one. Use
+/// [`Record::into_map`] to serialize them with their field names instead.
+///
+/// ```
+/// # use bolt_proto::{message::Record, Value};
+/// let record = Record::new(vec![Value::from("Alice"), Value::from(42)]);
+/// let json = serde_json::to_string(&record).unwrap();
+/// assert_eq!(json, r#"["Alice",42]"#);
+/// assert_eq!(serde_json::from_str::<Record>(&json).unwrap(), record);
+/// ```
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl serde::Serialize for Record {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(&self.fields)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de> serde::Deserialize<'de> for Record {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<Value>::deserialize(deserializer).map(Record::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> Vec<String> {
+        vec![String::from("name"), String::from("age")]
+    }
+
+    #[test]
+    fn into_map() {
+        let record = Record::new(vec![Value::from("Alice"), Value::from(42)]);
+        let map = record.clone().into_map(&fields());
+        assert_eq!(
+            map,
+            HashMap::from_iter([
+                (String::from("name"), Value::from("Alice")),
+                (String::from("age"), Value::from(42)),
+            ])
+        );
+        assert_eq!(Record::from_map(map.clone(), &fields()), record);
+
+        assert_eq!(record.clone().into_map(&fields()[..1]).len(), 1);
+        let fields = vec![String::from("age"), String::from("email")];
+        assert_eq!(
+            Record::from_map(map, &fields).fields(),
+            &[Value::from(42), Value::Null]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let record = Record::new(vec![
+            Value::from("Alice"),
+            Value::from(vec![Value::from(1.5), Value::Null]),
+        ]);
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(json, r#"["Alice",[1.5,null]]"#);
+        assert_eq!(serde_json::from_str::<Record>(&json).unwrap(), record);
+        assert!(serde_json::from_str::<Record>(r#"{"name": "Alice"}"#).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Person {
+        name: String,
+        age: i64,
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_with_fields() {
+        let fields = fields();
+        let record = Record::new(vec![Value::from("Alice"), Value::from(42)]);
+        assert_eq!(
+            record
+                .clone()
+                .deserialize_with_fields::<Person>(&fields)
+                .unwrap(),
+            Person {
+                name: String::from("Alice"),
+                age: 42
+            }
+        );
+
+        assert!(record
+            .deserialize_with_fields::<Person>(&fields[..1])
+            .is_err());
+    }
+}
@@ -1,8 +1,8 @@
-use std::collections::HashMap;
-
 use bolt_proto_derive::*;
 
-use crate::{impl_try_from_message, message::SIGNATURE_ROUTE, Value};
+use crate::{
+    collections::HashMap, impl_try_from_message, message::SIGNATURE_ROUTE, prelude::*, Value,
+};
 
 #[bolt_structure(SIGNATURE_ROUTE)]
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -1,8 +1,8 @@
-use std::collections::HashMap;
-
 use bolt_proto_derive::*;
 
-use crate::{impl_try_from_message, message::SIGNATURE_RUN, Value};
+use crate::{
+    collections::HashMap, impl_try_from_message, message::SIGNATURE_RUN, prelude::*, Value,
+};
 
 #[bolt_structure(SIGNATURE_RUN)]
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -1,8 +1,9 @@
-use std::collections::HashMap;
-
 use bolt_proto_derive::*;
 
-use crate::{impl_try_from_message, message::SIGNATURE_RUN_WITH_METADATA, Value};
+use crate::{
+    collections::HashMap, impl_try_from_message, message::SIGNATURE_RUN_WITH_METADATA, prelude::*,
+    Value,
+};
 
 #[bolt_structure(SIGNATURE_RUN_WITH_METADATA)]
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -1,8 +1,9 @@
-use std::collections::HashMap;
-
 use bolt_proto_derive::*;
 
-use crate::{impl_message_with_metadata, impl_try_from_message, message::SIGNATURE_SUCCESS, Value};
+use crate::{
+    collections::HashMap, impl_message_with_metadata, impl_try_from_message,
+    message::SIGNATURE_SUCCESS, prelude::*, Value,
+};
 
 #[bolt_structure(SIGNATURE_SUCCESS)]
 #[derive(Debug, Clone, Eq, PartialEq)]
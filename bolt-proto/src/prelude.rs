@@ -0,0 +1,8 @@
+// Items from the standard library prelude that must be imported from `alloc` without `std`.
+pub(crate) use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
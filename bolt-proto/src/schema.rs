@@ -0,0 +1,524 @@
+//! A machine-readable description of the messages and structures in each protocol version, for
+//! tooling that needs to know the shape of the protocol, like code generators or fuzzers.
+//!
+//! ```
+//! use bolt_proto::{message::MessageKind, schema, version::*};
+//!
+//! let begin = schema::message(MessageKind::Begin);
+//! assert_eq!(begin.name, "BEGIN");
+//! assert_eq!(begin.signature, 0x11);
+//! assert_eq!(begin.fields.len(), 1);
+//! assert!(begin.is_supported(V3_0) && !begin.is_supported(V2_0));
+//!
+//! let v2_structures = schema::STRUCTURES.iter().filter(|s| s.is_supported(V2_0));
+//! assert_eq!(v2_structures.count(), 13);
+//! ```
+
+use crate::{
+    message::{MessageKind, *},
+    value::*,
+    version::*,
+};
+
+/// The type of a field in a message or structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    /// Any [`Value`].
+    Any,
+    Integer,
+    Float,
+    String,
+    /// A map with string keys and values of any type.
+    Map,
+    /// A list with elements of the given type.
+    List(&'static FieldType),
+    /// A structure with the given signature, described in [`STRUCTURES`].
+    Structure(u8),
+    /// Either a value of the given type or null.
+    Nullable(&'static FieldType),
+}
+
+/// A field in a message or structure, in the order it is sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FieldSchema {
+    pub name: &'static str,
+    pub field_type: FieldType,
+}
+
+/// Which side of the connection sends a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Sent by the client.
+    Request,
+    /// Sent by the server.
+    Response,
+}
+
+/// The description of a message in the versions that support it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MessageSchema {
+    pub kind: MessageKind,
+    /// The name of the message in the protocol documentation. Different kinds of messages share a
+    /// name if they are different versions of the same message, e.g. `ROUTE`.
+    pub name: &'static str,
+    /// The signature byte of the message. Different kinds of messages share a signature if they
+    /// are never supported by the same version, or if their number of fields differs.
+    pub signature: u8,
+    pub direction: Direction,
+    pub fields: &'static [FieldSchema],
+    /// The [versions](crate::version) that support the message.
+    pub versions: &'static [u32],
+}
+
+impl MessageSchema {
+    /// Return whether a [version](crate::version) supports this message.
+    pub fn is_supported(&self, version: u32) -> bool {
+        self.versions.contains(&version)
+    }
+}
+
+/// The description of a structure that can be sent as a [`Value`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StructureSchema {
+    /// The name of the structure in the protocol documentation.
+    pub name: &'static str,
+    pub signature: u8,
+    pub fields: &'static [FieldSchema],
+    /// The [versions](crate::version) that support the structure.
+    pub versions: &'static [u32],
+}
+
+impl StructureSchema {
+    /// Return whether a [version](crate::version) supports this structure.
+    pub fn is_supported(&self, version: u32) -> bool {
+        self.versions.contains(&version)
+    }
+}
+
+/// Every protocol version supported by this crate, oldest first.
+pub const VERSIONS: &[u32] = &[V1_0, V2_0, V3_0, V4_0, V4_1, V4_2, V4_3, V4_4];
+
+const V1_TO_V2: &[u32] = &[V1_0, V2_0];
+const V1_TO_V3: &[u32] = &[V1_0, V2_0, V3_0];
+const V2_UP: &[u32] = &[V2_0, V3_0, V4_0, V4_1, V4_2, V4_3, V4_4];
+const V3_UP: &[u32] = &[V3_0, V4_0, V4_1, V4_2, V4_3, V4_4];
+const V4_UP: &[u32] = &[V4_0, V4_1, V4_2, V4_3, V4_4];
+
+const fn field(name: &'static str, field_type: FieldType) -> FieldSchema {
+    FieldSchema { name, field_type }
+}
+
+const METADATA: &[FieldSchema] = &[field("metadata", FieldType::Map)];
+
+/// Every message, in the order of the [`MessageKind`] variants.
+pub const MESSAGES: &[MessageSchema] = &[
+    MessageSchema {
+        kind: MessageKind::Init,
+        name: "INIT",
+        signature: SIGNATURE_INIT,
+        direction: Direction::Request,
+        fields: &[
+            field("user_agent", FieldType::String),
+            field("auth_token", FieldType::Map),
+        ],
+        versions: V1_TO_V2,
+    },
+    MessageSchema {
+        kind: MessageKind::Run,
+        name: "RUN",
+        signature: SIGNATURE_RUN,
+        direction: Direction::Request,
+        fields: &[
+            field("query", FieldType::String),
+            field("parameters", FieldType::Map),
+        ],
+        versions: V1_TO_V2,
+    },
+    MessageSchema {
+        kind: MessageKind::DiscardAll,
+        name: "DISCARD_ALL",
+        signature: SIGNATURE_DISCARD_ALL,
+        direction: Direction::Request,
+        fields: &[],
+        versions: V1_TO_V3,
+    },
+    MessageSchema {
+        kind: MessageKind::PullAll,
+        name: "PULL_ALL",
+        signature: SIGNATURE_PULL_ALL,
+        direction: Direction::Request,
+        fields: &[],
+        versions: V1_TO_V3,
+    },
+    MessageSchema {
+        kind: MessageKind::AckFailure,
+        name: "ACK_FAILURE",
+        signature: SIGNATURE_ACK_FAILURE,
+        direction: Direction::Request,
+        fields: &[],
+        versions: V1_TO_V2,
+    },
+    MessageSchema {
+        kind: MessageKind::Reset,
+        name: "RESET",
+        signature: SIGNATURE_RESET,
+        direction: Direction::Request,
+        fields: &[],
+        versions: VERSIONS,
+    },
+    MessageSchema {
+        kind: MessageKind::Record,
+        name: "RECORD",
+        signature: SIGNATURE_RECORD,
+        direction: Direction::Response,
+        fields: &[field("fields", FieldType::List(&FieldType::Any))],
+        versions: VERSIONS,
+    },
+    MessageSchema {
+        kind: MessageKind::Success,
+        name: "SUCCESS",
+        signature: SIGNATURE_SUCCESS,
+        direction: Direction::Response,
+        fields: METADATA,
+        versions: VERSIONS,
+    },
+    MessageSchema {
+        kind: MessageKind::Failure,
+        name: "FAILURE",
+        signature: SIGNATURE_FAILURE,
+        direction: Direction::Response,
+        fields: METADATA,
+        versions: VERSIONS,
+    },
+    MessageSchema {
+        kind: MessageKind::Ignored,
+        name: "IGNORED",
+        signature: SIGNATURE_IGNORED,
+        direction: Direction::Response,
+        fields: &[],
+        versions: VERSIONS,
+    },
+    MessageSchema {
+        kind: MessageKind::Hello,
+        name: "HELLO",
+        signature: SIGNATURE_HELLO,
+        direction: Direction::Request,
+        fields: METADATA,
+        versions: V3_UP,
+    },
+    MessageSchema {
+        kind: MessageKind::Goodbye,
+        name: "GOODBYE",
+        signature: SIGNATURE_GOODBYE,
+        direction: Direction::Request,
+        fields: &[],
+        versions: V3_UP,
+    },
+    MessageSchema {
+        kind: MessageKind::RunWithMetadata,
+        name: "RUN",
+        signature: SIGNATURE_RUN_WITH_METADATA,
+        direction: Direction::Request,
+        fields: &[
+            field("query", FieldType::String),
+            field("parameters", FieldType::Map),
+            field("metadata", FieldType::Map),
+        ],
+        versions: V3_UP,
+    },
+    MessageSchema {
+        kind: MessageKind::Begin,
+        name: "BEGIN",
+        signature: SIGNATURE_BEGIN,
+        direction: Direction::Request,
+        fields: METADATA,
+        versions: V3_UP,
+    },
+    MessageSchema {
+        kind: MessageKind::Commit,
+        name: "COMMIT",
+        signature: SIGNATURE_COMMIT,
+        direction: Direction::Request,
+        fields: &[],
+        versions: V3_UP,
+    },
+    MessageSchema {
+        kind: MessageKind::Rollback,
+        name: "ROLLBACK",
+        signature: SIGNATURE_ROLLBACK,
+        direction: Direction::Request,
+        fields: &[],
+        versions: V3_UP,
+    },
+    MessageSchema {
+        kind: MessageKind::Discard,
+        name: "DISCARD",
+        signature: SIGNATURE_DISCARD,
+        direction: Direction::Request,
+        fields: METADATA,
+        versions: V4_UP,
+    },
+    MessageSchema {
+        kind: MessageKind::Pull,
+        name: "PULL",
+        signature: SIGNATURE_PULL,
+        direction: Direction::Request,
+        fields: METADATA,
+        versions: V4_UP,
+    },
+    MessageSchema {
+        kind: MessageKind::Route,
+        name: "ROUTE",
+        signature: SIGNATURE_ROUTE,
+        direction: Direction::Request,
+        fields: &[
+            field("context", FieldType::Map),
+            field("bookmarks", FieldType::List(&FieldType::String)),
+            field("database", FieldType::Nullable(&FieldType::String)),
+        ],
+        versions: &[V4_3],
+    },
+    MessageSchema {
+        kind: MessageKind::RouteWithMetadata,
+        name: "ROUTE",
+        signature: SIGNATURE_ROUTE,
+        direction: Direction::Request,
+        fields: &[
+            field("context", FieldType::Map),
+            field("bookmarks", FieldType::List(&FieldType::String)),
+            field("metadata", FieldType::Map),
+        ],
+        versions: &[V4_4],
+    },
+];
+
+/// Every structure that can be sent as a value.
+pub const STRUCTURES: &[StructureSchema] = &[
+    StructureSchema {
+        name: "Node",
+        signature: SIGNATURE_NODE,
+        fields: &[
+            field("id", FieldType::Integer),
+            field("labels", FieldType::List(&FieldType::String)),
+            field("properties", FieldType::Map),
+        ],
+        versions: VERSIONS,
+    },
+    StructureSchema {
+        name: "Relationship",
+        signature: SIGNATURE_RELATIONSHIP,
+        fields: &[
+            field("id", FieldType::Integer),
+            field("startNodeId", FieldType::Integer),
+            field("endNodeId", FieldType::Integer),
+            field("type", FieldType::String),
+            field("properties", FieldType::Map),
+        ],
+        versions: VERSIONS,
+    },
+    StructureSchema {
+        name: "UnboundRelationship",
+        signature: SIGNATURE_UNBOUND_RELATIONSHIP,
+        fields: &[
+            field("id", FieldType::Integer),
+            field("type", FieldType::String),
+            field("properties", FieldType::Map),
+        ],
+        versions: VERSIONS,
+    },
+    StructureSchema {
+        name: "Path",
+        signature: SIGNATURE_PATH,
+        fields: &[
+            field(
+                "nodes",
+                FieldType::List(&FieldType::Structure(SIGNATURE_NODE)),
+            ),
+            field(
+                "rels",
+                FieldType::List(&FieldType::Structure(SIGNATURE_UNBOUND_RELATIONSHIP)),
+            ),
+            field("indices", FieldType::List(&FieldType::Integer)),
+        ],
+        versions: VERSIONS,
+    },
+    StructureSchema {
+        name: "Date",
+        signature: SIGNATURE_DATE,
+        fields: &[field("days", FieldType::Integer)],
+        versions: V2_UP,
+    },
+    StructureSchema {
+        name: "Time",
+        signature: SIGNATURE_TIME,
+        fields: &[
+            field("nanoseconds", FieldType::Integer),
+            field("tz_offset_seconds", FieldType::Integer),
+        ],
+        versions: V2_UP,
+    },
+    StructureSchema {
+        name: "DateTime",
+        signature: SIGNATURE_DATE_TIME_OFFSET,
+        fields: &[
+            field("seconds", FieldType::Integer),
+            field("nanoseconds", FieldType::Integer),
+            field("tz_offset_seconds", FieldType::Integer),
+        ],
+        versions: V2_UP,
+    },
+    StructureSchema {
+        name: "DateTimeZoneId",
+        signature: SIGNATURE_DATE_TIME_ZONED,
+        fields: &[
+            field("seconds", FieldType::Integer),
+            field("nanoseconds", FieldType::Integer),
+            field("tz_id", FieldType::String),
+        ],
+        versions: V2_UP,
+    },
+    StructureSchema {
+        name: "LocalTime",
+        signature: SIGNATURE_LOCAL_TIME,
+        fields: &[field("nanoseconds", FieldType::Integer)],
+        versions: V2_UP,
+    },
+    StructureSchema {
+        name: "LocalDateTime",
+        signature: SIGNATURE_LOCAL_DATE_TIME,
+        fields: &[
+            field("seconds", FieldType::Integer),
+            field("nanoseconds", FieldType::Integer),
+        ],
+        versions: V2_UP,
+    },
+    StructureSchema {
+        name: "Duration",
+        signature: SIGNATURE_DURATION,
+        fields: &[
+            field("months", FieldType::Integer),
+            field("days", FieldType::Integer),
+            field("seconds", FieldType::Integer),
+            field("nanoseconds", FieldType::Integer),
+        ],
+        versions: V2_UP,
+    },
+    StructureSchema {
+        name: "Point2D",
+        signature: SIGNATURE_POINT_2D,
+        fields: &[
+            field("srid", FieldType::Integer),
+            field("x", FieldType::Float),
+            field("y", FieldType::Float),
+        ],
+        versions: V2_UP,
+    },
+    StructureSchema {
+        name: "Point3D",
+        signature: SIGNATURE_POINT_3D,
+        fields: &[
+            field("srid", FieldType::Integer),
+            field("x", FieldType::Float),
+            field("y", FieldType::Float),
+            field("z", FieldType::Float),
+        ],
+        versions: V2_UP,
+    },
+];
+
+/// Get the description of a kind of message.
+pub fn message(kind: MessageKind) -> &'static MessageSchema {
+    MESSAGES
+        .iter()
+        .find(|schema| schema.kind == kind)
+        .expect("every message kind is described")
+}
+
+/// Get the description of the structure with a signature, if it is one of the [`STRUCTURES`].
+pub fn structure(signature: u8) -> Option<&'static StructureSchema> {
+    STRUCTURES
+        .iter()
+        .find(|schema| schema.signature == signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+
+    use crate::{serialization::BoltValue, Message, Value};
+
+    use super::*;
+
+    // The marker and signature bytes at the start of a serialized structure
+    fn header(bytes: &[u8]) -> (usize, u8) {
+        ((bytes[0] & 0x0F) as usize, bytes[1])
+    }
+
+    #[test]
+    fn messages_match_serialization() {
+        let map = HashMap::new;
+        let messages = [
+            Message::Init(Init::new(String::new(), map())),
+            Message::Run(Run::new(String::new(), map())),
+            Message::DiscardAll,
+            Message::PullAll,
+            Message::AckFailure,
+            Message::Reset,
+            Message::Record(Record::new(vec![])),
+            Message::Success(Success::new(map())),
+            Message::Failure(Failure::new(map())),
+            Message::Ignored,
+            Message::Hello(Hello::new(map())),
+            Message::Goodbye,
+            Message::RunWithMetadata(RunWithMetadata::new(String::new(), map(), map())),
+            Message::Begin(Begin::new(map())),
+            Message::Commit,
+            Message::Rollback,
+            Message::Discard(Discard::new(map())),
+            Message::Pull(Pull::new(map())),
+            Message::Route(Route::new(map(), vec![], None)),
+            Message::RouteWithMetadata(RouteWithMetadata::new(map(), vec![], map())),
+        ];
+        assert_eq!(messages.len(), MESSAGES.len());
+        for (message, schema) in messages.into_iter().zip(MESSAGES) {
+            assert_eq!(message.kind(), schema.kind);
+            assert_eq!(message.name(), schema.name);
+            let bytes = message.serialize().unwrap();
+            assert_eq!(header(&bytes), (schema.fields.len(), schema.signature));
+        }
+    }
+
+    #[test]
+    fn structures_match_serialization() {
+        let date_time = NaiveDate::from_ymd_opt(2000, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let offset = FixedOffset::east_opt(3600).unwrap();
+        let properties = HashMap::<String, Value>::new;
+        let values = [
+            Value::Node(Node::new(0, vec![], properties())),
+            Value::Relationship(Relationship::new(0, 0, 0, String::new(), properties())),
+            Value::UnboundRelationship(UnboundRelationship::new(0, String::new(), properties())),
+            Value::Path(Path::new(vec![], vec![], vec![])),
+            Value::Date(date_time.date()),
+            Value::from((NaiveTime::MIN, offset)),
+            Value::from(offset.from_utc_datetime(&date_time)),
+            Value::from((date_time, chrono_tz::UTC)),
+            Value::LocalTime(NaiveTime::MIN),
+            Value::LocalDateTime(NaiveDateTime::default()),
+            Value::Duration(Duration::new(0, 0, 0, 0)),
+            Value::Point2D(Point2D::new(0, 0.0, 0.0)),
+            Value::Point3D(Point3D::new(0, 0.0, 0.0, 0.0)),
+        ];
+        assert_eq!(values.len(), STRUCTURES.len());
+        for (value, schema) in values.into_iter().zip(STRUCTURES) {
+            let bytes = value.serialize().unwrap();
+            assert_eq!(header(&bytes), (schema.fields.len(), schema.signature));
+            assert_eq!(structure(schema.signature), Some(schema));
+        }
+        assert_eq!(structure(0x00), None);
+    }
+}
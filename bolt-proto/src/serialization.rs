@@ -1,25 +1,112 @@
-use std::panic::UnwindSafe;
+use core::{mem, panic::UnwindSafe};
 
 use bytes::{Buf, Bytes};
 
 use crate::{
     error::{DeserializationError, DeserializeResult, SerializeResult},
-    value::{MARKER_MEDIUM_STRUCT, MARKER_SMALL_STRUCT, MARKER_TINY_STRUCT},
+    value::{Utf8Mode, MARKER_MEDIUM_STRUCT, MARKER_SMALL_STRUCT, MARKER_TINY_STRUCT},
 };
 
+/// Settings for deserializing values, passed down to every nested value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct DecodeConfig {
+    pub(crate) utf8_mode: Utf8Mode,
+    pub(crate) max_value_size: Option<usize>,
+    pub(crate) float_arrays: bool,
+}
+
+impl DecodeConfig {
+    /// Check the declared size of a value against the limit, before anything is allocated for it.
+    pub(crate) fn check_value_size(&self, size: usize) -> DeserializeResult<()> {
+        match self.max_value_size {
+            Some(max) if size > max => Err(DeserializationError::ValueTooLarge { size, max }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Get the settings for deserializing a map key. Map keys can only be strings, so they are
+    /// decoded lossily instead of as bytes in [`Utf8Mode::Bytes`].
+    pub(crate) fn for_map_key(&self) -> Self {
+        match self.utf8_mode {
+            Utf8Mode::Bytes => Self {
+                utf8_mode: Utf8Mode::Lossy,
+                ..*self
+            },
+            _ => *self,
+        }
+    }
+}
+
 pub(crate) trait BoltValue: Sized {
     fn marker(&self) -> SerializeResult<u8>;
 
     fn serialize(self) -> SerializeResult<Bytes>;
 
-    fn deserialize<B: Buf + UnwindSafe>(bytes: B) -> DeserializeResult<(Self, B)>;
+    #[cfg(test)]
+    fn deserialize<B: Buf + UnwindSafe>(bytes: B) -> DeserializeResult<(Self, B)> {
+        Self::deserialize_with(bytes, &DecodeConfig::default())
+    }
+
+    fn deserialize_with<B: Buf + UnwindSafe>(
+        bytes: B,
+        config: &DecodeConfig,
+    ) -> DeserializeResult<(Self, B)>;
 }
 
 pub(crate) trait BoltStructure: BoltValue {
     fn signature(&self) -> u8;
 }
 
-/// Returns size and signature. Might panic - use this inside a catch_unwind block
+macro_rules! checked_reads {
+    ($($name:ident => $get:ident -> $ty:ty),* $(,)?) => {
+        $(
+            fn $name(&mut self) -> DeserializeResult<$ty> {
+                self.ensure(mem::size_of::<$ty>())?;
+                Ok(self.$get())
+            }
+        )*
+    };
+}
+
+/// Reads that return an [`UnexpectedEnd`](DeserializationError::UnexpectedEnd) error instead of
+/// panicking when the input is too short, so untrusted input can be deserialized without relying
+/// on `catch_unwind`.
+pub(crate) trait CheckedBuf: Buf {
+    fn ensure(&self, needed: usize) -> DeserializeResult<()> {
+        match self.remaining() {
+            remaining if remaining < needed => {
+                Err(DeserializationError::UnexpectedEnd { needed, remaining })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    checked_reads! {
+        read_u8 => get_u8 -> u8,
+        read_u16 => get_u16 -> u16,
+        read_u32 => get_u32 -> u32,
+        read_i8 => get_i8 -> i8,
+        read_i16 => get_i16 -> i16,
+        read_i32 => get_i32 -> i32,
+        read_i64 => get_i64 -> i64,
+        read_f64 => get_f64 -> f64,
+    }
+
+    fn read_bytes(&mut self, len: usize) -> DeserializeResult<Bytes> {
+        self.ensure(len)?;
+        Ok(self.copy_to_bytes(len))
+    }
+
+    fn skip(&mut self, len: usize) -> DeserializeResult<()> {
+        self.ensure(len)?;
+        self.advance(len);
+        Ok(())
+    }
+}
+
+impl<B: Buf + ?Sized> CheckedBuf for B {}
+
+/// Returns size and signature.
 pub(crate) fn get_structure_info(
     marker: u8,
     bytes: &mut impl Buf,
@@ -28,10 +115,105 @@ pub(crate) fn get_structure_info(
         marker if (MARKER_TINY_STRUCT..=(MARKER_TINY_STRUCT | 0x0F)).contains(&marker) => {
             0x0F & marker as usize
         }
-        MARKER_SMALL_STRUCT => bytes.get_u8() as usize,
-        MARKER_MEDIUM_STRUCT => bytes.get_u16() as usize,
+        MARKER_SMALL_STRUCT => bytes.read_u8()? as usize,
+        MARKER_MEDIUM_STRUCT => bytes.read_u16()? as usize,
         _ => return Err(DeserializationError::InvalidMarkerByte(marker)),
     };
-    let signature = bytes.get_u8();
+    let signature = bytes.read_u8()?;
     Ok((size, signature))
 }
+
+/// Run a deserializer, converting any panic into an error. Reads are checked, so this only guards
+/// against bugs in deserializers. Without the `std` feature, panics cannot be caught and are left
+/// to propagate.
+pub(crate) fn catch_panic<T>(
+    deserialize: impl FnOnce() -> DeserializeResult<T> + UnwindSafe,
+) -> DeserializeResult<T> {
+    #[cfg(feature = "std")]
+    {
+        std::panic::catch_unwind(deserialize).map_err(|_| DeserializationError::Panicked)?
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        deserialize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bolt_proto_derive::*;
+
+    use crate::{error::ConversionError, Value};
+
+    use super::*;
+
+    #[bolt_structure(0x01)]
+    #[derive(Debug, PartialEq)]
+    struct Tuple(i64, String);
+
+    #[bolt_structure(0x02)]
+    #[derive(Debug, PartialEq)]
+    struct Attributes {
+        name: String,
+        #[bolt(skip)]
+        cached_len: usize,
+        #[bolt(default)]
+        labels: Vec<String>,
+    }
+
+    #[bolt_structure(0x03)]
+    #[derive(Debug, PartialEq)]
+    struct Generic<T>
+    where
+        T: Into<Value> + TryFrom<Value, Error = ConversionError>,
+    {
+        value: T,
+    }
+
+    #[bolt_structure(0x04)]
+    #[derive(Debug, PartialEq)]
+    struct Unit;
+
+    #[test]
+    fn tuple_struct() {
+        let bytes = Tuple(1, String::from("a")).serialize().unwrap();
+        assert_eq!(&bytes[..], &[0xB2, 0x01, 0x01, 0x81, b'a']);
+        let (value, remaining) = Tuple::deserialize(&bytes[2..]).unwrap();
+        assert_eq!(value, Tuple(1, String::from("a")));
+        assert!(remaining.is_empty());
+    }
+
+    #[test]
+    fn field_attributes() {
+        let value = Attributes {
+            name: String::from("a"),
+            cached_len: 1,
+            labels: vec![String::from("b")],
+        };
+        let bytes = value.serialize().unwrap();
+        // The skipped field is not counted or serialized
+        assert_eq!(&bytes[..4], &[0xB2, 0x02, 0x81, b'a']);
+        let (value, _) = Attributes::deserialize(&bytes[2..]).unwrap();
+        assert_eq!(value.cached_len, 0);
+        assert_eq!(value.labels, vec![String::from("b")]);
+
+        // Null values are replaced with the default for fields marked as default
+        let (value, _) = Attributes::deserialize(&[0x81, b'a', 0xC0][..]).unwrap();
+        assert!(value.labels.is_empty());
+    }
+
+    #[test]
+    fn generic_struct() {
+        let bytes = Generic { value: 5_i64 }.serialize().unwrap();
+        assert_eq!(&bytes[..], &[0xB1, 0x03, 0x05]);
+        let (value, _) = Generic::<i64>::deserialize(&bytes[2..]).unwrap();
+        assert_eq!(value.value, 5);
+        assert!(Generic::<i64>::deserialize(&[0x81, b'a'][..]).is_err());
+    }
+
+    #[test]
+    fn unit_struct() {
+        assert_eq!(&Unit.serialize().unwrap()[..], &[0xB0, 0x04]);
+        assert_eq!(Unit::deserialize(&[][..]).unwrap().0, Unit);
+    }
+}
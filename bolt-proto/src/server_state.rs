@@ -1,3 +1,12 @@
+use core::{fmt, str::FromStr};
+
+use crate::{
+    error::ConversionError,
+    message::{MessageKind, MessageKind::*},
+    prelude::*,
+    Message, Value,
+};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ServerState {
     Disconnected,
@@ -10,3 +19,247 @@ pub enum ServerState {
     Failed,
     Interrupted,
 }
+
+impl ServerState {
+    /// All server states, in the order they are declared.
+    pub const ALL: [ServerState; 9] = [
+        ServerState::Disconnected,
+        ServerState::Connected,
+        ServerState::Defunct,
+        ServerState::Ready,
+        ServerState::Streaming,
+        ServerState::TxReady,
+        ServerState::TxStreaming,
+        ServerState::Failed,
+        ServerState::Interrupted,
+    ];
+
+    /// Get the name of this state, as used in the protocol documentation (e.g. `"TX_READY"`).
+    pub fn name(&self) -> &'static str {
+        match self {
+            ServerState::Disconnected => "DISCONNECTED",
+            ServerState::Connected => "CONNECTED",
+            ServerState::Defunct => "DEFUNCT",
+            ServerState::Ready => "READY",
+            ServerState::Streaming => "STREAMING",
+            ServerState::TxReady => "TX_READY",
+            ServerState::TxStreaming => "TX_STREAMING",
+            ServerState::Failed => "FAILED",
+            ServerState::Interrupted => "INTERRUPTED",
+        }
+    }
+
+    /// Get the kinds of requests the server accepts in this state. Any other request makes the
+    /// server close the connection.
+    pub fn valid_requests(&self) -> &'static [MessageKind] {
+        match self {
+            ServerState::Disconnected | ServerState::Defunct => &[],
+            ServerState::Connected => &[Init, Hello],
+            ServerState::Ready => &[
+                Run,
+                RunWithMetadata,
+                Begin,
+                Route,
+                RouteWithMetadata,
+                Reset,
+                Goodbye,
+            ],
+            ServerState::Streaming => &[PullAll, Pull, DiscardAll, Discard, Reset, Goodbye],
+            ServerState::TxReady => &[RunWithMetadata, Commit, Rollback, Reset, Goodbye],
+            ServerState::TxStreaming => &[
+                RunWithMetadata,
+                PullAll,
+                Pull,
+                DiscardAll,
+                Discard,
+                Reset,
+                Goodbye,
+            ],
+            ServerState::Failed => &[
+                Run,
+                RunWithMetadata,
+                PullAll,
+                Pull,
+                DiscardAll,
+                Discard,
+                AckFailure,
+                Reset,
+                Goodbye,
+            ],
+            ServerState::Interrupted => &[
+                Run,
+                RunWithMetadata,
+                PullAll,
+                Pull,
+                DiscardAll,
+                Discard,
+                AckFailure,
+                Begin,
+                Commit,
+                Rollback,
+                Reset,
+                Goodbye,
+            ],
+        }
+    }
+
+    /// Get the state the server moves to from this state after sending the given response to the
+    /// given request, or [`None`] if the server can't send that response in this state.
+    ///
+    /// A `SUCCESS` in response to `PULL` or `DISCARD` leaves the server streaming if its
+    /// `has_more` entry is `true`. In a transaction, the server only returns to
+    /// [`TxReady`](ServerState::TxReady) once every open result has been consumed, which this
+    /// function can't track, so it assumes the consumed result was the only one. While
+    /// [`Interrupted`](ServerState::Interrupted), any response to a request other than `RESET`
+    /// is treated as `IGNORED`.
+    ///
+    /// ```
+    /// # use bolt_proto::{message::Success, Message, ServerState};
+    /// let success = Message::Success(Success::new(Default::default()));
+    /// let state = ServerState::Ready.transition(&Message::Commit, &success);
+    /// assert_eq!(state, None);
+    /// let state = ServerState::TxReady.transition(&Message::Commit, &success);
+    /// assert_eq!(state, Some(ServerState::Ready));
+    /// ```
+    pub fn transition(self, request: &Message, response: &Message) -> Option<ServerState> {
+        use ServerState::*;
+
+        let has_more = match response {
+            Message::Success(success) => {
+                success.metadata().get("has_more") == Some(&Value::Boolean(true))
+            }
+            _ => false,
+        };
+        let next = match (self, request.kind(), response.kind()) {
+            (Connected, Init | Hello, Success) => Ready,
+            (Connected, Init | Hello, Failure) => Defunct,
+
+            (Ready, Run | RunWithMetadata, Success) => Streaming,
+            (Ready, Begin, Success) => TxReady,
+            (Ready, Route | RouteWithMetadata, Success) => Ready,
+            (Ready, Run | RunWithMetadata | Begin | Route | RouteWithMetadata, Failure) => Failed,
+
+            (Streaming, PullAll | Pull, Record) => Streaming,
+            (Streaming, PullAll | DiscardAll, Success) => Ready,
+            (Streaming, Pull | Discard, Success) if has_more => Streaming,
+            (Streaming, Pull | Discard, Success) => Ready,
+            (Streaming, PullAll | Pull | DiscardAll | Discard, Failure) => Failed,
+
+            (TxReady | TxStreaming, RunWithMetadata, Success) => TxStreaming,
+            (TxReady | TxStreaming, RunWithMetadata, Failure) => Failed,
+            (TxReady, Commit | Rollback, Success) => Ready,
+            (TxReady, Commit | Rollback, Failure) => Failed,
+
+            (TxStreaming, PullAll | Pull, Record) => TxStreaming,
+            (TxStreaming, PullAll | DiscardAll, Success) => TxReady,
+            (TxStreaming, Pull | Discard, Success) if has_more => TxStreaming,
+            (TxStreaming, Pull | Discard, Success) => TxReady,
+            (TxStreaming, PullAll | Pull | DiscardAll | Discard, Failure) => Failed,
+
+            (
+                Failed,
+                Run | RunWithMetadata | PullAll | Pull | DiscardAll | Discard | Route
+                | RouteWithMetadata,
+                Ignored,
+            ) => Failed,
+            (Failed, AckFailure, Success) => Ready,
+            (Failed, AckFailure, Failure) => Defunct,
+
+            (Interrupted, Reset, Success) => Ready,
+            (Interrupted, Reset, Failure) => Defunct,
+            (
+                Interrupted,
+                Run | RunWithMetadata | PullAll | Pull | DiscardAll | Discard | Begin | Commit
+                | Rollback | AckFailure | Route | RouteWithMetadata,
+                _,
+            ) => Interrupted,
+
+            _ => return None,
+        };
+        Some(next)
+    }
+}
+
+impl fmt::Display for ServerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ServerState {
+    type Err = ConversionError;
+
+    /// Parse a state from its [name](ServerState::name), ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ServerState::ALL
+            .into_iter()
+            .find(|state| state.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ConversionError::InvalidServerState(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        collections::HashMap,
+        message::{self, Failure, Record, Success},
+    };
+
+    use super::{ServerState::*, *};
+
+    fn success(has_more: bool) -> Message {
+        Message::Success(Success::new(HashMap::from_iter([(
+            String::from("has_more"),
+            Value::from(has_more),
+        )])))
+    }
+
+    #[test]
+    fn display_and_parse() {
+        for state in ServerState::ALL {
+            assert_eq!(state.to_string().parse::<ServerState>().unwrap(), state);
+        }
+        assert_eq!(TxStreaming.to_string(), "TX_STREAMING");
+        assert_eq!("tx_ready".parse::<ServerState>().unwrap(), TxReady);
+        assert!(matches!(
+            "TxReady".parse::<ServerState>(),
+            Err(ConversionError::InvalidServerState(s)) if s == "TxReady"
+        ));
+    }
+
+    #[test]
+    fn transition() {
+        let pull = Message::Pull(message::Pull::new(HashMap::default()));
+        let record = Message::Record(Record::new(vec![]));
+        let failure = Message::Failure(Failure::new(HashMap::default()));
+
+        assert_eq!(Streaming.transition(&pull, &record), Some(Streaming));
+        assert_eq!(Streaming.transition(&pull, &success(true)), Some(Streaming));
+        assert_eq!(Streaming.transition(&pull, &success(false)), Some(Ready));
+        assert_eq!(
+            TxStreaming.transition(&pull, &success(false)),
+            Some(TxReady)
+        );
+        assert_eq!(Streaming.transition(&pull, &failure), Some(Failed));
+        assert_eq!(Failed.transition(&pull, &Message::Ignored), Some(Failed));
+        assert_eq!(Interrupted.transition(&pull, &record), Some(Interrupted));
+        assert_eq!(
+            Interrupted.transition(&Message::Reset, &success(false)),
+            Some(Ready)
+        );
+        assert_eq!(Interrupted.transition(&Message::Reset, &record), None);
+        assert_eq!(Ready.transition(&pull, &success(false)), None);
+        assert_eq!(Streaming.transition(&pull, &Message::Ignored), None);
+    }
+
+    #[test]
+    fn valid_requests() {
+        assert_eq!(Connected.valid_requests(), [Init, Hello]);
+        assert!(Defunct.valid_requests().is_empty());
+        for state in [Ready, Streaming, TxReady, TxStreaming, Failed, Interrupted] {
+            assert!(state.valid_requests().contains(&Reset));
+            assert!(state.valid_requests().contains(&Goodbye));
+        }
+        assert!(!TxReady.valid_requests().contains(&Begin));
+    }
+}
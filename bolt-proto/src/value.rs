@@ -1,8 +1,4 @@
-use std::{
-    collections::HashMap,
-    mem,
-    panic::{catch_unwind, UnwindSafe},
-};
+use core::{mem, panic::UnwindSafe};
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use chrono::{
@@ -18,17 +14,36 @@ pub use point_3d::Point3D;
 pub use relationship::Relationship;
 pub use unbound_relationship::UnboundRelationship;
 
+pub use custom::StructureDeserializer;
+#[cfg(feature = "std")]
+pub use custom::{register_structure, unregister_structure};
+#[cfg(feature = "serde")]
+pub use de::{datetime_utc, datetime_utc_option, from_value, from_value_with, TemporalFormat};
+pub use diff::Difference;
+pub use utf8::Utf8Mode;
+
+use crate::collections::HashMap;
 use crate::error::*;
+use crate::prelude::*;
 use crate::serialization::*;
 
+pub(crate) mod access;
 pub(crate) mod conversions;
+pub(crate) mod custom;
+#[cfg(feature = "serde")]
+pub(crate) mod de;
+pub(crate) mod diff;
 pub(crate) mod duration;
 pub(crate) mod node;
 pub(crate) mod path;
 pub(crate) mod point_2d;
 pub(crate) mod point_3d;
 pub(crate) mod relationship;
+#[cfg(feature = "serde")]
+pub(crate) mod ser;
+pub(crate) mod size;
 pub(crate) mod unbound_relationship;
+pub(crate) mod utf8;
 
 pub(crate) const MARKER_FALSE: u8 = 0xC2;
 pub(crate) const MARKER_TRUE: u8 = 0xC3;
@@ -79,7 +94,16 @@ pub(crate) const SIGNATURE_POINT_3D: u8 = 0x59;
 /// such types, conversions are still provided, but may feel a bit clunky (for example, you can
 /// convert a `(`[`NaiveTime`](chrono::NaiveTime)`, impl `[`Offset`](chrono::Offset)`)` tuple into
 /// a [`Value::Time`]).
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Bolt only allows strings as map keys, so only maps with keys that convert into a [`String`] can
+/// be converted into a [`Value::Map`]. Other maps are rejected at compile time:
+/// ```compile_fail
+/// # use std::collections::HashMap;
+/// # use bolt_proto::Value;
+/// let value = Value::from(HashMap::from([(1, "one")]));
+/// ```
+/// Maps received from a server with non-string keys fail to deserialize.
+#[derive(Debug, Clone)]
 pub enum Value {
     // V1-compatible value types
     Boolean(bool),
@@ -105,16 +129,71 @@ pub enum Value {
     Duration(Duration),
     Point2D(Point2D),
     Point3D(Point3D),
+
+    /// A structure with a signature that is not part of the protocol, holding its signature and
+    /// fields. See [`register_structure`] for converting these into other values.
+    ///
+    /// Zoned date-times with a time zone ID that isn't in the
+    /// [time zone database](https://docs.rs/chrono-tz), or that are out of range, are also kept
+    /// as structures with signature `0x66` and their epoch seconds, nanoseconds, and time zone ID
+    /// as fields.
+    Custom(u8, Vec<Value>),
+
+    /// A list of floats, e.g. an embedding vector, stored without wrapping each float in a
+    /// [`Value`]. None of the supported protocol versions has a dedicated type for vectors, so it is
+    /// serialized as a list of floats and servers receive it as a `LIST<FLOAT>`.
+    ///
+    /// Lists received from a server are only deserialized as float arrays if enabled with
+    /// [`MessageDecoder::with_float_arrays`](crate::message::MessageDecoder::with_float_arrays).
+    /// Otherwise they are [`Value::List`]s, which convert into [`Vec<f64>`] and [`Vec<f32>`] all
+    /// the same. A float array is equal to a [`Value::List`] of the same floats, so values compare
+    /// the same whether or not float arrays are enabled.
+    FloatArray(Vec<f64>),
 }
 
-impl Eq for Value {
-    fn assert_receiver_is_total_eq(&self) {
-        if let Value::Float(_) | Value::Point2D(_) | Value::Point3D(_) = self {
-            panic!("{:?} does not impl Eq", self)
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Integer(a), Value::Integer(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => a == b,
+            (Value::Null, Value::Null) => true,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Node(a), Value::Node(b)) => a == b,
+            (Value::Relationship(a), Value::Relationship(b)) => a == b,
+            (Value::Path(a), Value::Path(b)) => a == b,
+            (Value::UnboundRelationship(a), Value::UnboundRelationship(b)) => a == b,
+            (Value::Date(a), Value::Date(b)) => a == b,
+            (Value::Time(a, a_offset), Value::Time(b, b_offset)) => a == b && a_offset == b_offset,
+            (Value::DateTimeOffset(a), Value::DateTimeOffset(b)) => a == b,
+            (Value::DateTimeZoned(a), Value::DateTimeZoned(b)) => a == b,
+            (Value::LocalTime(a), Value::LocalTime(b)) => a == b,
+            (Value::LocalDateTime(a), Value::LocalDateTime(b)) => a == b,
+            (Value::Duration(a), Value::Duration(b)) => a == b,
+            (Value::Point2D(a), Value::Point2D(b)) => a == b,
+            (Value::Point3D(a), Value::Point3D(b)) => a == b,
+            (Value::Custom(a, a_fields), Value::Custom(b, b_fields)) => {
+                a == b && a_fields == b_fields
+            }
+            (Value::FloatArray(a), Value::FloatArray(b)) => a == b,
+            (Value::FloatArray(floats), Value::List(list))
+            | (Value::List(list), Value::FloatArray(floats)) => {
+                floats.len() == list.len()
+                    && floats
+                        .iter()
+                        .zip(list)
+                        .all(|(float, value)| matches!(value, Value::Float(v) if v == float))
+            }
+            _ => false,
         }
     }
 }
 
+impl Eq for Value {}
+
 impl BoltValue for Value {
     fn marker(&self) -> SerializeResult<u8> {
         match self {
@@ -135,13 +214,8 @@ impl BoltValue for Value {
                 65_536..=2_147_483_647 => Ok(MARKER_LARGE_BYTES),
                 _ => Err(SerializationError::ValueTooLarge(bytes.len())),
             },
-            Value::List(list) => match list.len() {
-                0..=15 => Ok(MARKER_TINY_LIST | list.len() as u8),
-                16..=255 => Ok(MARKER_SMALL_LIST),
-                256..=65_535 => Ok(MARKER_MEDIUM_LIST),
-                65_536..=4_294_967_295 => Ok(MARKER_LARGE_LIST),
-                len => Err(SerializationError::ValueTooLarge(len)),
-            },
+            Value::List(list) => list_marker(list.len()),
+            Value::FloatArray(floats) => list_marker(floats.len()),
             Value::Map(map) => match map.len() {
                 0..=15 => Ok(MARKER_TINY_MAP | map.len() as u8),
                 16..=255 => Ok(MARKER_SMALL_MAP),
@@ -170,6 +244,12 @@ impl BoltValue for Value {
             Value::Duration(duration) => duration.marker(),
             Value::Point2D(point_2d) => point_2d.marker(),
             Value::Point3D(point_3d) => point_3d.marker(),
+            Value::Custom(_, fields) => match fields.len() {
+                0..=15 => Ok(MARKER_TINY_STRUCT | fields.len() as u8),
+                16..=255 => Ok(MARKER_SMALL_STRUCT),
+                256..=65_535 => Ok(MARKER_MEDIUM_STRUCT),
+                len => Err(SerializationError::ValueTooLarge(len)),
+            },
         }
     }
 
@@ -259,6 +339,31 @@ impl BoltValue for Value {
 
                 Ok(bytes.freeze())
             }
+            Value::FloatArray(floats) => {
+                let length = floats.len();
+                // Marker byte, 32-bit size value, and a marker byte and 64-bit value per float
+                let mut bytes = BytesMut::with_capacity(
+                    mem::size_of::<u8>()
+                        + mem::size_of::<u32>()
+                        + length * (mem::size_of::<u8>() + mem::size_of::<f64>()),
+                );
+
+                bytes.put_u8(marker);
+                match length {
+                    0..=15 => {} // The marker contains the length
+                    16..=255 => bytes.put_u8(length as u8),
+                    256..=65_535 => bytes.put_u16(length as u16),
+                    65_536..=4_294_967_295 => bytes.put_u32(length as u32),
+                    _ => return Err(SerializationError::ValueTooLarge(length)),
+                }
+
+                for float in floats {
+                    bytes.put_u8(MARKER_FLOAT);
+                    bytes.put_f64(float);
+                }
+
+                Ok(bytes.freeze())
+            }
             Value::Map(map) => {
                 let length = map.len();
 
@@ -379,19 +484,54 @@ impl BoltValue for Value {
             Value::LocalDateTime(local_date_time) => Ok(vec![marker, SIGNATURE_LOCAL_DATE_TIME]
                 .into_iter()
                 // Seconds since UNIX epoch
-                .chain(Value::from(local_date_time.timestamp()).serialize()?)
+                .chain(Value::from(local_date_time.and_utc().timestamp()).serialize()?)
                 // Nanoseconds
                 .chain(Value::from(i64::from(local_date_time.nanosecond())).serialize()?)
                 .collect()),
             Value::Duration(duration) => duration.serialize(),
             Value::Point2D(point_2d) => point_2d.serialize(),
             Value::Point3D(point_3d) => point_3d.serialize(),
+            Value::Custom(signature, fields) => {
+                let length = fields.len();
+                let mut total_value_bytes: usize = 0;
+                let mut value_bytes_vec: Vec<Bytes> = Vec::with_capacity(length);
+
+                for value in fields {
+                    let value_bytes = value.serialize()?;
+                    total_value_bytes += value_bytes.len();
+                    value_bytes_vec.push(value_bytes);
+                }
+
+                // Worst case is a medium structure, with marker byte, 16-bit size value, signature
+                // byte, and all the Value bytes
+                let mut bytes = BytesMut::with_capacity(
+                    mem::size_of::<u8>() * 2 + mem::size_of::<u16>() + total_value_bytes,
+                );
+
+                bytes.put_u8(marker);
+                match length {
+                    0..=15 => {} // The marker contains the length
+                    16..=255 => bytes.put_u8(length as u8),
+                    256..=65_535 => bytes.put_u16(length as u16),
+                    _ => return Err(SerializationError::ValueTooLarge(length)),
+                }
+                bytes.put_u8(signature);
+
+                for value_bytes in value_bytes_vec {
+                    bytes.put(value_bytes);
+                }
+
+                Ok(bytes.freeze())
+            }
         }
     }
 
-    fn deserialize<B: Buf + UnwindSafe>(mut bytes: B) -> DeserializeResult<(Self, B)> {
-        catch_unwind(move || {
-            let marker = bytes.get_u8();
+    fn deserialize_with<B: Buf + UnwindSafe>(
+        mut bytes: B,
+        config: &DecodeConfig,
+    ) -> DeserializeResult<(Self, B)> {
+        catch_panic(move || {
+            let marker = bytes.read_u8()?;
             match marker {
                 // Boolean
                 MARKER_TRUE => Ok((Value::Boolean(true), bytes)),
@@ -401,21 +541,22 @@ impl BoltValue for Value {
                     Ok((Value::Integer(i64::from(marker as i8)), bytes))
                 }
                 // Other int types
-                MARKER_INT_8 => Ok((Value::Integer(i64::from(bytes.get_i8())), bytes)),
-                MARKER_INT_16 => Ok((Value::Integer(i64::from(bytes.get_i16())), bytes)),
-                MARKER_INT_32 => Ok((Value::Integer(i64::from(bytes.get_i32())), bytes)),
-                MARKER_INT_64 => Ok((Value::Integer(bytes.get_i64()), bytes)),
+                MARKER_INT_8 => Ok((Value::Integer(i64::from(bytes.read_i8()?)), bytes)),
+                MARKER_INT_16 => Ok((Value::Integer(i64::from(bytes.read_i16()?)), bytes)),
+                MARKER_INT_32 => Ok((Value::Integer(i64::from(bytes.read_i32()?)), bytes)),
+                MARKER_INT_64 => Ok((Value::Integer(bytes.read_i64()?), bytes)),
                 // Float
-                MARKER_FLOAT => Ok((Value::Float(bytes.get_f64()), bytes)),
+                MARKER_FLOAT => Ok((Value::Float(bytes.read_f64()?), bytes)),
                 // Byte array
                 MARKER_SMALL_BYTES | MARKER_MEDIUM_BYTES | MARKER_LARGE_BYTES => {
                     let size = match marker {
-                        MARKER_SMALL_BYTES => bytes.get_u8() as usize,
-                        MARKER_MEDIUM_BYTES => bytes.get_u16() as usize,
-                        MARKER_LARGE_BYTES => bytes.get_u32() as usize,
+                        MARKER_SMALL_BYTES => bytes.read_u8()? as usize,
+                        MARKER_MEDIUM_BYTES => bytes.read_u16()? as usize,
+                        MARKER_LARGE_BYTES => bytes.read_u32()? as usize,
                         _ => unreachable!(),
                     };
-                    Ok((Value::Bytes(bytes.copy_to_bytes(size).to_vec()), bytes))
+                    config.check_value_size(size)?;
+                    Ok((Value::Bytes(bytes.read_bytes(size)?.to_vec()), bytes))
                 }
                 // List
                 marker
@@ -431,14 +572,31 @@ impl BoltValue for Value {
                         {
                             0x0F & marker as usize
                         }
-                        MARKER_SMALL_LIST => bytes.get_u8() as usize,
-                        MARKER_MEDIUM_LIST => bytes.get_u16() as usize,
-                        MARKER_LARGE_LIST => bytes.get_u32() as usize,
+                        MARKER_SMALL_LIST => bytes.read_u8()? as usize,
+                        MARKER_MEDIUM_LIST => bytes.read_u16()? as usize,
+                        MARKER_LARGE_LIST => bytes.read_u32()? as usize,
                         _ => unreachable!(),
                     };
-                    let mut list: Vec<Value> = Vec::with_capacity(size);
-                    for _ in 0..size {
-                        let (v, b) = Value::deserialize(bytes)?;
+                    config.check_value_size(size)?;
+                    // Every entry takes at least one byte, so don't trust a larger declared size
+                    let mut list: Vec<Value> = Vec::new();
+                    if config.float_arrays && bytes.chunk().first() == Some(&MARKER_FLOAT) {
+                        // Read floats directly until the list ends or something else turns up
+                        let mut floats = Vec::with_capacity(size.min(bytes.remaining() / 9));
+                        while floats.len() < size && bytes.chunk().first() == Some(&MARKER_FLOAT) {
+                            bytes.advance(1);
+                            floats.push(bytes.read_f64()?);
+                        }
+                        if floats.len() == size {
+                            return Ok((Value::FloatArray(floats), bytes));
+                        }
+                        list.reserve(size.min(floats.len() + bytes.remaining()));
+                        list.extend(floats.into_iter().map(Value::Float));
+                    } else {
+                        list.reserve(size.min(bytes.remaining()));
+                    }
+                    while list.len() < size {
+                        let (v, b) = Value::deserialize_with(bytes, config)?;
                         bytes = b;
                         list.push(v);
                     }
@@ -458,20 +616,24 @@ impl BoltValue for Value {
                         {
                             0x0F & marker as usize
                         }
-                        MARKER_SMALL_MAP => bytes.get_u8() as usize,
-                        MARKER_MEDIUM_MAP => bytes.get_u16() as usize,
-                        MARKER_LARGE_MAP => bytes.get_u32() as usize,
+                        MARKER_SMALL_MAP => bytes.read_u8()? as usize,
+                        MARKER_MEDIUM_MAP => bytes.read_u16()? as usize,
+                        MARKER_LARGE_MAP => bytes.read_u32()? as usize,
                         _ => unreachable!(),
                     };
+                    config.check_value_size(size)?;
 
-                    let mut hash_map: HashMap<std::string::String, Value> =
-                        HashMap::with_capacity(size);
+                    let mut hash_map: HashMap<String, Value> = HashMap::with_capacity_and_hasher(
+                        size.min(bytes.remaining() / 2),
+                        Default::default(),
+                    );
                     for _ in 0..size {
-                        let (value, remaining) = Value::deserialize(bytes)?;
+                        let (value, remaining) =
+                            Value::deserialize_with(bytes, &config.for_map_key())?;
                         bytes = remaining;
                         match value {
                             Value::String(key) => {
-                                let (value, remaining) = Value::deserialize(bytes)?;
+                                let (value, remaining) = Value::deserialize_with(bytes, config)?;
                                 bytes = remaining;
                                 hash_map.insert(key, value);
                             }
@@ -498,14 +660,15 @@ impl BoltValue for Value {
                         {
                             0x0F & marker as usize
                         }
-                        MARKER_SMALL_STRING => bytes.get_u8() as usize,
-                        MARKER_MEDIUM_STRING => bytes.get_u16() as usize,
-                        MARKER_LARGE_STRING => bytes.get_u32() as usize,
+                        MARKER_SMALL_STRING => bytes.read_u8()? as usize,
+                        MARKER_MEDIUM_STRING => bytes.read_u16()? as usize,
+                        MARKER_LARGE_STRING => bytes.read_u32()? as usize,
                         _ => unreachable!(),
                     };
+                    config.check_value_size(size)?;
 
                     Ok((
-                        Value::String(String::from_utf8(bytes.copy_to_bytes(size).to_vec())?),
+                        utf8::decode_string(bytes.read_bytes(size)?.to_vec(), config.utf8_mode)?,
                         bytes,
                     ))
                 }
@@ -514,26 +677,73 @@ impl BoltValue for Value {
                     if (MARKER_TINY_STRUCT..=(MARKER_TINY_STRUCT | 0x0F)).contains(&marker)
                         || matches!(marker, MARKER_SMALL_STRUCT | MARKER_MEDIUM_STRUCT) =>
                 {
-                    deserialize_structure(marker, bytes)
+                    deserialize_structure(marker, bytes, config)
                 }
                 _ => Err(DeserializationError::InvalidMarkerByte(marker)),
             }
         })
-        .map_err(|_| DeserializationError::Panicked)?
     }
 }
 
+/// Advance past the next value without deserializing it. Nested values are skipped with a count
+/// of the values still to be read, rather than by recursing.
+pub(crate) fn skip_value(bytes: &mut impl Buf) -> DeserializeResult<()> {
+    let mut pending: usize = 1;
+    while pending > 0 {
+        pending -= 1;
+        let marker = bytes.read_u8()?;
+        // The number of bytes to skip, and the number of nested values that follow
+        let (len, values) = match marker {
+            MARKER_NULL | MARKER_TRUE | MARKER_FALSE => (0, 0),
+            marker if (-16..=127).contains(&(marker as i8)) => (0, 0),
+            MARKER_INT_8 => (1, 0),
+            MARKER_INT_16 => (2, 0),
+            MARKER_INT_32 => (4, 0),
+            MARKER_INT_64 | MARKER_FLOAT => (8, 0),
+            MARKER_SMALL_BYTES | MARKER_SMALL_STRING => (bytes.read_u8()? as usize, 0),
+            MARKER_MEDIUM_BYTES | MARKER_MEDIUM_STRING => (bytes.read_u16()? as usize, 0),
+            MARKER_LARGE_BYTES | MARKER_LARGE_STRING => (bytes.read_u32()? as usize, 0),
+            marker if (MARKER_TINY_STRING..=(MARKER_TINY_STRING | 0x0F)).contains(&marker) => {
+                (0x0F & marker as usize, 0)
+            }
+            marker if (MARKER_TINY_LIST..=(MARKER_TINY_LIST | 0x0F)).contains(&marker) => {
+                (0, 0x0F & marker as usize)
+            }
+            MARKER_SMALL_LIST => (0, bytes.read_u8()? as usize),
+            MARKER_MEDIUM_LIST => (0, bytes.read_u16()? as usize),
+            MARKER_LARGE_LIST => (0, bytes.read_u32()? as usize),
+            marker if (MARKER_TINY_MAP..=(MARKER_TINY_MAP | 0x0F)).contains(&marker) => {
+                (0, 2 * (0x0F & marker as usize))
+            }
+            MARKER_SMALL_MAP => (0, 2 * bytes.read_u8()? as usize),
+            MARKER_MEDIUM_MAP => (0, 2 * bytes.read_u16()? as usize),
+            MARKER_LARGE_MAP => (0, 2 * bytes.read_u32()? as usize),
+            marker
+                if (MARKER_TINY_STRUCT..=(MARKER_TINY_STRUCT | 0x0F)).contains(&marker)
+                    || matches!(marker, MARKER_SMALL_STRUCT | MARKER_MEDIUM_STRUCT) =>
+            {
+                let (size, _signature) = get_structure_info(marker, bytes)?;
+                (0, size)
+            }
+            _ => return Err(DeserializationError::InvalidMarkerByte(marker)),
+        };
+        bytes.skip(len)?;
+        pending += values;
+    }
+    Ok(())
+}
+
 macro_rules! deserialize_struct {
-    ($name:ident, $bytes:ident) => {{
-        let (value, remaining) = $name::deserialize($bytes)?;
+    ($name:ident, $bytes:ident, $config:ident) => {{
+        let (value, remaining) = $name::deserialize_with($bytes, $config)?;
         $bytes = remaining;
         Ok((Value::$name(value), $bytes))
     }};
 }
 
 macro_rules! deserialize_variant {
-    ($name:ident, $bytes:ident) => {{
-        let (value, remaining) = Value::deserialize($bytes)?;
+    ($name:ident, $bytes:ident, $config:ident) => {{
+        let (value, remaining) = Value::deserialize_with($bytes, $config)?;
         $bytes = remaining;
         if let Value::$name(inner) = value {
             inner
@@ -543,95 +753,165 @@ macro_rules! deserialize_variant {
     }};
 }
 
+fn list_marker(len: usize) -> SerializeResult<u8> {
+    match len {
+        0..=15 => Ok(MARKER_TINY_LIST | len as u8),
+        16..=255 => Ok(MARKER_SMALL_LIST),
+        256..=65_535 => Ok(MARKER_MEDIUM_LIST),
+        65_536..=4_294_967_295 => Ok(MARKER_LARGE_LIST),
+        len => Err(SerializationError::ValueTooLarge(len)),
+    }
+}
+
+// Keep temporal values that are out of range for chrono as custom structures, like zoned
+// date-times with unknown time zones, instead of failing the whole message
+fn temporal_or_custom(value: Option<Value>, signature: u8, fields: Vec<Value>) -> Value {
+    value.unwrap_or(Value::Custom(signature, fields))
+}
+
+fn time_from_nanos(nanos_since_midnight: i64) -> Option<NaiveTime> {
+    NaiveTime::from_num_seconds_from_midnight_opt(
+        u32::try_from(nanos_since_midnight / 1_000_000_000).ok()?,
+        u32::try_from(nanos_since_midnight % 1_000_000_000).ok()?,
+    )
+}
+
 fn deserialize_structure<B: Buf + UnwindSafe>(
     marker: u8,
     mut bytes: B,
+    config: &DecodeConfig,
 ) -> DeserializeResult<(Value, B)> {
-    let (_, signature) = get_structure_info(marker, &mut bytes)?;
+    let (size, signature) = get_structure_info(marker, &mut bytes)?;
 
     match signature {
-        SIGNATURE_NODE => deserialize_struct!(Node, bytes),
-        SIGNATURE_RELATIONSHIP => deserialize_struct!(Relationship, bytes),
-        SIGNATURE_PATH => deserialize_struct!(Path, bytes),
-        SIGNATURE_UNBOUND_RELATIONSHIP => deserialize_struct!(UnboundRelationship, bytes),
+        SIGNATURE_NODE => deserialize_struct!(Node, bytes, config),
+        SIGNATURE_RELATIONSHIP => deserialize_struct!(Relationship, bytes, config),
+        SIGNATURE_PATH => deserialize_struct!(Path, bytes, config),
+        SIGNATURE_UNBOUND_RELATIONSHIP => deserialize_struct!(UnboundRelationship, bytes, config),
         SIGNATURE_DATE => {
-            let days_since_epoch: i64 = deserialize_variant!(Integer, bytes);
+            let days_since_epoch: i64 = deserialize_variant!(Integer, bytes, config);
+            let date = chrono::Duration::try_days(days_since_epoch)
+                .and_then(|days| NaiveDate::from_ymd_opt(1970, 1, 1)?.checked_add_signed(days));
             Ok((
-                Value::Date(
-                    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
-                        + chrono::Duration::days(days_since_epoch),
+                temporal_or_custom(
+                    date.map(Value::Date),
+                    signature,
+                    vec![Value::Integer(days_since_epoch)],
                 ),
                 bytes,
             ))
         }
         SIGNATURE_TIME => {
-            let nanos_since_midnight: i64 = deserialize_variant!(Integer, bytes);
-            let zone_offset: i32 = deserialize_variant!(Integer, bytes) as i32;
+            let nanos_since_midnight: i64 = deserialize_variant!(Integer, bytes, config);
+            let zone_offset: i64 = deserialize_variant!(Integer, bytes, config);
+            let time = time_from_nanos(nanos_since_midnight).zip(
+                i32::try_from(zone_offset)
+                    .ok()
+                    .and_then(FixedOffset::east_opt),
+            );
             Ok((
-                Value::Time(
-                    NaiveTime::from_num_seconds_from_midnight_opt(
-                        (nanos_since_midnight / 1_000_000_000) as u32,
-                        (nanos_since_midnight % 1_000_000_000) as u32,
-                    )
-                    .unwrap(),
-                    FixedOffset::east_opt(zone_offset).unwrap(),
+                temporal_or_custom(
+                    time.map(|(time, offset)| Value::Time(time, offset)),
+                    signature,
+                    vec![
+                        Value::Integer(nanos_since_midnight),
+                        Value::Integer(zone_offset),
+                    ],
                 ),
                 bytes,
             ))
         }
         SIGNATURE_DATE_TIME_OFFSET => {
-            let epoch_seconds: i64 = deserialize_variant!(Integer, bytes);
-            let nanos: i64 = deserialize_variant!(Integer, bytes);
-            let offset_seconds: i32 = deserialize_variant!(Integer, bytes) as i32;
+            let epoch_seconds: i64 = deserialize_variant!(Integer, bytes, config);
+            let nanos: i64 = deserialize_variant!(Integer, bytes, config);
+            let offset_seconds: i64 = deserialize_variant!(Integer, bytes, config);
+            let date_time = i32::try_from(offset_seconds)
+                .ok()
+                .and_then(FixedOffset::east_opt)
+                .zip(u32::try_from(nanos).ok())
+                .and_then(|(offset, nanos)| offset.timestamp_opt(epoch_seconds, nanos).single());
             Ok((
-                Value::DateTimeOffset(DateTime::from_utc(
-                    NaiveDateTime::from_timestamp_opt(epoch_seconds, nanos as u32).unwrap(),
-                    FixedOffset::east_opt(offset_seconds).unwrap(),
-                )),
+                temporal_or_custom(
+                    date_time.map(Value::DateTimeOffset),
+                    signature,
+                    vec![
+                        Value::Integer(epoch_seconds),
+                        Value::Integer(nanos),
+                        Value::Integer(offset_seconds),
+                    ],
+                ),
                 bytes,
             ))
         }
         SIGNATURE_DATE_TIME_ZONED => {
-            let epoch_seconds: i64 = deserialize_variant!(Integer, bytes);
-            let nanos: i64 = deserialize_variant!(Integer, bytes);
-            let timezone_id: String = deserialize_variant!(String, bytes);
-            let timezone: Tz = timezone_id.parse().unwrap();
-            Ok((
-                Value::DateTimeZoned(timezone.timestamp_opt(epoch_seconds, nanos as u32).unwrap()),
-                bytes,
-            ))
+            let epoch_seconds: i64 = deserialize_variant!(Integer, bytes, config);
+            let nanos: i64 = deserialize_variant!(Integer, bytes, config);
+            let timezone_id: String = deserialize_variant!(String, bytes, config);
+            let date_time = timezone_id.parse::<Tz>().ok().and_then(|timezone| {
+                u32::try_from(nanos)
+                    .ok()
+                    .and_then(|nanos| timezone.timestamp_opt(epoch_seconds, nanos).single())
+            });
+            match date_time {
+                Some(date_time) => Ok((Value::DateTimeZoned(date_time), bytes)),
+                // Keep time zones newer than our time zone database, so the rest of the message
+                // is still usable and the value is sent back unchanged
+                None => Ok((
+                    Value::Custom(
+                        SIGNATURE_DATE_TIME_ZONED,
+                        vec![
+                            Value::Integer(epoch_seconds),
+                            Value::Integer(nanos),
+                            Value::String(timezone_id),
+                        ],
+                    ),
+                    bytes,
+                )),
+            }
         }
         SIGNATURE_LOCAL_TIME => {
-            let nanos_since_midnight: i64 = deserialize_variant!(Integer, bytes);
+            let nanos_since_midnight: i64 = deserialize_variant!(Integer, bytes, config);
             Ok((
-                Value::LocalTime(
-                    NaiveTime::from_num_seconds_from_midnight_opt(
-                        (nanos_since_midnight / 1_000_000_000) as u32,
-                        (nanos_since_midnight % 1_000_000_000) as u32,
-                    )
-                    .unwrap(),
+                temporal_or_custom(
+                    time_from_nanos(nanos_since_midnight).map(Value::LocalTime),
+                    signature,
+                    vec![Value::Integer(nanos_since_midnight)],
                 ),
                 bytes,
             ))
         }
         SIGNATURE_LOCAL_DATE_TIME => {
-            let epoch_seconds: i64 = deserialize_variant!(Integer, bytes);
-            let nanos: i64 = deserialize_variant!(Integer, bytes);
+            let epoch_seconds: i64 = deserialize_variant!(Integer, bytes, config);
+            let nanos: i64 = deserialize_variant!(Integer, bytes, config);
+            let date_time = u32::try_from(nanos)
+                .ok()
+                .and_then(|nanos| DateTime::from_timestamp(epoch_seconds, nanos));
             Ok((
-                Value::LocalDateTime(
-                    NaiveDateTime::from_timestamp_opt(epoch_seconds, nanos as u32).unwrap(),
+                temporal_or_custom(
+                    date_time.map(|date_time| Value::LocalDateTime(date_time.naive_utc())),
+                    signature,
+                    vec![Value::Integer(epoch_seconds), Value::Integer(nanos)],
                 ),
                 bytes,
             ))
         }
-        SIGNATURE_DURATION => deserialize_struct!(Duration, bytes),
-        SIGNATURE_POINT_2D => deserialize_struct!(Point2D, bytes),
-        SIGNATURE_POINT_3D => deserialize_struct!(Point3D, bytes),
-        _ => Err(DeserializationError::InvalidSignatureByte(signature)),
+        SIGNATURE_DURATION => deserialize_struct!(Duration, bytes, config),
+        SIGNATURE_POINT_2D => deserialize_struct!(Point2D, bytes, config),
+        SIGNATURE_POINT_3D => deserialize_struct!(Point3D, bytes, config),
+        _ => {
+            let mut fields: Vec<Value> = Vec::with_capacity(size);
+            for _ in 0..size {
+                let (value, remaining) = Value::deserialize_with(bytes, config)?;
+                bytes = remaining;
+                fields.push(value);
+            }
+            Ok((custom::deserialize_custom(signature, fields)?, bytes))
+        }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::identity_op)]
 mod tests {
     use std::collections::HashMap;
 
@@ -887,6 +1167,95 @@ mod tests {
         assert_eq!(remaining.len(), 0);
     }
 
+    #[test]
+    fn invalid_utf8_from_bytes() {
+        let mode = |utf8_mode| DecodeConfig {
+            utf8_mode,
+            ..Default::default()
+        };
+
+        let string = || Bytes::from_static(&[MARKER_TINY_STRING | 2, b'a', 0xFF]);
+        assert!(matches!(
+            Value::deserialize(string()),
+            Err(DeserializationError::InvalidUTF8(_))
+        ));
+        let (value, _) = Value::deserialize_with(string(), &mode(Utf8Mode::Lossy)).unwrap();
+        assert_eq!(value, Value::from("a\u{FFFD}"));
+        let (value, _) = Value::deserialize_with(string(), &mode(Utf8Mode::Bytes)).unwrap();
+        assert_eq!(value, Value::Bytes(vec![b'a', 0xFF]));
+
+        let map = Bytes::from_static(&[
+            MARKER_TINY_MAP | 1,
+            MARKER_TINY_STRING | 1,
+            0xFF,
+            MARKER_TINY_STRING | 1,
+            0xFF,
+        ]);
+        let (value, _) = Value::deserialize_with(map, &mode(Utf8Mode::Bytes)).unwrap();
+        assert_eq!(
+            value,
+            Value::from(HashMap::from([("\u{FFFD}", Value::Bytes(vec![0xFF]))]))
+        );
+    }
+
+    #[test]
+    fn float_array() {
+        let config = DecodeConfig {
+            float_arrays: true,
+            ..Default::default()
+        };
+
+        let floats: Vec<f64> = (0..300).map(|n| n as f64 / 2.0).collect();
+        let bytes = Value::FloatArray(floats.clone()).serialize().unwrap();
+        assert_eq!(bytes, Value::from(floats.clone()).serialize().unwrap());
+
+        let (value, _) = Value::deserialize(bytes.clone()).unwrap();
+        assert!(matches!(value, Value::List(_)));
+        assert_eq!(value, Value::from(floats.clone()));
+        assert_eq!(value, Value::FloatArray(floats.clone()));
+        let (value, remaining) = Value::deserialize_with(bytes, &config).unwrap();
+        assert!(matches!(value, Value::FloatArray(_)));
+        assert_eq!(value, Value::from(floats.clone()));
+        assert_eq!(remaining.len(), 0);
+        assert_eq!(Vec::<f32>::try_from(value).unwrap()[299], 149.5);
+
+        // Lists with anything other than floats are still lists
+        let mixed = vec![
+            Value::from(0.5),
+            Value::from(1.5),
+            Value::from(2),
+            Value::Null,
+        ];
+        let bytes = Value::from(mixed.clone()).serialize().unwrap();
+        let (value, _) = Value::deserialize_with(bytes, &config).unwrap();
+        assert_eq!(value, Value::from(mixed));
+        assert_ne!(
+            Value::FloatArray(vec![0.5, 1.5]),
+            Value::from(vec![Value::from(0.5), Value::from(2)])
+        );
+        assert_ne!(Value::FloatArray(vec![0.5]), Value::from(vec![0.5, 1.5]));
+        let bytes = Value::List(vec![]).serialize().unwrap();
+        let (value, _) = Value::deserialize_with(bytes, &config).unwrap();
+        assert!(matches!(value, Value::List(list) if list.is_empty()));
+
+        // Floats split across chunks
+        let bytes = Value::FloatArray(vec![1.0, 2.0]).serialize().unwrap();
+        let chunks = bytes.slice(..7).chain(bytes.slice(7..));
+        let (value, _) = Value::deserialize_with(chunks, &config).unwrap();
+        assert!(matches!(value, Value::FloatArray(floats) if floats == [1.0, 2.0]));
+    }
+
+    #[test]
+    fn map_with_non_string_key_from_bytes() {
+        let bytes = Bytes::from_static(&[MARKER_TINY_MAP | 1, 1, MARKER_TINY_STRING | 1, b'a']);
+        assert!(matches!(
+            Value::deserialize(bytes),
+            Err(DeserializationError::ConversionError(
+                ConversionError::FromValue(Value::Integer(1))
+            ))
+        ));
+    }
+
     value_test!(
         node,
         Value::Node(Node::new(
@@ -1039,6 +1408,71 @@ mod tests {
         b"Asia/Ulaanbaatar"
     );
 
+    value_test!(
+        date_time_zoned_unknown,
+        Value::Custom(
+            SIGNATURE_DATE_TIME_ZONED,
+            vec![
+                Value::from(1911969001),
+                Value::from(12345),
+                Value::from("Mars/Olympus_Mons"),
+            ],
+        ),
+        MARKER_TINY_STRUCT | 3,
+        &[SIGNATURE_DATE_TIME_ZONED],
+        &[MARKER_INT_32],
+        1911969001_i32.to_be_bytes(),
+        &[MARKER_INT_16],
+        12345_i16.to_be_bytes(),
+        &[MARKER_SMALL_STRING, 17],
+        b"Mars/Olympus_Mons"
+    );
+
+    value_test!(
+        date_out_of_range,
+        Value::Custom(SIGNATURE_DATE, vec![Value::from(i64::MAX)]),
+        MARKER_TINY_STRUCT | 1
+    );
+
+    value_test!(
+        time_out_of_range,
+        Value::Custom(SIGNATURE_TIME, vec![Value::from(0), Value::from(i64::MAX)]),
+        MARKER_TINY_STRUCT | 2
+    );
+
+    value_test!(
+        local_date_time_out_of_range,
+        Value::Custom(
+            SIGNATURE_LOCAL_DATE_TIME,
+            vec![Value::from(0), Value::from(-1)]
+        ),
+        MARKER_TINY_STRUCT | 2
+    );
+
+    #[test]
+    fn truncated_values() {
+        let truncated: [&[u8]; 7] = [
+            &[],
+            &[MARKER_INT_64, 0x00],
+            &[MARKER_SMALL_STRING, 0xFF, b'a'],
+            &[MARKER_LARGE_BYTES, 0x00],
+            &[MARKER_TINY_LIST | 2, 0x01],
+            &[MARKER_SMALL_MAP],
+            &[MARKER_SMALL_STRUCT, 0x01],
+        ];
+        for bytes in truncated {
+            match Value::deserialize(bytes) {
+                Err(error) => assert_eq!(
+                    error.kind(),
+                    DeserializationErrorKind::UnexpectedEnd,
+                    "{:x?}",
+                    bytes
+                ),
+                Ok(value) => panic!("unexpected value {:?} from {:x?}", value, bytes),
+            }
+        }
+    }
+
     value_test!(
         local_time,
         Value::LocalTime(NaiveTime::from_hms_nano_opt(23, 59, 59, 999).unwrap()),
@@ -1112,6 +1546,115 @@ mod tests {
         45_438.874_385_f64.to_be_bytes()
     );
 
+    value_test!(
+        custom,
+        Value::Custom(0x01, vec![Value::from(1), Value::from("a")]),
+        MARKER_TINY_STRUCT | 2,
+        &[0x01],
+        &[0x01],
+        &[MARKER_TINY_STRING | 1, b'a']
+    );
+
+    value_test!(
+        small_custom,
+        Value::Custom(0x02, vec![Value::Null; 20]),
+        MARKER_SMALL_STRUCT,
+        &[20, 0x02],
+        &[MARKER_NULL; 20]
+    );
+
+    #[test]
+    fn registered_custom() {
+        fn sum(signature: u8, fields: Vec<Value>) -> ConversionResult<Value> {
+            let mut total = i64::from(signature);
+            for field in fields {
+                total += i64::try_from(field)?;
+            }
+            Ok(Value::Integer(total))
+        }
+
+        let bytes = Value::Custom(0x03, vec![Value::from(10), Value::from(20)])
+            .serialize()
+            .unwrap();
+        assert!(register_structure(0x03, sum).is_none());
+        assert_eq!(
+            Value::deserialize(bytes.clone()).unwrap().0,
+            Value::Integer(33)
+        );
+        let bytes = Value::Custom(0x03, vec![Value::from("a")])
+            .serialize()
+            .unwrap();
+        assert!(matches!(
+            Value::deserialize(bytes),
+            Err(DeserializationError::ConversionError(
+                ConversionError::FromValue(_)
+            ))
+        ));
+
+        // Built-in signatures are not affected by registered functions
+        register_structure(SIGNATURE_POINT_2D, sum);
+        let point = Value::Point2D(Point2D::new(1, 2.0, 3.0));
+        assert_eq!(
+            Value::deserialize(point.clone().serialize().unwrap())
+                .unwrap()
+                .0,
+            point
+        );
+        assert!(unregister_structure(SIGNATURE_POINT_2D).is_some());
+        assert!(unregister_structure(0x03).is_some());
+    }
+
+    #[test]
+    fn integer_conversions() {
+        assert_eq!(u16::try_from(Value::from(65535)).unwrap(), 65535);
+        assert_eq!(i16::try_from(Value::from(-32768)).unwrap(), -32768);
+        assert_eq!(
+            u32::try_from(Value::from(u32::MAX as i64)).unwrap(),
+            u32::MAX
+        );
+        assert_eq!(
+            u64::try_from(Value::from(i64::MAX)).unwrap(),
+            i64::MAX as u64
+        );
+        assert_eq!(usize::try_from(Value::from(7)).unwrap(), 7);
+
+        for (result, value, target) in [
+            (u16::try_from(Value::from(65536)).map(|_| ()), 65536, "u16"),
+            (i8::try_from(Value::from(-129)).map(|_| ()), -129, "i8"),
+            (usize::try_from(Value::from(-1)).map(|_| ()), -1, "usize"),
+            (
+                i32::try_from(Value::from(i64::MAX)).map(|_| ()),
+                i64::MAX,
+                "i32",
+            ),
+            (
+                u64::try_from(Value::from(i64::MIN)).map(|_| ()),
+                i64::MIN,
+                "u64",
+            ),
+        ] {
+            match result {
+                Err(ConversionError::IntegerOutOfRange {
+                    value: actual_value,
+                    target: actual_target,
+                }) => assert_eq!((actual_value, actual_target), (value, target)),
+                other => panic!("expected IntegerOutOfRange, got {:?}", other),
+            }
+        }
+        assert_eq!(
+            ConversionError::IntegerOutOfRange {
+                value: 256,
+                target: "i8"
+            }
+            .to_string(),
+            "integer 256 is out of range for i8"
+        );
+        assert!(matches!(
+            u32::try_from(Value::from("1")),
+            Err(ConversionError::FromValue(_))
+        ));
+    }
+
     #[test]
     #[ignore]
     fn value_size() {
@@ -0,0 +1,267 @@
+use crate::{collections::HashMap, prelude::*, Value};
+
+impl Value {
+    /// Get the entry of a [`Value::Map`] with the given key. Returns [`None`] if the map has no
+    /// such entry, or if this is not a map.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    /// Get the element of a [`Value::List`] at the given index. Returns [`None`] if the index is
+    /// out of bounds, or if this is not a list.
+    pub fn get_index(&self, index: usize) -> Option<&Value> {
+        match self {
+            Value::List(list) => list.get(index),
+            _ => None,
+        }
+    }
+
+    /// Get a value nested inside maps and lists, following a path of map keys and list indices
+    /// separated by dots. Returns [`None`] if any part of the path does not exist.
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use bolt_proto::Value;
+    /// let server = HashMap::from([
+    ///     ("addresses", Value::from(vec!["core1:7687"])),
+    ///     ("role", Value::from("WRITE")),
+    /// ]);
+    /// let rt = HashMap::from([("servers", Value::from(vec![Value::from(server)]))]);
+    /// let metadata = Value::from(HashMap::from([("rt", rt)]));
+    ///
+    /// let address = metadata.get_path("rt.servers.0.addresses.0");
+    /// assert_eq!(address.and_then(Value::as_str), Some("core1:7687"));
+    /// assert_eq!(metadata.get_path("rt.servers.1"), None);
+    /// ```
+    pub fn get_path(&self, path: &str) -> Option<&Value> {
+        path.split('.').try_fold(self, |value, part| match value {
+            Value::List(list) => list.get(part.parse::<usize>().ok()?),
+            _ => value.get(part),
+        })
+    }
+
+    /// Get the value of a [`Value::Boolean`].
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(boolean) => Some(*boolean),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a [`Value::Integer`].
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Integer(integer) => Some(*integer),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a [`Value::Float`]. Integers are not converted.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Float(float) => Some(*float),
+            _ => None,
+        }
+    }
+
+    /// Get the contents of a [`Value::String`].
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    /// Get the contents of a [`Value::Bytes`].
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Get the elements of a [`Value::List`].
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Get the contents of a [`Value::FloatArray`].
+    pub fn as_float_array(&self) -> Option<&[f64]> {
+        match self {
+            Value::FloatArray(floats) => Some(floats),
+            _ => None,
+        }
+    }
+
+    /// Pack floats into a [`Value::Bytes`] as consecutive big-endian 32-bit floats, which takes
+    /// less than half the space of the equivalent list. The server stores the bytes as they are,
+    /// so they can only be unpacked by clients, with [`Value::to_f32_vec`].
+    ///
+    /// ```
+    /// # use bolt_proto::Value;
+    /// let value = Value::packed_f32(&[0.5, -1.0]);
+    /// assert_eq!(value.as_bytes().map(<[u8]>::len), Some(8));
+    /// assert_eq!(value.to_f32_vec(), Some(vec![0.5, -1.0]));
+    /// ```
+    pub fn packed_f32(floats: &[f32]) -> Value {
+        Value::Bytes(
+            floats
+                .iter()
+                .flat_map(|float| float.to_be_bytes())
+                .collect(),
+        )
+    }
+
+    /// Pack floats into a [`Value::Bytes`] as consecutive big-endian 64-bit floats. See
+    /// [`Value::packed_f32`].
+    pub fn packed_f64(floats: &[f64]) -> Value {
+        Value::Bytes(
+            floats
+                .iter()
+                .flat_map(|float| float.to_be_bytes())
+                .collect(),
+        )
+    }
+
+    /// Get the floats in a [`Value::FloatArray`], a [`Value::List`] that only contains floats, or
+    /// a [`Value::Bytes`] created with [`Value::packed_f32`], rounded to 32 bits.
+    pub fn to_f32_vec(&self) -> Option<Vec<f32>> {
+        match self {
+            Value::Bytes(bytes) if bytes.len() % 4 == 0 => Some(
+                bytes
+                    .chunks_exact(4)
+                    .map(|chunk| f32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                    .collect(),
+            ),
+            Value::Bytes(_) => None,
+            value => value
+                .to_f64_vec()
+                .map(|floats| floats.into_iter().map(|float| float as f32).collect()),
+        }
+    }
+
+    /// Get the floats in a [`Value::FloatArray`], a [`Value::List`] that only contains floats, or
+    /// a [`Value::Bytes`] created with [`Value::packed_f64`].
+    pub fn to_f64_vec(&self) -> Option<Vec<f64>> {
+        match self {
+            Value::FloatArray(floats) => Some(floats.clone()),
+            Value::List(list) => list.iter().map(Value::as_f64).collect(),
+            Value::Bytes(bytes) if bytes.len() % 8 == 0 => Some(
+                bytes
+                    .chunks_exact(8)
+                    .map(|chunk| {
+                        let mut float = [0; 8];
+                        float.copy_from_slice(chunk);
+                        f64::from_be_bytes(float)
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Get the entries of a [`Value::Map`].
+    pub fn as_map(&self) -> Option<&HashMap<String, Value>> {
+        match self {
+            Value::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Check whether this is [`Value::Null`].
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(entries: impl IntoIterator<Item = (&'static str, Value)>) -> Value {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (String::from(key), value))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn get() {
+        let value = map([("a", Value::from(1)), ("b", Value::Null)]);
+        assert_eq!(value.get("a"), Some(&Value::from(1)));
+        assert_eq!(value.get("b"), Some(&Value::Null));
+        assert_eq!(value.get("c"), None);
+        assert_eq!(Value::from(vec![1]).get("0"), None);
+
+        let value = Value::from(vec![Value::from("x"), Value::from("y")]);
+        assert_eq!(value.get_index(1), Some(&Value::from("y")));
+        assert_eq!(value.get_index(2), None);
+        assert_eq!(map([]).get_index(0), None);
+    }
+
+    #[test]
+    fn get_path() {
+        let value = map([(
+            "a",
+            Value::from(vec![map([("b", Value::from(vec![1, 2]))])]),
+        )]);
+        assert_eq!(value.get_path("a.0.b.1"), Some(&Value::from(2)));
+        assert_eq!(value.get_path("a.0.b"), Some(&Value::from(vec![1, 2])));
+        assert_eq!(value.get_path("a"), value.get("a"));
+        assert_eq!(value.get_path("a.b"), None);
+        assert_eq!(value.get_path("a.1"), None);
+        assert_eq!(value.get_path("a.0.b.1.c"), None);
+        assert_eq!(value.get_path(""), None);
+        // Keys that look like indices are still map keys
+        assert_eq!(
+            map([("0", Value::from(true))]).get_path("0"),
+            Some(&Value::from(true))
+        );
+    }
+
+    #[test]
+    fn accessors() {
+        assert_eq!(Value::from(true).as_bool(), Some(true));
+        assert_eq!(Value::from(-3).as_i64(), Some(-3));
+        assert_eq!(Value::from(-3).as_f64(), None);
+        assert_eq!(Value::from(0.5).as_f64(), Some(0.5));
+        assert_eq!(Value::from("s").as_str(), Some("s"));
+        assert_eq!(Value::from("s").as_bytes(), None);
+        assert_eq!(Value::Bytes(vec![1]).as_bytes(), Some(&[1][..]));
+        assert_eq!(Value::from(vec![1]).as_list(), Some(&[Value::from(1)][..]));
+        assert_eq!(
+            map([("a", Value::Null)]).as_map().map(|map| map.len()),
+            Some(1)
+        );
+        assert!(Value::Null.is_null());
+        assert!(!Value::from(0).is_null());
+    }
+
+    #[test]
+    fn float_vecs() {
+        let floats = vec![1.5, -0.25, f64::MAX];
+        let array = Value::FloatArray(floats.clone());
+        assert_eq!(array.as_float_array(), Some(&floats[..]));
+        assert_eq!(array.to_f64_vec(), Some(floats.clone()));
+        assert_eq!(
+            Value::from(floats.clone()).to_f64_vec(),
+            Some(floats.clone())
+        );
+        assert_eq!(Value::packed_f64(&floats).to_f64_vec(), Some(floats));
+        assert_eq!(array.to_f32_vec(), Some(vec![1.5, -0.25, f32::INFINITY]));
+        assert_eq!(Value::packed_f32(&[2.5]).to_f32_vec(), Some(vec![2.5]));
+
+        assert_eq!(Value::from(vec![1]).to_f64_vec(), None);
+        assert_eq!(Value::Bytes(vec![0; 6]).to_f32_vec(), None);
+        assert_eq!(Value::from(1.5).to_f64_vec(), None);
+        assert_eq!(Value::from(vec![1.5]).as_float_array(), None);
+    }
+}
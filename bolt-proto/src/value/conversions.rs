@@ -1,16 +1,16 @@
-use std::{collections::HashMap, hash::BuildHasher};
+use core::hash::BuildHasher;
 
 use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone};
 use chrono_tz::Tz;
 
-use crate::value::*;
+use crate::{collections::HashMap, prelude::*, value::*};
 
 // ------------------------- Into Value -------------------------
 
 #[doc(hidden)]
 macro_rules! impl_from {
     ($T:ty, $V:ident) => {
-        impl ::std::convert::From<$T> for $crate::Value {
+        impl ::core::convert::From<$T> for $crate::Value {
             fn from(value: $T) -> Self {
                 Value::$V(value)
             }
@@ -23,7 +23,7 @@ impl_from!(bool, Boolean);
 macro_rules! impl_from_int {
     ($($T:ty),+) => {
         $(
-            impl ::std::convert::From<$T> for $crate::Value {
+            impl ::core::convert::From<$T> for $crate::Value {
                 fn from(value: $T) -> Self {
                     Value::Integer(value as i64)
                 }
@@ -35,6 +35,12 @@ impl_from_int!(i8, i16, i32, i64);
 
 impl_from!(f64, Float);
 
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Value::Float(f64::from(value))
+    }
+}
+
 impl From<&[u8]> for Value {
     fn from(value: &[u8]) -> Self {
         Value::Bytes(value.to_vec())
@@ -54,7 +60,7 @@ where
 
 impl<K, V, S> From<HashMap<K, V, S>> for Value
 where
-    K: Into<std::string::String>,
+    K: Into<String>,
     V: Into<Value>,
     S: BuildHasher,
 {
@@ -95,7 +101,10 @@ impl<O: Offset> From<(NaiveTime, O)> for Value {
 
 impl<T: TimeZone> From<DateTime<T>> for Value {
     fn from(value: DateTime<T>) -> Self {
-        Value::DateTimeOffset(DateTime::from_utc(value.naive_utc(), value.offset().fix()))
+        Value::DateTimeOffset(DateTime::from_naive_utc_and_offset(
+            value.naive_utc(),
+            value.offset().fix(),
+        ))
     }
 }
 
@@ -113,8 +122,8 @@ impl_from!(NaiveDateTime, LocalDateTime);
 
 impl_from!(Duration, Duration);
 
-impl From<std::time::Duration> for Value {
-    fn from(value: std::time::Duration) -> Self {
+impl From<core::time::Duration> for Value {
+    fn from(value: core::time::Duration) -> Self {
         Value::Duration(Duration::from(value))
     }
 }
@@ -137,7 +146,7 @@ impl<T: Into<Value>> From<Option<T>> for Value {
 #[doc(hidden)]
 macro_rules! impl_try_from_value {
     ($T:ty, $V:ident) => {
-        impl ::std::convert::TryFrom<$crate::Value> for $T {
+        impl ::core::convert::TryFrom<$crate::Value> for $T {
             type Error = $crate::error::ConversionError;
 
             fn try_from(value: $crate::Value) -> $crate::error::ConversionResult<Self> {
@@ -159,10 +168,15 @@ macro_rules! impl_try_from_value_for_ints {
                 type Error = $crate::error::ConversionError;
 
                 fn try_from(value: $crate::Value) -> $crate::error::ConversionResult<Self> {
-                    use ::std::convert::TryInto;
+                    use ::core::convert::TryInto;
 
                     match value {
-                        $crate::Value::Integer(integer) => Ok(integer.try_into()?),
+                        $crate::Value::Integer(integer) => integer.try_into().map_err(|_| {
+                            $crate::error::ConversionError::IntegerOutOfRange {
+                                value: integer,
+                                target: stringify!($T),
+                            }
+                        }),
                         _ => Err($crate::error::ConversionError::FromValue(value)),
                     }
                 }
@@ -170,10 +184,25 @@ macro_rules! impl_try_from_value_for_ints {
         )*
     };
 }
-impl_try_from_value_for_ints!(i8, i16, i32, i64);
+// There is no conversion into u8, since it would make Vec<u8> a list of integers and conflict with
+// the conversion from Value::Bytes
+impl_try_from_value_for_ints!(i8, i16, i32, i64, isize, u16, u32, u64, usize);
 
 impl_try_from_value!(f64, Float);
 
+// Bolt only has 64-bit floats, so this rounds to the nearest f32, e.g. for embedding vectors that
+// are stored as 32-bit floats
+impl TryFrom<Value> for f32 {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> ConversionResult<Self> {
+        match value {
+            Value::Float(float) => Ok(float as f32),
+            _ => Err(ConversionError::FromValue(value)),
+        }
+    }
+}
+
 impl_try_from_value!(Vec<u8>, Bytes);
 
 impl<T> TryFrom<Value> for Vec<T>
@@ -185,14 +214,28 @@ where
     fn try_from(value: Value) -> ConversionResult<Self> {
         match value {
             Value::List(list) => list.into_iter().map(T::try_from).collect(),
+            Value::FloatArray(floats) => floats
+                .into_iter()
+                .map(|float| T::try_from(Value::Float(float)))
+                .collect(),
             _ => Err(ConversionError::FromValue(value)),
         }
     }
 }
 
-impl_try_from_value!(Vec<Value>, List);
+impl TryFrom<Value> for Vec<Value> {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> ConversionResult<Self> {
+        match value {
+            Value::List(list) => Ok(list),
+            Value::FloatArray(floats) => Ok(floats.into_iter().map(Value::Float).collect()),
+            _ => Err(ConversionError::FromValue(value)),
+        }
+    }
+}
 
-impl<V, S> TryFrom<Value> for HashMap<std::string::String, V, S>
+impl<V, S> TryFrom<Value> for HashMap<String, V, S>
 where
     V: TryFrom<Value, Error = ConversionError>,
     S: BuildHasher + Default,
@@ -213,7 +256,7 @@ where
     }
 }
 
-impl<S> TryFrom<Value> for HashMap<std::string::String, Value, S>
+impl<S> TryFrom<Value> for HashMap<String, Value, S>
 where
     S: BuildHasher + Default,
 {
@@ -265,9 +308,9 @@ impl_try_from_value!(NaiveTime, LocalTime);
 
 impl_try_from_value!(NaiveDateTime, LocalDateTime);
 
-// We cannot convert to std::time::Duration, since months are not well-defined in terms of
+// We cannot convert to core::time::Duration, since months are not well-defined in terms of
 // seconds, and our Duration can hold quantities that are impossible to hold in a
-// std::time::Duration (like negative durations).
+// core::time::Duration (like negative durations).
 impl_try_from_value!(Duration, Duration);
 
 impl_try_from_value!(Point2D, Point2D);
@@ -0,0 +1,64 @@
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
+use crate::{error::ConversionResult, prelude::*, Value};
+
+/// A function that converts the fields of a structure with a custom signature into a [`Value`].
+///
+/// The function is passed the signature byte and the deserialized fields of the structure.
+pub type StructureDeserializer = fn(u8, Vec<Value>) -> ConversionResult<Value>;
+
+#[cfg(feature = "std")]
+static REGISTRY: RwLock<[Option<StructureDeserializer>; 256]> = RwLock::new([None; 256]);
+
+/// Register a function used to deserialize structures with the given signature, returning the
+/// previously registered function, if any.
+///
+/// Structures with signatures that have no registered function are deserialized as
+/// [`Value::Custom`]. Signatures used by the protocol's own value types (nodes, dates, points,
+/// etc.) are always deserialized as those types, so registering them has no effect.
+///
+/// ```
+/// # use bolt_proto::{error::ConversionResult, value::register_structure, Value};
+/// // Deserialize structures with signature 0x01 as a list of their fields
+/// fn as_list(_signature: u8, fields: Vec<Value>) -> ConversionResult<Value> {
+///     Ok(Value::List(fields))
+/// }
+///
+/// register_structure(0x01, as_list);
+/// ```
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub fn register_structure(
+    signature: u8,
+    deserializer: StructureDeserializer,
+) -> Option<StructureDeserializer> {
+    let mut registry = REGISTRY.write().unwrap_or_else(|error| error.into_inner());
+    registry[signature as usize].replace(deserializer)
+}
+
+/// Remove the function registered for the given signature, returning it if any was registered.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub fn unregister_structure(signature: u8) -> Option<StructureDeserializer> {
+    let mut registry = REGISTRY.write().unwrap_or_else(|error| error.into_inner());
+    registry[signature as usize].take()
+}
+
+/// Convert a structure with a custom signature into a [`Value`], using the registered function if
+/// there is one.
+#[cfg(feature = "std")]
+pub(crate) fn deserialize_custom(signature: u8, fields: Vec<Value>) -> ConversionResult<Value> {
+    let deserializer =
+        REGISTRY.read().unwrap_or_else(|error| error.into_inner())[signature as usize];
+    match deserializer {
+        Some(deserializer) => deserializer(signature, fields),
+        None => Ok(Value::Custom(signature, fields)),
+    }
+}
+
+/// Without `std` there is no registry, so custom structures are always kept as [`Value::Custom`].
+#[cfg(not(feature = "std"))]
+pub(crate) fn deserialize_custom(signature: u8, fields: Vec<Value>) -> ConversionResult<Value> {
+    Ok(Value::Custom(signature, fields))
+}
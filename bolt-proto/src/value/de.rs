@@ -0,0 +1,886 @@
+use core::fmt;
+
+use chrono::{
+    DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone,
+    Timelike, Utc,
+};
+use serde::{
+    de::{
+        value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer},
+        DeserializeOwned, Error as _, IgnoredAny, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+    },
+    forward_to_deserialize_any, Deserialize, Deserializer,
+};
+
+use crate::{
+    collections::HashMap,
+    error::{ConversionError, ConversionResult},
+    prelude::*,
+    value::Duration,
+    Value,
+};
+
+impl serde::de::Error for ConversionError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        ConversionError::Serde(msg.to_string())
+    }
+}
+
+/// Deserialize a type from a [`Value`], using its [`Deserialize`](serde::Deserialize)
+/// implementation.
+///
+/// Maps deserialize into structs and maps, lists into sequences, and [`Value::Null`] into
+/// [`None`] or `()`. [`Node`](crate::value::Node)s and relationships deserialize as their
+/// properties, and points as maps with `srid`, `x`, `y`, and (for 3D points) `z` entries. Temporal
+/// values deserialize as ISO-8601 strings, which [`chrono`]'s types can be deserialized from; see
+/// [`from_value_with`] for other representations. Paths are not supported.
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use bolt_proto::{value::from_value, Value};
+/// #[derive(serde::Deserialize)]
+/// struct Person {
+///     name: String,
+///     age: i64,
+/// }
+///
+/// let value = Value::from(HashMap::<String, Value>::from_iter(vec![
+///     (String::from("name"), Value::from("Alice")),
+///     (String::from("age"), Value::from(42)),
+/// ]));
+/// let person: Person = from_value(value).unwrap();
+/// assert_eq!(person.age, 42);
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub fn from_value<T: DeserializeOwned>(value: Value) -> ConversionResult<T> {
+    T::deserialize(value)
+}
+
+/// Deserialize a type from a [`Value`] like [`from_value`] does, representing temporal values in
+/// the given format.
+///
+/// ```
+/// # use chrono::NaiveDate;
+/// # use bolt_proto::{value::{from_value_with, TemporalFormat}, Value};
+/// #[derive(serde::Deserialize)]
+/// struct Date {
+///     year: i32,
+///     month: u32,
+///     day: u32,
+/// }
+///
+/// let value = Value::from(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap());
+/// let date: Date = from_value_with(value.clone(), TemporalFormat::Structured).unwrap();
+/// assert_eq!((date.year, date.month, date.day), (2024, 1, 31));
+/// let date: String = from_value_with(value, TemporalFormat::Iso8601).unwrap();
+/// assert_eq!(date, "2024-01-31");
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub fn from_value_with<T: DeserializeOwned>(
+    value: Value,
+    format: TemporalFormat,
+) -> ConversionResult<T> {
+    T::deserialize(ValueDeserializer { value, format })
+}
+
+/// How temporal values ([`Value::Date`], [`Value::Time`], [`Value::Duration`], etc.) are
+/// represented when deserializing with serde.
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TemporalFormat {
+    /// ISO-8601 strings, e.g. `"2024-01-31T12:30:00+01:00"` for a date-time with an offset or
+    /// `"P1M2DT3.5S"` for a duration. The time zone ID of a [`Value::DateTimeZoned`] is lost, but
+    /// its offset is kept.
+    #[default]
+    Iso8601,
+    /// Maps of their components. Dates have `year`, `month`, and `day` entries, times have `hour`,
+    /// `minute`, `second`, and `nanosecond` entries, and values with an offset or time zone have
+    /// an `offset_seconds` entry and a `timezone` entry with the time zone ID, if any. Durations
+    /// have `months`, `days`, `seconds`, and `nanoseconds` entries.
+    Structured,
+}
+
+impl<'de> IntoDeserializer<'de, ConversionError> for Value {
+    type Deserializer = Value;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for Value {
+    type Error = ConversionError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> ConversionResult<V::Value> {
+        ValueDeserializer::from(self).deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> ConversionResult<V::Value> {
+        ValueDeserializer::from(self).deserialize_option(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> ConversionResult<V::Value> {
+        ValueDeserializer::from(self).deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> ConversionResult<V::Value> {
+        ValueDeserializer::from(self).deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Values can be deserialized from any self-describing format, e.g. JSON. Since such formats have
+/// no notion of graph structures or temporal values, nodes, relationships, and points deserialize
+/// as maps and temporal values as strings, so a value
+/// [serialized](#impl-Serialize-for-Value) as one of these types does not deserialize back into
+/// it. Unsigned integers that don't fit in an [`i64`] are rejected.
+///
+/// ```
+/// # use bolt_proto::Value;
+/// let value: Value = serde_json::from_str(r#"{"name": "Alice", "tags": [1, 2.5, null]}"#).unwrap();
+/// assert_eq!(
+///     value,
+///     Value::from(std::collections::HashMap::from([
+///         (String::from("name"), Value::from("Alice")),
+///         (
+///             String::from("tags"),
+///             Value::from(vec![Value::from(1), Value::from(2.5), Value::Null]),
+///         ),
+///     ]))
+/// );
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a value")
+    }
+
+    fn visit_bool<E: serde::de::Error>(self, boolean: bool) -> Result<Value, E> {
+        Ok(Value::Boolean(boolean))
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, integer: i64) -> Result<Value, E> {
+        Ok(Value::Integer(integer))
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, integer: u64) -> Result<Value, E> {
+        i64::try_from(integer)
+            .map(Value::Integer)
+            .map_err(|_| E::invalid_value(serde::de::Unexpected::Unsigned(integer), &"an i64"))
+    }
+
+    fn visit_f64<E: serde::de::Error>(self, float: f64) -> Result<Value, E> {
+        Ok(Value::Float(float))
+    }
+
+    fn visit_str<E: serde::de::Error>(self, string: &str) -> Result<Value, E> {
+        Ok(Value::String(String::from(string)))
+    }
+
+    fn visit_string<E: serde::de::Error>(self, string: String) -> Result<Value, E> {
+        Ok(Value::String(string))
+    }
+
+    fn visit_bytes<E: serde::de::Error>(self, bytes: &[u8]) -> Result<Value, E> {
+        Ok(Value::Bytes(bytes.to_vec()))
+    }
+
+    fn visit_byte_buf<E: serde::de::Error>(self, bytes: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::Bytes(bytes))
+    }
+
+    fn visit_unit<E: serde::de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: serde::de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut list = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element()? {
+            list.push(value);
+        }
+        Ok(Value::List(list))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut entries =
+            HashMap::with_capacity_and_hasher(map.size_hint().unwrap_or(0), Default::default());
+        while let Some((key, value)) = map.next_entry::<String, Value>()? {
+            entries.insert(key, value);
+        }
+        Ok(Value::Map(entries))
+    }
+}
+
+/// A [`Value`] along with the format of any temporal values inside it.
+pub(crate) struct ValueDeserializer {
+    pub(crate) value: Value,
+    pub(crate) format: TemporalFormat,
+}
+
+impl From<Value> for ValueDeserializer {
+    fn from(value: Value) -> Self {
+        Self {
+            value,
+            format: TemporalFormat::default(),
+        }
+    }
+}
+
+fn visit_entries<'de, V: Visitor<'de>>(
+    entries: Vec<(&'static str, Value)>,
+    visitor: V,
+) -> ConversionResult<V::Value> {
+    let mut map = MapDeserializer::new(entries.into_iter());
+    let value = visitor.visit_map(&mut map)?;
+    map.end()?;
+    Ok(value)
+}
+
+fn visit_temporal<'de, V: Visitor<'de>>(
+    format: TemporalFormat,
+    iso: String,
+    entries: Vec<(&'static str, Value)>,
+    visitor: V,
+) -> ConversionResult<V::Value> {
+    match format {
+        TemporalFormat::Iso8601 => visitor.visit_string(iso),
+        TemporalFormat::Structured => visit_entries(entries, visitor),
+    }
+}
+
+fn date_entries(date: &impl Datelike) -> Vec<(&'static str, Value)> {
+    vec![
+        ("year", Value::from(date.year())),
+        ("month", Value::from(date.month() as i64)),
+        ("day", Value::from(date.day() as i64)),
+    ]
+}
+
+fn time_entries(time: &impl Timelike) -> Vec<(&'static str, Value)> {
+    vec![
+        ("hour", Value::from(time.hour() as i64)),
+        ("minute", Value::from(time.minute() as i64)),
+        ("second", Value::from(time.second() as i64)),
+        ("nanosecond", Value::from(time.nanosecond() as i64)),
+    ]
+}
+
+fn local_date_time_iso(date_time: &NaiveDateTime) -> String {
+    date_time.format("%Y-%m-%dT%H:%M:%S%.f").to_string()
+}
+
+fn duration_iso(duration: &Duration) -> String {
+    // Nanoseconds always have the same sign as seconds, or seconds are zero
+    let seconds = match (duration.seconds, duration.nanos) {
+        (seconds, 0) => seconds.to_string(),
+        (0, nanos) if nanos < 0 => format!("-0.{:09}", -nanos),
+        (seconds, nanos) => format!("{}.{:09}", seconds, nanos.abs()),
+    };
+    format!(
+        "P{}M{}DT{}S",
+        duration.months,
+        duration.days,
+        seconds.trim_end_matches('0').trim_end_matches('.')
+    )
+}
+
+impl<'de> IntoDeserializer<'de, ConversionError> for ValueDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer {
+    type Error = ConversionError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> ConversionResult<V::Value> {
+        let format = self.format;
+        let nested = move |value| ValueDeserializer { value, format };
+        match self.value {
+            Value::Boolean(boolean) => visitor.visit_bool(boolean),
+            Value::Integer(integer) => visitor.visit_i64(integer),
+            Value::Float(float) => visitor.visit_f64(float),
+            Value::Bytes(bytes) => visitor.visit_byte_buf(bytes),
+            Value::List(list) => {
+                let mut seq = SeqDeserializer::new(list.into_iter().map(nested));
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::FloatArray(floats) => {
+                let floats = floats.into_iter().map(|float| nested(Value::Float(float)));
+                let mut seq = SeqDeserializer::new(floats);
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Map(map) => {
+                let mut map =
+                    MapDeserializer::new(map.into_iter().map(|(key, value)| (key, nested(value))));
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+            Value::Null => visitor.visit_unit(),
+            Value::String(string) => visitor.visit_string(string),
+            Value::Node(node) => nested(Value::Map(node.properties)).deserialize_any(visitor),
+            Value::Relationship(rel) => nested(Value::Map(rel.properties)).deserialize_any(visitor),
+            Value::UnboundRelationship(rel) => {
+                nested(Value::Map(rel.properties)).deserialize_any(visitor)
+            }
+            Value::Date(date) => {
+                let iso = date.format("%Y-%m-%d").to_string();
+                visit_temporal(format, iso, date_entries(&date), visitor)
+            }
+            Value::Time(time, offset) => {
+                let iso = format!("{}{}", time.format("%H:%M:%S%.f"), offset);
+                let mut entries = time_entries(&time);
+                entries.push(("offset_seconds", Value::from(offset.local_minus_utc())));
+                visit_temporal(format, iso, entries, visitor)
+            }
+            Value::DateTimeOffset(date_time) => {
+                let iso = date_time.to_rfc3339();
+                let mut entries = date_entries(&date_time);
+                entries.extend(time_entries(&date_time));
+                entries.push((
+                    "offset_seconds",
+                    Value::from(date_time.offset().local_minus_utc()),
+                ));
+                visit_temporal(format, iso, entries, visitor)
+            }
+            Value::DateTimeZoned(date_time) => {
+                let iso = date_time.to_rfc3339();
+                let mut entries = date_entries(&date_time);
+                entries.extend(time_entries(&date_time));
+                entries.push((
+                    "offset_seconds",
+                    Value::from(date_time.offset().fix().local_minus_utc()),
+                ));
+                entries.push(("timezone", Value::from(date_time.timezone().name())));
+                visit_temporal(format, iso, entries, visitor)
+            }
+            Value::LocalTime(time) => {
+                let iso = time.format("%H:%M:%S%.f").to_string();
+                visit_temporal(format, iso, time_entries(&time), visitor)
+            }
+            Value::LocalDateTime(date_time) => {
+                let iso = local_date_time_iso(&date_time);
+                let mut entries = date_entries(&date_time);
+                entries.extend(time_entries(&date_time));
+                visit_temporal(format, iso, entries, visitor)
+            }
+            Value::Duration(duration) => {
+                let iso = duration_iso(&duration);
+                let entries = vec![
+                    ("months", Value::from(duration.months)),
+                    ("days", Value::from(duration.days)),
+                    ("seconds", Value::from(duration.seconds)),
+                    ("nanoseconds", Value::from(duration.nanos)),
+                ];
+                visit_temporal(format, iso, entries, visitor)
+            }
+            Value::Point2D(point) => visit_entries(
+                vec![
+                    ("srid", Value::from(point.srid)),
+                    ("x", Value::from(point.x)),
+                    ("y", Value::from(point.y)),
+                ],
+                visitor,
+            ),
+            Value::Point3D(point) => visit_entries(
+                vec![
+                    ("srid", Value::from(point.srid)),
+                    ("x", Value::from(point.x)),
+                    ("y", Value::from(point.y)),
+                    ("z", Value::from(point.z)),
+                ],
+                visitor,
+            ),
+            other => Err(ConversionError::FromValue(other)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> ConversionResult<V::Value> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> ConversionResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> ConversionResult<V::Value> {
+        let format = self.format;
+        match self.value {
+            // Unit variants are represented by their name, other variants by a single-entry map
+            Value::String(string) => visitor.visit_enum(string.into_deserializer()),
+            Value::Map(map) if map.len() == 1 => {
+                visitor.visit_enum(MapAccessDeserializer::new(MapDeserializer::new(
+                    map.into_iter()
+                        .map(|(key, value)| (key, ValueDeserializer { value, format })),
+                )))
+            }
+            other => Err(ConversionError::invalid_type(
+                serde::de::Unexpected::Other(&format!("{:?}", other)),
+                &"a string or a map with a single entry",
+            )),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Deserialize a [`DateTime<Utc>`] from a temporal value, with serde's `deserialize_with`
+/// attribute. Date-times with an offset or time zone are converted to UTC, and local date-times are
+/// assumed to be in UTC. Values in either [`TemporalFormat`] are accepted.
+///
+/// ```
+/// # use chrono::{DateTime, NaiveDate, Utc};
+/// # use bolt_proto::{value::from_value, Value};
+/// #[derive(serde::Deserialize)]
+/// struct Event {
+///     #[serde(deserialize_with = "bolt_proto::value::datetime_utc::deserialize")]
+///     at: DateTime<Utc>,
+/// }
+///
+/// let at = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap().and_hms_opt(12, 30, 0).unwrap();
+/// let value = Value::from(std::collections::HashMap::from([(
+///     String::from("at"),
+///     Value::from(at),
+/// )]));
+/// let event: Event = from_value(value).unwrap();
+/// assert_eq!(event.at.naive_utc(), at);
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod datetime_utc {
+    use super::*;
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        deserializer.deserialize_any(DateTimeUtcVisitor)
+    }
+}
+
+/// Deserialize an optional [`DateTime<Utc>`] from a temporal value or [`Value::Null`], like
+/// [`datetime_utc`] does.
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod datetime_utc_option {
+    use super::*;
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Utc>>, D::Error> {
+        deserializer.deserialize_option(OptionVisitor)
+    }
+
+    struct OptionVisitor;
+
+    impl<'de> Visitor<'de> for OptionVisitor {
+        type Value = Option<DateTime<Utc>>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a date-time or null")
+        }
+
+        fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            datetime_utc::deserialize(deserializer).map(Some)
+        }
+    }
+}
+
+struct DateTimeUtcVisitor;
+
+impl<'de> Visitor<'de> for DateTimeUtcVisitor {
+    type Value = DateTime<Utc>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a date-time")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, string: &str) -> Result<Self::Value, E> {
+        if let Ok(date_time) = DateTime::parse_from_rfc3339(string) {
+            return Ok(date_time.with_timezone(&Utc));
+        }
+        NaiveDateTime::parse_from_str(string, "%Y-%m-%dT%H:%M:%S%.f")
+            .map(|date_time| Utc.from_utc_datetime(&date_time))
+            .map_err(|_| E::invalid_value(serde::de::Unexpected::Str(string), &self))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut fields = HashMap::<String, i64>::default();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "year" | "month" | "day" | "hour" | "minute" | "second" | "nanosecond"
+                | "offset_seconds" => {
+                    fields.insert(key, map.next_value::<i64>()?);
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        let field = |name: &'static str| {
+            fields
+                .get(name)
+                .copied()
+                .ok_or_else(|| A::Error::missing_field(name))
+        };
+        let invalid = || A::Error::custom("invalid date-time components");
+        let date = NaiveDate::from_ymd_opt(
+            i32::try_from(field("year")?).map_err(|_| invalid())?,
+            u32::try_from(field("month")?).map_err(|_| invalid())?,
+            u32::try_from(field("day")?).map_err(|_| invalid())?,
+        )
+        .ok_or_else(invalid)?;
+        let time = NaiveTime::from_hms_nano_opt(
+            u32::try_from(field("hour")?).map_err(|_| invalid())?,
+            u32::try_from(field("minute")?).map_err(|_| invalid())?,
+            u32::try_from(field("second")?).map_err(|_| invalid())?,
+            u32::try_from(field("nanosecond")?).map_err(|_| invalid())?,
+        )
+        .ok_or_else(invalid)?;
+        let offset = FixedOffset::east_opt(
+            i32::try_from(fields.get("offset_seconds").copied().unwrap_or(0))
+                .map_err(|_| invalid())?,
+        )
+        .ok_or_else(invalid)?;
+
+        offset
+            .from_local_datetime(&date.and_time(time))
+            .single()
+            .map(|date_time| date_time.with_timezone(&Utc))
+            .ok_or_else(invalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use crate::value::{Node, Path, Point2D, Point3D};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Person {
+        name: String,
+        age: u8,
+        nickname: Option<String>,
+        #[serde(default)]
+        tags: Vec<String>,
+        role: Role,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Role {
+        Admin,
+        Member { since: i32 },
+    }
+
+    fn map(entries: Vec<(&str, Value)>) -> Value {
+        Value::from(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect::<HashMap<_, _>>(),
+        )
+    }
+
+    #[test]
+    fn deserialize_struct() {
+        let value = map(vec![
+            ("name", Value::from("Alice")),
+            ("age", Value::from(42)),
+            ("nickname", Value::Null),
+            (
+                "role",
+                map(vec![("Member", map(vec![("since", 2020.into())]))]),
+            ),
+        ]);
+        assert_eq!(
+            from_value::<Person>(value).unwrap(),
+            Person {
+                name: String::from("Alice"),
+                age: 42,
+                nickname: None,
+                tags: vec![],
+                role: Role::Member { since: 2020 },
+            }
+        );
+    }
+
+    #[test]
+    fn deserialize_node() {
+        let node = Node::new(
+            1,
+            vec![String::from("Person")],
+            HashMap::from_iter(vec![
+                (String::from("name"), Value::from("Bob")),
+                (String::from("age"), Value::from(30)),
+                (String::from("nickname"), Value::from("B")),
+                (String::from("tags"), Value::from(vec!["a"])),
+                (String::from("role"), Value::from("Admin")),
+            ]),
+        );
+        let person: Person = from_value(Value::from(node)).unwrap();
+        assert_eq!(person.nickname.as_deref(), Some("B"));
+        assert_eq!(person.tags, vec![String::from("a")]);
+        assert_eq!(person.role, Role::Admin);
+    }
+
+    #[test]
+    fn deserialize_errors() {
+        // Out of range for u8
+        let value = map(vec![
+            ("name", Value::from("Alice")),
+            ("age", Value::from(1000)),
+            ("role", Value::from("Admin")),
+        ]);
+        assert!(matches!(
+            from_value::<Person>(value),
+            Err(ConversionError::Serde(_))
+        ));
+        assert!(matches!(
+            from_value::<i64>(Value::from(Point2D::new(1, 1.0, 2.0))),
+            Err(ConversionError::Serde(_))
+        ));
+        assert!(matches!(
+            from_value::<i64>(Value::from(Path::new(vec![], vec![], vec![]))),
+            Err(ConversionError::FromValue(Value::Path(_)))
+        ));
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Point {
+        srid: i32,
+        x: f64,
+        y: f64,
+        z: Option<f64>,
+    }
+
+    #[test]
+    fn deserialize_points() {
+        assert_eq!(
+            from_value::<Point>(Value::from(Point2D::new(7203, 1.0, 2.0))).unwrap(),
+            Point {
+                srid: 7203,
+                x: 1.0,
+                y: 2.0,
+                z: None
+            }
+        );
+        assert_eq!(
+            from_value::<Point>(Value::from(Point3D::new(9157, 1.0, 2.0, 3.0))).unwrap(),
+            Point {
+                srid: 9157,
+                x: 1.0,
+                y: 2.0,
+                z: Some(3.0)
+            }
+        );
+    }
+
+    #[test]
+    fn deserialize_temporal_iso8601() {
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let time = NaiveTime::from_hms_nano_opt(13, 5, 9, 250_000_000).unwrap();
+        let offset = FixedOffset::east_opt(3600).unwrap();
+
+        assert_eq!(
+            from_value::<String>(Value::from(date)).unwrap(),
+            "2024-02-29"
+        );
+        assert_eq!(
+            from_value::<String>(Value::from(time)).unwrap(),
+            "13:05:09.250"
+        );
+        assert_eq!(
+            from_value::<String>(Value::from(date.and_time(time))).unwrap(),
+            "2024-02-29T13:05:09.250"
+        );
+        assert_eq!(
+            from_value::<String>(Value::from(
+                offset.from_local_datetime(&date.and_time(time)).unwrap()
+            ))
+            .unwrap(),
+            "2024-02-29T13:05:09.250+01:00"
+        );
+        assert_eq!(
+            from_value::<String>(Value::from(Duration::new(1, 2, 3, 500_000_000))).unwrap(),
+            "P1M2DT3.5S"
+        );
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Components {
+        year: i32,
+        month: u32,
+        day: u32,
+        hour: u32,
+        minute: u32,
+        second: u32,
+        nanosecond: u32,
+        offset_seconds: i32,
+    }
+
+    #[test]
+    fn deserialize_temporal_structured() {
+        let date_time = FixedOffset::west_opt(1800)
+            .unwrap()
+            .with_ymd_and_hms(2024, 2, 29, 13, 5, 9)
+            .unwrap();
+        assert_eq!(
+            from_value_with::<Components>(Value::from(date_time), TemporalFormat::Structured)
+                .unwrap(),
+            Components {
+                year: 2024,
+                month: 2,
+                day: 29,
+                hour: 13,
+                minute: 5,
+                second: 9,
+                nanosecond: 0,
+                offset_seconds: -1800,
+            }
+        );
+
+        let duration: HashMap<String, i64> = from_value_with(
+            Value::from(Duration::new(1, 2, 3, 4)),
+            TemporalFormat::Structured,
+        )
+        .unwrap();
+        assert_eq!(duration["months"], 1);
+        assert_eq!(duration["nanoseconds"], 4);
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Event {
+        #[serde(deserialize_with = "datetime_utc::deserialize")]
+        at: DateTime<Utc>,
+        #[serde(deserialize_with = "datetime_utc_option::deserialize")]
+        until: Option<DateTime<Utc>>,
+    }
+
+    #[test]
+    fn deserialize_datetime_utc() {
+        let at = FixedOffset::east_opt(7200)
+            .unwrap()
+            .with_ymd_and_hms(2024, 2, 29, 13, 0, 0)
+            .unwrap();
+        let expected = Event {
+            at: Utc.with_ymd_and_hms(2024, 2, 29, 11, 0, 0).unwrap(),
+            until: None,
+        };
+
+        for format in [TemporalFormat::Iso8601, TemporalFormat::Structured] {
+            let value = map(vec![("at", Value::from(at)), ("until", Value::Null)]);
+            assert_eq!(from_value_with::<Event>(value, format).unwrap(), expected);
+
+            let value = map(vec![
+                ("at", Value::from(at.naive_utc())),
+                ("until", Value::from(at.naive_utc())),
+            ]);
+            assert_eq!(
+                from_value_with::<Event>(value, format).unwrap(),
+                Event {
+                    until: Some(expected.at),
+                    ..expected
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn deserialize_value() {
+        let value: Value =
+            serde_json::from_str(r#"{"a": [true, -1, 1.5, "b", null], "c": {}}"#).unwrap();
+        assert_eq!(
+            value,
+            map(vec![
+                (
+                    "a",
+                    Value::from(vec![
+                        Value::from(true),
+                        Value::from(-1),
+                        Value::from(1.5),
+                        Value::from("b"),
+                        Value::Null,
+                    ])
+                ),
+                ("c", map(vec![])),
+            ])
+        );
+        assert!(serde_json::from_str::<Value>("18446744073709551615").is_err());
+
+        // Values that only contain the types above deserialize into themselves
+        let value = Value::from(vec![value, Value::Bytes(vec![1, 2])]);
+        assert_eq!(from_value::<Value>(value.clone()).unwrap(), value);
+        let date = Value::from(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap());
+        assert_eq!(
+            from_value::<Value>(date).unwrap(),
+            Value::from("2024-01-31")
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use core::fmt;
+
+use crate::{collections::HashMap, prelude::*, Value};
+
+impl Value {
+    /// Compare two values, treating numbers as equal if they have the same numeric value, even if
+    /// one is a [`Value::Integer`] and the other a [`Value::Float`]. `NaN` floats are considered
+    /// equal to each other. Lists, maps, and the fields of custom structures are compared
+    /// recursively in the same way, and a [`Value::FloatArray`] is equal to a list of the same
+    /// floats; all other values are compared with [`PartialEq`].
+    ///
+    /// ```
+    /// # use bolt_proto::Value;
+    /// assert!(Value::from(1_i8).loosely_eq(&Value::from(1.0)));
+    /// assert!(Value::from(vec![f64::NAN]).loosely_eq(&Value::from(vec![f64::NAN])));
+    /// assert!(!Value::from(1).loosely_eq(&Value::from(1.5)));
+    /// ```
+    pub fn loosely_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Integer(integer), Value::Float(float))
+            | (Value::Float(float), Value::Integer(integer)) => *integer as f64 == *float,
+            (Value::Float(a), Value::Float(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (Value::List(a), Value::List(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.loosely_eq(b))
+            }
+            (Value::FloatArray(a), Value::FloatArray(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .zip(b)
+                        .all(|(a, b)| a == b || (a.is_nan() && b.is_nan()))
+            }
+            (Value::FloatArray(floats), Value::List(list))
+            | (Value::List(list), Value::FloatArray(floats)) => {
+                floats.len() == list.len()
+                    && floats
+                        .iter()
+                        .zip(list)
+                        .all(|(float, value)| Value::Float(*float).loosely_eq(value))
+            }
+            (Value::Map(a), Value::Map(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(key, a)| b.get(key).is_some_and(|b| a.loosely_eq(b)))
+            }
+            (Value::Custom(a_signature, a), Value::Custom(b_signature, b)) => {
+                a_signature == b_signature
+                    && a.len() == b.len()
+                    && a.iter().zip(b).all(|(a, b)| a.loosely_eq(b))
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// Get the differences between two values, descending into lists and maps to report each
+    /// differing element separately. Values are compared with [`Value::loosely_eq`], and map
+    /// entries are reported in order of their keys.
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use bolt_proto::Value;
+    /// let left = HashMap::from([("name", Value::from("Alice")), ("age", Value::from(42))]);
+    /// let right = HashMap::from([("name", Value::from("Bob")), ("age", Value::from(42.0))]);
+    /// let (left, right) = (Value::from(left), Value::from(right));
+    ///
+    /// let differences = left.diff(&right);
+    /// assert_eq!(differences.len(), 1);
+    /// assert_eq!(differences[0].path(), ".name");
+    /// assert_eq!(differences[0].to_string(), r#".name: String("Alice") != String("Bob")"#);
+    /// ```
+    pub fn diff(&self, other: &Value) -> Vec<Difference> {
+        let mut differences = Vec::new();
+        diff_into(&mut differences, String::new(), Some(self), Some(other));
+        differences
+    }
+}
+
+/// A difference between two values, found by [`Value::diff`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    path: String,
+    left: Option<Value>,
+    right: Option<Value>,
+}
+
+impl Difference {
+    /// Get the location of the difference, made up of `.key` for map entries and `[index]` for
+    /// list elements, e.g. `".people[2].name"`. This is empty if the values differ at the top
+    /// level.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Get the value on the left side of the comparison, or [`None`] if it has no element at this
+    /// path.
+    pub fn left(&self) -> Option<&Value> {
+        self.left.as_ref()
+    }
+
+    /// Get the value on the right side of the comparison, or [`None`] if it has no element at this
+    /// path.
+    pub fn right(&self) -> Option<&Value> {
+        self.right.as_ref()
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format_side = |side: &Option<Value>| match side {
+            Some(value) => format!("{:?}", value),
+            None => String::from("<missing>"),
+        };
+        let path = if self.path.is_empty() {
+            "<root>"
+        } else {
+            &self.path
+        };
+        write!(
+            f,
+            "{}: {} != {}",
+            path,
+            format_side(&self.left),
+            format_side(&self.right)
+        )
+    }
+}
+
+fn diff_into(
+    differences: &mut Vec<Difference>,
+    path: String,
+    left: Option<&Value>,
+    right: Option<&Value>,
+) {
+    match (left, right) {
+        (Some(Value::List(left)), Some(Value::List(right))) => {
+            for index in 0..left.len().max(right.len()) {
+                diff_into(
+                    differences,
+                    format!("{}[{}]", path, index),
+                    left.get(index),
+                    right.get(index),
+                );
+            }
+        }
+        (Some(Value::Map(left)), Some(Value::Map(right))) => {
+            for key in sorted_keys(left, right) {
+                diff_into(
+                    differences,
+                    format!("{}.{}", path, key),
+                    left.get(key),
+                    right.get(key),
+                );
+            }
+        }
+        (Some(left), Some(right)) if left.loosely_eq(right) => {}
+        (None, None) => {}
+        (left, right) => differences.push(Difference {
+            path,
+            left: left.cloned(),
+            right: right.cloned(),
+        }),
+    }
+}
+
+fn sorted_keys<'a>(
+    left: &'a HashMap<String, Value>,
+    right: &'a HashMap<String, Value>,
+) -> Vec<&'a String> {
+    let mut keys: Vec<&String> = left.keys().chain(right.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(entries: Vec<(&str, Value)>) -> Value {
+        Value::from(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect::<HashMap<_, _>>(),
+        )
+    }
+
+    #[test]
+    fn loosely_eq() {
+        assert!(Value::from(1_i8).loosely_eq(&Value::from(1_i64)));
+        assert!(Value::from(2).loosely_eq(&Value::from(2.0)));
+        assert!(Value::from(2.0).loosely_eq(&Value::from(2)));
+        assert!(Value::from(f64::NAN).loosely_eq(&Value::from(f64::NAN)));
+        assert!(!Value::from(f64::NAN).loosely_eq(&Value::from(0.0)));
+        assert!(!Value::from(1).loosely_eq(&Value::from("1")));
+        assert!(!Value::Null.loosely_eq(&Value::from(false)));
+
+        assert!(map(vec![("a", Value::from(vec![1, 2]))])
+            .loosely_eq(&map(vec![("a", Value::from(vec![1.0, 2.0]))])));
+        assert!(!map(vec![("a", Value::from(1))]).loosely_eq(&map(vec![("b", Value::from(1))])));
+        assert!(!Value::from(vec![1]).loosely_eq(&Value::from(vec![1, 1])));
+        assert!(Value::Custom(0x01, vec![Value::from(1)])
+            .loosely_eq(&Value::Custom(0x01, vec![Value::from(1.0)])));
+        assert!(!Value::Custom(0x01, vec![]).loosely_eq(&Value::Custom(0x02, vec![])));
+    }
+
+    #[test]
+    fn diff() {
+        assert!(Value::from(1).diff(&Value::from(1.0)).is_empty());
+        assert_eq!(
+            Value::from(1).diff(&Value::from("1")),
+            vec![Difference {
+                path: String::new(),
+                left: Some(Value::from(1)),
+                right: Some(Value::from("1")),
+            }]
+        );
+
+        let left = map(vec![
+            ("name", Value::from("Alice")),
+            ("tags", Value::from(vec!["a", "b"])),
+            ("nested", map(vec![("x", Value::from(1))])),
+            ("removed", Value::Null),
+        ]);
+        let right = map(vec![
+            ("name", Value::from("Alice")),
+            ("tags", Value::from(vec!["a", "c", "d"])),
+            ("nested", map(vec![("x", Value::from(2))])),
+        ]);
+        let differences: Vec<String> = left
+            .diff(&right)
+            .iter()
+            .map(Difference::to_string)
+            .collect();
+        assert_eq!(
+            differences,
+            vec![
+                ".nested.x: Integer(1) != Integer(2)",
+                ".removed: Null != <missing>",
+                r#".tags[1]: String("b") != String("c")"#,
+                r#".tags[2]: <missing> != String("d")"#,
+            ]
+        );
+    }
+}
@@ -38,8 +38,8 @@ impl Duration {
     }
 }
 
-impl From<std::time::Duration> for Duration {
-    fn from(duration: std::time::Duration) -> Self {
+impl From<core::time::Duration> for Duration {
+    fn from(duration: core::time::Duration) -> Self {
         // This fits in an i64 because u64::MAX / (3600 * 24) < i64::MAX
         let days = (duration.as_secs() / (3600 * 24)) as i64;
         // This fits in an i64 since it will be less than 3600 * 24
@@ -1,8 +1,6 @@
-use std::collections::HashMap;
-
 use bolt_proto_derive::*;
 
-use crate::{value::SIGNATURE_NODE, Value};
+use crate::{collections::HashMap, prelude::*, value::SIGNATURE_NODE, Value};
 
 #[bolt_structure(SIGNATURE_NODE)]
 #[derive(Debug, Clone, Eq, PartialEq)]
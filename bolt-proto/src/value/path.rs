@@ -1,6 +1,9 @@
 use bolt_proto_derive::*;
 
-use crate::value::{Node, UnboundRelationship, SIGNATURE_PATH};
+use crate::{
+    prelude::*,
+    value::{Node, UnboundRelationship, SIGNATURE_PATH},
+};
 
 #[bolt_structure(SIGNATURE_PATH)]
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -1,8 +1,6 @@
-use std::collections::HashMap;
-
 use bolt_proto_derive::*;
 
-use crate::{value::SIGNATURE_RELATIONSHIP, Value};
+use crate::{collections::HashMap, prelude::*, value::SIGNATURE_RELATIONSHIP, Value};
 
 #[bolt_structure(SIGNATURE_RELATIONSHIP)]
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -0,0 +1,160 @@
+use serde::{
+    ser::{Error as _, SerializeMap},
+    Deserialize, Serialize, Serializer,
+};
+
+use crate::{
+    prelude::*,
+    value::{de::ValueDeserializer, TemporalFormat},
+    Value,
+};
+
+/// Values serialize the same way they deserialize with [`from_value`](crate::value::from_value):
+/// maps as maps, lists as sequences, [`Value::Null`] as `()`, nodes and relationships as their
+/// properties, points as maps with `srid`, `x`, `y`, and (for 3D points) `z` entries, and temporal
+/// values as ISO-8601 strings. Paths and custom structures cannot be serialized.
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use bolt_proto::{value::Node, Value};
+/// let node = Node::new(1, vec![String::from("Person")], HashMap::from([
+///     (String::from("name"), Value::from("Alice")),
+/// ]));
+/// let json = serde_json::to_string(&Value::from(vec![Value::Node(node), Value::Null])).unwrap();
+/// assert_eq!(json, r#"[{"name":"Alice"},null]"#);
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Boolean(boolean) => serializer.serialize_bool(*boolean),
+            Value::Integer(integer) => serializer.serialize_i64(*integer),
+            Value::Float(float) => serializer.serialize_f64(*float),
+            Value::Bytes(bytes) => serializer.serialize_bytes(bytes),
+            Value::List(list) => serializer.collect_seq(list),
+            Value::FloatArray(floats) => serializer.collect_seq(floats),
+            Value::Map(map) => serializer.collect_map(map),
+            Value::Null => serializer.serialize_unit(),
+            Value::String(string) => serializer.serialize_str(string),
+            Value::Node(node) => serializer.collect_map(&node.properties),
+            Value::Relationship(rel) => serializer.collect_map(&rel.properties),
+            Value::UnboundRelationship(rel) => serializer.collect_map(&rel.properties),
+            Value::Point2D(point) => {
+                let mut map = serializer.serialize_map(Some(3))?;
+                map.serialize_entry("srid", &point.srid)?;
+                map.serialize_entry("x", &point.x)?;
+                map.serialize_entry("y", &point.y)?;
+                map.end()
+            }
+            Value::Point3D(point) => {
+                let mut map = serializer.serialize_map(Some(4))?;
+                map.serialize_entry("srid", &point.srid)?;
+                map.serialize_entry("x", &point.x)?;
+                map.serialize_entry("y", &point.y)?;
+                map.serialize_entry("z", &point.z)?;
+                map.end()
+            }
+            Value::Date(_)
+            | Value::Time(_, _)
+            | Value::DateTimeOffset(_)
+            | Value::DateTimeZoned(_)
+            | Value::LocalTime(_)
+            | Value::LocalDateTime(_)
+            | Value::Duration(_) => {
+                let deserializer = ValueDeserializer {
+                    value: self.clone(),
+                    format: TemporalFormat::Iso8601,
+                };
+                let iso = String::deserialize(deserializer).map_err(S::Error::custom)?;
+                serializer.serialize_str(&iso)
+            }
+            Value::Path(_) => Err(S::Error::custom("paths cannot be serialized")),
+            Value::Custom(signature, _) => Err(S::Error::custom(format!(
+                "custom structure with signature {:#04x} cannot be serialized",
+                signature
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{FixedOffset, NaiveDate, TimeZone};
+
+    use crate::value::{Duration, Node, Path, Point3D, Relationship};
+
+    use super::*;
+
+    fn json(value: Value) -> String {
+        serde_json::to_string(&value).unwrap()
+    }
+
+    #[test]
+    fn serialize() {
+        assert_eq!(
+            json(Value::from(vec![
+                Value::from(true),
+                Value::from(-1),
+                Value::from(0.5),
+                Value::from("a"),
+                Value::Null,
+                Value::Bytes(vec![1, 2]),
+            ])),
+            r#"[true,-1,0.5,"a",null,[1,2]]"#
+        );
+        assert_eq!(
+            json(Value::from(HashMap::from([(
+                String::from("a"),
+                Value::from(HashMap::<String, Value>::new())
+            )]))),
+            r#"{"a":{}}"#
+        );
+
+        let properties = HashMap::from([(String::from("since"), Value::from(2020))]);
+        assert_eq!(
+            json(Value::Relationship(Relationship::new(
+                1,
+                2,
+                3,
+                String::from("KNOWS"),
+                properties
+            ))),
+            r#"{"since":2020}"#
+        );
+        assert_eq!(
+            json(Value::Point3D(Point3D::new(9157, 1.0, 2.0, 3.0))),
+            r#"{"srid":9157,"x":1.0,"y":2.0,"z":3.0}"#
+        );
+    }
+
+    #[test]
+    fn serialize_temporal() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        assert_eq!(json(Value::from(date)), r#""2024-01-31""#);
+        let date_time = FixedOffset::east_opt(3600)
+            .unwrap()
+            .with_ymd_and_hms(2024, 1, 31, 12, 30, 0)
+            .unwrap();
+        assert_eq!(
+            json(Value::from(date_time)),
+            r#""2024-01-31T12:30:00+01:00""#
+        );
+        assert_eq!(
+            json(Value::Duration(Duration::new(1, 2, 3, 500_000_000))),
+            r#""P1M2DT3.5S""#
+        );
+    }
+
+    #[test]
+    fn serialize_errors() {
+        let path = Path::new(
+            vec![Node::new(1, vec![], HashMap::<String, Value>::new())],
+            vec![],
+            vec![],
+        );
+        assert!(serde_json::to_string(&Value::Path(path)).is_err());
+        assert!(serde_json::to_string(&Value::Custom(0x80, vec![])).is_err());
+    }
+}
@@ -0,0 +1,256 @@
+use core::mem::size_of;
+
+use crate::{
+    collections::HashMap,
+    prelude::*,
+    value::{Node, Path, Relationship, UnboundRelationship},
+    Value,
+};
+
+impl Value {
+    /// Estimate the memory used by this value in bytes, including the contents of strings, lists,
+    /// maps, and structures, recursively. Spare capacity and the allocator's and hash tables'
+    /// overhead are not counted, so the estimate is a lower bound, suitable for enforcing memory
+    /// budgets on cached results.
+    ///
+    /// ```
+    /// # use bolt_proto::Value;
+    /// let small = Value::from("a");
+    /// let large = Value::from(vec!["a".repeat(1000)]);
+    /// assert!(large.estimated_size_bytes() > 1000);
+    /// assert!(large.estimated_size_bytes() > small.estimated_size_bytes());
+    /// ```
+    pub fn estimated_size_bytes(&self) -> usize {
+        size_of::<Value>() + self.heap_size()
+    }
+
+    // The memory used by the value outside of the Value itself
+    fn heap_size(&self) -> usize {
+        match self {
+            Value::Bytes(bytes) => bytes.len(),
+            Value::String(string) => string.len(),
+            Value::List(list) => list.iter().map(Value::estimated_size_bytes).sum(),
+            Value::Map(map) => map_size(map),
+            Value::Node(node) => node_size(node),
+            Value::Relationship(rel) => rel.rel_type.len() + map_size(&rel.properties),
+            Value::Path(path) => {
+                path.nodes
+                    .iter()
+                    .map(|node| size_of::<Node>() + node_size(node))
+                    .sum::<usize>()
+                    + path
+                        .relationships
+                        .iter()
+                        .map(|rel| size_of::<UnboundRelationship>() + unbound_size(rel))
+                        .sum::<usize>()
+                    + path.sequence.len() * size_of::<i64>()
+            }
+            Value::UnboundRelationship(rel) => unbound_size(rel),
+            Value::Custom(_, fields) => fields.iter().map(Value::estimated_size_bytes).sum(),
+            Value::FloatArray(floats) => floats.len() * size_of::<f64>(),
+            _ => 0,
+        }
+    }
+
+    /// Get a copy of this value that is bounded in size, e.g. for logging or caching. Lists, maps,
+    /// byte arrays, float arrays, and the properties of nodes and relationships keep at most
+    /// `max_items` entries, and lists and maps (including properties) nested more than `depth`
+    /// levels deep are left empty. Map entries are kept in order of their keys.
+    ///
+    /// Paths and the fields of custom structures are not shortened, since their elements depend
+    /// on each other, but the values inside them are truncated.
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use bolt_proto::Value;
+    /// let value = Value::from(vec![
+    ///     Value::from(vec![1, 2, 3]),
+    ///     Value::from(HashMap::from([("a", 1), ("b", 2)])),
+    ///     Value::from(3),
+    /// ]);
+    /// assert_eq!(
+    ///     value.truncate(2, 2),
+    ///     Value::from(vec![
+    ///         Value::from(vec![1, 2]),
+    ///         Value::from(HashMap::from([("a", 1), ("b", 2)])),
+    ///     ])
+    /// );
+    /// assert_eq!(
+    ///     value.truncate(1, 1),
+    ///     Value::from(vec![Value::List(vec![])])
+    /// );
+    /// ```
+    pub fn truncate(&self, depth: usize, max_items: usize) -> Value {
+        match self {
+            Value::Bytes(bytes) => Value::Bytes(bytes[..bytes.len().min(max_items)].to_vec()),
+            Value::List(_) if depth == 0 => Value::List(Vec::new()),
+            Value::List(list) => Value::List(
+                list.iter()
+                    .take(max_items)
+                    .map(|value| value.truncate(depth - 1, max_items))
+                    .collect(),
+            ),
+            Value::Map(map) => Value::Map(truncate_map(map, depth, max_items)),
+            Value::Node(node) => Value::Node(truncate_node(node, depth, max_items)),
+            Value::Relationship(rel) => Value::Relationship(Relationship {
+                rel_identity: rel.rel_identity,
+                start_node_identity: rel.start_node_identity,
+                end_node_identity: rel.end_node_identity,
+                rel_type: rel.rel_type.clone(),
+                properties: truncate_map(&rel.properties, depth, max_items),
+            }),
+            Value::Path(path) => Value::Path(Path {
+                nodes: path
+                    .nodes
+                    .iter()
+                    .map(|node| truncate_node(node, depth, max_items))
+                    .collect(),
+                relationships: path
+                    .relationships
+                    .iter()
+                    .map(|rel| truncate_unbound(rel, depth, max_items))
+                    .collect(),
+                sequence: path.sequence.clone(),
+            }),
+            Value::UnboundRelationship(rel) => {
+                Value::UnboundRelationship(truncate_unbound(rel, depth, max_items))
+            }
+            Value::Custom(signature, fields) => Value::Custom(
+                *signature,
+                fields
+                    .iter()
+                    .map(|field| field.truncate(depth, max_items))
+                    .collect(),
+            ),
+            Value::FloatArray(floats) => {
+                Value::FloatArray(floats[..floats.len().min(max_items)].to_vec())
+            }
+            other => other.clone(),
+        }
+    }
+}
+
+fn map_size(map: &HashMap<String, Value>) -> usize {
+    map.iter()
+        .map(|(key, value)| size_of::<String>() + key.len() + value.estimated_size_bytes())
+        .sum()
+}
+
+fn node_size(node: &Node) -> usize {
+    node.labels
+        .iter()
+        .map(|label| size_of::<String>() + label.len())
+        .sum::<usize>()
+        + map_size(&node.properties)
+}
+
+fn unbound_size(rel: &UnboundRelationship) -> usize {
+    rel.rel_type.len() + map_size(&rel.properties)
+}
+
+fn truncate_map(
+    map: &HashMap<String, Value>,
+    depth: usize,
+    max_items: usize,
+) -> HashMap<String, Value> {
+    if depth == 0 {
+        return HashMap::default();
+    }
+    let mut keys: Vec<&String> = map.keys().collect();
+    keys.sort_unstable();
+    keys.into_iter()
+        .take(max_items)
+        .map(|key| (key.clone(), map[key].truncate(depth - 1, max_items)))
+        .collect()
+}
+
+fn truncate_node(node: &Node, depth: usize, max_items: usize) -> Node {
+    Node {
+        node_identity: node.node_identity,
+        labels: node.labels.clone(),
+        properties: truncate_map(&node.properties, depth, max_items),
+    }
+}
+
+fn truncate_unbound(
+    rel: &UnboundRelationship,
+    depth: usize,
+    max_items: usize,
+) -> UnboundRelationship {
+    UnboundRelationship {
+        rel_identity: rel.rel_identity,
+        rel_type: rel.rel_type.clone(),
+        properties: truncate_map(&rel.properties, depth, max_items),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimated_size() {
+        let value_size = size_of::<Value>();
+        assert_eq!(Value::from(1).estimated_size_bytes(), value_size);
+        assert_eq!(Value::from("abc").estimated_size_bytes(), value_size + 3);
+        assert_eq!(
+            Value::from(vec![1, 2]).estimated_size_bytes(),
+            3 * value_size
+        );
+        assert_eq!(
+            Value::FloatArray(vec![0.5; 4]).estimated_size_bytes(),
+            value_size + 32
+        );
+
+        let node = Node::new(
+            1,
+            vec![String::from("A")],
+            HashMap::from_iter([(String::from("k"), "v")]),
+        );
+        let string_size = size_of::<String>();
+        assert_eq!(
+            Value::Node(node).estimated_size_bytes(),
+            value_size + (string_size + 1) + (string_size + 1 + value_size + 1)
+        );
+    }
+
+    #[test]
+    fn truncate() {
+        let props = HashMap::from_iter([
+            (String::from("b"), Value::from(vec![1, 2, 3])),
+            (String::from("a"), Value::from(1)),
+            (String::from("c"), Value::from(2)),
+        ]);
+        let node = Node::new(1, vec![String::from("A")], props.clone());
+        let value = Value::from(vec![
+            Value::Node(node.clone()),
+            Value::Bytes(vec![1, 2, 3]),
+            Value::from("not truncated"),
+        ]);
+
+        let Value::List(truncated) = value.truncate(2, 2) else {
+            panic!("expected a list");
+        };
+        assert_eq!(truncated.len(), 2);
+        let Value::Node(truncated_node) = &truncated[0] else {
+            panic!("expected a node");
+        };
+        assert_eq!(truncated_node.labels(), node.labels());
+        assert_eq!(
+            truncated_node.properties(),
+            &HashMap::from_iter([
+                (String::from("a"), Value::from(1)),
+                (String::from("b"), Value::List(vec![])),
+            ])
+        );
+        assert_eq!(truncated[1], Value::Bytes(vec![1, 2]));
+
+        // Values that fit are unchanged
+        assert_eq!(value.truncate(3, 3), value);
+        assert_eq!(Value::from("abc").truncate(0, 0), Value::from("abc"));
+        assert_eq!(
+            Value::Map(props).truncate(0, 10),
+            Value::Map(HashMap::default())
+        );
+    }
+}
@@ -1,8 +1,6 @@
-use std::collections::HashMap;
-
 use bolt_proto_derive::*;
 
-use crate::{value::SIGNATURE_UNBOUND_RELATIONSHIP, Value};
+use crate::{collections::HashMap, prelude::*, value::SIGNATURE_UNBOUND_RELATIONSHIP, Value};
 
 #[bolt_structure(SIGNATURE_UNBOUND_RELATIONSHIP)]
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -0,0 +1,48 @@
+use crate::{error::DeserializeResult, prelude::*, Value};
+
+/// How strings that are not valid UTF-8 are deserialized.
+///
+/// Strings are expected to be valid UTF-8, but a buggy server or a corrupted store may send
+/// strings that aren't. By default, such a string fails the whole message it is part of, which
+/// means a single bad property can end a result stream. The other modes let the rest of the
+/// message be used instead.
+///
+/// ```
+/// # use bolt_proto::{message::MessageDecoder, value::Utf8Mode, Message};
+/// // A RECORD containing the bytes [0x66, 0xFF] as a string
+/// let chunk = [0x00, 0x06, 0xB1, 0x71, 0x91, 0x82, 0x66, 0xFF, 0x00, 0x00];
+/// assert!(MessageDecoder::new().feed(&chunk).is_err());
+///
+/// let mut decoder = MessageDecoder::new().with_utf8_mode(Utf8Mode::Lossy);
+/// let record = match decoder.feed(&chunk).unwrap() {
+///     Some(Message::Record(record)) => record,
+///     _ => unreachable!(),
+/// };
+/// assert_eq!(record.fields()[0], "f\u{FFFD}".into());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Utf8Mode {
+    /// Fail with an [`InvalidUTF8`](crate::error::DeserializationError::InvalidUTF8) error.
+    #[default]
+    Strict,
+    /// Replace invalid sequences with U+FFFD, as [`String::from_utf8_lossy`] does.
+    Lossy,
+    /// Deserialize the string as a [`Value::Bytes`] with its raw contents. Map keys are still
+    /// deserialized as strings, as if using [`Utf8Mode::Lossy`]. Fields that can only hold strings,
+    /// like node labels, still fail to deserialize.
+    Bytes,
+}
+
+/// Decode the contents of a string as a [`Value`], according to the given [`Utf8Mode`].
+pub(crate) fn decode_string(bytes: Vec<u8>, mode: Utf8Mode) -> DeserializeResult<Value> {
+    match String::from_utf8(bytes) {
+        Ok(string) => Ok(Value::String(string)),
+        Err(error) => match mode {
+            Utf8Mode::Strict => Err(error.into()),
+            Utf8Mode::Lossy => Ok(Value::String(
+                String::from_utf8_lossy(error.as_bytes()).into_owned(),
+            )),
+            Utf8Mode::Bytes => Ok(Value::Bytes(error.into_bytes())),
+        },
+    }
+}
@@ -16,3 +16,114 @@ pub const V4_3: u32 = 0x0304;
 pub const V4_4: u32 = 0x0404;
 /// Protocol version 4.0, 4.1, 4.2, 4.3, or 4.4
 pub const V4: u32 = 0x040404;
+
+/// A protocol version, ordered by major and then minor version.
+///
+/// The constants in this module pack versions into `u32`s the way they are sent in the
+/// handshake: the major version in the lowest byte, the minor version in the next byte, and, for
+/// version specifiers, the number of earlier minor versions that are also accepted in the byte
+/// after that. `Version` converts to and from that representation.
+///
+/// ```
+/// # use bolt_proto::version::*;
+/// let version = Version::new(4, 3);
+/// assert_eq!(version.to_string(), "4.3");
+/// assert!(version > Version::new(4, 2) && version < Version::new(5, 0));
+/// assert_eq!(u32::from(version), V4_3);
+/// assert_eq!(Version::new(4, 4).to_specifier(4), V4);
+/// assert_eq!(Version::from_negotiated(0x0304), Some(version));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    major: u8,
+    minor: u8,
+}
+
+impl Version {
+    pub const V1_0: Version = Version::new(1, 0);
+    pub const V2_0: Version = Version::new(2, 0);
+    pub const V3_0: Version = Version::new(3, 0);
+    pub const V4_0: Version = Version::new(4, 0);
+    pub const V4_1: Version = Version::new(4, 1);
+    pub const V4_2: Version = Version::new(4, 2);
+    pub const V4_3: Version = Version::new(4, 3);
+    pub const V4_4: Version = Version::new(4, 4);
+
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+
+    pub const fn major(self) -> u8 {
+        self.major
+    }
+
+    pub const fn minor(self) -> u8 {
+        self.minor
+    }
+
+    /// Get the version a server chose in response to a handshake, or [`None`] if the response
+    /// is zero (no version was chosen) or has bits set outside of the major and minor version.
+    pub const fn from_negotiated(response: u32) -> Option<Self> {
+        if response == 0 || response > 0xffff {
+            return None;
+        }
+        Some(Self::new(response as u8, (response >> 8) as u8))
+    }
+
+    /// Get a version specifier for the handshake that accepts this version and up to `range`
+    /// earlier minor versions with the same major version. The range is limited to the minor
+    /// version, e.g. version 4.2 with a range of 5 accepts versions 4.0 to 4.2.
+    pub const fn to_specifier(self, range: u8) -> u32 {
+        let range = if range < self.minor {
+            range
+        } else {
+            self.minor
+        };
+        (range as u32) << 16 | (self.minor as u32) << 8 | self.major as u32
+    }
+}
+
+impl From<Version> for u32 {
+    fn from(version: Version) -> Self {
+        version.to_specifier(0)
+    }
+}
+
+impl core::fmt::Display for Version {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constants() {
+        let versions = [
+            (Version::V1_0, V1_0),
+            (Version::V2_0, V2_0),
+            (Version::V3_0, V3_0),
+            (Version::V4_0, V4_0),
+            (Version::V4_1, V4_1),
+            (Version::V4_2, V4_2),
+            (Version::V4_3, V4_3),
+            (Version::V4_4, V4_4),
+        ];
+        for (version, packed) in versions {
+            assert_eq!(u32::from(version), packed);
+            assert_eq!(Version::from_negotiated(packed), Some(version));
+        }
+        assert!(versions.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn specifiers() {
+        assert_eq!(Version::V4_4.to_specifier(4), V4);
+        assert_eq!(Version::V4_2.to_specifier(5), 0x020204);
+        assert_eq!(Version::V3_0.to_specifier(1), V3_0);
+        assert_eq!(Version::from_negotiated(0), None);
+        assert_eq!(Version::from_negotiated(V4), None);
+    }
+}
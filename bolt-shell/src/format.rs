@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+
+use bolt_proto::{
+    value::{Duration, Node, Path, UnboundRelationship},
+    Value,
+};
+
+/// Format a value the way Cypher would write it as a literal, where possible.
+pub fn format_value(value: &Value) -> String {
+    match value {
+        Value::Boolean(boolean) => boolean.to_string(),
+        Value::Integer(integer) => integer.to_string(),
+        Value::Float(float) => format!("{:?}", float),
+        Value::Bytes(bytes) => {
+            let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+            format!("0x{}", hex)
+        }
+        Value::List(list) => format!("[{}]", join(list.iter().map(format_value))),
+        Value::FloatArray(floats) => format!(
+            "[{}]",
+            join(floats.iter().map(|float| format!("{:?}", float)))
+        ),
+        Value::Map(map) => format_map(map),
+        Value::Null => String::from("null"),
+        Value::String(string) => format!("{:?}", string),
+        Value::Node(node) => format_node(node),
+        Value::Relationship(rel) => format_relationship(rel.rel_type(), rel.properties()),
+        Value::Path(path) => format_path(path),
+        Value::UnboundRelationship(rel) => format_relationship(rel.rel_type(), rel.properties()),
+        Value::Date(date) => date.to_string(),
+        Value::Time(time, offset) => format!("{}{}", time, offset),
+        Value::DateTimeOffset(date_time) => date_time.to_rfc3339(),
+        Value::DateTimeZoned(date_time) => {
+            format!("{}[{}]", date_time.to_rfc3339(), date_time.timezone())
+        }
+        Value::LocalTime(time) => time.to_string(),
+        Value::LocalDateTime(date_time) => date_time.format("%Y-%m-%dT%H:%M:%S%.f").to_string(),
+        Value::Duration(duration) => format_duration(duration),
+        Value::Point2D(point) => format!(
+            "point({{srid: {}, x: {:?}, y: {:?}}})",
+            point.srid(),
+            point.x(),
+            point.y()
+        ),
+        Value::Point3D(point) => format!(
+            "point({{srid: {}, x: {:?}, y: {:?}, z: {:?}}})",
+            point.srid(),
+            point.x(),
+            point.y(),
+            point.z()
+        ),
+        Value::Custom(signature, fields) => format!(
+            "structure({:#04x}, [{}])",
+            signature,
+            join(fields.iter().map(format_value))
+        ),
+    }
+}
+
+/// Format records as a table with a header row containing the given field names.
+pub fn format_table(fields: &[String], rows: &[Vec<Value>]) -> String {
+    let rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| row.iter().map(format_value).collect())
+        .collect();
+    let mut widths: Vec<usize> = fields.iter().map(|field| field.chars().count()).collect();
+    for row in &rows {
+        for (column, cell) in row.iter().enumerate() {
+            match widths.get_mut(column) {
+                Some(width) => *width = (*width).max(cell.chars().count()),
+                None => widths.push(cell.chars().count()),
+            }
+        }
+    }
+
+    let border = format!(
+        "+{}+\n",
+        widths
+            .iter()
+            .map(|width| "-".repeat(width + 2))
+            .collect::<Vec<_>>()
+            .join("+")
+    );
+    let format_row = |cells: &[String]| {
+        let cells: Vec<String> = widths
+            .iter()
+            .enumerate()
+            .map(|(column, &width)| {
+                let cell = cells.get(column).map(String::as_str).unwrap_or("");
+                format!(" {}{} ", cell, " ".repeat(width - cell.chars().count()))
+            })
+            .collect();
+        format!("|{}|\n", cells.join("|"))
+    };
+
+    let mut table = border.clone();
+    table.push_str(&format_row(fields));
+    table.push_str(&border);
+    if !rows.is_empty() {
+        for row in &rows {
+            table.push_str(&format_row(row));
+        }
+        table.push_str(&border);
+    }
+    table
+}
+
+fn join(items: impl Iterator<Item = String>) -> String {
+    items.collect::<Vec<_>>().join(", ")
+}
+
+fn format_map(map: &HashMap<String, Value>) -> String {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    format!(
+        "{{{}}}",
+        join(
+            entries
+                .into_iter()
+                .map(|(key, value)| format!("{}: {}", key, format_value(value)))
+        )
+    )
+}
+
+fn format_properties(properties: &HashMap<String, Value>) -> String {
+    if properties.is_empty() {
+        String::new()
+    } else {
+        format!(" {}", format_map(properties))
+    }
+}
+
+fn format_node(node: &Node) -> String {
+    let labels: String = node
+        .labels()
+        .iter()
+        .map(|label| format!(":{}", label))
+        .collect();
+    format!("({}{})", labels, format_properties(node.properties()))
+}
+
+fn format_relationship(rel_type: &str, properties: &HashMap<String, Value>) -> String {
+    format!("[:{}{}]", rel_type, format_properties(properties))
+}
+
+fn format_path(path: &Path) -> String {
+    let (nodes, rels) = (path.nodes(), path.relationships());
+    let mut formatted = nodes.first().map(format_node).unwrap_or_default();
+    // The sequence alternates between 1-based relationship indices, negative if the relationship
+    // is traversed backwards, and node indices
+    for step in path.sequence().chunks(2) {
+        let (rel_index, node_index) = match step {
+            &[rel_index, node_index] => (rel_index, node_index),
+            _ => break,
+        };
+        let rel = match rels.get(rel_index.unsigned_abs() as usize - 1) {
+            Some(rel) => format_unbound(rel),
+            None => String::from("[?]"),
+        };
+        if rel_index > 0 {
+            formatted.push_str(&format!("-{}->", rel));
+        } else {
+            formatted.push_str(&format!("<-{}-", rel));
+        }
+        match nodes.get(node_index as usize) {
+            Some(node) => formatted.push_str(&format_node(node)),
+            None => formatted.push_str("(?)"),
+        }
+    }
+    formatted
+}
+
+fn format_unbound(rel: &UnboundRelationship) -> String {
+    format_relationship(rel.rel_type(), rel.properties())
+}
+
+fn format_duration(duration: &Duration) -> String {
+    let mut formatted = format!(
+        "P{}M{}DT{}",
+        duration.months(),
+        duration.days(),
+        duration.seconds()
+    );
+    if duration.nanos() != 0 {
+        let nanos = format!("{:09}", duration.nanos().unsigned_abs());
+        formatted.push('.');
+        formatted.push_str(nanos.trim_end_matches('0'));
+    }
+    formatted.push('S');
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use bolt_proto::value::{Point2D, Relationship};
+
+    use super::*;
+
+    fn properties(entries: Vec<(&str, Value)>) -> HashMap<String, Value> {
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect()
+    }
+
+    #[test]
+    fn format_values() {
+        assert_eq!(format_value(&Value::Null), "null");
+        assert_eq!(format_value(&Value::from(1.0)), "1.0");
+        assert_eq!(format_value(&Value::from("a \"b\"")), r#""a \"b\"""#);
+        assert_eq!(format_value(&Value::Bytes(vec![0x0a, 0xff])), "0x0aff");
+        assert_eq!(
+            format_value(&Value::from(vec![Value::from(1), Value::Null])),
+            "[1, null]"
+        );
+        assert_eq!(
+            format_value(&Value::from(properties(vec![
+                ("b", Value::from(true)),
+                ("a", Value::from(2)),
+            ]))),
+            "{a: 2, b: true}"
+        );
+        assert_eq!(
+            format_value(&Value::from(Point2D::new(7203, 1.5, 2.0))),
+            "point({srid: 7203, x: 1.5, y: 2.0})"
+        );
+        assert_eq!(
+            format_value(&Value::from(Duration::new(14, 2, 30, 500_000_000))),
+            "P14M2DT30.5S"
+        );
+        assert_eq!(
+            format_value(&Value::Custom(0x01, vec![Value::from(1)])),
+            "structure(0x01, [1])"
+        );
+    }
+
+    #[test]
+    fn format_graph_values() {
+        let alice = Node::new(
+            1,
+            vec![String::from("Person")],
+            properties(vec![("name", Value::from("Alice"))]),
+        );
+        let bob = Node::new(
+            2,
+            vec![String::from("Person")],
+            HashMap::<String, Value>::new(),
+        );
+        assert_eq!(format_node(&alice), r#"(:Person {name: "Alice"})"#);
+        assert_eq!(
+            format_value(&Value::from(Relationship::new(
+                3,
+                1,
+                2,
+                String::from("KNOWS"),
+                HashMap::<String, Value>::new()
+            ))),
+            "[:KNOWS]"
+        );
+
+        let knows =
+            UnboundRelationship::new(3, String::from("KNOWS"), HashMap::<String, Value>::new());
+        let path = Path::new(vec![alice, bob], vec![knows], vec![1, 1, -1, 0]);
+        assert_eq!(
+            format_path(&path),
+            r#"(:Person {name: "Alice"})-[:KNOWS]->(:Person)<-[:KNOWS]-(:Person {name: "Alice"})"#
+        );
+    }
+
+    #[test]
+    fn format_tables() {
+        let fields = vec![String::from("name"), String::from("n")];
+        let rows = vec![
+            vec![Value::from("Alice"), Value::from(1)],
+            vec![Value::from("Bo"), Value::from(100)],
+        ];
+        assert_eq!(
+            format_table(&fields, &rows),
+            "\
++---------+-----+
+| name    | n   |
++---------+-----+
+| \"Alice\" | 1   |
+| \"Bo\"    | 100 |
++---------+-----+
+"
+        );
+        assert_eq!(
+            format_table(&fields, &[]),
+            "\
++------+---+
+| name | n |
++------+---+
+"
+        );
+    }
+}
@@ -0,0 +1,440 @@
+#![warn(rust_2018_idioms)]
+
+//! An interactive shell for running Cypher queries against a Bolt-compatible server, built on
+//! [`bolt_client`].
+//!
+//! ```text
+//! bolt-shell [--user USER] [--password PASSWORD] [--database DATABASE] [--versions VERSIONS] [URI]
+//! ```
+//!
+//! Queries end with a `;` and may span multiple lines. Lines starting with `:` are shell
+//! commands; enter `:help` to list them.
+
+use std::{
+    env,
+    error::Error,
+    io::{self, Write},
+    process,
+};
+
+use bolt_client::{error::CommunicationResult, BoltUri, Client, ClientBuilder, Metadata, Stream};
+use bolt_proto::{message::Failure, version::*, Message, ServerState, Value};
+use tokio::io::{AsyncBufReadExt, BufReader, BufStream};
+use tokio_util::compat::Compat;
+
+mod format;
+
+use format::format_table;
+
+const USAGE: &str = "\
+usage: bolt-shell [OPTIONS] [URI]
+
+Connects to the server at URI (default: bolt://127.0.0.1:7687). Use a bolt+s:// or neo4j+s://
+URI to connect over TLS, or bolt+ssc:// or neo4j+ssc:// to accept self-signed certificates.
+
+options:
+  -u, --user USER            authenticate as USER
+  -p, --password PASSWORD    authenticate with PASSWORD
+  -d, --database DATABASE    run queries against DATABASE (Bolt v4+ only)
+      --versions VERSIONS    comma-separated protocol versions to offer, in order of preference
+                             (default: 4.4,4.3,4.2,4.1)
+  -h, --help                 print this message";
+
+const HELP: &str = "\
+:begin               begin an explicit transaction
+:commit              commit the current transaction
+:rollback            roll back the current transaction
+:versions [VERSIONS] show the negotiated protocol version, or reconnect offering the given
+                     comma-separated versions, e.g. :versions 4.3,3
+:help                print this message
+:exit, :quit         disconnect and exit";
+
+const KNOWN_VERSIONS: [(&str, u32); 8] = [
+    ("1", V1_0),
+    ("2", V2_0),
+    ("3", V3_0),
+    ("4", V4_0),
+    ("4.1", V4_1),
+    ("4.2", V4_2),
+    ("4.3", V4_3),
+    ("4.4", V4_4),
+];
+
+#[derive(Debug, Clone)]
+struct Options {
+    uri: BoltUri,
+    user: Option<String>,
+    password: Option<String>,
+    database: Option<String>,
+    versions: [u32; 4],
+}
+
+impl Options {
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut args = args.into_iter();
+        let mut uri = None;
+        let mut user = None;
+        let mut password = None;
+        let mut database = None;
+        let mut versions = [V4_4, V4_3, V4_2, V4_1];
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-u" | "--user" => user = Some(value()?),
+                "-p" | "--password" => password = Some(value()?),
+                "-d" | "--database" => database = Some(value()?),
+                "--versions" => versions = parse_versions(&value()?)?,
+                other if other.starts_with('-') => return Err(format!("unknown option {}", other)),
+                _ if uri.is_some() => return Err(String::from("only one URI may be given")),
+                _ => uri = Some(arg),
+            }
+        }
+        // Treat a plain address as a bolt:// URI
+        let uri = match uri {
+            Some(uri) if uri.contains("://") => uri,
+            Some(addr) => format!("bolt://{}", addr),
+            None => String::from("bolt://127.0.0.1:7687"),
+        };
+        Ok(Some(Options {
+            uri: BoltUri::parse(&uri).map_err(|error| error.to_string())?,
+            user,
+            password,
+            database,
+            versions,
+        }))
+    }
+
+    fn builder(&self) -> ClientBuilder {
+        let builder = ClientBuilder::from_uri(&self.uri)
+            .with_versions(self.versions)
+            .with_user_agent(concat!("bolt-shell/", env!("CARGO_PKG_VERSION")));
+        match (&self.user, &self.password) {
+            (Some(user), password) => {
+                builder.with_basic_auth(user, password.as_deref().unwrap_or_default())
+            }
+            _ => builder,
+        }
+    }
+}
+
+/// Parse a comma-separated list of up to four protocol versions.
+fn parse_versions(versions: &str) -> Result<[u32; 4], String> {
+    let mut specifiers = [0; 4];
+    let versions: Vec<&str> = versions.split(',').map(str::trim).collect();
+    if versions.len() > specifiers.len() {
+        return Err(String::from("at most 4 versions may be given"));
+    }
+    for (specifier, version) in specifiers.iter_mut().zip(versions) {
+        *specifier = KNOWN_VERSIONS
+            .iter()
+            .find(|(name, _)| *name == version)
+            .map(|&(_, specifier)| specifier)
+            .ok_or(format!("unknown protocol version {}", version))?;
+    }
+    Ok(specifiers)
+}
+
+fn version_name(version: u32) -> &'static str {
+    KNOWN_VERSIONS
+        .iter()
+        .find(|&&(_, specifier)| specifier == version)
+        .map(|&(name, _)| name)
+        .unwrap_or("unknown")
+}
+
+fn failure_message(failure: &Failure) -> String {
+    let get = |key| match failure.metadata().get(key) {
+        Some(Value::String(string)) => string.as_str(),
+        _ => "unknown",
+    };
+    format!("{}: {}", get("code"), get("message"))
+}
+
+struct Shell {
+    options: Options,
+    client: Client<Compat<BufStream<Stream>>>,
+}
+
+impl Shell {
+    async fn connect(options: Options) -> Result<Self, Box<dyn Error>> {
+        let client = options.builder().connect().await?;
+        Ok(Self { options, client })
+    }
+
+    fn describe_connection(&self) -> String {
+        format!(
+            "connected to {} ({}) using Bolt v{}",
+            self.options.uri.addr(),
+            self.client.server_agent().unwrap_or("unknown server"),
+            self.client.version()
+        )
+    }
+
+    fn in_transaction(&self) -> bool {
+        matches!(
+            self.client.server_state(),
+            ServerState::TxReady | ServerState::TxStreaming
+        )
+    }
+
+    fn prompt(&self, continuation: bool) -> &'static str {
+        match (continuation, self.in_transaction()) {
+            (true, _) => "    ...> ",
+            (false, true) => "bolt(tx)> ",
+            (false, false) => "bolt> ",
+        }
+    }
+
+    /// Run a shell command, returning `false` if the shell should exit.
+    async fn command(&mut self, line: &str) -> Result<bool, Box<dyn Error>> {
+        let (command, argument) = match line.split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (line, ""),
+        };
+        let response = match command {
+            ":exit" | ":quit" => return Ok(false),
+            ":help" => {
+                println!("{}", HELP);
+                return Ok(true);
+            }
+            ":begin" => self.client.begin(self.transaction_metadata()).await?,
+            ":commit" => self.client.commit().await?,
+            ":rollback" => self.client.rollback().await?,
+            ":versions" if argument.is_empty() => {
+                let offered: Vec<&str> = self
+                    .options
+                    .versions
+                    .iter()
+                    .filter(|&&version| version != 0)
+                    .map(|&version| version_name(version))
+                    .collect();
+                println!("{}", self.describe_connection());
+                println!("offered versions: {}", offered.join(", "));
+                return Ok(true);
+            }
+            ":versions" => {
+                let versions = parse_versions(argument)?;
+                if self.in_transaction() {
+                    println!("rolling back the open transaction");
+                }
+                let mut options = Options {
+                    versions,
+                    ..self.options.clone()
+                };
+                std::mem::swap(&mut options, &mut self.options);
+                match self.options.builder().connect().await {
+                    Ok(client) => {
+                        let mut previous = std::mem::replace(&mut self.client, client);
+                        previous.goodbye().await.ok();
+                        println!("{}", self.describe_connection());
+                    }
+                    Err(error) => {
+                        self.options = options;
+                        return Err(error.into());
+                    }
+                }
+                return Ok(true);
+            }
+            other => return Err(format!("unknown command {} (see :help)", other).into()),
+        };
+        self.print_failure(response).await?;
+        Ok(true)
+    }
+
+    fn transaction_metadata(&self) -> Option<Metadata> {
+        match (self.client.raw_version(), &self.options.database) {
+            (V1_0 | V2_0 | V3_0, _) | (_, None) => None,
+            (_, Some(database)) => Some(Metadata::from_iter(vec![("db", database.as_str())])),
+        }
+    }
+
+    async fn query(&mut self, query: &str) -> Result<(), Box<dyn Error>> {
+        // Queries in an explicit transaction use the database chosen when it began
+        let metadata = match self.in_transaction() {
+            true => None,
+            false => self.transaction_metadata(),
+        };
+        let success = match self.client.run(query, None, metadata).await? {
+            Message::Success(success) => success,
+            other => return self.print_failure(other).await,
+        };
+        let fields: Vec<String> = match success.metadata().get("fields") {
+            Some(Value::List(fields)) => fields
+                .iter()
+                .map(|field| match field {
+                    Value::String(field) => field.clone(),
+                    other => format::format_value(other),
+                })
+                .collect(),
+            _ => vec![],
+        };
+
+        let pull_all = Metadata::from_iter(vec![("n", -1)]);
+        let (records, response) = self.client.pull(Some(pull_all)).await?;
+        if !matches!(response, Message::Success(_)) {
+            return self.print_failure(response).await;
+        }
+        let rows: Vec<Vec<Value>> = records
+            .into_iter()
+            .map(|record| record.fields().to_vec())
+            .collect();
+        if !fields.is_empty() {
+            print!("{}", format_table(&fields, &rows));
+        }
+        println!(
+            "{} {}",
+            rows.len(),
+            if rows.len() == 1 { "row" } else { "rows" }
+        );
+        Ok(())
+    }
+
+    /// Print an unsuccessful response, and reset the connection if the server has failed.
+    async fn print_failure(&mut self, response: Message) -> Result<(), Box<dyn Error>> {
+        match response {
+            Message::Success(_) => return Ok(()),
+            Message::Failure(failure) => println!("error: {}", failure_message(&failure)),
+            other => println!("error: server responded with {:?}", other),
+        }
+        if self.client.server_state() == ServerState::Failed {
+            self.reset().await?;
+        }
+        Ok(())
+    }
+
+    async fn reset(&mut self) -> CommunicationResult<Message> {
+        match self.client.raw_version() {
+            V1_0 | V2_0 => self.client.ack_failure().await,
+            _ => self.client.reset().await,
+        }
+    }
+
+    async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        println!("{}", self.describe_connection());
+        println!("enter :help for a list of commands");
+
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        let mut query = String::new();
+        loop {
+            print!("{}", self.prompt(!query.is_empty()));
+            io::stdout().flush()?;
+            let line = match lines.next_line().await? {
+                Some(line) => line,
+                None => break,
+            };
+            let line = line.trim();
+
+            let result = if query.is_empty() && line.starts_with(':') {
+                match self.command(line).await {
+                    Ok(true) => Ok(()),
+                    Ok(false) => break,
+                    Err(error) => Err(error),
+                }
+            } else {
+                query.push_str(line);
+                query.push('\n');
+                match query.trim_end().strip_suffix(';') {
+                    Some(complete) => {
+                        let complete = complete.to_string();
+                        query.clear();
+                        self.query(&complete).await
+                    }
+                    None if query.trim().is_empty() => {
+                        query.clear();
+                        Ok(())
+                    }
+                    None => Ok(()),
+                }
+            };
+            if let Err(error) = result {
+                println!("error: {}", error);
+                if self.client.server_state() == ServerState::Defunct {
+                    return Err("connection is no longer usable".into());
+                }
+            }
+        }
+
+        self.client.goodbye().await.ok();
+        Ok(())
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, USAGE);
+            process::exit(2);
+        }
+    };
+    let result = match Shell::connect(options).await {
+        Ok(mut shell) => shell.run().await,
+        Err(error) => Err(error),
+    };
+    if let Err(error) = result {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn versions() {
+        assert_eq!(parse_versions("4.4, 3").unwrap(), [V4_4, V3_0, 0, 0]);
+        assert_eq!(parse_versions("1,2,3,4").unwrap(), [V1_0, V2_0, V3_0, V4_0]);
+        assert!(parse_versions("4.4,4.3,4.2,4.1,4").is_err());
+        assert!(parse_versions("5.0").is_err());
+        assert_eq!(version_name(V4_2), "4.2");
+    }
+
+    #[test]
+    fn options() {
+        let options = Options::parse(args(&[
+            "-u",
+            "neo4j",
+            "--password",
+            "secret",
+            "--versions",
+            "4.3",
+            "bolt://example.com:7000",
+        ]))
+        .unwrap()
+        .unwrap();
+        assert_eq!(options.uri.addr(), "example.com:7000");
+        assert_eq!(options.user.as_deref(), Some("neo4j"));
+        assert_eq!(options.password.as_deref(), Some("secret"));
+        assert_eq!(options.database, None);
+        assert_eq!(options.versions, [V4_3, 0, 0, 0]);
+        assert_eq!(options.builder().domain(), None);
+
+        let default = Options::parse(vec![]).unwrap().unwrap();
+        assert_eq!(default.uri.addr(), "127.0.0.1:7687");
+        assert_eq!(default.versions, [V4_4, V4_3, V4_2, V4_1]);
+
+        let secure = Options::parse(args(&["neo4j+s://db.example.com"]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(secure.builder().domain(), Some("db.example.com"));
+        let plain = Options::parse(args(&["localhost:1234"])).unwrap().unwrap();
+        assert_eq!(plain.uri.addr(), "localhost:1234");
+
+        assert!(Options::parse(args(&["--help"])).unwrap().is_none());
+        assert!(Options::parse(args(&["http://localhost"])).is_err());
+        assert!(Options::parse(args(&["--user"])).is_err());
+        assert!(Options::parse(args(&["--bogus"])).is_err());
+        assert!(Options::parse(args(&["a:1", "b:2"])).is_err());
+    }
+}